@@ -0,0 +1,78 @@
+//! `arrange_targets` on 100, 1k and 10k targets, spread uniformly around the rings or
+//! bunched into a few narrow sectors where labels compete for the same slots, in each
+//! placement mode.
+//!
+//! Run with `cargo bench --bench arrange`; criterion compares each run against the
+//! last one saved under `target/criterion` and reports regressions. Keep a baseline
+//! to compare branches against with `-- --save-baseline main` and
+//! `-- --baseline main`.
+
+use std::f32::consts::PI;
+
+use bevy_debris::layout::{arrange_targets, LayoutConfig, PlacementMode};
+use bevy_debris::target::Target;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+/// Sectors the clustered targets bunch into, and how wide each is in radians.
+const CLUSTERS: usize = 4;
+const CLUSTER_WIDTH: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy)]
+enum Spread {
+    Uniform,
+    Clustered,
+}
+
+fn targets(n: usize, spread: Spread, rng: &mut StdRng) -> Vec<Target> {
+    let centers = (0..CLUSTERS)
+        .map(|_| rng.gen_range(0.0, PI * 2.0))
+        .collect::<Vec<_>>();
+    let mut targets = (0..n as i32)
+        .map(|id| {
+            let azimuth = match spread {
+                Spread::Uniform => rng.gen_range(0.0, PI * 2.0),
+                Spread::Clustered => {
+                    let center = centers[rng.gen_range(0, CLUSTERS)];
+                    let offset = rng.gen_range(-CLUSTER_WIDTH, CLUSTER_WIDTH) / 2.0;
+                    (center + offset).rem_euclid(PI * 2.0)
+                }
+            };
+            Target {
+                id,
+                text: id.to_string(),
+                azimuth,
+                dist: rng.gen_range(1.0, 1000.0),
+                priority: rng.gen_range(0, 4),
+                ..Default::default()
+            }
+        })
+        .collect::<Vec<_>>();
+    targets.sort_by(|a, b| a.dist.total_cmp(&b.dist));
+    targets
+}
+
+fn arrange(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(7);
+    for &mode in &[PlacementMode::Nearest, PlacementMode::Priority] {
+        let config = LayoutConfig {
+            mode,
+            ..LayoutConfig::new(12.0)
+        };
+        for &spread in &[Spread::Uniform, Spread::Clustered] {
+            let name = format!("arrange/{:?}/{:?}", mode, spread).to_lowercase();
+            let mut group = c.benchmark_group(name);
+            for &n in &[100, 1_000, 10_000] {
+                let targets = targets(n, spread, &mut rng);
+                group.bench_with_input(BenchmarkId::from_parameter(n), &targets, |b, targets| {
+                    b.iter(|| arrange_targets(black_box(targets), &config))
+                });
+            }
+            group.finish();
+        }
+    }
+}
+
+criterion_group!(benches, arrange);
+criterion_main!(benches);
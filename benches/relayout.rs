@@ -0,0 +1,68 @@
+//! Full against incremental relayout: the time to lay out `n` targets from scratch
+//! after one changes, and to take that one target out and put it back with
+//! `RingLayout::remove_changes` and `RingLayout::insert_changes`.
+//!
+//! Run with `cargo bench --bench relayout`; criterion compares each run against the
+//! last one saved under `target/criterion`, and groups the two ways of laying out by
+//! target count so its report puts them side by side.
+
+use std::f32::consts::PI;
+
+use bevy_debris::layout::{LayoutConfig, PlacementMode, RingLayout};
+use bevy_debris::target::Target;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+fn targets(n: usize, rng: &mut StdRng) -> Vec<Target> {
+    let mut targets = (0..n as i32)
+        .map(|id| Target {
+            id,
+            text: id.to_string(),
+            azimuth: rng.gen_range(0.0, PI * 2.0),
+            dist: rng.gen_range(1.0, 1000.0),
+            priority: rng.gen_range(0, 4),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    targets.sort_by(|a, b| a.dist.total_cmp(&b.dist));
+    targets
+}
+
+fn relayout(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(7);
+    for &mode in &[PlacementMode::Nearest, PlacementMode::Priority] {
+        let config = LayoutConfig {
+            mode,
+            ..LayoutConfig::new(12.0)
+        };
+        let mut group = c.benchmark_group(format!("relayout/{:?}", mode).to_lowercase());
+        for &n in &[100, 1_000, 5_000] {
+            let mut targets = targets(n, &mut rng);
+            let mut round = 0;
+            group.bench_function(BenchmarkId::new("full", n), |b| {
+                b.iter(|| {
+                    let moved = &mut targets[round % n];
+                    moved.azimuth = (moved.azimuth + 0.01) % (PI * 2.0);
+                    round += 1;
+                    RingLayout::with_config(black_box(&targets), config)
+                })
+            });
+            let mut layout = RingLayout::with_config(&targets, config);
+            let mut round = 0;
+            group.bench_function(BenchmarkId::new("incremental", n), |b| {
+                b.iter(|| {
+                    let mut moved = targets[round % n].clone();
+                    moved.azimuth = (moved.azimuth + 0.01) % (PI * 2.0);
+                    round += 1;
+                    black_box(layout.remove_changes(moved.id));
+                    layout.insert_changes(black_box(moved))
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, relayout);
+criterion_main!(benches);
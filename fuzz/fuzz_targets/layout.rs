@@ -0,0 +1,10 @@
+#![no_main]
+use bevy_debris::fuzz::{fuzz_layout, FuzzConfig};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (u64, u8)| {
+    let (seed, count) = input;
+    if let Err(failure) = fuzz_layout(seed, count as usize, &FuzzConfig::default()) {
+        panic!("{}", failure);
+    }
+});
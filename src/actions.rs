@@ -0,0 +1,461 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use bevy::input::gamepad::{GamepadButton, GamepadButtonType};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+
+/// Stage the [`ActionState`] is brought up to date in, after bevy and gestures have
+/// taken in this frame's input and before anything acts on it.
+pub const ACTIONS: &str = "actions";
+
+/// Something the user does, whatever input it is bound to in the [`ActionMap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Picks what is under the cursor.
+    Select,
+    /// Held while dragging moves the 2D view.
+    Pan,
+    /// Held while dragging turns an orbit camera.
+    Orbit,
+    TurnLeft,
+    TurnRight,
+    TurnUp,
+    TurnDown,
+    ZoomIn,
+    ZoomOut,
+    /// Puts an orbit camera back on the orbit it started on.
+    ResetView,
+    /// Switches between the turntable and trackball orbits.
+    SwitchOrbitMode,
+    /// Turns measuring between clicked points on and off.
+    Measure,
+    /// Drops the measurement under way.
+    Cancel,
+    ToggleRings,
+    ToggleGrid,
+    ToggleMarkers,
+    ToggleLeaders,
+    ToggleLabels,
+    ToggleTrails,
+    ToggleZones,
+    ToggleOverlays,
+    /// Held while clicking the globe drops a marker there.
+    PlaceMarker,
+    /// Held while clicking the globe adds heat there.
+    AddHeat,
+    /// Held while clicking and dragging on the rings selects the markers in a box.
+    BandSelect,
+    /// Held while clicking and dragging on the rings selects the markers in a sector
+    /// around the nearest origin.
+    SectorSelect,
+    HideSelected,
+    ShowAll,
+    /// Gives the selected markers the next color of the palette.
+    RecolorSelected,
+    ResetColors,
+    ExportSelection,
+    PinSelected,
+    Undo,
+    Redo,
+    SaveScene,
+    /// Copies the designated target's details to the clipboard.
+    CopyTarget,
+    /// Turns dragging and adding targets on and off.
+    ToggleEditing,
+    ExportScenario,
+    /// Turns drawing alert zones on and off.
+    ToggleZoneDrawing,
+    /// Closes the zone drawn so far into a polygon.
+    FinishZone,
+    RemoveLastZone,
+    /// Plays or pauses the timeline or replay.
+    PlayPause,
+    HalfSpeed,
+    NormalSpeed,
+    DoubleSpeed,
+    SeekBack,
+    SeekForward,
+    SeekStart,
+    PageUp,
+    PageDown,
+    RingSpacingDown,
+    RingSpacingUp,
+    ScatterDown,
+    ScatterUp,
+    MarkerWidthDown,
+    MarkerWidthUp,
+    /// Moves the camera on to the next body.
+    FocusNextBody,
+    ToggleFlatMap,
+    ToggleAtmosphere,
+    /// Turns normal-mapped lighting on and off.
+    ToggleShading,
+    ToggleProbe,
+    ToggleTuning,
+    CycleCoordFormat,
+    ExportSvg,
+    Snapshot,
+    ToggleDebugOverlay,
+}
+
+/// An input that triggers an [`Action`]. Keys count only with exactly the modifiers
+/// of their binding, so that `key(Z)` and `ctrl(Z)` can do different things; Ctrl and
+/// Shift themselves, bound as keys, count whatever else is held. Mouse buttons count
+/// with any modifiers, and gamepad buttons on any gamepad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(KeyCode),
+    Ctrl(KeyCode),
+    Shift(KeyCode),
+    CtrlShift(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+/// Which inputs trigger each [`Action`]; an action takes any of its bindings, and one
+/// without any is unbound. The defaults give every action bindings of its own. Read
+/// from a file with [`ActionMap::merge_file`], in RON or, with a `.toml` extension,
+/// TOML, where the actions listed replace their bindings and the rest keep theirs,
+/// e.g. in RON:
+///
+/// ```text
+/// {
+///     orbit: [mouse(Left)],
+///     select: [mouse(Right)],
+///     reset_view: [key(Home), key(Key0), gamepad(Select)],
+///     redo: [ctrl_shift(Z)],
+///     measure: [],
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        use Binding::{Ctrl, CtrlShift, Gamepad, Key, Mouse, Shift};
+        let bindings = vec![
+            (Action::Select, vec![Mouse(MouseButton::Left)]),
+            (Action::Pan, vec![Mouse(MouseButton::Middle)]),
+            (Action::Orbit, vec![Mouse(MouseButton::Right)]),
+            (Action::TurnLeft, vec![Key(KeyCode::Left), Key(KeyCode::A)]),
+            (
+                Action::TurnRight,
+                vec![Key(KeyCode::Right), Key(KeyCode::D)],
+            ),
+            (Action::TurnUp, vec![Key(KeyCode::Up), Key(KeyCode::W)]),
+            (Action::TurnDown, vec![Key(KeyCode::Down), Key(KeyCode::S)]),
+            (
+                Action::ZoomIn,
+                vec![
+                    Key(KeyCode::Equals),
+                    Key(KeyCode::Plus),
+                    Key(KeyCode::NumpadAdd),
+                ],
+            ),
+            (
+                Action::ZoomOut,
+                vec![Key(KeyCode::Minus), Key(KeyCode::NumpadSubtract)],
+            ),
+            (
+                Action::ResetView,
+                vec![Key(KeyCode::Home), Gamepad(GamepadButtonType::Select)],
+            ),
+            (
+                Action::SwitchOrbitMode,
+                vec![Key(KeyCode::R), Gamepad(GamepadButtonType::North)],
+            ),
+            (Action::Measure, vec![Key(KeyCode::M)]),
+            (Action::Cancel, vec![Key(KeyCode::Escape)]),
+            (Action::ToggleRings, vec![Key(KeyCode::F1)]),
+            (Action::ToggleGrid, vec![Key(KeyCode::F2)]),
+            (Action::ToggleMarkers, vec![Key(KeyCode::F3)]),
+            (Action::ToggleLeaders, vec![Key(KeyCode::F4)]),
+            (Action::ToggleLabels, vec![Key(KeyCode::F5)]),
+            (Action::ToggleTrails, vec![Key(KeyCode::F6)]),
+            (Action::ToggleZones, vec![Key(KeyCode::F7)]),
+            (Action::ToggleOverlays, vec![Key(KeyCode::F8)]),
+            (
+                Action::PlaceMarker,
+                vec![Key(KeyCode::LAlt), Key(KeyCode::RAlt)],
+            ),
+            (Action::AddHeat, vec![Key(KeyCode::X)]),
+            (
+                Action::BandSelect,
+                vec![Key(KeyCode::LShift), Key(KeyCode::RShift)],
+            ),
+            (
+                Action::SectorSelect,
+                vec![Key(KeyCode::LControl), Key(KeyCode::RControl)],
+            ),
+            (Action::HideSelected, vec![Key(KeyCode::H)]),
+            (Action::ShowAll, vec![Shift(KeyCode::H)]),
+            (Action::RecolorSelected, vec![Key(KeyCode::V)]),
+            (Action::ResetColors, vec![Shift(KeyCode::V)]),
+            (Action::ExportSelection, vec![Ctrl(KeyCode::J)]),
+            (Action::PinSelected, vec![Key(KeyCode::K)]),
+            (Action::Undo, vec![Ctrl(KeyCode::Z)]),
+            (Action::Redo, vec![Ctrl(KeyCode::Y), CtrlShift(KeyCode::Z)]),
+            (Action::SaveScene, vec![Ctrl(KeyCode::S)]),
+            (Action::CopyTarget, vec![Ctrl(KeyCode::C)]),
+            (Action::ToggleEditing, vec![Key(KeyCode::E)]),
+            (Action::ExportScenario, vec![Ctrl(KeyCode::E)]),
+            (Action::ToggleZoneDrawing, vec![Key(KeyCode::Z)]),
+            (Action::FinishZone, vec![Key(KeyCode::Return)]),
+            (Action::RemoveLastZone, vec![Key(KeyCode::Back)]),
+            (Action::PlayPause, vec![Key(KeyCode::Space)]),
+            (Action::HalfSpeed, vec![Key(KeyCode::Key1)]),
+            (Action::NormalSpeed, vec![Key(KeyCode::Key2)]),
+            (Action::DoubleSpeed, vec![Key(KeyCode::Key3)]),
+            (Action::SeekBack, vec![Shift(KeyCode::Left)]),
+            (Action::SeekForward, vec![Shift(KeyCode::Right)]),
+            (Action::SeekStart, vec![Shift(KeyCode::Home)]),
+            (Action::PageUp, vec![Key(KeyCode::PageUp)]),
+            (Action::PageDown, vec![Key(KeyCode::PageDown)]),
+            (Action::RingSpacingDown, vec![Ctrl(KeyCode::Minus)]),
+            (Action::RingSpacingUp, vec![Ctrl(KeyCode::Equals)]),
+            (Action::ScatterDown, vec![Key(KeyCode::LBracket)]),
+            (Action::ScatterUp, vec![Key(KeyCode::RBracket)]),
+            (Action::MarkerWidthDown, vec![Key(KeyCode::Comma)]),
+            (Action::MarkerWidthUp, vec![Key(KeyCode::Period)]),
+            (Action::FocusNextBody, vec![Key(KeyCode::Tab)]),
+            (Action::ToggleFlatMap, vec![Key(KeyCode::F)]),
+            (Action::ToggleAtmosphere, vec![Key(KeyCode::G)]),
+            (Action::ToggleShading, vec![Key(KeyCode::L)]),
+            (Action::ToggleProbe, vec![Key(KeyCode::P)]),
+            (Action::ToggleTuning, vec![Key(KeyCode::T)]),
+            (Action::CycleCoordFormat, vec![Key(KeyCode::F9)]),
+            (Action::ExportSvg, vec![Key(KeyCode::F10)]),
+            (Action::Snapshot, vec![Key(KeyCode::F11)]),
+            (Action::ToggleDebugOverlay, vec![Key(KeyCode::F12)]),
+        ];
+        ActionMap {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl ActionMap {
+    /// No action bound to anything.
+    pub fn empty() -> Self {
+        ActionMap {
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], |bindings| bindings)
+    }
+
+    /// Binds `action` to `bindings` instead of what it was bound to.
+    pub fn bind(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.bindings.insert(action, bindings);
+    }
+
+    /// A binding that triggers more than one action, with the first two it triggers.
+    pub fn duplicate(&self) -> Option<(Binding, Action, Action)> {
+        let mut seen = HashMap::new();
+        for (&action, bindings) in &self.bindings {
+            for &binding in bindings {
+                match seen.insert(binding, action) {
+                    Some(first) if first != action => return Some((binding, first, action)),
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// Rebinds the actions the file at `path` lists. Leaves the map as it was and
+    /// fails if that would leave a binding on two actions.
+    pub fn merge_file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let name = path.display().to_string();
+        let text = fs::read_to_string(path).map_err(|e| ConfigError::Io(name.clone(), e))?;
+        let is_toml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        let bindings: BTreeMap<Action, Vec<Binding>> = if is_toml {
+            toml::from_str(&text).map_err(|e| ConfigError::Parse(name.clone(), e.to_string()))?
+        } else {
+            ron::de::from_str(&text).map_err(|e| ConfigError::Parse(name.clone(), e.to_string()))?
+        };
+        let mut merged = self.clone();
+        merged.bindings.extend(bindings);
+        if let Some((binding, first, second)) = merged.duplicate() {
+            return Err(ConfigError::Bindings(
+                name,
+                format!(
+                    "{:?} is bound to both {:?} and {:?}",
+                    binding, first, second
+                ),
+            ));
+        }
+        *self = merged;
+        Ok(())
+    }
+}
+
+/// Which [`Action`]s are held, and which started or stopped this frame, as the
+/// [`ActionMap`] says of the raw input. Systems read it instead of the keyboard, mouse
+/// and gamepad, so that what they respond to can be rebound.
+#[derive(Debug, Clone, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+    /// Held by [`ActionState::press`] rather than by a binding.
+    forced: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Held this frame and not the one before, however many of its bindings are.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Let go this frame, none of its bindings held any more.
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// How far the held actions push along an axis: 1 for `more`, -1 for `less`, 0
+    /// for both or neither.
+    pub fn axis(&self, less: Action, more: Action) -> f32 {
+        self.pressed(more) as i32 as f32 - self.pressed(less) as i32 as f32
+    }
+
+    /// Holds `action` from the next update on until [`ActionState::release`], as if one
+    /// of its bindings were, for input the [`ActionMap`] doesn't cover, such as taps.
+    pub fn press(&mut self, action: Action) {
+        self.forced.insert(action);
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.forced.remove(&action);
+    }
+
+    /// Moves on a frame, with `held` the actions now held by their bindings.
+    fn update(&mut self, held: impl IntoIterator<Item = Action>) {
+        let pressed = held
+            .into_iter()
+            .chain(self.forced.iter().copied())
+            .collect::<HashSet<_>>();
+        self.just_pressed = pressed.difference(&self.pressed).copied().collect();
+        self.just_released = self.pressed.difference(&pressed).copied().collect();
+        self.pressed = pressed;
+    }
+}
+
+/// Keeps the [`ActionState`] up to date from the [`ActionMap`] resource, the default
+/// one unless the app adds its own. The plugins that act on actions add it
+/// themselves; it only installs once however often it is added.
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+    }
+}
+
+pub(crate) fn add_actions(app: &mut AppBuilder) {
+    if app.resources().contains::<ActionState>() {
+        return;
+    }
+    if !app.resources().contains::<ActionMap>() {
+        app.init_resource::<ActionMap>();
+    }
+    app.init_resource::<ActionState>()
+        .add_stage_after(stage::PRE_UPDATE, ACTIONS)
+        .add_system_to_stage(ACTIONS, action_system.system());
+}
+
+/// Whether `button` is among `buttons`, whichever gamepad it is on.
+fn on_any_pad<'a>(
+    mut buttons: impl Iterator<Item = &'a GamepadButton>,
+    button: GamepadButtonType,
+) -> bool {
+    buttons.any(|&GamepadButton(_, pressed)| pressed == button)
+}
+
+fn action_system(
+    map: Res<ActionMap>,
+    mut state: ResMut<ActionState>,
+    keyboard: Res<Input<KeyCode>>,
+    mouse_button: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    let any = |keys: [KeyCode; 2]| keys.iter().any(|&key| keyboard.pressed(key));
+    let ctrl = any([KeyCode::LControl, KeyCode::RControl]);
+    let shift = any([KeyCode::LShift, KeyCode::RShift]);
+    let with = |key: KeyCode, with_ctrl: bool, with_shift: bool| {
+        keyboard.pressed(key) && ctrl == with_ctrl && shift == with_shift
+    };
+    let held = |binding: Binding| match binding {
+        Binding::Key(key) if is_modifier(key) => keyboard.pressed(key),
+        Binding::Key(key) => with(key, false, false),
+        Binding::Ctrl(key) => with(key, true, false),
+        Binding::Shift(key) => with(key, false, true),
+        Binding::CtrlShift(key) => with(key, true, true),
+        Binding::Mouse(button) => mouse_button.pressed(button),
+        Binding::Gamepad(button) => on_any_pad(gamepad_buttons.get_pressed(), button),
+    };
+    state.update(
+        map.bindings
+            .iter()
+            .filter(|(_, bindings)| bindings.iter().any(|&binding| held(binding)))
+            .map(|(&action, _)| action),
+    );
+}
+
+fn is_modifier(key: KeyCode) -> bool {
+    matches!(
+        key,
+        KeyCode::LControl | KeyCode::RControl | KeyCode::LShift | KeyCode::RShift
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_are_distinct() {
+        assert_eq!(ActionMap::default().duplicate(), None);
+    }
+
+    #[test]
+    fn a_second_binding_held_does_not_press_again() {
+        let mut state = ActionState::default();
+        state.update([Action::ZoomIn]);
+        assert!(state.just_pressed(Action::ZoomIn));
+        state.update([Action::ZoomIn]);
+        assert!(!state.just_pressed(Action::ZoomIn));
+        state.update([]);
+        assert!(state.just_released(Action::ZoomIn));
+    }
+
+    #[test]
+    fn pressed_actions_stay_held_until_released() {
+        let mut state = ActionState::default();
+        state.press(Action::Select);
+        state.update([]);
+        assert!(state.just_pressed(Action::Select));
+        state.update([Action::Select]);
+        assert!(state.pressed(Action::Select) && !state.just_pressed(Action::Select));
+        state.release(Action::Select);
+        state.update([]);
+        assert!(state.just_released(Action::Select));
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::display::{Poi, PoiLabel};
+use crate::easing::{Easing, EasingConfig};
+use crate::emphasis::Emphasis;
+use crate::target::Target;
+
+/// When a target was last reported, in seconds since startup. Set by
+/// [`TargetUpdatesPlugin`](crate::updates::TargetUpdatesPlugin) on every report;
+/// targets without it, like those loaded from a scenario, never age.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LastSeen(pub f64);
+
+/// How [`AgingPlugin`] fades out targets whose reports stop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aging {
+    /// Seconds without a report before a target starts to fade.
+    pub fade_after: f32,
+    /// Seconds without a report before a target is dropped.
+    pub timeout: f32,
+    /// Alpha a target has faded to just before it is dropped, relative to its
+    /// emphasis.
+    pub min_alpha: f32,
+    /// How the alpha goes from full to `min_alpha` between `fade_after` and `timeout`.
+    pub easing: Easing,
+}
+
+impl Default for Aging {
+    fn default() -> Self {
+        Aging {
+            fade_after: 10.0,
+            timeout: 60.0,
+            min_alpha: 0.2,
+            easing: EasingConfig::default().fade,
+        }
+    }
+}
+
+impl Aging {
+    /// How much of its normal alpha a target `age` seconds after its last report is
+    /// drawn with.
+    pub fn fade(&self, age: f32) -> f32 {
+        if age <= self.fade_after {
+            return 1.0;
+        }
+        let span = self.timeout - self.fade_after;
+        if span <= 0.0 {
+            return self.min_alpha;
+        }
+        self.easing
+            .lerp(1.0, self.min_alpha, (age - self.fade_after) / span)
+            .clamp(0.0, 1.0)
+    }
+}
+
+/// A target was dropped by [`AgingPlugin`] after going [`Aging::timeout`] seconds
+/// without a report.
+#[derive(Debug, Clone)]
+pub struct TargetExpired {
+    pub target: Target,
+    pub last_seen: f64,
+}
+
+/// Fades the markers and labels of targets as their [`LastSeen`] report ages, as set
+/// by the [`Aging`] resource, and despawns them with a [`TargetExpired`] event once
+/// they time out.
+pub struct AgingPlugin;
+
+impl Plugin for AgingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Aging>() {
+            app.init_resource::<Aging>();
+        }
+        app.add_event::<TargetExpired>()
+            .add_system(aging_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn aging_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    aging: Res<Aging>,
+    mut expired: ResMut<Events<TargetExpired>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    targets: Query<(Entity, &Target, &LastSeen)>,
+    markers: Query<With<Poi, (&Target, &Handle<ColorMaterial>, Option<&Emphasis>)>>,
+    mut labels: Query<(&PoiLabel, Option<&Emphasis>, Mut<Text>)>,
+) {
+    let now = time.seconds_since_startup;
+    let mut fades = HashMap::new();
+    for (entity, target, last_seen) in targets.iter() {
+        let age = (now - last_seen.0) as f32;
+        if age >= aging.timeout {
+            commands.despawn(entity);
+            expired.send(TargetExpired {
+                target: target.clone(),
+                last_seen: last_seen.0,
+            });
+        } else {
+            fades.insert(target.id, aging.fade(age));
+        }
+    }
+
+    for (target, material, emphasis) in markers.iter() {
+        let alpha = emphasis.copied().unwrap_or_default().alpha()
+            * fades.get(&target.id).copied().unwrap_or(1.0);
+        // Only touch materials that need it, so unchanged ones are not re-uploaded.
+        if materials
+            .get(material)
+            .is_some_and(|m| m.color.a() != alpha)
+        {
+            if let Some(material) = materials.get_mut(material) {
+                material.color.set_a(alpha);
+            }
+        }
+    }
+    for (label, emphasis, mut text) in labels.iter_mut() {
+        let alpha = emphasis.copied().unwrap_or_default().alpha()
+            * fades.get(&label.id).copied().unwrap_or(1.0);
+        if text.style.color.a() != alpha {
+            text.style.color.set_a(alpha);
+        }
+    }
+}
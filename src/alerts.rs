@@ -0,0 +1,209 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::display::Slot;
+use crate::emphasis::{Emphasis, TargetEmphasis};
+use crate::events::DisplayEvent;
+use crate::target::Target;
+
+/// One alert rule, as listed under `alerts` in a scenario file:
+///
+/// ```json
+/// { "category": "hostile", "when": { "within_ring": 2 }, "then": ["highlight", { "sound": "alert.mp3" }] }
+/// { "category": "friend", "when": "lost", "then": ["log"] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Only targets of this category trip the rule; all targets when absent.
+    #[serde(default)]
+    pub category: Option<String>,
+    pub when: AlertTrigger,
+    #[serde(default)]
+    pub then: Vec<AlertAction>,
+}
+
+impl AlertRule {
+    fn applies_to(&self, category: Option<&str>) -> bool {
+        self.category.is_none() || self.category.as_deref() == category
+    }
+}
+
+/// What trips an [`AlertRule`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertTrigger {
+    /// The target is placed on this ring or an inner one, counting the innermost as 0.
+    /// Trips again only after the target has been placed further out.
+    WithinRing(usize),
+    /// The target was removed.
+    Lost,
+    /// The target entered the named zone.
+    EnteredZone(String),
+    /// The target left the named zone.
+    LeftZone(String),
+}
+
+/// What an [`AlertRule`] does when tripped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertAction {
+    /// Highlights the target, see [`Emphasis::Highlight`].
+    Highlight,
+    /// Plays the sound file at this asset path.
+    Sound(String),
+    /// Prints the alert to stdout.
+    Log,
+}
+
+/// Sent by [`AlertsPlugin`] for every tripped rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// Index of the rule in [`AlertsPlugin::rules`].
+    pub rule: usize,
+    pub target: i32,
+    pub message: String,
+}
+
+/// Checks the targets against alert rules every update and carries out their actions,
+/// see [`AlertRule`]. Zone rules follow the
+/// [`DisplayEvent::ZoneCrossed`] events of
+/// [`AlertZonesPlugin`](crate::zones::AlertZonesPlugin); sounds need bevy's
+/// `AudioPlugin`, part of `DefaultPlugins`.
+#[derive(Debug, Clone, Default)]
+pub struct AlertsPlugin {
+    pub rules: Vec<AlertRule>,
+}
+
+impl Plugin for AlertsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<TargetEmphasis>() {
+            app.init_resource::<TargetEmphasis>();
+        }
+        app.add_resource(AlertRules(self.rules.clone()))
+            .add_event::<Alert>()
+            .add_system(alert_system.system())
+            .add_system(alert_action_system.system());
+    }
+}
+
+struct AlertRules(Vec<AlertRule>);
+
+#[derive(Default)]
+struct AlertState {
+    reader: EventReader<DisplayEvent>,
+    /// Id, category and label of every target seen, for telling what was lost.
+    seen: HashMap<Entity, (i32, Option<String>, String)>,
+    /// Rule and target pairs currently inside their ring.
+    inside: HashSet<(usize, i32)>,
+}
+
+fn alert_system(
+    mut state: Local<AlertState>,
+    rules: Res<AlertRules>,
+    display_events: Res<Events<DisplayEvent>>,
+    mut alerts: ResMut<Events<Alert>>,
+    targets: Query<(Entity, &Target, Option<&Slot>)>,
+) {
+    let state = &mut *state;
+    let mut raise = |rule: usize, target: i32, message: String| {
+        alerts.send(Alert {
+            rule,
+            target,
+            message,
+        })
+    };
+    for (entity, target, slot) in targets.iter() {
+        state.seen.insert(
+            entity,
+            (target.id, target.category.clone(), target.text.clone()),
+        );
+        for (i, rule) in rules.0.iter().enumerate() {
+            let max = match rule.when {
+                AlertTrigger::WithinRing(max) => max,
+                _ => continue,
+            };
+            let ring = slot
+                .map(|slot| slot.ring)
+                .filter(|&ring| ring <= max && rule.applies_to(target.category.as_deref()));
+            match ring {
+                None => {
+                    state.inside.remove(&(i, target.id));
+                }
+                Some(ring) if state.inside.insert((i, target.id)) => raise(
+                    i,
+                    target.id,
+                    format!("{} entered ring {}", describe(target), ring),
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+    for entity in targets.removed::<Target>() {
+        let (id, category, text) = match state.seen.remove(entity) {
+            Some(seen) => seen,
+            None => continue,
+        };
+        state.inside.retain(|&(_, target)| target != id);
+        for (i, rule) in rules.0.iter().enumerate() {
+            if rule.when == AlertTrigger::Lost && rule.applies_to(category.as_deref()) {
+                raise(i, id, format!("{} (#{}) lost", text, id));
+            }
+        }
+    }
+    for event in state.reader.iter(&display_events) {
+        let (id, zone, entered) = match event {
+            DisplayEvent::ZoneCrossed {
+                target,
+                zone,
+                entered,
+            } => (*target, zone, *entered),
+            _ => continue,
+        };
+        let target = match targets.iter().find(|(_, t, _)| t.id == id) {
+            Some((_, target, _)) => target,
+            None => continue,
+        };
+        for (i, rule) in rules.0.iter().enumerate() {
+            let tripped = match &rule.when {
+                AlertTrigger::EnteredZone(name) => entered && name == zone,
+                AlertTrigger::LeftZone(name) => !entered && name == zone,
+                _ => false,
+            };
+            if tripped && rule.applies_to(target.category.as_deref()) {
+                let verb = if entered { "entered" } else { "left" };
+                raise(
+                    i,
+                    id,
+                    format!("{} {} zone {}", describe(target), verb, zone),
+                );
+            }
+        }
+    }
+}
+
+fn describe(target: &Target) -> String {
+    format!("{} (#{})", target.text, target.id)
+}
+
+fn alert_action_system(
+    mut reader: Local<EventReader<Alert>>,
+    rules: Res<AlertRules>,
+    alerts: Res<Events<Alert>>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    mut emphasis: ResMut<TargetEmphasis>,
+) {
+    for alert in reader.iter(&alerts) {
+        for action in &rules.0[alert.rule].then {
+            match action {
+                AlertAction::Highlight => {
+                    emphasis.set_target_emphasis(alert.target, Emphasis::Highlight)
+                }
+                AlertAction::Sound(path) => audio.play(asset_server.load(path.as_str())),
+                AlertAction::Log => tracing::info!("alert: {}", alert.message),
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use bevy::asset::{AssetServerError, HandleId};
+use bevy::prelude::*;
+use bevy::render::render_graph::{base, Node, RenderGraph, ResourceSlots};
+use bevy::render::renderer::{BufferInfo, BufferUsage, RenderContext};
+use bevy::render::texture::{Extent3d, TEXTURE_ASSET_INDEX};
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+
+/// Render graph node copying [`AnimatedTexture`] frames onto the GPU.
+const TEXTURE_UPLOAD_NODE: &str = "animated_texture_upload";
+/// Rows of a buffer copied into a texture start this many bytes apart, at least.
+const ROW_ALIGNMENT: usize = 256;
+
+/// Where an [`AnimatedTexture`]'s frames come from.
+pub enum FrameSource {
+    /// Textures shown one after another, `fps` a second, such as an image sequence
+    /// from [`image_sequence`]. Frames that haven't loaded yet are skipped.
+    Sequence {
+        frames: Vec<Handle<Texture>>,
+        fps: f32,
+        /// Start over after the last frame rather than stay on it.
+        looping: bool,
+    },
+    /// Frames made elsewhere, such as by a video decoder on its own thread, each shown
+    /// as it arrives; when several arrive in one frame only the last is.
+    Stream(Receiver<Texture>),
+}
+
+/// Plays a [`FrameSource`] on `texture`, so that every material sampling it, such as
+/// the globe's, shows the frames in turn. Frames of the texture's size and format are
+/// copied straight into the GPU's copy of it, leaving its sampler and bind groups
+/// alone, and the texture asset keeps the data it had; any other frame replaces the
+/// asset, which is much slower but makes later frames of that size fast. Needs an
+/// [`AnimatedTexturePlugin`].
+pub struct AnimatedTexture {
+    pub texture: Handle<Texture>,
+    pub source: FrameSource,
+    /// Advance through a [`FrameSource::Sequence`]; streams always show what arrives.
+    pub playing: bool,
+    /// Seconds of [`AnimationTime`] the sequence has played for.
+    elapsed: f64,
+    /// Sequence frame last shown.
+    shown: Option<usize>,
+}
+
+impl AnimatedTexture {
+    pub fn new(texture: Handle<Texture>, source: FrameSource) -> Self {
+        AnimatedTexture {
+            texture,
+            source,
+            playing: true,
+            elapsed: 0.0,
+            shown: None,
+        }
+    }
+
+    /// `frames` on `texture` at `fps`, looping.
+    pub fn sequence(texture: Handle<Texture>, frames: Vec<Handle<Texture>>, fps: f32) -> Self {
+        AnimatedTexture::new(
+            texture,
+            FrameSource::Sequence {
+                frames,
+                fps,
+                looping: true,
+            },
+        )
+    }
+
+    /// Frames sent down the returned channel, which holds up to `capacity` of them
+    /// before sending blocks, shown on `texture`.
+    pub fn stream(texture: Handle<Texture>, capacity: usize) -> (Self, Sender<Texture>) {
+        let (sender, receiver) = crossbeam_channel::bounded(capacity);
+        (
+            AnimatedTexture::new(texture, FrameSource::Stream(receiver)),
+            sender,
+        )
+    }
+
+    /// The sequence frame shown last, if any has been.
+    pub fn frame(&self) -> Option<usize> {
+        self.shown
+    }
+
+    /// Goes to `seconds` into a sequence.
+    pub fn seek(&mut self, seconds: f64) {
+        self.elapsed = seconds.max(0.0);
+    }
+
+    /// Which of `frames` to show `elapsed` seconds in, `None` for none at all.
+    fn sequence_frame(&self, frames: usize, fps: f32, looping: bool) -> Option<usize> {
+        if frames == 0 {
+            return None;
+        }
+        let index = (self.elapsed * fps.max(0.0) as f64) as usize;
+        Some(if looping {
+            index % frames
+        } else {
+            index.min(frames - 1)
+        })
+    }
+}
+
+/// Loads every image in `folder`, relative to the assets directory, as the frames of a
+/// [`FrameSource::Sequence`], in order of their names, so numbered ones like
+/// `clouds/0001.png` play in order.
+pub fn image_sequence(
+    asset_server: &AssetServer,
+    folder: &str,
+) -> Result<Vec<Handle<Texture>>, AssetServerError> {
+    let mut frames = asset_server
+        .load_folder(folder)?
+        .into_iter()
+        .filter_map(|handle| {
+            let path = asset_server.get_handle_path(&handle)?;
+            Some((path.path().to_path_buf(), handle.typed::<Texture>()))
+        })
+        .collect::<Vec<_>>();
+    frames.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(frames.into_iter().map(|(_, frame)| frame).collect())
+}
+
+/// A frame waiting for [`TextureUploadNode`], to copy into `texture`.
+struct Upload {
+    texture: HandleId,
+    frame: Frame,
+}
+
+enum Frame {
+    Asset(Handle<Texture>),
+    Decoded(Texture),
+}
+
+#[derive(Default)]
+struct TextureUploads(Vec<Upload>);
+
+/// Plays every [`AnimatedTexture`]. Add after the render plugins.
+pub struct AnimatedTexturePlugin;
+
+impl Plugin for AnimatedTexturePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<TextureUploads>()
+            .add_plugin(AnimationTimePlugin)
+            .add_system(animated_texture_system.system());
+        let resources = app.resources();
+        let mut graph = resources.get_mut::<RenderGraph>().unwrap();
+        graph.add_node(TEXTURE_UPLOAD_NODE, TextureUploadNode::default());
+        // After the textures created or replaced this frame are on the GPU.
+        graph
+            .add_node_edge(base::node::TEXTURE_COPY, TEXTURE_UPLOAD_NODE)
+            .unwrap();
+        graph
+            .add_node_edge(TEXTURE_UPLOAD_NODE, base::node::MAIN_PASS)
+            .unwrap();
+    }
+}
+
+/// Whether `frame` can be copied into `texture` as it is.
+fn fits(frame: &Texture, texture: &Texture) -> bool {
+    frame.size == texture.size && frame.format == texture.format
+}
+
+fn animated_texture_system(
+    time: Res<AnimationTime>,
+    mut uploads: ResMut<TextureUploads>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut animated: Query<Mut<AnimatedTexture>>,
+) {
+    for mut animated in animated.iter_mut() {
+        let animated = &mut *animated;
+        let target = animated.texture.id;
+        // Nothing to show frames on until the texture itself has loaded.
+        if textures.get(target).is_none() {
+            continue;
+        }
+        let frame = match &animated.source {
+            FrameSource::Sequence {
+                frames,
+                fps,
+                looping,
+            } => {
+                if animated.playing {
+                    animated.elapsed += time.delta_seconds() as f64;
+                }
+                let index = animated.sequence_frame(frames.len(), *fps, *looping);
+                let frame = match index.map(|index| &frames[index]) {
+                    Some(frame) if index != animated.shown && textures.get(frame).is_some() => {
+                        frame
+                    }
+                    _ => continue,
+                };
+                animated.shown = index;
+                Frame::Asset(frame.clone())
+            }
+            FrameSource::Stream(receiver) => match receiver.try_iter().last() {
+                Some(frame) => Frame::Decoded(frame),
+                None => continue,
+            },
+        };
+        let texture = textures.get(target).unwrap();
+        let fitting = match &frame {
+            Frame::Asset(handle) => textures.get(handle),
+            Frame::Decoded(frame) => Some(frame),
+        }
+        .is_some_and(|frame| fits(frame, texture));
+        if fitting {
+            uploads.0.retain(|upload| upload.texture != target);
+            uploads.0.push(Upload {
+                texture: target,
+                frame,
+            });
+            continue;
+        }
+        // Another size or format: replace the asset, keeping how it was sampled.
+        let sampler = texture.sampler;
+        let mut replacement = match frame {
+            Frame::Asset(handle) => match textures.get(&handle) {
+                Some(frame) => frame.clone(),
+                None => continue,
+            },
+            Frame::Decoded(frame) => frame,
+        };
+        replacement.sampler = sampler;
+        uploads.0.retain(|upload| upload.texture != target);
+        textures.set_untracked(target, replacement);
+    }
+}
+
+/// Copies the frames [`animated_texture_system`] queued into the GPU textures already
+/// made for their targets, through a staging buffer, like bevy's own texture copy but
+/// without making the texture again. Frames for textures not on the GPU yet wait.
+#[derive(Default)]
+struct TextureUploadNode {
+    /// Rows padded out to [`ROW_ALIGNMENT`], kept between frames.
+    padded: Vec<u8>,
+}
+
+impl Node for TextureUploadNode {
+    fn update(
+        &mut self,
+        _world: &World,
+        resources: &Resources,
+        render_context: &mut dyn RenderContext,
+        _input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        let mut uploads = resources.get_mut::<TextureUploads>().unwrap();
+        let textures = resources.get::<Assets<Texture>>().unwrap();
+        let mut waiting = Vec::new();
+        for upload in uploads.0.drain(..) {
+            let resource = render_context.resources().get_asset_resource(
+                &Handle::<Texture>::weak(upload.texture),
+                TEXTURE_ASSET_INDEX,
+            );
+            let destination = match resource.and_then(|resource| resource.get_texture()) {
+                Some(destination) => destination,
+                None => {
+                    waiting.push(upload);
+                    continue;
+                }
+            };
+            let frame = match &upload.frame {
+                Frame::Asset(handle) => textures.get(handle),
+                Frame::Decoded(frame) => Some(frame),
+            };
+            let frame = match frame {
+                Some(frame) => frame,
+                None => continue,
+            };
+            let row = frame.size.x() as usize * frame.format.pixel_size();
+            let height = frame.size.y() as usize;
+            let stride = row.div_ceil(ROW_ALIGNMENT) * ROW_ALIGNMENT;
+            let data = if stride == row {
+                &frame.data[..row * height]
+            } else {
+                self.padded.resize(stride * height, 0);
+                for (from, to) in frame
+                    .data
+                    .chunks_exact(row)
+                    .zip(self.padded.chunks_exact_mut(stride))
+                {
+                    to[..row].copy_from_slice(from);
+                }
+                &self.padded[..]
+            };
+            let buffer = render_context.resources().create_buffer_with_data(
+                BufferInfo {
+                    buffer_usage: BufferUsage::COPY_SRC,
+                    ..Default::default()
+                },
+                data,
+            );
+            render_context.copy_buffer_to_texture(
+                buffer,
+                0,
+                stride as u32,
+                destination,
+                [0, 0, 0],
+                0,
+                Extent3d {
+                    width: frame.size.x() as u32,
+                    height: height as u32,
+                    depth: 1,
+                },
+            );
+            render_context.resources().remove_buffer(buffer);
+        }
+        uploads.0 = waiting;
+    }
+}
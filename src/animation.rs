@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+
+/// The clock animations advance by, so motion depends on how much time passed rather
+/// than on how many frames or events there were. It follows `Time` with each frame's
+/// step capped at `max_delta`, so a hitch such as a window drag or a long asset load
+/// plays out as one short step instead of a jump, and scaled by `scale`, 0 while
+/// `paused`.
+///
+/// [`delta_seconds`](AnimationTime::delta_seconds) and
+/// [`seconds`](AnimationTime::seconds) are for things that move on their own, like
+/// tweens, the sweep and orbits; motion the user drives, like camera moves, takes
+/// [`unscaled_delta_seconds`](AnimationTime::unscaled_delta_seconds) so it still
+/// responds while animations are paused.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationTime {
+    /// Speed of animations relative to real time.
+    pub scale: f32,
+    pub paused: bool,
+    /// Longest step of one frame, in seconds.
+    pub max_delta: f32,
+    unscaled_delta: f32,
+    delta: f32,
+    elapsed: f64,
+}
+
+impl Default for AnimationTime {
+    fn default() -> Self {
+        AnimationTime {
+            scale: 1.0,
+            paused: false,
+            max_delta: 0.1,
+            unscaled_delta: 0.0,
+            delta: 0.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl AnimationTime {
+    /// Seconds animations advanced by this frame.
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta
+    }
+
+    /// Seconds since this frame's step, capped but neither scaled nor paused.
+    pub fn unscaled_delta_seconds(&self) -> f32 {
+        self.unscaled_delta
+    }
+
+    /// Seconds animations advanced by in all, the sum of every
+    /// [`delta_seconds`](AnimationTime::delta_seconds).
+    pub fn seconds(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Steps the clock by `real_delta` seconds of real time.
+    pub fn advance(&mut self, real_delta: f32) {
+        self.unscaled_delta = real_delta.max(0.0).min(self.max_delta);
+        self.delta = if self.paused {
+            0.0
+        } else {
+            self.unscaled_delta * self.scale.max(0.0)
+        };
+        self.elapsed += self.delta as f64;
+    }
+}
+
+/// Keeps [`AnimationTime`] up to date. The plugins that animate add it themselves; it
+/// only installs once however often it is added.
+pub struct AnimationTimePlugin;
+
+/// Present once [`AnimationTimePlugin`] has installed its system.
+struct AnimationClock;
+
+impl Plugin for AnimationTimePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if app.resources().contains::<AnimationClock>() {
+            return;
+        }
+        if !app.resources().contains::<AnimationTime>() {
+            app.init_resource::<AnimationTime>();
+        }
+        app.add_resource(AnimationClock)
+            .add_system_to_stage(stage::PRE_UPDATE, animation_time_system.system());
+    }
+}
+
+fn animation_time_system(time: Res<Time>, mut animation: ResMut<AnimationTime>) {
+    animation.advance(time.delta_seconds);
+}
@@ -0,0 +1,203 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::render::texture::TextureFormat;
+use thiserror::Error;
+
+/// The label font, built in so that text still shows when `arial.ttf` can't be loaded.
+const FALLBACK_FONT: &[u8] = include_bytes!("../assets/arial.ttf");
+const WARNING_FONT_SIZE: f32 = 16.0;
+/// Room between the on-screen warnings, and around them, in pixels.
+const WARNING_SPACING: f32 = 6.0;
+/// Degrees between the lines of the fallback texture.
+const GRID_STEP: usize = 30;
+
+/// An asset [`AssetCheckPlugin`] couldn't load, and what shows in its place. The
+/// asset server doesn't tell a missing file from one it couldn't read.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AssetError {
+    #[error("font {0} is missing or unreadable; using the built-in font")]
+    Font(String),
+    #[error("texture {0} is missing or unreadable; using a plain grid")]
+    Texture(String),
+}
+
+impl AssetError {
+    /// The asset's path, relative to the assets directory.
+    pub fn path(&self) -> &str {
+        match self {
+            AssetError::Font(path) | AssetError::Texture(path) => path,
+        }
+    }
+}
+
+/// Loads `fonts` and `textures` and, for each that fails to load, puts a built-in
+/// stand-in under its handle, so that everything using it shows the stand-in, sends
+/// an [`AssetError`] and, with `warn_on_screen`, lists it in the top left corner of
+/// the window, which needs a UI camera. Add after the default plugins.
+pub struct AssetCheckPlugin {
+    /// Paths relative to the assets directory.
+    pub fonts: Vec<String>,
+    pub textures: Vec<String>,
+    pub warn_on_screen: bool,
+}
+
+impl Default for AssetCheckPlugin {
+    fn default() -> Self {
+        AssetCheckPlugin {
+            fonts: vec!["arial.ttf".to_string()],
+            textures: vec!["theworld.png".to_string()],
+            warn_on_screen: true,
+        }
+    }
+}
+
+enum Checked {
+    Font(Handle<Font>),
+    Texture(Handle<Texture>),
+}
+
+struct AssetChecks {
+    /// Still loading.
+    pending: Vec<(String, Checked)>,
+    /// Failed ones, held so that they aren't loaded again over their stand-ins.
+    failed: Vec<Checked>,
+    fallback_font: Handle<Font>,
+}
+
+impl Plugin for AssetCheckPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let checks = {
+            let resources = app.resources();
+            let asset_server = resources
+                .get::<AssetServer>()
+                .expect("the asset server comes with the default plugins");
+            let mut fonts = resources.get_mut::<Assets<Font>>().unwrap();
+            let pending = self
+                .fonts
+                .iter()
+                .map(|path| {
+                    (
+                        path.clone(),
+                        Checked::Font(asset_server.load(path.as_str())),
+                    )
+                })
+                .chain(self.textures.iter().map(|path| {
+                    let texture = asset_server.load(path.as_str());
+                    (path.clone(), Checked::Texture(texture))
+                }))
+                .collect();
+            AssetChecks {
+                pending,
+                failed: Vec::new(),
+                fallback_font: fonts.add(fallback_font()),
+            }
+        };
+        app.add_event::<AssetError>()
+            .add_resource(checks)
+            .add_system(asset_check_system.system());
+        if self.warn_on_screen {
+            app.add_system(asset_warning_system.system());
+        }
+    }
+}
+
+fn fallback_font() -> Font {
+    Font::try_from_bytes(FALLBACK_FONT.to_vec()).expect("the built-in font is valid")
+}
+
+/// An equirectangular stand-in for a globe texture: dark grey with a line every
+/// [`GRID_STEP`] degrees of latitude and longitude.
+fn fallback_texture() -> Texture {
+    const BACKGROUND: [u8; 4] = [40, 40, 44, 255];
+    const LINE: [u8; 4] = [110, 110, 118, 255];
+    let (width, height) = (360, 180);
+    let mut data = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let line = x % GRID_STEP == 0 || y % GRID_STEP == 0;
+            data.extend_from_slice(if line { &LINE } else { &BACKGROUND });
+        }
+    }
+    Texture::new(
+        Vec2::new(width as f32, height as f32),
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn asset_check_system(
+    asset_server: Res<AssetServer>,
+    mut checks: ResMut<AssetChecks>,
+    mut errors: ResMut<Events<AssetError>>,
+    mut fonts: ResMut<Assets<Font>>,
+    mut textures: ResMut<Assets<Texture>>,
+) {
+    if checks.pending.is_empty() {
+        return;
+    }
+    let checks = &mut *checks;
+    let mut pending = Vec::new();
+    for (path, checked) in checks.pending.drain(..) {
+        let id = match &checked {
+            Checked::Font(handle) => handle.id,
+            Checked::Texture(handle) => handle.id,
+        };
+        match asset_server.get_load_state(id) {
+            LoadState::Loaded => continue,
+            LoadState::Failed => {}
+            LoadState::NotLoaded | LoadState::Loading => {
+                pending.push((path, checked));
+                continue;
+            }
+        }
+        match &checked {
+            Checked::Font(handle) => {
+                fonts.set_untracked(handle, fallback_font());
+                errors.send(AssetError::Font(path));
+            }
+            Checked::Texture(handle) => {
+                textures.set_untracked(handle, fallback_texture());
+                errors.send(AssetError::Texture(path));
+            }
+        }
+        checks.failed.push(checked);
+    }
+    checks.pending = pending;
+}
+
+/// Writes each [`AssetError`] to stderr and, one under another, in the top left
+/// corner of the window.
+fn asset_warning_system(
+    mut commands: Commands,
+    mut shown: Local<usize>,
+    mut reader: Local<EventReader<AssetError>>,
+    errors: Res<Events<AssetError>>,
+    checks: Res<AssetChecks>,
+) {
+    for error in reader.iter(&errors) {
+        let line = format!("warning: {}", error);
+        tracing::warn!("{}", line);
+        let top = WARNING_SPACING + *shown as f32 * (WARNING_FONT_SIZE + WARNING_SPACING);
+        commands.spawn(TextComponents {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(WARNING_SPACING),
+                    top: Val::Px(top),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text {
+                value: line,
+                font: checks.fallback_font.clone(),
+                style: TextStyle {
+                    font_size: WARNING_FONT_SIZE,
+                    color: Color::rgb(1.0, 0.75, 0.2),
+                },
+            },
+            ..Default::default()
+        });
+        *shown += 1;
+    }
+}
@@ -0,0 +1,285 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::pipeline::{
+    CullMode, DynamicBinding, PipelineDescriptor, PipelineSpecialization, RenderPipeline,
+};
+use bevy::render::render_graph::base::camera::CAMERA3D;
+use bevy::render::render_graph::base::{self, MainPass};
+use bevy::render::render_graph::{AssetRenderResourcesNode, RenderGraph};
+use bevy::render::renderer::RenderResources;
+use bevy::render::shader::{ShaderStage, ShaderStages};
+use bevy::type_registry::TypeUuid;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::bodies::Body;
+use crate::geo_marker::body_for;
+use crate::mesh::sphere_mesh;
+
+/// The pipeline drawing [`AtmosphereMaterial`]s.
+pub const ATMOSPHERE_PIPELINE_HANDLE: Handle<PipelineDescriptor> =
+    Handle::weak_from_u64(PipelineDescriptor::TYPE_UUID, 2_417_905_338_046_118_527);
+/// Render graph node uploading [`AtmosphereMaterial`]s.
+const ATMOSPHERE_MATERIAL_NODE: &str = "atmosphere_material";
+/// Rows of the shell's sphere, which has four times as many columns.
+const SHELL_ROWS: u32 = 48;
+
+const VERTEX_SHADER: &str = r#"
+#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec3 Vertex_Normal;
+layout(location = 2) in vec2 Vertex_Uv;
+
+layout(location = 0) out vec3 v_Position;
+layout(location = 1) out vec3 v_Normal;
+
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 ViewProj;
+};
+
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+
+void main() {
+    v_Position = (Model * vec4(Vertex_Position, 1.0)).xyz;
+    v_Normal = mat3(Model) * Vertex_Normal;
+    gl_Position = ViewProj * vec4(v_Position, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 450
+
+layout(location = 0) in vec3 v_Position;
+layout(location = 1) in vec3 v_Normal;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 2, binding = 0) uniform AtmosphereMaterial_color {
+    vec4 Color;
+};
+layout(set = 2, binding = 1) uniform AtmosphereMaterial_eye {
+    vec3 Eye;
+};
+layout(set = 2, binding = 2) uniform AtmosphereMaterial_limb {
+    float Limb;
+};
+layout(set = 2, binding = 3) uniform AtmosphereMaterial_falloff {
+    float Falloff;
+};
+
+void main() {
+    // Only the far side of the shell is drawn, so its normals face away from the eye,
+    // squarest at the shell's edge and least at the body's.
+    float facing = max(-dot(normalize(v_Normal), normalize(Eye - v_Position)), 0.0);
+    float glow = pow(clamp(facing / Limb, 0.0, 1.0), Falloff);
+    o_Target = vec4(Color.rgb, Color.a * glow);
+}
+"#;
+
+/// A glow around the edge of a [`Body`], drawn by [`AtmospherePlugin`] on the inside of
+/// a shell a little larger than the body. Spawn it as a child of the body it belongs
+/// on, or on its own to put it on the body nearest the world origin, like a
+/// [`Graticule`](crate::graticule::Graticule).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atmosphere {
+    /// Color at the body's edge, fading to nothing at the shell's.
+    pub color: Color,
+    /// How far the shell reaches above the surface, relative to the body's radius.
+    pub thickness: f32,
+    /// How sharply the glow falls off away from the body, as the exponent of the
+    /// shell's facing.
+    pub falloff: f32,
+    pub visible: bool,
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Atmosphere {
+            color: Color::rgba(0.35, 0.6, 1.0, 0.8),
+            thickness: 0.03,
+            falloff: 2.0,
+            visible: true,
+        }
+    }
+}
+
+impl Atmosphere {
+    /// How squarely the far side of the shell faces away from an eye far off, where
+    /// the body's edge lies over it.
+    fn limb(&self) -> f32 {
+        let scale = 1.0 + self.thickness.max(f32::EPSILON);
+        (1.0 - 1.0 / (scale * scale)).sqrt()
+    }
+}
+
+/// The material of an [`Atmosphere`]'s shell, kept up to date with it by
+/// [`AtmospherePlugin`].
+#[derive(Debug, RenderResources, TypeUuid)]
+#[uuid = "9d83f0c2-6a4b-4e57-b1a8-3c20d5e7f614"]
+pub struct AtmosphereMaterial {
+    pub color: Color,
+    /// The 3D camera's position in world space.
+    pub eye: Vec3,
+    /// The shell's facing at the body's edge, where the glow is brightest.
+    pub limb: f32,
+    pub falloff: f32,
+}
+
+/// The render pipelines to give an [`Atmosphere`]'s shell: drawn from the inside, with
+/// the transform bound dynamically like bevy's own.
+fn render_pipelines() -> RenderPipelines {
+    RenderPipelines::from_pipelines(vec![RenderPipeline::specialized(
+        ATMOSPHERE_PIPELINE_HANDLE,
+        PipelineSpecialization {
+            dynamic_bindings: vec![
+                // Transform
+                DynamicBinding {
+                    bind_group: 1,
+                    binding: 0,
+                },
+            ],
+            ..Default::default()
+        },
+    )])
+}
+
+/// Marks an [`Atmosphere`] whose shell has been added, on `body`.
+pub struct AtmosphereDrawn {
+    pub body: Entity,
+}
+
+/// Draws each [`Atmosphere`] as a shell around its body, follows changes to it, and
+/// turns every atmosphere on and off with [`Action::ToggleAtmosphere`], G by default.
+/// Add after the render plugins.
+pub struct AtmospherePlugin;
+
+impl Plugin for AtmospherePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        app.add_asset::<AtmosphereMaterial>()
+            .add_system(atmosphere_draw_system.system())
+            .add_system(atmosphere_sync_system.system())
+            .add_system(atmosphere_toggle_system.system());
+        let resources = app.resources();
+        let mut shaders = resources.get_mut::<Assets<Shader>>().unwrap();
+        let mut pipelines = resources.get_mut::<Assets<PipelineDescriptor>>().unwrap();
+        let mut pipeline = PipelineDescriptor::default_config(ShaderStages {
+            vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER)),
+            fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER))),
+        });
+        if let Some(rasterization) = pipeline.rasterization_state.as_mut() {
+            rasterization.cull_mode = CullMode::Front;
+        }
+        // The glow lies over what is behind it without hiding anything drawn later.
+        if let Some(depth) = pipeline.depth_stencil_state.as_mut() {
+            depth.depth_write_enabled = false;
+        }
+        pipelines.set_untracked(ATMOSPHERE_PIPELINE_HANDLE, pipeline);
+        let mut graph = resources.get_mut::<RenderGraph>().unwrap();
+        graph.add_system_node(
+            ATMOSPHERE_MATERIAL_NODE,
+            AssetRenderResourcesNode::<AtmosphereMaterial>::new(false),
+        );
+        graph
+            .add_node_edge(ATMOSPHERE_MATERIAL_NODE, base::node::MAIN_PASS)
+            .unwrap();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn atmosphere_draw_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AtmosphereMaterial>>,
+    atmospheres: Query<Without<AtmosphereDrawn, (Entity, &Atmosphere, Option<&Parent>)>>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    for (entity, atmosphere, parent) in atmospheres.iter() {
+        let (body, adopt) = match body_for(parent, &bodies) {
+            Some(body) => body,
+            None => continue,
+        };
+        let radius = bodies.get(body).map_or(1.0, |(_, body, _)| body.radius);
+        let material = materials.add(AtmosphereMaterial {
+            color: atmosphere.color,
+            eye: Vec3::zero(),
+            limb: atmosphere.limb(),
+            falloff: atmosphere.falloff,
+        });
+        // The shell is sized by its scale, so changes to the thickness need no new mesh.
+        commands.insert(
+            entity,
+            (
+                AtmosphereDrawn { body },
+                meshes.add(sphere_mesh(radius, SHELL_ROWS, 4 * SHELL_ROWS)),
+                material,
+                MainPass,
+                Draw {
+                    is_visible: atmosphere.visible,
+                    is_transparent: true,
+                    ..Default::default()
+                },
+                render_pipelines(),
+                Transform::from_scale(Vec3::splat(1.0 + atmosphere.thickness)),
+                GlobalTransform::default(),
+            ),
+        );
+        if adopt {
+            commands.push_children(body, &[entity]);
+        }
+    }
+}
+
+fn atmosphere_sync_system(
+    mut materials: ResMut<Assets<AtmosphereMaterial>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut atmospheres: Query<(
+        &Atmosphere,
+        &Handle<AtmosphereMaterial>,
+        &mut Transform,
+        &mut Draw,
+    )>,
+) {
+    let eye = cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
+        .map(|(_, transform)| transform.translation);
+    for (atmosphere, handle, mut transform, mut draw) in atmospheres.iter_mut() {
+        let scale = Vec3::splat(1.0 + atmosphere.thickness);
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+        if draw.is_visible != atmosphere.visible {
+            draw.is_visible = atmosphere.visible;
+        }
+        let material = match materials.get(handle) {
+            Some(material) => material,
+            None => continue,
+        };
+        let eye = eye.unwrap_or(material.eye);
+        let limb = atmosphere.limb();
+        // Only touch materials that changed, as changing one uploads it again.
+        let stale = material.color != atmosphere.color
+            || material.eye != eye
+            || material.limb != limb
+            || material.falloff != atmosphere.falloff;
+        if stale {
+            if let Some(material) = materials.get_mut(handle) {
+                material.color = atmosphere.color;
+                material.eye = eye;
+                material.limb = limb;
+                material.falloff = atmosphere.falloff;
+            }
+        }
+    }
+}
+
+fn atmosphere_toggle_system(actions: Res<ActionState>, mut atmospheres: Query<&mut Atmosphere>) {
+    if actions.just_pressed(Action::ToggleAtmosphere) {
+        for mut atmosphere in atmospheres.iter_mut() {
+            atmosphere.visible = !atmosphere.visible;
+        }
+    }
+}
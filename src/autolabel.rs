@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+use crate::target::Target;
+
+/// When a designator given up by a removed target may be handed out again.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReusePolicy {
+    /// Every designator is used once per run.
+    #[default]
+    Never,
+    /// Released designators are reused, lowest first, once they have been free for this
+    /// many seconds. A target that comes back before then gets its old one again.
+    After(f64),
+}
+
+impl FromStr for ReusePolicy {
+    type Err = String;
+
+    /// `never`, or the timeout in seconds.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "never" {
+            return Ok(ReusePolicy::Never);
+        }
+        match s.parse::<f64>() {
+            Ok(timeout) if timeout >= 0.0 => Ok(ReusePolicy::After(timeout)),
+            _ => Err(format!(
+                "expected never or a number of seconds, got {:?}",
+                s
+            )),
+        }
+    }
+}
+
+/// Hands out short display designators, `A1` to `A9`, `B1` and so on through `Z9`,
+/// then `AA1`, to targets without a label. Designators are unique among the targets
+/// shown, including ones whose own label happens to look like a designator.
+#[derive(Debug, Default)]
+pub struct Designators {
+    pub policy: ReusePolicy,
+    next: usize,
+    assigned: HashMap<i32, usize>,
+    /// Designators given up by removed targets, with who had them and since when.
+    released: Vec<(usize, i32, f64)>,
+}
+
+impl Designators {
+    pub fn new(policy: ReusePolicy) -> Self {
+        Designators {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// The designator of target `id`, if it has one.
+    pub fn get(&self, id: i32) -> Option<String> {
+        self.assigned.get(&id).map(|&n| designator(n))
+    }
+
+    /// The designator of target `id`, assigning one at time `now` (in seconds) if it
+    /// has none. Designators for which `taken` holds are skipped.
+    pub fn assign(&mut self, id: i32, now: f64, taken: impl Fn(&str) -> bool) -> String {
+        if let Some(n) = self.assigned.get(&id) {
+            return designator(*n);
+        }
+        let n = self
+            .reclaim(id, now, &taken)
+            .unwrap_or_else(|| self.fresh(&taken));
+        self.assigned.insert(id, n);
+        designator(n)
+    }
+
+    /// Gives up the designator of target `id` at time `now`.
+    pub fn release(&mut self, id: i32, now: f64) {
+        if let Some(n) = self.assigned.remove(&id) {
+            self.released.push((n, id, now));
+        }
+    }
+
+    /// A released designator `id` may have: its own, or under [`ReusePolicy::After`] the
+    /// lowest one free for long enough.
+    fn reclaim(&mut self, id: i32, now: f64, taken: impl Fn(&str) -> bool) -> Option<usize> {
+        let timeout = match self.policy {
+            ReusePolicy::Never => None,
+            ReusePolicy::After(timeout) => Some(timeout),
+        };
+        let own = self.released.iter().position(|&(_, owner, _)| owner == id);
+        let reusable = || {
+            self.released
+                .iter()
+                .enumerate()
+                .filter(|(_, &(n, _, since))| {
+                    timeout.is_some_and(|t| now - since >= t) && !taken(&designator(n))
+                })
+                .min_by_key(|(_, &(n, ..))| n)
+                .map(|(i, _)| i)
+        };
+        let i = own.or_else(reusable)?;
+        Some(self.released.swap_remove(i).0)
+    }
+
+    fn fresh(&mut self, taken: impl Fn(&str) -> bool) -> usize {
+        while taken(&designator(self.next)) {
+            self.next += 1;
+        }
+        self.next += 1;
+        self.next - 1
+    }
+}
+
+/// The `n`th designator, counting from `A1` at 0.
+pub fn designator(n: usize) -> String {
+    let mut letters = Vec::new();
+    let mut rest = n / 9 + 1;
+    while rest > 0 {
+        rest -= 1;
+        letters.push(b'A' + (rest % 26) as u8);
+        rest /= 26;
+    }
+    letters.reverse();
+    format!("{}{}", String::from_utf8(letters).unwrap(), n % 9 + 1)
+}
+
+/// Writes a designator from the [`Designators`] resource into `Target::text` wherever
+/// it is empty, so markers, labels and tooltips show it, and releases it when the
+/// target goes away. A target that loses its label again, e.g. from a feed update
+/// without one, gets the same designator back.
+pub struct DesignatorPlugin;
+
+impl Plugin for DesignatorPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Designators>() {
+            app.init_resource::<Designators>();
+        }
+        // Before the display lays out the targets this frame.
+        app.add_system_to_stage(stage::PRE_UPDATE, designator_system.system());
+    }
+}
+
+fn designator_system(
+    mut ids: Local<HashMap<Entity, i32>>,
+    time: Res<Time>,
+    mut designators: ResMut<Designators>,
+    mut targets: Query<(Entity, Mut<Target>)>,
+) {
+    let now = time.seconds_since_startup;
+    for entity in targets.removed::<Target>() {
+        if let Some(id) = ids.remove(entity) {
+            designators.release(id, now);
+        }
+    }
+    let labels = targets
+        .iter_mut()
+        .filter(|(_, target)| !target.text.is_empty())
+        .map(|(_, target)| target.text.clone())
+        .collect::<HashSet<_>>();
+    for (entity, mut target) in targets.iter_mut() {
+        if target.text.is_empty() {
+            match ids.insert(entity, target.id) {
+                Some(old) if old != target.id => designators.release(old, now),
+                _ => {}
+            }
+            target.text = designators.assign(target.id, now, |d| labels.contains(d));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use bevy::prelude::*;
+use bevy::type_registry::TypeUuid;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::display::{LeaderRoutes, MarkerBundle, MarkerContext, Poi, RadarDisplay};
+use crate::layers::{Collapsed, Layer};
+use crate::layout::LayoutConfig;
+use crate::motion::PolarTween;
+use crate::style::{LineStyle, MarkerShape};
+use crate::target::Target;
+use crate::theme::Theme;
+
+/// Like [`styled_marker`](crate::display::styled_marker), except that markers of the
+/// same shape, size and line width all share one mesh, tessellated the first time it
+/// is asked for; only the entity's transform and material are its own. Set it with
+/// [`RadarDisplay::set_marker_factory`] to keep `Assets<Mesh>` small with thousands of
+/// targets. The mesh is sized rather than scaled into place, since the transform's
+/// scale belongs to [`ConstantSizePlugin`](crate::constant_size::ConstantSizePlugin).
+pub fn shared_marker(_target: &Target, ctx: &mut MarkerContext) -> MarkerBundle {
+    let (shape, translation) = ctx.shape.shape_type(ctx.poi_width);
+    let mesh = marker_mesh(ctx.shape, ctx.poi_width, ctx.stroke_width);
+    if !ctx.meshes.contains(&mesh) {
+        let built = primitive(
+            ctx.material.clone(),
+            ctx.meshes,
+            shape,
+            TessellationMode::Stroke(&StrokeOptions::default().with_line_width(ctx.stroke_width)),
+            translation,
+        );
+        if let Some(built) = ctx.meshes.remove(&built.mesh) {
+            ctx.meshes.set_untracked(&mesh, built);
+        }
+    }
+    SpriteComponents {
+        material: ctx.material.clone(),
+        mesh,
+        sprite: Sprite {
+            size: Vec2::new(1.0, 1.0),
+            ..Default::default()
+        },
+        transform: Transform::from_translation(translation),
+        ..Default::default()
+    }
+}
+
+/// The weak handle [`shared_marker`] keeps the mesh for one outline under. It never
+/// holds the asset alive, so the mesh stays until the app exits.
+fn marker_mesh(shape: MarkerShape, poi_width: f32, stroke_width: f32) -> Handle<Mesh> {
+    let mut hasher = DefaultHasher::new();
+    (shape, poi_width.to_bits(), stroke_width.to_bits()).hash(&mut hasher);
+    Handle::weak_from_u64(Mesh::TYPE_UUID, hasher.finish())
+}
+
+/// One entity drawing the leader lines of every marker whose category shares a
+/// [`LineStyle`], see [`LayoutConfig::batch_leaders`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderBatch {
+    pub style: LineStyle,
+}
+
+#[derive(Default)]
+pub(crate) struct BatchState {
+    built: bool,
+    markers: usize,
+    routes: LeaderRoutes,
+}
+
+/// While [`LayoutConfig::batch_leaders`] is on, tessellates the leader lines of all
+/// markers into one mesh per [`LineStyle`] in use, rebuilt only on frames where a
+/// marker moved, folded away or came and went, or the routes changed. Widening the
+/// selected target's line is left out, as is any per-target change of width.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn leader_batch_system(
+    mut commands: Commands,
+    mut state: Local<BatchState>,
+    config: Res<LayoutConfig>,
+    display: Res<RadarDisplay>,
+    theme: Res<Theme>,
+    routes: Res<LeaderRoutes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    markers: Query<With<Poi, (&Target, &PolarTween, &Collapsed)>>,
+    moved: Query<With<Poi, Changed<PolarTween>>>,
+    folded: Query<With<Poi, Changed<Collapsed>>>,
+    batches: Query<(Entity, &LeaderBatch, &Handle<Mesh>, &Handle<ColorMaterial>)>,
+) {
+    if !config.batch_leaders {
+        if state.built {
+            for (entity, ..) in batches.iter() {
+                commands.despawn(entity);
+            }
+            *state = BatchState::default();
+        }
+        return;
+    }
+    let count = markers.iter().count();
+    let dirty = !state.built
+        || state.markers != count
+        || state.routes != *routes
+        || moved.iter().next().is_some()
+        || folded.iter().next().is_some();
+    if !dirty {
+        return;
+    }
+    state.built = true;
+    state.markers = count;
+    state.routes = routes.clone();
+
+    let lines = markers
+        .iter()
+        .filter(|(_, _, collapsed)| !collapsed.0)
+        .map(|(target, tween, _)| {
+            let elbows = routes.0.get(&target.id).map_or(&[][..], |e| &e[..]);
+            let mut points = std::iter::once(tween.origin)
+                .chain(elbows.iter().map(|&elbow| tween.origin + elbow))
+                .chain(std::iter::once(tween.point()))
+                .collect::<Vec<_>>();
+            let leader = display.categories().leader(target);
+            let shape = display.categories().style(target).shape;
+            leader.end_at_marker(&mut points, shape, config.poi_width);
+            (leader, points)
+        })
+        .collect::<Vec<_>>();
+    let mut styles = Vec::<&LineStyle>::new();
+    for (style, _) in &lines {
+        if !styles.contains(&style) {
+            styles.push(style);
+        }
+    }
+    for (entity, batch, ..) in batches.iter() {
+        if !styles.contains(&&batch.style) {
+            commands.despawn(entity);
+        }
+    }
+    for style in styles {
+        let mut builder = PathBuilder::new();
+        for (_, points) in lines.iter().filter(|(other, _)| other == style) {
+            style.trace(&mut builder, points, style.width);
+        }
+        let existing = batches.iter().find(|(_, batch, ..)| batch.style == *style);
+        let material = match existing {
+            Some((.., material)) => material.clone(),
+            None => materials.add(style.color.unwrap_or_else(|| theme.stroke()).into()),
+        };
+        let sprite = builder.build().stroke(
+            material,
+            &mut meshes,
+            Vec3::zero(),
+            &style.stroke_options(style.width),
+        );
+        match existing {
+            Some((_, _, mesh, _)) => {
+                if let Some(rebuilt) = meshes.remove(&sprite.mesh) {
+                    meshes.set(mesh, rebuilt);
+                }
+            }
+            None => {
+                commands
+                    .spawn(sprite)
+                    .with(LeaderBatch { style: *style })
+                    .with(Layer::Leaders);
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::render::camera::{Camera, PerspectiveProjection};
+use bevy::render::render_graph::base::camera::CAMERA3D;
+
+use crate::geo_marker::{GeoMarker, GeoMarkerPin};
+use crate::occlusion::{occlusion_alpha, Occluder, Occlusion};
+
+/// On a 3D entity drawn flat in its xy plane, such as a quad behind a label: turned by
+/// [`BillboardPlugin`] every frame to lie parallel to the screen, facing the 3D camera
+/// with its +y up on screen. It may be the child of a turning body; its own rotation
+/// and scale are set against its parent's.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Billboard {
+    /// Pixels on screen a unit of its mesh takes up, however far it is from the
+    /// camera; `None` keeps its scale and lets it shrink with distance.
+    pub pixels: Option<f32>,
+}
+
+/// On a UI text node labelling the 3D `anchor` entity: kept by [`BillboardPlugin`]
+/// `offset` pixels right of and above where the anchor is on screen. Being drawn on the
+/// screen it always faces the camera and keeps its font size at any distance. It is
+/// hidden while the anchor is, or is behind the camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BillboardText {
+    pub anchor: Entity,
+    pub offset: Vec2,
+}
+
+/// How [`BillboardPlugin`] draws billboards and labels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BillboardSettings {
+    /// Whether billboards and labels on the far side of an [`Occluder`] are hidden.
+    /// Labels fade out towards the horizon and are dimmed rather than hidden behind it
+    /// if [`Occlusion`] says so, like occludable annotations.
+    pub hide_far_side: bool,
+    /// Whether each [`GeoMarker`] with a pin and a label gets a [`BillboardText`].
+    pub geo_marker_labels: bool,
+    /// Asset path of the font of [`GeoMarker`] labels.
+    pub font: &'static str,
+    pub font_size: f32,
+    pub color: Color,
+    /// Where [`GeoMarker`] labels sit from their pin, in pixels.
+    pub offset: Vec2,
+}
+
+impl Default for BillboardSettings {
+    fn default() -> Self {
+        BillboardSettings {
+            hide_far_side: true,
+            geo_marker_labels: true,
+            font: "arial.ttf",
+            font_size: 14.0,
+            color: Color::WHITE,
+            offset: Vec2::new(6.0, 6.0),
+        }
+    }
+}
+
+/// Keeps [`Billboard`] entities facing the 3D camera, at a constant size on screen if
+/// they ask for one, and [`BillboardText`] labels next to their anchors. With
+/// [`BillboardSettings::geo_marker_labels`], the label of every [`GeoMarker`] is shown
+/// next to its pin, following changes to the text and despawned with the marker; add
+/// [`GeoMarkerPlugin`](crate::geo_marker::GeoMarkerPlugin) alongside for the pins.
+pub struct BillboardPlugin;
+
+impl Plugin for BillboardPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<BillboardSettings>() {
+            app.init_resource::<BillboardSettings>();
+        }
+        if !app.resources().contains::<Occlusion>() {
+            app.init_resource::<Occlusion>();
+        }
+        app.add_system(geo_marker_label_system.system())
+            .add_system(billboard_system.system())
+            .add_system(billboard_text_system.system());
+    }
+}
+
+/// The 3D camera's transform and projection, if there is one.
+fn camera_3d<'a>(
+    cameras: &'a Query<(&Camera, &PerspectiveProjection, &GlobalTransform)>,
+) -> Option<(&'a Camera, &'a PerspectiveProjection, &'a GlobalTransform)> {
+    cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA3D))
+}
+
+fn occluder_spheres(occluders: &Query<(&Occluder, &GlobalTransform)>) -> Vec<(Vec3, f32)> {
+    occluders
+        .iter()
+        .map(|(occluder, transform)| (transform.translation, occluder.radius * transform.scale.x()))
+        .collect()
+}
+
+#[allow(clippy::type_complexity)]
+fn billboard_system(
+    settings: Res<BillboardSettings>,
+    occlusion: Res<Occlusion>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &PerspectiveProjection, &GlobalTransform)>,
+    occluders: Query<(&Occluder, &GlobalTransform)>,
+    parents: Query<&GlobalTransform>,
+    mut billboards: Query<(
+        &Billboard,
+        &GlobalTransform,
+        Option<&Parent>,
+        Mut<Transform>,
+        Option<Mut<Draw>>,
+    )>,
+) {
+    let window_height = match windows.get_primary() {
+        Some(window) => window.height() as f32,
+        None => return,
+    };
+    let (projection, eye) = match camera_3d(&cameras) {
+        Some((_, projection, eye)) => (projection, eye),
+        None => return,
+    };
+    let spheres = occluder_spheres(&occluders);
+    let forward = eye.rotation * -Vec3::unit_z();
+    for (billboard, global, parent, mut transform, draw) in billboards.iter_mut() {
+        let at = global.translation;
+        if let (true, Some(mut draw)) = (settings.hide_far_side, draw) {
+            let shown = occlusion_alpha(&occlusion, &spheres, eye.translation, at) > 0.0;
+            if draw.is_visible != shown {
+                draw.is_visible = shown;
+            }
+        }
+        let (parent_rotation, parent_scale) = match parent.and_then(|p| parents.get(p.0).ok()) {
+            Some(parent) => (parent.rotation, parent.scale.x()),
+            None => (Quat::identity(), 1.0),
+        };
+        let rotation = parent_rotation.conjugate() * eye.rotation;
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+        let depth = (at - eye.translation).dot(forward);
+        if let Some(pixels) = billboard.pixels {
+            if depth > 0.0 && parent_scale > 0.0 {
+                let per_pixel = 2.0 * depth * (projection.fov / 2.0).tan() / window_height;
+                let scale = Vec3::splat(pixels * per_pixel / parent_scale);
+                if transform.scale != scale {
+                    transform.scale = scale;
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn billboard_text_system(
+    settings: Res<BillboardSettings>,
+    occlusion: Res<Occlusion>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &PerspectiveProjection, &GlobalTransform)>,
+    occluders: Query<(&Occluder, &GlobalTransform)>,
+    anchors: Query<Without<BillboardText, (&GlobalTransform, Option<&Draw>)>>,
+    mut labels: Query<(&BillboardText, Mut<Style>, Mut<Text>, Mut<Draw>)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let (camera, eye) = match camera_3d(&cameras) {
+        Some((camera, _, eye)) => (camera, eye),
+        None => return,
+    };
+    let spheres = occluder_spheres(&occluders);
+    let view_projection = camera.projection_matrix * eye.compute_matrix().inverse();
+    for (label, mut style, mut text, mut draw) in labels.iter_mut() {
+        let (at, anchor_shown) = match anchors.get(label.anchor) {
+            Ok((transform, draw)) => (
+                transform.translation,
+                draw.is_none_or(|draw| draw.is_visible),
+            ),
+            Err(_) => (Vec3::zero(), false),
+        };
+        let clip = view_projection * at.extend(1.0);
+        let alpha = if settings.hide_far_side {
+            occlusion_alpha(&occlusion, &spheres, eye.translation, at)
+        } else {
+            1.0
+        };
+        let shown = anchor_shown && clip.w() > 0.0 && alpha > 0.0;
+        if draw.is_visible != shown {
+            draw.is_visible = shown;
+        }
+        if !shown {
+            continue;
+        }
+        if text.style.color.a() != alpha {
+            text.style.color.set_a(alpha);
+        }
+        let ndc = Vec2::new(clip.x(), clip.y()) / clip.w();
+        let screen = (ndc + Vec2::one()) / 2.0 * size + label.offset;
+        let position = Rect {
+            left: Val::Px(screen.x()),
+            bottom: Val::Px(screen.y()),
+            ..Default::default()
+        };
+        if style.position_type != PositionType::Absolute || style.position != position {
+            style.position_type = PositionType::Absolute;
+            style.position = position;
+        }
+    }
+}
+
+/// Spawns, updates and despawns the labels of [`GeoMarker`]s, keyed by marker.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn geo_marker_label_system(
+    mut commands: Commands,
+    mut labels: Local<HashMap<Entity, Entity>>,
+    settings: Res<BillboardSettings>,
+    asset_server: Res<AssetServer>,
+    markers: Query<With<GeoMarkerPin, (Entity, &GeoMarker)>>,
+    mut texts: Query<With<BillboardText, Mut<Text>>>,
+) {
+    let mut live = HashMap::new();
+    if settings.geo_marker_labels {
+        for (marker, geo) in markers.iter() {
+            if geo.label.is_empty() {
+                continue;
+            }
+            match labels.remove(&marker) {
+                Some(label) => {
+                    if let Ok(mut text) = texts.get_mut(label) {
+                        if text.value != geo.label {
+                            text.value = geo.label.clone();
+                        }
+                    }
+                    live.insert(marker, label);
+                }
+                None => {
+                    let label = commands
+                        .spawn(TextComponents {
+                            text: Text {
+                                value: geo.label.clone(),
+                                font: asset_server.load(settings.font),
+                                style: TextStyle {
+                                    font_size: settings.font_size,
+                                    color: settings.color,
+                                },
+                            },
+                            draw: Draw {
+                                is_visible: false,
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .with(BillboardText {
+                            anchor: marker,
+                            offset: settings.offset,
+                        })
+                        .current_entity();
+                    if let Some(label) = label {
+                        live.insert(marker, label);
+                    }
+                }
+            }
+        }
+    }
+    for (_, stale) in labels.drain() {
+        commands.despawn(stale);
+    }
+    *labels = live;
+}
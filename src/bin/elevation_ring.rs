@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+use bevy_debris::actions::{Action, ActionMap, ActionState, ActionsPlugin};
+use bevy_debris::animation::{AnimationTime, AnimationTimePlugin};
+use bevy_debris::asset_check::AssetCheckPlugin;
+use bevy_debris::cli::{init_logging, DisplayArgs};
+use bevy_debris::layout::LayoutConfig;
+use bevy_debris::ring3d::ElevationRingPlugin;
+use bevy_debris::scenario::{Preset, RandomTargets, Scenario};
+use bevy_debris::theme::RingTextScale;
+use clap::Parser;
+use rand::prelude::*;
+
+/// Radians a second the camera orbits the origin while a turn left or right is held.
+const ORBIT_SPEED: f32 = 1.0;
+
+/// Declutter targets onto rings in 3D, lifted to their elevation angle.
+#[derive(Parser)]
+struct Args {
+    /// Scenario file (JSON) with the targets to show
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+    /// Built-in scenario to show
+    #[arg(long, value_enum, conflicts_with = "scenario")]
+    preset: Option<Preset>,
+    /// Seed for presets, random demo targets and the elevations made up for targets
+    /// without one
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Number of random demo targets used when no scenario is given
+    #[arg(long, default_value_t = RandomTargets::default().count)]
+    count: usize,
+    /// Give targets without an elevation a random one up to this many degrees
+    #[arg(long, default_value_t = 60.0)]
+    max_elevation: f32,
+    /// Side length of a marker square
+    #[arg(long, default_value_t = 30.0)]
+    poi_width: f32,
+    /// Scale label text by this factor per ring outwards, between 0.6x and 1.5x; below 1
+    /// shrinks the labels of outer rings
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    label_ring_scale: f32,
+    #[command(flatten)]
+    display: DisplayArgs,
+}
+
+fn main() {
+    init_logging();
+    let args = Args::parse();
+    let scenario = match (&args.scenario, args.preset) {
+        (Some(path), _) => Scenario::from_file(path),
+        (None, Some(preset)) => Ok(preset.build(args.seed)),
+        (None, None) => Ok(Scenario {
+            targets: RandomTargets {
+                count: args.count,
+                ..Default::default()
+            }
+            .generate(args.seed),
+            ..Default::default()
+        }),
+    };
+    let mut scenario = match scenario {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let max_elevation = args.max_elevation.to_radians().max(0.0);
+    for target in &mut scenario.targets {
+        if target.elevation.is_none() && max_elevation > 0.0 {
+            target.elevation = Some(rng.gen_range(0.0, max_elevation));
+        }
+    }
+
+    let actions = args
+        .display
+        .action_map(ActionMap::default())
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        });
+
+    App::build()
+        .add_resource(args.display.window_descriptor("elevation ring"))
+        .add_resource(ClearColor(args.display.theme.background()))
+        .add_resource(args.display.theme)
+        .add_resource(RingTextScale {
+            per_ring: args.label_ring_scale,
+            ..Default::default()
+        })
+        .add_resource(scenario)
+        .add_resource(actions)
+        .add_plugins(DefaultPlugins)
+        .add_plugin(AssetCheckPlugin {
+            textures: Vec::new(),
+            ..Default::default()
+        })
+        .add_plugin(AnimationTimePlugin)
+        .add_plugin(ActionsPlugin)
+        .add_plugin(ElevationRingPlugin {
+            config: LayoutConfig::new(args.poi_width),
+            ..Default::default()
+        })
+        .add_startup_system(setup.system())
+        .add_system(orbit_system.system())
+        .run();
+}
+
+fn setup(mut commands: Commands, scenario: Res<Scenario>) {
+    commands
+        .spawn(Camera3dComponents {
+            transform: Transform::from_translation(Vec3::new(0.0, 900.0, 1600.0))
+                .looking_at(Vec3::zero(), Vec3::unit_y()),
+            ..Default::default()
+        })
+        .spawn(UiCameraComponents::default());
+    for target in &scenario.targets {
+        commands.spawn((target.clone(),));
+    }
+}
+
+fn orbit_system(
+    time: Res<AnimationTime>,
+    actions: Res<ActionState>,
+    mut cameras: Query<(&Camera, Mut<Transform>)>,
+) {
+    let direction = actions.axis(Action::TurnLeft, Action::TurnRight);
+    if direction == 0.0 {
+        return;
+    }
+    let turn = Quat::from_rotation_y(direction * ORBIT_SPEED * time.unscaled_delta_seconds());
+    for (camera, mut transform) in cameras.iter_mut() {
+        if camera.name.as_deref() == Some(CAMERA3D) {
+            transform.translation = turn * transform.translation;
+            transform.rotation = turn * transform.rotation;
+        }
+    }
+}
@@ -1,213 +1,1709 @@
 use std::f32::consts::PI;
+use std::num::NonZeroU8;
+use std::path::PathBuf;
+use std::thread;
 
 use bevy::{
-    input::{
-        mouse::{MouseButtonInput, MouseMotion, MouseWheel},
-        ElementState,
-    },
+    asset::{HandleId, LoadState},
     prelude::*,
-    render::{camera::Camera, mesh::Indices, pipeline::PrimitiveTopology},
+    render::{
+        camera::Camera,
+        camera::PerspectiveProjection,
+        render_graph::base::{camera::CAMERA3D, MainPass},
+        texture::{AddressMode, TextureFormat},
+    },
+};
+use bevy_debris::actions::{Action, ActionMap, ActionState, ActionsPlugin};
+use bevy_debris::animated_texture::{image_sequence, AnimatedTexture, AnimatedTexturePlugin};
+use bevy_debris::animation::AnimationTime;
+use bevy_debris::asset_check::AssetCheckPlugin;
+use bevy_debris::atmosphere::Atmosphere;
+use bevy_debris::billboard::BillboardPlugin;
+use bevy_debris::bodies::{geo_to_local, Bodies, BodiesPlugin, Body, BodyConfig};
+use bevy_debris::camera::{
+    AutoSpin, CameraCommands, GamepadBindings, Orbit, OrbitBindings, OrbitCamera,
+    OrbitCameraPlugin, OrbitControls, OrbitMode,
+};
+use bevy_debris::capture::CapturePlugin;
+use bevy_debris::cli::{init_logging, DisplayArgs};
+use bevy_debris::cluster::cluster_points;
+use bevy_debris::coords::{CoordFormat, CoordsPlugin};
+use bevy_debris::coverage::CoverageVolume;
+use bevy_debris::day_night::{parse_utc, DayNightMaterial, SunClock};
+use bevy_debris::events::DisplayEventsPlugin;
+use bevy_debris::flat_map::{FlatMap, FlatMapPlugin};
+use bevy_debris::geo::{LatLon, Projection, EARTH_RADIUS_M};
+use bevy_debris::geo_marker::{GeoMarker, GeoMarkerPlugin};
+use bevy_debris::geojson::{GeoJsonLayer, GeoJsonOverlay, GeoJsonPlugin, GeoJsonStyle};
+use bevy_debris::globe_pick::{GlobeClicked, GlobePickPlugin};
+use bevy_debris::globe_render::GlobeRenderPlugin;
+use bevy_debris::graticule::{Graticule, GraticulePlugin};
+use bevy_debris::heatmap::{Heatmap, HeatmapPlugin};
+use bevy_debris::impostor::{Impostor, ImpostorPlugin, ImpostorSettings};
+use bevy_debris::layers::{Layer, LayersPlugin};
+use bevy_debris::lighting::{LightingPlugin, LightingRig};
+use bevy_debris::lod_sphere::{LodSphere, LodSpherePlugin};
+use bevy_debris::mesh::{cube_sphere_mesh, generate_tangents, icosphere_mesh, sphere_mesh};
+use bevy_debris::mipmap::{MipChain, SamplerSettings};
+use bevy_debris::normal_map::NormalMappedMaterial;
+use bevy_debris::occlusion::{
+    occluded, occlusion_alpha, FarSide, Occludable, Occluder, Occlusion, OcclusionPlugin,
 };
+use bevy_debris::persist::Persist;
+use bevy_debris::planet::{Palette, PlanetTexture};
+use bevy_debris::pointer::CursorPosition;
+use bevy_debris::polyline::GeoPolylinePlugin;
+use bevy_debris::probe::{DataProbe, DataProbePlugin, ProbeSurface};
+use bevy_debris::raster::GeoRaster;
+use bevy_debris::route::GeoRoutePlugin;
+use bevy_debris::scenario::{Preset, Scenario};
+use bevy_debris::sky::{Sky, SkyCubemap, SkyPlugin, Starfield};
+use bevy_debris::terrain::{Heightmap, Relief};
+use bevy_debris::tiles::{GlobeTiles, GlobeTilesPlugin, TileSource};
+use clap::{Parser, ValueEnum};
+use crossbeam_channel::Receiver;
+
+/// Stands for [`EARTH_RADIUS_M`].
+const GLOBE_RADIUS: f32 = 2.0;
+/// Lowest and highest the camera gets, in globe radii above the surface.
+const MIN_ALTITUDE: f32 = 0.05;
+const MAX_ALTITUDE: f32 = 10.0;
+const STEM_WIDTH: f32 = 0.006;
+const COVERAGE_SEGMENTS: usize = 64;
+/// Seconds the globe texture takes to fade in once loaded.
+const TEXTURE_FADE: f64 = 0.8;
+/// Radius of the fade-in shell relative to the globe, enough to clear the surface.
+const SHELL_SCALE: f32 = 1.002;
+/// Degrees between the placeholder's grid lines.
+const GRATICULE_STEP: f32 = 15.0;
+/// Radius of highlighted regions relative to the globe, above the fade-in shell.
+const REGION_SCALE: f32 = 1.004;
+/// Longest edge of a region's triangles, in degrees of arc.
+const REGION_MAX_EDGE: f32 = 2.0;
+/// Radius of raster overlays relative to the globe, between the fade-in shell and
+/// regions.
+const RASTER_SCALE: f32 = 1.003;
+/// Radius of the heatmap relative to the globe, over raster overlays and under regions.
+const HEATMAP_SCALE: f32 = 1.0035;
+/// Longest step of a raster overlay's grid, in degrees of latitude or longitude.
+const RASTER_MAX_STEP: f32 = 2.0;
+/// Radius of the day and night or normal-mapped shading relative to the globe, over
+/// the fade-in shell and under raster overlays.
+const SHADING_SCALE: f32 = 1.0025;
+/// Furthest in pixels from a pin a click still picks it, and the cursor may move
+/// between pressing and letting go for a click.
+const PICK_PIXELS: f32 = 12.0;
+/// Seconds the camera takes to fly to a clicked pin, and the height in globe radii it
+/// comes down to if it is higher.
+const FLY_SECS: f32 = 1.5;
+const FLY_ALTITUDE: f32 = 1.0;
+/// How high over the flat map, in globe radii, the camera goes to take it all in.
+const MAP_ALTITUDE: f32 = 6.0;
+
+/// Textured globe viewer.
+#[derive(Parser)]
+struct Args {
+    /// Globe texture, relative to the assets directory; .ktx2 files need the `ktx2`
+    /// feature
+    #[arg(long, default_value = "theworld.png")]
+    texture: String,
+    /// Paint a fictional planet from this seed instead of loading a texture
+    #[arg(long, value_name = "SEED", conflicts_with = "texture")]
+    planet: Option<u64>,
+    /// Share of the generated planet's surface covered by ocean, from 0 to 1
+    #[arg(long, requires = "planet", default_value_t = PlanetTexture::default().ocean)]
+    ocean: f32,
+    /// Latitude in degrees poleward of which the generated planet is iced over
+    #[arg(long, requires = "planet",
+          default_value_t = PlanetTexture::default().ice_latitude.to_degrees().round())]
+    ice_latitude: f32,
+    /// Colors of the generated planet
+    #[arg(long, requires = "planet", value_enum, default_value_t = Palette::Temperate)]
+    palette: Palette,
+    /// Width of the generated planet texture in texels; it is half as tall
+    #[arg(long, requires = "planet", default_value_t = PlanetTexture::default().width)]
+    planet_size: u32,
+    /// Scenario file (JSON) whose geodetic points are pinned on the globe
+    #[arg(long, conflicts_with = "preset")]
+    scenario: Option<PathBuf>,
+    /// Built-in scenario to show
+    #[arg(long, value_enum)]
+    preset: Option<Preset>,
+    /// Seed for presets
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Merge pins closer than this many pixels on screen into a count bubble (0 to
+    /// never merge)
+    #[arg(long, default_value_t = 24.0)]
+    cluster_radius: f32,
+    /// Fraction of the camera's height above the surface one wheel notch zooms in
+    #[arg(long, default_value_t = OrbitControls::default().zoom_step)]
+    zoom_step: f32,
+    /// Seconds a zoom takes to ease most of the way in; 0 jumps
+    #[arg(long, default_value_t = OrbitControls::default().smoothing)]
+    zoom_smoothing: f32,
+    /// Degrees the globe turns per pixel dragged
+    #[arg(long, default_value_t = OrbitControls::default().sensitivity.to_degrees())]
+    orbit_sensitivity: f32,
+    /// How dragging turns the globe to start with; R switches between the modes
+    #[arg(long, value_enum, default_value_t = OrbitMode::default())]
+    orbit_mode: OrbitMode,
+    /// Turn the globe with a gamepad's left stick and zoom with its right one, besides
+    /// the arrow keys or WASD and +/-
+    #[arg(long)]
+    gamepad: bool,
+    /// How quickly the globe stops turning after a drag, 0 to keep it turning
+    #[arg(long, default_value_t = OrbitControls::default().damping)]
+    orbit_damping: f32,
+    /// Spin the globe at this many degrees per second while nobody turns it
+    #[arg(long, value_name = "DEG_PER_S")]
+    auto_spin: Option<f32>,
+    /// Axis the --auto-spin turns the globe about; its own axis if not given
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_direction, requires = "auto_spin")]
+    auto_spin_axis: Option<Vec3>,
+    /// Seconds the globe is left alone before --auto-spin picks up again
+    #[arg(long, default_value_t = AutoSpin::default().delay, requires = "auto_spin")]
+    auto_spin_delay: f32,
+    /// How many times taller than to scale airborne targets' altitude stems are drawn
+    #[arg(long, default_value_t = 50.0)]
+    altitude_exaggeration: f32,
+    /// Intensity of the key light, 1 for full brightness
+    #[arg(long, default_value_t = LightingRig::default().key)]
+    key_light: f32,
+    /// Intensity of the fill light opposite the key light
+    #[arg(long, default_value_t = LightingRig::default().fill)]
+    fill_light: f32,
+    /// Intensity of the light reaching every surface
+    #[arg(long, default_value_t = LightingRig::default().ambient)]
+    ambient_light: f32,
+    /// Which way the key light shines, as X,Y,Z with the camera looking down -z
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_direction)]
+    light_direction: Option<Vec3>,
+    /// Draw the globe texture at full size however far away, without a mip chain
+    #[arg(long)]
+    no_mipmaps: bool,
+    /// Play the images in this directory, relative to the assets directory, over the
+    /// globe texture in order of their names, such as cloud cover frames; turns off
+    /// the mip chain
+    #[arg(long, value_name = "DIR")]
+    animate: Option<String>,
+    /// Frames a second of --animate
+    #[arg(long, requires = "animate", default_value_t = 10.0)]
+    animate_fps: f32,
+    /// Draw the globe as patches that get finer where the camera comes close, instead
+    /// of a fixed UV sphere
+    #[arg(long)]
+    lod_sphere: bool,
+    /// How the globe's sphere is built
+    #[arg(long, value_enum, default_value_t = SphereMeshKind::Uv, conflicts_with = "lod_sphere")]
+    sphere_mesh: SphereMeshKind,
+    /// Quads along a quarter of the globe's equator, or as near as --sphere-mesh allows
+    #[arg(long, default_value_t = 45)]
+    sphere_resolution: u32,
+    /// Grayscale equirectangular heightmap to raise the globe's surface by, as a PNG
+    #[arg(long, value_name = "PATH", conflicts_with = "lod_sphere")]
+    heightmap: Option<PathBuf>,
+    /// Height of the highest point of --heightmap above the lowest, relative to the
+    /// globe's radius
+    #[arg(long, default_value_t = 0.05, requires = "heightmap")]
+    relief: f32,
+    /// Night side texture, such as city lights, relative to the assets directory;
+    /// shades the globe by day and night from where the sun is
+    #[arg(long, value_name = "PATH")]
+    night_texture: Option<String>,
+    /// Time the sun is shown at, as YYYY-MM-DDTHH:MM[:SS]Z; now if not given
+    #[arg(long, value_name = "TIME", value_parser = parse_utc_arg, requires = "night_texture")]
+    utc: Option<f64>,
+    /// How many times faster than real time the sun moves
+    #[arg(long, default_value_t = 1.0, requires = "night_texture")]
+    sun_rate: f64,
+    /// Tangent-space normal map of the globe's surface, relative to the assets
+    /// directory; lights the globe's detail by the key light, and L turns the shading on
+    /// and off
+    #[arg(long, value_name = "PATH", conflicts_with = "night_texture")]
+    normal_map: Option<String>,
+    /// Glow around the globe's edge, as from an atmosphere; G turns it on and off
+    #[arg(long)]
+    atmosphere: bool,
+    /// Color of the --atmosphere glow, as RRGGBB or RRGGBBAA
+    #[arg(long, value_name = "HEX", value_parser = parse_color, requires = "atmosphere")]
+    atmosphere_color: Option<Color>,
+    /// Surround the scene with random stars
+    #[arg(long)]
+    starfield: bool,
+    /// Surround the scene with a skybox of px.png, nx.png, py.png, ny.png, pz.png and
+    /// nz.png in this directory, relative to the assets directory
+    #[arg(long, value_name = "DIR", conflicts_with = "starfield")]
+    skybox: Option<String>,
+    /// Drape a heatmap of where the scenario's geo points lie over the globe;
+    /// clicking the globe with X held adds heat there
+    #[arg(long)]
+    heatmap: bool,
+    /// Degrees of arc each --heatmap point's heat spreads over
+    #[arg(long, default_value_t = Heatmap::default().spread, requires = "heatmap")]
+    heatmap_spread: f32,
+    /// Anisotropic filtering of the globe texture, in samples (needs device support)
+    #[arg(long, value_name = "SAMPLES")]
+    anisotropy: Option<NonZeroU8>,
+    /// How the globe texture wraps across its left and right edges
+    #[arg(long, value_enum, default_value_t = Wrap::Repeat)]
+    texture_wrap: Wrap,
+    /// Draw the globe as a flat impostor once it is narrower on screen than this many
+    /// pixels; 0 never does
+    #[arg(long, value_name = "PIXELS", default_value_t = ImpostorSettings::default().max_pixels)]
+    impostor_size: f32,
+    /// Add a moon orbiting the globe, besides any bodies in the scenario; Tab moves the
+    /// camera between them
+    #[arg(long)]
+    moon: bool,
+    /// Draw lines of latitude and longitude this many degrees apart over the globe
+    #[arg(long, value_name = "DEGREES")]
+    graticule: Option<f32>,
+    /// Write the degrees of the --graticule lines along the globe's edge
+    #[arg(long, requires = "graticule")]
+    graticule_labels: bool,
+    /// What to do with pins and labels on the far side of the globe
+    #[arg(long, value_enum, default_value_t = FarSide::Hide)]
+    far_side: FarSide,
+    /// How far from the horizon pins and labels start fading out as the globe turns
+    /// them away, as the cosine of their view angle; 0 switches them at the horizon
+    #[arg(long, value_name = "COSINE", default_value_t = Occlusion::default().fade)]
+    far_side_fade: f32,
+    /// Equirectangular data raster, e.g. temperatures, whose value under the cursor is
+    /// shown along with its lat/lon; P toggles the probe
+    #[arg(long, value_name = "TEXTURE")]
+    probe: Option<String>,
+    /// Factor taking --probe pixel values to physical units
+    #[arg(long, default_value_t = 1.0)]
+    probe_scale: f32,
+    /// Added to --probe pixel values after --probe-scale
+    #[arg(long, default_value_t = 0.0)]
+    probe_offset: f32,
+    /// Unit written after --probe values
+    #[arg(long, default_value = "")]
+    probe_unit: String,
+    /// The flat map F flattens the globe into, with everything on it
+    #[arg(long, value_enum, default_value_t = MapProjection::Equirectangular)]
+    map_projection: MapProjection,
+    /// Seconds the globe takes to flatten into the map and back
+    #[arg(long, default_value_t = FlatMap::default().duration)]
+    map_seconds: f32,
+    /// Georeferenced raster to drape over the globe at its lat/lon extent: a GeoTIFF,
+    /// or a PNG with a world file (.pgw, .pngw or .wld) next to it; may be repeated
+    #[arg(long, value_name = "PATH")]
+    raster: Vec<PathBuf>,
+    /// Opacity of --raster overlays, from 0 to 1
+    #[arg(long, default_value_t = 0.6)]
+    raster_opacity: f32,
+    /// GeoJSON file whose points, lines and polygons to draw over the globe; may be
+    /// repeated
+    #[arg(long, value_name = "PATH")]
+    geojson: Vec<PathBuf>,
+    /// Fill the polygons of --geojson files rather than only outlining them
+    #[arg(long, requires = "geojson")]
+    geojson_fill: bool,
+    /// Stream slippy-map tiles over the globe from this URL or path template with
+    /// {z}, {x} and {y}, e.g. https://tile.openstreetmap.org/{z}/{x}/{y}.png
+    #[arg(long, value_name = "URL")]
+    tiles: Option<String>,
+    /// Keep --tiles fetched over HTTP in this directory and read them from there
+    #[arg(long, value_name = "DIR", requires = "tiles")]
+    tile_cache: Option<PathBuf>,
+    /// Finest zoom level of --tiles to fetch
+    #[arg(long, default_value_t = 18, requires = "tiles")]
+    max_tile_zoom: u8,
+    /// Where F11 and --timelapse save PNG captures of the window
+    #[arg(long, value_name = "DIR", default_value = "captures")]
+    capture_dir: PathBuf,
+    /// Also capture a PNG every N frames
+    #[arg(long, value_name = "N")]
+    timelapse: Option<u32>,
+    /// Load KTX2 globe textures at the largest stored mip level no wider or taller
+    /// than this
+    #[cfg(feature = "ktx2")]
+    #[arg(long, default_value_t = 8192)]
+    max_texture_size: u32,
+    #[command(flatten)]
+    display: DisplayArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Wrap {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl From<Wrap> for AddressMode {
+    fn from(wrap: Wrap) -> Self {
+        match wrap {
+            Wrap::Repeat => AddressMode::Repeat,
+            Wrap::Clamp => AddressMode::ClampToEdge,
+            Wrap::Mirror => AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SphereMeshKind {
+    /// Rings of latitude and longitude, pinched at the poles
+    Uv,
+    /// A subdivided cube pushed out onto the sphere, of even quads all over
+    Cube,
+    /// A subdivided icosahedron
+    Ico,
+}
+
+impl SphereMeshKind {
+    /// The sphere's mesh with about `resolution` quads, or pairs of triangles, along a
+    /// quarter of a great circle.
+    fn mesh(self, radius: f32, resolution: u32) -> Mesh {
+        match self {
+            SphereMeshKind::Uv => sphere_mesh(radius, resolution, resolution * 4),
+            SphereMeshKind::Cube => cube_sphere_mesh(radius, resolution),
+            // An edge of the icosahedron spans about 63 degrees, and is cut into one
+            // more piece than the divisions.
+            SphereMeshKind::Ico => icosphere_mesh(
+                radius,
+                (resolution as f32 * 0.7).round().max(1.0) as usize - 1,
+            ),
+        }
+    }
+}
+
+enum GlobeTexture {
+    /// Loaded from the assets directory.
+    File(String),
+    /// Generated on a background thread.
+    Planet(PlanetTexture),
+}
+
+/// The globe texture while it loads and fades in over the placeholder.
+struct TextureLoad {
+    texture: Handle<Texture>,
+    /// Material of the [`TextureShell`], which the globe takes over once faded in.
+    material: Handle<StandardMaterial>,
+    state: LoadPhase,
+    /// Delivers a generated texture once painted, to be stored under `texture`.
+    generating: Option<Receiver<Texture>>,
+    /// The loaded texture's mip chain, unless turned off, and the level shown.
+    mips: Option<(MipChain, usize)>,
+}
+
+struct Mipmaps(bool);
+
+/// How the globe is meshed, for `--sphere-mesh`, `--sphere-resolution` and
+/// `--lod-sphere`.
+struct GlobeMesh {
+    kind: SphereMeshKind,
+    resolution: u32,
+    lod: bool,
+    /// The `--heightmap` and `--relief`.
+    relief: Option<(Heightmap, f32)>,
+}
+
+impl GlobeMesh {
+    fn mesh(&self, radius: f32) -> Mesh {
+        let mut mesh = self.kind.mesh(radius, self.resolution);
+        if let Some((heightmap, relief)) = &self.relief {
+            if let Err(e) = heightmap.displace(&mut mesh, relief * radius) {
+                eprintln!("not raising the globe: {}", e);
+            }
+        }
+        mesh
+    }
+}
+
+enum LoadPhase {
+    Loading,
+    Fading { since: f64 },
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MapProjection {
+    /// Longitude across and latitude up
+    Equirectangular,
+    /// True distances and bearings from the scenario's own ship, or from 0°N 0°E
+    /// without one
+    Azimuthal,
+}
+
+/// The sphere just above the globe the texture fades in on.
+struct TextureShell;
+
+struct Globe;
+
+/// On the [`GeoMarker`] for `scenario.geo[index]`, at `local` in the globe's frame.
+struct GeoPin {
+    index: usize,
+    local: Vec3,
+}
+
+/// The stem from the surface up to the pin for `scenario.geo[index]`.
+struct AltitudeStem(usize);
+
+/// Marks a translucent volume drawn for one of `scenario.coverage`.
+struct Coverage;
+
+/// The altitude shown next to the pin for `scenario.geo[index]`.
+struct AltitudeLabel(usize);
+
+struct AltitudeExaggeration(f32);
+
+/// Marks a bubble standing in for several pins.
+struct ClusterBubble;
+
+/// The count shown over the `n`th cluster bubble.
+struct ClusterLabel(usize);
+
+struct ClusterRadius(f32);
+
+/// The `--raster` overlays, drawn at `opacity`.
+struct RasterOverlays {
+    rasters: Vec<GeoRaster>,
+    opacity: f32,
+}
+
+/// The `--geojson` overlays.
+struct GeoJsonOverlays(Vec<GeoJsonOverlay>);
+
+/// The `--tiles` streamed over the globe, if any.
+struct GlobeTileSource(Option<GlobeTiles>);
+
+/// The `--night-texture` or `--normal-map` shading the globe, if any.
+struct GlobeShading {
+    night: Option<String>,
+    normal_map: Option<String>,
+}
+
+/// The `--graticule` over the globe, if any.
+struct GlobeGraticule(Option<Graticule>);
+
+struct GlobeHeatmap(Option<Heatmap>);
+
+/// The `--animate` directory and `--animate-fps`.
+struct GlobeAnimation(Option<(String, f32)>);
+
+/// How the globe flattens into a map.
+struct GlobeMap(FlatMap);
+
+/// The `--atmosphere` around the globe, if any.
+struct GlobeAtmosphere(Option<Atmosphere>);
+
+/// The `--starfield` or `--skybox` around the scene, if any.
+struct SceneSky(Option<Sky>);
+
+struct ClusterAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    font: Handle<Font>,
+}
 
 fn main() {
-    App::build()
-        .add_resource(MouseButtonState { pressed: false })
+    init_logging();
+    let args = Args::parse();
+    let graticule = args.graticule.map(|spacing| Graticule {
+        spacing: spacing.max(1.0),
+        labels: args.graticule_labels,
+        ..Default::default()
+    });
+    let tiles = args.tiles.clone().map(|url| GlobeTiles {
+        max_zoom: args.max_tile_zoom,
+        ..GlobeTiles::new(TileSource {
+            url,
+            cache: args.tile_cache.clone(),
+        })
+    });
+    let restored = args.display.restore_session();
+    let scenario = match (&restored, &args.scenario, args.preset) {
+        (Some(state), _, _) => Ok(state.scenario.clone()),
+        (None, Some(path), _) => Scenario::from_file(path),
+        (None, None, Some(preset)) => Ok(preset.build(args.seed)),
+        (None, None, None) => Ok(Scenario::default()),
+    };
+    let scenario = match scenario {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let rasters = args
+        .raster
+        .iter()
+        .filter_map(|path| match GeoRaster::load(path) {
+            Ok(raster) => Some(raster),
+            Err(e) => {
+                eprintln!("skipping raster: {}", e);
+                None
+            }
+        })
+        .collect();
+    let geojson_style = GeoJsonStyle {
+        fill: args.geojson_fill.then(|| Color::rgba(0.9, 0.9, 0.8, 0.25)),
+        ..Default::default()
+    };
+    let geojson = args
+        .geojson
+        .iter()
+        .filter_map(|path| match GeoJsonLayer::from_file(path) {
+            Ok(layer) => Some(GeoJsonOverlay {
+                layer,
+                style: geojson_style,
+            }),
+            Err(e) => {
+                eprintln!("skipping geojson: {}", e);
+                None
+            }
+        })
+        .collect();
+    let sky = match &args.skybox {
+        Some(dir) => Some(Sky::Cubemap(SkyCubemap::from_dir(dir, "png"))),
+        None => args.starfield.then(|| Sky::Starfield(Starfield::default())),
+    };
+    let heatmap = args.heatmap.then(|| {
+        let mut heatmap = Heatmap {
+            spread: args.heatmap_spread.max(0.1),
+            lift: HEATMAP_SCALE - 1.0,
+            ..Default::default()
+        };
+        for point in &scenario.geo {
+            heatmap.add(point.lat, point.lon, 1.0);
+        }
+        heatmap
+    });
+    let projection = match args.map_projection {
+        MapProjection::Equirectangular => Projection::Equirectangular,
+        MapProjection::Azimuthal => Projection::AzimuthalEquidistant {
+            center: scenario
+                .own_ship
+                .as_ref()
+                .map_or_else(LatLon::default, |own_ship| {
+                    LatLon::new(own_ship.lat, own_ship.lon)
+                }),
+        },
+    };
+    let mut flat_map = FlatMap::new(projection);
+    flat_map.duration = args.map_seconds.max(0.0);
+    let atmosphere = args.atmosphere.then(|| Atmosphere {
+        color: args
+            .atmosphere_color
+            .unwrap_or_else(|| Atmosphere::default().color),
+        ..Default::default()
+    });
+    let auto_spin = args.auto_spin.map(|speed| AutoSpin {
+        // The globe's north pole is its -z.
+        axis: args
+            .auto_spin_axis
+            .unwrap_or_else(|| globe_rotation() * -Vec3::unit_z()),
+        speed: speed.to_radians(),
+        delay: args.auto_spin_delay.max(0.0),
+    });
+    let relief = args
+        .heightmap
+        .as_ref()
+        .and_then(|path| match Heightmap::load(path) {
+            Ok(heightmap) => Some((heightmap, args.relief)),
+            Err(e) => {
+                eprintln!("skipping heightmap: {}", e);
+                None
+            }
+        });
+    let actions = args
+        .display
+        .action_map(ActionMap::default())
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        });
+    let checked_textures = match args.planet {
+        Some(_) => Vec::new(),
+        None => vec![args.texture.clone()],
+    };
+    let animation = args
+        .animate
+        .clone()
+        .map(|folder| (folder, args.animate_fps));
+    let mut app = App::build();
+    app.add_resource(args.display.window_descriptor("render sphere"))
+        .add_resource(ClearColor(args.display.theme.background()))
+        .add_resource(match args.planet {
+            Some(seed) => GlobeTexture::Planet(PlanetTexture {
+                seed,
+                width: args.planet_size,
+                height: args.planet_size / 2,
+                ocean: args.ocean,
+                ice_latitude: args.ice_latitude.to_radians(),
+                palette: args.palette,
+                ..Default::default()
+            }),
+            None => GlobeTexture::File(args.texture),
+        })
+        .add_resource(Bodies(
+            scenario
+                .bodies
+                .iter()
+                .cloned()
+                .chain(args.moon.then(BodyConfig::moon))
+                .collect(),
+        ))
+        .add_resource(scenario)
+        .add_resource(ClusterRadius(args.cluster_radius))
+        .add_resource(OrbitControls {
+            zoom_step: args.zoom_step.clamp(0.0, 0.9),
+            smoothing: args.zoom_smoothing.max(0.0),
+            sensitivity: args.orbit_sensitivity.to_radians(),
+            damping: args.orbit_damping.max(0.0),
+            mode: args.orbit_mode,
+            auto_spin,
+            ..Default::default()
+        })
+        .add_resource(OrbitBindings {
+            gamepad: args.gamepad.then(GamepadBindings::default),
+            ..Default::default()
+        })
+        .add_resource(RasterOverlays {
+            rasters,
+            opacity: args.raster_opacity.clamp(0.0, 1.0),
+        })
+        .add_resource(SamplerSettings {
+            address_u: args.texture_wrap.into(),
+            anisotropy: args.anisotropy,
+            ..Default::default()
+        })
+        .add_resource(actions)
+        .add_resource(Mipmaps(!args.no_mipmaps && args.animate.is_none()))
+        .add_resource(GlobeAnimation(animation))
+        .add_resource(GlobeMesh {
+            kind: args.sphere_mesh,
+            resolution: args.sphere_resolution.max(1),
+            lod: args.lod_sphere,
+            relief,
+        })
+        .add_resource(GlobeGraticule(graticule))
+        .add_resource(GlobeHeatmap(heatmap))
+        .add_resource(GlobeMap(flat_map))
+        .add_resource(GlobeAtmosphere(atmosphere))
+        .add_resource(SceneSky(sky))
+        .add_resource(GeoJsonOverlays(geojson))
+        .add_resource(GlobeTileSource(tiles))
+        .add_resource(GlobeShading {
+            night: args.night_texture.clone(),
+            normal_map: args.normal_map.clone(),
+        })
+        .add_resource(SunClock {
+            utc: args.utc.unwrap_or_else(|| SunClock::default().utc),
+            rate: args.sun_rate,
+        })
+        .add_resource(ImpostorSettings {
+            max_pixels: args.impostor_size,
+            ..Default::default()
+        })
+        .add_resource(AltitudeExaggeration(args.altitude_exaggeration))
+        .add_resource(Occlusion {
+            far_side: args.far_side,
+            fade: args.far_side_fade.max(0.0),
+            ..Default::default()
+        })
+        .add_resource(LightingRig {
+            key: args.key_light,
+            fill: args.fill_light,
+            ambient: args.ambient_light,
+            direction: args
+                .light_direction
+                .unwrap_or(LightingRig::default().direction),
+            ..Default::default()
+        })
         .add_plugins(DefaultPlugins)
+        .add_plugin(AssetCheckPlugin {
+            textures: checked_textures,
+            ..Default::default()
+        })
+        .add_plugin(ActionsPlugin)
+        .add_plugin(LightingPlugin)
+        .add_plugin(args.display.metrics_plugin())
+        .add_plugin(DisplayEventsPlugin)
+        .add_plugin(OrbitCameraPlugin)
+        .add_plugin(LayersPlugin)
+        .add_plugin(ImpostorPlugin)
+        .add_plugin(BodiesPlugin)
+        .add_plugin(OcclusionPlugin)
+        .add_plugin(GeoMarkerPlugin)
+        .add_plugin(BillboardPlugin)
+        .add_plugin(GlobePickPlugin)
+        .add_plugin(CoordsPlugin)
+        .add_plugin(GeoRoutePlugin)
+        .add_plugin(GeoPolylinePlugin)
+        .add_plugin(GraticulePlugin::default())
+        .add_plugin(HeatmapPlugin)
+        .add_plugin(FlatMapPlugin)
+        .add_plugin(GeoJsonPlugin)
+        .add_plugin(GlobeTilesPlugin)
+        .add_plugin(LodSpherePlugin)
+        .add_plugin(GlobeRenderPlugin)
+        .add_plugin(SkyPlugin)
         .add_startup_system(setup.system())
-        .add_system(mouse_events_system.system())
-        .run();
+        .add_system(cluster_system.system())
+        .add_system(fly_to_pin_system.system())
+        .add_system(place_marker_system.system())
+        .add_system(heat_click_system.system())
+        .add_system(map_view_system.system())
+        .add_system(altitude_label_system.system())
+        .add_system(texture_fade_system.system())
+        .add_system(mip_level_system.system());
+    if args.animate.is_some() {
+        app.add_plugin(AnimatedTexturePlugin);
+    }
+    if let Some(path) = &args.probe {
+        let raster = app
+            .resources()
+            .get::<AssetServer>()
+            .expect("the asset server comes with the default plugins")
+            .load(path.as_str());
+        app.add_resource(DataProbe {
+            raster,
+            scale: args.probe_scale,
+            offset: args.probe_offset,
+            unit: args.probe_unit.clone(),
+            enabled: true,
+        })
+        .add_plugin(DataProbePlugin::default());
+    }
+    #[cfg(feature = "ktx2")]
+    app.add_plugin(bevy_debris::ktx2::Ktx2Plugin {
+        max_size: args.max_texture_size,
+    });
+    if let Some(persist) = args.display.persist_plugin(restored) {
+        app.add_plugin(persist);
+    }
+    // Last, once every pass drawing into the window is in the render graph.
+    app.add_plugin(CapturePlugin {
+        dir: args.capture_dir.clone(),
+        every: args.timelapse,
+    });
+    app.run();
 }
 
+fn parse_utc_arg(s: &str) -> Result<f64, String> {
+    parse_utc(s).ok_or_else(|| format!("expected YYYY-MM-DDTHH:MM[:SS]Z, got {:?}", s))
+}
+
+fn parse_color(s: &str) -> Result<Color, String> {
+    Color::hex(s.trim_start_matches('#')).map_err(|e| format!("{:?}: {:?}", s, e))
+}
+
+fn parse_direction(s: &str) -> Result<Vec3, String> {
+    let values = s
+        .split(',')
+        .map(|v| {
+            v.trim()
+                .parse::<f32>()
+                .map_err(|e| format!("{:?}: {}", v, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match values[..] {
+        [x, y, z] if x != 0.0 || y != 0.0 || z != 0.0 => Ok(Vec3::new(x, y, z)),
+        [_, _, _] => Err("direction must not be zero".to_string()),
+        _ => Err(format!("expected X,Y,Z, got {:?}", s)),
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    texture: Res<GlobeTexture>,
+    scenario: Res<Scenario>,
+    exaggeration: Res<AltitudeExaggeration>,
+    (mut day_night, mut normal_mapped): (
+        ResMut<Assets<DayNightMaterial>>,
+        ResMut<Assets<NormalMappedMaterial>>,
+    ),
+    (
+        overlays,
+        graticule,
+        heatmap,
+        map,
+        atmosphere,
+        sky,
+        geojson,
+        tiles,
+        globe_mesh,
+        shading,
+        animation,
+    ): (
+        Res<RasterOverlays>,
+        Res<GlobeGraticule>,
+        Res<GlobeHeatmap>,
+        Res<GlobeMap>,
+        Res<GlobeAtmosphere>,
+        Res<SceneSky>,
+        Res<GeoJsonOverlays>,
+        Res<GlobeTileSource>,
+        Res<GlobeMesh>,
+        Res<GlobeShading>,
+        Res<GlobeAnimation>,
+    ),
 ) {
     //let sphere_handle = meshes.add(Mesh::from(shape::Icosphere {
     //    radius: 1.0,
     //    subdivisions: 5,
     //}));
-    let sphere_handle = meshes.add(sphere_mesh(2.0, 45, 180));
-    //let sphere_handle = meshes.add(icosphere_mesh(2.0, 5));
-    let texture_handle = asset_server.load("theworld.png");
+    let sphere = globe_mesh.mesh(GLOBE_RADIUS);
+    // Picked on the raised triangles, unless patches of a sphere without relief are
+    // drawn in their place.
+    let relief = match &globe_mesh.relief {
+        Some(_) if !globe_mesh.lod => Relief::from_mesh(&sphere).ok(),
+        _ => None,
+    };
+    let sphere_handle = meshes.add(sphere);
+    // Show a graticule until the texture is in, then fade the texture in over it on a
+    // shell just above the surface.
+    let (texture_handle, generating) = match &*texture {
+        GlobeTexture::File(path) => (asset_server.load(path.as_str()), None),
+        GlobeTexture::Planet(planet) => {
+            let (sender, receiver) = crossbeam_channel::bounded(1);
+            let planet = *planet;
+            thread::spawn(move || sender.send(planet.generate()));
+            (
+                textures.get_handle(HandleId::random::<Texture>()),
+                Some(receiver),
+            )
+        }
+    };
     let material_handle = materials.add(StandardMaterial {
+        albedo_texture: Some(textures.add(graticule_texture(1024, 512))),
+        ..Default::default()
+    });
+    let textured = materials.add(StandardMaterial {
+        albedo: Color::rgba(1.0, 1.0, 1.0, 0.0),
         albedo_texture: Some(texture_handle.clone()),
+        ..Default::default()
+    });
+    let day_night = shading.night.as_ref().map(|night| {
+        day_night.add(DayNightMaterial::new(
+            texture_handle.clone(),
+            asset_server.load(night.as_str()),
+        ))
+    });
+    let normal_mapped = shading.normal_map.as_ref().map(|normal_map| {
+        normal_mapped.add(NormalMappedMaterial::new(
+            texture_handle.clone(),
+            Some(asset_server.load(normal_map.as_str())),
+        ))
+    });
+    if let Some((folder, fps)) = &animation.0 {
+        match image_sequence(&asset_server, folder) {
+            Ok(frames) => {
+                commands.spawn((AnimatedTexture::sequence(
+                    texture_handle.clone(),
+                    frames,
+                    *fps,
+                ),));
+            }
+            Err(e) => eprintln!("not animating the globe: {}", e),
+        }
+    }
+    commands.insert_resource(TextureLoad {
+        texture: texture_handle,
+        material: textured.clone(),
+        state: LoadPhase::Loading,
+        generating,
+        mips: None,
+    });
+    let pin_handle = meshes.add(Mesh::from(shape::Icosphere {
+        radius: 0.03,
+        subdivisions: 2,
+    }));
+    // Each pin and stem has a material of its own, which fades as it goes behind the
+    // globe.
+    let pin_color = Color::rgb(1.0, 0.8, 0.0);
+    let stem_handle = meshes.add(Mesh::from(shape::Cube { size: 0.5 }));
+    let coverage_material = materials.add(StandardMaterial {
+        albedo: Color::rgba(0.2, 0.8, 1.0, 0.2),
         shaded: false,
         ..Default::default()
     });
+    let font = asset_server.load("arial.ttf");
+    commands.insert_resource(ClusterAssets {
+        mesh: pin_handle.clone(),
+        material: materials.add(StandardMaterial {
+            albedo: Color::rgb(1.0, 0.4, 0.0),
+            ..Default::default()
+        }),
+        font: font.clone(),
+    });
+    for (index, point) in scenario.geo.iter().enumerate() {
+        if point.alt > 0.0 {
+            commands
+                .spawn(TextComponents {
+                    text: Text {
+                        value: format_altitude(point.alt),
+                        font: font.clone(),
+                        style: TextStyle {
+                            font_size: 14.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    ..Default::default()
+                })
+                .with(AltitudeLabel(index));
+        }
+    }
+    if let Some(sky) = &sky.0 {
+        commands.spawn((sky.clone(),));
+    }
     commands
         // textured quad - normal
         .spawn(PbrComponents {
             mesh: sphere_handle.clone(),
             material: material_handle,
-            transform: Transform::from_rotation(Quat::from_rotation_ypr(0.0, PI * 0.6, PI / 6.0)),
+            transform: Transform::from_rotation(globe_rotation()),
             draw: Draw {
                 is_transparent: true,
                 ..Default::default()
             },
             ..Default::default()
         })
+        .with(Globe)
+        .with(ProbeSurface)
+        .with(Body {
+            name: "globe".to_string(),
+            radius: GLOBE_RADIUS,
+        })
+        .with(Impostor {
+            radius: GLOBE_RADIUS,
+        })
+        .with(Occluder {
+            radius: GLOBE_RADIUS,
+        })
+        .with(map.0)
+        .with(Persist("globe"));
+    if let Some(relief) = relief {
+        commands.with(relief);
+    }
+    if globe_mesh.lod {
+        commands.with(LodSphere::new(GLOBE_RADIUS));
+    }
+    commands
+        .with_children(|globe| {
+            globe
+                .spawn(PbrComponents {
+                    mesh: meshes.add(globe_mesh.mesh(GLOBE_RADIUS * SHELL_SCALE)),
+                    material: textured,
+                    draw: Draw {
+                        is_transparent: true,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(TextureShell);
+            if globe_mesh.lod {
+                globe.with(LodSphere::new(GLOBE_RADIUS * SHELL_SCALE));
+            }
+            if let Some(material) = day_night {
+                globe.spawn((
+                    meshes.add(globe_mesh.mesh(GLOBE_RADIUS * SHADING_SCALE)),
+                    material,
+                    MainPass,
+                    Draw::default(),
+                    DayNightMaterial::render_pipelines(),
+                    Transform::default(),
+                    GlobalTransform::default(),
+                ));
+            }
+            if let Some(material) = normal_mapped {
+                let mut mesh = globe_mesh.mesh(GLOBE_RADIUS * SHADING_SCALE);
+                generate_tangents(&mut mesh);
+                globe.spawn((
+                    meshes.add(mesh),
+                    material,
+                    MainPass,
+                    Draw::default(),
+                    NormalMappedMaterial::render_pipelines(),
+                    Transform::default(),
+                    GlobalTransform::default(),
+                ));
+            }
+            for (index, point) in scenario.geo.iter().enumerate() {
+                let height = point.alt.max(0.0) / EARTH_RADIUS_M * GLOBE_RADIUS * exaggeration.0;
+                let local = geo_to_local(point.lat, point.lon, GLOBE_RADIUS + height);
+                if height > 0.0 {
+                    let up = local.normalize();
+                    globe
+                        .spawn(PbrComponents {
+                            mesh: stem_handle.clone(),
+                            material: materials.add(pin_color.into()),
+                            transform: Transform {
+                                translation: up * (GLOBE_RADIUS + height / 2.0),
+                                rotation: rotation_to(up),
+                                scale: Vec3::new(STEM_WIDTH, STEM_WIDTH, height),
+                            },
+                            draw: Draw {
+                                is_transparent: true,
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .with(AltitudeStem(index))
+                        .with(Occludable)
+                        .with(Layer::Markers);
+                }
+                globe
+                    .spawn((GeoMarker {
+                        height,
+                        ..GeoMarker::new(point.lat, point.lon, point.text.clone())
+                    },))
+                    .with(GeoPin { index, local })
+                    .with(Occludable)
+                    .with(Layer::Markers);
+            }
+            if let Some(own_ship) = &scenario.own_ship {
+                let local = geo_to_local(
+                    own_ship.lat,
+                    own_ship.lon,
+                    GLOBE_RADIUS + own_ship.alt.max(0.0) / EARTH_RADIUS_M * GLOBE_RADIUS,
+                );
+                for volume in &scenario.coverage {
+                    let scaled = CoverageVolume {
+                        min_range: volume.min_range / EARTH_RADIUS_M * GLOBE_RADIUS,
+                        max_range: volume.max_range / EARTH_RADIUS_M * GLOBE_RADIUS,
+                        ..*volume
+                    };
+                    globe
+                        .spawn(PbrComponents {
+                            mesh: meshes.add(scaled.mesh(COVERAGE_SEGMENTS)),
+                            material: coverage_material.clone(),
+                            transform: Transform {
+                                translation: local,
+                                rotation: local_level(local),
+                                ..Default::default()
+                            },
+                            draw: Draw {
+                                is_transparent: true,
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .with(Coverage)
+                        .with(Layer::Zones);
+                }
+            }
+            for region in &scenario.regions {
+                let mesh =
+                    match region.mesh(GLOBE_RADIUS * REGION_SCALE, REGION_MAX_EDGE.to_radians()) {
+                        Ok(mesh) => mesh,
+                        Err(e) => {
+                            eprintln!("skipping region: {}", e);
+                            continue;
+                        }
+                    };
+                let [r, g, b, a] = region.color;
+                globe
+                    .spawn(PbrComponents {
+                        mesh: meshes.add(mesh),
+                        material: materials.add(StandardMaterial {
+                            albedo: Color::rgba(r, g, b, a),
+                            shaded: false,
+                            ..Default::default()
+                        }),
+                        draw: Draw {
+                            is_transparent: true,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with(Layer::Zones);
+            }
+            for route in &scenario.routes {
+                globe.spawn((route.clone(),)).with(Layer::Trails);
+            }
+            for polyline in &scenario.polylines {
+                globe.spawn((polyline.clone(),)).with(Layer::Trails);
+            }
+            if let Some(graticule) = graticule.0 {
+                globe.spawn((graticule,)).with(Layer::Grid);
+            }
+            if let Some(heatmap) = &heatmap.0 {
+                globe.spawn((heatmap.clone(),)).with(Layer::Overlays);
+            }
+            if let Some(atmosphere) = atmosphere.0 {
+                globe.spawn((atmosphere,));
+            }
+            if let Some(tiles) = &tiles.0 {
+                globe.spawn((tiles.clone(),)).with(Layer::Overlays);
+            }
+            for overlay in &geojson.0 {
+                globe.spawn((overlay.clone(),)).with(Layer::Overlays);
+            }
+            for raster in &overlays.rasters {
+                globe
+                    .spawn(PbrComponents {
+                        mesh: meshes.add(raster.mesh(GLOBE_RADIUS * RASTER_SCALE, RASTER_MAX_STEP)),
+                        material: materials.add(StandardMaterial {
+                            albedo: Color::rgba(1.0, 1.0, 1.0, overlays.opacity),
+                            albedo_texture: Some(textures.add(raster.texture())),
+                            shaded: false,
+                        }),
+                        draw: Draw {
+                            is_transparent: true,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with(Layer::Overlays);
+            }
+        })
         // camera
-        .spawn(Camera3dComponents {
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 6.0)),
-            ..Default::default()
-        });
+        .spawn(Camera3dComponents::default())
+        .with(
+            OrbitCamera::new(
+                Orbit::around(Vec3::zero(), Vec3::new(0.0, 0.0, 6.0)),
+                GLOBE_RADIUS * (1.0 + MIN_ALTITUDE),
+                GLOBE_RADIUS * (1.0 + MAX_ALTITUDE),
+            )
+            .with_surface(GLOBE_RADIUS),
+        )
+        // cluster counts
+        .spawn(UiCameraComponents::default());
 }
 
-struct MouseButtonState {
-    pressed: bool,
-}
+/// Replaces pins that crowd together on screen with a bubble sized by their count,
+/// which splits up again as the globe is zoomed in or turned. Pins on the far side
+/// are left alone.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn cluster_system(
+    mut commands: Commands,
+    mut drawn: Local<Vec<Vec<usize>>>,
+    radius: Res<ClusterRadius>,
+    assets: Res<ClusterAssets>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    globes: Query<With<Globe, (Entity, &GlobalTransform, &Occluder)>>,
+    mut pins: Query<(&GeoPin, &GlobalTransform, Mut<Draw>)>,
+    bubbles: Query<With<ClusterBubble, Entity>>,
+    mut labels: Query<(Entity, &ClusterLabel, Mut<Style>)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let (camera, eye) = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some(camera) => camera,
+        None => return,
+    };
+    let (globe, center, globe_radius) = match globes.iter().next() {
+        Some((entity, transform, occluder)) => (
+            entity,
+            transform.translation,
+            occluder.radius * transform.scale.x(),
+        ),
+        None => return,
+    };
+    let view_projection = camera.projection_matrix * eye.compute_matrix().inverse();
+    let to_screen = |world: Vec3| world_to_screen(&view_projection, world, size);
+
+    let mut visible = pins
+        .iter_mut()
+        .filter(|(_, transform, _)| {
+            !occluded(eye.translation, center, globe_radius, transform.translation)
+        })
+        .map(|(pin, transform, _)| (pin.index, pin.local, to_screen(transform.translation)))
+        .collect::<Vec<_>>();
+    visible.sort_by_key(|(index, ..)| *index);
+    let screen = visible.iter().map(|(.., at)| *at).collect::<Vec<_>>();
+    let clusters = if radius.0 > 0.0 {
+        cluster_points(&screen, radius.0)
+    } else {
+        Vec::new()
+    };
+    let clusters = clusters
+        .into_iter()
+        .filter(|cluster| cluster.len() > 1)
+        .collect::<Vec<_>>();
+    let groups = clusters
+        .iter()
+        .map(|cluster| {
+            cluster
+                .members
+                .iter()
+                .map(|&i| visible[i].0)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
-#[derive(Default)]
-struct State {
-    mouse_button_event_reader: EventReader<MouseButtonInput>,
-    mouse_motion_event_reader: EventReader<MouseMotion>,
-    mouse_wheel_event_reader: EventReader<MouseWheel>,
+    if groups != *drawn {
+        for entity in bubbles.iter() {
+            commands.despawn(entity);
+        }
+        for (entity, ..) in labels.iter_mut() {
+            commands.despawn(entity);
+        }
+        for (n, (cluster, members)) in clusters.iter().zip(groups.iter()).enumerate() {
+            let local = cluster
+                .members
+                .iter()
+                .fold(Vec3::zero(), |sum, &i| sum + visible[i].1)
+                .normalize()
+                * 2.05;
+            let scale = 1.5 + (members.len() as f32).sqrt();
+            let bubble = commands
+                .spawn(PbrComponents {
+                    mesh: assets.mesh.clone(),
+                    material: assets.material.clone(),
+                    transform: Transform {
+                        translation: local,
+                        scale: Vec3::splat(scale),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(ClusterBubble)
+                .with(Layer::Markers)
+                .current_entity()
+                .unwrap();
+            commands.push_children(globe, &[bubble]);
+            commands
+                .spawn(TextComponents {
+                    style: label_style(cluster.center),
+                    text: Text {
+                        value: members.len().to_string(),
+                        font: assets.font.clone(),
+                        style: TextStyle {
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    ..Default::default()
+                })
+                .with(ClusterLabel(n));
+        }
+        for (pin, _, mut draw) in pins.iter_mut() {
+            draw.is_visible = !groups.iter().any(|group| group.contains(&pin.index));
+        }
+        *drawn = groups;
+    } else {
+        for (_, label, mut style) in labels.iter_mut() {
+            if let Some(cluster) = clusters.get(label.0) {
+                *style = label_style(cluster.center);
+            }
+        }
+    }
 }
 
-fn mouse_events_system(
-    mut state: Local<State>,
-    mut btn: ResMut<MouseButtonState>,
-    mouse_button_input_events: Res<Events<MouseButtonInput>>,
-    mouse_motion_events: Res<Events<MouseMotion>>,
-    //cursor_moved_events: Res<Events<CursorMoved>>,
-    mouse_wheel_events: Res<Events<MouseWheel>>,
-    mut sphere_query: Query<(&Handle<Mesh>, Mut<Transform>)>,
-    mut camera_query: Query<(&Camera, Mut<Transform>)>,
+/// Keeps each altitude label next to its pin, and hides it with the stem while the pin
+/// is merged into a cluster. Labels on the far side of the globe are hidden or dimmed
+/// like the pins, following [`Occlusion`].
+#[allow(clippy::type_complexity)]
+fn altitude_label_system(
+    windows: Res<Windows>,
+    occlusion: Res<Occlusion>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    globes: Query<With<Globe, (&GlobalTransform, &Occluder)>>,
+    pins: Query<(&GeoPin, &GlobalTransform, &Draw)>,
+    mut stems: Query<Without<GeoPin, (&AltitudeStem, Mut<Draw>)>>,
+    mut labels: Query<Without<GeoPin, (&AltitudeLabel, Mut<Style>, Mut<Text>, Mut<Draw>)>>,
 ) {
-    for event in state
-        .mouse_button_event_reader
-        .iter(&mouse_button_input_events)
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let (camera, eye) = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
     {
-        match event {
-            MouseButtonInput {
-                button: MouseButton::Left,
-                state,
-            } => match state {
-                ElementState::Pressed => btn.pressed = true,
-                ElementState::Released => btn.pressed = false,
-            },
-            _ => {}
+        Some(camera) => camera,
+        None => return,
+    };
+    let (center, globe_radius) = match globes.iter().next() {
+        Some((transform, occluder)) => {
+            (transform.translation, occluder.radius * transform.scale.x())
+        }
+        None => return,
+    };
+    let view_projection = camera.projection_matrix * eye.compute_matrix().inverse();
+    let pin = |index: usize| {
+        pins.iter()
+            .find(|(pin, ..)| pin.index == index)
+            .map(|(_, transform, draw)| (transform.translation, draw.is_visible))
+    };
+    for (stem, mut draw) in stems.iter_mut() {
+        let shown = pin(stem.0).is_some_and(|(_, shown)| shown);
+        if draw.is_visible != shown {
+            draw.is_visible = shown;
         }
     }
+    for (label, mut style, mut text, mut draw) in labels.iter_mut() {
+        let (at, shown) = match pin(label.0) {
+            Some(pin) => pin,
+            None => continue,
+        };
+        let alpha = occlusion_alpha(&occlusion, &[(center, globe_radius)], eye.translation, at);
+        if text.style.color.a() != alpha {
+            text.style.color.set_a(alpha);
+        }
+        draw.is_visible = shown && alpha > 0.0;
+        if draw.is_visible {
+            *style = label_style(world_to_screen(&view_projection, at, size) + Vec2::new(6.0, 0.0));
+        }
+    }
+}
 
-    for event in state.mouse_motion_event_reader.iter(&mouse_motion_events) {
-        if btn.pressed {
-            let MouseMotion { delta } = event;
-            if delta.length_squared() > 0.0 {
-                for (_, mut transform) in sphere_query.iter_mut() {
-                    let phi = delta.x() * PI / 720.0;
-                    let theta = delta.y() * PI / 720.0;
-                    let r = Quat::from_rotation_ypr(phi, theta, 0.0);
-                    transform.rotation = r * transform.rotation;
-                }
-            }
+/// Drops a marker labelled in the chosen [`CoordFormat`] where the globe, or any other
+/// body, is clicked with [`Action::PlaceMarker`], Alt, held, and tells how far it is
+/// from own ship and on what bearing.
+fn place_marker_system(
+    mut commands: Commands,
+    mut reader: Local<EventReader<GlobeClicked>>,
+    actions: Res<ActionState>,
+    format: Res<CoordFormat>,
+    clicks: Res<Events<GlobeClicked>>,
+    scenario: Res<Scenario>,
+) {
+    let placing = actions.pressed(Action::PlaceMarker);
+    for click in reader.iter(&clicks) {
+        if !placing {
+            continue;
+        }
+        let label = format.format_geo(click.lat, click.lon);
+        tracing::info!("marker placed at {}", label);
+        if let Some(own_ship) = &scenario.own_ship {
+            let from = LatLon::new(own_ship.lat, own_ship.lon);
+            let to = LatLon::new(click.lat, click.lon);
+            tracing::info!(
+                "  {:.1} km from own ship, bearing {:.1}°",
+                from.distance_to(to, EARTH_RADIUS_M) / 1000.0,
+                from.bearing_to(to)
+            );
+        }
+        let marker = commands
+            .spawn((GeoMarker::new(click.lat, click.lon, label),))
+            .with(Occludable)
+            .with(Layer::Markers)
+            .current_entity();
+        if let Some(marker) = marker {
+            commands.push_children(click.body, &[marker]);
         }
     }
+}
 
-    for event in state.mouse_wheel_event_reader.iter(&mouse_wheel_events) {
-        let MouseWheel { unit: _, x: _, y } = event;
-        for (_, mut transform) in camera_query.iter_mut() {
-            let delta = transform.translation.normalize() * *y;
-            let new_translation = transform.translation - delta;
-            if new_translation.length() > 3.0 {
-                transform.translation = new_translation;
+/// Adds heat to the globe's heatmap where it is clicked with [`Action::AddHeat`], X,
+/// held.
+fn heat_click_system(
+    mut reader: Local<EventReader<GlobeClicked>>,
+    actions: Res<ActionState>,
+    clicks: Res<Events<GlobeClicked>>,
+    mut heatmaps: Query<(&Parent, Mut<Heatmap>)>,
+) {
+    let heating = actions.pressed(Action::AddHeat);
+    for click in reader.iter(&clicks) {
+        if !heating {
+            continue;
+        }
+        for (parent, mut heatmap) in heatmaps.iter_mut() {
+            if parent.0 == click.body {
+                heatmap.add(click.lat, click.lon, 1.0);
             }
         }
     }
 }
 
-#[allow(dead_code)]
-fn icosphere_mesh(radius: f32, divisions: usize) -> Mesh {
-    use hexasphere::IcoSphere;
-    let hexasphere = IcoSphere::new(divisions, |point| {
-        let inclination = if point.x() > 0.0 {
-            point.z().acos()
+/// Flies the camera over the middle of the flat map as the globe flattens into it, and
+/// hides the atmosphere until it is a globe again.
+fn map_view_system(
+    mut was_flat: Local<bool>,
+    mut camera_commands: ResMut<CameraCommands>,
+    maps: Query<With<Globe, &FlatMap>>,
+    mut atmospheres: Query<Mut<Atmosphere>>,
+) {
+    let map = match maps.iter().next() {
+        Some(map) => map,
+        None => return,
+    };
+    if map.flat == *was_flat {
+        return;
+    }
+    *was_flat = map.flat;
+    if map.flat {
+        let center = map.center();
+        let distance = GLOBE_RADIUS * (2.0 + MAP_ALTITUDE);
+        camera_commands.fly_to(center.lat, center.lon, distance, map.duration.max(FLY_SECS));
+    }
+    for mut atmosphere in atmospheres.iter_mut() {
+        atmosphere.visible = !map.flat;
+    }
+}
+
+/// Flies the camera to the pin clicked on, if any, through [`CameraCommands`]. Pins on
+/// the far side or merged into a bubble cannot be clicked.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn fly_to_pin_system(
+    mut pressed_at: Local<Option<Vec2>>,
+    actions: Res<ActionState>,
+    (cursor, windows, scenario): (Res<CursorPosition>, Res<Windows>, Res<Scenario>),
+    mut camera_commands: ResMut<CameraCommands>,
+    cameras: Query<(&Camera, &GlobalTransform, Option<&OrbitCamera>)>,
+    globes: Query<With<Globe, (&GlobalTransform, &Occluder)>>,
+    pins: Query<(&GeoPin, &GlobalTransform, &Draw)>,
+) {
+    if actions.just_pressed(Action::Select) {
+        *pressed_at = cursor.screen;
+    }
+    if !actions.just_released(Action::Select) {
+        return;
+    }
+    let (from, at) = match (pressed_at.take(), cursor.screen) {
+        (Some(from), Some(at)) => (from, at),
+        _ => return,
+    };
+    if (at - from).length() > PICK_PIXELS {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let (camera, eye, orbit) = match cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some(camera) => camera,
+        None => return,
+    };
+    let (center, globe_radius) = match globes.iter().next() {
+        Some((transform, occluder)) => {
+            (transform.translation, occluder.radius * transform.scale.x())
+        }
+        None => return,
+    };
+    let view_projection = camera.projection_matrix * eye.compute_matrix().inverse();
+    let picked = pins
+        .iter()
+        .filter(|(_, transform, draw)| {
+            draw.is_visible
+                && !occluded(eye.translation, center, globe_radius, transform.translation)
+        })
+        .map(|(pin, transform, _)| {
+            let screen = world_to_screen(&view_projection, transform.translation, size);
+            (pin.index, (screen - at).length())
+        })
+        .filter(|&(_, pixels)| pixels <= PICK_PIXELS)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    let point = match picked.and_then(|(index, _)| scenario.geo.get(index)) {
+        Some(point) => point,
+        None => return,
+    };
+    let lowest = GLOBE_RADIUS * (1.0 + FLY_ALTITUDE);
+    let distance = orbit.map_or(lowest, |orbit| orbit.distance.min(lowest));
+    camera_commands.fly_to(point.lat, point.lon, distance, FLY_SECS);
+}
+
+/// How the globe is turned in the world.
+fn globe_rotation() -> Quat {
+    Quat::from_rotation_ypr(0.0, PI * 0.6, PI / 6.0)
+}
+
+/// Window position of `world` under `view_projection`, origin bottom-left.
+fn world_to_screen(view_projection: &Mat4, world: Vec3, size: Vec2) -> Vec2 {
+    let clip = *view_projection * world.extend(1.0);
+    let ndc = Vec2::new(clip.x(), clip.y()) / clip.w();
+    (ndc + Vec2::one()) / 2.0 * size
+}
+
+/// Turns +z onto the unit vector `dir`.
+fn rotation_to(dir: Vec3) -> Quat {
+    let axis = Vec3::unit_z().cross(dir);
+    if axis.length_squared() < 1e-12 {
+        return if dir.z() >= 0.0 {
+            Quat::identity()
         } else {
-            PI * 2.0 - point.z().acos()
+            Quat::from_rotation_x(PI)
         };
-        let azumith = Vec2::new(point.x(), point.z()).length().acos() * point.y().signum();
-        //let azumith = point.y().atan2(point.x().abs());
+    }
+    Quat::from_axis_angle(
+        axis.normalize(),
+        Vec3::unit_z().dot(dir).clamp(-1.0, 1.0).acos(),
+    )
+}
 
-        let norm_inclination = inclination / (PI * 2.0);
-        let norm_azumith = (azumith / PI) + 0.5;
+/// Kilometres from 1 km up, whole metres below.
+fn format_altitude(meters: f32) -> String {
+    if meters >= 1000.0 {
+        format!("{:.1} km", meters / 1000.0)
+    } else {
+        format!("{:.0} m", meters)
+    }
+}
 
-        [norm_inclination, norm_azumith]
-    });
-    let raw_points = hexasphere.raw_points();
+fn label_style(at: Vec2) -> Style {
+    Style {
+        position_type: PositionType::Absolute,
+        position: Rect {
+            left: Val::Px(at.x()),
+            bottom: Val::Px(at.y()),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
 
-    let points = raw_points
-        .iter()
-        .map(|&p| (p * radius).into())
-        .collect::<Vec<[f32; 3]>>();
+/// Fades the globe texture in once it has loaded or been generated, then hands its material to the globe
+/// and drops the shell. If it fails to load the placeholder stays. The loaded texture
+/// gets the [`SamplerSettings`] and, unless turned off, a [`MipChain`].
+#[allow(clippy::too_many_arguments)]
+fn texture_fade_system(
+    mut commands: Commands,
+    time: Res<AnimationTime>,
+    asset_server: Res<AssetServer>,
+    sampler: Res<SamplerSettings>,
+    mipmaps: Res<Mipmaps>,
+    mut load: ResMut<TextureLoad>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut globes: Query<With<Globe, Mut<Handle<StandardMaterial>>>>,
+    shells: Query<With<TextureShell, Entity>>,
+) {
+    let now = time.seconds();
+    let load = &mut *load;
+    let since = match load.state {
+        LoadPhase::Loading => {
+            if let Some(generated) = load.generating.as_ref().and_then(|r| r.try_recv().ok()) {
+                textures.set_untracked(&load.texture, generated);
+                load.generating = None;
+            }
+            if asset_server.get_load_state(&load.texture) == LoadState::Failed {
+                eprintln!("warning: globe texture failed to load, keeping the graticule");
+                load.state = LoadPhase::Done;
+                return;
+            }
+            let texture = match textures.get_mut(&load.texture) {
+                Some(texture) => texture,
+                None => return,
+            };
+            sampler.apply(&mut texture.sampler);
+            if mipmaps.0 {
+                load.mips = Some((MipChain::new(texture), 0));
+            }
+            load.state = LoadPhase::Fading { since: now };
+            now
+        }
+        LoadPhase::Fading { since } => since,
+        LoadPhase::Done => return,
+    };
+    let t = ((now - since) / TEXTURE_FADE).min(1.0) as f32;
+    if let Some(material) = materials.get_mut(&load.material) {
+        material.albedo.set_a(t);
+    }
+    if t < 1.0 {
+        return;
+    }
+    for mut material in globes.iter_mut() {
+        *material = load.material.clone();
+    }
+    for entity in shells.iter() {
+        commands.despawn(entity);
+    }
+    load.state = LoadPhase::Done;
+}
 
-    let normals = raw_points
+/// Swaps the globe texture for the mip level matching how large the globe is on
+/// screen.
+fn mip_level_system(
+    windows: Res<Windows>,
+    mut load: ResMut<TextureLoad>,
+    mut textures: ResMut<Assets<Texture>>,
+    cameras: Query<(&Camera, &PerspectiveProjection, &Transform)>,
+    globes: Query<With<Globe, &Transform>>,
+) {
+    let load = &mut *load;
+    let (chain, shown) = match &mut load.mips {
+        Some(mips) => mips,
+        None => return,
+    };
+    let window_height = match windows.get_primary() {
+        Some(window) => window.height() as f32,
+        None => return,
+    };
+    let (projection, camera) = match cameras
         .iter()
-        .copied()
-        .map(Into::into)
-        .collect::<Vec<[f32; 3]>>();
-
-    let uvs = hexasphere.raw_data().to_owned();
-
-    let mut indices = Vec::with_capacity(hexasphere.indices_per_main_triangle() * 20);
-
-    for i in 0..20 {
-        hexasphere.get_indices(i, &mut indices);
-    }
-
-    let indices = Indices::U32(indices);
-
-    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-    mesh.set_indices(Some(indices));
-    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, points.into());
-    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
-    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
-    mesh
-}
-
-fn sphere_mesh(radius: f32, lat_counts: u32, lon_counts: u32) -> Mesh {
-    let lat_step = PI / lat_counts as f32;
-    let lon_step = PI * 2.0 / lon_counts as f32;
-    let vertex_count = ((lat_counts + 1) * (lon_counts + 1)) as usize;
-    let mut positions = Vec::with_capacity(vertex_count);
-    let mut normals = Vec::with_capacity(vertex_count);
-    let mut uvs = Vec::with_capacity(vertex_count);
-    for lon in 0..=lon_counts {
-        let theta = lon_step * lon as f32;
-        for lat in 0..=lat_counts {
-            let azu = -PI / 2.0 + lat_step * lat as f32;
-            let pos = Vec3::new(
-                radius * theta.cos() * azu.cos(),
-                radius * theta.sin() * azu.cos(),
-                radius * azu.sin(),
-            );
-            positions.push([pos.x(), pos.y(), pos.z()]);
-            let n = pos.normalize();
-            normals.push([n.x(), n.y(), n.z()]);
-            uvs.push([
-                1.0 - lon as f32 / lon_counts as f32,
-                lat as f32 / lat_counts as f32,
-            ])
-        }
-    }
-    let mut indices = Vec::with_capacity((lon_counts * lat_counts) as usize);
-    for lon in 0..lon_counts {
-        let idx = lon * (lat_counts + 1);
-        for lat in 0..lat_counts {
-            let idx = idx + lat;
-            if lat < lat_counts {
-                indices.extend(vec![idx, idx + lat_counts + 1, idx + 1]);
-            }
-            if lat > 0 {
-                indices.extend(vec![idx, idx + lat_counts, idx + lat_counts + 1]);
-            }
-        }
-    }
-    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
-    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
-    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
-    mesh.set_indices(Some(Indices::U32(indices)));
-    mesh
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some((_, projection, transform)) => (projection, transform),
+        None => return,
+    };
+    let globe = match globes.iter().next() {
+        Some(globe) => globe,
+        None => return,
+    };
+    // Screen pixels per world unit at the near side of the globe.
+    let distance = ((camera.translation - globe.translation).length()
+        - GLOBE_RADIUS * globe.scale.x())
+    .max(0.01);
+    let pixels_per_unit = window_height / (2.0 * distance * (projection.fov / 2.0).tan());
+    let circumference = PI * 2.0 * GLOBE_RADIUS * globe.scale.x() * pixels_per_unit;
+    let width = chain.levels()[0].size.x();
+    let level = chain.level_for(width / circumference);
+    if level != *shown {
+        if let Some(texture) = textures.get_mut(&load.texture) {
+            *texture = chain.levels()[level].clone();
+        }
+        *shown = level;
+    }
+}
+
+/// An equirectangular placeholder for the globe texture: dark ocean with a line every
+/// [`GRATICULE_STEP`] degrees of latitude and longitude.
+fn graticule_texture(width: u32, height: u32) -> Texture {
+    const OCEAN: [u8; 4] = [16, 32, 56, 255];
+    const LINE: [u8; 4] = [70, 110, 150, 255];
+    let (cell_x, cell_y) = (
+        width as f32 * GRATICULE_STEP / 360.0,
+        height as f32 * GRATICULE_STEP / 180.0,
+    );
+    let on_line = |at: u32, cell: f32| {
+        let offset = at as f32 % cell;
+        offset < 1.0 || cell - offset < 1.0
+    };
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = if on_line(x, cell_x) || on_line(y, cell_y) {
+                LINE
+            } else {
+                OCEAN
+            };
+            data.extend_from_slice(&pixel);
+        }
+    }
+    Texture::new(
+        Vec2::new(width as f32, height as f32),
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/// Turns the frame of a [`CoverageVolume`] to stand on the globe at `local`: z up, x
+/// east and y north.
+fn local_level(local: Vec3) -> Quat {
+    let up = local.normalize();
+    // The north pole is towards -z, see `geo_to_local`.
+    let pole = -Vec3::unit_z();
+    let north = pole - up * pole.dot(up);
+    let north = if north.length_squared() > f32::EPSILON {
+        north.normalize()
+    } else {
+        Vec3::unit_x()
+    };
+    let east = north.cross(up);
+    Quat::from_rotation_mat3(&Mat3::from_cols(east, north, up))
 }
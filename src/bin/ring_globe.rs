@@ -0,0 +1,330 @@
+use std::f32::consts::FRAC_PI_2;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy_debris::actions::ActionMap;
+use bevy_debris::asset_check::AssetCheckPlugin;
+use bevy_debris::bodies::Body;
+use bevy_debris::camera::{Orbit, OrbitCamera, OrbitCameraPlugin};
+use bevy_debris::cli::{init_logging, parse_positive, DisplayArgs};
+use bevy_debris::culling::{Culling, CullingPlugin};
+use bevy_debris::display::PoiRingPlugin;
+use bevy_debris::emphasis::EmphasisPlugin;
+use bevy_debris::events::DisplayEventsPlugin;
+use bevy_debris::geo::{LatLon, Polar};
+use bevy_debris::geo_marker::GeoMarkerPlugin;
+use bevy_debris::globe_link::{FollowSelection, GlobeLink, GlobeLinkPlugin};
+use bevy_debris::globe_pick::GlobePickPlugin;
+use bevy_debris::instancing::InstancedMarkerPlugin;
+use bevy_debris::layout::LayoutConfig;
+use bevy_debris::lighting::LightingPlugin;
+use bevy_debris::mesh::sphere_mesh;
+use bevy_debris::occlusion::{Occluder, OcclusionPlugin};
+use bevy_debris::polyline::GeoPolylinePlugin;
+use bevy_debris::prediction::{GlobePredictionPlugin, Prediction, PredictionPlugin};
+use bevy_debris::range_rings::RangeRingsPlugin;
+use bevy_debris::scenario::{Preset, RandomTargets, Scenario};
+use bevy_debris::selection::SelectionPlugin;
+use bevy_debris::split_view::{SplitViewPlugin, ViewRect};
+use bevy_debris::target::{GeoPoint, Target};
+use bevy_debris::timeline::{Timeline, TimelinePlugin};
+use bevy_debris::tooltip::TooltipPlugin;
+use bevy_debris::undo::UndoPlugin;
+use bevy_debris::viewport::{DisplayViewport, ViewOffset, ViewportPlugin};
+use bevy_debris::zones::AlertZonesPlugin;
+use clap::Parser;
+
+const GLOBE_RADIUS: f32 = 2.0;
+/// Lowest and highest the camera gets, in globe radii above the surface.
+const MIN_ALTITUDE: f32 = 0.05;
+const MAX_ALTITUDE: f32 = 10.0;
+/// Rows of the globe's sphere, which has four times as many columns.
+const GLOBE_ROWS: u32 = 64;
+/// Name of the camera of `--inset`.
+const INSET_CAMERA: &str = "inset";
+/// How high the inset looks down from, in globe radii above the surface.
+const INSET_ALTITUDE: f32 = 0.15;
+
+/// The ring display on the left of the window and a globe on the right, showing the
+/// same targets: each is placed on the rings by its bearing and distance from the
+/// observer, and pinned on the globe at its latitude and longitude. Selecting a target
+/// in either view selects it in both; the right button turns the globe.
+#[derive(Parser)]
+struct Args {
+    /// Scenario file (JSON); its targets with a geodetic point of the same id are
+    /// placed from it
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+    /// Built-in scenario to show
+    #[arg(long, value_enum, conflicts_with = "scenario")]
+    preset: Option<Preset>,
+    /// Seed for presets and the random demo targets used when no scenario is given
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Number of random demo targets
+    #[arg(long, default_value_t = RandomTargets::default().count)]
+    count: usize,
+    /// Latitude of the observer at the center of the rings, in degrees; defaults to
+    /// the scenario's own ship, or 0
+    #[arg(long, allow_hyphen_values = true)]
+    observer_lat: Option<f32>,
+    /// Longitude of the observer, in degrees
+    #[arg(long, allow_hyphen_values = true)]
+    observer_lon: Option<f32>,
+    /// Meters over the ground per distance unit of the rings
+    #[arg(long, default_value_t = GlobeLink::default().meters_per_unit)]
+    meters_per_unit: f32,
+    /// Side length of a marker square
+    #[arg(long, default_value_t = 30.0, value_parser = parse_positive)]
+    poi_width: f32,
+    /// Label the rings with their distance and draw bearing ticks and N/E/S/W
+    #[arg(long)]
+    range_rings: bool,
+    /// Fly the globe camera over each target selected
+    #[arg(long)]
+    fly_to_selected: bool,
+    /// Show a close-up of the selected target in an inset at the top right
+    #[arg(long)]
+    inset: bool,
+    /// Number of targets from which the globe's pins are drawn instanced
+    #[arg(long, default_value_t = InstancedMarkerPlugin::default().threshold)]
+    instance_threshold: usize,
+    /// Seconds ahead to draw predicted positions of moving targets on the globe too
+    #[arg(long)]
+    horizon: Option<f32>,
+    /// Draw a line from each moving target to where it will be at the horizon
+    #[arg(long)]
+    prediction_vectors: bool,
+    /// Hide pins farther than this from the globe camera, in globe radii
+    #[arg(long)]
+    pin_cull_distance: Option<f32>,
+    /// Show only the targets timestamped within this many seconds before the position
+    /// of a timeline along the bottom, with a scrubber and play/pause, in both views
+    #[arg(long, value_name = "SECS")]
+    timeline_window: Option<f64>,
+    #[command(flatten)]
+    display: DisplayArgs,
+}
+
+fn main() {
+    init_logging();
+    let args = Args::parse();
+    let scenario = match (&args.scenario, args.preset) {
+        (Some(path), _) => Scenario::from_file(path).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }),
+        (None, Some(preset)) => preset.build(args.seed),
+        (None, None) => Scenario {
+            targets: RandomTargets {
+                count: args.count,
+                ..Default::default()
+            }
+            .generate(args.seed),
+            ..Default::default()
+        },
+    };
+    let own_ship = scenario
+        .own_ship
+        .as_ref()
+        .map_or_else(LatLon::default, |own| LatLon::new(own.lat, own.lon));
+    let link = GlobeLink {
+        observer: LatLon::new(
+            args.observer_lat.unwrap_or(own_ship.lat),
+            args.observer_lon.unwrap_or(own_ship.lon),
+        ),
+        meters_per_unit: args.meters_per_unit,
+        fly_to: args
+            .fly_to_selected
+            .then_some(GLOBE_RADIUS * (1.0 + MAX_ALTITUDE / 4.0)),
+        ..Default::default()
+    };
+
+    let actions = args
+        .display
+        .action_map(ActionMap::default())
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        });
+
+    let mut app = App::build();
+    app.add_resource(args.display.window_descriptor("ring and globe"))
+        .add_resource(ClearColor(args.display.theme.background()))
+        .add_resource(args.display.theme)
+        .add_resource(scenario)
+        .add_resource(link)
+        .add_resource(Inset(args.inset))
+        .add_resource(actions)
+        .add_plugins(DefaultPlugins)
+        .add_plugin(AssetCheckPlugin::default())
+        .add_plugin(LightingPlugin)
+        .add_plugin(PoiRingPlugin {
+            config: LayoutConfig::new(args.poi_width),
+            ..Default::default()
+        })
+        .add_plugin(DisplayEventsPlugin)
+        .add_plugin(ViewportPlugin)
+        .add_plugin(EmphasisPlugin)
+        .add_plugin(SelectionPlugin)
+        .add_plugin(TooltipPlugin)
+        .add_plugin(OrbitCameraPlugin)
+        .add_plugin(OcclusionPlugin)
+        .add_plugin(GeoMarkerPlugin)
+        .add_plugin(InstancedMarkerPlugin {
+            threshold: args.instance_threshold,
+        })
+        .add_plugin(GlobePickPlugin)
+        .add_plugin(GlobeLinkPlugin)
+        .add_plugin(GeoPolylinePlugin)
+        .add_plugin(AlertZonesPlugin::default())
+        .add_plugin(UndoPlugin)
+        .add_resource(Culling {
+            max_distance: args.pin_cull_distance.map(|radii| radii * GLOBE_RADIUS),
+            ..Default::default()
+        })
+        .add_plugin(CullingPlugin)
+        .add_startup_system(setup.system());
+    if args.range_rings {
+        app.add_plugin(RangeRingsPlugin);
+    }
+    if let Some(horizon) = args.horizon {
+        app.add_resource(Prediction {
+            horizon,
+            vectors: args.prediction_vectors,
+            ..Default::default()
+        })
+        .add_plugin(PredictionPlugin)
+        .add_plugin(GlobePredictionPlugin);
+    }
+    if let Some(window) = args.timeline_window {
+        app.add_resource(Timeline::new(window))
+            .add_plugin(TimelinePlugin);
+    }
+    if args.inset {
+        app.add_plugin(SplitViewPlugin {
+            cameras: vec![INSET_CAMERA.to_string()],
+        });
+    }
+    #[cfg(feature = "web")]
+    app.add_plugin(bevy_debris::web::WebPlugin);
+    app.run();
+}
+
+/// The `--inset` option, for `setup` to spawn the inset's camera.
+struct Inset(bool);
+
+#[allow(clippy::too_many_arguments)]
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    windows: Res<Windows>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    scenario: Res<Scenario>,
+    link: Res<GlobeLink>,
+    inset: Res<Inset>,
+) {
+    let size = windows
+        .get_primary()
+        .map_or(Vec2::new(1280.0, 720.0), |window| {
+            Vec2::new(window.width() as f32, window.height() as f32)
+        });
+    commands
+        .spawn(Camera2dComponents::default())
+        .with(DisplayViewport {
+            mask: false,
+            ..DisplayViewport::new(Vec2::zero(), Vec2::new(size.x() / 2.0, size.y()))
+        })
+        .spawn(UiCameraComponents::default())
+        .spawn(Camera3dComponents::default())
+        .with(
+            OrbitCamera::new(
+                Orbit::around(Vec3::zero(), Vec3::new(0.0, 0.0, GLOBE_RADIUS * 3.0)),
+                GLOBE_RADIUS * (1.0 + MIN_ALTITUDE),
+                GLOBE_RADIUS * (1.0 + MAX_ALTITUDE),
+            )
+            .with_surface(GLOBE_RADIUS),
+        )
+        // Centered in the right half, which the display leaves free.
+        .with(ViewOffset::new(Vec2::new(0.25, 0.0)));
+    if inset.0 {
+        let distance = GLOBE_RADIUS * (1.0 + INSET_ALTITUDE);
+        commands
+            .spawn(Camera3dComponents {
+                camera: Camera {
+                    name: Some(INSET_CAMERA.to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(
+                OrbitCamera::new(
+                    Orbit::around(Vec3::zero(), Vec3::new(0.0, 0.0, distance)),
+                    GLOBE_RADIUS * (1.0 + MIN_ALTITUDE),
+                    GLOBE_RADIUS * (1.0 + MAX_ALTITUDE),
+                )
+                .with_surface(GLOBE_RADIUS),
+            )
+            .with(FollowSelection { distance })
+            .with(ViewRect::new(Vec2::new(0.72, 0.6), Vec2::new(0.26, 0.37)));
+    }
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(sphere_mesh(GLOBE_RADIUS, GLOBE_ROWS, 4 * GLOBE_ROWS)),
+            material: materials.add(StandardMaterial {
+                albedo_texture: Some(asset_server.load("theworld.png")),
+                ..Default::default()
+            }),
+            // The sphere's north pole is along -z; turn it up.
+            transform: Transform::from_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+            ..Default::default()
+        })
+        .with(Body {
+            name: "globe".to_string(),
+            radius: GLOBE_RADIUS,
+        })
+        .with(Occluder {
+            radius: GLOBE_RADIUS,
+        });
+
+    // Targets without a geodetic point of their own are put where the rings show them,
+    // so that every target is on the globe too.
+    for target in &scenario.targets {
+        let geo = scenario
+            .geo
+            .iter()
+            .find(|geo| geo.id == target.id)
+            .cloned()
+            .unwrap_or_else(|| {
+                let place = link.place(Polar::new(target.azimuth, target.dist));
+                GeoPoint {
+                    id: target.id,
+                    text: target.text.clone(),
+                    lat: place.lat,
+                    lon: place.lon,
+                    alt: 0.0,
+                }
+            });
+        let polar = link.polar(LatLon::new(geo.lat, geo.lon));
+        let mut target = target.clone();
+        target.azimuth = polar.azimuth;
+        target.dist = polar.dist;
+        commands.spawn((target, geo));
+    }
+    // And points without a target get one.
+    for geo in &scenario.geo {
+        if scenario.targets.iter().all(|target| target.id != geo.id) {
+            let polar = link.polar(LatLon::new(geo.lat, geo.lon));
+            let target = Target {
+                id: geo.id,
+                text: geo.text.clone(),
+                azimuth: polar.azimuth,
+                dist: polar.dist,
+                ..Default::default()
+            };
+            commands.spawn((target, geo.clone()));
+        }
+    }
+}
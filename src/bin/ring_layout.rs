@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+use bevy_debris::cli::{init_logging, parse_positive};
+use bevy_debris::display::RadarDisplay;
+use bevy_debris::io::load_targets;
+use bevy_debris::layout::{
+    verify, verify_order, AdaptiveSpacing, ForceDirected, LayoutBackend, LayoutConfig,
+    PlacementMode, RingLayout, TieBreak, DEFAULT_SCATTER,
+};
+use bevy_debris::origins::SensorOrigins;
+use bevy_debris::regression::LayoutCase;
+use bevy_debris::scale::RadialScale;
+use bevy_debris::scenario::Scenario;
+use bevy_debris::svg::SvgExport;
+use bevy_debris::target::Target;
+use bevy_debris::theme::{RingTextScale, Theme};
+use clap::Parser;
+use serde::Serialize;
+
+/// Lay targets out on rings without opening a window, and write which ring and
+/// azimuth each one got as JSON.
+#[derive(Parser)]
+struct Args {
+    /// Target list (.json or .csv) with id, label, azimuth (degrees), distance and,
+    /// optionally, category and priority
+    #[arg(long, value_name = "FILE", required_unless_present_any = ["scenario", "case"])]
+    targets: Option<PathBuf>,
+    /// Scenario file (JSON) to take the targets from instead
+    #[arg(long, value_name = "FILE", conflicts_with = "targets")]
+    scenario: Option<PathBuf>,
+    /// Replay a layout case saved by `square_ring --dump-layout-failures` with its own
+    /// config instead, and fail if it still doesn't verify
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["targets", "scenario"])]
+    case: Option<PathBuf>,
+    /// Write the assignments here instead of to standard output
+    #[arg(long, short, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// Also draw the layout as SVG into this file
+    #[arg(long, value_name = "FILE")]
+    svg: Option<PathBuf>,
+    /// Side length of a marker square
+    #[arg(long, default_value_t = 30.0, value_parser = parse_positive)]
+    poi_width: f32,
+    /// Distance between rings (defaults to twice the marker size)
+    #[arg(long, value_parser = parse_positive)]
+    ring_spacing: Option<f32>,
+    /// How much wider than a marker the gap between neighbours on a ring is
+    #[arg(long, default_value_t = DEFAULT_SCATTER, value_parser = parse_positive)]
+    scatter: f32,
+    /// Widen the gap below crowded rings so they hold more markers
+    #[arg(long)]
+    adaptive_spacing: bool,
+    /// Turn each ring slightly so markers on consecutive rings don't line up
+    #[arg(long)]
+    stagger: bool,
+    /// Which targets get the inner rings when they collide
+    #[arg(long, value_enum, default_value_t = PlacementMode::Nearest)]
+    placement: PlacementMode,
+    /// Which ring a target takes when several have room
+    #[arg(long, value_enum, default_value_t = TieBreak::Innermost)]
+    tie_break: TieBreak,
+    /// How distance maps onto the rings: linear, log:NEAR[,RATIO] or
+    /// breakpoints:DIST:RING,DIST:RING,...
+    #[arg(long, default_value_t = RadialScale::Linear)]
+    scale: RadialScale,
+    /// Let markers slide sideways off their azimuth to stay on inner rings
+    #[arg(long)]
+    force_directed: bool,
+    /// Route leader lines around the markers of inner rings and write their points
+    #[arg(long)]
+    route_leaders: bool,
+    /// Check the layout for overlaps, missing targets and targets pushed out of order,
+    /// and fail if there are any
+    #[arg(long)]
+    verify: bool,
+    /// Run the layout this many times and print the mean time it took to standard error
+    #[arg(long, value_name = "N")]
+    bench: Option<u32>,
+}
+
+/// The layout as written out.
+#[derive(Serialize)]
+struct Assignments {
+    poi_width: f32,
+    rings: Vec<RingInfo>,
+    /// Ordered by target id, so layouts of the same input diff cleanly.
+    targets: Vec<Assignment>,
+}
+
+#[derive(Serialize)]
+struct RingInfo {
+    radius: f32,
+    /// Distance the ring stands for.
+    distance: f32,
+    /// Degrees the ring is turned by when drawn.
+    offset: f32,
+    targets: usize,
+}
+
+/// Where one target went. Angles are in degrees, like target lists.
+#[derive(Serialize)]
+struct Assignment {
+    id: i32,
+    text: String,
+    distance: f32,
+    /// The target's own azimuth.
+    bearing: f32,
+    ring: usize,
+    radius: f32,
+    /// Azimuth it was placed at on its ring.
+    azimuth: f32,
+    /// Azimuth its marker is drawn at, with the ring's offset.
+    drawn_azimuth: f32,
+    /// Points of its leader line from the origin, with `--route-leaders`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    leader: Option<Vec<(f32, f32)>>,
+}
+
+fn layout_config(args: &Args) -> LayoutConfig {
+    let defaults = LayoutConfig::new(args.poi_width);
+    LayoutConfig {
+        ring_spacing: args.ring_spacing.unwrap_or(defaults.ring_spacing),
+        scatter: args.scatter,
+        mode: args.placement,
+        tie_break: args.tie_break,
+        scale: args.scale,
+        adaptive: args.adaptive_spacing.then(AdaptiveSpacing::default),
+        stagger: args.stagger,
+        leader_routing: args.route_leaders,
+        backend: if args.force_directed {
+            LayoutBackend::ForceDirected(ForceDirected::default())
+        } else {
+            LayoutBackend::Greedy
+        },
+        ..defaults
+    }
+}
+
+fn assignments(layout: &RingLayout) -> Assignments {
+    let mut routes = if layout.config.leader_routing {
+        layout
+            .route_leaders()
+            .into_iter()
+            .map(|route| (route.id, route.points))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let rings = (0..layout.rings.len())
+        .map(|ring| RingInfo {
+            radius: layout.ring_radius(ring),
+            distance: layout.ring_distance(ring),
+            offset: layout.ring_offset(ring).to_degrees(),
+            targets: layout.rings[ring].len(),
+        })
+        .collect();
+    let mut targets: Vec<_> = layout
+        .placements()
+        .map(|placement| Assignment {
+            id: placement.target.id,
+            text: placement.target.text.clone(),
+            distance: placement.target.dist,
+            bearing: placement.target.azimuth.to_degrees(),
+            ring: placement.ring,
+            radius: placement.radius,
+            azimuth: placement.azimuth.to_degrees(),
+            drawn_azimuth: placement.drawn_azimuth().to_degrees(),
+            leader: routes.remove(&placement.target.id),
+        })
+        .collect();
+    targets.sort_by_key(|assignment| assignment.id);
+    Assignments {
+        poi_width: layout.poi_width(),
+        rings,
+        targets,
+    }
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    if let Some(path) = &args.case {
+        let layout = LayoutCase::load(path)?.check()?;
+        return write_assignments(args, &layout);
+    }
+    let mut targets: Vec<Target> = match (&args.targets, &args.scenario) {
+        (_, Some(path)) => Scenario::from_file(path)?.targets,
+        (Some(path), None) => load_targets(path)?,
+        (None, None) => unreachable!("clap requires --targets, --scenario or --case"),
+    };
+    targets.sort_by(|a, b| a.dist.total_cmp(&b.dist));
+    let config = layout_config(args);
+
+    if let Some(runs) = args.bench.filter(|&runs| runs > 0) {
+        let start = Instant::now();
+        for _ in 0..runs {
+            RingLayout::with_config(&targets, config);
+        }
+        let mean = start.elapsed() / runs;
+        eprintln!(
+            "{} targets: {:.3} ms per layout over {} runs",
+            targets.len(),
+            mean.as_secs_f64() * 1000.0,
+            runs
+        );
+    }
+
+    let layout = RingLayout::with_config(&targets, config);
+    if args.verify {
+        verify(&layout, &targets)?;
+        verify_order(&layout, &targets)?;
+    }
+    write_assignments(args, &layout)
+}
+
+fn write_assignments(args: &Args, layout: &RingLayout) -> Result<(), Box<dyn Error>> {
+    if let Some(path) = &args.svg {
+        let export = SvgExport {
+            display: &RadarDisplay::default(),
+            origins: &SensorOrigins::default(),
+            theme: Theme::default(),
+            text_scale: RingTextScale::default(),
+        };
+        let layouts = std::iter::once((0, layout.clone())).collect();
+        export.save(&layouts, path)?;
+    }
+    let json = serde_json::to_string_pretty(&assignments(layout))?;
+    match &args.output {
+        Some(path) => fs::write(path, json + "\n")?,
+        None => writeln!(io::stdout(), "{}", json)?,
+    }
+    Ok(())
+}
+
+fn main() {
+    init_logging();
+    let args = Args::parse();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -1,240 +1,740 @@
-use std::collections::BTreeMap;
-use std::f32::consts::{FRAC_1_SQRT_2, PI};
-use std::fmt;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 
 use bevy::prelude::*;
-use bevy::render::render_graph::base::MainPass;
-use bevy_prototype_lyon::prelude::*;
-use ordered_float::OrderedFloat;
-use rand::prelude::*;
+use bevy_debris::actions::ActionMap;
+use bevy_debris::aging::{Aging, AgingPlugin};
+use bevy_debris::alerts::AlertsPlugin;
+use bevy_debris::asset_check::AssetCheckPlugin;
+use bevy_debris::autolabel::{DesignatorPlugin, Designators, ReusePolicy};
+use bevy_debris::batch::shared_marker;
+use bevy_debris::camera::{CameraControlPlugin, CameraControls};
+use bevy_debris::capture::CapturePlugin;
+use bevy_debris::cli::{init_logging, parse_gain, parse_positive, DisplayArgs};
+use bevy_debris::clipboard::ClipboardPlugin;
+use bevy_debris::cluster::{SectorClusterPlugin, SectorClustering};
+use bevy_debris::config::{ConfigPlugin, DebrisConfig};
+use bevy_debris::constant_size::ConstantSizePlugin;
+use bevy_debris::coords::CoordsPlugin;
+use bevy_debris::culling::{Culling, CullingPlugin};
+use bevy_debris::curved_labels::CurvedLabelsPlugin;
+use bevy_debris::debug_overlay::DebugOverlayPlugin;
+use bevy_debris::demo::{demo_scenario, DemoPlugin};
+use bevy_debris::designation::{DesignationPlugin, HandoffSink};
+use bevy_debris::display::{LayoutCheck, LayoutTuningPlugin, PoiRingPlugin, RadarDisplay};
+use bevy_debris::easing::EasingConfig;
+use bevy_debris::edit::EditPlugin;
+use bevy_debris::emphasis::EmphasisPlugin;
+use bevy_debris::events::DisplayEventsPlugin;
+use bevy_debris::feed::{FeedSource, TargetFeedPlugin};
+use bevy_debris::frame::FramePlugin;
+use bevy_debris::io::load_targets;
+use bevy_debris::label_fit::{LabelFit, LabelOverflow};
+use bevy_debris::label_zoom::LabelZoomPlugin;
+use bevy_debris::layers::LayersPlugin;
+use bevy_debris::layout::{
+    AdaptiveSpacing, ForceDirected, LayoutBackend, LayoutConfig, PlacementMode, TieBreak,
+    DEFAULT_SCATTER,
+};
+use bevy_debris::leaks::{LeakCheck, LeakCheckPlugin};
+use bevy_debris::legend::LegendPlugin;
+use bevy_debris::links::{TargetLink, TargetLinksPlugin};
+use bevy_debris::lod::RingLodPlugin;
+use bevy_debris::measure::MeasurePlugin;
+use bevy_debris::minimap::{MinimapPlugin, MINIMAP_CAMERA};
+use bevy_debris::motion::TweenConfig;
+use bevy_debris::multi_select::MultiSelectPlugin;
+use bevy_debris::notes::NotesPlugin;
+use bevy_debris::origins::SensorOrigins;
+use bevy_debris::palette::Ramp;
+use bevy_debris::prediction::{Prediction, PredictionPlugin};
+use bevy_debris::range_rings::RangeRingsPlugin;
+use bevy_debris::replay::{FeedRecorderPlugin, FeedReplayPlugin, Recording};
+use bevy_debris::scale::RadialScale;
+use bevy_debris::scenario::{Preset, RandomTargets, Scenario};
+use bevy_debris::scene::{DisplayScene, ScenePlugin};
+use bevy_debris::selection::SelectionPlugin;
+use bevy_debris::simulation::SimulationPlugin;
+use bevy_debris::smoothing::SmoothingFilter;
+use bevy_debris::split_view::SplitViewPlugin;
+use bevy_debris::svg::SvgExportPlugin;
+use bevy_debris::sweep::{Sweep, SweepPlugin};
+use bevy_debris::target_list::TargetListPlugin;
+use bevy_debris::theme::RingTextScale;
+use bevy_debris::timeline::{Timeline, TimelinePlugin};
+use bevy_debris::tooltip::TooltipPlugin;
+use bevy_debris::trails::{TrailSettings, TrailsPlugin};
+use bevy_debris::tuning::TuningPlugin;
+use bevy_debris::undo::UndoPlugin;
+use bevy_debris::viewport::{DisplayViewport, ViewportPlugin};
+use bevy_debris::window_fit::{WindowFit, WindowFitPlugin};
+use bevy_debris::zones::AlertZonesPlugin;
+use clap::Parser;
 
-const POI_WIDTH: f32 = 30.0;
-
-fn main() {
-    App::build()
-        .add_plugins(DefaultPlugins)
-        .add_startup_system(setup.system())
-        .run();
+/// Declutter targets onto concentric rings around the origin.
+#[derive(Parser)]
+struct Args {
+    /// Scenario file (JSON) with the targets to show
+    #[arg(long, conflicts_with = "source")]
+    scenario: Option<PathBuf>,
+    /// Load targets from a path, file:// or http(s):// URL
+    #[arg(long)]
+    source: Option<String>,
+    /// Built-in scenario to show
+    #[arg(long, value_enum, conflicts_with_all = ["scenario", "source"])]
+    preset: Option<Preset>,
+    /// Target list (.json or .csv) with id, label, azimuth (degrees), distance and,
+    /// optionally, category and priority
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["scenario", "source", "preset"])]
+    targets: Option<PathBuf>,
+    /// Seed for presets and for the random demo targets used when no scenario is given;
+    /// a random one is picked and printed if not given
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Number of random demo targets
+    #[arg(long, default_value_t = RandomTargets::default().count)]
+    count: usize,
+    /// Nearest distance of the random demo targets
+    #[arg(long, default_value_t = RandomTargets::default().min_dist)]
+    min_dist: f32,
+    /// Farthest distance of the random demo targets
+    #[arg(long, default_value_t = RandomTargets::default().max_dist)]
+    max_dist: f32,
+    /// Gather the random demo targets around this many bearings instead of spreading
+    /// them all around
+    #[arg(long, default_value_t = 0)]
+    azimuth_clusters: usize,
+    /// Degrees the random demo targets stray either side of their cluster's bearing
+    #[arg(long, default_value_t = RandomTargets::default().spread.to_degrees())]
+    azimuth_spread: f32,
+    /// Seconds ahead to draw predicted positions of moving targets (0 to hide them)
+    #[arg(long, default_value_t = 10.0)]
+    horizon: f32,
+    /// Draw a line from each moving target's true position to where it will be at the
+    /// horizon
+    #[arg(long)]
+    prediction_vectors: bool,
+    /// Leave out the ghost markers at predicted positions
+    #[arg(long)]
+    no_ghosts: bool,
+    /// Send the designated target as JSON over UDP to this address whenever it changes
+    #[arg(long, value_name = "ADDR")]
+    handoff: Option<SocketAddr>,
+    /// Take live target updates from udp://ADDR or ws://ADDR (line-delimited JSON)
+    #[arg(long, value_name = "SOURCE")]
+    feed: Option<FeedSource>,
+    /// Read the layout, category styles and feeds from a RON or TOML file, and apply it
+    /// again whenever it changes; its layout takes the place of the layout flags
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Side length of a marker square
+    #[arg(long, default_value_t = 30.0, value_parser = parse_positive)]
+    poi_width: f32,
+    /// Distance between rings (defaults to twice the marker size)
+    #[arg(long, value_parser = parse_positive)]
+    ring_spacing: Option<f32>,
+    /// How much wider than a marker the gap between neighbours on a ring is
+    #[arg(long, default_value_t = DEFAULT_SCATTER, value_parser = parse_positive)]
+    scatter: f32,
+    /// Widen the gap below crowded rings so they hold more markers
+    #[arg(long)]
+    adaptive_spacing: bool,
+    /// Turn each ring slightly so markers on consecutive rings don't line up
+    #[arg(long)]
+    stagger: bool,
+    /// Which targets get the inner rings when they collide
+    #[arg(long, value_enum, default_value_t = PlacementMode::Nearest)]
+    placement: PlacementMode,
+    /// Which ring a target takes when several have room
+    #[arg(long, value_enum, default_value_t = TieBreak::Innermost)]
+    tie_break: TieBreak,
+    /// How distance maps onto the rings: linear, log:NEAR[,RATIO] or
+    /// breakpoints:DIST:RING,DIST:RING,...
+    #[arg(long, default_value_t = RadialScale::Linear)]
+    scale: RadialScale,
+    /// Let markers slide sideways off their azimuth to stay on inner rings
+    #[arg(long)]
+    force_directed: bool,
+    /// Bend leader lines around the markers of inner rings
+    #[arg(long)]
+    route_leaders: bool,
+    /// Smooth jittery azimuth and distance reports by an exponential moving average,
+    /// weighing each new report by this gain within (0, 1]
+    #[arg(long, value_name = "GAIN", value_parser = parse_gain)]
+    smoothing_alpha: Option<f32>,
+    /// Track the rate of change too, as an alpha-beta filter correcting it by this gain
+    /// within (0, 1]
+    #[arg(long, value_name = "GAIN", requires = "smoothing_alpha", value_parser = parse_gain)]
+    smoothing_beta: Option<f32>,
+    /// Share one mesh among markers of the same shape and draw all leader lines as a
+    /// few batched meshes, for thousands of targets
+    #[arg(long)]
+    batch: bool,
+    /// Confine the display to this part of the window, as X,Y,WIDTH,HEIGHT in pixels
+    /// from the bottom-left corner
+    #[arg(long, value_name = "RECT")]
+    viewport: Option<DisplayViewport>,
+    /// Frame the display in a bezel and mask everything outside the outermost ring
+    #[arg(long)]
+    bezel: bool,
+    /// Label the rings with their distance and draw bearing ticks and N/E/S/W
+    #[arg(long)]
+    range_rings: bool,
+    /// Smallest zoom the mouse wheel goes to, in pixels per distance unit
+    #[arg(long, default_value_t = 0.1)]
+    min_zoom: f32,
+    /// Largest zoom the mouse wheel goes to
+    #[arg(long, default_value_t = 10.0)]
+    max_zoom: f32,
+    /// Hide labels when zoomed out below this many pixels per distance unit
+    #[arg(long)]
+    label_min_zoom: Option<f32>,
+    /// Show an overview of all rings in the top right corner while zoomed in, outlining
+    /// the part in view; click in it to move there
+    #[arg(long)]
+    minimap: bool,
+    /// Zoom to fit the rings in the window, again on every resize
+    #[arg(long)]
+    fit_window: bool,
+    /// Size markers, rings and labels in physical pixels for the screen's scale factor,
+    /// so they are as large and as sharp on a HiDPI screen as on any other
+    #[arg(long)]
+    hidpi: bool,
+    /// Keep text at its design size on screen while zooming, re-rasterizing it at
+    /// power-of-two zoom steps
+    #[arg(long)]
+    zoom_labels: bool,
+    /// Keep markers and labels a fixed pixel size while zooming; only the rings scale
+    #[arg(long, conflicts_with = "zoom_labels")]
+    constant_size: bool,
+    /// Merge targets within this many degrees and --cluster-range of each other into
+    /// one marker with a count; click it to expand
+    #[arg(long, value_name = "DEG")]
+    cluster_angle: Option<f32>,
+    /// Distance window of --cluster-angle
+    #[arg(long, default_value_t = 10.0)]
+    cluster_range: f32,
+    /// Fewest targets merged by --cluster-angle
+    #[arg(long, default_value_t = 3)]
+    cluster_min: usize,
+    /// Most past positions drawn in each target's trail
+    #[arg(long, default_value_t = 16)]
+    trail_length: usize,
+    /// Seconds over which trail positions fade out; 0 keeps them until pushed out
+    #[arg(long, default_value_t = 30.0)]
+    trail_fade: f32,
+    /// Fewest seconds between trail positions
+    #[arg(long, default_value_t = 1.0)]
+    trail_interval: f32,
+    /// Color trails by age through this ramp instead of in their target's color
+    #[arg(long, value_enum)]
+    trail_ramp: Option<Ramp>,
+    /// Draw a radar sweep turning clockwise once every this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    sweep: Option<f32>,
+    /// Seconds swept targets take to fade back to normal
+    #[arg(long, default_value_t = Sweep::default().afterglow)]
+    afterglow: f32,
+    /// Fade out fed targets after this many seconds without a report
+    #[arg(long, value_name = "SECONDS")]
+    stale_after: Option<f32>,
+    /// Drop fed targets after this many seconds without a report
+    #[arg(long, default_value_t = Aging::default().timeout)]
+    stale_timeout: f32,
+    /// Label targets without a label A1, A2, ...; reuse released designators after
+    /// this many seconds, or never
+    #[arg(long, value_name = "never|SECONDS")]
+    designators: Option<ReusePolicy>,
+    /// Truncate or wrap labels wider than this, and space markers for their labels
+    #[arg(long, value_name = "WIDTH")]
+    fit_labels: Option<f32>,
+    /// What to do with labels wider than --fit-labels
+    #[arg(long, value_enum, requires = "fit_labels", default_value_t = LabelOverflow::Wrap)]
+    label_overflow: LabelOverflow,
+    /// Bend labels along their ring instead of writing them straight across
+    #[arg(long)]
+    curved_labels: bool,
+    /// Scale label text by this factor per ring outwards, between 0.6x and 1.5x; below 1
+    /// shrinks the labels of outer rings
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    label_ring_scale: f32,
+    /// Deal the targets out over this many sensor origins side by side, each with its
+    /// own rings; overrides the origins of the scenario
+    #[arg(long, value_name = "N")]
+    origins: Option<usize>,
+    /// Distance between the origins of --origins
+    #[arg(long, default_value_t = 1200.0)]
+    origin_spacing: f32,
+    /// Display scene (RON for .ron, JSON otherwise) to save with Ctrl+S; when it exists
+    /// its targets, slots, selection, layout config and origins are restored, overriding
+    /// the other options for them
+    #[arg(long, value_name = "FILE")]
+    scene: Option<PathBuf>,
+    /// Record every target event to this file, one JSON line each, for --replay
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+    /// Play back a --record file instead of showing a scenario; Space pauses, 1/2/3 set
+    /// 0.5x/1x/2x, Shift+Left/Right seek and Shift+Home restarts
+    #[arg(long, value_name = "FILE",
+          conflicts_with_all = ["scenario", "source", "preset", "targets", "scene"])]
+    replay: Option<PathBuf>,
+    /// Speed of --replay relative to how it was recorded
+    #[arg(long, requires = "replay", default_value_t = 1.0)]
+    replay_speed: f32,
+    /// Show only the targets timestamped within this many seconds before the position
+    /// of a timeline along the bottom, with a scrubber and play/pause; --replay plays
+    /// through it instead
+    #[arg(long, value_name = "SECS")]
+    timeline_window: Option<f64>,
+    /// Move targets along their course and speed between reports, in fixed steps of
+    /// this many seconds whatever the frame rate
+    #[arg(long, value_name = "SECONDS")]
+    simulation_step: Option<f32>,
+    /// Verify every layout change and save each one that fails into this directory as
+    /// a case to replay with `ring_layout --case`
+    #[arg(long, value_name = "DIR")]
+    dump_layout_failures: Option<PathBuf>,
+    /// Every SECS seconds, look for leader lines, labels and markers left behind by
+    /// removed targets and report them
+    #[arg(long, value_name = "SECS")]
+    leak_check: Option<f32>,
+    /// Clean up what --leak-check finds too
+    #[arg(long, requires = "leak_check")]
+    reconcile_leaks: bool,
+    /// Take a scripted tour instead of showing a scenario: --count clustered targets
+    /// close in from --max-dist, raising alerts, while the theme and scale change every
+    /// few seconds
+    #[arg(long, conflicts_with_all = ["scenario", "source", "preset", "targets", "replay", "scene"])]
+    demo: bool,
+    /// Show a sortable list of the targets at the top right of the window
+    #[arg(long)]
+    target_list: bool,
+    /// Show a legend of the category markers and a status bar with target and ring
+    /// counts and latencies
+    #[arg(long)]
+    legend: bool,
+    /// Show a panel at the top left for tuning the layout, leader lines, sweep and zoom
+    /// while running; T hides it
+    #[arg(long)]
+    tuning: bool,
+    /// Where Ctrl+E exports the targets as a scenario in editing mode, which E turns
+    /// on: click empty space to add a target, drag a marker to move one
+    #[arg(long, value_name = "FILE", default_value = "edited.json")]
+    export: PathBuf,
+    /// Where Ctrl+J exports the targets selected by Shift+drag or Ctrl+drag as a
+    /// scenario
+    #[arg(long, value_name = "FILE", default_value = "selection.json")]
+    selection_export: PathBuf,
+    /// Where F10 exports the layout as SVG
+    #[arg(long, value_name = "FILE", default_value = "layout.svg")]
+    svg: PathBuf,
+    /// Where F11 and --timelapse save PNG captures of the window
+    #[arg(long, value_name = "DIR", default_value = "captures")]
+    capture_dir: PathBuf,
+    /// Also capture a PNG every N frames
+    #[arg(long, value_name = "N")]
+    timelapse: Option<u32>,
+    #[command(flatten)]
+    display: DisplayArgs,
 }
 
-fn setup(
-    mut commands: Commands,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-) {
-    let material = materials.add(Color::rgb(0.8, 0.0, 0.0).into());
-    let font = asset_server.load("arial.ttf");
-
-    let cmd = commands
-        .spawn(Camera2dComponents::default())
-        .spawn(origin(material.clone(), &mut meshes));
-
-    let mut targets = test_data(20);
-    targets.sort_unstable_by(|a, b| a.dist.partial_cmp(&b.dist).unwrap());
-    let rings = arrange_targets(&targets, POI_WIDTH);
-    for (ring_ord, ring) in rings.iter().enumerate() {
-        cmd.spawn(ref_ring(material.clone(), &mut meshes, POI_WIDTH, ring_ord));
-        for (azi, target) in ring {
-            let r = ring_radius(POI_WIDTH, ring_ord);
-            let trans = Vec3::new(r * azi.cos(), r * azi.sin(), 0.0);
-            let (line, poi, text) = poi(
-                material.clone(),
-                &mut meshes,
-                trans,
-                font.clone(),
-                target.text.clone(),
-            );
-            cmd.spawn(line).spawn(poi).spawn(text).with(MainPass);
+fn main() {
+    init_logging();
+    let args = Args::parse();
+    let restored = args.display.restore_session();
+    let scenario: Result<Scenario, Box<dyn Error>> = match (
+        &restored,
+        &args.scenario,
+        &args.source,
+        args.preset,
+        &args.targets,
+    ) {
+        (Some(state), ..) => Ok(state.scenario.clone()),
+        (None, Some(path), ..) => Scenario::from_file(path).map_err(Into::into),
+        (None, None, Some(source), ..) => Scenario::from_source(source).map_err(Into::into),
+        (None, None, None, Some(preset), _) => Ok(preset.build(args.seed.unwrap_or(0))),
+        (None, None, None, None, Some(path)) => load_targets(path)
+            .map(|targets| Scenario {
+                targets,
+                ..Default::default()
+            })
+            .map_err(Into::into),
+        (None, None, None, None, None) => {
+            let seed = args.seed.unwrap_or_else(|| {
+                let seed = rand::random();
+                println!("random targets from --seed {}", seed);
+                seed
+            });
+            let targets = RandomTargets {
+                count: args.count,
+                min_dist: args.min_dist,
+                max_dist: args.max_dist,
+                clusters: args.azimuth_clusters,
+                spread: args.azimuth_spread.to_radians(),
+            };
+            Ok(Scenario {
+                targets: targets.generate(seed),
+                ..Default::default()
+            })
+        }
+    };
+    let mut scenario = match scenario {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
         }
+    };
+    if args.demo {
+        let seed = args.seed.unwrap_or(0);
+        scenario = demo_scenario(seed, args.count, args.min_dist, args.max_dist);
     }
-}
-
-fn origin(
-    material: Handle<ColorMaterial>,
-    meshes: &mut ResMut<'_, Assets<Mesh>>,
-) -> SpriteComponents {
-    primitive(
-        material,
-        meshes,
-        ShapeType::Circle(5.0),
-        TessellationMode::Fill(&FillOptions::default()),
-        Vec3::new(0.0, 0.0, 0.0).into(),
-    )
-}
-
-fn ref_ring(
-    material: Handle<ColorMaterial>,
-    meshes: &mut ResMut<'_, Assets<Mesh>>,
-    poi_width: f32,
-    ring_ord: usize,
-) -> SpriteComponents {
-    let r = ring_radius(poi_width, ring_ord);
-    primitive(
-        material,
-        meshes,
-        ShapeType::Circle(r),
-        TessellationMode::Stroke(&StrokeOptions::default()),
-        Vec3::new(0.0, 0.0, 0.0).into(),
-    )
-}
-
-fn poi(
-    material: Handle<ColorMaterial>,
-    meshes: &mut ResMut<'_, Assets<Mesh>>,
-    translation: Vec3,
-    font: Handle<Font>,
-    text: String,
-) -> (SpriteComponents, SpriteComponents, TextComponents) {
-    let square = primitive(
-        material.clone(),
-        meshes,
-        ShapeType::Rectangle {
-            width: POI_WIDTH,
-            height: POI_WIDTH,
-        },
-        TessellationMode::Stroke(&StrokeOptions::default()),
-        translation - Vec3::new(POI_WIDTH / 2.0, POI_WIDTH / 2.0, 0.0),
-    );
-    let line = primitive(
-        material,
-        meshes,
-        ShapeType::Polyline {
-            points: vec![point(0.0, 0.0), point(translation.x(), translation.y())],
-            closed: false,
-        },
-        TessellationMode::Stroke(&StrokeOptions::default()),
-        Vec3::new(0.0, 0.0, 0.0),
-    );
-    let textc = TextComponents {
-        //style: Style {
-        //    margin: Rect::all(Val::Px(1.0)),
-        //    ..Default::default()
-        //},
-        text: Text {
-            value: text,
-            font,
-            style: TextStyle {
-                font_size: 20.0,
-                color: Color::WHITE,
-            },
+    let replay = args
+        .replay
+        .as_ref()
+        .map(|path| match Recording::load(path) {
+            Ok(recording) => recording,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        });
+    if replay.is_some() {
+        scenario.targets.clear();
+    }
+    let scene = match &args.scene {
+        Some(path) if path.exists() => match DisplayScene::load(path) {
+            Ok(scene) => Some(scene),
+            Err(e) => {
+                eprintln!("error: failed to load scene {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
         },
-        transform: Transform::from_translation(translation),
-        ..Default::default()
+        _ => None,
     };
-    (line, square, textc)
-}
-
-fn test_data(num: usize) -> Vec<Target> {
-    let mut rng = rand::thread_rng();
-    (0..num)
-        .map(|id| {
-            let text = format!("{}", id);
-            Target {
-                id: id as i32,
-                text,
-                azimuth: rng.gen_range(0.0, PI * 2.0),
-                dist: rng.gen_range(10.0, 100.0),
+    let origins = match args.origins {
+        Some(count) => {
+            let count = count.max(1);
+            for (i, target) in scenario.targets.iter_mut().enumerate() {
+                target.origin = i % count;
             }
-        })
-        .collect()
-}
+            SensorOrigins::in_a_row(count, args.origin_spacing)
+        }
+        None if !scenario.origins.is_empty() => SensorOrigins(scenario.origins.clone()),
+        None => SensorOrigins::default(),
+    };
+    let file_config = args
+        .config
+        .as_ref()
+        .map(|path| match DebrisConfig::load(path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        });
+    let (config, origins) = match (&scene, &file_config) {
+        (Some(scene), _) => {
+            scenario.targets = scene.targets.clone();
+            (scene.config, scene.sensor_origins())
+        }
+        (None, Some(file_config)) => (file_config.layout, origins),
+        (None, None) => (layout_config(&args), origins),
+    };
 
-#[derive(Clone)]
-struct Target {
-    pub id: i32,
-    pub text: String,
-    pub azimuth: f32,
-    pub dist: f32,
-}
+    let easing = file_config
+        .as_ref()
+        .map_or_else(EasingConfig::default, |c| c.easing);
 
-impl fmt::Debug for Target {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Target")
-            .field("id", &self.id)
-            .field("text", &self.text)
-            .field("azimuth(deg)", &self.azimuth.to_degrees())
-            .field("(rad)", &self.azimuth)
-            .field("dist", &self.dist)
-            .finish()
+    let alerts = AlertsPlugin {
+        rules: scenario.alerts.clone(),
+    };
+    let mut display = RadarDisplay::default();
+    if args.batch {
+        display.set_marker_factory(shared_marker);
     }
-}
-
-fn arrange_targets(targets: &[Target], poi_width: f32) -> Vec<BTreeMap<OrderedFloat<f32>, Target>> {
-    let mut rings = Vec::new();
-    targets.iter().for_each(|t| {
-        println!("{:?}", t);
-        let mut ring_ord = 0;
-        loop {
-            let min_azi = min_angle(poi_width, ring_ord);
-            println!(
-                "\tring {}, min_azi(deg|rad): {}|{}",
-                ring_ord,
-                min_azi.to_degrees(),
-                min_azi
-            );
-            if rings.len() == ring_ord {
-                rings.push(BTreeMap::<OrderedFloat<f32>, Target>::new());
-            }
-            let ring = &mut rings[ring_ord];
-            if ring.len() > 0 {
-                let mut nearest = ring.range(OrderedFloat(t.azimuth)..);
-                if let Some((azi, _)) = nearest.next() {
-                    if **azi - t.azimuth < min_azi {
-                        println!(
-                            "\t\tnearest ge azimuth(deg|rad): {}|{}, overlap",
-                            azi.to_degrees(),
-                            azi
-                        );
-                        ring_ord += 1;
-                        continue;
-                    }
-                } else if **ring.keys().next().unwrap() + PI * 2.0 - t.azimuth < min_azi {
-                    let azi = ring.keys().next().unwrap();
-                    println!(
-                        "\t\tminimum azimuth(deg|rad): {}|{}, overlap",
-                        azi.to_degrees(),
-                        azi
-                    );
-                    ring_ord += 1;
-                    continue;
-                }
-                let mut nearest = ring.range(..OrderedFloat(t.azimuth));
-                if let Some((azi, _)) = nearest.next_back() {
-                    if t.azimuth - **azi < min_azi {
-                        println!(
-                            "\t\tnearest lt azimuth(deg|rad): {}|{}, overlap",
-                            azi.to_degrees(),
-                            azi
-                        );
-                        ring_ord += 1;
-                        continue;
-                    }
-                } else if t.azimuth + PI * 2.0 - **ring.keys().next_back().unwrap() < min_azi {
-                    let azi = ring.keys().next_back().unwrap();
-                    println!(
-                        "\t\tmaximum azimuth(deg|rad): {}|{}, overlap",
-                        azi.to_degrees(),
-                        azi
-                    );
-                    ring_ord += 1;
-                    continue;
-                }
-            }
-            println!("\t\tno overlap, insert");
-            ring.insert(OrderedFloat(t.azimuth), t.clone());
-            break;
+    if let (Some(path), Some(file_config)) = (&args.config, &file_config) {
+        let path = path.display().to_string();
+        if let Err(e) = file_config.apply_styles(&path, display.categories_mut()) {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
         }
+    }
+    let actions = args
+        .display
+        .action_map(ActionMap::default())
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        });
+    let mut app = App::build();
+    app.add_resource(args.display.window_descriptor("square ring"))
+        .add_resource(display)
+        .add_resource(origins)
+        .add_resource(ClearColor(args.display.theme.background()))
+        .add_resource(args.display.theme)
+        .add_resource(RingTextScale {
+            per_ring: args.label_ring_scale,
+            ..Default::default()
+        })
+        .add_resource(scenario)
+        .add_resource(Viewport(args.viewport))
+        .add_resource(TweenConfig {
+            easing: easing.tween,
+            ..Default::default()
+        })
+        .add_resource(LayoutCheck {
+            dump_dir: args.dump_layout_failures.clone(),
+        })
+        .add_resource(actions)
+        .add_plugins(DefaultPlugins)
+        .add_plugin(AssetCheckPlugin {
+            textures: Vec::new(),
+            ..Default::default()
+        })
+        .add_plugin(args.display.metrics_plugin())
+        .add_plugin(PoiRingPlugin {
+            config,
+            label_fit: args.fit_labels.map(|max_width| LabelFit {
+                max_width,
+                overflow: args.label_overflow,
+            }),
+            ..Default::default()
+        })
+        .add_plugin(LayoutTuningPlugin)
+        .add_plugin(DisplayEventsPlugin)
+        .add_resource(CameraControls {
+            min_zoom: args.min_zoom,
+            max_zoom: args.max_zoom,
+            ..Default::default()
+        })
+        .add_plugin(CameraControlPlugin)
+        .add_plugin(DesignationPlugin {
+            sink: args.handoff.map(HandoffSink::Udp),
+        })
+        .add_plugin(LayersPlugin)
+        .add_plugin(EmphasisPlugin)
+        .add_plugin(SelectionPlugin)
+        .add_plugin(MultiSelectPlugin {
+            export: args.selection_export.clone(),
+        })
+        .add_plugin(TooltipPlugin)
+        .add_plugin(DebugOverlayPlugin)
+        .add_resource(Culling {
+            label_max_scale: args.label_min_zoom.map(|zoom| 1.0 / zoom),
+            ..Default::default()
+        })
+        .add_plugin(CullingPlugin)
+        .add_plugin(alerts)
+        .add_plugin(AlertZonesPlugin {
+            zones: file_config
+                .as_ref()
+                .map_or_else(Vec::new, |config| config.zones.clone()),
+        })
+        .add_plugin(RingLodPlugin)
+        .add_resource(Prediction {
+            horizon: args.horizon,
+            vectors: args.prediction_vectors,
+            ghosts: !args.no_ghosts,
+        })
+        .add_plugin(PredictionPlugin)
+        .add_resource(TrailSettings {
+            length: args.trail_length,
+            fade: args.trail_fade,
+            interval: args.trail_interval,
+            easing: easing.trail,
+            ramp: args.trail_ramp,
+        })
+        .add_plugin(TrailsPlugin)
+        .add_plugin(NotesPlugin)
+        .add_plugin(CoordsPlugin)
+        .add_plugin(ClipboardPlugin)
+        .add_plugin(TargetLinksPlugin)
+        .add_plugin(MeasurePlugin)
+        .add_plugin(EditPlugin {
+            export: args.export.clone(),
+        })
+        .add_plugin(UndoPlugin)
+        .add_plugin(SvgExportPlugin {
+            path: args.svg.clone(),
+        })
+        .add_startup_system(setup.system());
+    if let Some(source) = args.feed {
+        app.add_plugin(TargetFeedPlugin { source });
+    }
+    if let (Some(path), Some(file_config)) = (args.config.clone(), file_config) {
+        for &source in &file_config.feeds {
+            app.add_plugin(TargetFeedPlugin { source });
+        }
+        app.add_plugin(ConfigPlugin { path, poll: 1.0 });
+    }
+    if args.target_list {
+        app.add_plugin(TargetListPlugin);
+    }
+    if args.legend {
+        app.add_plugin(LegendPlugin);
+    }
+    if args.tuning {
+        app.add_plugin(TuningPlugin);
+    }
+    if let Some(interval) = args.leak_check {
+        app.add_resource(LeakCheck::new(interval, args.reconcile_leaks))
+            .add_plugin(LeakCheckPlugin);
+    }
+    if args.viewport.is_some() {
+        app.add_plugin(ViewportPlugin);
+    }
+    if args.bezel {
+        app.add_plugin(FramePlugin);
+    }
+    if args.range_rings {
+        app.add_plugin(RangeRingsPlugin);
+    }
+    if args.minimap {
+        app.add_plugin(SplitViewPlugin {
+            cameras: vec![MINIMAP_CAMERA.to_string()],
+        })
+        .add_plugin(MinimapPlugin);
+    }
+    if args.fit_window || args.hidpi {
+        app.add_resource(WindowFit {
+            fit_rings: args.fit_window,
+            physical_pixels: args.hidpi,
+            ..Default::default()
+        })
+        .add_plugin(WindowFitPlugin);
+    }
+    if args.curved_labels {
+        app.add_plugin(CurvedLabelsPlugin);
+    }
+    if args.zoom_labels {
+        app.add_plugin(LabelZoomPlugin);
+    }
+    if args.constant_size {
+        app.add_plugin(ConstantSizePlugin);
+    }
+    if let Some(angle) = args.cluster_angle {
+        app.add_resource(SectorClustering {
+            angle: angle.to_radians(),
+            range: args.cluster_range,
+            min_size: args.cluster_min,
+        })
+        .add_plugin(SectorClusterPlugin);
+    }
+    if let Some(period) = args.sweep {
+        app.add_resource(Sweep {
+            speed: -PI * 2.0 / period,
+            afterglow: args.afterglow,
+            easing: easing.afterglow,
+        })
+        .add_plugin(SweepPlugin);
+    }
+    if let Some(fade_after) = args.stale_after {
+        app.add_resource(Aging {
+            fade_after,
+            timeout: args.stale_timeout,
+            easing: easing.fade,
+            ..Default::default()
+        })
+        .add_plugin(AgingPlugin);
+    }
+    if let Some(policy) = args.designators {
+        app.add_resource(Designators::new(policy))
+            .add_plugin(DesignatorPlugin);
+    }
+    if args.demo {
+        app.add_plugin(DemoPlugin {
+            min_dist: args.min_dist,
+            max_dist: args.max_dist,
+            ..Default::default()
+        });
+    }
+    if let Some(path) = args.record {
+        app.add_plugin(FeedRecorderPlugin { path });
+    }
+    match (replay, args.timeline_window) {
+        (Some(recording), Some(window)) => {
+            let mut timeline = Timeline::from_recording(&recording, window);
+            timeline.speed = args.replay_speed;
+            app.add_resource(timeline).add_plugin(TimelinePlugin);
+        }
+        (Some(recording), None) => {
+            app.add_plugin(FeedReplayPlugin {
+                recording,
+                speed: args.replay_speed,
+            });
+        }
+        (None, Some(window)) => {
+            app.add_resource(Timeline::new(window))
+                .add_plugin(TimelinePlugin);
+        }
+        (None, None) => {}
+    }
+    if let Some(step) = args.simulation_step {
+        app.add_plugin(SimulationPlugin { step });
+    }
+    if let Some(path) = args.scene {
+        app.add_plugin(ScenePlugin {
+            path,
+            restored: scene,
+        });
+    }
+    if let Some(persist) = args.display.persist_plugin(restored) {
+        app.add_plugin(persist);
+    }
+    #[cfg(feature = "web")]
+    app.add_plugin(bevy_debris::web::WebPlugin);
+    // Last, once every pass drawing into the window is in the render graph.
+    app.add_plugin(CapturePlugin {
+        dir: args.capture_dir.clone(),
+        every: args.timelapse,
     });
-    rings
+    app.run();
 }
 
-fn ring_radius(poi_width: f32, ring_ord: usize) -> f32 {
-    (ring_ord + 1) as f32 * poi_width * 2.0
+fn layout_config(args: &Args) -> LayoutConfig {
+    let defaults = LayoutConfig::new(args.poi_width);
+    LayoutConfig {
+        ring_spacing: args.ring_spacing.unwrap_or(defaults.ring_spacing),
+        scatter: args.scatter,
+        mode: args.placement,
+        tie_break: args.tie_break,
+        scale: args.scale,
+        adaptive: args.adaptive_spacing.then(AdaptiveSpacing::default),
+        stagger: args.stagger,
+        leader_routing: args.route_leaders,
+        batch_leaders: args.batch,
+        smoothing: args.smoothing_alpha.map(|alpha| match args.smoothing_beta {
+            Some(beta) => SmoothingFilter::AlphaBeta { alpha, beta },
+            None => SmoothingFilter::Ema { alpha },
+        }),
+        backend: if args.force_directed {
+            LayoutBackend::ForceDirected(ForceDirected::default())
+        } else {
+            LayoutBackend::Greedy
+        },
+        ..defaults
+    }
 }
 
-fn min_angle(poi_width: f32, ring_ord: usize) -> f32 {
-    const SCATTER_COEF: f32 = 1.2;
-    let r = ring_radius(poi_width, ring_ord);
-    (poi_width * FRAC_1_SQRT_2 / r).asin() * 2.0 * SCATTER_COEF
+/// The `--viewport` option, for `setup` to put on the camera.
+struct Viewport(Option<DisplayViewport>);
+
+fn setup(mut commands: Commands, scenario: Res<Scenario>, viewport: Res<Viewport>) {
+    commands.spawn(Camera2dComponents::default());
+    if let Some(viewport) = viewport.0 {
+        commands.with(viewport);
+    }
+    commands.spawn(UiCameraComponents::default());
+    for target in &scenario.targets {
+        commands.spawn((target.clone(),));
+        if let Some(geo) = scenario.geo.iter().find(|geo| geo.id == target.id) {
+            commands.with(geo.clone());
+        }
+    }
+    for &[from, to] in &scenario.links {
+        commands.spawn((TargetLink::new(from, to),));
+    }
 }
@@ -0,0 +1,358 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::camera::OrbitCamera;
+use crate::geo::{Cartesian3, LatLon};
+use crate::impostor::Impostor;
+use crate::mesh::{annulus_mesh, sphere_mesh};
+use crate::occlusion::Occluder;
+
+/// Latitude and longitude segments of a body's sphere.
+const BODY_SEGMENTS: (u32, u32) = (32, 64);
+/// Segments around a body's rings.
+const RING_SEGMENTS: u32 = 128;
+
+/// A circular orbit about the parent body, in the parent's frame: in its xy plane,
+/// tilted about x by `inclination`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Orbit {
+    /// From the parent's center, in scene units.
+    pub distance: f32,
+    /// Seconds per revolution; 0 keeps the body still at `phase`.
+    pub period: f32,
+    /// Degrees.
+    pub inclination: f32,
+    /// Degrees along the orbit at startup, from the parent's +x.
+    pub phase: f32,
+}
+
+impl Orbit {
+    /// Where the body is `seconds` after startup, relative to its parent.
+    pub fn position(&self, seconds: f64) -> Vec3 {
+        let turns = if self.period > 0.0 {
+            (seconds / self.period as f64).fract() as f32
+        } else {
+            0.0
+        };
+        let angle = self.phase.to_radians() + turns * PI * 2.0;
+        let flat = Vec3::new(angle.cos(), angle.sin(), 0.0) * self.distance;
+        Quat::from_rotation_x(self.inclination.to_radians()) * flat
+    }
+}
+
+/// A sphere of the globe view besides the main globe, such as a moon, as listed in
+/// [`Scenario::bodies`](crate::scenario::Scenario::bodies).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BodyConfig {
+    pub name: String,
+    /// In scene units; the main globe's is 2.
+    pub radius: f32,
+    /// Equirectangular texture relative to the assets directory; plain `color` if
+    /// unset.
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// Surface color, or the tint of `texture`, as linear RGB.
+    #[serde(default = "white")]
+    pub color: [f32; 3],
+    /// Name of an earlier body this one orbits; the main globe at the origin if unset.
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub orbit: Orbit,
+    /// Seconds per turn about its own axis; 0 for none.
+    #[serde(default)]
+    pub day: f32,
+    /// Degrees the body's axis leans from its orbit's, about x.
+    #[serde(default)]
+    pub tilt: f32,
+    #[serde(default)]
+    pub rings: Option<PlanetRings>,
+}
+
+fn white() -> [f32; 3] {
+    [1.0; 3]
+}
+
+/// A flat ring system around a body, like Saturn's, drawn with [`annulus_mesh`]. It
+/// stays put while the body spins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanetRings {
+    /// Radii of the ring's edges, in scene units.
+    pub inner: f32,
+    pub outer: f32,
+    /// Degrees the ring plane leans from the body's orbit plane, about x; usually the
+    /// body's own tilt, so the rings circle its equator.
+    #[serde(default)]
+    pub tilt: f32,
+    /// Texture relative to the assets directory, its left edge drawn at the inner
+    /// radius and its right edge at the outer one, so a strip one texel high will do.
+    /// Its alpha sets how see-through the rings are.
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// Color, or the tint of `texture`, as linear RGBA.
+    #[serde(default = "ring_color")]
+    pub color: [f32; 4],
+}
+
+fn ring_color() -> [f32; 4] {
+    [0.8, 0.75, 0.65, 0.6]
+}
+
+impl BodyConfig {
+    /// A grey moon a little over a quarter the main globe's size, orbiting it once a
+    /// minute.
+    pub fn moon() -> Self {
+        BodyConfig {
+            name: "moon".to_string(),
+            radius: 0.55,
+            texture: None,
+            color: [0.55, 0.55, 0.52],
+            parent: None,
+            orbit: Orbit {
+                distance: 12.0,
+                period: 60.0,
+                inclination: 5.0,
+                phase: 0.0,
+            },
+            day: 60.0,
+            tilt: 0.0,
+            rings: None,
+        }
+    }
+}
+
+/// The bodies [`BodiesPlugin`] spawns at startup.
+#[derive(Debug, Clone, Default)]
+pub struct Bodies(pub Vec<BodyConfig>);
+
+/// On the frame of every body the camera can focus: an entity at the body's center,
+/// not turning with its surface, that the frames of the bodies orbiting it are
+/// children of. The main globe carries it too, and the surface is the frame itself
+/// there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Body {
+    pub name: String,
+    pub radius: f32,
+}
+
+/// Moves a body's frame along its orbit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Orbiting(pub Orbit);
+
+/// Turns a body's surface about its axis, a child of its frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spin {
+    pub day: f32,
+    pub tilt: f32,
+}
+
+/// The body the camera looks at and follows. [`Action::FocusNextBody`], Tab by
+/// default, cycles through the [`Body`] entities in the order they were spawned.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BodyFocus {
+    /// The origin, where the main globe sits, when `None`.
+    pub body: Option<Entity>,
+    /// The focused body's center as of the last frame, which the camera keeps its
+    /// distance from.
+    pub center: Vec3,
+    /// The focused body's radius, 0 until one is focused.
+    pub radius: f32,
+}
+
+/// Spawns the [`Bodies`] with their orbits about each other, moves them along by
+/// [`AnimationTime`], and keeps the 3D camera on the [`BodyFocus`], or an
+/// [`OrbitCamera`]'s focus on it. Each body gets an [`Impostor`], so
+/// [`ImpostorPlugin`](crate::impostor::ImpostorPlugin) draws distant ones cheaply, and
+/// is an [`Occluder`] for annotations behind it.
+pub struct BodiesPlugin;
+
+impl Plugin for BodiesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<Bodies>() {
+            app.init_resource::<Bodies>();
+        }
+        app.add_plugin(AnimationTimePlugin)
+            .init_resource::<BodyFocus>()
+            .add_startup_system(spawn_bodies.system())
+            .add_system(orbit_system.system())
+            .add_system(spin_system.system())
+            .add_system(focus_system.system());
+    }
+}
+
+fn spawn_bodies(
+    mut commands: Commands,
+    bodies: Res<Bodies>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut frames: Vec<(&str, Entity)> = Vec::new();
+    for config in &bodies.0 {
+        let parent = match &config.parent {
+            Some(name) => match frames.iter().find(|(frame, _)| frame == name) {
+                Some(&(_, parent)) => Some(parent),
+                None => {
+                    tracing::warn!(
+                        "body {:?} orbits {:?}, which is not listed before it; orbiting the globe instead",
+                        config.name, name
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        let [r, g, b] = config.color;
+        let material = materials.add(StandardMaterial {
+            albedo: Color::rgb(r, g, b),
+            albedo_texture: config
+                .texture
+                .as_ref()
+                .map(|path| asset_server.load(path.as_str())),
+            ..Default::default()
+        });
+        let (lat, lon) = BODY_SEGMENTS;
+        let mesh = meshes.add(sphere_mesh(config.radius, lat, lon));
+        let rings = config.rings.as_ref().map(|rings| {
+            let [r, g, b, a] = rings.color;
+            let material = materials.add(StandardMaterial {
+                albedo: Color::rgba(r, g, b, a),
+                albedo_texture: rings
+                    .texture
+                    .as_ref()
+                    .map(|path| asset_server.load(path.as_str())),
+                ..Default::default()
+            });
+            PbrComponents {
+                mesh: meshes.add(annulus_mesh(rings.inner, rings.outer, RING_SEGMENTS)),
+                material,
+                transform: Transform::from_rotation(Quat::from_rotation_x(rings.tilt.to_radians())),
+                draw: Draw {
+                    is_transparent: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        });
+        let frame = commands
+            .spawn((
+                Transform::from_translation(config.orbit.position(0.0)),
+                GlobalTransform::default(),
+                Body {
+                    name: config.name.clone(),
+                    radius: config.radius,
+                },
+                Orbiting(config.orbit),
+            ))
+            .with_children(|frame| {
+                frame
+                    .spawn(PbrComponents {
+                        mesh,
+                        material,
+                        ..Default::default()
+                    })
+                    .with(Spin {
+                        day: config.day,
+                        tilt: config.tilt,
+                    })
+                    .with(Impostor {
+                        radius: config.radius,
+                    })
+                    .with(Occluder {
+                        radius: config.radius,
+                    });
+                if let Some(rings) = rings {
+                    frame.spawn(rings);
+                }
+            })
+            .current_entity();
+        if let Some(frame) = frame {
+            if let Some(parent) = parent {
+                commands.push_children(parent, &[frame]);
+            }
+            frames.push((&config.name, frame));
+        }
+    }
+}
+
+fn orbit_system(time: Res<AnimationTime>, mut frames: Query<(&Orbiting, Mut<Transform>)>) {
+    for (orbiting, mut transform) in frames.iter_mut() {
+        transform.translation = orbiting.0.position(time.seconds());
+    }
+}
+
+fn spin_system(time: Res<AnimationTime>, mut surfaces: Query<(&Spin, Mut<Transform>)>) {
+    for (spin, mut transform) in surfaces.iter_mut() {
+        let turns = if spin.day > 0.0 {
+            (time.seconds() / spin.day as f64).fract() as f32
+        } else {
+            0.0
+        };
+        transform.rotation =
+            Quat::from_rotation_x(spin.tilt.to_radians()) * Quat::from_rotation_z(turns * PI * 2.0);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn focus_system(
+    actions: Res<ActionState>,
+    mut focus: ResMut<BodyFocus>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+    mut cameras: Query<(&Camera, Mut<Transform>, Option<Mut<OrbitCamera>>)>,
+) {
+    if actions.just_pressed(Action::FocusNextBody) {
+        let mut order = bodies.iter().map(|(entity, ..)| entity).collect::<Vec<_>>();
+        order.sort_by_key(|entity| entity.id());
+        let next = match focus
+            .body
+            .and_then(|body| order.iter().position(|&e| e == body))
+        {
+            Some(at) => order.get(at + 1).copied(),
+            None => order.first().copied(),
+        };
+        focus.body = next;
+    }
+    let (center, radius) = match focus.body.and_then(|body| bodies.get(body).ok()) {
+        Some((_, body, transform)) => (transform.translation, body.radius),
+        None => (Vec3::zero(), 0.0),
+    };
+    let moved = center - focus.center;
+    if moved == Vec3::zero() && radius == focus.radius {
+        return;
+    }
+    focus.center = center;
+    focus.radius = radius;
+    for (camera, mut transform, orbit) in cameras.iter_mut() {
+        if camera.name.as_deref() != Some(CAMERA3D) {
+            continue;
+        }
+        if let Some(mut orbit) = orbit {
+            orbit.focus = center;
+            continue;
+        }
+        let up = transform.rotation * Vec3::unit_y();
+        transform.translation += moved;
+        transform.look_at(center, up);
+    }
+}
+
+/// Position of a geodetic coordinate (degrees) in the frame of [`sphere_mesh`], which
+/// puts longitude 0 at the texture center and the north pole (texture top) towards -z.
+pub fn geo_to_local(lat: f32, lon: f32, radius: f32) -> Vec3 {
+    LatLon::new(lat, lon).to_cartesian(radius).into()
+}
+
+/// The geodetic coordinate (degrees) of a point in the frame of [`sphere_mesh`], the
+/// inverse of [`geo_to_local`] at any radius.
+pub fn local_to_geo(local: Vec3) -> (f32, f32) {
+    let LatLon { lat, lon } = Cartesian3(local).into();
+    (lat, lon)
+}
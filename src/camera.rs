@@ -0,0 +1,989 @@
+use std::collections::HashSet;
+use std::f32::consts::PI;
+
+use bevy::input::gamepad::{Gamepad, GamepadAxis, GamepadAxisType, GamepadEvent, GamepadEventType};
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::input::touch::Touches;
+use bevy::input::Axis;
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::{CAMERA2D, CAMERA3D};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::bodies::{geo_to_local, Body};
+use crate::easing::{Easing, EasingConfig};
+use crate::events::DisplayEvent;
+use crate::gesture::{add_gestures, Gesture};
+use crate::pointer::{screen_to_world, CursorPosition};
+use crate::split_view::{view_under_cursor, ViewRect};
+
+/// Pixels of a pixel-based scroll that count as one wheel notch.
+const PIXELS_PER_LINE: f32 = 100.0;
+/// Seconds an [`AutoSpin`] takes to come up to speed once it picks up again.
+const AUTO_SPIN_RAMP: f32 = 1.0;
+
+/// Limits and speed of [`CameraControlPlugin`]. Zoom is pixels per world unit, so 1
+/// is the unzoomed view and 2 shows everything twice as large.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraControls {
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// Zoom factor of one wheel notch.
+    pub zoom_step: f32,
+}
+
+impl Default for CameraControls {
+    fn default() -> Self {
+        CameraControls {
+            min_zoom: 0.1,
+            max_zoom: 10.0,
+            zoom_step: 1.2,
+        }
+    }
+}
+
+impl CameraControls {
+    /// The camera scale after `notches` wheel notches at scale `scale`, within the
+    /// zoom limits.
+    pub fn zoomed_scale(&self, scale: f32, notches: f32) -> f32 {
+        let zoom = (self.zoom_step.powf(notches) / scale)
+            .max(self.min_zoom)
+            .min(self.max_zoom);
+        1.0 / zoom
+    }
+}
+
+/// Zooms the 2D camera with the mouse wheel, keeping the point under the cursor in
+/// place, and pans it by dragging with [`Action::Pan`] held, the middle button unless
+/// rebound. On a touch screen a finger
+/// drag pans and a pinch zooms about the fingers, see [`Gesture`]. Sends
+/// [`DisplayEvent::ViewChanged`] on every change. Needs
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin) for the cursor position.
+pub struct CameraControlPlugin;
+
+impl Plugin for CameraControlPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<CameraControls>() {
+            app.init_resource::<CameraControls>();
+        }
+        add_gestures(app);
+        add_actions(app);
+        app.add_system(camera_control_system.system());
+    }
+}
+
+#[derive(Default)]
+struct ControlState {
+    wheel: EventReader<MouseWheel>,
+    gestures: EventReader<Gesture>,
+    /// Cursor position at the last frame of a pan.
+    drag: Option<Vec2>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn camera_control_system(
+    mut state: Local<ControlState>,
+    controls: Res<CameraControls>,
+    (cursor, windows): (Res<CursorPosition>, Res<Windows>),
+    actions: Res<ActionState>,
+    (wheel_events, gesture_events): (Res<Events<MouseWheel>>, Res<Events<Gesture>>),
+    mut display_events: ResMut<Events<DisplayEvent>>,
+    mut cameras: Query<(&Camera, Mut<Transform>)>,
+) {
+    let mut notches = state
+        .wheel
+        .iter(&wheel_events)
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum::<f32>();
+    let mut drag = match (actions.pressed(Action::Pan), cursor.screen) {
+        (true, Some(screen)) => {
+            let delta = state.drag.map_or(Vec2::zero(), |last| screen - last);
+            state.drag = Some(screen);
+            delta
+        }
+        _ => {
+            state.drag = None;
+            Vec2::zero()
+        }
+    };
+    // A pinch zooms about the fingers rather than the cursor.
+    let mut anchor = None;
+    for gesture in state.gestures.iter(&gesture_events) {
+        match *gesture {
+            Gesture::Drag { delta } => drag += delta,
+            Gesture::Pinch { center, scale } if controls.zoom_step > 1.0 => {
+                notches += scale.ln() / controls.zoom_step.ln();
+                anchor = Some(center);
+            }
+            _ => {}
+        }
+    }
+    if notches == 0.0 && drag == Vec2::zero() {
+        return;
+    }
+    let mut transform = match cameras
+        .iter_mut()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, transform)) => transform,
+        None => return,
+    };
+
+    let old = transform.translation;
+    let scale = transform.scale.x();
+    transform.translation -= (drag * scale).extend(0.0);
+    if notches != 0.0 {
+        let zoomed = controls.zoomed_scale(scale, notches);
+        // Keep the world point under the cursor fixed.
+        let window = windows
+            .get_primary()
+            .map(|window| Vec2::new(window.width() as f32, window.height() as f32));
+        let anchor = match (anchor, window) {
+            (Some(center), Some(size)) => Some(screen_to_world(center, size, &transform)),
+            _ => cursor.world,
+        }
+        .unwrap_or_else(|| transform.translation.truncate());
+        let center = transform.translation.truncate();
+        let center = anchor + (center - anchor) * (zoomed / scale);
+        transform.translation = center.extend(transform.translation.z());
+        transform.scale = Vec3::new(zoomed, zoomed, transform.scale.z());
+    }
+    if transform.translation != old || transform.scale.x() != scale {
+        display_events.send(DisplayEvent::ViewChanged);
+    }
+}
+
+/// How a 3D camera zooms in on a globe with the mouse wheel. Zooming in moves toward
+/// the surface point under the cursor, which stays under it, and zooming out moves
+/// straight away from the center so the globe comes back into the middle of the view.
+/// Wheel notches set where the camera is headed and it eases there over a few frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobeZoom {
+    /// Fraction of the height above the surface one wheel notch closes.
+    pub step: f32,
+    /// Lowest the camera gets, as a fraction of the globe's radius above its surface.
+    pub min_altitude: f32,
+    /// Highest the camera gets, in globe radii above its surface.
+    pub max_altitude: f32,
+    /// Seconds the camera takes to cover about two thirds of the way to where a zoom
+    /// heads; 0 jumps straight there.
+    pub smoothing: f32,
+    /// Zoom toward the point under the cursor; toward the center when off.
+    pub to_cursor: bool,
+}
+
+impl Default for GlobeZoom {
+    fn default() -> Self {
+        GlobeZoom {
+            step: 0.2,
+            min_altitude: 0.05,
+            max_altitude: 10.0,
+            smoothing: 0.12,
+            to_cursor: true,
+        }
+    }
+}
+
+impl GlobeZoom {
+    /// Where a camera at `eye` heads after `notches` wheel notches over a globe of
+    /// `radius` at `center`, positive zooming in. `anchor` is the surface point under
+    /// the cursor, if it is over the globe.
+    pub fn zoomed(
+        &self,
+        eye: Vec3,
+        center: Vec3,
+        radius: f32,
+        anchor: Option<Vec3>,
+        notches: f32,
+    ) -> Vec3 {
+        let distance = (eye - center).length();
+        let altitude = distance - radius;
+        if altitude <= 0.0 || notches == 0.0 {
+            return eye;
+        }
+        let zoomed = (altitude * (1.0 - self.step).powf(notches))
+            .max(radius * self.min_altitude)
+            .min(radius * self.max_altitude);
+        match anchor {
+            Some(anchor) if self.to_cursor && zoomed < altitude => {
+                // Along the line through the anchor, so it stays under the cursor.
+                let toward = anchor + (eye - anchor) * (zoomed / altitude);
+                let lowest = radius * (1.0 + self.min_altitude);
+                if (toward - center).length() >= lowest {
+                    toward
+                } else {
+                    center + (toward - center).normalize() * lowest
+                }
+            }
+            _ => center + (eye - center) * ((radius + zoomed) / distance),
+        }
+    }
+
+    /// How far from `from` toward `to` the camera gets in `delta_seconds`.
+    pub fn approach(&self, from: Vec3, to: Vec3, delta_seconds: f32) -> Vec3 {
+        if self.smoothing <= 0.0 {
+            return to;
+        }
+        from + (to - from) * (1.0 - (-delta_seconds / self.smoothing).exp())
+    }
+}
+
+/// Where the 2D camera looks: the world point in the middle of the view and the world
+/// units per pixel, the inverse of the zoom.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PanZoom {
+    pub center: [f32; 2],
+    pub scale: f32,
+}
+
+/// A 3D camera looking at `focus` from `distance` away, turned `yaw` radians about the
+/// vertical from the +z side and `pitch` radians above the horizontal.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Orbit {
+    pub focus: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl Orbit {
+    /// The orbit a camera at `eye` is on around `focus`.
+    pub fn around(focus: Vec3, eye: Vec3) -> Self {
+        let offset = eye - focus;
+        let distance = offset.length();
+        let (yaw, pitch) = if distance > 0.0 {
+            (
+                offset.x().atan2(offset.z()),
+                (offset.y() / distance).clamp(-1.0, 1.0).asin(),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        Orbit {
+            focus: [focus.x(), focus.y(), focus.z()],
+            yaw,
+            pitch,
+            distance,
+        }
+    }
+
+    pub fn focus(&self) -> Vec3 {
+        let [x, y, z] = self.focus;
+        Vec3::new(x, y, z)
+    }
+
+    /// Where the camera is.
+    pub fn eye(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        self.focus()
+            + Vec3::new(sin_yaw * cos_pitch, sin_pitch, cos_yaw * cos_pitch) * self.distance
+    }
+
+    /// The camera's transform, at [`Orbit::eye`] and looking at the focus.
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.eye()).looking_at(self.focus(), Vec3::unit_y())
+    }
+}
+
+/// The pose of a view's cameras, to keep across restarts or for a host application to
+/// switch between views: the 2D camera's pan and zoom and the 3D camera's orbit, each
+/// if the view has that camera. [`PersistPlugin`](crate::persist::PersistPlugin) saves
+/// it with the session.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct CameraState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pan_zoom: Option<PanZoom>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit: Option<Orbit>,
+}
+
+impl CameraState {
+    /// The pose of the 2D and 3D cameras among `cameras`, the 3D one as an orbit
+    /// around `focus`.
+    pub fn save<'a>(
+        cameras: impl IntoIterator<Item = (&'a Camera, &'a Transform)>,
+        focus: Vec3,
+    ) -> Self {
+        let mut state = CameraState::default();
+        for (camera, transform) in cameras {
+            match camera.name.as_deref() {
+                Some(CAMERA2D) => {
+                    state.pan_zoom = Some(PanZoom {
+                        center: [transform.translation.x(), transform.translation.y()],
+                        scale: transform.scale.x(),
+                    })
+                }
+                Some(CAMERA3D) => state.orbit = Some(Orbit::around(focus, transform.translation)),
+                _ => {}
+            }
+        }
+        state
+    }
+
+    /// Puts `camera` back where the state has it, if it is the 2D or 3D camera and the
+    /// state has a pose for it, and tells whether it did. A 3D camera that was turned
+    /// away from its focus comes back looking at it.
+    pub fn restore(&self, camera: &Camera, transform: &mut Transform) -> bool {
+        match (camera.name.as_deref(), self.pan_zoom, self.orbit) {
+            (
+                Some(CAMERA2D),
+                Some(PanZoom {
+                    center: [x, y],
+                    scale,
+                }),
+                _,
+            ) => {
+                transform.translation = Vec3::new(x, y, transform.translation.z());
+                transform.scale = Vec3::new(scale, scale, transform.scale.z());
+                true
+            }
+            (Some(CAMERA3D), _, Some(orbit)) => {
+                let orbit = orbit.transform();
+                transform.translation = orbit.translation;
+                transform.rotation = orbit.rotation;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// How dragging turns an [`OrbitCamera`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OrbitMode {
+    /// Sideways drags change the yaw about the vertical and upward ones the pitch, so
+    /// the camera stays upright and stops short of the poles.
+    #[default]
+    Turntable,
+    /// A drag turns the camera about the axis across the screen perpendicular to it,
+    /// as if rolling a ball under the cursor. The camera goes over the poles and rolls
+    /// freely, so up is wherever the drags leave it.
+    Trackball,
+}
+
+/// How [`OrbitCameraPlugin`] turns and zooms [`OrbitCamera`]s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitControls {
+    /// How drags turn the camera; [`Action::SwitchOrbitMode`] switches between the
+    /// modes.
+    pub mode: OrbitMode,
+    /// Radians turned per pixel dragged.
+    pub sensitivity: f32,
+    /// How quickly a turn slows down once the drag is let go: its speed falls by a
+    /// factor of e every `1 / damping` seconds. 0 keeps it turning.
+    pub damping: f32,
+    /// Furthest the camera pitches above or below the horizontal in
+    /// [`OrbitMode::Turntable`], kept short of the poles where looking at the focus
+    /// leaves no way to tell up.
+    pub max_pitch: f32,
+    /// Fraction of the camera's height above the orbited surface one wheel notch closes.
+    pub zoom_step: f32,
+    /// Seconds the camera takes to cover about two thirds of the way to a new distance;
+    /// 0 jumps straight there.
+    pub smoothing: f32,
+    /// How [`CameraCommands::fly_to`] moves the camera along its way.
+    pub flight_easing: Easing,
+    /// Turns the camera on its own while nobody else does; `None` leaves it still.
+    pub auto_spin: Option<AutoSpin>,
+}
+
+impl Default for OrbitControls {
+    fn default() -> Self {
+        OrbitControls {
+            mode: OrbitMode::default(),
+            sensitivity: PI / 720.0,
+            damping: 6.0,
+            max_pitch: 85_f32.to_radians(),
+            zoom_step: 0.2,
+            smoothing: 0.12,
+            flight_easing: EasingConfig::default().fly_to,
+            auto_spin: None,
+        }
+    }
+}
+
+/// A slow turn of the [`OrbitCamera`]s around their focus while they are left alone,
+/// so that what they look at seems to spin on its own. It stops as soon as a drag, a
+/// key, a zoom or a flight moves the camera, and picks up again, easing in, once the
+/// camera has been still for `delay` seconds, counted from when any turn a drag left
+/// it with dies down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoSpin {
+    /// What the camera looks at seems to turn about this axis in world space,
+    /// anticlockwise seen from its tip.
+    pub axis: Vec3,
+    /// Radians per second.
+    pub speed: f32,
+    pub delay: f32,
+}
+
+impl Default for AutoSpin {
+    fn default() -> Self {
+        AutoSpin {
+            axis: Vec3::unit_y(),
+            speed: 5_f32.to_radians(),
+            delay: 3.0,
+        }
+    }
+}
+
+/// Where [`CameraCommands::fly_to`] sends the orbit cameras.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlyTo {
+    /// Degrees.
+    pub lat: f32,
+    pub lon: f32,
+    /// From the center of the body flown over.
+    pub distance: f32,
+    /// Seconds.
+    pub duration: f32,
+    /// The camera to fly; `None` flies them all.
+    pub camera: Option<Entity>,
+}
+
+/// Moves for [`OrbitCameraPlugin`] to make with the [`OrbitCamera`]s, for systems
+/// reacting to e.g. a selection to ask for.
+#[derive(Debug, Clone, Default)]
+pub struct CameraCommands {
+    flights: Vec<FlyTo>,
+}
+
+impl CameraCommands {
+    /// Flies the orbit cameras over the point at `lat` and `lon` on the [`Body`]
+    /// nearest their focus, `distance` from its center, in `duration` seconds. The
+    /// point is found in the body's frame as [`geo_to_local`] puts it, turned as the
+    /// body is now; without bodies it is on a sphere around the focus, unturned. The
+    /// camera turns along the shorter way round and comes out upright, easing as
+    /// [`OrbitControls::flight_easing`] says. Turning or zooming the camera meanwhile
+    /// stops the flight where it is; a later flight replaces an earlier one.
+    pub fn fly_to(&mut self, lat: f32, lon: f32, distance: f32, duration: f32) {
+        self.flights.push(FlyTo {
+            lat,
+            lon,
+            distance,
+            duration,
+            camera: None,
+        });
+    }
+
+    /// Flies only the orbit camera on the entity `camera`, as
+    /// [`CameraCommands::fly_to`] would, e.g. that of an inset view.
+    pub fn fly_camera_to(
+        &mut self,
+        camera: Entity,
+        lat: f32,
+        lon: f32,
+        distance: f32,
+        duration: f32,
+    ) {
+        self.flights.push(FlyTo {
+            lat,
+            lon,
+            distance,
+            duration,
+            camera: Some(camera),
+        });
+    }
+}
+
+/// A move of an [`OrbitCamera`] under way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Flight {
+    from: (Quat, f32),
+    to: (Quat, f32),
+    elapsed: f32,
+    duration: f32,
+    easing: Easing,
+}
+
+/// The gamepad sticks of [`OrbitBindings`], read from every connected gamepad. Its
+/// buttons are bound in the [`ActionMap`](crate::actions::ActionMap) like keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GamepadBindings {
+    /// Turns the camera left and right.
+    pub turn_x: GamepadAxisType,
+    /// Turns the camera up and down.
+    pub turn_y: GamepadAxisType,
+    /// Zooms in when pushed up.
+    pub zoom: GamepadAxisType,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        GamepadBindings {
+            turn_x: GamepadAxisType::LeftStickX,
+            turn_y: GamepadAxisType::LeftStickY,
+            zoom: GamepadAxisType::RightStickY,
+        }
+    }
+}
+
+/// How [`OrbitCameraPlugin`] drives cameras besides dragging and the wheel: with the
+/// turn and zoom [`Action`]s, held, and with gamepad sticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitBindings {
+    /// `None` ignores gamepad sticks.
+    pub gamepad: Option<GamepadBindings>,
+    /// Radians per second a held key or a stick pushed all the way turns the camera.
+    pub turn_speed: f32,
+    /// Wheel notches per second a held key or a stick pushed all the way zooms by.
+    pub zoom_speed: f32,
+}
+
+impl Default for OrbitBindings {
+    fn default() -> Self {
+        OrbitBindings {
+            gamepad: None,
+            turn_speed: PI / 2.0,
+            zoom_speed: 4.0,
+        }
+    }
+}
+
+/// A 3D camera on an [`Orbit`] that [`OrbitCameraPlugin`] turns and zooms, and which
+/// sets the camera's transform to match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitCamera {
+    pub focus: Vec3,
+    /// In [`OrbitMode::Trackball`] these follow where the camera's rotation puts it,
+    /// and setting them has no effect.
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Radius of what the camera orbits, whose surface zooming closes in on; 0 for a
+    /// point.
+    pub surface: f32,
+    /// The distance a zoom is headed for.
+    target: f32,
+    /// Radians per second still turning after a drag, across and up the screen.
+    spin: Vec2,
+    /// The camera's orientation in [`OrbitMode::Trackball`], looking down its -z at
+    /// the focus.
+    rotation: Quat,
+    /// The orbit the camera started on.
+    home: Orbit,
+    flight: Option<Flight>,
+    /// Seconds since the camera last moved other than by an [`AutoSpin`].
+    idle: f32,
+}
+
+impl OrbitCamera {
+    pub fn new(orbit: Orbit, min_distance: f32, max_distance: f32) -> Self {
+        let mut camera = OrbitCamera {
+            focus: Vec3::zero(),
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 0.0,
+            min_distance,
+            max_distance,
+            surface: 0.0,
+            target: 0.0,
+            spin: Vec2::zero(),
+            rotation: Quat::identity(),
+            home: orbit,
+            flight: None,
+            idle: 0.0,
+        };
+        camera.set(orbit);
+        camera
+    }
+
+    /// The camera orbiting something of `radius`, see [`OrbitCamera::surface`].
+    pub fn with_surface(mut self, radius: f32) -> Self {
+        self.surface = radius;
+        self
+    }
+
+    /// The orbit the camera is on.
+    pub fn orbit(&self) -> Orbit {
+        Orbit {
+            focus: [self.focus.x(), self.focus.y(), self.focus.z()],
+            yaw: self.yaw,
+            pitch: self.pitch,
+            distance: self.distance,
+        }
+    }
+
+    /// Puts the camera on `orbit` at once, stopping any turn or zoom under way.
+    pub fn set(&mut self, orbit: Orbit) {
+        self.focus = orbit.focus();
+        self.yaw = orbit.yaw;
+        self.pitch = orbit.pitch;
+        self.distance = orbit.distance.max(self.min_distance).min(self.max_distance);
+        self.target = self.distance;
+        self.spin = Vec2::zero();
+        self.rotation = upright(self.yaw, self.pitch);
+        self.flight = None;
+        self.idle = 0.0;
+    }
+
+    /// Flies the camera to look at the focus from `direction` and `distance` away, see
+    /// [`CameraCommands::fly_to`].
+    pub fn fly_to(
+        &mut self,
+        controls: &OrbitControls,
+        direction: Vec3,
+        distance: f32,
+        duration: f32,
+    ) {
+        let mut orbit = Orbit::around(Vec3::zero(), direction);
+        if controls.mode == OrbitMode::Turntable {
+            orbit.pitch = orbit.pitch.clamp(-controls.max_pitch, controls.max_pitch);
+        }
+        let mut to = upright(orbit.yaw, orbit.pitch);
+        // Both stand for the same turn; the nearer one is the shorter way there.
+        if self.rotation.dot(to) < 0.0 {
+            to = -to;
+        }
+        self.spin = Vec2::zero();
+        self.flight = Some(Flight {
+            from: (self.rotation, self.distance),
+            to: (to, distance.max(self.min_distance).min(self.max_distance)),
+            elapsed: 0.0,
+            duration: duration.max(0.0),
+            easing: controls.flight_easing,
+        });
+    }
+
+    /// Moves the camera `delta_seconds` further along its flight, if it is on one.
+    fn fly(&mut self, delta_seconds: f32) {
+        let flight = match &mut self.flight {
+            Some(flight) => flight,
+            None => return,
+        };
+        flight.elapsed += delta_seconds;
+        let t = if flight.duration > 0.0 {
+            flight.elapsed / flight.duration
+        } else {
+            1.0
+        };
+        let ((from, from_distance), (to, to_distance)) = (flight.from, flight.to);
+        self.rotation = from.slerp(to, flight.easing.apply(t)).normalize();
+        self.distance = flight.easing.lerp(from_distance, to_distance, t);
+        self.target = self.distance;
+        if t >= 1.0 {
+            self.flight = None;
+        }
+        self.follow_rotation();
+    }
+
+    /// Sets the yaw and pitch to where the rotation puts the camera.
+    fn follow_rotation(&mut self) {
+        let orbit = Orbit::around(Vec3::zero(), self.rotation * Vec3::unit_z());
+        self.yaw = orbit.yaw.rem_euclid(PI * 2.0);
+        self.pitch = orbit.pitch;
+    }
+
+    /// Puts the camera back at the yaw, pitch and distance it started at, still around
+    /// its current focus.
+    pub fn reset(&mut self) {
+        self.set(Orbit {
+            focus: [self.focus.x(), self.focus.y(), self.focus.z()],
+            ..self.home
+        });
+    }
+
+    /// Turns the camera by `turn` radians across and up the screen the way `mode`
+    /// does, and keeps the other mode's pose in step with where it ends up.
+    fn turn(&mut self, controls: &OrbitControls, turn: Vec2) {
+        match controls.mode {
+            OrbitMode::Turntable => {
+                self.yaw = (self.yaw + turn.x()).rem_euclid(PI * 2.0);
+                self.pitch = (self.pitch + turn.y()).clamp(-controls.max_pitch, controls.max_pitch);
+                self.rotation = upright(self.yaw, self.pitch);
+            }
+            OrbitMode::Trackball => {
+                // Yaw turns about the camera's up and pitch about its right, as the
+                // turntable does, so the axis is perpendicular to the drag.
+                let axis = Vec3::new(-turn.y(), turn.x(), 0.0);
+                let angle = axis.length();
+                if angle > 0.0 {
+                    self.rotation =
+                        (self.rotation * Quat::from_axis_angle(axis / angle, angle)).normalize();
+                }
+                self.follow_rotation();
+            }
+        }
+    }
+
+    /// Turns the camera `angle` radians about `axis`, of unit length, through its
+    /// focus, the opposite way to how what it looks at is to seem to turn. The
+    /// turntable comes out upright and within its pitch.
+    fn orbit_about(&mut self, controls: &OrbitControls, axis: Vec3, angle: f32) {
+        self.rotation = (Quat::from_axis_angle(axis, -angle) * self.rotation).normalize();
+        self.follow_rotation();
+        if controls.mode == OrbitMode::Turntable {
+            self.pitch = self.pitch.clamp(-controls.max_pitch, controls.max_pitch);
+            self.rotation = upright(self.yaw, self.pitch);
+        }
+    }
+
+    /// Where the camera is and which way it faces in `mode`.
+    fn pose(&self, mode: OrbitMode) -> Transform {
+        match mode {
+            OrbitMode::Turntable => self.orbit().transform(),
+            OrbitMode::Trackball => Transform {
+                translation: self.focus + self.rotation * Vec3::new(0.0, 0.0, self.distance),
+                rotation: self.rotation,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Heads for the distance `notches` wheel notches of `controls` lead to, positive
+    /// zooming in.
+    pub fn zoom(&mut self, controls: &OrbitControls, notches: f32) {
+        let height = (self.target - self.surface).max(0.0);
+        self.target = (self.surface + height * (1.0 - controls.zoom_step).powf(notches))
+            .max(self.min_distance)
+            .min(self.max_distance);
+    }
+}
+
+/// The rotation of an upright camera at `yaw` and `pitch` looking at its focus.
+fn upright(yaw: f32, pitch: f32) -> Quat {
+    Quat::from_rotation_y(yaw) * Quat::from_rotation_x(-pitch)
+}
+
+/// Turns each [`OrbitCamera`] around its focus by dragging with [`Action::Orbit`] held,
+/// carrying on and slowing down after it is let go, and zooms it with the mouse wheel,
+/// easing to the new distance. The turn, zoom and [`Action::ResetView`] actions and
+/// the gamepad sticks of the [`OrbitBindings`] resource turn, zoom and reset it too,
+/// and so do touches: a finger drag turns it, a pinch zooms and a double tap
+/// resets it, see [`Gesture`]. Left alone, it turns as [`OrbitControls::auto_spin`]
+/// says. Switching [`OrbitControls::mode`] back to the turntable sets the camera upright
+/// again. In the turntable, pitch stops short of the poles. Distance stays within the
+/// camera's limits. With several views, only the camera of the one under the cursor
+/// takes input, see [`ViewRect`], and a drag stays with the view it started in. Sends
+/// [`DisplayEvent::ViewChanged`] whenever a camera moves. Needs
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin).
+pub struct OrbitCameraPlugin;
+
+impl Plugin for OrbitCameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<OrbitControls>() {
+            app.init_resource::<OrbitControls>();
+        }
+        if !app.resources().contains::<OrbitBindings>() {
+            app.init_resource::<OrbitBindings>();
+        }
+        if !app.resources().contains::<CameraCommands>() {
+            app.init_resource::<CameraCommands>();
+        }
+        if !app.resources().contains::<CursorPosition>() {
+            app.init_resource::<CursorPosition>();
+        }
+        add_gestures(app);
+        add_actions(app);
+        app.add_system(orbit_camera_system.system());
+    }
+}
+
+#[derive(Default)]
+struct OrbitState {
+    motion: EventReader<MouseMotion>,
+    wheel: EventReader<MouseWheel>,
+    gamepad_events: EventReader<GamepadEvent>,
+    gamepads: HashSet<Gamepad>,
+    gestures: EventReader<Gesture>,
+    /// The view a drag started in, which keeps it until the button is let go: the
+    /// camera of a [`ViewRect`], or `None` for those over the whole window.
+    grabbed: Option<Option<Entity>>,
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn orbit_camera_system(
+    mut state: Local<OrbitState>,
+    time: Res<Time>,
+    (mut controls, bindings): (ResMut<OrbitControls>, Res<OrbitBindings>),
+    (actions, touches, gamepad_axes): (Res<ActionState>, Res<Touches>, Res<Axis<GamepadAxis>>),
+    (motion_events, wheel_events): (Res<Events<MouseMotion>>, Res<Events<MouseWheel>>),
+    (gamepad_events, gesture_events): (Res<Events<GamepadEvent>>, Res<Events<Gesture>>),
+    (mut display_events, mut commands): (ResMut<Events<DisplayEvent>>, ResMut<CameraCommands>),
+    (cursor, windows): (Res<CursorPosition>, Res<Windows>),
+    mut cameras: Query<(Entity, Mut<OrbitCamera>, Mut<Transform>, Option<&ViewRect>)>,
+    bodies: Query<(&Body, &GlobalTransform)>,
+) {
+    let mut dragged = state
+        .motion
+        .iter(&motion_events)
+        .fold(Vec2::zero(), |sum, event| sum + event.delta);
+    let mut notches = state
+        .wheel
+        .iter(&wheel_events)
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum::<f32>();
+    let delta = time.delta_seconds;
+
+    for GamepadEvent(gamepad, event) in state.gamepad_events.iter(&gamepad_events) {
+        match event {
+            GamepadEventType::Connected => {
+                state.gamepads.insert(*gamepad);
+            }
+            GamepadEventType::Disconnected => {
+                state.gamepads.remove(gamepad);
+            }
+            _ => {}
+        }
+    }
+    let mut reset = actions.just_pressed(Action::ResetView);
+    for gesture in state.gestures.iter(&gesture_events) {
+        match *gesture {
+            // Gestures are measured up the screen, mouse motion down it.
+            Gesture::Drag { delta } => {
+                dragged += Vec2::new(delta.x(), -delta.y());
+            }
+            Gesture::Pinch { scale, .. } if controls.zoom_step > 0.0 => {
+                notches += scale.ln() / -(1.0 - controls.zoom_step).ln();
+            }
+            Gesture::Pinch { .. } => {}
+            Gesture::DoubleTap { .. } => reset = true,
+            Gesture::Tap { .. } => {}
+        }
+    }
+    let switch_mode = actions.just_pressed(Action::SwitchOrbitMode);
+    // Held actions and sticks move the camera as "right" and "up" on the screen.
+    let mut held = Vec2::new(
+        actions.axis(Action::TurnLeft, Action::TurnRight),
+        actions.axis(Action::TurnDown, Action::TurnUp),
+    );
+    let mut zooming = actions.axis(Action::ZoomOut, Action::ZoomIn);
+    if let Some(pad) = bindings.gamepad {
+        for &gamepad in &state.gamepads {
+            let axis = |axis| gamepad_axes.get(GamepadAxis(gamepad, axis)).unwrap_or(0.0);
+            held += Vec2::new(axis(pad.turn_x), axis(pad.turn_y));
+            zooming += axis(pad.zoom);
+        }
+    }
+    // Moving the camera right turns it the way dragging left does.
+    let steered = Vec2::new(-held.x(), held.y()) * bindings.turn_speed * delta;
+    notches += zooming * bindings.zoom_speed * delta;
+
+    if switch_mode {
+        controls.mode = match controls.mode {
+            OrbitMode::Turntable => OrbitMode::Trackball,
+            OrbitMode::Trackball => OrbitMode::Turntable,
+        };
+        tracing::info!("orbit mode: {:?}", controls.mode);
+    }
+    // Input goes to the view under the cursor, or the one a drag started in.
+    let window = windows.get_primary().map_or(Vec2::zero(), |w| {
+        Vec2::new(w.width() as f32, w.height() as f32)
+    });
+    let views = cameras
+        .iter_mut()
+        .map(|(entity, .., rect)| (entity, rect.copied()))
+        .collect::<Vec<_>>();
+    let hovered = cursor.screen.and_then(|screen| {
+        view_under_cursor(screen, window, views.into_iter(), None).map(|(entity, ..)| entity)
+    });
+    if actions.just_pressed(Action::Orbit) {
+        state.grabbed = Some(hovered);
+    } else if !actions.pressed(Action::Orbit) {
+        state.grabbed = None;
+    }
+    let active = state.grabbed.unwrap_or(hovered);
+    // A finger held still stops the camera as the held button does.
+    let held_down = actions.pressed(Action::Orbit) || touches.iter().count() == 1;
+    let flights = std::mem::take(&mut commands.flights);
+    let mut moved = false;
+    for (entity, mut camera, mut transform, rect) in cameras.iter_mut() {
+        let routed = if rect.is_some() {
+            active == Some(entity)
+        } else {
+            active.is_none()
+        };
+        let (dragging, dragged, steered, notches, reset) = if routed {
+            (held_down, dragged, steered, notches, reset)
+        } else {
+            (false, Vec2::zero(), Vec2::zero(), 0.0, false)
+        };
+        let steering = dragging || steered != Vec2::zero() || notches != 0.0;
+        let camera = &mut *camera;
+        if reset {
+            camera.reset();
+        }
+        let flight = flights
+            .iter()
+            .rev()
+            .find(|flight| flight.camera.is_none_or(|c| c == entity))
+            .copied();
+        if let Some(flight) = flight {
+            let frame = bodies
+                .iter()
+                .map(|(_, transform)| transform)
+                .min_by(|a, b| {
+                    let d = |t: &GlobalTransform| (t.translation - camera.focus).length_squared();
+                    d(a).partial_cmp(&d(b)).unwrap()
+                })
+                .map_or(Quat::identity(), |transform| transform.rotation);
+            let direction = frame * geo_to_local(flight.lat, flight.lon, 1.0);
+            camera.fly_to(&controls, direction, flight.distance, flight.duration);
+        }
+        if steering {
+            camera.flight = None;
+        } else {
+            camera.fly(delta);
+        }
+        let turn = if dragging {
+            // Dragging right turns the view right, so the camera goes left.
+            let turn = Vec2::new(-dragged.x(), dragged.y()) * controls.sensitivity;
+            if delta > 0.0 {
+                camera.spin = turn / delta;
+            }
+            turn
+        } else {
+            let turn = camera.spin * delta;
+            camera.spin *= (-controls.damping * delta).exp();
+            if camera.spin.length() < 1e-3 {
+                camera.spin = Vec2::zero();
+            }
+            turn
+        };
+        camera.turn(&controls, turn + steered);
+        let still = !steering && !reset && camera.flight.is_none() && camera.spin == Vec2::zero();
+        camera.idle = if still { camera.idle + delta } else { 0.0 };
+        match controls.auto_spin {
+            Some(spin) if camera.idle > spin.delay && spin.axis.length_squared() > 0.0 => {
+                let ramp = ((camera.idle - spin.delay) / AUTO_SPIN_RAMP).min(1.0);
+                camera.orbit_about(&controls, spin.axis.normalize(), spin.speed * ramp * delta);
+            }
+            _ => {}
+        }
+        if notches != 0.0 {
+            camera.zoom(&controls, notches);
+        }
+        camera.distance = if controls.smoothing <= 0.0 {
+            camera.target
+        } else {
+            let eased = 1.0 - (-delta / controls.smoothing).exp();
+            let next = camera.distance + (camera.target - camera.distance) * eased;
+            // Close enough to stop, at a ten-thousandth of the way still to go.
+            if (next - camera.target).abs() < camera.target * 1e-4 {
+                camera.target
+            } else {
+                next
+            }
+        };
+        let pose = camera.pose(controls.mode);
+        if transform.translation != pose.translation || transform.rotation != pose.rotation {
+            transform.translation = pose.translation;
+            transform.rotation = pose.rotation;
+            moved = true;
+        }
+    }
+    if moved {
+        display_events.send(DisplayEvent::ViewChanged);
+    }
+}
@@ -0,0 +1,363 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy::render::render_graph::{
+    base, Node, RenderGraph, ResourceSlotInfo, ResourceSlots, WindowSwapChainNode,
+};
+use bevy::render::renderer::{RenderContext, RenderResourceId, TextureId};
+use bevy::render::texture::{Extent3d, TextureDescriptor, TextureFormat, TextureUsage};
+use bevy::wgpu::renderer::{WgpuRenderContext, WgpuRenderResourceContext};
+use bevy::window::WindowId;
+use image::RgbaImage;
+
+use crate::actions::{add_actions, Action, ActionState};
+
+/// Render graph node copying a captured frame into a buffer to read back.
+const CAPTURE_READBACK_NODE: &str = "capture_readback";
+/// Rows of a texture copied into a buffer start this many bytes apart, at least.
+const ROW_ALIGNMENT: u32 = 256;
+
+/// Saves the frame in the window as a PNG into `dir`: on [`Action::Snapshot`], F11 by
+/// default, named `capture-NNNNN.png`, and with `every` set one every that many frames
+/// for a timelapse, named `timelapse-NNNNN.png`. Numbers carry on from the files
+/// already there.
+///
+/// The frame is read back from the GPU, so it holds all the window shows: labels, UI,
+/// overlays, split views and shader effects. The window's swap chain textures can only
+/// be drawn into, so on a frame being captured every pass draws into a texture of the
+/// plugin's own instead, which is copied into a buffer and read back the frame after;
+/// the window keeps showing the frame before for that one frame, and no two frames in a
+/// row are captured. Needs the wgpu backend. Add after the render, UI and split view
+/// plugins, as the passes drawing into the window when it is added are the ones waited
+/// for.
+pub struct CapturePlugin {
+    pub dir: PathBuf,
+    pub every: Option<u32>,
+}
+
+struct Captures {
+    dir: PathBuf,
+    every: Option<u32>,
+    frame: u32,
+    /// Next free number of each file name prefix, found on first use.
+    next: Vec<(&'static str, u32)>,
+    /// Files the next frame drawn offscreen is saved to, with their prefixes.
+    due: Vec<(&'static str, PathBuf)>,
+    /// The frame drawn offscreen this frame, for [`CaptureReadbackNode`] to copy.
+    drawn: Option<Drawn>,
+}
+
+struct Drawn {
+    texture: TextureId,
+    size: Extent3d,
+    paths: Vec<(&'static str, PathBuf)>,
+}
+
+impl Captures {
+    fn new(dir: &Path, every: Option<u32>) -> Self {
+        Captures {
+            dir: dir.to_path_buf(),
+            every: every.filter(|&every| every > 0),
+            frame: 0,
+            next: Vec::new(),
+            due: Vec::new(),
+            drawn: None,
+        }
+    }
+
+    /// The file name prefixes due this frame, the snapshot action's first.
+    fn prefixes(&mut self, actions: &ActionState) -> Vec<&'static str> {
+        self.frame += 1;
+        let timelapse = self
+            .every
+            .is_some_and(|every| self.frame.is_multiple_of(every));
+        actions
+            .just_pressed(Action::Snapshot)
+            .then_some("capture")
+            .into_iter()
+            .chain(timelapse.then_some("timelapse"))
+            .collect()
+    }
+
+    fn path(&mut self, prefix: &'static str) -> PathBuf {
+        let dir = self.dir.clone();
+        let number = match self.next.iter_mut().find(|(p, _)| *p == prefix) {
+            Some((_, next)) => next,
+            None => {
+                self.next.push((prefix, first_free(&dir, prefix)));
+                &mut self.next.last_mut().unwrap().1
+            }
+        };
+        let path = dir.join(file_name(prefix, *number));
+        *number += 1;
+        path
+    }
+}
+
+fn file_name(prefix: &str, number: u32) -> String {
+    format!("{}-{:05}.png", prefix, number)
+}
+
+fn first_free(dir: &Path, prefix: &str) -> u32 {
+    (1..)
+        .find(|&n| !dir.join(file_name(prefix, n)).exists())
+        .unwrap()
+}
+
+fn save(image: &RgbaImage, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    image.save(path).map_err(io::Error::other)
+}
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        app.add_resource(Captures::new(&self.dir, self.every))
+            .add_system(capture_system.system());
+        let resources = app.resources();
+        let mut graph = resources.get_mut::<RenderGraph>().unwrap();
+        let drawing: Vec<String> = graph
+            .iter_node_outputs(base::node::PRIMARY_SWAP_CHAIN)
+            .unwrap()
+            .filter_map(|(_, node)| node.name.as_deref().map(str::to_string))
+            .collect();
+        let swap_chain = CaptureSwapChainNode::new(WindowSwapChainNode::new(WindowId::primary()));
+        graph
+            .get_node_state_mut(base::node::PRIMARY_SWAP_CHAIN)
+            .unwrap()
+            .node = Box::new(swap_chain);
+        graph.add_node(CAPTURE_READBACK_NODE, CaptureReadbackNode::default());
+        for node in drawing {
+            graph.add_node_edge(node, CAPTURE_READBACK_NODE).unwrap();
+        }
+    }
+}
+
+fn capture_system(actions: Res<ActionState>, mut captures: ResMut<Captures>) {
+    for prefix in captures.prefixes(&actions) {
+        let path = captures.path(prefix);
+        captures.due.push((prefix, path));
+    }
+}
+
+/// The window's swap chain node, giving out a texture of its own in place of the swap
+/// chain's on frames being captured.
+struct CaptureSwapChainNode {
+    swap_chain: WindowSwapChainNode,
+    /// The offscreen texture, made again when the window changes size.
+    target: Option<(TextureId, Extent3d)>,
+    /// Whether the last frame was drawn offscreen, or there was none yet.
+    captured_last: bool,
+}
+
+impl CaptureSwapChainNode {
+    fn new(swap_chain: WindowSwapChainNode) -> Self {
+        CaptureSwapChainNode {
+            swap_chain,
+            target: None,
+            captured_last: true,
+        }
+    }
+}
+
+impl Node for CaptureSwapChainNode {
+    fn output(&self) -> &[ResourceSlotInfo] {
+        self.swap_chain.output()
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        resources: &Resources,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        output: &mut ResourceSlots,
+    ) {
+        let mut captures = resources.get_mut::<Captures>().unwrap();
+        let size = resources.get::<Windows>().and_then(|windows| {
+            windows.get_primary().map(|window| Extent3d {
+                width: window.width(),
+                height: window.height(),
+                depth: 1,
+            })
+        });
+        // At most every other frame is drawn offscreen, so that the swap chain still
+        // sees the window's resize events before they are dropped.
+        let size = match size {
+            Some(size) if !captures.due.is_empty() && !self.captured_last => size,
+            _ => {
+                self.captured_last = false;
+                self.swap_chain
+                    .update(world, resources, render_context, input, output);
+                return;
+            }
+        };
+        let render_resources = render_context.resources_mut();
+        let texture = match self.target {
+            Some((texture, target)) if target == size => texture,
+            _ => {
+                if let Some((texture, _)) = self.target.take() {
+                    render_resources.remove_texture(texture);
+                }
+                let texture = render_resources.create_texture(TextureDescriptor {
+                    size,
+                    format: TextureFormat::default(),
+                    usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::COPY_SRC,
+                    ..Default::default()
+                });
+                self.target = Some((texture, size));
+                texture
+            }
+        };
+        output.set(0, RenderResourceId::Texture(texture));
+        captures.drawn = Some(Drawn {
+            texture,
+            size,
+            paths: std::mem::take(&mut captures.due),
+        });
+        self.captured_last = true;
+    }
+}
+
+/// Copies the frame drawn offscreen into a buffer once every pass has drawn it, and
+/// reads back and saves the frames copied before, whose commands have been submitted
+/// since.
+#[derive(Default)]
+struct CaptureReadbackNode {
+    copied: Vec<Copied>,
+}
+
+struct Copied {
+    buffer: wgpu::Buffer,
+    size: Extent3d,
+    /// Bytes from one row to the next, padded to [`ROW_ALIGNMENT`].
+    stride: u32,
+    paths: Vec<(&'static str, PathBuf)>,
+}
+
+impl Node for CaptureReadbackNode {
+    fn update(
+        &mut self,
+        _world: &World,
+        resources: &Resources,
+        render_context: &mut dyn RenderContext,
+        _input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        let drawn = resources.get_mut::<Captures>().unwrap().drawn.take();
+        let context = match wgpu_context(render_context) {
+            Some(context) => context,
+            None => {
+                if drawn.is_some() {
+                    tracing::error!("frame capture needs the wgpu render backend");
+                }
+                return;
+            }
+        };
+        for copied in self.copied.drain(..) {
+            read_back(&context.device, copied);
+        }
+        let drawn = match drawn {
+            Some(drawn) => drawn,
+            None => return,
+        };
+        let textures = context.render_resource_context.resources.textures.read();
+        let texture = match textures.get(&drawn.texture) {
+            Some(texture) => texture,
+            None => return,
+        };
+        let Extent3d { width, height, .. } = drawn.size;
+        let stride = (width * 4).div_ceil(ROW_ALIGNMENT) * ROW_ALIGNMENT;
+        let buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("capture_readback"),
+            size: (stride * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        context
+            .command_encoder
+            .get_or_create(&context.device)
+            .copy_texture_to_buffer(
+                wgpu::TextureCopyView {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                wgpu::BufferCopyView {
+                    buffer: &buffer,
+                    layout: wgpu::TextureDataLayout {
+                        offset: 0,
+                        bytes_per_row: stride,
+                        rows_per_image: height,
+                    },
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth: 1,
+                },
+            );
+        drop(textures);
+        self.copied.push(Copied {
+            buffer,
+            size: drawn.size,
+            stride,
+            paths: drawn.paths,
+        });
+    }
+}
+
+/// The wgpu context under `render_context`, for the texture-to-buffer copy bevy's
+/// render context doesn't offer; `None` with another backend.
+fn wgpu_context(render_context: &mut dyn RenderContext) -> Option<&mut WgpuRenderContext> {
+    render_context
+        .resources()
+        .downcast_ref::<WgpuRenderResourceContext>()?;
+    // SAFETY: only bevy_wgpu's render context has its resources, and its graph
+    // executor hands every node that context itself rather than a wrapper.
+    Some(unsafe { &mut *(render_context as *mut dyn RenderContext as *mut WgpuRenderContext) })
+}
+
+/// Waits for `copied` to be mapped and saves it to each of its paths, dropping the row
+/// padding and putting the channels in RGBA order.
+fn read_back(device: &wgpu::Device, copied: Copied) {
+    let slice = copied.buffer.slice(..);
+    let mapped = slice.map_async(wgpu::MapMode::Read);
+    device.poll(wgpu::Maintain::Wait);
+    if let Err(e) = futures_lite::future::block_on(mapped) {
+        tracing::error!("failed to read back captured frame: {:?}", e);
+        return;
+    }
+    let Extent3d { width, height, .. } = copied.size;
+    let row = width as usize * 4;
+    let mut image = RgbaImage::new(width, height);
+    {
+        let data = slice.get_mapped_range();
+        for (to, from) in image
+            .chunks_exact_mut(row)
+            .zip(data.chunks_exact(copied.stride as usize))
+        {
+            to.copy_from_slice(&from[..row]);
+        }
+    }
+    copied.buffer.unmap();
+    let bgra = TextureFormat::default() == TextureFormat::Bgra8UnormSrgb;
+    for pixel in image.chunks_exact_mut(4) {
+        if bgra {
+            pixel.swap(0, 2);
+        }
+        // What blending left in the alpha channel isn't what the window shows.
+        pixel[3] = 255;
+    }
+    for (prefix, path) in &copied.paths {
+        match save(&image, path) {
+            Ok(()) if *prefix == "capture" => tracing::info!("saved {}", path.display()),
+            Ok(()) => {}
+            Err(e) => tracing::error!("failed to save capture {}: {}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use bevy::window::WindowDescriptor;
+use clap::Args;
+
+use crate::actions::ActionMap;
+use crate::config::ConfigError;
+use crate::metrics::{MetricsExport, MetricsPlugin};
+use crate::persist::{self, PersistPlugin, SessionState};
+use crate::theme::Theme;
+
+/// Options understood by every binary.
+#[derive(Debug, Clone, Args)]
+pub struct DisplayArgs {
+    /// Color theme
+    #[arg(long, value_enum, default_value_t = Theme::Classic)]
+    pub theme: Theme,
+    /// Window width in logical pixels
+    #[arg(long, default_value_t = 1280)]
+    pub width: u32,
+    /// Window height in logical pixels
+    #[arg(long, default_value_t = 720)]
+    pub height: u32,
+    /// Periodically write metrics to this file (Prometheus format for *.prom, JSON otherwise)
+    #[arg(long)]
+    pub metrics: Option<PathBuf>,
+    /// Seconds between metrics exports
+    #[arg(long, default_value_t = 10.0)]
+    pub metrics_interval: f32,
+    /// Save the session to this file periodically and offer to restore it on launch
+    #[arg(long)]
+    pub state: Option<PathBuf>,
+    /// Restore the saved session without asking
+    #[arg(long, requires = "state")]
+    pub restore: bool,
+    /// Rebind the actions listed in this RON (or .toml) file, such as
+    /// { select: [mouse(Left)], reset_view: [key(Home), gamepad(Select)] }
+    #[arg(long, value_name = "FILE")]
+    pub bindings: Option<PathBuf>,
+}
+
+impl DisplayArgs {
+    pub fn window_descriptor(&self, title: &str) -> WindowDescriptor {
+        WindowDescriptor {
+            title: title.to_string(),
+            width: self.width,
+            height: self.height,
+            ..Default::default()
+        }
+    }
+
+    pub fn metrics_plugin(&self) -> MetricsPlugin {
+        MetricsPlugin {
+            export: self
+                .metrics
+                .as_ref()
+                .map(|path| MetricsExport::new(path, self.metrics_interval)),
+        }
+    }
+
+    /// `defaults` with the actions `--bindings` lists rebound.
+    pub fn action_map(&self, defaults: ActionMap) -> Result<ActionMap, ConfigError> {
+        let mut map = defaults;
+        if let Some(path) = &self.bindings {
+            map.merge_file(path)?;
+        }
+        Ok(map)
+    }
+
+    /// The saved session to resume from, if `--state` names one the user accepts.
+    pub fn restore_session(&self) -> Option<SessionState> {
+        let path = self.state.as_ref()?;
+        persist::offer_restore(path, self.restore)
+    }
+
+    pub fn persist_plugin(&self, restored: Option<SessionState>) -> Option<PersistPlugin> {
+        self.state.as_ref().map(|path| PersistPlugin {
+            path: path.clone(),
+            interval: 5.0,
+            restored,
+        })
+    }
+}
+
+/// Parses a finite number greater than 0, for sizes and spacings given as flags.
+pub fn parse_positive(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(value) if value.is_finite() && value > 0.0 => Ok(value),
+        Ok(value) => Err(format!("must be a positive number, got {}", value)),
+        Err(e) => Err(format!("{:?}: {}", s, e)),
+    }
+}
+
+/// Parses a gain within `(0, 1]`, for filter weights given as flags.
+pub fn parse_gain(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(value) if value > 0.0 && value <= 1.0 => Ok(value),
+        Ok(value) => Err(format!("must be within (0, 1], got {}", value)),
+        Err(e) => Err(format!("{:?}: {}", s, e)),
+    }
+}
+
+/// Writes what the library logs to stderr, at `info` and above unless `RUST_LOG` says
+/// otherwise, e.g. `RUST_LOG=bevy_debris::layout=trace` for every placement. For each
+/// binary to call first thing; in the browser nothing is written.
+pub fn init_logging() {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use tracing_subscriber::EnvFilter;
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+        tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_target(false)
+            .with_writer(std::io::stderr)
+            .init();
+    }
+}
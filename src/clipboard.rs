@@ -0,0 +1,166 @@
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::coords::CoordFormat;
+use crate::designation::Designation;
+use crate::geo::Polar;
+use crate::prediction::TrackHistory;
+use crate::target::{GeoPoint, Target};
+
+/// Copies a summary of the designated target to the system clipboard on
+/// [`Action::CopyTarget`], Ctrl+C by default, see
+/// [`summary`]. Put a [`GeoPoint`] with the same id on a target entity to include its
+/// latitude and longitude. The clipboard is only reached with the `clipboard` feature,
+/// which hands the text to `wl-copy`, `xclip`, `xsel`, `pbcopy` or `clip`, whichever
+/// is installed; otherwise, or when none is, the summary is printed instead.
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<Designation>() {
+            app.init_resource::<Designation>();
+        }
+        if !app.resources().contains::<CoordFormat>() {
+            app.init_resource::<CoordFormat>();
+        }
+        app.add_system(copy_system.system());
+    }
+}
+
+/// The text [`ClipboardPlugin`] copies: id and label, bearing and range, latitude and
+/// longitude when known, then when the target was last reported (in seconds since
+/// startup) and when the summary was made.
+pub fn summary(
+    target: &Target,
+    geo: Option<&GeoPoint>,
+    history: Option<&TrackHistory>,
+    format: CoordFormat,
+) -> String {
+    let mut summary = format!(
+        "{} (#{})\n{}",
+        target.text,
+        target.id,
+        CoordFormat::BearingRange.format_polar(Polar::new(target.azimuth, target.dist))
+    );
+    if let Some(geo) = geo {
+        let format = match format {
+            CoordFormat::BearingRange => CoordFormat::DecimalDegrees,
+            format => format,
+        };
+        summary.push_str("\nposition ");
+        summary.push_str(&format.format_geo(geo.lat, geo.lon));
+    }
+    if let Some(category) = &target.category {
+        summary.push_str("\ncategory ");
+        summary.push_str(category);
+    }
+    if let Some(time) = history.and_then(TrackHistory::last_update) {
+        summary.push_str(&format!("\nlast update t+{:.1}s", time));
+    }
+    summary.push_str("\ncopied ");
+    summary.push_str(&utc_timestamp(SystemTime::now()));
+    summary
+}
+
+fn copy_system(
+    actions: Res<ActionState>,
+    designation: Res<Designation>,
+    format: Res<CoordFormat>,
+    targets: Query<(&Target, Option<&GeoPoint>, Option<&TrackHistory>)>,
+) {
+    if !actions.just_pressed(Action::CopyTarget) {
+        return;
+    }
+    let id = match designation.target {
+        Some(id) => id,
+        None => return,
+    };
+    if let Some((target, geo, history)) = targets.iter().find(|(t, ..)| t.id == id) {
+        let text = summary(target, geo, history, *format);
+        if let Err(e) = copy(&text) {
+            tracing::warn!("could not copy target {} to the clipboard: {}", id, e);
+            tracing::info!("{}", text);
+        }
+    }
+}
+
+#[cfg(all(feature = "clipboard", not(feature = "web")))]
+fn copy(text: &str) -> io::Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const TOOLS: [&[&str]; 5] = [
+        &["wl-copy"],
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+        &["pbcopy"],
+        &["clip"],
+    ];
+    for tool in TOOLS.iter() {
+        let mut child = match Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(text.as_bytes())?;
+        return match child.wait()? {
+            status if status.success() => Ok(()),
+            status => Err(io::Error::other(format!("{} failed: {}", tool[0], status))),
+        };
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard tool found",
+    ))
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy(_text: &str) -> io::Result<()> {
+    Err(io::Error::other("built without the clipboard feature"))
+}
+
+#[cfg(all(feature = "clipboard", feature = "web"))]
+fn copy(_text: &str) -> io::Result<()> {
+    Err(io::Error::other("no clipboard tools in the browser"))
+}
+
+/// `time` as `YYYY-MM-DDTHH:MM:SSZ`.
+fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, rest) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil date from days since 1970-01-01, after Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60
+    )
+}
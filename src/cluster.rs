@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::events::DisplayEvent;
+use crate::layout::angular_distance;
+use crate::target::Target;
+use crate::updates::TargetRemoved;
+
+/// Points drawn close enough together to be shown as one, see [`cluster_points`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    /// Indices into the clustered points, in their original order.
+    pub members: Vec<usize>,
+    /// Mean position of the members.
+    pub center: Vec2,
+}
+
+impl Cluster {
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// Groups screen positions that lie within `radius` pixels of a group's first
+/// point. Points are taken in order, so the same input always gives the same
+/// clusters; every point ends up in exactly one, lone points in one of their own.
+pub fn cluster_points(points: &[Vec2], radius: f32) -> Vec<Cluster> {
+    let mut seeds: Vec<Vec2> = Vec::new();
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for (i, &p) in points.iter().enumerate() {
+        match seeds
+            .iter()
+            .position(|&seed| (seed - p).length_squared() <= radius * radius)
+        {
+            Some(c) => clusters[c].members.push(i),
+            None => {
+                seeds.push(p);
+                clusters.push(Cluster {
+                    members: vec![i],
+                    center: p,
+                });
+            }
+        }
+    }
+    for cluster in &mut clusters {
+        let sum = cluster
+            .members
+            .iter()
+            .fold(Vec2::zero(), |sum, &i| sum + points[i]);
+        cluster.center = sum / cluster.len() as f32;
+    }
+    clusters
+}
+
+/// How close targets must be to be merged by [`SectorClusterPlugin`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectorClustering {
+    /// Largest azimuth difference from a cluster's first member, in radians.
+    pub angle: f32,
+    /// Largest distance difference from a cluster's first member.
+    pub range: f32,
+    /// Fewest targets worth merging.
+    pub min_size: usize,
+}
+
+impl Default for SectorClustering {
+    fn default() -> Self {
+        SectorClustering {
+            angle: 5f32.to_radians(),
+            range: 10.0,
+            min_size: 3,
+        }
+    }
+}
+
+/// Groups polar positions `(azimuth, dist)` that lie within `window` of a group's first
+/// member, in the same way as [`cluster_points`]. Returns the indices of each group.
+pub fn cluster_sectors(points: &[(f32, f32)], window: &SectorClustering) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, &(azimuth, dist)) in points.iter().enumerate() {
+        let near = |group: &Vec<usize>| {
+            let (seed_azimuth, seed_dist) = points[group[0]];
+            angular_distance(azimuth, seed_azimuth) <= window.angle
+                && (dist - seed_dist).abs() <= window.range
+        };
+        match groups.iter().position(near) {
+            Some(g) => groups[g].push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    groups
+}
+
+/// On a cluster entity: the targets it stands for, which have no entities of their own
+/// while merged.
+#[derive(Debug, Clone)]
+pub struct TargetCluster {
+    pub members: Vec<Target>,
+}
+
+impl TargetCluster {
+    /// The target shown for the cluster: at the members' mean azimuth and the nearest
+    /// member's distance, labelled with the count and in the `cluster` category, under
+    /// an id no member has.
+    pub fn target(&self) -> Target {
+        let (sin, cos) = self.members.iter().fold((0.0, 0.0), |(s, c), t| {
+            (s + t.azimuth.sin(), c + t.azimuth.cos())
+        });
+        let dist = self
+            .members
+            .iter()
+            .map(|t| t.dist)
+            .fold(f32::INFINITY, f32::min);
+        let first = self.members.iter().map(|t| t.id).min().unwrap_or(0);
+        Target {
+            id: -1 - first.abs(),
+            text: format!("[{}]", self.members.len()),
+            azimuth: sin.atan2(cos).rem_euclid(PI * 2.0),
+            dist,
+            category: Some(CLUSTER_CATEGORY.to_string()),
+            priority: self.members.iter().map(|t| t.priority).max().unwrap_or(0),
+            origin: self.members.first().map_or(0, |t| t.origin),
+            ..Default::default()
+        }
+    }
+}
+
+/// Category of the targets shown for [`TargetCluster`]s.
+pub const CLUSTER_CATEGORY: &str = "cluster";
+
+/// Merges targets crowding a sector of the ring display, as configured by the
+/// [`SectorClustering`] resource, into one cluster target badged with their count.
+/// Clicking a cluster's marker expands it back into its members, which then stay out of
+/// clustering. Feed updates for a merged target are folded into its cluster.
+pub struct SectorClusterPlugin;
+
+impl Plugin for SectorClusterPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<SectorClustering>() {
+            app.init_resource::<SectorClustering>();
+        }
+        // Before the display lays out the targets this frame.
+        app.add_system_to_stage(stage::PRE_UPDATE, sector_cluster_system.system());
+    }
+}
+
+#[derive(Default)]
+struct SectorClusterState {
+    clicks: EventReader<DisplayEvent>,
+    removals: EventReader<TargetRemoved>,
+    /// Targets of expanded clusters.
+    expanded: HashSet<i32>,
+    settings: Option<SectorClustering>,
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn sector_cluster_system(
+    mut commands: Commands,
+    mut state: Local<SectorClusterState>,
+    settings: Res<SectorClustering>,
+    display_events: Res<Events<DisplayEvent>>,
+    removed_events: Res<Events<TargetRemoved>>,
+    changed: Query<Without<TargetCluster, (Entity, Changed<Target>)>>,
+    targets: Query<Without<TargetCluster, (Entity, &Target)>>,
+    clusters: Query<(Entity, &Target, &TargetCluster)>,
+) {
+    let state = &mut *state;
+    let mut dirty = state.settings != Some(*settings)
+        || changed.iter().next().is_some()
+        || !targets.removed::<Target>().is_empty();
+    state.settings = Some(*settings);
+
+    let mut members = clusters
+        .iter()
+        .map(|(entity, _, cluster)| (entity, cluster.members.clone()))
+        .collect::<HashMap<_, _>>();
+    for event in state.clicks.iter(&display_events) {
+        let id = match event {
+            DisplayEvent::Clicked {
+                target: Some(id), ..
+            } => *id,
+            _ => continue,
+        };
+        if let Some((entity, ..)) = clusters.iter().find(|(_, t, _)| t.id == id) {
+            for member in members.remove(&entity).unwrap_or_default() {
+                state.expanded.insert(member.id);
+                commands.spawn((member,));
+            }
+            commands.despawn(entity);
+        }
+    }
+    for TargetRemoved(id) in state.removals.iter(&removed_events) {
+        state.expanded.remove(id);
+        for members in members.values_mut() {
+            let before = members.len();
+            members.retain(|t| t.id != *id);
+            dirty |= members.len() != before;
+        }
+    }
+    if !dirty {
+        return;
+    }
+
+    // Live entities win over merged copies of the same target.
+    let live = targets
+        .iter()
+        .filter(|(_, t)| !state.expanded.contains(&t.id))
+        .map(|(entity, t)| (t.id, (Some(entity), t.clone())))
+        .collect::<BTreeMap<_, _>>();
+    let mut pool = members
+        .values()
+        .flatten()
+        .map(|t| (t.id, (None, t.clone())))
+        .collect::<BTreeMap<_, _>>();
+    pool.extend(live);
+    let pool = pool.into_values().collect::<Vec<_>>();
+    // Targets of different origins are never merged.
+    let mut by_origin = BTreeMap::<usize, Vec<usize>>::new();
+    for (i, (_, t)) in pool.iter().enumerate() {
+        by_origin.entry(t.origin).or_default().push(i);
+    }
+    let groups = by_origin.values().flat_map(|indices| {
+        let polar = indices
+            .iter()
+            .map(|&i| (pool[i].1.azimuth, pool[i].1.dist))
+            .collect::<Vec<_>>();
+        cluster_sectors(&polar, &settings)
+            .into_iter()
+            .map(move |group| group.into_iter().map(|j| indices[j]).collect::<Vec<_>>())
+    });
+
+    let mut kept = HashSet::new();
+    for group in groups {
+        let group_members = group.iter().map(|&i| &pool[i]).collect::<Vec<_>>();
+        if group.len() < settings.min_size.max(2) {
+            for (entity, target) in group_members {
+                if entity.is_none() {
+                    commands.spawn((target.clone(),));
+                }
+            }
+            continue;
+        }
+        let ids = group_members
+            .iter()
+            .map(|(_, t)| t.id)
+            .collect::<BTreeSet<_>>();
+        let existing = members.iter().find(|(_, m)| {
+            m.len() == ids.len()
+                && m.iter().all(|t| ids.contains(&t.id))
+                && group_members.iter().all(|(entity, _)| entity.is_none())
+        });
+        if let Some((entity, _)) = existing {
+            kept.insert(*entity);
+            continue;
+        }
+        let cluster = TargetCluster {
+            members: group_members.iter().map(|(_, t)| t.clone()).collect(),
+        };
+        for (entity, _) in group_members {
+            if let Some(entity) = entity {
+                commands.despawn(*entity);
+            }
+        }
+        commands.spawn((cluster.target(), cluster));
+    }
+    for (entity, ..) in clusters.iter() {
+        if !kept.contains(&entity) {
+            commands.despawn(entity);
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::display::{restyle_leaders, MarkerContext, Poi, RadarDisplay, Slot};
+use crate::easing::EasingConfig;
+use crate::emphasis::Emphasis;
+use crate::feed::{add_feeds, FeedSource, TargetFeeds};
+use crate::layout::LayoutConfig;
+use crate::motion::{LeaderLine, PolarTween, TweenConfig};
+use crate::style::{CategoryStyle, LinePattern, LineStyle, MarkerShape, StyleRegistry};
+use crate::target::Target;
+use crate::zones::{AlertZone, AlertZones};
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("{0}: {1}")]
+    Parse(String, String),
+    #[error("{0}: layout: {1}")]
+    Layout(String, String),
+    #[error("{0}: bindings: {1}")]
+    Bindings(String, String),
+    #[error("{path}: style {style:?}: {color:?} is not a color, expected #rrggbb or #rrggbbaa")]
+    Color {
+        path: String,
+        style: String,
+        color: String,
+    },
+}
+
+/// How a category's leader lines are written in a [`DebrisConfig`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LeaderConfig {
+    /// `#rrggbb` or `#rrggbbaa`; the theme's stroke color when left out.
+    pub color: Option<String>,
+    pub width: f32,
+    pub pattern: LinePattern,
+    pub arrowhead: bool,
+    pub to_marker_edge: bool,
+}
+
+impl Default for LeaderConfig {
+    fn default() -> Self {
+        let style = LineStyle::default();
+        LeaderConfig {
+            color: None,
+            width: style.width,
+            pattern: style.pattern,
+            arrowhead: style.arrowhead,
+            to_marker_edge: style.to_marker_edge,
+        }
+    }
+}
+
+/// How a category's markers and leader lines are written in a [`DebrisConfig`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleConfig {
+    /// `#rrggbb` or `#rrggbbaa`; the theme's stroke color when left out.
+    pub color: Option<String>,
+    pub shape: MarkerShape,
+    pub stroke_width: f32,
+    pub leader: LeaderConfig,
+}
+
+impl Default for StyleConfig {
+    fn default() -> Self {
+        StyleConfig {
+            color: None,
+            shape: MarkerShape::default(),
+            stroke_width: CategoryStyle::default().stroke_width,
+            leader: LeaderConfig::default(),
+        }
+    }
+}
+
+fn parse_color(color: &str) -> Option<Color> {
+    Color::hex(color.strip_prefix('#')?).ok()
+}
+
+impl StyleConfig {
+    /// The style, with `path` and `name` to say where a bad color was written.
+    fn to_style(&self, path: &str, name: &str) -> Result<CategoryStyle, ConfigError> {
+        let color = |color: &Option<String>| match color {
+            None => Ok(None),
+            Some(color) => parse_color(color)
+                .map(Some)
+                .ok_or_else(|| ConfigError::Color {
+                    path: path.to_string(),
+                    style: name.to_string(),
+                    color: color.clone(),
+                }),
+        };
+        Ok(CategoryStyle {
+            color: color(&self.color)?,
+            shape: self.shape,
+            stroke_width: self.stroke_width,
+            leader: LineStyle {
+                color: color(&self.leader.color)?,
+                width: self.leader.width,
+                pattern: self.leader.pattern,
+                arrowhead: self.leader.arrowhead,
+                to_marker_edge: self.leader.to_marker_edge,
+            },
+        })
+    }
+}
+
+/// The settings of a deployment, read from a RON file or, with a `.toml` extension, a
+/// TOML file. Everything left out takes its default, e.g. in RON:
+///
+/// ```text
+/// (
+///     layout: (ring_spacing: 40.0, stagger: true),
+///     styles: {
+///         "vessel": (color: Some("#3080ff"), shape: diamond),
+///         "buoy": (shape: circle, leader: (pattern: dashed(dash: 4.0, gap: 3.0))),
+///     },
+///     feeds: ["udp://0.0.0.0:7400"],
+///     easing: (tween: elastic, fade: quad_in),
+/// )
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DebrisConfig {
+    pub layout: LayoutConfig,
+    /// Styles by category. When any are given they replace the built-in ones.
+    pub styles: BTreeMap<String, StyleConfig>,
+    /// Style of targets without a listed category, the built-in one when left out.
+    pub fallback: Option<StyleConfig>,
+    /// Where to take target updates from, see [`TargetFeedPlugin`](crate::feed::TargetFeedPlugin).
+    pub feeds: Vec<FeedSource>,
+    /// How each kind of animation eases.
+    pub easing: EasingConfig,
+    /// Zones that raise alerts, see [`AlertZonesPlugin`](crate::zones::AlertZonesPlugin).
+    pub zones: Vec<AlertZone>,
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+impl DebrisConfig {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let name = path.display().to_string();
+        let text = fs::read_to_string(path).map_err(|e| ConfigError::Io(name.clone(), e))?;
+        let config: DebrisConfig = if is_toml(path) {
+            toml::from_str(&text).map_err(|e| ConfigError::Parse(name.clone(), e.to_string()))?
+        } else {
+            ron::de::from_str(&text).map_err(|e| ConfigError::Parse(name.clone(), e.to_string()))?
+        };
+        // Catch bad colors and spacing on loading rather than when applying.
+        config
+            .layout
+            .validate()
+            .map_err(|e| ConfigError::Layout(name.clone(), e))?;
+        config.apply_styles(&name, &mut StyleRegistry::empty())?;
+        Ok(config)
+    }
+
+    /// Sets the styles written in the config in `registry`, leaving those that are
+    /// left out and any per-target leader styles alone.
+    pub fn apply_styles(
+        &self,
+        path: &str,
+        registry: &mut StyleRegistry,
+    ) -> Result<(), ConfigError> {
+        if let Some(fallback) = &self.fallback {
+            registry.fallback = fallback.to_style(path, "fallback")?;
+        }
+        if self.styles.is_empty() {
+            return Ok(());
+        }
+        let listed = registry
+            .categories()
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        for name in listed {
+            if !self.styles.contains_key(&name) {
+                registry.remove(&name);
+            }
+        }
+        for (name, style) in &self.styles {
+            registry.insert(name.clone(), style.to_style(path, name)?);
+        }
+        Ok(())
+    }
+}
+
+/// Watches the [`DebrisConfig`] at `path`, checking every `poll` seconds whether the
+/// file changed, and applies it again when it did: a new layout lays the display out
+/// again, markers and leader lines take on new styles in place, marker tweens take on
+/// a new easing, feeds are opened and closed to match, see [`TargetFeeds`], and the
+/// [`AlertZones`] are replaced by those written. A file that fails to load is reported
+/// and the settings before it are kept. The config is expected to be applied at
+/// startup already, e.g. through `PoiRingPlugin::config` and
+/// [`DebrisConfig::apply_styles`], so the first check only notes the file's time.
+/// Needs [`PoiRingPlugin`](crate::display::PoiRingPlugin).
+#[derive(Debug, Clone)]
+pub struct ConfigPlugin {
+    pub path: PathBuf,
+    pub poll: f32,
+}
+
+struct ConfigWatch {
+    path: PathBuf,
+    poll: f32,
+    since_poll: f32,
+    modified: Option<SystemTime>,
+    applied: Option<DebrisConfig>,
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_feeds(app);
+        if !app.resources().contains::<AlertZones>() {
+            app.init_resource::<AlertZones>();
+        }
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        app.add_resource(ConfigWatch {
+            path: self.path.clone(),
+            poll: self.poll,
+            since_poll: 0.0,
+            modified,
+            applied: DebrisConfig::load(&self.path).ok(),
+        })
+        .add_system(config_watch_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn config_watch_system(
+    time: Res<Time>,
+    mut watch: ResMut<ConfigWatch>,
+    (mut layout, mut tween): (ResMut<LayoutConfig>, ResMut<TweenConfig>),
+    mut display: ResMut<RadarDisplay>,
+    (mut feeds, mut zones): (ResMut<TargetFeeds>, ResMut<AlertZones>),
+    (mut meshes, mut materials): (ResMut<Assets<Mesh>>, ResMut<Assets<ColorMaterial>>),
+    targets: Query<&Target>,
+    mut markers: Query<
+        With<
+            Poi,
+            (
+                &Target,
+                &Emphasis,
+                &Handle<ColorMaterial>,
+                Mut<Handle<Mesh>>,
+            ),
+        >,
+    >,
+    mut leaders: Query<(
+        &Slot,
+        Mut<LeaderLine>,
+        &PolarTween,
+        &Handle<Mesh>,
+        &Handle<ColorMaterial>,
+    )>,
+) {
+    watch.since_poll += time.delta_seconds;
+    if watch.since_poll < watch.poll {
+        return;
+    }
+    watch.since_poll = 0.0;
+    let modified = fs::metadata(&watch.path).and_then(|m| m.modified()).ok();
+    if modified.is_none() || modified == watch.modified {
+        return;
+    }
+    watch.modified = modified;
+    let name = watch.path.display().to_string();
+    let config = match DebrisConfig::load(&watch.path) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::warn!("config not reloaded: {}", e);
+            return;
+        }
+    };
+    if watch.applied.as_ref() == Some(&config) {
+        return;
+    }
+    let before = watch.applied.replace(config.clone()).unwrap_or_default();
+    tracing::info!("config reloaded from {}", name);
+
+    // What the display derives at runtime stays as it is.
+    let new_layout = LayoutConfig {
+        marker_scale: layout.marker_scale,
+        label_width: layout.label_width,
+        ..config.layout
+    };
+    if new_layout != *layout {
+        *layout = new_layout;
+    }
+    // Tweens already running keep their easing until retargeted.
+    if tween.easing != config.easing.tween {
+        tween.easing = config.easing.tween;
+    }
+    if config.styles != before.styles || config.fallback != before.fallback {
+        if let Err(e) = config.apply_styles(&name, display.categories_mut()) {
+            tracing::warn!("config styles not applied: {}", e);
+        }
+        restyle_markers(
+            &display,
+            layout.poi_width,
+            &mut meshes,
+            &mut materials,
+            &mut markers,
+        );
+        restyle_leaders(
+            display.categories(),
+            layout.poi_width,
+            &targets,
+            &mut leaders,
+            &mut meshes,
+        );
+    }
+    if config.zones != before.zones {
+        zones.zones = config.zones.clone();
+    }
+    for &source in &before.feeds {
+        if !config.feeds.contains(&source) {
+            feeds.close(source);
+        }
+    }
+    for &source in &config.feeds {
+        if let Err(e) = feeds.open(source) {
+            tracing::warn!("target feed {} disabled: {}", source, e);
+        }
+    }
+}
+
+/// Rebuilds each marker's outline, and recolors it if its category has a color, like
+/// [`EmphasisPlugin`](crate::emphasis::EmphasisPlugin) does for a change of emphasis.
+#[allow(clippy::type_complexity)]
+fn restyle_markers(
+    display: &RadarDisplay,
+    poi_width: f32,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut Assets<ColorMaterial>,
+    markers: &mut Query<
+        With<
+            Poi,
+            (
+                &Target,
+                &Emphasis,
+                &Handle<ColorMaterial>,
+                Mut<Handle<Mesh>>,
+            ),
+        >,
+    >,
+) {
+    for (target, emphasis, material, mut mesh) in markers.iter_mut() {
+        let category = display.categories().style(target);
+        if let (Some(color), Some(material)) = (category.color, materials.get_mut(material)) {
+            let mut color = color;
+            color.set_a(emphasis.alpha());
+            material.color = color;
+        }
+        let mut ctx = MarkerContext {
+            meshes,
+            materials,
+            material: material.clone(),
+            poi_width,
+            stroke_width: category.stroke_width * emphasis.stroke_width(),
+            shape: category.shape,
+        };
+        *mesh = display.marker(target, &mut ctx).mesh;
+    }
+}
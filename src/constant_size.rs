@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+
+use crate::display::{Poi, PoiLabel};
+use crate::layout::LayoutConfig;
+use crate::motion::PolarTween;
+
+/// Steps per doubling of the zoom at which the layout is redone for the new marker size.
+const LAYOUT_STEPS_PER_OCTAVE: f32 = 4.0;
+
+/// The offset from its placement, and for labels the font size, an entity had before
+/// [`ConstantSizePlugin`] scaled it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unscaled {
+    pub offset: Vec2,
+    pub font_size: Option<f32>,
+}
+
+/// Keeps markers and labels a fixed pixel size while zooming, like map annotations,
+/// while the rings scale with the view. Markers and labels are counter-scaled against
+/// the 2D camera every frame, and [`LayoutConfig::marker_scale`] follows the zoom in
+/// quarter-octave steps so the rings keep room for markers at their drawn size. Labels
+/// already keep their size, so [`LabelZoomPlugin`](crate::label_zoom::LabelZoomPlugin)
+/// is not needed alongside.
+pub struct ConstantSizePlugin;
+
+impl Plugin for ConstantSizePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(constant_size_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn constant_size_system(
+    mut commands: Commands,
+    mut applied: Local<Option<f32>>,
+    mut config: ResMut<LayoutConfig>,
+    cameras: Query<(&Camera, &Transform)>,
+    mut markers: Query<(
+        Entity,
+        Option<&Unscaled>,
+        Mut<Transform>,
+        Mut<PolarTween>,
+        Mut<Poi>,
+    )>,
+    mut labels: Query<
+        Without<
+            Poi,
+            With<
+                PoiLabel,
+                (
+                    Entity,
+                    Option<&Unscaled>,
+                    Mut<Transform>,
+                    Mut<PolarTween>,
+                    Mut<Text>,
+                ),
+            >,
+        >,
+    >,
+) {
+    let scale = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, transform)) => transform.scale.x(),
+        None => return,
+    };
+    if scale <= 0.0 {
+        return;
+    }
+    let rescale = *applied != Some(scale);
+    *applied = Some(scale);
+
+    let place = |transform: &mut Transform, tween: &mut PolarTween, offset: Vec2| {
+        tween.offset = offset * scale;
+        let at = tween.point() + tween.offset;
+        let mut translation = transform.translation;
+        translation.set_x(at.x());
+        translation.set_y(at.y());
+        transform.translation = translation;
+    };
+    for (entity, unscaled, mut transform, mut tween, mut poi) in markers.iter_mut() {
+        let offset = match unscaled {
+            Some(_) if !rescale => continue,
+            Some(unscaled) => unscaled.offset,
+            None => {
+                let offset = tween.offset;
+                commands.insert_one(
+                    entity,
+                    Unscaled {
+                        offset,
+                        font_size: None,
+                    },
+                );
+                offset
+            }
+        };
+        transform.scale = Vec3::new(scale, scale, transform.scale.z());
+        place(&mut transform, &mut tween, offset);
+        poi.half_width = config.poi_width * scale / 2.0;
+    }
+    for (entity, unscaled, mut transform, mut tween, mut text) in labels.iter_mut() {
+        let (offset, font_size) = match unscaled {
+            Some(_) if !rescale => continue,
+            Some(unscaled) => (
+                unscaled.offset,
+                unscaled.font_size.unwrap_or(text.style.font_size),
+            ),
+            None => {
+                let unscaled = Unscaled {
+                    offset: tween.offset,
+                    font_size: Some(text.style.font_size),
+                };
+                commands.insert_one(entity, unscaled);
+                (unscaled.offset, text.style.font_size)
+            }
+        };
+        place(&mut transform, &mut tween, offset);
+        text.style.font_size = font_size * scale;
+    }
+
+    let steps = (scale.log2() * LAYOUT_STEPS_PER_OCTAVE).round() / LAYOUT_STEPS_PER_OCTAVE;
+    let marker_scale = steps.exp2();
+    if config.marker_scale != marker_scale {
+        config.marker_scale = marker_scale;
+    }
+}
@@ -0,0 +1,285 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::geo::Polar;
+
+/// How positions are written in readouts and tooltips. Cycle through the formats with
+/// [`Action::CycleCoordFormat`], F9 by default, once [`CoordsPlugin`] is added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoordFormat {
+    /// `48.85830°N 2.29450°E`, bearings as `123.4°`.
+    #[default]
+    DecimalDegrees,
+    /// `48°51'29.9"N 2°17'40.2"E`, bearings as `123°24'00"`.
+    Dms,
+    /// Military grid reference, e.g. `31U DQ 48251 11943`. Falls back to decimal
+    /// degrees in the polar regions MGRS leaves to UPS.
+    #[cfg(feature = "mgrs")]
+    Mgrs,
+    /// Bearing and range from the display origin. Geodetic positions, which have no
+    /// such origin, are written in decimal degrees.
+    BearingRange,
+}
+
+impl CoordFormat {
+    pub const ALL: &'static [CoordFormat] = &[
+        CoordFormat::DecimalDegrees,
+        CoordFormat::Dms,
+        #[cfg(feature = "mgrs")]
+        CoordFormat::Mgrs,
+        CoordFormat::BearingRange,
+    ];
+
+    /// The format after this one, wrapping around.
+    pub fn next(self) -> Self {
+        let i = CoordFormat::ALL
+            .iter()
+            .position(|f| *f == self)
+            .unwrap_or(0);
+        CoordFormat::ALL[(i + 1) % CoordFormat::ALL.len()]
+    }
+
+    /// A geodetic position, both in degrees.
+    pub fn format_geo(self, lat: f32, lon: f32) -> String {
+        match self {
+            CoordFormat::Dms => format!(
+                "{} {}",
+                dms(lat.abs(), if lat < 0.0 { 'S' } else { 'N' }),
+                dms(lon.abs(), if lon < 0.0 { 'W' } else { 'E' })
+            ),
+            #[cfg(feature = "mgrs")]
+            CoordFormat::Mgrs => mgrs::format(lat as f64, lon as f64)
+                .unwrap_or_else(|| CoordFormat::DecimalDegrees.format_geo(lat, lon)),
+            CoordFormat::DecimalDegrees | CoordFormat::BearingRange => format!(
+                "{:.5}\u{b0}{} {:.5}\u{b0}{}",
+                lat.abs(),
+                if lat < 0.0 { 'S' } else { 'N' },
+                lon.abs(),
+                if lon < 0.0 { 'W' } else { 'E' }
+            ),
+        }
+    }
+
+    /// A position relative to the display origin, written as its compass bearing,
+    /// clockwise from north, and range.
+    pub fn format_polar(self, polar: Polar) -> String {
+        let (degrees, range) = (polar.bearing(), polar.dist);
+        match self {
+            CoordFormat::Dms => format!(
+                "bearing {}\nrange {:.1}",
+                dms(degrees, ' ').trim_end(),
+                range
+            ),
+            _ => format!("bearing {:.1}\u{b0}\nrange {:.1}", degrees, range),
+        }
+    }
+}
+
+impl fmt::Display for CoordFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CoordFormat::DecimalDegrees => "decimal degrees",
+            CoordFormat::Dms => "DMS",
+            #[cfg(feature = "mgrs")]
+            CoordFormat::Mgrs => "MGRS",
+            CoordFormat::BearingRange => "bearing/range",
+        })
+    }
+}
+
+fn dms(degrees: f32, hemisphere: char) -> String {
+    let total = (degrees as f64 * 36000.0).round() as u64;
+    let (d, rest) = (total / 36000, total % 36000);
+    let (m, s) = (rest / 600, rest % 600);
+    format!(
+        "{}\u{b0}{:02}'{:02}.{}\"{}",
+        d,
+        m,
+        s / 10,
+        s % 10,
+        hemisphere
+    )
+}
+
+/// Makes [`CoordFormat`] a resource and cycles it with [`Action::CycleCoordFormat`].
+pub struct CoordsPlugin;
+
+impl Plugin for CoordsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<CoordFormat>() {
+            app.init_resource::<CoordFormat>();
+        }
+        app.add_system(cycle_system.system());
+    }
+}
+
+fn cycle_system(actions: Res<ActionState>, mut format: ResMut<CoordFormat>) {
+    if actions.just_pressed(Action::CycleCoordFormat) {
+        *format = format.next();
+    }
+}
+
+#[cfg(feature = "mgrs")]
+mod mgrs {
+    const A: f64 = 6_378_137.0;
+    const F: f64 = 1.0 / 298.257_223_563;
+    const K0: f64 = 0.9996;
+    const BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWXX";
+    const ROWS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+    const COLUMNS: [&[u8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+
+    fn zone(lat: f64, lon: f64) -> u32 {
+        if (56.0..64.0).contains(&lat) && (3.0..12.0).contains(&lon) {
+            return 32;
+        }
+        if (72.0..=84.0).contains(&lat) && (0.0..42.0).contains(&lon) {
+            return match lon {
+                l if l < 9.0 => 31,
+                l if l < 21.0 => 33,
+                l if l < 33.0 => 35,
+                _ => 37,
+            };
+        }
+        (((lon + 180.0) / 6.0).floor() as u32 % 60) + 1
+    }
+
+    /// Easting and northing in metres within `zone`.
+    fn utm(lat: f64, lon: f64, zone: u32) -> (f64, f64) {
+        let e2 = F * (2.0 - F);
+        let ep2 = e2 / (1.0 - e2);
+        let phi = lat.to_radians();
+        let lon0 = ((zone as f64 - 1.0) * 6.0 - 180.0 + 3.0).to_radians();
+        let n = A / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+        let t = phi.tan().powi(2);
+        let c = ep2 * phi.cos().powi(2);
+        let a = phi.cos() * (lon.to_radians() - lon0);
+        let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+        let m = A
+            * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+                - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+                + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+                - (35.0 * e6 / 3072.0) * (6.0 * phi).sin());
+        let easting = K0
+            * n
+            * (a + (1.0 - t + c) * a.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0)
+            + 500_000.0;
+        let mut northing = K0
+            * (m + n
+                * phi.tan()
+                * (a * a / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+        if lat < 0.0 {
+            northing += 10_000_000.0;
+        }
+        (easting, northing)
+    }
+
+    /// `None` outside the UTM latitudes (80°S to 84°N).
+    pub fn format(lat: f64, lon: f64) -> Option<String> {
+        if !(-80.0..=84.0).contains(&lat) {
+            return None;
+        }
+        let zone = zone(lat, lon);
+        let (easting, northing) = utm(lat, lon, zone);
+        let band = BANDS[((lat + 80.0) / 8.0).floor() as usize] as char;
+        let set = ((zone - 1) % 3) as usize;
+        let column =
+            COLUMNS[set][((easting / 100_000.0).floor() as usize).saturating_sub(1)] as char;
+        let row_offset = if zone.is_multiple_of(2) { 5 } else { 0 };
+        let row = ROWS[((northing / 100_000.0).floor() as usize + row_offset) % ROWS.len()] as char;
+        Some(format!(
+            "{}{} {}{} {:05} {:05}",
+            zone,
+            band,
+            column,
+            row,
+            (easting % 100_000.0).floor() as u32,
+            (northing % 100_000.0).floor() as u32
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::units::parse_geo;
+
+    /// The Eiffel Tower.
+    const EIFFEL: (f32, f32) = (48.8583, 2.2945);
+
+    #[test]
+    fn decimal_degrees_round_trip() {
+        let (lat, lon) = (-33.8568, 151.2153);
+        let text = CoordFormat::DecimalDegrees.format_geo(lat, lon);
+        assert_eq!(text, "33.85680\u{b0}S 151.21530\u{b0}E");
+        let (back_lat, back_lon) = parse_geo(&text).unwrap();
+        assert!((back_lat - lat).abs() < 1e-5 && (back_lon - lon).abs() < 1e-5);
+    }
+
+    #[test]
+    fn dms_round_trip() {
+        let (lat, lon) = EIFFEL;
+        // Written to a tenth of a second.
+        let tenth = 0.1 / 3600.0;
+        let text = CoordFormat::Dms.format_geo(lat, lon);
+        assert_eq!(text, "48\u{b0}51'29.9\"N 2\u{b0}17'40.2\"E");
+        let (back_lat, back_lon) = parse_geo(&text).unwrap();
+        assert!((back_lat - lat).abs() < tenth && (back_lon - lon).abs() < tenth);
+
+        let text = CoordFormat::Dms.format_geo(-lat, -lon);
+        assert_eq!(text, "48\u{b0}51'29.9\"S 2\u{b0}17'40.2\"W");
+        let (back_lat, back_lon) = parse_geo(&text).unwrap();
+        assert!((back_lat + lat).abs() < tenth && (back_lon + lon).abs() < tenth);
+    }
+
+    #[test]
+    fn dms_carries_rounded_seconds() {
+        // 59.99 seconds round up to the next minute rather than to 60.0 seconds.
+        let degrees = 10.0 + 59.0 / 60.0 + 59.99 / 3600.0;
+        assert_eq!(dms(degrees, 'N'), "11\u{b0}00'00.0\"N");
+    }
+
+    #[test]
+    fn polar_is_a_compass_bearing() {
+        let east = Polar::new(0.0, 5.0);
+        assert_eq!(
+            CoordFormat::DecimalDegrees.format_polar(east),
+            "bearing 90.0\u{b0}\nrange 5.0"
+        );
+        assert_eq!(
+            CoordFormat::Dms.format_polar(east),
+            "bearing 90\u{b0}00'00.0\"\nrange 5.0"
+        );
+        let west = Polar::new(PI, 12.0);
+        assert_eq!(
+            CoordFormat::DecimalDegrees.format_polar(west),
+            "bearing 270.0\u{b0}\nrange 12.0"
+        );
+    }
+
+    #[cfg(feature = "mgrs")]
+    #[test]
+    fn mgrs_known_points() {
+        // On zone 31's central meridian: 500 km east, and north by the meridian arc
+        // from the equator, scaled by 0.9996.
+        assert_eq!(CoordFormat::Mgrs.format_geo(0.0, 3.0), "31N EA 00000 00000");
+        assert_eq!(
+            CoordFormat::Mgrs.format_geo(45.0, 3.0),
+            "31T EK 00000 82950"
+        );
+        let (lat, lon) = EIFFEL;
+        assert_eq!(CoordFormat::Mgrs.format_geo(lat, lon), "31U DQ 48251 11943");
+        // Beyond 84°N MGRS leaves to UPS, so the position is written in degrees.
+        assert_eq!(
+            CoordFormat::Mgrs.format_geo(85.0, 0.0),
+            CoordFormat::DecimalDegrees.format_geo(85.0, 0.0)
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use serde::{Deserialize, Serialize};
+
+/// Step used to estimate surface normals from the volume's parametrization.
+const NORMAL_EPSILON: f32 = 1e-3;
+
+/// The space a sensor sees: everything between `min_range` and `max_range` whose
+/// elevation lies between `min_elevation` and `max_elevation` and whose azimuth is
+/// within `width / 2` of `azimuth`. Angles are in radians. In the volume's own frame z
+/// is up and azimuth runs counter-clockwise from +x, as on the ring display, so a
+/// north-up placement puts +x east and +y north.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CoverageVolume {
+    #[serde(default)]
+    pub min_range: f32,
+    pub max_range: f32,
+    #[serde(default)]
+    pub min_elevation: f32,
+    #[serde(default = "zenith")]
+    pub max_elevation: f32,
+    /// Boresight azimuth.
+    #[serde(default)]
+    pub azimuth: f32,
+    /// Azimuth extent; `2π` or more covers all around.
+    #[serde(default = "full_circle")]
+    pub width: f32,
+}
+
+fn zenith() -> f32 {
+    PI / 2.0
+}
+
+fn full_circle() -> f32 {
+    PI * 2.0
+}
+
+impl CoverageVolume {
+    /// A cone opening `half_angle` either side of straight up, out to `range`.
+    pub fn cone(half_angle: f32, range: f32) -> Self {
+        CoverageVolume {
+            min_range: 0.0,
+            max_range: range,
+            min_elevation: PI / 2.0 - half_angle,
+            max_elevation: PI / 2.0,
+            azimuth: 0.0,
+            width: PI * 2.0,
+        }
+    }
+
+    /// A spherical sector all around between two elevations and two ranges.
+    pub fn sector(min_elevation: f32, max_elevation: f32, min_range: f32, max_range: f32) -> Self {
+        CoverageVolume {
+            min_range,
+            max_range,
+            min_elevation,
+            max_elevation,
+            azimuth: 0.0,
+            width: PI * 2.0,
+        }
+    }
+
+    /// Whether the volume closes on itself around the vertical.
+    pub fn is_all_around(&self) -> bool {
+        self.width >= PI * 2.0
+    }
+
+    /// The point at `range`, `elevation` and `azimuth` in the volume's frame.
+    pub fn point(range: f32, elevation: f32, azimuth: f32) -> Vec3 {
+        let (sin_e, cos_e) = elevation.sin_cos();
+        let (sin_a, cos_a) = azimuth.sin_cos();
+        Vec3::new(range * cos_e * cos_a, range * cos_e * sin_a, range * sin_e)
+    }
+
+    /// A closed triangle mesh of the volume's boundary in its own frame, with
+    /// `segments` steps around the widest curve and proportionally fewer elsewhere.
+    /// Faces are two-sided so the volume reads the same from inside and out.
+    pub fn mesh(&self, segments: usize) -> Mesh {
+        let mut builder = VolumeBuilder::default();
+        let width = self.width.min(PI * 2.0);
+        let (a0, a1) = (self.azimuth - width / 2.0, self.azimuth + width / 2.0);
+        let (e0, e1) = (
+            self.min_elevation,
+            self.max_elevation.max(self.min_elevation),
+        );
+        let (r0, r1) = (self.min_range.max(0.0), self.max_range.max(self.min_range));
+        let steps = |span: f32| ((segments as f32 * span / (PI * 2.0)).ceil() as usize).max(1);
+        let (na, ne) = (steps(width), steps(e1 - e0).max(2));
+        let lerp = |from: f32, to: f32, t: f32| from + (to - from) * t;
+
+        // Outer and inner shells.
+        for &r in &[r1, r0] {
+            if r > 0.0 {
+                builder.patch(na, ne, |u, v| {
+                    Self::point(r, lerp(e0, e1, v), lerp(a0, a1, u))
+                });
+            }
+        }
+        // Lower and upper cones; the upper one vanishes at the zenith.
+        for &e in &[e0, e1] {
+            if e < PI / 2.0 && r1 > r0 {
+                builder.patch(na, 1, |u, v| {
+                    Self::point(lerp(r0, r1, v), e, lerp(a0, a1, u))
+                });
+            }
+        }
+        // Side walls of a partial sector.
+        if !self.is_all_around() && r1 > r0 {
+            for &a in &[a0, a1] {
+                builder.patch(ne, 1, |u, v| {
+                    Self::point(lerp(r0, r1, v), lerp(e0, e1, u), a)
+                });
+            }
+        }
+        builder.build()
+    }
+}
+
+#[derive(Default)]
+struct VolumeBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl VolumeBuilder {
+    /// Adds the surface `f(u, v)` for `u` and `v` in `0..=1`, in `nu` by `nv` quads,
+    /// once facing each way.
+    fn patch(&mut self, nu: usize, nv: usize, f: impl Fn(f32, f32) -> Vec3) {
+        for &flip in &[false, true] {
+            let base = self.positions.len() as u32;
+            for j in 0..=nv {
+                for i in 0..=nu {
+                    let (u, v) = (i as f32 / nu as f32, j as f32 / nv as f32);
+                    let p = f(u, v);
+                    let du =
+                        f((u + NORMAL_EPSILON).min(1.0), v) - f((u - NORMAL_EPSILON).max(0.0), v);
+                    let dv =
+                        f(u, (v + NORMAL_EPSILON).min(1.0)) - f(u, (v - NORMAL_EPSILON).max(0.0));
+                    let mut n = du.cross(dv);
+                    if n.length_squared() <= f32::EPSILON {
+                        // Apex or pole of the parametrization.
+                        n = if p.length_squared() > 0.0 {
+                            p
+                        } else {
+                            Vec3::unit_z()
+                        };
+                    }
+                    let n = if flip { -n.normalize() } else { n.normalize() };
+                    self.positions.push([p.x(), p.y(), p.z()]);
+                    self.normals.push([n.x(), n.y(), n.z()]);
+                    self.uvs.push([u, v]);
+                }
+            }
+            let row = nu as u32 + 1;
+            for j in 0..nv as u32 {
+                for i in 0..nu as u32 {
+                    let a = base + j * row + i;
+                    let (b, c, d) = (a + 1, a + row, a + row + 1);
+                    if flip {
+                        self.indices.extend_from_slice(&[a, c, b, b, c, d]);
+                    } else {
+                        self.indices.extend_from_slice(&[a, b, c, b, d, c]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs.into());
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh
+    }
+}
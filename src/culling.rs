@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::{CAMERA2D, CAMERA3D};
+
+use crate::billboard::BillboardText;
+use crate::display::{Poi, PoiLabel};
+use crate::geo_marker::GeoMarkerPin;
+use crate::pointer::screen_to_world;
+
+/// What [`CullingPlugin`] hides.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Culling {
+    pub enabled: bool,
+    /// Window pixels around the window's edge within which entities still count as in
+    /// view, so that those partly inside aren't cut off.
+    pub margin: f32,
+    /// Globe markers farther than this from the 3D camera are hidden.
+    pub max_distance: Option<f32>,
+    /// Labels are hidden once the 2D camera is zoomed out past this many world units
+    /// per pixel.
+    pub label_max_scale: Option<f32>,
+}
+
+impl Default for Culling {
+    fn default() -> Self {
+        Culling {
+            enabled: true,
+            margin: 32.0,
+            max_distance: None,
+            label_max_scale: None,
+        }
+    }
+}
+
+/// How many entities [`CullingPlugin`] looked at and hid on the latest frame, shown by
+/// [`DebugOverlayPlugin`](crate::debug_overlay::DebugOverlayPlugin).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CullStats {
+    /// Entities that would have been drawn.
+    pub checked: usize,
+    /// Those hidden for being out of the camera's view.
+    pub outside: usize,
+    /// Those hidden for being too far away or zoomed out past.
+    pub too_far: usize,
+}
+
+impl CullStats {
+    pub fn hidden(&self) -> usize {
+        self.outside + self.too_far
+    }
+}
+
+/// On an entity [`CullingPlugin`] has looked at, whether it hid it this frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Culled(pub bool);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cull {
+    Keep,
+    Outside,
+    TooFar,
+}
+
+/// Hides the display's [`Poi`] markers and [`PoiLabel`]s outside the 2D camera's view,
+/// and pinned [`GeoMarker`](crate::geo_marker::GeoMarker)s outside the 3D camera's
+/// view or beyond [`Culling::max_distance`], along with their [`BillboardText`]
+/// labels; labels also go once zoomed out past [`Culling::label_max_scale`]. Only
+/// entities something else left visible are hidden, and they are shown again at the
+/// start of every frame, so that the systems owning their visibility decide as before
+/// and culling only ever takes away. Counts go to [`CullStats`].
+///
+/// Culling is against the main cameras: entities drawn only by a split view's camera
+/// can be hidden from it. Instanced pins are left to the GPU.
+pub struct CullingPlugin;
+
+impl Plugin for CullingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Culling>() {
+            app.init_resource::<Culling>();
+        }
+        app.init_resource::<CullStats>()
+            .add_system_to_stage(stage::PRE_UPDATE, cull_restore_system.system())
+            .add_system_to_stage(stage::POST_UPDATE, cull_system.system());
+    }
+}
+
+fn cull_restore_system(mut culled: Query<(Mut<Culled>, Mut<Draw>)>) {
+    for (mut culled, mut draw) in culled.iter_mut() {
+        if culled.0 {
+            culled.0 = false;
+            draw.is_visible = true;
+        }
+    }
+}
+
+/// The world rectangle the 2D camera shows, grown by `margin` window pixels, as its
+/// lower-left and upper-right corners.
+fn view_2d(window_size: Vec2, camera: &Transform, margin: f32) -> (Vec2, Vec2) {
+    let corners = [
+        Vec2::new(-margin, -margin),
+        Vec2::new(window_size.x() + margin, -margin),
+        Vec2::new(-margin, window_size.y() + margin),
+        window_size + Vec2::new(margin, margin),
+    ];
+    let world = corners
+        .iter()
+        .map(|&corner| screen_to_world(corner, window_size, camera))
+        .collect::<Vec<_>>();
+    let min = world.iter().fold(world[0], |a, &b| a.min(b));
+    let max = world.iter().fold(world[0], |a, &b| a.max(b));
+    (min, max)
+}
+
+fn hide(commands: &mut Commands, entity: Entity, culled: Option<Mut<Culled>>, draw: &mut Draw) {
+    draw.is_visible = false;
+    match culled {
+        Some(mut culled) => culled.0 = true,
+        None => {
+            commands.insert_one(entity, Culled(true));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn cull_system(
+    mut commands: Commands,
+    culling: Res<Culling>,
+    mut stats: ResMut<CullStats>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &Transform, &GlobalTransform)>,
+    mut pois: Query<With<Poi, (Entity, &GlobalTransform, Mut<Draw>, Option<Mut<Culled>>)>>,
+    mut labels: Query<With<PoiLabel, (Entity, &GlobalTransform, Mut<Draw>, Option<Mut<Culled>>)>>,
+    mut pins: Query<With<GeoMarkerPin, (Entity, &GlobalTransform, Mut<Draw>, Option<Mut<Culled>>)>>,
+    mut billboards: Query<(Entity, &BillboardText, Mut<Draw>, Option<Mut<Culled>>)>,
+) {
+    let mut counted = CullStats::default();
+    let window_size = match windows.get_primary() {
+        Some(window) if culling.enabled => Vec2::new(window.width() as f32, window.height() as f32),
+        _ => {
+            if *stats != counted {
+                *stats = counted;
+            }
+            return;
+        }
+    };
+    let mut count = |cull: Cull| {
+        counted.checked += 1;
+        match cull {
+            Cull::Keep => {}
+            Cull::Outside => counted.outside += 1,
+            Cull::TooFar => counted.too_far += 1,
+        }
+        cull != Cull::Keep
+    };
+
+    let camera_2d = cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA2D))
+        .map(|(_, transform, _)| *transform);
+    if let Some(camera) = camera_2d {
+        let (min, max) = view_2d(window_size, &camera, culling.margin);
+        let inside = |at: Vec3| {
+            let at = at.truncate();
+            at.cmpge(min).all() && at.cmple(max).all()
+        };
+        for (entity, transform, mut draw, culled) in pois.iter_mut() {
+            if !draw.is_visible {
+                continue;
+            }
+            let cull = if inside(transform.translation) {
+                Cull::Keep
+            } else {
+                Cull::Outside
+            };
+            if count(cull) {
+                hide(&mut commands, entity, culled, &mut draw);
+            }
+        }
+        let zoomed_out = culling
+            .label_max_scale
+            .is_some_and(|scale| camera.scale.x() > scale);
+        for (entity, transform, mut draw, culled) in labels.iter_mut() {
+            if !draw.is_visible {
+                continue;
+            }
+            let cull = if zoomed_out {
+                Cull::TooFar
+            } else if inside(transform.translation) {
+                Cull::Keep
+            } else {
+                Cull::Outside
+            };
+            if count(cull) {
+                hide(&mut commands, entity, culled, &mut draw);
+            }
+        }
+    }
+
+    let camera_3d = cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA3D))
+        .map(|(camera, _, eye)| (camera.projection_matrix, *eye));
+    let mut hidden_pins = HashMap::new();
+    if let Some((projection, eye)) = camera_3d {
+        let view_projection = projection * eye.compute_matrix().inverse();
+        // As far past ±1 as the margin reaches in normalized device coordinates.
+        let slack = Vec2::one() + Vec2::splat(2.0 * culling.margin) / window_size;
+        for (entity, transform, mut draw, culled) in pins.iter_mut() {
+            if !draw.is_visible {
+                continue;
+            }
+            let at = transform.translation;
+            let clip = view_projection * at.extend(1.0);
+            let ndc = Vec2::new(clip.x(), clip.y()) / clip.w();
+            let cull = if culling
+                .max_distance
+                .is_some_and(|max| (at - eye.translation).length() > max)
+            {
+                Cull::TooFar
+            } else if clip.w() <= 0.0 || ndc.abs().cmpgt(slack).any() {
+                Cull::Outside
+            } else {
+                Cull::Keep
+            };
+            if count(cull) {
+                hide(&mut commands, entity, culled, &mut draw);
+                hidden_pins.insert(entity, cull);
+            }
+        }
+    }
+    for (entity, label, mut draw, culled) in billboards.iter_mut() {
+        if !draw.is_visible {
+            continue;
+        }
+        let cull = hidden_pins
+            .get(&label.anchor)
+            .copied()
+            .unwrap_or(Cull::Keep);
+        if count(cull) {
+            hide(&mut commands, entity, culled, &mut draw);
+        }
+    }
+
+    if *stats != counted {
+        *stats = counted;
+    }
+}
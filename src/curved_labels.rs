@@ -0,0 +1,219 @@
+use ab_glyph::{Font as _, Glyph, PxScale, ScaleFont};
+use bevy::prelude::*;
+use bevy::render::draw::{DrawContext, DrawError, Drawable};
+use bevy::render::mesh;
+use bevy::render::pipeline::{PipelineSpecialization, VertexBufferDescriptor};
+use bevy::render::render_graph::base::Msaa;
+use bevy::render::renderer::{
+    AssetRenderResourceBindings, BindGroup, BufferUsage, RenderResourceBindings, RenderResourceId,
+};
+use bevy::render::stage as render_stage;
+use bevy::sprite::{TextureAtlas, TextureAtlasSprite, QUAD_HANDLE, SPRITE_SHEET_PIPELINE_HANDLE};
+use bevy::text::{FontAtlasSet, TextStyle};
+use bevy::ui::Node;
+
+use crate::display::PoiLabel;
+use crate::motion::PolarTween;
+
+/// Labels closer than this to the center of their ring, in world units, stay straight.
+const MIN_RADIUS: f32 = 1.0;
+
+/// Where a glyph centered `(across, up)` from the middle of a straight label goes when
+/// the label is bent along the circle about `center` through `at`, and how far it
+/// turns. Along the upper half of the circle text runs clockwise with its top outward,
+/// along the lower half counterclockwise with its top inward, so that it reads left
+/// to right and upright either way.
+pub fn arc_glyph(center: Vec2, at: Vec2, across: f32, up: f32) -> (Vec2, f32) {
+    let radial = at - center;
+    let radius = radial.length();
+    let azimuth = radial.y().atan2(radial.x());
+    let (turn, lift, tilt) = if azimuth < 0.0 {
+        (1.0, -1.0, std::f32::consts::FRAC_PI_2)
+    } else {
+        (-1.0, 1.0, -std::f32::consts::FRAC_PI_2)
+    };
+    let angle = azimuth + turn * across / radius;
+    let distance = radius + lift * up;
+    (
+        center + Vec2::new(angle.cos(), angle.sin()) * distance,
+        angle + tilt,
+    )
+}
+
+/// Draws every [`PoiLabel`] bent along its ring instead of straight across, each glyph
+/// placed and turned to follow the arc through the label's middle, so that labels on a
+/// dense ring run beside each other rather than sticking out into the next ring. The
+/// labels stay bevy_ui text that only draws differently, so moving, hiding, recoloring
+/// and resizing them works as before. Add after the UI plugin.
+pub struct CurvedLabelsPlugin;
+
+impl Plugin for CurvedLabelsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // After bevy_ui's text drawing, whose commands it replaces.
+        app.add_system_to_stage(render_stage::DRAW, curved_label_draw_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn curved_label_draw_system(
+    mut draw_context: DrawContext,
+    fonts: Res<Assets<Font>>,
+    msaa: Res<Msaa>,
+    (font_atlas_sets, texture_atlases, meshes): (
+        Res<Assets<FontAtlasSet>>,
+        Res<Assets<TextureAtlas>>,
+        Res<Assets<Mesh>>,
+    ),
+    mut render_resource_bindings: ResMut<RenderResourceBindings>,
+    mut asset_render_resource_bindings: ResMut<AssetRenderResourceBindings>,
+    mut labels: Query<With<PoiLabel, (Mut<Draw>, &Text, &Node, &GlobalTransform, &PolarTween)>>,
+) {
+    let quad_descriptor = match meshes.get(&QUAD_HANDLE) {
+        Some(quad) => quad.get_vertex_buffer_descriptor(),
+        None => return,
+    };
+    for (mut draw, text, node, transform, tween) in labels.iter_mut() {
+        let at = transform.translation.truncate();
+        let font = fonts.get(&text.font);
+        let atlas_set = font_atlas_sets.get(text.font.id);
+        let (font, font_atlas_set) = match (font, atlas_set) {
+            (Some(font), Some(atlas_set)) if (at - tween.origin).length() >= MIN_RADIUS => {
+                (font, atlas_set)
+            }
+            _ => continue,
+        };
+        draw.clear_render_commands();
+        let mut arc_text = ArcText {
+            font,
+            font_atlas_set,
+            texture_atlases: &texture_atlases,
+            render_resource_bindings: &mut render_resource_bindings,
+            asset_render_resource_bindings: &mut asset_render_resource_bindings,
+            center: tween.origin,
+            at,
+            z: transform.translation.z(),
+            size: node.size,
+            style: &text.style,
+            text: &text.value,
+            msaa: &msaa,
+            font_quad_vertex_descriptor: &quad_descriptor,
+        };
+        if let Err(e) = arc_text.draw(&mut draw, &mut draw_context) {
+            tracing::warn!("curved label: {:?}", e);
+        }
+    }
+}
+
+/// bevy_text's `DrawableText`, with each glyph put through [`arc_glyph`].
+struct ArcText<'a> {
+    font: &'a Font,
+    font_atlas_set: &'a FontAtlasSet,
+    texture_atlases: &'a Assets<TextureAtlas>,
+    render_resource_bindings: &'a mut RenderResourceBindings,
+    asset_render_resource_bindings: &'a mut AssetRenderResourceBindings,
+    /// Center of the ring and middle of the label, in world space.
+    center: Vec2,
+    at: Vec2,
+    z: f32,
+    /// Size of the straight label.
+    size: Vec2,
+    style: &'a TextStyle,
+    text: &'a str,
+    msaa: &'a Msaa,
+    font_quad_vertex_descriptor: &'a VertexBufferDescriptor,
+}
+
+impl Drawable for ArcText<'_> {
+    fn draw(&mut self, draw: &mut Draw, context: &mut DrawContext) -> Result<(), DrawError> {
+        context.set_pipeline(
+            draw,
+            &SPRITE_SHEET_PIPELINE_HANDLE,
+            &PipelineSpecialization {
+                sample_count: self.msaa.samples,
+                vertex_buffer_descriptor: self.font_quad_vertex_descriptor.clone(),
+                ..Default::default()
+            },
+        )?;
+        let render_resource_context = &**context.render_resource_context;
+        if let Some(RenderResourceId::Buffer(vertices)) = render_resource_context
+            .get_asset_resource(&QUAD_HANDLE, mesh::VERTEX_ATTRIBUTE_BUFFER_ID)
+        {
+            draw.set_vertex_buffer(0, vertices, 0);
+        }
+        let mut indices = 0..0;
+        if let Some(RenderResourceId::Buffer(quad_indices)) =
+            render_resource_context.get_asset_resource(&QUAD_HANDLE, mesh::INDEX_BUFFER_ASSET_INDEX)
+        {
+            draw.set_index_buffer(quad_indices, 0);
+            if let Some(info) = render_resource_context.get_buffer_info(quad_indices) {
+                indices = 0..(info.size / 4) as u32;
+            }
+        }
+        context.set_bind_groups_from_bindings(draw, &mut [self.render_resource_bindings])?;
+
+        let font = self
+            .font
+            .font
+            .as_scaled(PxScale::from(self.style.font_size));
+        // The straight label's caret, from the middle of the label.
+        let start = -self.size / 2.0;
+        let mut caret = start;
+        let mut last: Option<Glyph> = None;
+        for character in self.text.chars() {
+            if character.is_control() {
+                if character == '\n' {
+                    caret = Vec2::new(start.x(), caret.y() - font.height());
+                }
+                continue;
+            }
+            let glyph = font.scaled_glyph(character);
+            if let Some(last) = last.take() {
+                caret += Vec2::new(font.kern(last.id, glyph.id), 0.0);
+            }
+            let info = self
+                .font_atlas_set
+                .get_glyph_atlas_info(self.style.font_size, character);
+            let outlined = font.outline_glyph(glyph.clone());
+            if let (Some(info), Some(outlined)) = (info, outlined) {
+                let atlas = self.texture_atlases.get(&info.texture_atlas).unwrap();
+                let rect = atlas.textures[info.char_index as usize];
+                let atlas_bindings = self
+                    .asset_render_resource_bindings
+                    .get_mut(&info.texture_atlas)
+                    .unwrap();
+                context.set_bind_groups_from_bindings(draw, &mut [atlas_bindings])?;
+
+                let bounds = outlined.px_bounds();
+                let across = caret.x() + bounds.min.x + rect.width() / 2.0;
+                let up = caret.y() - bounds.max.y + rect.height() / 2.0 - font.descent() + 0.5;
+                let (position, angle) = arc_glyph(self.center, self.at, across, up);
+                let transform = Mat4::from_rotation_translation(
+                    Quat::from_rotation_z(angle),
+                    position.extend(self.z),
+                );
+                let sprite = TextureAtlasSprite {
+                    index: info.char_index,
+                    color: self.style.color,
+                };
+                let transform_buffer = context
+                    .shared_buffers
+                    .get_buffer(&transform, BufferUsage::UNIFORM)
+                    .unwrap();
+                let sprite_buffer = context
+                    .shared_buffers
+                    .get_buffer(&sprite, BufferUsage::UNIFORM)
+                    .unwrap();
+                let bind_group = BindGroup::build()
+                    .add_binding(0, transform_buffer)
+                    .add_binding(1, sprite_buffer)
+                    .finish();
+                context.create_bind_group_resource(2, &bind_group)?;
+                draw.set_bind_group(2, &bind_group);
+                draw.draw_indexed(indices.clone(), 0, 0..1);
+            }
+            caret += Vec2::new(font.h_advance(glyph.id), 0.0);
+            last = Some(glyph);
+        }
+        Ok(())
+    }
+}
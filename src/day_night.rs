@@ -0,0 +1,265 @@
+use std::f64::consts::PI;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::render::pipeline::{
+    DynamicBinding, PipelineDescriptor, PipelineSpecialization, RenderPipeline,
+};
+use bevy::render::render_graph::base;
+use bevy::render::render_graph::{AssetRenderResourcesNode, RenderGraph};
+use bevy::render::renderer::RenderResources;
+use bevy::render::shader::{ShaderStage, ShaderStages};
+use bevy::type_registry::TypeUuid;
+
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::bodies::geo_to_local;
+
+/// The pipeline drawing [`DayNightMaterial`]s.
+pub const DAY_NIGHT_PIPELINE_HANDLE: Handle<PipelineDescriptor> =
+    Handle::weak_from_u64(PipelineDescriptor::TYPE_UUID, 6_051_748_902_311_427_301);
+/// Render graph node uploading [`DayNightMaterial`]s.
+const DAY_NIGHT_MATERIAL_NODE: &str = "day_night_material";
+
+const VERTEX_SHADER: &str = r#"
+#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec3 Vertex_Normal;
+layout(location = 2) in vec2 Vertex_Uv;
+
+layout(location = 0) out vec3 v_Normal;
+layout(location = 1) out vec2 v_Uv;
+
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 ViewProj;
+};
+
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+
+void main() {
+    v_Normal = mat3(Model) * Vertex_Normal;
+    v_Uv = Vertex_Uv;
+    gl_Position = ViewProj * Model * vec4(Vertex_Position, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 450
+
+layout(location = 0) in vec3 v_Normal;
+layout(location = 1) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 2, binding = 0) uniform DayNightMaterial_sun {
+    vec3 Sun;
+};
+layout(set = 2, binding = 1) uniform DayNightMaterial_twilight {
+    float Twilight;
+};
+layout(set = 2, binding = 2) uniform DayNightMaterial_ambient {
+    float Ambient;
+};
+layout(set = 2, binding = 3) uniform texture2D DayNightMaterial_day;
+layout(set = 2, binding = 4) uniform sampler DayNightMaterial_day_sampler;
+layout(set = 2, binding = 5) uniform texture2D DayNightMaterial_night;
+layout(set = 2, binding = 6) uniform sampler DayNightMaterial_night_sampler;
+
+void main() {
+    float facing = dot(normalize(v_Normal), normalize(Sun));
+    float day = smoothstep(-Twilight, Twilight, facing);
+    vec3 day_color = texture(
+        sampler2D(DayNightMaterial_day, DayNightMaterial_day_sampler), v_Uv).rgb;
+    vec3 night_color = texture(
+        sampler2D(DayNightMaterial_night, DayNightMaterial_night_sampler), v_Uv).rgb;
+    vec3 lit = day_color * (Ambient + (1.0 - Ambient) * max(facing, 0.0));
+    o_Target = vec4(mix(night_color, lit, day), 1.0);
+}
+"#;
+
+/// A globe material lit by the sun alone: `day` on the side facing it, shaded by how
+/// squarely the sun falls, and `night`, such as city lights, on the other, blended
+/// across the terminator. Both are equirectangular like the textures of
+/// [`sphere_mesh`](crate::mesh::sphere_mesh). [`DayNightPlugin`] keeps `sun` pointing
+/// at the sun.
+#[derive(Debug, RenderResources, TypeUuid)]
+#[uuid = "5b1c9a4e-2f0d-4c1b-9e66-0f3a8d7c2e41"]
+pub struct DayNightMaterial {
+    /// Towards the sun in world space.
+    pub sun: Vec3,
+    /// Half the width of the terminator, as the cosine of the sun's angle from the
+    /// zenith over which day turns to night.
+    pub twilight: f32,
+    /// Share of full sunlight the day side gets however the sun falls.
+    pub ambient: f32,
+    pub day: Handle<Texture>,
+    pub night: Handle<Texture>,
+}
+
+impl DayNightMaterial {
+    pub fn new(day: Handle<Texture>, night: Handle<Texture>) -> Self {
+        DayNightMaterial {
+            sun: Vec3::unit_x(),
+            twilight: 0.1,
+            ambient: 0.1,
+            day,
+            night,
+        }
+    }
+
+    /// The render pipelines to give a mesh drawn with a [`DayNightMaterial`], along
+    /// with the mesh and material handles, [`MainPass`](base::MainPass), [`Draw`] and
+    /// its transforms.
+    pub fn render_pipelines() -> RenderPipelines {
+        RenderPipelines::from_pipelines(vec![RenderPipeline::specialized(
+            DAY_NIGHT_PIPELINE_HANDLE,
+            PipelineSpecialization {
+                dynamic_bindings: vec![
+                    // Transform
+                    DynamicBinding {
+                        bind_group: 1,
+                        binding: 0,
+                    },
+                ],
+                ..Default::default()
+            },
+        )])
+    }
+}
+
+/// The time the sun is shown at: `utc`, in seconds since 1970-01-01 UTC, when
+/// [`AnimationTime`] started, running `rate` times as fast as it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SunClock {
+    pub utc: f64,
+    pub rate: f64,
+}
+
+impl Default for SunClock {
+    /// Now, in real time.
+    fn default() -> Self {
+        let utc = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |since| since.as_secs_f64());
+        SunClock { utc, rate: 1.0 }
+    }
+}
+
+impl SunClock {
+    /// Seconds since 1970-01-01 UTC at `time`.
+    pub fn utc_at(&self, time: &AnimationTime) -> f64 {
+        self.utc + time.seconds() * self.rate
+    }
+}
+
+/// Parses `YYYY-MM-DDTHH:MM[:SS]Z`, or the same with a space for `T` or without the
+/// `Z`, into seconds since 1970-01-01 UTC.
+pub fn parse_utc(text: &str) -> Option<f64> {
+    let text = text.trim().trim_end_matches('Z');
+    let (date, time) = match text.find(['T', ' ']) {
+        Some(at) => (&text[..at], &text[at + 1..]),
+        None => (text, "00:00"),
+    };
+    let numbers = |part: &str, sep| {
+        part.split(sep)
+            .map(|n| n.parse::<i64>().ok())
+            .collect::<Option<Vec<_>>>()
+    };
+    let (year, month, day) = match numbers(date, '-')?[..] {
+        [year, month, day] if (1..=12).contains(&month) && (1..=31).contains(&day) => {
+            (year, month, day)
+        }
+        _ => return None,
+    };
+    let seconds = match numbers(time, ':')?[..] {
+        [h, m] if h < 24 && m < 60 => h * 3600 + m * 60,
+        [h, m, s] if h < 24 && m < 60 && s < 61 => h * 3600 + m * 60 + s,
+        _ => return None,
+    };
+    // Days since 1970-01-01 of a civil date, after Howard Hinnant's algorithm.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some((days * 86_400 + seconds) as f64)
+}
+
+/// The point on Earth with the sun straight overhead at `utc`, in seconds since
+/// 1970-01-01 UTC, as `(lat, lon)` in degrees; good to a fraction of a degree for
+/// centuries either side of 2000.
+pub fn subsolar_point(utc: f64) -> (f32, f32) {
+    let n = utc / 86_400.0 - 10_957.5;
+    let mean_longitude = 280.460 + 0.985_647_4 * n;
+    let anomaly = (357.528 + 0.985_600_3 * n).to_radians();
+    let ecliptic =
+        (mean_longitude + 1.915 * anomaly.sin() + 0.020 * (2.0 * anomaly).sin()).to_radians();
+    let obliquity = (23.439 - 0.000_000_4 * n).to_radians();
+    let declination = (obliquity.sin() * ecliptic.sin()).asin();
+    let right_ascension = (obliquity.cos() * ecliptic.sin()).atan2(ecliptic.cos());
+    let sidereal = (280.460_618_37 + 360.985_647_366_29 * n).to_radians();
+    let lon = (right_ascension - sidereal + PI).rem_euclid(2.0 * PI) - PI;
+    (declination.to_degrees() as f32, lon.to_degrees() as f32)
+}
+
+/// Adds [`DayNightMaterial`] and its pipeline, and points the sun of every entity's
+/// material at where [`SunClock`] has it, in the entity's own frame as laid out by
+/// [`geo_to_local`], so it stays put as the globe turns. Add after the render plugins.
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_asset::<DayNightMaterial>()
+            .add_plugin(AnimationTimePlugin)
+            .add_system(sun_system.system());
+        if !app.resources().contains::<SunClock>() {
+            app.init_resource::<SunClock>();
+        }
+        let resources = app.resources();
+        let mut shaders = resources.get_mut::<Assets<Shader>>().unwrap();
+        let mut pipelines = resources.get_mut::<Assets<PipelineDescriptor>>().unwrap();
+        pipelines.set_untracked(
+            DAY_NIGHT_PIPELINE_HANDLE,
+            PipelineDescriptor::default_config(ShaderStages {
+                vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER)),
+                fragment: Some(
+                    shaders.add(Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER)),
+                ),
+            }),
+        );
+        let mut graph = resources.get_mut::<RenderGraph>().unwrap();
+        graph.add_system_node(
+            DAY_NIGHT_MATERIAL_NODE,
+            AssetRenderResourcesNode::<DayNightMaterial>::new(false),
+        );
+        graph
+            .add_node_edge(DAY_NIGHT_MATERIAL_NODE, base::node::MAIN_PASS)
+            .unwrap();
+    }
+}
+
+fn sun_system(
+    clock: Res<SunClock>,
+    time: Res<AnimationTime>,
+    mut materials: ResMut<Assets<DayNightMaterial>>,
+    globes: Query<(&Handle<DayNightMaterial>, &GlobalTransform)>,
+) {
+    let (lat, lon) = subsolar_point(clock.utc_at(&time));
+    let local = geo_to_local(lat, lon, 1.0);
+    for (handle, transform) in globes.iter() {
+        let sun = transform.rotation * local;
+        // Only touch materials whose sun moved, as changing one uploads it again.
+        let moved = materials
+            .get(handle)
+            .is_some_and(|material| (material.sun - sun).length_squared() > 1e-10);
+        if moved {
+            if let Some(material) = materials.get_mut(handle) {
+                material.sun = sun;
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+use bevy::render::render_graph::base::MainPass;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::culling::CullStats;
+use crate::display::LabelFont;
+use crate::layout::LayoutDiagnostics;
+use crate::metrics::{self, Metrics, PerfCounters, TimeCounter};
+use crate::pointer::screen_to_world;
+use crate::theme::Theme;
+
+/// Whether [`DebugOverlayPlugin`] shows its panel, and how.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugOverlay {
+    pub visible: bool,
+    pub font_size: f32,
+    /// From the window's top-left corner to the panel's, in window pixels.
+    pub margin: Vec2,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        DebugOverlay {
+            visible: false,
+            font_size: 14.0,
+            margin: Vec2::new(10.0, 10.0),
+        }
+    }
+}
+
+/// Marks the panel spawned by [`DebugOverlayPlugin`].
+pub struct DebugPanel;
+
+/// Shows the latest layout's [`LayoutDiagnostics`], ring occupancy, turned-away
+/// targets and minimum angles, in a panel at the top left of the window, with the
+/// recent frame and relayout times of [`PerfCounters`] and what
+/// [`CullingPlugin`](crate::culling::CullingPlugin) hid if it's added.
+/// [`Action::ToggleDebugOverlay`], F12 by default, toggles it. Needs [`PoiRingPlugin`](crate::display::PoiRingPlugin), which publishes the
+/// diagnostics and the label font.
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<DebugOverlay>() {
+            app.init_resource::<DebugOverlay>();
+        }
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        if !app.resources().contains::<Metrics>() {
+            app.init_resource::<Metrics>();
+        }
+        if !app.resources().contains::<CullStats>() {
+            app.init_resource::<CullStats>();
+        }
+        metrics::add_perf_counters(app);
+        app.init_resource::<LayoutDiagnostics>()
+            .add_system(debug_overlay_system.system());
+    }
+}
+
+/// `counter`'s mean and worst case in milliseconds, or a dash before its first sample.
+fn millis(counter: &TimeCounter) -> String {
+    match (counter.mean(), counter.max()) {
+        (Some(mean), Some(max)) => format!(
+            "{:.2} ms mean, {:.2} max",
+            mean.as_secs_f64() * 1000.0,
+            max.as_secs_f64() * 1000.0
+        ),
+        _ => "-".to_string(),
+    }
+}
+
+fn panel_text(
+    diagnostics: &LayoutDiagnostics,
+    metrics: &Metrics,
+    perf: &PerfCounters,
+    culled: &CullStats,
+) -> String {
+    let targets = diagnostics.rings.iter().map(|r| r.targets).sum::<usize>();
+    let latency = metrics
+        .layout_latency()
+        .map(|l| format!(" in {:.2} ms", l.as_secs_f64() * 1000.0))
+        .unwrap_or_default();
+    let culling = if culled.checked > 0 {
+        format!(
+            "\nculled: {} of {} ({} out of view, {} too far)",
+            culled.hidden(),
+            culled.checked,
+            culled.outside,
+            culled.too_far
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        "layout: {} targets on {} rings{}\nframe: {}\nrelayout: {} ({} runs){}\n{}",
+        targets,
+        diagnostics.rings.len(),
+        latency,
+        millis(&perf.frame),
+        millis(&perf.relayout),
+        perf.relayout.count(),
+        culling,
+        diagnostics
+    )
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn debug_overlay_system(
+    mut commands: Commands,
+    mut shown: Local<Option<Entity>>,
+    actions: Res<ActionState>,
+    mut overlay: ResMut<DebugOverlay>,
+    diagnostics: Res<LayoutDiagnostics>,
+    (metrics, perf): (Res<Metrics>, Res<PerfCounters>),
+    culled: Res<CullStats>,
+    windows: Res<Windows>,
+    theme: Res<Theme>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    cameras: Query<(&Camera, &Transform)>,
+    mut panels: Query<With<DebugPanel, (Mut<Transform>, Mut<Text>)>>,
+) {
+    if actions.just_pressed(Action::ToggleDebugOverlay) {
+        overlay.visible = !overlay.visible;
+    }
+    if !overlay.visible {
+        if let Some(entity) = shown.take() {
+            commands.despawn(entity);
+        }
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let camera = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, camera)) => camera,
+        None => return,
+    };
+    let window_size = Vec2::new(window.width() as f32, window.height() as f32);
+    let corner = Vec2::new(overlay.margin.x(), window_size.y() - overlay.margin.y());
+    let translation = screen_to_world(corner, window_size, camera).extend(0.0);
+    let text = panel_text(&diagnostics, &metrics, &perf, &culled);
+
+    if let Some(entity) = *shown {
+        if let Ok((mut transform, mut panel)) = panels.get_mut(entity) {
+            transform.translation = translation;
+            if panel.value != text {
+                panel.value = text;
+            }
+        }
+        return;
+    }
+    *shown = commands
+        .spawn(TextComponents {
+            text: Text {
+                value: text,
+                font: asset_server.load(label_font.0),
+                style: TextStyle {
+                    font_size: overlay.font_size,
+                    color: theme.text(),
+                },
+            },
+            transform: Transform::from_translation(translation),
+            ..Default::default()
+        })
+        .with(MainPass)
+        .with(DebugPanel)
+        .current_entity();
+}
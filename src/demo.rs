@@ -0,0 +1,199 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::alerts::{AlertAction, AlertRule, AlertTrigger};
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::geo::Polar;
+use crate::layout::LayoutConfig;
+use crate::scale::RadialScale;
+use crate::scenario::{RandomTargets, Scenario};
+use crate::target::{Target, Velocity};
+use crate::theme::Theme;
+
+/// Seconds between the position reports of the demo targets.
+const REPORT_INTERVAL: f32 = 0.5;
+/// Categories the demo targets take turns in.
+const CATEGORIES: [&str; 4] = ["friend", "foe", "neutral", "unknown"];
+
+/// One stop of the [`DemoPlugin`] tour: what is shown and how.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DemoStep {
+    /// Printed when the step starts.
+    pub caption: &'static str,
+    pub theme: Theme,
+    pub scale: RadialScale,
+}
+
+/// The tour `--demo` takes: every theme, on a linear and a logarithmic scale.
+pub fn tour() -> Vec<DemoStep> {
+    let log = RadialScale::Log {
+        near: 15.0,
+        ratio: 1.6,
+    };
+    vec![
+        DemoStep {
+            caption: "clustered targets closing in; foes inside the first ring raise alerts",
+            theme: Theme::Classic,
+            scale: RadialScale::Linear,
+        },
+        DemoStep {
+            caption: "dark theme",
+            theme: Theme::Dark,
+            scale: RadialScale::Linear,
+        },
+        DemoStep {
+            caption: "logarithmic scale, spreading the near targets over more rings",
+            theme: Theme::Dark,
+            scale: log,
+        },
+        DemoStep {
+            caption: "light theme",
+            theme: Theme::Light,
+            scale: log,
+        },
+    ]
+}
+
+/// The targets of the demo: `count` of them gathered around three bearings, from
+/// `min_dist` out to `max_dist`, every one heading in towards the origin in one of
+/// [`CATEGORIES`], with an alert rule highlighting foes that reach the first ring.
+pub fn demo_scenario(seed: u64, count: usize, min_dist: f32, max_dist: f32) -> Scenario {
+    let mut targets = RandomTargets {
+        count,
+        min_dist,
+        max_dist,
+        clusters: 3,
+        spread: 0.15,
+    }
+    .generate(seed);
+    for (i, target) in targets.iter_mut().enumerate() {
+        target.category = Some(CATEGORIES[i % CATEGORIES.len()].to_string());
+        target.velocity = Some(Velocity {
+            course: target.azimuth + PI,
+            speed: 2.0 + (i % 5) as f32,
+        });
+    }
+    Scenario {
+        targets,
+        alerts: vec![AlertRule {
+            category: Some("foe".to_string()),
+            when: AlertTrigger::WithinRing(0),
+            then: vec![AlertAction::Highlight, AlertAction::Log],
+        }],
+        ..Default::default()
+    }
+}
+
+/// A scripted tour of the ring display for exhibitions and as a quick look at what
+/// the crate does: spawn [`demo_scenario`] targets and add
+/// [`AlertsPlugin`](crate::alerts::AlertsPlugin) with its rules, and this plugin
+/// moves targets along their [`Velocity`] in reports every half second, sending those
+/// that reach `min_dist` back out to `max_dist`, and steps through `steps` every
+/// `step_secs`, switching the [`Theme`] and the [`LayoutConfig::scale`]. Runs on
+/// [`AnimationTime`], so pausing animations pauses the tour.
+pub struct DemoPlugin {
+    pub steps: Vec<DemoStep>,
+    pub step_secs: f32,
+    pub min_dist: f32,
+    pub max_dist: f32,
+}
+
+impl Default for DemoPlugin {
+    fn default() -> Self {
+        DemoPlugin {
+            steps: tour(),
+            step_secs: 10.0,
+            min_dist: RandomTargets::default().min_dist,
+            max_dist: RandomTargets::default().max_dist,
+        }
+    }
+}
+
+struct DemoTour {
+    steps: Vec<DemoStep>,
+    step_secs: f32,
+    min_dist: f32,
+    max_dist: f32,
+}
+
+impl Plugin for DemoPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(AnimationTimePlugin)
+            .add_resource(DemoTour {
+                steps: self.steps.clone(),
+                step_secs: self.step_secs.max(1.0),
+                min_dist: self.min_dist,
+                max_dist: self.max_dist,
+            })
+            .add_system(demo_tour_system.system())
+            .add_system(demo_motion_system.system());
+    }
+}
+
+#[derive(Default)]
+struct TourState {
+    elapsed: f32,
+    step: Option<usize>,
+}
+
+fn demo_tour_system(
+    mut state: Local<TourState>,
+    tour: Res<DemoTour>,
+    time: Res<AnimationTime>,
+    (mut theme, mut clear_color): (ResMut<Theme>, ResMut<ClearColor>),
+    mut config: ResMut<LayoutConfig>,
+) {
+    if tour.steps.is_empty() {
+        return;
+    }
+    state.elapsed += time.delta_seconds();
+    let step = (state.elapsed / tour.step_secs) as usize % tour.steps.len();
+    if state.step == Some(step) {
+        return;
+    }
+    state.step = Some(step);
+    let DemoStep {
+        caption,
+        theme: step_theme,
+        scale,
+    } = tour.steps[step];
+    tracing::info!("demo {}/{}: {}", step + 1, tour.steps.len(), caption);
+    if *theme != step_theme {
+        *theme = step_theme;
+        clear_color.0 = step_theme.background();
+    }
+    if config.scale != scale {
+        config.scale = scale;
+    }
+}
+
+fn demo_motion_system(
+    mut since_report: Local<f32>,
+    tour: Res<DemoTour>,
+    time: Res<AnimationTime>,
+    mut targets: Query<Mut<Target>>,
+) {
+    *since_report += time.delta_seconds();
+    if *since_report < REPORT_INTERVAL {
+        return;
+    }
+    let dt = *since_report;
+    *since_report = 0.0;
+    for mut target in targets.iter_mut() {
+        let velocity = match target.velocity {
+            Some(velocity) if velocity.speed > 0.0 => velocity,
+            _ => continue,
+        };
+        let at = Polar::new(target.azimuth, target.dist).to_cartesian()
+            + Polar::new(velocity.course, velocity.speed * dt).to_cartesian();
+        if at.length() <= tour.min_dist {
+            // Round again from the far side of the same bearing.
+            target.dist = tour.max_dist;
+            continue;
+        }
+        let Polar { azimuth, dist } = Polar::from_cartesian(at);
+        target.azimuth = azimuth;
+        target.dist = dist;
+    }
+}
@@ -0,0 +1,125 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::events::DisplayEvent;
+use crate::geo::azimuth_to_bearing;
+use crate::target::Target;
+
+/// The target the operator designated by clicking it, if any.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Designation {
+    pub target: Option<i32>,
+}
+
+/// Sent to external systems whenever the designation changes or the designated
+/// target moves. All fields are `None` when the designation was cleared.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Handoff {
+    pub id: Option<i32>,
+    /// Compass bearing, in degrees clockwise from north.
+    pub bearing: Option<f32>,
+    pub range: Option<f32>,
+}
+
+/// Where [`Handoff`] messages go.
+#[derive(Clone)]
+pub enum HandoffSink {
+    /// One JSON object per datagram. Not available with the `web` feature.
+    Udp(SocketAddr),
+    Callback(Arc<dyn Fn(&Handoff) + Send + Sync>),
+}
+
+/// Designates the target under a left click (clearing it on a click into empty
+/// space), reports changes as [`DisplayEvent::Selected`] and hands them off to `sink`,
+/// again each time the designated target moves.
+#[derive(Clone, Default)]
+pub struct DesignationPlugin {
+    pub sink: Option<HandoffSink>,
+}
+
+struct HandoffOutput {
+    sink: HandoffSink,
+    socket: Option<UdpSocket>,
+}
+
+impl Plugin for DesignationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Designation>()
+            .add_system(designation_system.system());
+        if let Some(sink) = &self.sink {
+            let socket = match sink {
+                #[cfg(feature = "web")]
+                HandoffSink::Udp(_) => {
+                    tracing::warn!("designation handoff disabled: no UDP in the browser");
+                    return;
+                }
+                #[cfg(not(feature = "web"))]
+                HandoffSink::Udp(_) => match UdpSocket::bind("0.0.0.0:0") {
+                    Ok(socket) => Some(socket),
+                    Err(e) => {
+                        tracing::warn!("designation handoff disabled: {}", e);
+                        return;
+                    }
+                },
+                HandoffSink::Callback(_) => None,
+            };
+            app.add_resource(HandoffOutput {
+                sink: sink.clone(),
+                socket,
+            })
+            .add_system(handoff_system.system());
+        }
+    }
+}
+
+fn designation_system(
+    mut reader: Local<EventReader<DisplayEvent>>,
+    mut events: ResMut<Events<DisplayEvent>>,
+    mut designation: ResMut<Designation>,
+) {
+    let clicked = reader
+        .iter(&events)
+        .filter_map(|event| match event {
+            DisplayEvent::Clicked { target, .. } => Some(*target),
+            _ => None,
+        })
+        .next_back();
+    if let Some(target) = clicked {
+        if designation.target != target {
+            designation.target = target;
+            events.send(DisplayEvent::Selected { target });
+        }
+    }
+}
+
+fn handoff_system(
+    mut sent: Local<Handoff>,
+    designation: Res<Designation>,
+    output: Res<HandoffOutput>,
+    targets: Query<&Target>,
+) {
+    let target = designation
+        .target
+        .and_then(|id| targets.iter().find(|t| t.id == id));
+    let handoff = Handoff {
+        id: target.map(|t| t.id),
+        bearing: target.map(|t| azimuth_to_bearing(t.azimuth)),
+        range: target.map(|t| t.dist),
+    };
+    if *sent == handoff {
+        return;
+    }
+    *sent = handoff.clone();
+    match &output.sink {
+        HandoffSink::Udp(addr) => {
+            let json = serde_json::to_vec(&handoff).expect("handoff serializes");
+            if let Some(Err(e)) = output.socket.as_ref().map(|s| s.send_to(&json, addr)) {
+                tracing::error!("failed to send designation handoff to {}: {}", addr, e);
+            }
+        }
+        HandoffSink::Callback(callback) => callback(&handoff),
+    }
+}
@@ -0,0 +1,1348 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+#[cfg(not(feature = "web"))]
+use std::time::Instant;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+use bevy::render::render_graph::base::MainPass;
+use bevy_prototype_lyon::prelude::*;
+#[cfg(feature = "web")]
+use instant::Instant;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::batch::leader_batch_system;
+use crate::constant_size::Unscaled;
+use crate::coords::CoordFormat;
+use crate::emphasis::Emphasis;
+use crate::geo::Polar;
+use crate::label_fit::{AverageAdvance, FittedLabel, GlyphAdvances, LabelFit, TextMeasure};
+use crate::label_zoom::BaseFontSize;
+use crate::layers::{Collapsed, Layer};
+use crate::layout::{self, LayoutConfig, LayoutDiagnostics, Placement, RingLayout};
+use crate::metrics::{self, Metrics, PerfCounters};
+use crate::motion::{leader_line, LeaderLine, MotionPlugin, PolarTween, TweenConfig};
+use crate::origins::SensorOrigins;
+use crate::regression::LayoutCase;
+use crate::style::{MarkerShape, StyleRegistry, TargetCategory};
+use crate::target::Target;
+use crate::theme::{RingTextScale, Theme};
+use crate::updates::TargetUpdatesPlugin;
+
+/// How long a marker takes to glide to a new placement unless a
+/// [`TweenConfig`] resource says otherwise.
+pub const MARKER_TWEEN_SECS: f32 = 0.5;
+
+/// Furthest a reference ring's outline may stray from the true circle, in pixels.
+const RING_PIXEL_TOLERANCE: f32 = 0.25;
+/// Fewest straight segments a reference ring is drawn with, however far out the view is
+/// zoomed.
+const MIN_RING_SEGMENTS: f32 = 24.0;
+
+/// A marker as returned by a [`MarkerFactory`]. Its transform is relative to the
+/// target's placed position, which the display adds when inserting it.
+pub type MarkerBundle = SpriteComponents;
+
+/// Builds the marker entity for a target.
+pub type MarkerFactory = fn(&Target, &mut MarkerContext) -> MarkerBundle;
+
+/// What a [`MarkerFactory`] gets to build its marker with.
+pub struct MarkerContext<'a, 'b> {
+    pub meshes: &'a mut ResMut<'b, Assets<Mesh>>,
+    pub materials: &'a mut Assets<ColorMaterial>,
+    /// The themed stroke material used for the rest of the display.
+    pub material: Handle<ColorMaterial>,
+    pub poi_width: f32,
+    /// Line width for stroked markers; changes with the target's [`Emphasis`] and
+    /// category.
+    pub stroke_width: f32,
+    /// Outline for the target's category, see [`StyleRegistry`].
+    pub shape: MarkerShape,
+}
+
+/// Produces the text shown for a target. Implemented for any
+/// `Fn(&Target) -> String` closure, which then only customizes the label.
+pub trait LabelContent: Send + Sync + 'static {
+    /// The short label drawn next to the marker.
+    fn label(&self, target: &Target) -> String;
+
+    /// The longer text shown when hovering the marker, with positions written in
+    /// `format`.
+    fn tooltip(&self, target: &Target, format: CoordFormat) -> String {
+        default_tooltip(target, format)
+    }
+}
+
+impl<F> LabelContent for F
+where
+    F: Fn(&Target) -> String + Send + Sync + 'static,
+{
+    fn label(&self, target: &Target) -> String {
+        self(target)
+    }
+}
+
+/// Labels with `Target::text` and the default tooltip.
+pub struct DefaultLabels;
+
+impl LabelContent for DefaultLabels {
+    fn label(&self, target: &Target) -> String {
+        target.text.clone()
+    }
+}
+
+pub fn default_tooltip(target: &Target, format: CoordFormat) -> String {
+    let mut tooltip = format!(
+        "{} (#{})\n{}",
+        target.text,
+        target.id,
+        format.format_polar(Polar::new(target.azimuth, target.dist))
+    );
+    if let Some(category) = &target.category {
+        tooltip.push_str("\ncategory: ");
+        tooltip.push_str(category);
+    }
+    if target.priority != 0 {
+        tooltip.push_str(&format!("\npriority: {}", target.priority));
+    }
+    if let Some(velocity) = target.velocity {
+        tooltip.push_str(&format!(
+            "\ncourse {:.1}\u{b0}, speed {:.1}",
+            velocity.course.to_degrees().rem_euclid(360.0),
+            velocity.speed
+        ));
+    }
+    for note in &target.notes {
+        tooltip.push_str("\nnote: ");
+        tooltip.push_str(note);
+    }
+    tooltip
+}
+
+/// Attached to every marker entity: which target it shows, the square it covers in the
+/// world and the [`SensorOrigins`] entry it is laid out around.
+#[derive(Debug, Clone, Copy)]
+pub struct Poi {
+    pub id: i32,
+    pub origin: usize,
+    pub center: Vec2,
+    pub half_width: f32,
+}
+
+/// Attached to every label entity: which target it names.
+#[derive(Debug, Clone, Copy)]
+pub struct PoiLabel {
+    pub id: i32,
+}
+
+impl Poi {
+    pub fn contains(&self, point: Vec2) -> bool {
+        let d = point - self.center;
+        d.x().abs() <= self.half_width && d.y().abs() <= self.half_width
+    }
+}
+
+/// Attached to every reference ring circle.
+#[derive(Debug, Clone, Copy)]
+pub struct RefRing {
+    pub origin: usize,
+    pub ring: usize,
+    pub radius: f32,
+}
+
+/// World units per pixel of the 2D camera the reference rings are tessellated for,
+/// following the zoom in half-octave steps so that zooming smoothly only now and then
+/// tessellates them again, see [`ring_tolerance`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RingDetail {
+    pub scale: f32,
+}
+
+impl Default for RingDetail {
+    fn default() -> Self {
+        RingDetail { scale: 1.0 }
+    }
+}
+
+/// Lyon's tolerance for a reference ring of `radius` seen at camera `scale`: a fraction
+/// of a pixel, so large rings stay round when zoomed in without small ones getting more
+/// triangles than they need, but never so coarse that a ring is drawn with fewer than
+/// [`MIN_RING_SEGMENTS`] segments.
+pub fn ring_tolerance(radius: f32, scale: f32) -> f32 {
+    let on_screen = RING_PIXEL_TOLERANCE * scale;
+    let round = radius * (1.0 - (PI / MIN_RING_SEGMENTS).cos());
+    on_screen.min(round).max(1e-3)
+}
+
+/// Attached to the marker, leader line and label of a placed target: where the layout
+/// of its origin put it, relative to that origin.
+#[derive(Debug, Clone, Copy)]
+pub struct Slot {
+    pub id: i32,
+    pub origin: usize,
+    pub ring: usize,
+    pub azimuth: f32,
+    pub radius: f32,
+}
+
+/// Customization hooks for the ring display.
+pub struct RadarDisplay {
+    marker_factory: MarkerFactory,
+    label_content: Box<dyn LabelContent>,
+    categories: StyleRegistry,
+    label_fit: Option<LabelFit>,
+    label_measure: Box<dyn TextMeasure>,
+}
+
+impl Default for RadarDisplay {
+    fn default() -> Self {
+        RadarDisplay {
+            marker_factory: styled_marker,
+            label_content: Box::new(DefaultLabels),
+            categories: StyleRegistry::default(),
+            label_fit: None,
+            label_measure: Box::new(AverageAdvance::for_font_size(LABEL_FONT_SIZE)),
+        }
+    }
+}
+
+impl RadarDisplay {
+    /// Replaces the default category-styled markers with ones built by `factory`. Layout,
+    /// leader lines and labels are unaffected.
+    pub fn set_marker_factory(&mut self, factory: MarkerFactory) {
+        self.marker_factory = factory;
+    }
+
+    pub fn marker(&self, target: &Target, ctx: &mut MarkerContext) -> MarkerBundle {
+        (self.marker_factory)(target, ctx)
+    }
+
+    /// Replaces how label and tooltip text is derived from a target.
+    pub fn set_label_content(&mut self, content: impl LabelContent) {
+        self.label_content = Box::new(content);
+    }
+
+    /// The label drawn for `target`, fitted under [`RadarDisplay::label_fit`] if set.
+    pub fn label(&self, target: &Target) -> String {
+        self.fitted_label(target).text
+    }
+
+    pub fn fitted_label(&self, target: &Target) -> FittedLabel {
+        let label = self.label_content.label(target);
+        match &self.label_fit {
+            Some(fit) => fit.fit(&label, &*self.label_measure),
+            None => FittedLabel {
+                width: self.label_measure.width(&label),
+                text: label,
+                lines: 1,
+            },
+        }
+    }
+
+    /// How labels are kept from running into their neighbours; `None` draws them whole.
+    pub fn label_fit(&self) -> Option<LabelFit> {
+        self.label_fit
+    }
+
+    /// Changes how labels are fitted; labels built from then on follow it.
+    pub fn set_label_fit(&mut self, fit: Option<LabelFit>) {
+        self.label_fit = fit;
+    }
+
+    /// Replaces how label widths are measured. [`PoiRingPlugin`] measures with the
+    /// label font's glyphs once it has loaded, and an average advance until then.
+    pub fn set_label_measure(&mut self, measure: impl TextMeasure) {
+        self.label_measure = Box::new(measure);
+    }
+
+    pub fn tooltip(&self, target: &Target, format: CoordFormat) -> String {
+        self.label_content.tooltip(target, format)
+    }
+
+    /// Marker styles by target category, used by the default marker factory.
+    pub fn categories(&self) -> &StyleRegistry {
+        &self.categories
+    }
+
+    /// Changes the category styles; markers and leader lines built from then on follow
+    /// them.
+    pub fn categories_mut(&mut self) -> &mut StyleRegistry {
+        &mut self.categories
+    }
+}
+
+/// The default marker: the outline of the target's category, see [`StyleRegistry`],
+/// stroked and centered on the target's position.
+pub fn styled_marker(_target: &Target, ctx: &mut MarkerContext) -> MarkerBundle {
+    let (shape, translation) = ctx.shape.shape_type(ctx.poi_width);
+    primitive(
+        ctx.material.clone(),
+        ctx.meshes,
+        shape,
+        TessellationMode::Stroke(&StrokeOptions::default().with_line_width(ctx.stroke_width)),
+        translation,
+    )
+}
+
+/// A stroked square centered on the target's position, whatever its category.
+pub fn square_marker(_target: &Target, ctx: &mut MarkerContext) -> MarkerBundle {
+    primitive(
+        ctx.material.clone(),
+        ctx.meshes,
+        ShapeType::Rectangle {
+            width: ctx.poi_width,
+            height: ctx.poi_width,
+        },
+        TessellationMode::Stroke(&StrokeOptions::default().with_line_width(ctx.stroke_width)),
+        Vec3::new(-ctx.poi_width / 2.0, -ctx.poi_width / 2.0, 0.0),
+    )
+}
+
+/// Size labels are drawn at, in world units.
+pub const LABEL_FONT_SIZE: f32 = 20.0;
+
+/// Font asset path for labels, set by [`PoiRingPlugin`].
+#[derive(Debug, Clone, Copy)]
+pub struct LabelFont(pub &'static str);
+
+/// Marks the entities [`PoiRingPlugin`] owns outright (origin, rings, leader lines and
+/// labels); they are respawned on every full re-layout.
+#[derive(Debug, Clone, Copy)]
+pub struct RingPart;
+
+/// The POI ring visualization as a plugin. It spawns no camera; add it to any app with
+/// a 2D camera.
+///
+/// Every entity with a [`Target`] component is laid out with [`RingLayout`] and gets
+/// marker components ([`Poi`], [`Slot`], a sprite from the [`RadarDisplay`] marker
+/// factory) added to it, plus a leader line and label of its own. Adding, changing or
+/// despawning a `Target`, directly or through the [`TargetUpdatesPlugin`] events,
+/// re-places just that target, and moved markers glide to their new slot. Target ids
+/// must be unique.
+///
+/// Targets of each of the [`SensorOrigins`] get a layout of their own, drawn around
+/// that origin in its color. Ids are unique across all origins, so markers are picked
+/// by id alone; [`Poi::origin`] tells which origin a marker belongs to.
+///
+/// `config` becomes the [`LayoutConfig`] resource. It is read every frame, and any
+/// change to it (see [`LayoutTuningPlugin`]) lays everything out again. With a
+/// `label_fit`, long labels are truncated or wrapped, and
+/// [`LayoutConfig::label_width`] follows the widest fitted label so that markers are
+/// spread far enough apart for their labels.
+pub struct PoiRingPlugin {
+    pub config: LayoutConfig,
+    /// Asset path of the label font.
+    pub font: &'static str,
+    pub label_fit: Option<LabelFit>,
+}
+
+impl Default for PoiRingPlugin {
+    fn default() -> Self {
+        PoiRingPlugin {
+            config: LayoutConfig::default(),
+            font: "arial.ttf",
+            label_fit: None,
+        }
+    }
+}
+
+impl Plugin for PoiRingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<RadarDisplay>() {
+            app.init_resource::<RadarDisplay>();
+        }
+        if self.label_fit.is_some() {
+            if let Some(mut display) = app.resources().get_mut::<RadarDisplay>() {
+                display.set_label_fit(self.label_fit);
+            }
+        }
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        if !app.resources().contains::<Metrics>() {
+            app.init_resource::<Metrics>();
+        }
+        metrics::add_perf_counters(app);
+        if !app.resources().contains::<SensorOrigins>() {
+            app.init_resource::<SensorOrigins>();
+        }
+        if !app.resources().contains::<LayoutCheck>() {
+            app.init_resource::<LayoutCheck>();
+        }
+        if !app.resources().contains::<RingTextScale>() {
+            app.init_resource::<RingTextScale>();
+        }
+        if !app.resources().contains::<LayoutDiagnostics>() {
+            app.init_resource::<LayoutDiagnostics>();
+        }
+        app.add_resource(self.config)
+            .add_resource(LabelFont(self.font))
+            .init_resource::<LeaderRoutes>()
+            .init_resource::<PresetLayouts>()
+            .init_resource::<RingDetail>()
+            .add_plugin(MotionPlugin)
+            .add_plugin(TargetUpdatesPlugin)
+            .add_system(layout_system.system())
+            .add_system(leader_route_system.system())
+            .add_system(ring_detail_system.system())
+            .add_system(leader_batch_system.system())
+            .add_system(label_fit_system.system());
+    }
+}
+
+/// Tunes the [`LayoutConfig`] resource from the keyboard, for dialing in a display
+/// while it runs: [`Action::RingSpacingDown`] and [`Action::RingSpacingUp`] shrink and
+/// grow the ring spacing, the scatter and marker width actions the others; by default
+/// Ctrl with `-`/`=`, `[`/`]` and `,`/`.`. Each change re-lays out everything.
+pub struct LayoutTuningPlugin;
+
+impl Plugin for LayoutTuningPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        app.add_system(tuning_system.system());
+    }
+}
+
+fn tuning_system(actions: Res<ActionState>, mut config: ResMut<LayoutConfig>) {
+    let step = |less: Action, more: Action, by: f32| {
+        if actions.just_pressed(less) {
+            -by
+        } else if actions.just_pressed(more) {
+            by
+        } else {
+            0.0
+        }
+    };
+    let spacing = step(Action::RingSpacingDown, Action::RingSpacingUp, 5.0);
+    let scatter = step(Action::ScatterDown, Action::ScatterUp, 0.1);
+    let width = step(Action::MarkerWidthDown, Action::MarkerWidthUp, 2.0);
+    if spacing == 0.0 && scatter == 0.0 && width == 0.0 {
+        return;
+    }
+    config.ring_spacing = (config.ring_spacing + spacing).max(5.0);
+    config.scatter = (config.scatter + scatter).max(1.0);
+    config.poi_width = (config.poi_width + width).max(4.0);
+}
+
+/// Measures labels with the label font once it has loaded, and keeps
+/// [`LayoutConfig::label_width`] at the widest fitted label while labels are fitted.
+#[allow(clippy::too_many_arguments)]
+fn label_fit_system(
+    mut font: Local<Option<Handle<Font>>>,
+    mut measured: Local<Option<f32>>,
+    mut fitted: Local<bool>,
+    asset_server: Res<AssetServer>,
+    (label_font, text_scale): (Res<LabelFont>, Res<RingTextScale>),
+    fonts: Res<Assets<Font>>,
+    mut display: ResMut<RadarDisplay>,
+    mut config: ResMut<LayoutConfig>,
+    changed: Query<Changed<Target>>,
+    targets: Query<&Target>,
+) {
+    let mut remeasure = !targets.removed::<Target>().is_empty() || changed.iter().next().is_some();
+    // Measured again at the new size when the text scales, e.g. to another screen.
+    if *measured != Some(text_scale.scale_factor) {
+        let handle = font.get_or_insert_with(|| asset_server.load(label_font.0));
+        if let Some(font) = fonts.get(&*handle) {
+            let size = LABEL_FONT_SIZE * text_scale.scale_factor;
+            display.set_label_measure(GlyphAdvances::new(font, size));
+            *measured = Some(text_scale.scale_factor);
+            remeasure = true;
+        }
+    }
+    if display.label_fit().is_none() || (*fitted && !remeasure) {
+        return;
+    }
+    *fitted = true;
+    let label_width = targets
+        .iter()
+        .map(|target| display.fitted_label(target).width)
+        .fold(0.0, f32::max);
+    // Small changes aren't worth laying everything out again for.
+    if (label_width - config.label_width).abs() > 0.5 {
+        config.label_width = label_width;
+    }
+}
+
+/// Layouts for the next full layout to start from instead of placing every target
+/// afresh, by origin, such as the slots saved in a
+/// [`DisplayScene`](crate::scene::DisplayScene). Targets keep their slot there if they
+/// have one; the others are inserted as if they had just arrived, and slots of targets
+/// no longer around are dropped. Taken once used.
+#[derive(Debug, Clone, Default)]
+pub struct PresetLayouts(pub Option<BTreeMap<usize, RingLayout>>);
+
+/// Set `dump_dir` to have the ring display run [`layout::verify`] on every layout it
+/// changes, and save each one that fails there as a [`LayoutCase`]: the config, the
+/// layout it started from and the changes made to it, enough to replay the failure
+/// with [`LayoutCase::check`]. Off by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayoutCheck {
+    pub dump_dir: Option<PathBuf>,
+}
+
+/// The elbows of each target's leader line in the current layout, by target id. Empty
+/// unless [`LayoutConfig::leader_routing`] is on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LeaderRoutes(pub HashMap<i32, Vec<Vec2>>);
+
+impl LeaderRoutes {
+    pub fn of(layout: &RingLayout) -> Self {
+        if !layout.config.leader_routing {
+            return LeaderRoutes::default();
+        }
+        let routes = layout
+            .route_leaders()
+            .into_iter()
+            .filter(|route| route.points.len() > 2)
+            .map(|route| {
+                let elbows = route.elbows().iter().map(|&(x, y)| Vec2::new(x, y));
+                (route.id, elbows.collect())
+            })
+            .collect();
+        LeaderRoutes(routes)
+    }
+}
+
+/// Gives each leader line the width, pattern and ending its target's style in
+/// `categories` has now, for style changes that don't lay the display out again. Line
+/// colors are left for the next full re-layout, as uncolored lines share their origin's
+/// material.
+#[allow(clippy::type_complexity)]
+pub(crate) fn restyle_leaders(
+    categories: &StyleRegistry,
+    poi_width: f32,
+    targets: &Query<&Target>,
+    leaders: &mut Query<(
+        &Slot,
+        Mut<LeaderLine>,
+        &PolarTween,
+        &Handle<Mesh>,
+        &Handle<ColorMaterial>,
+    )>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+) {
+    let targets = targets.iter().map(|t| (t.id, t)).collect::<HashMap<_, _>>();
+    for (slot, mut leader, tween, mesh, material) in leaders.iter_mut() {
+        if let Some(target) = targets.get(&slot.id) {
+            let style = categories.leader(target);
+            let marker = Some((categories.style(target).shape, poi_width));
+            if leader.style != style || leader.marker != marker {
+                // The selection widens its line again if it is selected.
+                leader.style = style;
+                leader.width = style.width;
+                leader.marker = marker;
+                leader.redraw(tween, mesh, material, meshes);
+            }
+        }
+    }
+}
+
+/// Bends leader lines to the [`LeaderRoutes`] of the latest layout.
+#[allow(clippy::type_complexity)]
+fn leader_route_system(
+    routes: Res<LeaderRoutes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut leaders: Query<(
+        &Slot,
+        Mut<LeaderLine>,
+        &PolarTween,
+        &Handle<Mesh>,
+        &Handle<ColorMaterial>,
+    )>,
+) {
+    for (slot, mut leader, tween, mesh, material) in leaders.iter_mut() {
+        let elbows = routes.0.get(&slot.id).map_or(&[][..], |e| &e[..]);
+        if leader.elbows != elbows {
+            leader.elbows = elbows.to_vec();
+            leader.redraw(tween, mesh, material, &mut meshes);
+        }
+    }
+}
+
+#[derive(Default)]
+struct RingState {
+    /// Stroke material of each origin, for its dot, rings and leader lines.
+    materials: HashMap<usize, Handle<ColorMaterial>>,
+    font: Option<Handle<Font>>,
+    config: Option<LayoutConfig>,
+    origins: Option<SensorOrigins>,
+    text_scale: Option<RingTextScale>,
+    theme: Option<Theme>,
+    /// One layout per origin in use, by index into [`SensorOrigins`].
+    layouts: BTreeMap<usize, RingLayout>,
+    /// Origin and target id of every entity currently in `layouts`.
+    ids: HashMap<Entity, (usize, i32)>,
+    /// The entity of every target id in `layouts`, for the targets an insertion moves.
+    entities: HashMap<i32, Entity>,
+    /// What each origin's layout adds to the [`LayoutDiagnostics`] and the
+    /// [`LeaderRoutes`], kept so that only the origins changed are worked out again.
+    diagnostics: BTreeMap<usize, LayoutDiagnostics>,
+    routes: BTreeMap<usize, LeaderRoutes>,
+}
+
+impl RingState {
+    /// Works out the diagnostics and leader routes of `origin` again after its layout
+    /// changed, and updates `routes` with them.
+    fn refresh(&mut self, origin: usize, routes: &mut LeaderRoutes) {
+        if let Some(old) = self.routes.remove(&origin) {
+            for id in old.0.keys() {
+                routes.0.remove(id);
+            }
+        }
+        self.diagnostics.remove(&origin);
+        let layout = match self.layouts.get(&origin) {
+            Some(layout) => layout,
+            None => return,
+        };
+        let new = LeaderRoutes::of(layout);
+        routes
+            .0
+            .extend(new.0.iter().map(|(&id, elbows)| (id, elbows.clone())));
+        self.routes.insert(origin, new);
+        self.diagnostics.insert(origin, layout.diagnostics());
+    }
+
+    /// The diagnostics of every origin's layout, one after another.
+    fn diagnostics(&self) -> LayoutDiagnostics {
+        LayoutDiagnostics {
+            rings: self
+                .diagnostics
+                .values()
+                .flat_map(|d| d.rings.iter().copied())
+                .collect(),
+        }
+    }
+}
+
+/// `layout` with only `targets` in it: theirs kept where `layout` has them, the others
+/// inserted.
+/// The steps are recorded in `case` too, if given.
+fn resume_layout(
+    mut layout: RingLayout,
+    targets: &[Target],
+    mut case: Option<&mut LayoutCase>,
+) -> RingLayout {
+    let ids = targets.iter().map(|t| t.id).collect::<HashSet<_>>();
+    let placed = layout
+        .placements()
+        .map(|p| p.target.id)
+        .collect::<HashSet<_>>();
+    for &id in placed.difference(&ids) {
+        layout.remove(id);
+        if let Some(case) = &mut case {
+            case.remove(id);
+        }
+    }
+    for target in targets {
+        if !placed.contains(&target.id) {
+            layout.insert(target.clone());
+            if let Some(case) = &mut case {
+                case.insert(target);
+            }
+        }
+    }
+    layout
+}
+
+/// The case of `origin`'s layout this frame, started from its layout in `layouts` the
+/// first time it is asked for, so before the layout first changes; `None` unless
+/// [`LayoutCheck`] is on.
+fn case_of<'a>(
+    cases: &'a mut Option<BTreeMap<usize, LayoutCase>>,
+    layouts: &BTreeMap<usize, RingLayout>,
+    config: LayoutConfig,
+    origin: usize,
+) -> Option<&'a mut LayoutCase> {
+    let case = cases.as_mut()?.entry(origin).or_insert_with(|| {
+        layouts.get(&origin).map_or_else(
+            || LayoutCase::resume(&RingLayout::with_config(&[], config)),
+            LayoutCase::resume,
+        )
+    });
+    Some(case)
+}
+
+/// Verifies the layout each of `cases` was recorded for and dumps the cases of those
+/// that fail into `dir`.
+fn check_layouts(
+    dir: &Path,
+    layouts: &BTreeMap<usize, RingLayout>,
+    cases: BTreeMap<usize, LayoutCase>,
+) {
+    for (origin, mut case) in cases {
+        let layout = match layouts.get(&origin) {
+            Some(layout) => layout,
+            None => continue,
+        };
+        if let Err(violation) = layout::verify(layout, &case.expected()) {
+            case.violation = Some(violation.to_string());
+            match case.dump(dir) {
+                Ok(path) => tracing::error!(
+                    "layout of origin {} failed to verify: {}; saved the case to {}",
+                    origin,
+                    violation,
+                    path.display()
+                ),
+                Err(e) => tracing::error!(
+                    "layout of origin {} failed to verify: {}; failed to save the case: {}",
+                    origin,
+                    violation,
+                    e
+                ),
+            }
+        }
+    }
+}
+
+/// What spawning a slot needs besides the [`MarkerContext`].
+struct SlotStyle<'a> {
+    display: &'a RadarDisplay,
+    categories: &'a StyleRegistry,
+    font: Handle<Font>,
+    text_color: Color,
+    text_scale: RingTextScale,
+    tween: TweenConfig,
+    /// Leave leader lines to [`LeaderBatch`](crate::batch::LeaderBatch) entities.
+    batch_leaders: bool,
+    /// Index and world position of the origin the slot is around.
+    origin: (usize, Vec2),
+}
+
+/// The stroke material of `origin`, added the first time it is asked for.
+fn origin_material(
+    cache: &mut HashMap<usize, Handle<ColorMaterial>>,
+    materials: &mut Assets<ColorMaterial>,
+    origins: &SensorOrigins,
+    stroke: Color,
+    origin: usize,
+) -> Handle<ColorMaterial> {
+    cache
+        .entry(origin)
+        .or_insert_with(|| materials.add(origins.color(origin).unwrap_or(stroke).into()))
+        .clone()
+}
+
+/// The diagnostics of every origin's layout, the rings of each origin after those of
+/// the one before.
+// The first run and any change of `LayoutConfig` or `SensorOrigins` lay out everything
+// from scratch. After that only targets whose component was added, changed or removed
+// are taken out of their origin's layout and re-inserted, and of the others only those
+// the insertions report moving are touched; everyone else keeps their slot and entities. Targets that stay in a layout keep their marker, leader line and
+// label too, which glide to the new slot.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn layout_system(
+    mut commands: Commands,
+    mut state: Local<RingState>,
+    display: Res<RadarDisplay>,
+    config: Res<LayoutConfig>,
+    (theme, text_scale, origins, mut preset, check, detail): (
+        Res<Theme>,
+        Res<RingTextScale>,
+        Res<SensorOrigins>,
+        ResMut<PresetLayouts>,
+        Res<LayoutCheck>,
+        Res<RingDetail>,
+    ),
+    label_font: Res<LabelFont>,
+    tween: Res<TweenConfig>,
+    asset_server: Res<AssetServer>,
+    (mut metrics, mut perf, mut routes, mut layout_diagnostics): (
+        ResMut<Metrics>,
+        ResMut<PerfCounters>,
+        ResMut<LeaderRoutes>,
+        ResMut<LayoutDiagnostics>,
+    ),
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    changed: Query<(Entity, Changed<Target>)>,
+    targets: Query<(Entity, &Target)>,
+    mut markers: Query<(Mut<Poi>, Mut<Slot>, Mut<PolarTween>)>,
+    mut rigging: Query<
+        Without<
+            Poi,
+            (
+                Mut<Slot>,
+                Mut<PolarTween>,
+                Option<Mut<Text>>,
+                Option<Mut<BaseFontSize>>,
+                Option<Mut<Unscaled>>,
+            ),
+        >,
+    >,
+    parts: Query<With<RingPart, (Entity, Option<&Slot>, Option<&RefRing>)>>,
+) {
+    let state = &mut *state;
+    let rebuild = state.config != Some(*config)
+        || state.origins.as_ref() != Some(&*origins)
+        || state.text_scale != Some(*text_scale)
+        || state.theme != Some(*theme)
+        || preset.0.is_some();
+    let removed = targets.removed::<Target>();
+    if !rebuild && removed.is_empty() && changed.iter().next().is_none() {
+        return;
+    }
+    if rebuild {
+        state.materials.clear();
+    }
+    let stroke = theme.stroke();
+    let material = origin_material(&mut state.materials, &mut materials, &origins, stroke, 0);
+    let mut style = SlotStyle {
+        display: &display,
+        categories: display.categories(),
+        font: state
+            .font
+            .get_or_insert_with(|| asset_server.load(label_font.0))
+            .clone(),
+        text_color: theme.text(),
+        text_scale: *text_scale,
+        tween: *tween,
+        batch_leaders: config.batch_leaders,
+        origin: (0, origins.offset(0)),
+    };
+    let mut ctx = MarkerContext {
+        meshes: &mut meshes,
+        materials: &mut materials,
+        material,
+        poi_width: config.poi_width,
+        stroke_width: Emphasis::Normal.stroke_width(),
+        shape: MarkerShape::default(),
+    };
+    let start = Instant::now();
+    let mut cases = check.dump_dir.as_ref().map(|_| BTreeMap::new());
+
+    if rebuild {
+        state.config = Some(*config);
+        state.origins = Some(origins.clone());
+        state.text_scale = Some(*text_scale);
+        state.theme = Some(*theme);
+        for (entity, _, _) in parts.iter() {
+            commands.despawn(entity);
+        }
+        let mut sorted = targets
+            .iter()
+            .map(|(entity, target)| (entity, target.clone()))
+            .collect::<Vec<_>>();
+        sorted.sort_unstable_by(|a, b| a.1.dist.total_cmp(&b.1.dist));
+        let added = sorted
+            .iter()
+            .filter(|(entity, _)| !state.ids.contains_key(entity))
+            .count();
+        state.ids = sorted.iter().map(|(e, t)| (*e, (t.origin, t.id))).collect();
+        state.entities = sorted
+            .iter()
+            .map(|(entity, target)| (target.id, *entity))
+            .collect();
+        // Listed origins show their rings even before any of their targets turn up.
+        let mut by_origin = (0..origins.len())
+            .map(|origin| (origin, Vec::new()))
+            .collect::<BTreeMap<_, _>>();
+        for (_, target) in sorted {
+            by_origin.entry(target.origin).or_default().push(target);
+        }
+        let mut preset = preset.0.take().unwrap_or_default();
+        state.layouts = by_origin
+            .iter()
+            .map(|(origin, targets)| {
+                let case = cases.as_mut().map(|cases| {
+                    let case = match preset.get(origin) {
+                        Some(layout) => LayoutCase::resume(layout),
+                        None => LayoutCase::fresh(targets, *config),
+                    };
+                    cases.entry(*origin).or_insert(case)
+                });
+                let layout = match preset.remove(origin) {
+                    Some(layout) => resume_layout(layout, targets, case),
+                    None => RingLayout::with_config(targets, *config),
+                };
+                (*origin, layout)
+            })
+            .collect();
+        let elapsed = start.elapsed();
+        metrics.record_layout(elapsed);
+        perf.relayout.record(elapsed);
+        if let (Some(dir), Some(cases)) = (&check.dump_dir, cases) {
+            check_layouts(dir, &state.layouts, cases);
+        }
+        metrics.record_ingest(added);
+
+        for (&origin, layout) in &state.layouts {
+            let offset = origins.offset(origin);
+            ctx.material = origin_material(
+                &mut state.materials,
+                ctx.materials,
+                &origins,
+                stroke,
+                origin,
+            );
+            style.origin = (origin, offset);
+            commands
+                .spawn(origin_dot(ctx.material.clone(), ctx.meshes, offset))
+                .with(RingPart)
+                .with(Layer::Rings);
+            for ring_ord in 0..layout.rings.len() {
+                spawn_ring(
+                    &mut commands,
+                    &mut ctx,
+                    layout,
+                    style.origin,
+                    ring_ord,
+                    detail.scale,
+                );
+            }
+            for placement in layout.placements() {
+                let entity = state.entities[&placement.target.id];
+                spawn_slot(
+                    &mut commands,
+                    &mut ctx,
+                    &style,
+                    &mut markers,
+                    &mut rigging,
+                    &[],
+                    entity,
+                    &placement,
+                );
+            }
+        }
+        metrics.set_active_tracks(state.ids.len());
+        routes.0.clear();
+        state.routes.clear();
+        state.diagnostics.clear();
+        let origins_laid_out = state.layouts.keys().copied().collect::<Vec<_>>();
+        for origin in origins_laid_out {
+            state.refresh(origin, &mut routes);
+        }
+        *layout_diagnostics = state.diagnostics();
+        return;
+    }
+
+    // Ids whose leader line and label go away with them.
+    let mut stale = HashSet::new();
+    // Origins whose layout changed, and those among them laid out for the first time.
+    let mut touched = BTreeSet::new();
+    let mut new_origins = Vec::new();
+    for entity in removed {
+        if let Some((origin, id)) = state.ids.remove(entity) {
+            if let Some(case) = case_of(&mut cases, &state.layouts, *config, origin) {
+                case.remove(id);
+            }
+            if let Some(layout) = state.layouts.get_mut(&origin) {
+                layout.remove_changes(id);
+                touched.insert(origin);
+            }
+            if state.entities.get(&id) == Some(entity) {
+                state.entities.remove(&id);
+            }
+            stale.insert(id);
+        }
+    }
+    let mut added = 0;
+    let mut placed = Vec::new();
+    for (entity, target) in changed.iter() {
+        match state.ids.insert(entity, (target.origin, target.id)) {
+            Some((old_origin, old_id)) => {
+                if let Some(case) = case_of(&mut cases, &state.layouts, *config, old_origin) {
+                    case.remove(old_id);
+                }
+                if let Some(layout) = state.layouts.get_mut(&old_origin) {
+                    layout.remove_changes(old_id);
+                    touched.insert(old_origin);
+                }
+                if old_id != target.id {
+                    if state.entities.get(&old_id) == Some(&entity) {
+                        state.entities.remove(&old_id);
+                    }
+                    stale.insert(old_id);
+                }
+            }
+            None => added += 1,
+        }
+        if let Some(case) = case_of(&mut cases, &state.layouts, *config, target.origin) {
+            case.insert(&target);
+        }
+        let layout = state.layouts.entry(target.origin).or_insert_with(|| {
+            new_origins.push(target.origin);
+            RingLayout::with_config(&[], *config)
+        });
+        // Targets pushed aside by the insertion glide to their new slots too.
+        for change in layout.insert_changes(target.clone()) {
+            if change.id != target.id && change.from != change.to {
+                placed.extend(state.entities.get(&change.id).map(|&e| (e, change.id)));
+            }
+        }
+        touched.insert(target.origin);
+        state.entities.insert(target.id, entity);
+        placed.push((entity, target.id));
+    }
+    let elapsed = start.elapsed();
+    metrics.record_layout(elapsed);
+    perf.relayout.record(elapsed);
+    if let (Some(dir), Some(cases)) = (&check.dump_dir, cases) {
+        check_layouts(dir, &state.layouts, cases);
+    }
+    metrics.record_ingest(added);
+    metrics.set_active_tracks(state.ids.len());
+    for origin in touched {
+        state.refresh(origin, &mut routes);
+    }
+    *layout_diagnostics = state.diagnostics();
+
+    let mut rings_shown = HashMap::<usize, usize>::new();
+    let mut rigged = HashMap::<i32, Vec<Entity>>::new();
+    for (entity, slot, ring) in parts.iter() {
+        if let Some(slot) = slot {
+            if stale.contains(&slot.id) {
+                commands.despawn(entity);
+            } else {
+                rigged.entry(slot.id).or_default().push(entity);
+            }
+        }
+        if let Some(ring) = ring {
+            match state.layouts.get(&ring.origin) {
+                Some(layout) if ring.ring < layout.rings.len() => {
+                    let shown = rings_shown.entry(ring.origin).or_default();
+                    *shown = (*shown).max(ring.ring + 1);
+                }
+                _ => {
+                    commands.despawn(entity);
+                }
+            }
+        }
+    }
+    for (&origin, layout) in &state.layouts {
+        let offset = origins.offset(origin);
+        ctx.material = origin_material(
+            &mut state.materials,
+            ctx.materials,
+            &origins,
+            stroke,
+            origin,
+        );
+        if new_origins.contains(&origin) {
+            commands
+                .spawn(origin_dot(ctx.material.clone(), ctx.meshes, offset))
+                .with(RingPart)
+                .with(Layer::Rings);
+        }
+        let shown = rings_shown.get(&origin).copied().unwrap_or(0);
+        for ring_ord in shown..layout.rings.len() {
+            spawn_ring(
+                &mut commands,
+                &mut ctx,
+                layout,
+                (origin, offset),
+                ring_ord,
+                detail.scale,
+            );
+        }
+    }
+    // A moved target may also have been re-inserted itself this frame.
+    placed.sort_unstable_by_key(|(_, id)| *id);
+    placed.dedup();
+    for (entity, id) in placed {
+        let origin = match state.ids.get(&entity) {
+            Some(&(origin, _)) => origin,
+            None => continue,
+        };
+        if let Some(placement) = state.layouts.get(&origin).and_then(|l| l.find(id)) {
+            ctx.material = origin_material(
+                &mut state.materials,
+                ctx.materials,
+                &origins,
+                stroke,
+                origin,
+            );
+            style.origin = (origin, origins.offset(origin));
+            let rigged = rigged.get(&id).map_or(&[][..], |parts| &parts[..]);
+            spawn_slot(
+                &mut commands,
+                &mut ctx,
+                &style,
+                &mut markers,
+                &mut rigging,
+                rigged,
+                entity,
+                &placement,
+            );
+        }
+    }
+}
+
+fn spawn_ring(
+    commands: &mut Commands,
+    ctx: &mut MarkerContext,
+    layout: &RingLayout,
+    (origin, offset): (usize, Vec2),
+    ring_ord: usize,
+    scale: f32,
+) {
+    let r = layout.ring_radius(ring_ord);
+    commands
+        .spawn(ref_ring(ctx.material.clone(), ctx.meshes, offset, r, scale))
+        .with(RefRing {
+            origin,
+            ring: ring_ord,
+            radius: r,
+        })
+        .with(Collapsed::default())
+        .with(RingPart)
+        .with(Layer::Rings);
+}
+
+/// Moves the target's marker and its `rigged` leader line and label to a placement,
+/// spawning the leader line and label if it has none and turning the target entity
+/// into a marker first if it is not one yet.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn spawn_slot(
+    commands: &mut Commands,
+    ctx: &mut MarkerContext,
+    style: &SlotStyle,
+    markers: &mut Query<(Mut<Poi>, Mut<Slot>, Mut<PolarTween>)>,
+    rigging: &mut Query<
+        Without<
+            Poi,
+            (
+                Mut<Slot>,
+                Mut<PolarTween>,
+                Option<Mut<Text>>,
+                Option<Mut<BaseFontSize>>,
+                Option<Mut<Unscaled>>,
+            ),
+        >,
+    >,
+    rigged: &[Entity],
+    entity: Entity,
+    placement: &Placement,
+) {
+    let (target, azi, r) = (
+        placement.target,
+        placement.drawn_azimuth(),
+        placement.radius,
+    );
+    let (origin, offset) = style.origin;
+    let slot = Slot {
+        id: target.id,
+        origin,
+        ring: placement.ring,
+        azimuth: azi,
+        radius: r,
+    };
+    let trans = (offset + Vec2::new(r * azi.cos(), r * azi.sin())).extend(0.0);
+    let retarget = |tween: &mut PolarTween| {
+        tween.origin = offset;
+        tween.duration = style.tween.duration;
+        tween.easing = style.tween.easing;
+        tween.retarget(azi, r);
+    };
+    let label = style.display.label(target);
+    if rigged.is_empty() {
+        if !style.batch_leaders {
+            let leader = style.categories.leader(target);
+            let marker = (style.categories.style(target).shape, ctx.poi_width);
+            let material = match leader.color {
+                Some(color) => ctx.materials.add(color.into()),
+                None => ctx.material.clone(),
+            };
+            let line = leader_line(
+                material,
+                ctx.meshes,
+                offset,
+                &[],
+                azi,
+                r,
+                &leader,
+                leader.width,
+                Some(marker),
+            );
+            commands
+                .spawn(line)
+                .with(slot)
+                .with(PolarTween::with_config(azi, r, Vec2::zero(), &style.tween).around(offset))
+                .with(LeaderLine::new(leader).ending_at(marker.0, marker.1))
+                .with(Collapsed::default())
+                .with(RingPart)
+                .with(Layer::Leaders);
+        }
+        commands
+            .spawn(label_text(
+                trans.truncate(),
+                style.font.clone(),
+                label.clone(),
+                style.text_color,
+                style.text_scale.font_size(LABEL_FONT_SIZE, placement.ring),
+            ))
+            .with(MainPass)
+            .with(PoiLabel { id: target.id })
+            .with(slot)
+            .with(PolarTween::with_config(azi, r, Vec2::zero(), &style.tween).around(offset))
+            .with(Collapsed::default())
+            .with(Emphasis::Normal)
+            .with(RingPart)
+            .with(Layer::Labels);
+    }
+    for part in rigged {
+        if let Ok((mut placed, mut tween, text, base, mut unscaled)) = rigging.get_mut(*part) {
+            let from_ring = placed.ring;
+            *placed = slot;
+            retarget(&mut tween);
+            if let Some(mut text) = text {
+                if text.value != label {
+                    text.value = label.clone();
+                }
+                if from_ring != slot.ring {
+                    // Rescale rather than set, keeping any zoom applied on top.
+                    let k = style.text_scale.font_size(1.0, slot.ring)
+                        / style.text_scale.font_size(1.0, from_ring);
+                    text.style.font_size *= k;
+                    if let Some(mut base) = base {
+                        base.0 *= k;
+                    }
+                    if let Some(size) = unscaled.as_mut().and_then(|u| u.font_size.as_mut()) {
+                        *size *= k;
+                    }
+                }
+            }
+        }
+    }
+
+    if let Ok((mut poi, mut placed, mut tween)) = markers.get_mut(entity) {
+        poi.origin = origin;
+        poi.center = trans.truncate();
+        *placed = slot;
+        retarget(&mut tween);
+        return;
+    }
+    let category = style.categories.style(target);
+    ctx.shape = category.shape;
+    ctx.stroke_width = category.stroke_width * Emphasis::Normal.stroke_width();
+    let mut marker = style.display.marker(target, ctx);
+    let marker_offset = marker.transform.translation.truncate();
+    marker.transform.translation += trans;
+    // Each marker gets its own copy of the material so [`Emphasis`] can change its
+    // alpha without affecting the others.
+    let color = category.color.unwrap_or_else(|| {
+        ctx.materials
+            .get(&ctx.material)
+            .map_or(Color::WHITE, |m| m.color)
+    });
+    marker.material = ctx.materials.add(color.into());
+    commands
+        .insert(entity, marker)
+        .insert_one(
+            entity,
+            Poi {
+                id: target.id,
+                origin,
+                center: trans.truncate(),
+                half_width: ctx.poi_width / 2.0,
+            },
+        )
+        .insert_one(entity, slot)
+        .insert_one(
+            entity,
+            PolarTween::with_config(azi, r, marker_offset, &style.tween).around(offset),
+        )
+        .insert_one(entity, Collapsed::default())
+        .insert_one(entity, Emphasis::Normal)
+        .insert_one(entity, Layer::Markers);
+    if let Some(category) = TargetCategory::of(target) {
+        commands.insert_one(entity, category);
+    }
+}
+
+fn origin_dot(
+    material: Handle<ColorMaterial>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    at: Vec2,
+) -> SpriteComponents {
+    primitive(
+        material,
+        meshes,
+        ShapeType::Circle(5.0),
+        TessellationMode::Fill(&FillOptions::default()),
+        at.extend(0.0),
+    )
+}
+
+fn ref_ring(
+    material: Handle<ColorMaterial>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    center: Vec2,
+    r: f32,
+    scale: f32,
+) -> SpriteComponents {
+    primitive(
+        material,
+        meshes,
+        ShapeType::Circle(r),
+        TessellationMode::Stroke(
+            &StrokeOptions::default().with_tolerance(ring_tolerance(r, scale)),
+        ),
+        center.extend(0.0),
+    )
+}
+
+/// Follows the 2D camera's zoom with [`RingDetail`], tessellating the reference rings
+/// again when it takes a step.
+fn ring_detail_system(
+    mut detail: ResMut<RingDetail>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cameras: Query<(&Camera, &Transform)>,
+    rings: Query<(&RefRing, &Handle<Mesh>, &Handle<ColorMaterial>)>,
+) {
+    let scale = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, transform)) => transform.scale.x(),
+        None => return,
+    };
+    if scale <= 0.0 {
+        return;
+    }
+    let scale = ((scale.log2() * 2.0).round() / 2.0).exp2();
+    if detail.scale == scale {
+        return;
+    }
+    detail.scale = scale;
+    for (ring, mesh, material) in rings.iter() {
+        let circle = ref_ring(
+            material.clone(),
+            &mut meshes,
+            Vec2::zero(),
+            ring.radius,
+            scale,
+        );
+        if let Some(tessellated) = meshes.remove(&circle.mesh) {
+            meshes.set(mesh, tessellated);
+        }
+    }
+}
+
+fn label_text(
+    at: Vec2,
+    font: Handle<Font>,
+    text: String,
+    text_color: Color,
+    font_size: f32,
+) -> TextComponents {
+    let translation = at.extend(0.0);
+    TextComponents {
+        //style: Style {
+        //    margin: Rect::all(Val::Px(1.0)),
+        //    ..Default::default()
+        //},
+        text: Text {
+            value: text,
+            font,
+            style: TextStyle {
+                font_size,
+                color: text_color,
+            },
+        },
+        transform: Transform::from_translation(translation),
+        ..Default::default()
+    }
+}
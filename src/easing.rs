@@ -0,0 +1,91 @@
+use std::f32::consts::PI;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// How an animation's progress is spread over its duration, for the marker tweens,
+/// fades and afterglows of the display. Each animation picks its own, see
+/// [`EasingConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// Steady from start to end.
+    #[default]
+    Linear,
+    /// Gentle start, fast arrival.
+    QuadIn,
+    /// Fast start, gentle arrival.
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// Overshoots and springs back a few times before settling.
+    Elastic,
+}
+
+impl Easing {
+    /// Maps linear progress `t`, clamped to `[0, 1]`, to eased progress. Every easing
+    /// starts at 0 and ends at 1; [`Easing::Elastic`] goes past 1 on the way.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Easing::QuadInOut if t < 0.5 => 2.0 * t * t,
+            Easing::QuadInOut => 1.0 - (2.0 - 2.0 * t).powi(2) / 2.0,
+            Easing::CubicIn => t.powi(3),
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut if t < 0.5 => 4.0 * t.powi(3),
+            Easing::CubicInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+            Easing::Elastic if t == 0.0 || t == 1.0 => t,
+            Easing::Elastic => {
+                2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (PI * 2.0 / 3.0)).sin() + 1.0
+            }
+        }
+    }
+
+    /// The value `t` of the way from `from` to `to` with this easing.
+    pub fn lerp(self, from: f32, to: f32, t: f32) -> f32 {
+        from + (to - from) * self.apply(t)
+    }
+}
+
+/// The easing of each kind of animation the display plays, as written in a
+/// [`DebrisConfig`](crate::config::DebrisConfig), e.g. in RON
+/// `easing: (tween: elastic, fade: quad_in)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EasingConfig {
+    /// Markers gliding to a new placement, see
+    /// [`TweenConfig`](crate::motion::TweenConfig).
+    pub tween: Easing,
+    /// Targets fading out as their reports age, see [`Aging`](crate::aging::Aging).
+    pub fade: Easing,
+    /// Markers dimming again after the sweep passed them, and the wedge behind the
+    /// beam, see [`Sweep`](crate::sweep::Sweep).
+    pub afterglow: Easing,
+    /// Trails fading along their length, see
+    /// [`TrailSettings`](crate::trails::TrailSettings).
+    pub trail: Easing,
+    /// The orbit camera flying to a point, see
+    /// [`OrbitControls::flight_easing`](crate::camera::OrbitControls::flight_easing).
+    pub fly_to: Easing,
+    /// A body morphing between globe and map, see
+    /// [`FlatMap`](crate::flat_map::FlatMap).
+    pub map_morph: Easing,
+}
+
+impl Default for EasingConfig {
+    fn default() -> Self {
+        EasingConfig {
+            tween: Easing::CubicOut,
+            fade: Easing::Linear,
+            afterglow: Easing::Linear,
+            trail: Easing::Linear,
+            fly_to: Easing::CubicInOut,
+            map_morph: Easing::CubicInOut,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::events::DisplayEvent;
+use crate::layout::LayoutConfig;
+use crate::origins::SensorOrigins;
+use crate::pointer::CursorPosition;
+use crate::scenario::Scenario;
+use crate::target::Target;
+use crate::undo::{EditCommand, UndoStack};
+
+/// The state of [`EditPlugin`]'s editing mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EditMode {
+    /// Whether clicks and drags edit targets; toggled with E.
+    pub active: bool,
+    /// The target being dragged.
+    pub dragging: Option<i32>,
+}
+
+/// The origin drawn nearest to `world`, and the true azimuth and distance from it of
+/// a target drawn there.
+pub fn place_at(world: Vec2, config: &LayoutConfig, origins: &SensorOrigins) -> (usize, f32, f32) {
+    let origin = (0..origins.len())
+        .min_by(|&a, &b| {
+            let d = |o| (origins.offset(o) - world).length_squared();
+            d(a).partial_cmp(&d(b)).unwrap()
+        })
+        .unwrap_or(0);
+    let drawn = world - origins.offset(origin);
+    let (azimuth, dist) = config.polar_at(drawn.x(), drawn.y());
+    (origin, azimuth, dist)
+}
+
+/// An editing mode for authoring scenarios on the 2D ring display.
+/// [`Action::ToggleEditing`], E by default, turns it on and off; while on, clicking empty space adds a target at that bearing and range from
+/// the nearest origin, read back through [`LayoutConfig::scale`] like
+/// [`MeasurePlugin`](crate::measure::MeasurePlugin) reads free points, with the next
+/// unused id. Dragging a marker moves its target's true azimuth and distance to where
+/// the cursor is, and the display lays it out again as it goes. Edits are kept in the
+/// [`Scenario`] resource, and [`Action::ExportScenario`], Ctrl+E by default, writes it
+/// with every target on the display to
+/// `export`. Each addition and drag goes on the [`UndoStack`] for
+/// [`UndoPlugin`](crate::undo::UndoPlugin). Needs [`PoiRingPlugin`](crate::display::PoiRingPlugin) and
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin).
+pub struct EditPlugin {
+    pub export: PathBuf,
+}
+
+struct EditExport(PathBuf);
+
+impl Plugin for EditPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<Scenario>() {
+            app.init_resource::<Scenario>();
+        }
+        if !app.resources().contains::<UndoStack>() {
+            app.init_resource::<UndoStack>();
+        }
+        app.init_resource::<EditMode>()
+            .add_resource(EditExport(self.export.clone()))
+            .add_system(edit_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn edit_system(
+    mut commands: Commands,
+    mut reader: Local<EventReader<DisplayEvent>>,
+    mut pressed_at: Local<Option<Vec2>>,
+    mut drag_from: Local<Option<(i32, (f32, f32))>>,
+    actions: Res<ActionState>,
+    (cursor, config, origins): (Res<CursorPosition>, Res<LayoutConfig>, Res<SensorOrigins>),
+    events: Res<Events<DisplayEvent>>,
+    export: Res<EditExport>,
+    mut mode: ResMut<EditMode>,
+    (mut scenario, mut undo): (ResMut<Scenario>, ResMut<UndoStack>),
+    mut targets: Query<Mut<Target>>,
+) {
+    if actions.just_pressed(Action::ExportScenario) {
+        let exported = Scenario {
+            targets: {
+                let mut live = targets.iter_mut().map(|t| t.clone()).collect::<Vec<_>>();
+                live.sort_by_key(|t| t.id);
+                live
+            },
+            ..scenario.clone()
+        };
+        match exported.to_file(&export.0) {
+            Ok(()) => tracing::info!("scenario exported to {}", export.0.display()),
+            Err(e) => tracing::error!("failed to export {}: {}", export.0.display(), e),
+        }
+    }
+    if actions.just_pressed(Action::ToggleEditing) {
+        mode.active = !mode.active;
+        mode.dragging = None;
+        tracing::info!("editing {}", if mode.active { "on" } else { "off" });
+    }
+    let clicks = reader
+        .iter(&events)
+        .filter_map(|event| match event {
+            DisplayEvent::Clicked { world, target, .. } => Some((*world, *target)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !mode.active {
+        return;
+    }
+    for (world, target) in clicks {
+        match (target, world) {
+            (Some(id), _) => {
+                mode.dragging = Some(id);
+                *pressed_at = world;
+                *drag_from = targets
+                    .iter_mut()
+                    .find(|t| t.id == id)
+                    .map(|t| (id, (t.azimuth, t.dist)));
+            }
+            (None, Some(world)) => {
+                let (origin, azimuth, dist) = place_at(world, &config, &origins);
+                let id = targets
+                    .iter_mut()
+                    .map(|t| t.id)
+                    .chain(scenario.targets.iter().map(|t| t.id))
+                    .max()
+                    .map_or(0, |id| id + 1);
+                let target = Target {
+                    id,
+                    azimuth,
+                    dist,
+                    origin,
+                    ..Default::default()
+                };
+                scenario.targets.push(target.clone());
+                undo.push(EditCommand::AddTarget(target.clone()));
+                commands.spawn((target,));
+            }
+            (None, None) => {}
+        }
+    }
+
+    let id = match mode.dragging {
+        Some(id) if actions.pressed(Action::Select) => id,
+        _ => {
+            mode.dragging = None;
+            if let Some((id, from)) = drag_from.take() {
+                let to = targets
+                    .iter_mut()
+                    .find(|t| t.id == id)
+                    .map(|t| (t.azimuth, t.dist));
+                if let Some(to) = to.filter(|&to| to != from) {
+                    undo.push(EditCommand::MoveTarget { id, from, to });
+                }
+            }
+            return;
+        }
+    };
+    // A click that does not move leaves the target where it truly is, rather than
+    // where its ring has it drawn.
+    let world = match cursor.world {
+        Some(world) if Some(world) != *pressed_at => world,
+        _ => return,
+    };
+    *pressed_at = None;
+    for mut target in targets.iter_mut() {
+        if target.id != id {
+            continue;
+        }
+        let drawn = world - origins.offset(target.origin);
+        let (azimuth, dist) = config.polar_at(drawn.x(), drawn.y());
+        if (target.azimuth, target.dist) != (azimuth, dist) {
+            target.azimuth = azimuth;
+            target.dist = dist;
+            if let Some(saved) = scenario.targets.iter_mut().find(|t| t.id == id) {
+                saved.azimuth = azimuth;
+                saved.dist = dist;
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::display::{MarkerContext, Poi, PoiLabel, RadarDisplay};
+use crate::layout::LayoutConfig;
+use crate::target::Target;
+
+/// How strongly a target is drawn relative to the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Emphasis {
+    Dim,
+    #[default]
+    Normal,
+    Highlight,
+}
+
+impl Emphasis {
+    /// Alpha of the marker and label.
+    pub fn alpha(self) -> f32 {
+        match self {
+            Emphasis::Dim => 0.3,
+            Emphasis::Normal | Emphasis::Highlight => 1.0,
+        }
+    }
+
+    /// Stroke width handed to the marker factory.
+    pub fn stroke_width(self) -> f32 {
+        match self {
+            Emphasis::Dim | Emphasis::Normal => 1.0,
+            Emphasis::Highlight => 3.0,
+        }
+    }
+}
+
+/// Per-target emphasis requested by the host application. Targets without an entry
+/// are drawn [`Emphasis::Normal`].
+#[derive(Debug, Default, Clone)]
+pub struct TargetEmphasis {
+    by_id: HashMap<i32, Emphasis>,
+}
+
+impl TargetEmphasis {
+    pub fn set_target_emphasis(&mut self, id: i32, emphasis: Emphasis) {
+        if emphasis == Emphasis::Normal {
+            self.by_id.remove(&id);
+        } else {
+            self.by_id.insert(id, emphasis);
+        }
+    }
+
+    pub fn emphasis(&self, id: i32) -> Emphasis {
+        self.by_id.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Resets every target to [`Emphasis::Normal`].
+    pub fn clear(&mut self) {
+        self.by_id.clear();
+    }
+}
+
+/// Applies [`TargetEmphasis`] to the markers and labels spawned by the ring display.
+pub struct EmphasisPlugin;
+
+impl Plugin for EmphasisPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<TargetEmphasis>()
+            .add_system(emphasis_system.system());
+    }
+}
+
+// Each marker and label carries the emphasis it is currently drawn with, so only
+// targets whose requested emphasis changed get a new alpha or a re-tessellated mesh.
+#[allow(clippy::type_complexity)]
+fn emphasis_system(
+    requested: Res<TargetEmphasis>,
+    display: Res<RadarDisplay>,
+    config: Res<LayoutConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut markers: Query<(
+        &Poi,
+        &Target,
+        Mut<Emphasis>,
+        &Handle<ColorMaterial>,
+        Mut<Handle<Mesh>>,
+    )>,
+    mut labels: Query<(&PoiLabel, Mut<Emphasis>, Mut<Text>)>,
+) {
+    for (poi, target, mut applied, material, mut mesh) in markers.iter_mut() {
+        let emphasis = requested.emphasis(poi.id);
+        if *applied == emphasis {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_a(emphasis.alpha());
+        }
+        let category = display.categories().style(target);
+        let mut ctx = MarkerContext {
+            meshes: &mut meshes,
+            materials: &mut materials,
+            material: material.clone(),
+            poi_width: config.poi_width,
+            stroke_width: category.stroke_width * emphasis.stroke_width(),
+            shape: category.shape,
+        };
+        *mesh = display.marker(target, &mut ctx).mesh;
+        *applied = emphasis;
+    }
+    for (label, mut applied, mut text) in labels.iter_mut() {
+        let emphasis = requested.emphasis(label.id);
+        if *applied != emphasis {
+            text.style.color.set_a(emphasis.alpha());
+            *applied = emphasis;
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::display::Poi;
+use crate::pointer::{self, CursorPosition};
+use crate::spatial::{self, SpatialIndex};
+
+/// Everything the user does with a display, as one event stream host applications can
+/// read with `EventReader<DisplayEvent>`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayEvent {
+    /// An [`Action::Select`] click inside the display's viewport, with the target under the cursor if
+    /// any and the [`SensorOrigins`](crate::origins::SensorOrigins) entry it belongs to.
+    /// Clicks on bevy_ui nodes that take [`Interaction`] are theirs and not sent.
+    Clicked {
+        screen: Vec2,
+        world: Option<Vec2>,
+        target: Option<i32>,
+        origin: Option<usize>,
+    },
+    /// The target under the cursor changed; `None` when the cursor left all targets.
+    Hovered { target: Option<i32> },
+    /// The selected target changed.
+    Selected { target: Option<i32> },
+    /// A target entered or left a named zone.
+    ZoneCrossed {
+        target: i32,
+        zone: String,
+        entered: bool,
+    },
+    /// A range/bearing measurement between two world points was finished.
+    MeasurementCompleted {
+        from: Vec2,
+        to: Vec2,
+        distance: f32,
+        /// In degrees clockwise from north.
+        bearing: f32,
+    },
+    /// The camera or the displayed object was moved, rotated or zoomed.
+    ViewChanged,
+}
+
+/// Registers [`DisplayEvent`] and emits the pointer events (clicks and hovers over
+/// [`Poi`] markers), picking them through the [`SpatialIndex`]. Other subsystems send
+/// the remaining variants.
+pub struct DisplayEventsPlugin;
+
+impl Plugin for DisplayEventsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        spatial::add_index(app);
+        add_actions(app);
+        app.add_event::<DisplayEvent>()
+            .init_resource::<CursorPosition>()
+            .add_system(pointer::cursor_system.system())
+            .add_system(pointer_events_system.system());
+    }
+}
+
+/// The target under `world`, preferring the closest marker center when markers overlap.
+pub fn poi_at<'a>(world: Vec2, pois: impl Iterator<Item = &'a Poi>) -> Option<i32> {
+    poi_under(world, pois).map(|poi| poi.id)
+}
+
+/// The marker under `world` like [`poi_at`], which also tells the origin it belongs to
+/// where the rings of several origins overlap.
+pub fn poi_under<'a>(world: Vec2, pois: impl Iterator<Item = &'a Poi>) -> Option<&'a Poi> {
+    pois.filter(|poi| poi.contains(world))
+        .map(|poi| (poi, (poi.center - world).length_squared()))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(poi, _)| poi)
+}
+
+#[derive(Default)]
+struct HoverState {
+    target: Option<i32>,
+}
+
+fn pointer_events_system(
+    mut hover: Local<HoverState>,
+    cursor: Res<CursorPosition>,
+    actions: Res<ActionState>,
+    mut events: ResMut<Events<DisplayEvent>>,
+    index: Res<SpatialIndex>,
+    ui: Query<&Interaction>,
+) {
+    let hit = cursor.world.and_then(|world| index.under(world));
+    let target = hit.map(|poi| poi.id);
+    if target != hover.target {
+        hover.target = target;
+        events.send(DisplayEvent::Hovered { target });
+    }
+    let on_ui = ui.iter().any(|i| *i != Interaction::None);
+    if actions.just_pressed(Action::Select) && cursor.viewport.is_some() && !on_ui {
+        if let Some(screen) = cursor.screen {
+            events.send(DisplayEvent::Clicked {
+                screen,
+                world: cursor.world,
+                target,
+                origin: hit.map(|poi| poi.origin),
+            });
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+#[cfg(not(feature = "web"))]
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::str::FromStr;
+#[cfg(not(feature = "web"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(feature = "web"))]
+use std::sync::Arc;
+#[cfg(not(feature = "web"))]
+use std::thread;
+#[cfg(not(feature = "web"))]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "web")]
+use instant::Instant;
+
+use bevy::prelude::*;
+use crossbeam_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "web"))]
+use tungstenite::handshake::HandshakeError;
+#[cfg(not(feature = "web"))]
+use tungstenite::protocol::WebSocketConfig;
+#[cfg(not(feature = "web"))]
+use tungstenite::{error::ProtocolError, Error as WsError, Message};
+
+use crate::io::TargetRecord;
+use crate::metrics::Metrics;
+use crate::updates::{TargetAdded, TargetChanged, TargetRemoved};
+
+/// Messages waiting to be applied, per source. Past this the threads reading a source
+/// wait for room, holding back its sockets rather than queueing without bound.
+const QUEUE: usize = 4096;
+/// Largest WebSocket message accepted; bigger ones close the connection.
+#[cfg(not(feature = "web"))]
+const MAX_MESSAGE: usize = 1 << 20;
+/// WebSocket clients served at once; more are turned away until one leaves.
+#[cfg(not(feature = "web"))]
+const MAX_CLIENTS: usize = 32;
+/// How long a WebSocket client has to complete the opening handshake.
+#[cfg(not(feature = "web"))]
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a WebSocket client may send nothing, not even a ping, before it's dropped.
+#[cfg(not(feature = "web"))]
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// One line of the feed protocol: a JSON object per line, e.g.
+///
+/// ```text
+/// {"type": "add", "target": {"id": 7, "label": "buoy", "azimuth": 45.0, "distance": 30.0}}
+/// {"type": "change", "target": {"id": 7, "azimuth": 46.5, "distance": 29.0}}
+/// {"type": "remove", "id": 7}
+/// ```
+///
+/// Targets use the same fields as target files (see [`crate::io`]), with the azimuth
+/// in degrees.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FeedMessage {
+    Add { target: TargetRecord },
+    Change { target: TargetRecord },
+    Remove { id: i32 },
+}
+
+impl FeedMessage {
+    /// Parses and validates one protocol line.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let message: FeedMessage = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        match &message {
+            FeedMessage::Add { target } | FeedMessage::Change { target } => target.validate()?,
+            FeedMessage::Remove { .. } => {}
+        }
+        Ok(message)
+    }
+}
+
+/// Where [`TargetFeedPlugin`] listens for messages. Written as [`FeedSource::from_str`]
+/// reads it in config files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FeedSource {
+    /// Datagrams of one or more protocol lines. Not available with the `web` feature,
+    /// as pages can't receive them.
+    Udp(SocketAddr),
+    /// A WebSocket server; each text message holds one or more protocol lines. Up to
+    /// [`MAX_CLIENTS`] clients may connect at once, and ones quiet for
+    /// [`IDLE_TIMEOUT`] are dropped. With the `web` feature the page is a client
+    /// instead, of the server at this address.
+    WebSocket(SocketAddr),
+}
+
+impl FromStr for FeedSource {
+    type Err = String;
+
+    /// `udp://HOST:PORT` or `ws://HOST:PORT`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, addr) = s
+            .split_once("://")
+            .ok_or_else(|| format!("expected udp://ADDR or ws://ADDR, got {:?}", s))?;
+        let addr = addr
+            .trim_end_matches('/')
+            .parse()
+            .map_err(|e| format!("{:?}: {}", addr, e))?;
+        match scheme {
+            "udp" => Ok(FeedSource::Udp(addr)),
+            "ws" => Ok(FeedSource::WebSocket(addr)),
+            _ => Err(format!("unsupported feed scheme {:?}", scheme)),
+        }
+    }
+}
+
+impl TryFrom<String> for FeedSource {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<FeedSource> for String {
+    fn from(source: FeedSource) -> Self {
+        source.to_string()
+    }
+}
+
+impl fmt::Display for FeedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedSource::Udp(addr) => write!(f, "udp://{}", addr),
+            FeedSource::WebSocket(addr) => write!(f, "ws://{}", addr),
+        }
+    }
+}
+
+/// Listens on `source` for [`FeedMessage`]s and turns them into [`TargetAdded`],
+/// [`TargetChanged`] and [`TargetRemoved`] events. Malformed lines are reported and
+/// skipped. How long messages wait to be applied is recorded in [`Metrics`] as the
+/// feed latency. Add it once per source, or open more through [`TargetFeeds`]. Needs
+/// [`TargetUpdatesPlugin`](crate::updates::TargetUpdatesPlugin), which
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin) adds.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetFeedPlugin {
+    pub source: FeedSource,
+}
+
+/// A message and when it arrived.
+type Received = (Instant, FeedMessage);
+
+/// The sources [`TargetFeedPlugin`] takes messages from, to open and close them while
+/// the app runs.
+#[derive(Default)]
+pub struct TargetFeeds {
+    open: Vec<(FeedSource, Receiver<Received>)>,
+    /// Closed sources, whose messages are dropped as they arrive.
+    closed: Vec<(FeedSource, Receiver<Received>)>,
+}
+
+impl TargetFeeds {
+    /// Starts taking messages from `source`, binding it unless it was open before.
+    pub fn open(&mut self, source: FeedSource) -> io::Result<()> {
+        if self.open.iter().any(|(open, _)| *open == source) {
+            return Ok(());
+        }
+        if let Some(i) = self.closed.iter().position(|(closed, _)| *closed == source) {
+            self.open.push(self.closed.remove(i));
+            return Ok(());
+        }
+        let (sender, receiver) = crossbeam_channel::bounded(QUEUE);
+        listen(source, sender)?;
+        self.open.push((source, receiver));
+        Ok(())
+    }
+
+    /// Stops taking messages from `source`. The threads reading it can't be stopped, so
+    /// it stays bound until the app exits and opening it again picks up from there.
+    pub fn close(&mut self, source: FeedSource) {
+        if let Some(i) = self.open.iter().position(|(open, _)| *open == source) {
+            self.closed.push(self.open.remove(i));
+        }
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = FeedSource> + '_ {
+        self.open.iter().map(|(source, _)| *source)
+    }
+}
+
+/// Adds [`TargetFeeds`] and the system applying their messages, unless already added.
+pub(crate) fn add_feeds(app: &mut AppBuilder) {
+    if app.resources().contains::<TargetFeeds>() {
+        return;
+    }
+    if !app.resources().contains::<Metrics>() {
+        app.init_resource::<Metrics>();
+    }
+    app.init_resource::<TargetFeeds>()
+        .add_system(feed_system.system());
+}
+
+impl Plugin for TargetFeedPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_feeds(app);
+        let mut feeds = app.resources().get_mut::<TargetFeeds>().unwrap();
+        if let Err(e) = feeds.open(self.source) {
+            tracing::warn!("target feed {} disabled: {}", self.source, e);
+        }
+    }
+}
+
+fn feed_system(
+    feeds: Res<TargetFeeds>,
+    mut added: ResMut<Events<TargetAdded>>,
+    mut changed: ResMut<Events<TargetChanged>>,
+    mut removed: ResMut<Events<TargetRemoved>>,
+    mut metrics: ResMut<Metrics>,
+) {
+    for (_, receiver) in &feeds.closed {
+        receiver.try_iter().for_each(drop);
+    }
+    let mut latency = None;
+    let messages = feeds
+        .open
+        .iter()
+        .flat_map(|(_, receiver)| receiver.try_iter());
+    for (arrived, message) in messages {
+        latency = latency.max(Some(arrived.elapsed()));
+        match message {
+            FeedMessage::Add { target } => added.send(TargetAdded(target.into_target())),
+            FeedMessage::Change { target } => changed.send(TargetChanged(target.into_target())),
+            FeedMessage::Remove { id } => removed.send(TargetRemoved(id)),
+        }
+    }
+    if let Some(latency) = latency {
+        metrics.record_feed(latency);
+    }
+}
+
+/// Binds `source` and starts the threads that read from it.
+#[cfg(not(feature = "web"))]
+fn listen(source: FeedSource, sender: Sender<Received>) -> io::Result<()> {
+    match source {
+        FeedSource::Udp(addr) => {
+            let socket = UdpSocket::bind(addr)?;
+            spawn("target-feed-udp", move || {
+                let mut buf = vec![0; 65536];
+                loop {
+                    match socket.recv_from(&mut buf) {
+                        Ok((len, from)) => {
+                            forward(&String::from_utf8_lossy(&buf[..len]), from, &sender)
+                        }
+                        Err(e) => tracing::warn!("target feed: {}", e),
+                    }
+                }
+            })
+        }
+        FeedSource::WebSocket(addr) => {
+            let listener = TcpListener::bind(addr)?;
+            let clients = Arc::new(AtomicUsize::new(0));
+            spawn("target-feed-ws", move || {
+                for stream in listener.incoming() {
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            tracing::warn!("target feed: {}", e);
+                            continue;
+                        }
+                    };
+                    // Only this thread adds clients, so the count can't pass the cap.
+                    if clients.load(Ordering::SeqCst) >= MAX_CLIENTS {
+                        if let Ok(from) = stream.peer_addr() {
+                            tracing::warn!("target feed: {}: too many clients", from);
+                        }
+                        continue;
+                    }
+                    let client = Client::new(&clients);
+                    let sender = sender.clone();
+                    let result = spawn("target-feed-client", move || {
+                        let _client = client;
+                        let from = match stream.peer_addr() {
+                            Ok(from) => from,
+                            Err(_) => return,
+                        };
+                        match serve_websocket(stream, &sender) {
+                            // Closed, with or without a close frame.
+                            Ok(())
+                            | Err(WsError::ConnectionClosed)
+                            | Err(WsError::Protocol(ProtocolError::ResetWithoutClosingHandshake)) =>
+                                {}
+                            Err(WsError::Io(e))
+                                if matches!(
+                                    e.kind(),
+                                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                                ) =>
+                            {
+                                tracing::warn!("target feed: {}: timed out", from)
+                            }
+                            Err(e) => tracing::warn!("target feed: {}: {}", from, e),
+                        }
+                    });
+                    if let Err(e) = result {
+                        tracing::warn!("target feed: {}", e);
+                    }
+                }
+            })
+        }
+    }
+}
+
+/// Connects to `source` from the page.
+#[cfg(feature = "web")]
+fn listen(source: FeedSource, sender: Sender<Received>) -> io::Result<()> {
+    match source {
+        FeedSource::Udp(_) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "UDP can't be received in the browser",
+        )),
+        FeedSource::WebSocket(addr) => {
+            crate::web::connect(&format!("ws://{}", addr), move |text| {
+                forward(text, addr, &sender)
+            })
+        }
+    }
+}
+
+#[cfg(not(feature = "web"))]
+fn spawn(name: &str, f: impl FnOnce() + Send + 'static) -> io::Result<()> {
+    thread::Builder::new()
+        .name(name.to_string())
+        .spawn(f)
+        .map(|_| ())
+}
+
+/// Parses each non-empty line of `text` and passes the messages on.
+fn forward(text: &str, from: SocketAddr, sender: &Sender<Received>) {
+    let arrived = Instant::now();
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        match FeedMessage::parse(line) {
+            // The display is gone once nobody receives; the thread ends with the app.
+            #[cfg(not(feature = "web"))]
+            Ok(message) => {
+                let _ = sender.send((arrived, message));
+            }
+            // Waiting for room would block the page, which is what makes room.
+            #[cfg(feature = "web")]
+            Ok(message) => {
+                if sender.try_send((arrived, message)).is_err() {
+                    tracing::warn!("target feed: {}: queue full, message dropped", from);
+                }
+            }
+            Err(e) => tracing::warn!("target feed: {}: {}", from, e),
+        }
+    }
+}
+
+/// Completes the opening handshake and forwards text messages until the client
+/// closes the connection or goes quiet for [`IDLE_TIMEOUT`].
+#[cfg(not(feature = "web"))]
+#[allow(clippy::result_large_err)]
+fn serve_websocket(stream: TcpStream, sender: &Sender<Received>) -> tungstenite::Result<()> {
+    let from = stream.peer_addr()?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(MAX_MESSAGE))
+        .max_frame_size(Some(MAX_MESSAGE));
+    let mut socket =
+        tungstenite::accept_with_config(stream, Some(config)).map_err(|e| match e {
+            HandshakeError::Failure(e) => e,
+            // A blocking stream only stops short of the end when its read timed out.
+            HandshakeError::Interrupted(_) => io::Error::from(io::ErrorKind::TimedOut).into(),
+        })?;
+    socket.get_ref().set_read_timeout(Some(IDLE_TIMEOUT))?;
+    loop {
+        // Pings are answered and close frames returned by `read` itself.
+        match socket.read()? {
+            Message::Text(text) => forward(text.as_str(), from, sender),
+            // Binary messages are taken as UTF-8 text too.
+            Message::Binary(data) => forward(&String::from_utf8_lossy(&data), from, sender),
+            _ => {}
+        }
+    }
+}
+
+/// Counts toward [`MAX_CLIENTS`] until dropped.
+#[cfg(not(feature = "web"))]
+struct Client(Arc<AtomicUsize>);
+
+#[cfg(not(feature = "web"))]
+impl Client {
+    fn new(clients: &Arc<AtomicUsize>) -> Self {
+        clients.fetch_add(1, Ordering::SeqCst);
+        Client(clients.clone())
+    }
+}
+
+#[cfg(not(feature = "web"))]
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
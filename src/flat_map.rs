@@ -0,0 +1,479 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+
+use bevy::asset::HandleId;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::render::pipeline::PrimitiveTopology;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::bodies::{local_to_geo, Body};
+use crate::easing::{Easing, EasingConfig};
+use crate::geo::{LatLon, Projection};
+use crate::occlusion::Occluder;
+
+/// Degrees within which a vertex counts as on a pole, where its longitude means nothing,
+/// or on the antimeridian, where it could be either side.
+const ON_LINE: f32 = 1e-3;
+/// Longest edge, in radians of arc on the map, of a triangle or line still drawn there;
+/// longer ones are those the map cuts through, such as across its edge.
+const MAX_EDGE: f32 = PI / 2.0;
+
+/// Flattens the [`Body`] it is on, and everything drawn on it, into a map in
+/// `projection`, morphing smoothly between globe and map as [`FlatMapPlugin`] plays
+/// it. The map lies against the globe where it touches `projection`'s center, facing
+/// out from there, so a camera looking down on that point looks straight at it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlatMap {
+    pub projection: Projection,
+    /// Whether the body shows as the map rather than the globe.
+    pub flat: bool,
+    /// Seconds the morph takes.
+    pub duration: f32,
+    pub easing: Easing,
+    progress: f32,
+}
+
+impl Default for FlatMap {
+    fn default() -> Self {
+        FlatMap {
+            projection: Projection::default(),
+            flat: false,
+            duration: 1.5,
+            easing: EasingConfig::default().map_morph,
+            progress: 0.0,
+        }
+    }
+}
+
+impl FlatMap {
+    pub fn new(projection: Projection) -> Self {
+        FlatMap {
+            projection,
+            ..Default::default()
+        }
+    }
+
+    /// How far the body has morphed, from 0 as the globe to 1 as the map, before
+    /// easing.
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Whether the body is the map and not on the way to or from it.
+    pub fn is_flat(&self) -> bool {
+        self.progress >= 1.0
+    }
+
+    /// The place where the map touches the globe.
+    pub fn center(&self) -> LatLon {
+        match self.projection {
+            Projection::Equirectangular => LatLon::default(),
+            Projection::AzimuthalEquidistant { center } => center,
+        }
+    }
+
+    /// Where `place`, `height` above a body of `radius`, lies on the map, in the body's
+    /// frame.
+    pub fn flat_point(&self, place: LatLon, radius: f32, height: f32) -> Vec3 {
+        let (normal, east, north) = self.axes();
+        let xy = self.projection.project(place);
+        normal * (radius + height) + (east * xy.x() + north * xy.y()) * radius
+    }
+
+    /// Where a ray from `origin` along `dir`, both in the frame of a body of `radius`,
+    /// meets the map, and the place shown there, if it meets it on the map at all.
+    pub fn pick(&self, origin: Vec3, dir: Vec3, radius: f32) -> Option<(Vec3, LatLon)> {
+        let (normal, east, north) = self.axes();
+        let towards = dir.dot(normal);
+        if towards.abs() <= f32::EPSILON {
+            return None;
+        }
+        let t = (radius - origin.dot(normal)) / towards;
+        if t < 0.0 {
+            return None;
+        }
+        let hit = origin + dir * t;
+        let xy = Vec2::new(hit.dot(east), hit.dot(north)) / radius;
+        self.projection.unproject(xy).map(|place| (hit, place))
+    }
+
+    /// The map's axes in the body's frame: out of its face, then east and north at its
+    /// center.
+    fn axes(&self) -> (Vec3, Vec3, Vec3) {
+        let center = self.center();
+        let (sin_lat, cos_lat) = center.lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = center.lon.to_radians().sin_cos();
+        (
+            center.to_cartesian(1.0).into(),
+            Vec3::new(sin_lon, cos_lon, 0.0),
+            Vec3::new(sin_lat * cos_lon, -sin_lat * sin_lon, -cos_lat),
+        )
+    }
+
+    /// Where `local` on a body of `radius` is with the morph eased to `t`.
+    fn morph_point(&self, local: Vec3, place: LatLon, radius: f32, t: f32) -> Vec3 {
+        let flat = self.flat_point(place, radius, local.length() - radius);
+        local + (flat - local) * t
+    }
+}
+
+/// A mesh drawn in a body's frame as it is on the globe, and how it was last morphed.
+struct MeshMorph {
+    body: Entity,
+    base: Vec<[f32; 3]>,
+    normals: Option<Vec<[f32; 3]>>,
+    places: Vec<LatLon>,
+    indices: Option<Vec<u32>>,
+    /// The vertices of each of the mesh's triangles or lines in turn, if it is made of
+    /// either, `size` to a primitive.
+    primitives: Vec<usize>,
+    size: usize,
+    /// The indices on a map in the projection, with the primitives it cuts through
+    /// left out.
+    cut: Option<(Projection, Vec<u32>)>,
+    written: Vec<[f32; 3]>,
+    at: Option<(f32, Projection)>,
+}
+
+impl MeshMorph {
+    fn new(body: Entity, mesh: &Mesh, positions: &[[f32; 3]]) -> Self {
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float3(normals)) => Some(normals.clone()),
+            _ => None,
+        };
+        let indices = match mesh.indices() {
+            Some(Indices::U32(indices)) => Some(indices.clone()),
+            Some(Indices::U16(indices)) => Some(indices.iter().map(|&i| i as u32).collect()),
+            None => None,
+        };
+        let order = match &indices {
+            Some(indices) => indices.iter().map(|&i| i as usize).collect(),
+            None => (0..positions.len()).collect::<Vec<_>>(),
+        };
+        let size = match mesh.primitive_topology() {
+            PrimitiveTopology::TriangleList => 3,
+            PrimitiveTopology::LineList => 2,
+            _ => 0,
+        };
+        let primitives = if size > 0 {
+            order.chunks_exact(size).collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        let places = places(positions, &primitives);
+        MeshMorph {
+            body,
+            base: positions.to_vec(),
+            normals,
+            places,
+            indices,
+            primitives: primitives.concat(),
+            size,
+            cut: None,
+            written: positions.to_vec(),
+            at: None,
+        }
+    }
+
+    /// The indices on a map in `projection`, each primitive with an edge longer than
+    /// [`MAX_EDGE`] there squashed into a point.
+    fn cut(&self, projection: Projection) -> Vec<u32> {
+        let xy = self
+            .places
+            .iter()
+            .map(|&place| projection.project(place))
+            .collect::<Vec<_>>();
+        self.primitives
+            .chunks_exact(self.size)
+            .flat_map(|primitive| {
+                let long = primitive
+                    .iter()
+                    .zip(primitive.iter().cycle().skip(1))
+                    .any(|(&a, &b)| (xy[a] - xy[b]).length() > MAX_EDGE);
+                primitive
+                    .iter()
+                    .map(move |&i| if long { primitive[0] } else { i } as u32)
+            })
+            .collect()
+    }
+
+    fn write(&mut self, mesh: &mut Mesh, map: &FlatMap, radius: f32, t: f32) {
+        let positions = self
+            .base
+            .iter()
+            .zip(&self.places)
+            .map(|(&p, &place)| <[f32; 3]>::from(map.morph_point(Vec3::from(p), place, radius, t)))
+            .collect::<Vec<_>>();
+        if let Some(normals) = &self.normals {
+            let (out, _, _) = map.axes();
+            let normals = normals
+                .iter()
+                .map(|&n| {
+                    let n = Vec3::from(n);
+                    <[f32; 3]>::from((n + (out - n) * t).normalize())
+                })
+                .collect::<Vec<_>>();
+            mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+        }
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone().into());
+        let indices = if t > 0.0 && self.size > 0 {
+            if self
+                .cut
+                .as_ref()
+                .is_none_or(|(at, _)| *at != map.projection)
+            {
+                self.cut = Some((map.projection, self.cut(map.projection)));
+            }
+            self.cut.as_ref().map(|(_, cut)| cut.clone())
+        } else {
+            self.indices.clone()
+        };
+        mesh.set_indices(indices.map(Indices::U32));
+        self.written = positions;
+        self.at = Some((t, map.projection));
+    }
+}
+
+/// The place under each of `positions`, those on the antimeridian put on the side of
+/// the primitives they are in, and those on a pole at the longitude of the rest of
+/// theirs, so that the map has no primitives reaching across it.
+fn places(positions: &[[f32; 3]], primitives: &[&[usize]]) -> Vec<LatLon> {
+    let mut places = positions
+        .iter()
+        .map(|&p| {
+            let (lat, lon) = local_to_geo(Vec3::from(p));
+            LatLon::new(lat, lon)
+        })
+        .collect::<Vec<_>>();
+    let on_pole = |place: &LatLon| place.lat.abs() > 90.0 - ON_LINE;
+    let on_seam = |place: &LatLon| !on_pole(place) && place.lon.abs() > 180.0 - ON_LINE;
+    let mut sides = vec![0.0; places.len()];
+    for primitive in primitives {
+        for &i in primitive.iter().filter(|&&i| on_seam(&places[i])) {
+            sides[i] += primitive
+                .iter()
+                .filter(|&&j| !on_seam(&places[j]) && !on_pole(&places[j]))
+                .map(|&j| places[j].lon.signum())
+                .sum::<f32>();
+        }
+    }
+    for (place, side) in places.iter_mut().zip(&sides) {
+        if *side != 0.0 {
+            place.lon = 180_f32.copysign(*side);
+        }
+    }
+    let mut around = vec![Vec2::zero(); places.len()];
+    for primitive in primitives {
+        for &i in primitive.iter().filter(|&&i| on_pole(&places[i])) {
+            for &j in primitive.iter().filter(|&&j| !on_pole(&places[j])) {
+                let (sin, cos) = places[j].lon.to_radians().sin_cos();
+                around[i] += Vec2::new(cos, sin);
+            }
+        }
+    }
+    for (place, around) in places.iter_mut().zip(&around) {
+        if around.length_squared() > 0.0 {
+            place.lon = around.y().atan2(around.x()).to_degrees();
+        }
+    }
+    places
+}
+
+/// An entity placed on a body by its translation, such as a pin, and where it was.
+struct PlacedMorph {
+    base: Transform,
+    written: Transform,
+}
+
+#[derive(Default)]
+struct MorphState {
+    meshes: HashMap<HandleId, MeshMorph>,
+    placed: HashMap<Entity, PlacedMorph>,
+    /// Each morphed body, with its [`Occluder`]'s radius on the globe.
+    bodies: HashMap<Entity, Option<f32>>,
+}
+
+/// Plays each [`FlatMap`]'s morph and flattens its body with it: meshes drawn in the
+/// body's frame, like the globe itself, its shells, routes, lines and GeoJSON layers,
+/// have their vertices moved onto the map, and entities placed on the body by their
+/// translation, like [`GeoMarker`](crate::geo_marker::GeoMarker)s, are moved there and
+/// turned to face out of it. Meshes and places changed while flat are flattened again.
+/// The body's [`Occluder`] is shrunk away meanwhile, as nothing on the map is behind
+/// it. [`Action::ToggleFlatMap`], F by default, switches every flat map between globe
+/// and map.
+pub struct FlatMapPlugin;
+
+impl Plugin for FlatMapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        app.add_system(flat_map_toggle_system.system())
+            .add_system(flat_map_morph_system.system());
+    }
+}
+
+fn flat_map_toggle_system(actions: Res<ActionState>, mut maps: Query<Mut<FlatMap>>) {
+    if actions.just_pressed(Action::ToggleFlatMap) {
+        for mut map in maps.iter_mut() {
+            map.flat = !map.flat;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn flat_map_morph_system(
+    mut state: Local<MorphState>,
+    time: Res<Time>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut maps: Query<(Entity, &Body, Mut<FlatMap>, Option<Mut<Occluder>>)>,
+    mut nodes: Query<(Mut<Transform>, Option<&Handle<Mesh>>, Option<&Children>)>,
+) {
+    for (body, Body { radius, .. }, mut map, occluder) in maps.iter_mut() {
+        let step = if map.duration > 0.0 {
+            time.delta_seconds / map.duration
+        } else {
+            1.0
+        };
+        let progress = if map.flat {
+            (map.progress + step).min(1.0)
+        } else {
+            (map.progress - step).max(0.0)
+        };
+        if progress != map.progress {
+            map.progress = progress;
+        }
+        let morphed = state.bodies.contains_key(&body);
+        if progress == 0.0 && !morphed {
+            continue;
+        }
+        let t = map.easing.apply(progress);
+        if !morphed {
+            let radius = occluder.as_ref().map(|occluder| occluder.radius);
+            state.bodies.insert(body, radius);
+        }
+        if let Some(mut occluder) = occluder {
+            let globe = state.bodies.get(&body).copied().flatten();
+            let radius = if progress > 0.0 {
+                0.0
+            } else {
+                globe.unwrap_or(occluder.radius)
+            };
+            if occluder.radius != radius {
+                occluder.radius = radius;
+            }
+        }
+
+        // The body's own meshes and those of its children drawn in its frame, down to
+        // the ones placed on it.
+        let mut frame = vec![body];
+        let mut placed = Vec::new();
+        let mut seen = HashSet::new();
+        while let Some(entity) = frame.pop() {
+            let (transform, mesh, children) = match nodes.get_mut(entity) {
+                Ok(node) => node,
+                Err(_) => continue,
+            };
+            let in_frame = entity == body
+                || (transform.translation.length() <= f32::EPSILON
+                    && transform.rotation.is_near_identity()
+                    && transform.scale.abs_diff_eq(Vec3::one(), f32::EPSILON));
+            if !in_frame {
+                if transform.translation.length() > f32::EPSILON {
+                    placed.push(entity);
+                }
+                continue;
+            }
+            if let Some(children) = children {
+                frame.extend(children.0.iter().copied());
+            }
+            let handle = match mesh {
+                Some(handle) if seen.insert(handle.id) => handle,
+                _ => continue,
+            };
+            let mesh = match meshes.get_mut(handle) {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+            let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+                Some(VertexAttributeValues::Float3(positions)) => positions,
+                _ => continue,
+            };
+            // A mesh changed since it was last morphed is as it is meant to be on the
+            // globe.
+            let stale = state
+                .meshes
+                .get(&handle.id)
+                .is_none_or(|morph| morph.written != *positions);
+            if stale {
+                let morph = MeshMorph::new(body, mesh, positions);
+                state.meshes.insert(handle.id, morph);
+            }
+            let morph = state.meshes.get_mut(&handle.id).unwrap();
+            if morph.at != Some((t, map.projection)) {
+                morph.write(mesh, &map, *radius, t);
+            }
+        }
+
+        let (out, _, _) = map.axes();
+        for entity in placed {
+            let mut transform = match nodes.get_mut(entity) {
+                Ok((transform, ..)) => transform,
+                Err(_) => continue,
+            };
+            let stale = state
+                .placed
+                .get(&entity)
+                .is_none_or(|morph| morph.written != *transform);
+            if stale {
+                state.placed.insert(
+                    entity,
+                    PlacedMorph {
+                        base: *transform,
+                        written: *transform,
+                    },
+                );
+            }
+            let morph = state.placed.get_mut(&entity).unwrap();
+            let base = morph.base;
+            let (lat, lon) = local_to_geo(base.translation);
+            let up = base.translation.normalize();
+            let turn = up.cross(out);
+            let facing = if turn.length() > f32::EPSILON {
+                Quat::from_axis_angle(turn.normalize(), up.dot(out).clamp(-1.0, 1.0).acos())
+            } else {
+                Quat::identity()
+            };
+            let moved = Transform {
+                translation: map.morph_point(base.translation, LatLon::new(lat, lon), *radius, t),
+                rotation: base.rotation.slerp(facing * base.rotation, t),
+                scale: base.scale,
+            };
+            if *transform != moved {
+                *transform = moved;
+            }
+            morph.written = moved;
+            if progress == 0.0 {
+                state.placed.remove(&entity);
+            }
+        }
+        if progress == 0.0 {
+            // Meshes it no longer shows, such as other levels of detail, are put back on
+            // the globe too.
+            let done = state
+                .meshes
+                .iter()
+                .filter(|(_, morph)| morph.body == body)
+                .map(|(&id, _)| id)
+                .collect::<Vec<_>>();
+            for id in done {
+                let mut morph = state.meshes.remove(&id).unwrap();
+                if let Some(mesh) = meshes.get_mut(id) {
+                    if morph.at.is_some_and(|(t, _)| t != 0.0) {
+                        morph.write(mesh, &map, *radius, 0.0);
+                    }
+                }
+            }
+            state.bodies.remove(&body);
+        }
+    }
+}
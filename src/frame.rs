@@ -0,0 +1,145 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::display::RefRing;
+use crate::theme::Theme;
+
+/// How far past the bezel the mask reaches. Anything drawn beyond that is
+/// assumed to be off screen.
+const MASK_EXTENT: f32 = 10_000.0;
+const MASK_Z: f32 = 50.0;
+const BEZEL_Z: f32 = 51.0;
+
+/// The console-style frame drawn around the outermost ring by [`FramePlugin`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bezel {
+    /// Gap between the outermost ring and the inner edge of the bezel.
+    pub margin: f32,
+    /// Width of the bezel band.
+    pub width: f32,
+    /// Draws bearing ticks every 10°, longer every 30°.
+    pub ticks: bool,
+    /// Covers everything outside the bezel in the background color.
+    pub mask: bool,
+}
+
+impl Default for Bezel {
+    fn default() -> Self {
+        Bezel {
+            margin: 10.0,
+            width: 12.0,
+            ticks: true,
+            mask: true,
+        }
+    }
+}
+
+impl Bezel {
+    /// Inner radius of the bezel around rings reaching out to `outer_ring`.
+    pub fn inner_radius(&self, outer_ring: f32) -> f32 {
+        outer_ring + self.margin
+    }
+}
+
+/// Marks the entities drawn by [`FramePlugin`].
+#[derive(Debug, Clone, Copy)]
+pub struct FramePart;
+
+/// Frames the display in a [`Bezel`] that follows the outermost ring as rings come
+/// and go. With `mask` set, markers and rings outside the bezel are covered up; text
+/// labels are drawn by the UI pass and stay visible.
+pub struct FramePlugin;
+
+impl Plugin for FramePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Bezel>() {
+            app.init_resource::<Bezel>();
+        }
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        app.add_system(frame_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn frame_system(
+    mut commands: Commands,
+    mut drawn: Local<Option<(f32, Bezel, Theme)>>,
+    bezel: Res<Bezel>,
+    theme: Res<Theme>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    rings: Query<&RefRing>,
+    parts: Query<With<FramePart, Entity>>,
+) {
+    // Framed around the world origin, that is the rings of the first origin.
+    let outer = rings
+        .iter()
+        .filter(|r| r.origin == 0)
+        .map(|r| r.radius)
+        .fold(0.0, f32::max);
+    let state = (outer, *bezel, *theme);
+    if *drawn == Some(state) {
+        return;
+    }
+    *drawn = Some(state);
+    for entity in parts.iter() {
+        commands.despawn(entity);
+    }
+
+    let inner = bezel.inner_radius(outer);
+    let outside = inner + bezel.width;
+    if bezel.mask {
+        let material = materials.add(theme.background().into());
+        commands
+            .spawn(ring(material, &mut meshes, outside, MASK_EXTENT, MASK_Z))
+            .with(FramePart);
+    }
+    let material = materials.add(theme.bezel().into());
+    commands
+        .spawn(ring(material, &mut meshes, inner, bezel.width, BEZEL_Z))
+        .with(FramePart);
+    if bezel.ticks {
+        let material = materials.add(theme.stroke().into());
+        let mut builder = PathBuilder::new();
+        for i in 0..36 {
+            let angle = i as f32 * PI / 18.0;
+            let length = if i % 3 == 0 {
+                bezel.width
+            } else {
+                bezel.width / 2.0
+            };
+            let dir = Vec2::new(angle.cos(), angle.sin());
+            let (from, to) = (dir * inner, dir * (inner + length));
+            builder.move_to(point(from.x(), from.y()));
+            builder.line_to(point(to.x(), to.y()));
+        }
+        let ticks = builder.build().stroke(
+            material,
+            &mut meshes,
+            Vec3::new(0.0, 0.0, BEZEL_Z + 1.0),
+            &StrokeOptions::default(),
+        );
+        commands.spawn(ticks).with(FramePart);
+    }
+}
+
+/// A band `width` wide starting at radius `inner`.
+fn ring(
+    material: Handle<ColorMaterial>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    inner: f32,
+    width: f32,
+    z: f32,
+) -> SpriteComponents {
+    primitive(
+        material,
+        meshes,
+        ShapeType::Circle(inner + width / 2.0),
+        TessellationMode::Stroke(&StrokeOptions::default().with_line_width(width)),
+        Vec3::new(0.0, 0.0, z),
+    )
+}
@@ -0,0 +1,114 @@
+use std::f32::consts::PI;
+use std::fmt;
+
+use rand::prelude::*;
+
+use crate::layout::{self, LayoutViolation, RingLayout};
+use crate::target::Target;
+
+/// Shapes the random target sets produced by [`fuzz_layout`].
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    pub poi_width: f32,
+    pub min_dist: f32,
+    pub max_dist: f32,
+    /// Probability that a target is placed within `seam_width` of the 0/2π seam.
+    pub seam_bias: f64,
+    pub seam_width: f32,
+    /// Probability that a target reuses the azimuth of an earlier one.
+    pub duplicate_bias: f64,
+    /// Probability that a target copies both the azimuth and the distance of an
+    /// earlier one.
+    pub identical_bias: f64,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        FuzzConfig {
+            poi_width: 30.0,
+            min_dist: 10.0,
+            max_dist: 100.0,
+            seam_bias: 0.2,
+            seam_width: 0.1,
+            duplicate_bias: 0.2,
+            identical_bias: 0.05,
+        }
+    }
+}
+
+/// A target set the layout failed on, with the reason.
+#[derive(Debug, Clone)]
+pub struct FuzzFailure {
+    pub seed: u64,
+    pub targets: Vec<Target>,
+    pub violation: LayoutViolation,
+}
+
+impl fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed {}: {}", self.seed, self.violation)?;
+        for t in &self.targets {
+            writeln!(f, "\t{:?}", t)?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates `count` random targets from `seed`, lays them out and runs
+/// [`layout::verify`] and [`layout::verify_order`] on the result. The same inputs
+/// always produce the same targets.
+pub fn fuzz_layout(
+    seed: u64,
+    count: usize,
+    config: &FuzzConfig,
+) -> Result<RingLayout, FuzzFailure> {
+    let targets = fuzz_targets(seed, count, config);
+    let layout = RingLayout::arrange(&targets, config.poi_width);
+    match layout::verify(&layout, &targets).and_then(|_| layout::verify_order(&layout, &targets)) {
+        Ok(()) => Ok(layout),
+        Err(violation) => Err(FuzzFailure {
+            seed,
+            targets,
+            violation,
+        }),
+    }
+}
+
+/// The target set [`fuzz_layout`] would test, sorted by distance.
+pub fn fuzz_targets(seed: u64, count: usize, config: &FuzzConfig) -> Vec<Target> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut targets: Vec<Target> = Vec::with_capacity(count);
+    for id in 0..count {
+        if !targets.is_empty() && rng.gen_bool(config.identical_bias) {
+            let copy = Target {
+                id: id as i32,
+                text: format!("{}", id),
+                ..targets.choose(&mut rng).unwrap().clone()
+            };
+            targets.push(copy);
+            continue;
+        }
+        let azimuth = if !targets.is_empty() && rng.gen_bool(config.duplicate_bias) {
+            targets.choose(&mut rng).unwrap().azimuth
+        } else if rng.gen_bool(config.seam_bias) {
+            let offset = rng.gen_range(-config.seam_width, config.seam_width);
+            offset.rem_euclid(PI * 2.0)
+        } else {
+            rng.gen_range(0.0, PI * 2.0)
+        };
+        let dist = if config.max_dist > config.min_dist {
+            rng.gen_range(config.min_dist, config.max_dist)
+        } else {
+            config.min_dist
+        };
+        targets.push(Target {
+            id: id as i32,
+            text: format!("{}", id),
+            azimuth,
+            dist,
+            ..Default::default()
+        });
+    }
+    targets.sort_by(|a, b| a.dist.total_cmp(&b.dist));
+    targets
+}
@@ -0,0 +1,373 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Mean radius of the Earth in meters.
+pub const EARTH_RADIUS_M: f32 = 6_371_000.0;
+
+/// A place on a globe in degrees, north and east positive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatLon {
+    pub lat: f32,
+    pub lon: f32,
+}
+
+impl LatLon {
+    pub fn new(lat: f32, lon: f32) -> Self {
+        LatLon { lat, lon }
+    }
+
+    /// The same place with the latitude clamped to ±90° and the longitude brought into
+    /// -180° to 180° if outside; either end of that stays as it is.
+    pub fn normalized(self) -> Self {
+        let lon = if self.lon.abs() <= 180.0 {
+            self.lon
+        } else {
+            (self.lon + 180.0).rem_euclid(360.0) - 180.0
+        };
+        LatLon {
+            lat: self.lat.clamp(-90.0, 90.0),
+            lon,
+        }
+    }
+
+    /// Where the place is `radius` out from the center of a body.
+    pub fn to_cartesian(self, radius: f32) -> Cartesian3 {
+        let (sin_lat, cos_lat) = self.lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.lon.to_radians().sin_cos();
+        Cartesian3(Vec3::new(
+            -radius * cos_lat * cos_lon,
+            radius * cos_lat * sin_lon,
+            -radius * sin_lat,
+        ))
+    }
+
+    /// The texture coordinates of the place on
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh) and the other sphere meshes, which
+    /// share an equirectangular layout from the north-west corner.
+    pub fn uv(self) -> [f32; 2] {
+        [
+            ((self.lon + 180.0) / 360.0).rem_euclid(1.0),
+            ((90.0 - self.lat) / 180.0).clamp(0.0, 1.0),
+        ]
+    }
+
+    /// The angle between two places seen from the center of the body, in radians, by
+    /// the haversine formula, which stays accurate for places close together.
+    pub fn angle_to(self, other: LatLon) -> f32 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (other.lon - self.lon).to_radians();
+        let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * h.sqrt().min(1.0).asin()
+    }
+
+    /// The great-circle distance between two places on a body of `radius`.
+    pub fn distance_to(self, other: LatLon, radius: f32) -> f32 {
+        self.angle_to(other) * radius
+    }
+
+    /// The initial bearing of the great circle from here to `other`, in degrees
+    /// clockwise from north, from 0 up to 360.
+    pub fn bearing_to(self, other: LatLon) -> f32 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlon = (other.lon - self.lon).to_radians();
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Where a great circle leaving here on `bearing`, in degrees clockwise from north,
+    /// is once it has gone `angle` radians around the body.
+    pub fn destination(self, bearing: f32, angle: f32) -> LatLon {
+        let lat1 = self.lat.to_radians();
+        let bearing = bearing.to_radians();
+        let (sin_angle, cos_angle) = angle.sin_cos();
+        let sin_lat = lat1.sin() * cos_angle + lat1.cos() * sin_angle * bearing.cos();
+        let lat = sin_lat.clamp(-1.0, 1.0).asin();
+        let dlon = (bearing.sin() * sin_angle * lat1.cos()).atan2(cos_angle - lat1.sin() * sin_lat);
+        LatLon::new(lat.to_degrees(), self.lon + dlon.to_degrees()).normalized()
+    }
+}
+
+impl From<Cartesian3> for LatLon {
+    /// The place under a point, at any radius; the center of the body is at 0°, 0°.
+    fn from(point: Cartesian3) -> Self {
+        let local = point.0;
+        let radius = local.length();
+        if radius <= f32::EPSILON {
+            return LatLon::default();
+        }
+        let lat = (-local.z() / radius).clamp(-1.0, 1.0).asin();
+        let lon = local.y().atan2(-local.x());
+        LatLon::new(lat.to_degrees(), lon.to_degrees())
+    }
+}
+
+/// The compass bearing of `azimuth`, in radians counter-clockwise from east as a
+/// [`Target`](crate::target::Target) gives it, in degrees clockwise from north from 0
+/// up to 360.
+pub fn azimuth_to_bearing(azimuth: f32) -> f32 {
+    let bearing = (90.0 - azimuth.to_degrees()).rem_euclid(360.0);
+    // A hair below 0° rounds up to 360° as it wraps.
+    if bearing < 360.0 {
+        bearing
+    } else {
+        0.0
+    }
+}
+
+/// The azimuth from 0 up to 2π of `bearing`, in degrees clockwise from north; the
+/// inverse of [`azimuth_to_bearing`].
+pub fn bearing_to_azimuth(bearing: f32) -> f32 {
+    (90.0 - bearing).to_radians().rem_euclid(PI * 2.0)
+}
+
+/// A point in the frame of a body's sphere mesh, such as
+/// [`sphere_mesh`](crate::mesh::sphere_mesh): the north pole along -z, the prime
+/// meridian along -x and 90° east along +y.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Cartesian3(pub Vec3);
+
+impl Cartesian3 {
+    /// The place under the point.
+    pub fn to_lat_lon(self) -> LatLon {
+        self.into()
+    }
+}
+
+impl From<Cartesian3> for Vec3 {
+    fn from(point: Cartesian3) -> Self {
+        point.0
+    }
+}
+
+impl From<Vec3> for Cartesian3 {
+    fn from(point: Vec3) -> Self {
+        Cartesian3(point)
+    }
+}
+
+/// A point around a sensor as a [`Target`](crate::target::Target) gives it: `azimuth`
+/// in radians counter-clockwise from +x and `dist` out from the sensor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Polar {
+    pub azimuth: f32,
+    pub dist: f32,
+}
+
+impl Polar {
+    pub fn new(azimuth: f32, dist: f32) -> Self {
+        Polar { azimuth, dist }
+    }
+
+    /// The point `dist` out on `bearing`, in degrees clockwise from north.
+    pub fn from_bearing(bearing: f32, dist: f32) -> Self {
+        Polar::new(bearing_to_azimuth(bearing), dist)
+    }
+
+    /// The compass bearing of the point, in degrees clockwise from north from 0 up to
+    /// 360.
+    pub fn bearing(self) -> f32 {
+        azimuth_to_bearing(self.azimuth)
+    }
+
+    /// The polar position of `point`, its azimuth from 0 up to 2π.
+    pub fn from_cartesian(point: Vec2) -> Self {
+        Polar {
+            azimuth: point.y().atan2(point.x()).rem_euclid(PI * 2.0),
+            dist: point.length(),
+        }
+    }
+
+    pub fn to_cartesian(self) -> Vec2 {
+        let (sin, cos) = self.azimuth.sin_cos();
+        Vec2::new(cos, sin) * self.dist
+    }
+
+    /// Where the point is in the 3D display when raised `elevation` radians above the
+    /// ground plane, keeping `dist` from the origin, with y up and azimuth
+    /// counter-clockwise seen from above.
+    pub fn to_world(self, elevation: f32) -> Vec3 {
+        let ground = self.dist * elevation.cos();
+        let (sin, cos) = self.azimuth.sin_cos();
+        Vec3::new(ground * cos, self.dist * elevation.sin(), -ground * sin)
+    }
+}
+
+/// A flat map of a globe, taking places to the plane with x east and y north, both in
+/// radians of arc on the unit sphere.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Projection {
+    /// Plate carrée: longitude across and latitude up, like the globe's textures.
+    #[default]
+    Equirectangular,
+    /// Distances and bearings from `center` kept true, as on a radar scope centered on
+    /// the sensor.
+    AzimuthalEquidistant { center: LatLon },
+}
+
+impl Projection {
+    pub fn project(&self, place: LatLon) -> Vec2 {
+        match *self {
+            Projection::Equirectangular => {
+                let place = place.normalized();
+                Vec2::new(place.lon.to_radians(), place.lat.to_radians())
+            }
+            Projection::AzimuthalEquidistant { center } => {
+                let angle = center.angle_to(place);
+                let (sin, cos) = center.bearing_to(place).to_radians().sin_cos();
+                Vec2::new(sin, cos) * angle
+            }
+        }
+    }
+
+    /// The place projected to `point`, or `None` off the map.
+    pub fn unproject(&self, point: Vec2) -> Option<LatLon> {
+        match *self {
+            Projection::Equirectangular => {
+                let (lon, lat) = (point.x().to_degrees(), point.y().to_degrees());
+                if lat.abs() > 90.0 || lon.abs() > 180.0 {
+                    return None;
+                }
+                Some(LatLon::new(lat, lon))
+            }
+            Projection::AzimuthalEquidistant { center } => {
+                let angle = point.length();
+                if angle > PI {
+                    return None;
+                }
+                let bearing = point.x().atan2(point.y()).to_degrees();
+                Some(center.destination(bearing, angle))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    /// Within about a kilometer on the Earth, allowing for f32 trigonometry.
+    fn close_degrees(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-2
+    }
+
+    #[test]
+    fn bearing_is_clockwise_from_north() {
+        for &(azimuth, bearing) in &[
+            (0.0, 90.0),
+            (PI / 2.0, 0.0),
+            (PI, 270.0),
+            (PI * 1.5, 180.0),
+            (PI / 4.0, 45.0),
+            (-0.1, 90.0 + 0.1f32.to_degrees()),
+            (PI * 2.0 + PI / 2.0, 0.0),
+        ] {
+            let got = Polar::new(azimuth, 1.0).bearing();
+            assert!(
+                close(got, bearing) || close(got, bearing + 360.0),
+                "{} -> {}",
+                azimuth,
+                got
+            );
+            assert!((0.0..360.0).contains(&got));
+        }
+    }
+
+    #[test]
+    fn from_bearing_inverts_bearing() {
+        for &bearing in &[0.0, 10.0, 90.0, 179.5, 230.0, 359.0] {
+            let polar = Polar::from_bearing(bearing, 4.2);
+            assert!(
+                close(polar.bearing(), bearing),
+                "{} -> {}",
+                bearing,
+                polar.bearing()
+            );
+            assert!((0.0..PI * 2.0).contains(&polar.azimuth));
+            assert!(close(polar.dist, 4.2));
+        }
+        let north = Polar::from_bearing(0.0, 1.0).to_cartesian();
+        assert!(close(north.x(), 0.0) && close(north.y(), 1.0));
+        let east = Polar::from_bearing(90.0, 1.0).to_cartesian();
+        assert!(close(east.x(), 1.0) && close(east.y(), 0.0));
+    }
+
+    #[test]
+    fn bearing_never_reaches_360() {
+        // Just past north, where 90° less the azimuth is a hair below 0°.
+        let azimuth = f32::from_bits((PI / 2.0).to_bits() + 1);
+        assert_eq!(azimuth_to_bearing(azimuth), 0.0);
+    }
+
+    #[test]
+    fn haversine_distance() {
+        let paris = LatLon::new(48.8566, 2.3522);
+        let london = LatLon::new(51.5074, -0.1278);
+        let km = paris.distance_to(london, EARTH_RADIUS_M) / 1000.0;
+        assert!((km - 343.56).abs() < 0.5, "{}", km);
+        // A quarter of the way around the equator.
+        let quarter = LatLon::default().angle_to(LatLon::new(0.0, 90.0));
+        assert!(close(quarter, PI / 2.0));
+        assert_eq!(paris.angle_to(paris), 0.0);
+    }
+
+    #[test]
+    fn destination_round_trip() {
+        let start = LatLon::new(48.8566, 2.3522);
+        for &bearing in &[0.0, 45.0, 90.0, 135.0, 200.0, 315.0] {
+            for &angle in &[0.01, 0.3, 1.2] {
+                let end = start.destination(bearing, angle);
+                assert!(close(start.angle_to(end), angle), "{} {}", bearing, angle);
+                let back = start.bearing_to(end);
+                assert!(
+                    close_degrees(back, bearing) || close_degrees(back, bearing + 360.0),
+                    "{} -> {}",
+                    bearing,
+                    back
+                );
+            }
+        }
+        let across = LatLon::new(0.0, 170.0).destination(90.0, 20f32.to_radians());
+        assert!(close_degrees(across.lat, 0.0) && close_degrees(across.lon, -170.0));
+    }
+
+    #[test]
+    fn unproject_inverts_project() {
+        let places = [
+            LatLon::new(0.0, 0.0),
+            LatLon::new(48.8566, 2.3522),
+            LatLon::new(-33.8568, 151.2153),
+            LatLon::new(64.1, -21.9),
+            LatLon::new(-54.8, -68.3),
+        ];
+        let projections = [
+            Projection::Equirectangular,
+            Projection::AzimuthalEquidistant {
+                center: LatLon::new(51.5074, -0.1278),
+            },
+        ];
+        for projection in &projections {
+            for &place in &places {
+                let back = projection.unproject(projection.project(place)).unwrap();
+                assert!(
+                    close_degrees(back.lat, place.lat) && close_degrees(back.lon, place.lon),
+                    "{:?}: {:?} -> {:?}",
+                    projection,
+                    place,
+                    back
+                );
+            }
+        }
+        let equirectangular = Projection::Equirectangular;
+        assert_eq!(equirectangular.unproject(Vec2::new(4.0, 0.0)), None);
+        let azimuthal = projections[1];
+        assert_eq!(azimuthal.unproject(Vec2::new(3.0, 3.0)), None);
+    }
+}
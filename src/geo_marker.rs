@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+
+use crate::bodies::{geo_to_local, Body};
+use crate::instancing::MarkerInstancing;
+
+/// A pin at a geodetic position on a globe, drawn and kept in place by
+/// [`GeoMarkerPlugin`]. Spawn it as a child of the [`Body`] it belongs on, or on its
+/// own to put it on the body nearest the world origin, where the main globe sits.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GeoMarker {
+    /// Degrees.
+    pub lat: f32,
+    pub lon: f32,
+    pub label: String,
+    /// How far above the surface the pin stands, in the body's units.
+    pub height: f32,
+}
+
+impl GeoMarker {
+    pub fn new(lat: f32, lon: f32, label: impl Into<String>) -> Self {
+        GeoMarker {
+            lat,
+            lon,
+            label: label.into(),
+            height: 0.0,
+        }
+    }
+
+    /// Where the marker is in the frame of a body of `radius`.
+    pub fn local(&self, radius: f32) -> Vec3 {
+        geo_to_local(self.lat, self.lon, radius + self.height)
+    }
+}
+
+/// On a [`GeoMarker`] once [`GeoMarkerPlugin`] has given it a pin on the body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoMarkerPin {
+    pub body: Entity,
+}
+
+/// How [`GeoMarkerPlugin`] draws pins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoMarkerStyle {
+    /// Radius of the pin's ball.
+    pub size: f32,
+    pub color: Color,
+}
+
+impl Default for GeoMarkerStyle {
+    fn default() -> Self {
+        GeoMarkerStyle {
+            size: 0.03,
+            color: Color::rgb(1.0, 0.8, 0.0),
+        }
+    }
+}
+
+/// Gives each [`GeoMarker`] a pin on its [`Body`], as set by the [`GeoMarkerStyle`]
+/// resource. The pin is made a child of the body at the marker's position in its
+/// frame, so it stays on the surface as the body turns and moves, and moves when the
+/// marker's coordinates change. Each pin has a material of its own, so it can
+/// be faded on its own, e.g. by [`OcclusionPlugin`](crate::occlusion::OcclusionPlugin)
+/// with an [`Occludable`](crate::occlusion::Occludable). Markers spawned before any
+/// body wait for one. While [`MarkerInstancing`] is active, pins are left to
+/// [`InstancedMarkerPlugin`](crate::instancing::InstancedMarkerPlugin) instead.
+pub struct GeoMarkerPlugin;
+
+impl Plugin for GeoMarkerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<GeoMarkerStyle>() {
+            app.init_resource::<GeoMarkerStyle>();
+        }
+        if !app.resources().contains::<MarkerInstancing>() {
+            app.init_resource::<MarkerInstancing>();
+        }
+        app.add_system(geo_marker_pin_system.system())
+            .add_system(geo_marker_move_system.system());
+    }
+}
+
+/// The body an annotation with `parent` goes on: its parent if that is a [`Body`],
+/// otherwise the one nearest the world origin, with whether it still has to be made
+/// the body's child.
+pub(crate) fn body_for(
+    parent: Option<&Parent>,
+    bodies: &Query<(Entity, &Body, &GlobalTransform)>,
+) -> Option<(Entity, bool)> {
+    if let Some(parent) = parent.filter(|parent| bodies.get(parent.0).is_ok()) {
+        return Some((parent.0, false));
+    }
+    bodies
+        .iter()
+        .min_by(|a, b| {
+            let d = |t: &GlobalTransform| t.translation.length_squared();
+            d(a.2).partial_cmp(&d(b.2)).unwrap()
+        })
+        .map(|(entity, ..)| (entity, true))
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn geo_marker_pin_system(
+    mut commands: Commands,
+    mut mesh: Local<Option<(GeoMarkerStyle, Handle<Mesh>)>>,
+    style: Res<GeoMarkerStyle>,
+    instancing: Res<MarkerInstancing>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    markers: Query<Without<GeoMarkerPin, (Entity, &GeoMarker, Option<&Parent>)>>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    if instancing.active() {
+        return;
+    }
+    for (entity, marker, parent) in markers.iter() {
+        let (body, adopt) = match body_for(parent, &bodies) {
+            Some(body) => body,
+            None => continue,
+        };
+        let radius = bodies.get(body).map_or(0.0, |(_, body, _)| body.radius);
+        if mesh.as_ref().is_none_or(|(drawn, _)| drawn != &*style) {
+            let handle = meshes.add(Mesh::from(shape::Icosphere {
+                radius: style.size,
+                subdivisions: 2,
+            }));
+            *mesh = Some((*style, handle));
+        }
+        let handle = mesh.as_ref().map(|(_, handle)| handle.clone()).unwrap();
+        commands
+            .insert(
+                entity,
+                PbrComponents {
+                    mesh: handle,
+                    material: materials.add(style.color.into()),
+                    transform: Transform::from_translation(marker.local(radius)),
+                    draw: Draw {
+                        is_transparent: true,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .insert_one(entity, GeoMarkerPin { body });
+        if adopt {
+            commands.push_children(body, &[entity]);
+        }
+    }
+}
+
+fn geo_marker_move_system(
+    bodies: Query<&Body>,
+    mut markers: Query<(Changed<GeoMarker>, &GeoMarkerPin, Mut<Transform>)>,
+) {
+    for (marker, pin, mut transform) in markers.iter_mut() {
+        if let Ok(body) = bodies.get(pin.body) {
+            let local = marker.local(body.radius);
+            if transform.translation != local {
+                transform.translation = local;
+            }
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use lyon_tessellation::math::{point, Point};
+use lyon_tessellation::path::Path as LyonPath;
+use lyon_tessellation::{
+    BuffersBuilder, FillAttributes, FillOptions, FillTessellator, VertexBuffers,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::bodies::{geo_to_local, Body};
+use crate::geo_marker::{body_for, GeoMarker};
+use crate::layers::Layer;
+use crate::region::Gnomonic;
+
+/// Below this distance on the unit sphere, two corners of a ring count as one.
+const SAME_POINT: f32 = 1e-6;
+/// Scale of the gnomonic plane as handed to the tessellator, which works best with
+/// coordinates of screen size.
+const PLANE_SCALE: f32 = 1000.0;
+
+#[derive(Debug, Error)]
+pub enum GeoJsonError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("failed to parse {0}: {1}")]
+    Parse(String, #[source] serde_json::Error),
+    #[error("{0}: a position needs a longitude and a latitude")]
+    ShortPosition(String),
+    #[error("polygon {0:?} does not fit in one hemisphere")]
+    TooLarge(String),
+    #[error("polygon {0:?} could not be filled")]
+    Tessellation(String),
+}
+
+/// What a [`GeoFeature`] is, with positions as `[lat, lon]` in degrees, unlike GeoJSON's
+/// `[lon, lat]`. Edges run straight in latitude and longitude as GeoJSON has them,
+/// the short way round across the antimeridian.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoShape {
+    Point([f32; 2]),
+    Line(Vec<[f32; 2]>),
+    /// The outer ring, then any holes; each ring joins back to its start.
+    Polygon(Vec<Vec<[f32; 2]>>),
+}
+
+/// One shape of a [`GeoJsonLayer`], with the `name` property of the feature it came
+/// from, or `NAME` as Natural Earth has it, if any. Multi-part geometries give one per
+/// part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoFeature {
+    pub name: String,
+    pub shape: GeoShape,
+}
+
+/// The shapes of a GeoJSON document: a feature collection, a single feature or a bare
+/// geometry, of points, line strings and polygons and their multi-part and collection
+/// forms. Altitudes are ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoJsonLayer {
+    pub features: Vec<GeoFeature>,
+}
+
+#[derive(Deserialize)]
+struct RawCollection {
+    features: Vec<RawFeature>,
+}
+
+#[derive(Deserialize)]
+struct RawFeature {
+    geometry: Option<RawGeometry>,
+    #[serde(default)]
+    properties: Option<Map<String, Value>>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum RawGeometry {
+    Point {
+        coordinates: Vec<f32>,
+    },
+    MultiPoint {
+        coordinates: Vec<Vec<f32>>,
+    },
+    LineString {
+        coordinates: Vec<Vec<f32>>,
+    },
+    MultiLineString {
+        coordinates: Vec<Vec<Vec<f32>>>,
+    },
+    Polygon {
+        coordinates: Vec<Vec<Vec<f32>>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<Vec<f32>>>>,
+    },
+    GeometryCollection {
+        geometries: Vec<RawGeometry>,
+    },
+}
+
+impl GeoJsonLayer {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, GeoJsonError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let json = fs::read_to_string(path).map_err(|e| GeoJsonError::Io(name.clone(), e))?;
+        Self::from_json(&name, &json)
+    }
+
+    /// Reads the GeoJSON `json`, naming it `name` in errors.
+    pub fn from_json(name: &str, json: &str) -> Result<Self, GeoJsonError> {
+        let parse = |e| GeoJsonError::Parse(name.to_string(), e);
+        let value = serde_json::from_str::<Value>(json).map_err(parse)?;
+        let features = match value.get("type").and_then(Value::as_str) {
+            Some("FeatureCollection") => {
+                serde_json::from_value::<RawCollection>(value)
+                    .map_err(parse)?
+                    .features
+            }
+            Some("Feature") => vec![serde_json::from_value(value).map_err(parse)?],
+            _ => vec![RawFeature {
+                geometry: Some(serde_json::from_value(value).map_err(parse)?),
+                properties: None,
+            }],
+        };
+        let mut layer = GeoJsonLayer::default();
+        for feature in features {
+            let label = feature.properties.as_ref().and_then(|properties| {
+                ["name", "NAME"]
+                    .iter()
+                    .find_map(|key| properties.get(*key).and_then(Value::as_str))
+            });
+            if let Some(geometry) = &feature.geometry {
+                layer.add(name, label.unwrap_or_default(), geometry)?;
+            }
+        }
+        Ok(layer)
+    }
+
+    fn add(
+        &mut self,
+        source: &str,
+        name: &str,
+        geometry: &RawGeometry,
+    ) -> Result<(), GeoJsonError> {
+        let position = |p: &Vec<f32>| match p[..] {
+            [lon, lat, ..] => Ok([lat, lon]),
+            _ => Err(GeoJsonError::ShortPosition(source.to_string())),
+        };
+        let line = |l: &Vec<Vec<f32>>| l.iter().map(position).collect::<Result<Vec<_>, _>>();
+        let rings = |p: &Vec<Vec<Vec<f32>>>| p.iter().map(line).collect::<Result<Vec<_>, _>>();
+        let shapes = match geometry {
+            RawGeometry::Point { coordinates } => vec![GeoShape::Point(position(coordinates)?)],
+            RawGeometry::MultiPoint { coordinates } => coordinates
+                .iter()
+                .map(|p| position(p).map(GeoShape::Point))
+                .collect::<Result<_, _>>()?,
+            RawGeometry::LineString { coordinates } => vec![GeoShape::Line(line(coordinates)?)],
+            RawGeometry::MultiLineString { coordinates } => coordinates
+                .iter()
+                .map(|l| line(l).map(GeoShape::Line))
+                .collect::<Result<_, _>>()?,
+            RawGeometry::Polygon { coordinates } => vec![GeoShape::Polygon(rings(coordinates)?)],
+            RawGeometry::MultiPolygon { coordinates } => coordinates
+                .iter()
+                .map(|p| rings(p).map(GeoShape::Polygon))
+                .collect::<Result<_, _>>()?,
+            RawGeometry::GeometryCollection { geometries } => {
+                for geometry in geometries {
+                    self.add(source, name, geometry)?;
+                }
+                Vec::new()
+            }
+        };
+        self.features
+            .extend(shapes.into_iter().map(|shape| GeoFeature {
+                name: name.to_string(),
+                shape,
+            }));
+        Ok(())
+    }
+}
+
+/// The points of the unit sphere along `positions`, in the frame of
+/// [`sphere_mesh`](crate::mesh::sphere_mesh), with points added so that no step is
+/// longer than `max_step` degrees of latitude or longitude. Each step goes the short
+/// way round in longitude, so lines crossing the antimeridian, whether written as 179°
+/// to -179° or 179° to 181°, stay on the near side. Repeated points are dropped.
+pub fn densify(positions: &[[f32; 2]], max_step: f32) -> Vec<Vec3> {
+    let mut points: Vec<Vec3> = Vec::new();
+    let mut push = |p: Vec3| {
+        if points
+            .last()
+            .is_none_or(|last| (*last - p).length() > SAME_POINT)
+        {
+            points.push(p);
+        }
+    };
+    for (i, &[lat, lon]) in positions.iter().enumerate() {
+        if let Some(&[next_lat, next_lon]) = positions.get(i + 1) {
+            let d_lat = next_lat - lat;
+            let d_lon = (next_lon - lon + 180.0).rem_euclid(360.0) - 180.0;
+            let steps = (d_lat.abs().max(d_lon.abs()) / max_step.max(0.01))
+                .ceil()
+                .max(1.0);
+            for k in 0..steps as usize {
+                let t = k as f32 / steps;
+                push(geo_to_local(lat + d_lat * t, lon + d_lon * t, 1.0));
+            }
+        } else {
+            push(geo_to_local(lat, lon, 1.0));
+        }
+    }
+    points
+}
+
+impl GeoFeature {
+    /// The feature's lines, or its polygon's rings, as one mesh of line segments on a
+    /// body of `radius`, through [`densify`] at `max_step` degrees. Nothing for points.
+    pub fn line_mesh(&self, radius: f32, max_step: f32) -> Mesh {
+        let lines = match &self.shape {
+            GeoShape::Point(_) => Vec::new(),
+            GeoShape::Line(line) => vec![densify(line, max_step)],
+            GeoShape::Polygon(rings) => rings
+                .iter()
+                .map(|ring| {
+                    let mut closed = ring.clone();
+                    if ring.first() != ring.last() {
+                        closed.extend(ring.first());
+                    }
+                    densify(&closed, max_step)
+                })
+                .collect(),
+        };
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+        for points in lines {
+            let first = positions.len() as u32;
+            for p in &points {
+                positions.push(<[f32; 3]>::from(*p * radius));
+                normals.push(<[f32; 3]>::from(*p));
+            }
+            for i in 1..points.len() as u32 {
+                indices.extend([first + i - 1, first + i]);
+            }
+        }
+        let count = positions.len();
+        let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0f32, 0.0]; count].into());
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+
+    /// The fill of the feature's polygon on a body of `radius`, its holes left open:
+    /// its rings through [`densify`] at `max_step` degrees are tessellated in the
+    /// gnomonic projection about the polygon, then split until no edge spans more than
+    /// `max_step` degrees either, as for a [`GeoRegion`](crate::region::GeoRegion).
+    /// `None` for points and lines.
+    pub fn fill_mesh(&self, radius: f32, max_step: f32) -> Option<Result<Mesh, GeoJsonError>> {
+        let rings = match &self.shape {
+            GeoShape::Polygon(rings) => rings,
+            _ => return None,
+        };
+        let rings = rings
+            .iter()
+            .map(|ring| {
+                let mut points = densify(ring, max_step);
+                if points.len() > 1 && (points[0] - points[points.len() - 1]).length() <= SAME_POINT
+                {
+                    points.pop();
+                }
+                points
+            })
+            .filter(|points| points.len() >= 3)
+            .collect::<Vec<_>>();
+        let plane = match rings.first().and_then(|outer| Gnomonic::around(outer)) {
+            Some(plane) => plane,
+            None => return Some(Err(GeoJsonError::TooLarge(self.name.clone()))),
+        };
+        let mut builder = LyonPath::builder();
+        for ring in &rings {
+            let projected = ring
+                .iter()
+                .map(|&p| plane.project(p) * PLANE_SCALE)
+                .collect::<Vec<_>>();
+            builder.move_to(point(projected[0].x(), projected[0].y()));
+            for p in &projected[1..] {
+                builder.line_to(point(p.x(), p.y()));
+            }
+            builder.close();
+        }
+        let path = builder.build();
+        let mut buffers: VertexBuffers<Vec2, u32> = VertexBuffers::new();
+        let tessellated = FillTessellator::new().tessellate_path(
+            &path,
+            &FillOptions::default(),
+            &mut BuffersBuilder::new(&mut buffers, |p: Point, _: FillAttributes| {
+                Vec2::new(p.x, p.y) / PLANE_SCALE
+            }),
+        );
+        if tessellated.is_err() {
+            return Some(Err(GeoJsonError::Tessellation(self.name.clone())));
+        }
+        let projected = buffers.vertices;
+        let triangles = buffers
+            .indices
+            .chunks_exact(3)
+            .map(|t| {
+                let [a, b, c] = [t[0] as usize, t[1] as usize, t[2] as usize];
+                let (pa, pb, pc) = (projected[a], projected[b], projected[c]);
+                let (u, v) = (pb - pa, pc - pa);
+                if u.x() * v.y() - u.y() * v.x() < 0.0 {
+                    [a, c, b]
+                } else {
+                    [a, b, c]
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(Ok(plane.fill_mesh(
+            &projected,
+            &triangles,
+            radius,
+            max_step.to_radians(),
+        )))
+    }
+}
+
+/// How [`GeoJsonPlugin`] draws a [`GeoJsonOverlay`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoJsonStyle {
+    /// Lines, and the outlines of polygons.
+    pub line: Color,
+    /// Polygon fills; `None` draws polygons as outlines only.
+    pub fill: Option<Color>,
+    /// How far above the surface shapes lie, relative to the body's radius.
+    pub lift: f32,
+    /// Longest step in degrees lines and fills are drawn with.
+    pub max_step: f32,
+}
+
+impl Default for GeoJsonStyle {
+    fn default() -> Self {
+        GeoJsonStyle {
+            line: Color::rgba(0.9, 0.9, 0.8, 0.8),
+            fill: None,
+            lift: 0.003,
+            max_step: 2.0,
+        }
+    }
+}
+
+/// A [`GeoJsonLayer`] to draw over a globe with [`GeoJsonPlugin`]. Spawn it as a child
+/// of the [`Body`] it belongs on, or on its own to put it on the body nearest the world
+/// origin, like a [`GeoMarker`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoJsonOverlay {
+    pub layer: GeoJsonLayer,
+    pub style: GeoJsonStyle,
+}
+
+/// On a [`GeoJsonOverlay`] once [`GeoJsonPlugin`] has drawn it on the body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoJsonDrawn {
+    pub body: Entity,
+}
+
+/// Draws each [`GeoJsonOverlay`] on its [`Body`], turning with it: all its lines and
+/// polygon outlines as one unshaded mesh of lines, each polygon's fill if the style
+/// has one, and each point as a [`GeoMarker`] labelled with its feature's name, for
+/// [`GeoMarkerPlugin`](crate::geo_marker::GeoMarkerPlugin) to pin. Everything takes the
+/// overlay's [`Layer`] if it has one. Polygons that do not fit in a hemisphere are
+/// outlined but not filled, and reported.
+pub struct GeoJsonPlugin;
+
+impl Plugin for GeoJsonPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(geojson_draw_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn geojson_draw_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    overlays: Query<
+        Without<GeoJsonDrawn, (Entity, &GeoJsonOverlay, Option<&Parent>, Option<&Layer>)>,
+    >,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    for (entity, overlay, parent, layer) in overlays.iter() {
+        let (body, adopt) = match body_for(parent, &bodies) {
+            Some(body) => body,
+            None => continue,
+        };
+        let radius = bodies.get(body).map_or(0.0, |(_, body, _)| body.radius);
+        // The parts are children of the overlay, which needs a transform to pass on.
+        commands.insert(
+            entity,
+            (
+                GeoJsonDrawn { body },
+                Transform::default(),
+                GlobalTransform::default(),
+            ),
+        );
+        if adopt {
+            commands.push_children(body, &[entity]);
+        }
+        let style = overlay.style;
+        let lifted = radius * (1.0 + style.lift);
+        let mut unshaded = |color: Color| {
+            materials.add(StandardMaterial {
+                albedo: color,
+                shaded: false,
+                ..Default::default()
+            })
+        };
+        let line_material = unshaded(style.line);
+        let fill_material = style.fill.map(&mut unshaded);
+        let mut parts = Vec::new();
+        let mut markers = Vec::new();
+        for feature in &overlay.layer.features {
+            if let GeoShape::Point([lat, lon]) = feature.shape {
+                markers.push(GeoMarker::new(lat, lon, feature.name.clone()));
+                continue;
+            }
+            parts.push((
+                meshes.add(feature.line_mesh(lifted, style.max_step)),
+                line_material.clone(),
+            ));
+            let material = match &fill_material {
+                Some(material) => material,
+                None => continue,
+            };
+            match feature.fill_mesh(lifted, style.max_step) {
+                Some(Ok(mesh)) => parts.push((meshes.add(mesh), material.clone())),
+                Some(Err(e)) => tracing::warn!("not filling polygon: {}", e),
+                None => {}
+            }
+        }
+        for (mesh, material) in parts {
+            commands.spawn(PbrComponents {
+                mesh,
+                material,
+                draw: Draw {
+                    is_transparent: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            if let Some(layer) = layer {
+                commands.with(*layer);
+            }
+            if let Some(part) = commands.current_entity() {
+                commands.push_children(entity, &[part]);
+            }
+        }
+        for marker in markers {
+            commands.spawn((marker,));
+            if let Some(layer) = layer {
+                commands.with(*layer);
+            }
+            if let Some(marker) = commands.current_entity() {
+                commands.push_children(body, &[marker]);
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+
+/// A touch gesture recognized by [`GesturePlugin`]. Positions and movements are in
+/// window pixels with the origin bottom-left, like
+/// [`CursorPosition::screen`](crate::pointer::CursorPosition::screen).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// A single finger moved by `delta` since the last frame.
+    Drag { delta: Vec2 },
+    /// Two fingers moved apart by a factor of `scale` since the last frame, less than 1
+    /// when they moved closer, around their midpoint `center`.
+    Pinch { center: Vec2, scale: f32 },
+    /// A finger touched and lifted again without moving, away from other fingers.
+    Tap { position: Vec2 },
+    /// A finger tapped twice in about the same place.
+    DoubleTap { position: Vec2 },
+}
+
+/// What [`GesturePlugin`] takes for a tap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureSettings {
+    /// Furthest in pixels a finger may move between touching and lifting for a tap,
+    /// and the two taps of a double tap may lie apart.
+    pub tap_slop: f32,
+    /// Longest in seconds between the two taps of a double tap.
+    pub double_tap_secs: f32,
+}
+
+impl Default for GestureSettings {
+    fn default() -> Self {
+        GestureSettings {
+            tap_slop: 12.0,
+            double_tap_secs: 0.3,
+        }
+    }
+}
+
+/// Turns the touches on the window into [`Gesture`] events each frame, before the
+/// update stage, for camera controls to read alongside the mouse:
+/// [`CameraControlPlugin`](crate::camera::CameraControlPlugin) pans and zooms the 2D
+/// view with them, and [`OrbitCameraPlugin`](crate::camera::OrbitCameraPlugin) turns,
+/// zooms and resets the 3D one. Taps come before the double tap they make up. With more than two fingers down, only the first two
+/// count.
+pub struct GesturePlugin;
+
+impl Plugin for GesturePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_gestures(app);
+    }
+}
+
+/// Adds the [`Gesture`] events and the system recognizing them, once however many
+/// plugins ask for them.
+pub(crate) fn add_gestures(app: &mut AppBuilder) {
+    if app.resources().contains::<Events<Gesture>>() {
+        return;
+    }
+    if !app.resources().contains::<GestureSettings>() {
+        app.init_resource::<GestureSettings>();
+    }
+    app.add_event::<Gesture>()
+        .add_system_to_stage(stage::PRE_UPDATE, gesture_system.system());
+}
+
+#[derive(Default)]
+struct GestureState {
+    /// Where each finger down was last frame, flipped to the origin bottom-left.
+    fingers: HashMap<u64, Vec2>,
+    /// Where each finger down first touched.
+    starts: HashMap<u64, Vec2>,
+    /// Fingers that were down together with another, which no longer make a tap.
+    multi: Vec<u64>,
+    /// When and where the last tap was, to pair it with the next.
+    last_tap: Option<(f64, Vec2)>,
+}
+
+fn gesture_system(
+    mut state: Local<GestureState>,
+    time: Res<Time>,
+    settings: Res<GestureSettings>,
+    windows: Res<Windows>,
+    touches: Res<Touches>,
+    mut gestures: ResMut<Events<Gesture>>,
+) {
+    let height = windows
+        .get_primary()
+        .map_or(0.0, |window| window.height() as f32);
+    let flip = |p: Vec2| Vec2::new(p.x(), height - p.y());
+
+    let mut down = touches
+        .iter()
+        .map(|touch| (touch.id, flip(touch.position)))
+        .collect::<Vec<_>>();
+    down.sort_by_key(|&(id, _)| id);
+    if down.len() > 1 {
+        for &(id, _) in &down {
+            if !state.multi.contains(&id) {
+                state.multi.push(id);
+            }
+        }
+    }
+    let last = |id| state.fingers.get(&id).copied();
+    match down.as_slice() {
+        [(id, now)] => {
+            if let Some(then) = last(*id) {
+                if *now != then {
+                    gestures.send(Gesture::Drag { delta: *now - then });
+                }
+            }
+        }
+        [(a, a_now), (b, b_now), ..] => {
+            if let (Some(a_then), Some(b_then)) = (last(*a), last(*b)) {
+                let (then, now) = ((a_then - b_then).length(), (*a_now - *b_now).length());
+                if then > 0.0 && now != then {
+                    gestures.send(Gesture::Pinch {
+                        center: (*a_now + *b_now) / 2.0,
+                        scale: now / then,
+                    });
+                }
+            }
+        }
+        [] => {}
+    }
+
+    for &(id, at) in &down {
+        state.starts.entry(id).or_insert(at);
+    }
+    // Touches only keeps where a lifted finger ended up, so the fingers gone since last
+    // frame are looked up here instead.
+    let lifted = state
+        .fingers
+        .iter()
+        .filter(|(id, _)| !down.iter().any(|(down, _)| down == *id))
+        .map(|(&id, &at)| (id, at))
+        .collect::<Vec<_>>();
+    for (id, last_at) in lifted {
+        let was_multi = state.multi.contains(&id);
+        state.multi.retain(|&multi| multi != id);
+        let start = state.starts.remove(&id).unwrap_or(last_at);
+        let at = touches
+            .get_released(id)
+            .map_or(last_at, |touch| flip(touch.position));
+        if was_multi || !touches.just_released(id) || (at - start).length() > settings.tap_slop {
+            continue;
+        }
+        gestures.send(Gesture::Tap { position: at });
+        let now = time.seconds_since_startup;
+        match state.last_tap {
+            Some((then, first))
+                if now - then <= settings.double_tap_secs as f64
+                    && (at - first).length() <= settings.tap_slop =>
+            {
+                gestures.send(Gesture::DoubleTap { position: at });
+                state.last_tap = None;
+            }
+            _ => state.last_tap = Some((now, at)),
+        }
+    }
+    state.fingers = down.into_iter().collect();
+}
@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+
+use crate::bodies::Body;
+use crate::camera::CameraCommands;
+use crate::geo::{LatLon, Polar, EARTH_RADIUS_M};
+use crate::geo_marker::{body_for, GeoMarker, GeoMarkerPin, GeoMarkerStyle};
+use crate::globe_pick::GlobeClicked;
+use crate::instancing::{InstanceColor, InstancedPin};
+use crate::occlusion::Occludable;
+use crate::pointer::CursorPosition;
+use crate::selection::{SelectTarget, Selected, TargetSelected};
+use crate::target::{GeoPoint, Target};
+use crate::viewport::DisplayViewport;
+
+/// Seconds the orbit cameras take to fly over a selected target.
+const FLIGHT_SECONDS: f32 = 1.2;
+
+/// How [`GlobeLinkPlugin`] ties the ring display to a globe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobeLink {
+    /// Where the sensor at the center of the rings stands.
+    pub observer: LatLon,
+    /// Meters over the ground per unit of [`Target::dist`].
+    pub meters_per_unit: f32,
+    /// Degrees of arc around a click on the globe within which the nearest linked pin
+    /// is selected.
+    pub pick_angle: f32,
+    /// Color of the selected target's pin.
+    pub highlight: Color,
+    /// Flies the orbit cameras over the selected target, this far from the body's
+    /// center; `None` leaves them where they are.
+    pub fly_to: Option<f32>,
+}
+
+impl Default for GlobeLink {
+    fn default() -> Self {
+        GlobeLink {
+            observer: LatLon::default(),
+            meters_per_unit: 1000.0,
+            pick_angle: 2.0,
+            highlight: Color::rgb(0.2, 1.0, 1.0),
+            fly_to: None,
+        }
+    }
+}
+
+impl GlobeLink {
+    /// Where `place` is in the ring display: its bearing from the observer turned into
+    /// an azimuth counter-clockwise from east, and its distance over the ground in
+    /// units of [`GlobeLink::meters_per_unit`].
+    pub fn polar(&self, place: LatLon) -> Polar {
+        Polar::from_bearing(
+            self.observer.bearing_to(place),
+            self.observer.distance_to(place, EARTH_RADIUS_M) / self.meters_per_unit,
+        )
+    }
+
+    /// The place at `polar` in the ring display, the inverse of [`GlobeLink::polar`].
+    pub fn place(&self, polar: Polar) -> LatLon {
+        self.observer.destination(
+            polar.bearing(),
+            polar.dist * self.meters_per_unit / EARTH_RADIUS_M,
+        )
+    }
+}
+
+/// On an [`OrbitCamera`](crate::camera::OrbitCamera), such as that of an inset
+/// [`ViewRect`](crate::split_view::ViewRect), to fly it over each target selected,
+/// `distance` from the body's center, on its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FollowSelection {
+    pub distance: f32,
+}
+
+/// On the [`GeoMarker`] [`GlobeLinkPlugin`] spawned for the target with this id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkedMarker(pub i32);
+
+/// Shows each [`Target`] that has a [`GeoPoint`] in both the ring display and on a
+/// globe, as set by the [`GlobeLink`] resource. The target's azimuth and distance
+/// follow its latitude and longitude as seen from [`GlobeLink::observer`], and a
+/// [`GeoMarker`] with a [`LinkedMarker`] stands at it on the body nearest the world
+/// origin, moving with it and going with it. The selection is shared: the selected
+/// target's pin is highlighted, and a click on the globe near a pin selects its target
+/// through [`SelectTarget`], unless the click lands in a [`DisplayViewport`]. Cameras
+/// with [`FollowSelection`] fly over each target selected. Add after
+/// [`SelectionPlugin`](crate::selection::SelectionPlugin) and
+/// [`GlobePickPlugin`](crate::globe_pick::GlobePickPlugin), with
+/// [`GeoMarkerPlugin`](crate::geo_marker::GeoMarkerPlugin) to draw the pins.
+pub struct GlobeLinkPlugin;
+
+impl Plugin for GlobeLinkPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<GlobeLink>() {
+            app.init_resource::<GlobeLink>();
+        }
+        if !app.resources().contains::<GeoMarkerStyle>() {
+            app.init_resource::<GeoMarkerStyle>();
+        }
+        if !app.resources().contains::<CameraCommands>() {
+            app.init_resource::<CameraCommands>();
+        }
+        app.add_system(link_position_system.system())
+            .add_system(link_marker_system.system())
+            .add_system(link_highlight_system.system())
+            .add_system(link_select_system.system());
+    }
+}
+
+fn link_position_system(
+    link: Res<GlobeLink>,
+    mut last: Local<Option<GlobeLink>>,
+    changed: Query<With<Target, (Entity, Changed<GeoPoint>)>>,
+    mut targets: Query<(Entity, Mut<Target>, &GeoPoint)>,
+) {
+    let everything = *last != Some(*link);
+    *last = Some(*link);
+    let changed = changed.iter().map(|(entity, _)| entity).collect::<Vec<_>>();
+    for (entity, mut target, geo) in targets.iter_mut() {
+        if !everything && !changed.contains(&entity) {
+            continue;
+        }
+        let polar = link.polar(LatLon::new(geo.lat, geo.lon));
+        if target.azimuth != polar.azimuth || target.dist != polar.dist {
+            target.azimuth = polar.azimuth;
+            target.dist = polar.dist;
+        }
+    }
+}
+
+fn link_marker_system(
+    mut commands: Commands,
+    targets: Query<(&Target, &GeoPoint)>,
+    mut markers: Query<(Entity, &LinkedMarker, Mut<GeoMarker>)>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    let radius = match body_for(None, &bodies).and_then(|(body, _)| bodies.get(body).ok()) {
+        Some((_, body, _)) => body.radius,
+        None => return,
+    };
+    let wanted = |target: &Target, geo: &GeoPoint| GeoMarker {
+        height: geo.alt.max(0.0) / EARTH_RADIUS_M * radius,
+        ..GeoMarker::new(geo.lat, geo.lon, target.text.clone())
+    };
+    let mut linked = Vec::new();
+    for (entity, id, mut marker) in markers.iter_mut() {
+        match targets.iter().find(|(target, _)| target.id == id.0) {
+            Some((target, geo)) => {
+                let wanted = wanted(target, geo);
+                if *marker != wanted {
+                    *marker = wanted;
+                }
+                linked.push(id.0);
+            }
+            None => {
+                commands.despawn_recursive(entity);
+            }
+        }
+    }
+    for (target, geo) in targets.iter() {
+        if !linked.contains(&target.id) {
+            commands.spawn((wanted(target, geo), LinkedMarker(target.id), Occludable));
+        }
+    }
+}
+
+// Pins get their material a frame after they are spawned, so this keeps checking
+// rather than only reacting to selection changes. Instanced pins are colored through
+// their InstanceColor instead.
+#[allow(clippy::type_complexity)]
+fn link_highlight_system(
+    mut commands: Commands,
+    link: Res<GlobeLink>,
+    style: Res<GeoMarkerStyle>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    selected: Query<With<Selected, &Target>>,
+    markers: Query<(&LinkedMarker, &Handle<StandardMaterial>)>,
+    instanced: Query<With<InstancedPin, (Entity, &LinkedMarker, Option<&InstanceColor>)>>,
+) {
+    let selected = selected.iter().next().map(|t| t.id);
+    for (entity, id, color) in instanced.iter() {
+        let wanted = Some(id.0) == selected;
+        if wanted && color.map(|c| c.0) != Some(link.highlight) {
+            commands.insert_one(entity, InstanceColor(link.highlight));
+        } else if !wanted && color.is_some() {
+            commands.remove_one::<InstanceColor>(entity);
+        }
+    }
+    for (id, handle) in markers.iter() {
+        let color = if Some(id.0) == selected {
+            link.highlight
+        } else {
+            style.color
+        };
+        let stale = materials.get(handle).is_some_and(|m| {
+            (m.albedo.r(), m.albedo.g(), m.albedo.b()) != (color.r(), color.g(), color.b())
+        });
+        if stale {
+            if let Some(material) = materials.get_mut(handle) {
+                // The alpha is left to occlusion fading.
+                material.albedo = Color::rgba(color.r(), color.g(), color.b(), material.albedo.a());
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct LinkSelectState {
+    selected: EventReader<TargetSelected>,
+    clicks: EventReader<GlobeClicked>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn link_select_system(
+    mut state: Local<LinkSelectState>,
+    link: Res<GlobeLink>,
+    cursor: Res<CursorPosition>,
+    selected: Res<Events<TargetSelected>>,
+    clicks: Res<Events<GlobeClicked>>,
+    mut requests: ResMut<Events<SelectTarget>>,
+    mut camera: ResMut<CameraCommands>,
+    markers: Query<(&LinkedMarker, &GeoMarker, &GeoMarkerPin)>,
+    viewports: Query<&DisplayViewport>,
+    followers: Query<(Entity, &FollowSelection)>,
+) {
+    let id = state
+        .selected
+        .iter(&selected)
+        .next_back()
+        .and_then(|event| event.target);
+    if let Some((_, marker, _)) = markers.iter().find(|(linked, ..)| Some(linked.0) == id) {
+        if let Some(distance) = link.fly_to {
+            camera.fly_to(marker.lat, marker.lon, distance, FLIGHT_SECONDS);
+        }
+        for (entity, follow) in followers.iter() {
+            camera.fly_camera_to(
+                entity,
+                marker.lat,
+                marker.lon,
+                follow.distance,
+                FLIGHT_SECONDS,
+            );
+        }
+    }
+    let over_display = cursor
+        .screen
+        .is_some_and(|screen| viewports.iter().any(|viewport| viewport.contains(screen)));
+    for click in state.clicks.iter(&clicks) {
+        if over_display {
+            continue;
+        }
+        let at = LatLon::new(click.lat, click.lon);
+        let nearest = markers
+            .iter()
+            .filter(|(_, _, pin)| pin.body == click.body)
+            .map(|(id, marker, _)| (id.0, at.angle_to(LatLon::new(marker.lat, marker.lon))))
+            .filter(|&(_, angle)| angle <= link.pick_angle.to_radians())
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        if let Some((id, _)) = nearest {
+            requests.send(SelectTarget(Some(id)));
+        }
+    }
+}
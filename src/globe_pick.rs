@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::bodies::{local_to_geo, Body};
+use crate::flat_map::FlatMap;
+use crate::pointer::CursorPosition;
+use crate::probe::{ray_sphere, screen_ray};
+use crate::split_view::{view_under_cursor, ViewRect};
+use crate::terrain::Relief;
+
+/// Furthest in pixels the cursor may move between pressing and releasing the button
+/// for a click rather than a drag of the camera.
+const CLICK_PIXELS: f32 = 12.0;
+
+/// An [`Action::Select`] click on a globe, sent by [`GlobePickPlugin`] with where on its surface it
+/// landed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobeClicked {
+    /// Degrees, in the body's frame as [`GeoMarker`](crate::geo_marker::GeoMarker)s
+    /// are placed, so a marker spawned there sits under the cursor.
+    pub lat: f32,
+    pub lon: f32,
+    /// The [`Body`] clicked.
+    pub body: Entity,
+}
+
+/// Sends a [`GlobeClicked`] when the cursor is clicked over a [`Body`] as seen from the
+/// 3D camera: the ray through the cursor is met with the body's sphere, the nearest hit
+/// taken if it passes several, and the point turned into latitude and longitude in the
+/// body's frame, so however it has turned. A body flattened by a [`FlatMap`] is met on
+/// its map instead, and not at all while morphing. A body with a [`Relief`] is met on
+/// the triangles of its raised mesh, so the click lands on the terrain seen under the
+/// cursor rather than on the sphere beneath it. Over a [`ViewRect`], the ray is that
+/// view's camera's. Presses that move the cursor further than a
+/// few pixels before the release drag the camera and are not clicks, nor are clicks on
+/// bevy_ui nodes that take [`Interaction`]. Needs
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin) for the cursor.
+pub struct GlobePickPlugin;
+
+impl Plugin for GlobePickPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        app.add_event::<GlobeClicked>()
+            .add_system(globe_pick_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn globe_pick_system(
+    mut pressed_at: Local<Option<Vec2>>,
+    actions: Res<ActionState>,
+    cursor: Res<CursorPosition>,
+    windows: Res<Windows>,
+    mut clicks: ResMut<Events<GlobeClicked>>,
+    cameras: Query<(&Camera, &GlobalTransform, Option<&ViewRect>)>,
+    bodies: Query<(
+        Entity,
+        &Body,
+        &GlobalTransform,
+        Option<&FlatMap>,
+        Option<&Relief>,
+    )>,
+    ui: Query<&Interaction>,
+) {
+    if actions.just_pressed(Action::Select) {
+        let on_ui = ui.iter().any(|i| *i != Interaction::None);
+        *pressed_at = cursor.screen.filter(|_| !on_ui);
+    }
+    if !actions.just_released(Action::Select) {
+        return;
+    }
+    let (from, at) = match (pressed_at.take(), cursor.screen) {
+        (Some(from), Some(at)) => (from, at),
+        _ => return,
+    };
+    if (at - from).length() > CLICK_PIXELS {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let main = cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA3D))
+        .map(|(camera, eye, _)| (camera, eye));
+    let views = cameras
+        .iter()
+        .filter(|(.., rect)| rect.is_some())
+        .map(|(camera, eye, rect)| ((camera, eye), rect.copied()));
+    let ((camera, eye), at, size) = match view_under_cursor(at, size, views, main) {
+        Some(view) => view,
+        None => return,
+    };
+    let (origin, dir) = screen_ray(camera, eye, at, size);
+    let hit = bodies
+        .iter()
+        .filter_map(|(entity, body, transform, map, relief)| {
+            let matrix = transform.compute_matrix();
+            let (hit, lat, lon) = match (map.filter(|map| map.progress() > 0.0), relief) {
+                (Some(map), _) if map.is_flat() => {
+                    let to_local = matrix.inverse();
+                    let (local, place) = map.pick(
+                        to_local.transform_point3(origin),
+                        to_local.transform_vector3(dir),
+                        body.radius,
+                    )?;
+                    (matrix.transform_point3(local), place.lat, place.lon)
+                }
+                (Some(_), _) => return None,
+                (None, Some(relief)) => {
+                    let to_local = matrix.inverse();
+                    let local = relief.ray_hit(
+                        to_local.transform_point3(origin),
+                        to_local.transform_vector3(dir),
+                    )?;
+                    let (lat, lon) = local_to_geo(local);
+                    (matrix.transform_point3(local), lat, lon)
+                }
+                (None, None) => {
+                    let radius = body.radius * transform.scale.x();
+                    let hit = ray_sphere(origin, dir, transform.translation, radius)?;
+                    let (lat, lon) = local_to_geo(matrix.inverse().transform_point3(hit));
+                    (hit, lat, lon)
+                }
+            };
+            Some((
+                (hit - origin).length(),
+                GlobeClicked {
+                    lat,
+                    lon,
+                    body: entity,
+                },
+            ))
+        })
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    if let Some((_, click)) = hit {
+        clicks.send(click);
+    }
+}
@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+
+use crate::atmosphere::AtmospherePlugin;
+use crate::day_night::DayNightPlugin;
+use crate::normal_map::NormalMapPlugin;
+
+/// The globe's own materials: [`DayNightPlugin`] shading by the sun,
+/// [`NormalMapPlugin`] lighting surface detail and [`AtmospherePlugin`] glowing around
+/// the edge. Add after the render plugins.
+pub struct GlobeRenderPlugin;
+
+impl Plugin for GlobeRenderPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(DayNightPlugin)
+            .add_plugin(NormalMapPlugin)
+            .add_plugin(AtmospherePlugin);
+    }
+}
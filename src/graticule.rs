@@ -0,0 +1,381 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+
+use crate::bodies::{geo_to_local, Body};
+use crate::geo_marker::body_for;
+use crate::layers::Layer;
+
+/// How far degree labels sit from where their line meets the globe's edge, in pixels.
+const LABEL_GAP: f32 = 4.0;
+
+/// Lines of latitude and longitude over a globe, drawn by [`GraticulePlugin`]. Spawn it
+/// as a child of the [`Body`] it belongs on, or on its own to put it on the body
+/// nearest the world origin, like a [`GeoMarker`](crate::geo_marker::GeoMarker).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Graticule {
+    /// Degrees between neighboring lines, of latitude and of longitude alike. The
+    /// equator and the prime meridian are always among them.
+    pub spacing: f32,
+    /// Degrees between the points each line is drawn through.
+    pub resolution: f32,
+    /// How far above the surface the lines lie, relative to the body's radius.
+    pub lift: f32,
+    pub color: Color,
+    pub equator_color: Color,
+    pub prime_meridian_color: Color,
+    /// Whether each line is labelled with its degrees where it meets the globe's edge
+    /// on screen: parallels on the left, meridians at the bottom.
+    pub labels: bool,
+    pub font_size: f32,
+}
+
+impl Default for Graticule {
+    fn default() -> Self {
+        Graticule {
+            spacing: 15.0,
+            resolution: 2.0,
+            lift: 0.002,
+            color: Color::rgba(0.6, 0.75, 0.9, 0.35),
+            equator_color: Color::rgba(1.0, 0.85, 0.4, 0.8),
+            prime_meridian_color: Color::rgba(1.0, 0.5, 0.4, 0.8),
+            labels: false,
+            font_size: 12.0,
+        }
+    }
+}
+
+/// One line of a [`Graticule`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraticuleLine {
+    /// The circle of latitude at these degrees.
+    Parallel(f32),
+    /// The half circle of longitude at these degrees, pole to pole.
+    Meridian(f32),
+}
+
+impl GraticuleLine {
+    /// The line's degrees as labelled, such as `30°N` or `45°W`.
+    pub fn label(self) -> String {
+        let (degrees, positive, negative) = match self {
+            GraticuleLine::Parallel(lat) => (lat, 'N', 'S'),
+            GraticuleLine::Meridian(lon) => (lon, 'E', 'W'),
+        };
+        let hemisphere = if degrees.abs() < 1e-3 || (degrees.abs() - 180.0).abs() < 1e-3 {
+            String::new()
+        } else if degrees > 0.0 {
+            positive.to_string()
+        } else {
+            negative.to_string()
+        };
+        format!("{}°{}", degrees.abs().round(), hemisphere)
+    }
+
+    /// The line's points on the unit sphere, in the frame of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh), `resolution` degrees apart or a
+    /// little closer; a parallel ends back at its start.
+    pub fn points(self, resolution: f32) -> Vec<Vec3> {
+        let span = match self {
+            GraticuleLine::Parallel(_) => 360.0,
+            GraticuleLine::Meridian(_) => 180.0,
+        };
+        let steps = (span / resolution.max(0.01)).ceil().max(1.0) as usize;
+        (0..=steps)
+            .map(|i| {
+                let along = span * i as f32 / steps as f32;
+                match self {
+                    GraticuleLine::Parallel(lat) => geo_to_local(lat, along - 180.0, 1.0),
+                    GraticuleLine::Meridian(lon) => geo_to_local(along - 90.0, lon, 1.0),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Graticule {
+    /// Every line but the poles themselves, parallels first, each with its
+    /// [`GraticuleLine::label`].
+    pub fn lines(&self) -> Vec<GraticuleLine> {
+        let spacing = self.spacing.max(0.1);
+        let count = |limit: f32| (limit / spacing).floor() as i32;
+        let lats = (-count(89.999)..=count(89.999)).map(|k| k as f32 * spacing);
+        let lons = (-count(180.0)..=count(179.999)).map(|k| k as f32 * spacing);
+        lats.map(GraticuleLine::Parallel)
+            .chain(lons.map(GraticuleLine::Meridian))
+            .collect()
+    }
+
+    /// The `lines` on a body of `radius`, lifted by [`Graticule::lift`], as one mesh of
+    /// line segments in the frame of [`sphere_mesh`](crate::mesh::sphere_mesh).
+    pub fn mesh(&self, lines: &[GraticuleLine], radius: f32) -> Mesh {
+        let lifted = radius * (1.0 + self.lift);
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+        for line in lines {
+            let first = positions.len() as u32;
+            let points = line.points(self.resolution);
+            for p in &points {
+                positions.push(<[f32; 3]>::from(*p * lifted));
+                normals.push(<[f32; 3]>::from(*p));
+            }
+            for i in 1..points.len() as u32 {
+                indices.extend([first + i - 1, first + i]);
+            }
+        }
+        let count = positions.len();
+        let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0f32, 0.0]; count].into());
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+}
+
+/// Where the sphere's edge as seen from `eye`, both in the sphere's frame, crosses
+/// `line`: up to two points on the unit sphere. The edge is the circle of points whose
+/// normal is at `radius / distance` to the direction of the eye.
+fn edge_crossings(line: GraticuleLine, eye: Vec3, radius: f32) -> Vec<Vec3> {
+    let distance = eye.length();
+    if distance <= radius {
+        return Vec::new();
+    }
+    let (v, c) = (eye / distance, radius / distance);
+    // Along the line the normal is `a cos(t) + b sin(t) + k`; solve `normal · v = c`.
+    let (a, b, k, valid): (Vec3, Vec3, Vec3, fn(f32) -> bool) = match line {
+        GraticuleLine::Parallel(lat) => {
+            let lat = lat.to_radians();
+            (
+                Vec3::new(-lat.cos(), 0.0, 0.0),
+                Vec3::new(0.0, lat.cos(), 0.0),
+                Vec3::new(0.0, 0.0, -lat.sin()),
+                |_| true,
+            )
+        }
+        GraticuleLine::Meridian(lon) => {
+            let lon = lon.to_radians();
+            (
+                Vec3::new(-lon.cos(), lon.sin(), 0.0),
+                -Vec3::unit_z(),
+                Vec3::zero(),
+                |t: f32| t.cos() >= 0.0,
+            )
+        }
+    };
+    let (p, q, r) = (a.dot(v), b.dot(v), c - k.dot(v));
+    let amplitude = (p * p + q * q).sqrt();
+    if amplitude <= f32::EPSILON || r.abs() > amplitude {
+        return Vec::new();
+    }
+    let (phase, spread) = (q.atan2(p), (r / amplitude).acos());
+    IntoIterator::into_iter([phase + spread, phase - spread])
+        .filter(|&t| valid(t))
+        .map(|t| a * t.cos() + b * t.sin() + k)
+        .collect()
+}
+
+/// On a [`Graticule`] once [`GraticulePlugin`] has drawn it on the body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraticuleDrawn {
+    pub body: Entity,
+}
+
+/// On a degree label of the `graticule` entity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraticuleLabel {
+    pub graticule: Entity,
+    pub line: GraticuleLine,
+}
+
+/// Font asset path of graticule labels, set by [`GraticulePlugin`].
+#[derive(Debug, Clone, Copy)]
+pub struct GraticuleFont(pub &'static str);
+
+/// Draws each [`Graticule`] as unshaded lines hugging its [`Body`], a child of the body
+/// so it turns with it: the equator and the prime meridian on children of their own in
+/// their own colors, which take the graticule's [`Layer`] if it has one. With
+/// [`Graticule::labels`], each line's degrees are written next to the globe's edge on
+/// screen where the line goes over it, as seen from the 3D camera, and follow the edge
+/// as the camera and globe turn.
+pub struct GraticulePlugin {
+    /// Asset path of the label font.
+    pub font: &'static str,
+}
+
+impl Default for GraticulePlugin {
+    fn default() -> Self {
+        GraticulePlugin { font: "arial.ttf" }
+    }
+}
+
+impl Plugin for GraticulePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(GraticuleFont(self.font))
+            .add_system(graticule_draw_system.system())
+            .add_system(graticule_label_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn graticule_draw_system(
+    mut commands: Commands,
+    font: Res<GraticuleFont>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    graticules: Query<
+        Without<GraticuleDrawn, (Entity, &Graticule, Option<&Parent>, Option<&Layer>)>,
+    >,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    for (entity, graticule, parent, layer) in graticules.iter() {
+        let (body, adopt) = match body_for(parent, &bodies) {
+            Some(body) => body,
+            None => continue,
+        };
+        let radius = bodies.get(body).map_or(0.0, |(_, body, _)| body.radius);
+        commands.insert_one(entity, GraticuleDrawn { body });
+        if adopt {
+            commands.push_children(body, &[entity]);
+        }
+        let lines = graticule.lines();
+        let mut lines_in = |color: Color, lines: &[GraticuleLine]| PbrComponents {
+            mesh: meshes.add(graticule.mesh(lines, radius)),
+            material: materials.add(StandardMaterial {
+                albedo: color,
+                shaded: false,
+                ..Default::default()
+            }),
+            draw: Draw {
+                is_transparent: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (equator, prime) = (GraticuleLine::Parallel(0.0), GraticuleLine::Meridian(0.0));
+        let rest = lines
+            .iter()
+            .copied()
+            .filter(|&line| line != equator && line != prime)
+            .collect::<Vec<_>>();
+        commands.insert(entity, lines_in(graticule.color, &rest));
+        for (color, line) in [
+            (graticule.equator_color, equator),
+            (graticule.prime_meridian_color, prime),
+        ] {
+            let part = lines_in(color, &[line]);
+            commands.spawn(part);
+            if let Some(layer) = layer {
+                commands.with(*layer);
+            }
+            if let Some(part) = commands.current_entity() {
+                commands.push_children(entity, &[part]);
+            }
+        }
+        if !graticule.labels {
+            continue;
+        }
+        for line in lines {
+            commands
+                .spawn(TextComponents {
+                    text: Text {
+                        value: line.label(),
+                        font: asset_server.load(font.0),
+                        style: TextStyle {
+                            font_size: graticule.font_size,
+                            color: Color::rgb(
+                                graticule.color.r(),
+                                graticule.color.g(),
+                                graticule.color.b(),
+                            ),
+                        },
+                    },
+                    draw: Draw {
+                        is_visible: false,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(GraticuleLabel {
+                    graticule: entity,
+                    line,
+                });
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn graticule_label_system(
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    bodies: Query<&Body>,
+    graticules: Query<(&Graticule, &GraticuleDrawn, &GlobalTransform, &Draw)>,
+    mut labels: Query<(&GraticuleLabel, &Text, Mut<Style>, Mut<Draw>)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let (camera, eye) = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some(camera) => camera,
+        None => return,
+    };
+    let view_projection = camera.projection_matrix * eye.compute_matrix().inverse();
+    let to_screen = |world: Vec3| {
+        let clip = view_projection * world.extend(1.0);
+        (clip.w() > 0.0)
+            .then(|| (Vec2::new(clip.x(), clip.y()) / clip.w() + Vec2::one()) / 2.0 * size)
+    };
+    for (label, text, mut style, mut draw) in labels.iter_mut() {
+        let found = graticules
+            .get(label.graticule)
+            .ok()
+            .filter(|(.., draw)| draw.is_visible)
+            .and_then(|(graticule, drawn, transform, _)| {
+                let radius = bodies.get(drawn.body).ok()?.radius * (1.0 + graticule.lift);
+                let frame = transform.compute_matrix();
+                let local_eye = frame.inverse().transform_point3(eye.translation);
+                let screen = edge_crossings(label.line, local_eye, radius)
+                    .into_iter()
+                    .filter_map(|p| to_screen(frame.transform_point3(p * radius)))
+                    .min_by(|a, b| match label.line {
+                        GraticuleLine::Parallel(_) => a.x().partial_cmp(&b.x()).unwrap(),
+                        GraticuleLine::Meridian(_) => a.y().partial_cmp(&b.y()).unwrap(),
+                    })?;
+                let extent = Vec2::new(
+                    text.value.chars().count() as f32 * text.style.font_size * 0.6,
+                    text.style.font_size,
+                );
+                Some(match label.line {
+                    GraticuleLine::Parallel(_) => {
+                        screen - Vec2::new(extent.x() + LABEL_GAP, extent.y() / 2.0)
+                    }
+                    GraticuleLine::Meridian(_) => {
+                        screen - Vec2::new(extent.x() / 2.0, extent.y() + LABEL_GAP)
+                    }
+                })
+            });
+        let shown = found.is_some();
+        if draw.is_visible != shown {
+            draw.is_visible = shown;
+        }
+        if let Some(at) = found {
+            let position = Rect {
+                left: Val::Px(at.x()),
+                bottom: Val::Px(at.y()),
+                ..Default::default()
+            };
+            if style.position_type != PositionType::Absolute || style.position != position {
+                style.position_type = PositionType::Absolute;
+                style.position = position;
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::texture::TextureFormat;
+
+use crate::bodies::Body;
+use crate::geo_marker::body_for;
+use crate::mesh::sphere_mesh;
+use crate::palette::{ColorRamp, Ramp};
+
+/// Rows of the heatmap's sphere, which has four times as many columns.
+const SHELL_ROWS: u32 = 48;
+/// Standard deviations out from a point past which its heat is left out.
+const CUTOFF: f32 = 3.0;
+
+/// One point of a [`Heatmap`], in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeatPoint {
+    pub lat: f32,
+    pub lon: f32,
+    pub weight: f32,
+}
+
+/// How densely weighted points lie over a [`Body`], smoothed and colored by `ramp`
+/// into a texture on a shell just above the surface, drawn by [`HeatmapPlugin`]. Spawn
+/// it as a child of the body it belongs on, or on its own to put it on the body
+/// nearest the world origin, like a [`Graticule`](crate::graticule::Graticule). Points
+/// added or changed later are baked in again, no more often than every `refresh`
+/// seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap {
+    pub points: Vec<HeatPoint>,
+    /// Degrees of arc each point's heat spreads over, as the standard deviation of a
+    /// bell curve around it.
+    pub spread: f32,
+    /// Density at the top of the ramp, in weight at a point's center; `None` puts the
+    /// densest spot there.
+    pub max: Option<f32>,
+    pub ramp: ColorRamp,
+    /// Width of the texture in texels; it is half as tall.
+    pub width: u32,
+    /// How far above the surface the shell lies, relative to the body's radius.
+    pub lift: f32,
+    pub refresh: f32,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Heatmap {
+            points: Vec::new(),
+            spread: 3.0,
+            max: None,
+            ramp: Ramp::Heat.ramp(),
+            width: 1024,
+            lift: 0.0035,
+            refresh: 0.2,
+        }
+    }
+}
+
+impl Heatmap {
+    pub fn add(&mut self, lat: f32, lon: f32, weight: f32) {
+        self.points.push(HeatPoint { lat, lon, weight });
+    }
+
+    /// The summed heat at each texel of [`Heatmap::texture`], row by row from the
+    /// north, each row from longitude -180.
+    pub fn density(&self) -> Vec<f32> {
+        let (width, height) = self.size();
+        let spread = self.spread.max(0.01);
+        let reach = spread * CUTOFF;
+        let lat_at = |y: usize| 90.0 - (y as f32 + 0.5) / height as f32 * 180.0;
+        let lon_at = |x: usize| (x as f32 + 0.5) / width as f32 * 360.0 - 180.0;
+        let columns = (0..width)
+            .map(|x| lon_at(x).to_radians().sin_cos())
+            .collect::<Vec<_>>();
+        let mut density = vec![0.0; width * height];
+        for point in &self.points {
+            let (sin_lat, cos_lat) = point.lat.to_radians().sin_cos();
+            let (sin_lon, cos_lon) = point.lon.to_radians().sin_cos();
+            let row = |lat: f32| ((90.0 - lat) / 180.0 * height as f32).floor();
+            let top = row(point.lat + reach).max(0.0) as usize;
+            let bottom = (row(point.lat - reach).max(0.0) as usize).min(height - 1);
+            for y in top..=bottom {
+                let lat = lat_at(y);
+                let (sin_row, cos_row) = lat.to_radians().sin_cos();
+                // Longitudes narrow towards the poles, so the reach spans more of them.
+                let across = (lat.abs() + reach).min(90.0).to_radians().cos();
+                let half = if across > f32::EPSILON {
+                    (reach / across).min(180.0)
+                } else {
+                    180.0
+                };
+                let span = ((half / 360.0 * width as f32).ceil() as usize).min(width / 2);
+                let center = ((point.lon + 180.0) / 360.0 * width as f32).floor() as isize;
+                for dx in -(span as isize)..=span as isize {
+                    let x = (center + dx).rem_euclid(width as isize) as usize;
+                    let (sin_col, cos_col) = columns[x];
+                    let cos_dlon = cos_col * cos_lon + sin_col * sin_lon;
+                    let cos_arc = sin_lat * sin_row + cos_lat * cos_row * cos_dlon;
+                    let arc = cos_arc.clamp(-1.0, 1.0).acos() * 180.0 / PI;
+                    if arc <= reach {
+                        density[y * width + x] +=
+                            point.weight * (-arc * arc / (2.0 * spread * spread)).exp();
+                    }
+                }
+            }
+        }
+        density
+    }
+
+    /// The heatmap as an equirectangular texture like that of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh).
+    pub fn texture(&self) -> Texture {
+        let (width, height) = self.size();
+        let density = self.density();
+        let max = self
+            .max
+            .unwrap_or_else(|| density.iter().copied().fold(0.0, f32::max))
+            .max(f32::EPSILON);
+        let data = density
+            .iter()
+            .flat_map(|&heat| {
+                let color = self.ramp.color(heat / max);
+                let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+                [
+                    byte(color.r()),
+                    byte(color.g()),
+                    byte(color.b()),
+                    byte(color.a()),
+                ]
+            })
+            .collect();
+        Texture::new(
+            Vec2::new(width as f32, height as f32),
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+
+    fn size(&self) -> (usize, usize) {
+        (self.width.max(2) as usize, (self.width / 2).max(1) as usize)
+    }
+}
+
+/// On a [`Heatmap`] once [`HeatmapPlugin`] has drawn it on the body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeatmapDrawn {
+    pub body: Entity,
+}
+
+/// When a drawn [`Heatmap`] was last baked, and whether it changed since.
+struct HeatmapBake {
+    texture: Handle<Texture>,
+    baked_at: f64,
+    stale: bool,
+}
+
+/// Draws each [`Heatmap`] on its [`Body`] as an unshaded, see-through shell, a child of
+/// the body so it turns with it, and bakes it again as its points change.
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(heatmap_draw_system.system())
+            .add_system(heatmap_update_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn heatmap_draw_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    heatmaps: Query<Without<HeatmapDrawn, (Entity, &Heatmap, Option<&Parent>)>>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    for (entity, heatmap, parent) in heatmaps.iter() {
+        let (body, adopt) = match body_for(parent, &bodies) {
+            Some(body) => body,
+            None => continue,
+        };
+        let radius = bodies.get(body).map_or(1.0, |(_, body, _)| body.radius);
+        let texture = textures.add(heatmap.texture());
+        commands.insert(
+            entity,
+            PbrComponents {
+                mesh: meshes.add(sphere_mesh(
+                    radius * (1.0 + heatmap.lift),
+                    SHELL_ROWS,
+                    4 * SHELL_ROWS,
+                )),
+                material: materials.add(StandardMaterial {
+                    albedo_texture: Some(texture.clone()),
+                    shaded: false,
+                    ..Default::default()
+                }),
+                draw: Draw {
+                    is_transparent: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        commands.insert(
+            entity,
+            (
+                HeatmapDrawn { body },
+                HeatmapBake {
+                    texture,
+                    baked_at: time.seconds_since_startup,
+                    stale: false,
+                },
+            ),
+        );
+        if adopt {
+            commands.push_children(body, &[entity]);
+        }
+    }
+}
+
+fn heatmap_update_system(
+    time: Res<Time>,
+    mut textures: ResMut<Assets<Texture>>,
+    changed: Query<With<HeatmapBake, (Entity, Changed<Heatmap>)>>,
+    mut heatmaps: Query<(&Heatmap, Mut<HeatmapBake>)>,
+) {
+    let changed = changed.iter().map(|(entity, _)| entity).collect::<Vec<_>>();
+    for entity in changed {
+        if let Ok((_, mut bake)) = heatmaps.get_mut(entity) {
+            bake.stale = true;
+        }
+    }
+    let now = time.seconds_since_startup;
+    for (heatmap, mut bake) in heatmaps.iter_mut() {
+        if !bake.stale || now - bake.baked_at < heatmap.refresh as f64 {
+            continue;
+        }
+        if let Some(texture) = textures.get_mut(&bake.texture) {
+            *texture = heatmap.texture();
+        }
+        bake.baked_at = now;
+        bake.stale = false;
+    }
+}
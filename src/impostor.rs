@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::camera::{Camera, PerspectiveProjection};
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+use bevy::render::texture::TextureFormat;
+
+use crate::lighting::LightingRig;
+
+const DISC_SEGMENTS: u32 = 48;
+/// Light the renderer adds to every shaded surface, which a baked impostor includes.
+const SHADER_AMBIENT: f32 = 0.05;
+
+/// When and how finely [`ImpostorPlugin`] draws spheres as impostors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImpostorSettings {
+    /// On-screen diameter, in pixels, below which a sphere is drawn as an impostor; 0
+    /// never uses them.
+    pub max_pixels: f32,
+    /// Side length of the texture an impostor is baked into.
+    pub resolution: u32,
+    /// Radians the view of a sphere may turn before its impostor is baked again.
+    pub rebake_angle: f32,
+}
+
+impl Default for ImpostorSettings {
+    fn default() -> Self {
+        ImpostorSettings {
+            max_pixels: 64.0,
+            resolution: 128,
+            rebake_angle: 3f32.to_radians(),
+        }
+    }
+}
+
+/// On a textured sphere centered on its entity: stand in for it with an [`ImpostorDisc`]
+/// once it is small on screen. The sphere's texture is expected to be equirectangular
+/// as laid out by the globe mesh of `render_sphere`: `u` falling from 1 to 0 as
+/// longitude runs from +x towards +y, `v` rising from the -z pole to the +z pole.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impostor {
+    /// Radius of the sphere before its entity's scale.
+    pub radius: f32,
+}
+
+/// A camera-facing disc drawn instead of the `sphere` entity, with the sphere as seen
+/// from the camera baked into its texture.
+pub struct ImpostorDisc {
+    pub sphere: Entity,
+    texture: Handle<Texture>,
+    baked: Option<Baked>,
+}
+
+/// What an impostor was last baked from.
+#[derive(Debug, Clone, PartialEq)]
+struct Baked {
+    /// The disc's rotation in the sphere's frame.
+    view: Quat,
+    texture: Option<Handle<Texture>>,
+    texture_size: Vec2,
+    albedo: Color,
+    rig: LightingRig,
+}
+
+/// Draws each [`Impostor`] sphere as a flat, camera-facing disc while it is smaller on
+/// screen than [`ImpostorSettings::max_pixels`], saving the cost of its mesh in scenes
+/// with many distant planets and moons. The disc's texture is baked on the CPU from the
+/// sphere's material, lit by the [`LightingRig`], and baked again as the view turns.
+/// Only the sphere itself is hidden meanwhile, not its children.
+pub struct ImpostorPlugin;
+
+impl Plugin for ImpostorPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<ImpostorSettings>() {
+            app.init_resource::<ImpostorSettings>();
+        }
+        if !app.resources().contains::<LightingRig>() {
+            app.init_resource::<LightingRig>();
+        }
+        app.add_system(spawn_disc_system.system())
+            .add_system(impostor_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_disc_system(
+    mut commands: Commands,
+    mut discs: Local<HashMap<Entity, Entity>>,
+    mut disc_mesh: Local<Option<Handle<Mesh>>>,
+    settings: Res<ImpostorSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    spheres: Query<With<Impostor, Entity>>,
+) {
+    for sphere in spheres.iter() {
+        if discs.contains_key(&sphere) {
+            continue;
+        }
+        let size = settings.resolution.max(1);
+        let texture = textures.add(Texture::new(
+            Vec2::new(size as f32, size as f32),
+            vec![0; (size * size * 4) as usize],
+            TextureFormat::Rgba8UnormSrgb,
+        ));
+        let mesh = disc_mesh
+            .get_or_insert_with(|| meshes.add(disc(DISC_SEGMENTS)))
+            .clone();
+        let disc = commands
+            .spawn(PbrComponents {
+                mesh,
+                material: materials.add(StandardMaterial {
+                    albedo_texture: Some(texture.clone()),
+                    shaded: false,
+                    ..Default::default()
+                }),
+                draw: Draw {
+                    is_visible: false,
+                    is_transparent: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(ImpostorDisc {
+                sphere,
+                texture,
+                baked: None,
+            })
+            .current_entity();
+        if let Some(disc) = disc {
+            discs.insert(sphere, disc);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn impostor_system(
+    settings: Res<ImpostorSettings>,
+    rig: Res<LightingRig>,
+    windows: Res<Windows>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    cameras: Query<(&Camera, &PerspectiveProjection, &GlobalTransform)>,
+    mut spheres: Query<
+        With<
+            Impostor,
+            (
+                &Impostor,
+                &GlobalTransform,
+                &Handle<StandardMaterial>,
+                Mut<Draw>,
+            ),
+        >,
+    >,
+    mut discs: Query<(Mut<ImpostorDisc>, Mut<Transform>, Mut<Draw>)>,
+) {
+    let window_height = match windows.get_primary() {
+        Some(window) => window.height() as f32,
+        None => return,
+    };
+    let (projection, camera) = match cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some((_, projection, transform)) => (projection, transform),
+        None => return,
+    };
+    for (mut disc, mut transform, mut disc_draw) in discs.iter_mut() {
+        let (impostor, sphere, material, mut sphere_draw) = match spheres.get_mut(disc.sphere) {
+            Ok(sphere) => sphere,
+            Err(_) => continue,
+        };
+        let radius = impostor.radius * sphere.scale.x();
+        let to_camera = camera.translation - sphere.translation;
+        let distance = to_camera.length();
+        let pixels = if distance > radius {
+            radius * 2.0 * window_height / (2.0 * distance * (projection.fov / 2.0).tan())
+        } else {
+            f32::INFINITY
+        };
+        let active = pixels < settings.max_pixels;
+        if sphere_draw.is_visible == active {
+            sphere_draw.is_visible = !active;
+        }
+        if disc_draw.is_visible != active {
+            disc_draw.is_visible = active;
+        }
+        if !active {
+            continue;
+        }
+
+        let facing = facing_rotation(to_camera / distance, camera.rotation * Vec3::unit_y());
+        *transform = Transform {
+            translation: sphere.translation,
+            rotation: facing,
+            scale: Vec3::splat(radius),
+        };
+        let material = match materials.get(material) {
+            Some(material) => material,
+            None => continue,
+        };
+        let source = material
+            .albedo_texture
+            .as_ref()
+            .and_then(|handle| textures.get(handle));
+        let baked = Baked {
+            view: sphere.rotation.conjugate() * facing,
+            texture: material.albedo_texture.clone(),
+            texture_size: source.map_or(Vec2::zero(), |t| t.size),
+            albedo: material.albedo,
+            rig: *rig,
+        };
+        if disc
+            .baked
+            .as_ref()
+            .is_some_and(|old| !needs_rebake(old, &baked, settings.rebake_angle))
+        {
+            continue;
+        }
+        let data = bake(
+            source,
+            material.albedo,
+            &rig,
+            sphere.rotation,
+            facing,
+            settings.resolution.max(1),
+        );
+        if let Some(texture) = textures.get_mut(&disc.texture) {
+            texture.data = data;
+        }
+        disc.baked = Some(baked);
+    }
+}
+
+fn needs_rebake(old: &Baked, new: &Baked, rebake_angle: f32) -> bool {
+    let turned = 2.0 * old.view.dot(new.view).abs().min(1.0).acos();
+    turned > rebake_angle
+        || old.texture != new.texture
+        || old.texture_size != new.texture_size
+        || old.albedo != new.albedo
+        || old.rig != new.rig
+}
+
+/// Turns the disc's +z towards `forward` with its +y as close to `up` as it goes.
+fn facing_rotation(forward: Vec3, up: Vec3) -> Quat {
+    let mut x = up.cross(forward);
+    if x.length_squared() < 1e-6 {
+        x = forward.cross(Vec3::unit_x());
+        if x.length_squared() < 1e-6 {
+            x = forward.cross(Vec3::unit_y());
+        }
+    }
+    let x = x.normalize();
+    let y = forward.cross(x);
+    Quat::from_rotation_mat3(&Mat3::from_cols(x, y, forward))
+}
+
+/// The sphere as seen straight on through the disc, one `size`×`size` texel grid of
+/// RGBA8 data, transparent outside the disc.
+fn bake(
+    source: Option<&Texture>,
+    albedo: Color,
+    rig: &LightingRig,
+    sphere_rotation: Quat,
+    facing: Quat,
+    size: u32,
+) -> Vec<u8> {
+    let lights = rig
+        .lights()
+        .into_iter()
+        .map(|(position, color)| (position.normalize(), color))
+        .collect::<Vec<_>>();
+    let to_sphere = sphere_rotation.conjugate();
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for j in 0..size {
+        for i in 0..size {
+            let u = (i as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+            let v = 1.0 - (j as f32 + 0.5) / size as f32 * 2.0;
+            let r2 = u * u + v * v;
+            if r2 > 1.0 {
+                data.extend_from_slice(&[0, 0, 0, 0]);
+                continue;
+            }
+            let normal = facing * Vec3::new(u, v, (1.0 - r2).sqrt());
+            let local = to_sphere * normal;
+            let mut color = albedo;
+            if let Some(texture) = source {
+                let texel = sample(texture, local);
+                color = Color::rgba(
+                    color.r() * texel[0],
+                    color.g() * texel[1],
+                    color.b() * texel[2],
+                    color.a() * texel[3],
+                );
+            }
+            let mut light = Vec3::splat(SHADER_AMBIENT);
+            for (direction, light_color) in &lights {
+                let lambert = normal.dot(*direction).max(0.0);
+                light += Vec3::new(light_color.r(), light_color.g(), light_color.b()) * lambert;
+            }
+            let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            data.extend_from_slice(&[
+                channel(color.r() * light.x()),
+                channel(color.g() * light.y()),
+                channel(color.b() * light.z()),
+                channel(color.a()),
+            ]);
+        }
+    }
+    data
+}
+
+/// The texel of an equirectangular `texture` at the point of the unit sphere facing
+/// `normal`, as `[0, 1]` RGBA. Formats other than 8-bit RGBA/BGRA read as white.
+fn sample(texture: &Texture, normal: Vec3) -> [f32; 4] {
+    let bgra = match texture.format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        _ => return [1.0; 4],
+    };
+    let (width, height) = (texture.size.x() as usize, texture.size.y() as usize);
+    if width == 0 || height == 0 {
+        return [1.0; 4];
+    }
+    let u = 1.0 - normal.y().atan2(normal.x()).rem_euclid(PI * 2.0) / (PI * 2.0);
+    let v = (normal.z().clamp(-1.0, 1.0).asin() + PI / 2.0) / PI;
+    let x = ((u * width as f32) as usize).min(width - 1);
+    let y = ((v * height as f32) as usize).min(height - 1);
+    let at = (y * width + x) * 4;
+    let texel = match texture.data.get(at..at + 4) {
+        Some(texel) => texel,
+        None => return [1.0; 4],
+    };
+    let [r, g, b] = if bgra {
+        [texel[2], texel[1], texel[0]]
+    } else {
+        [texel[0], texel[1], texel[2]]
+    };
+    [r, g, b, texel[3]].map(|c| c as f32 / 255.0)
+}
+
+/// A unit disc facing +z, textured with the square `[0, 1]` UV range.
+fn disc(segments: u32) -> Mesh {
+    let mut positions = vec![[0.0, 0.0, 0.0]];
+    let mut uvs = vec![[0.5, 0.5]];
+    for i in 0..=segments {
+        let angle = i as f32 / segments as f32 * PI * 2.0;
+        let (x, y) = (angle.cos(), angle.sin());
+        positions.push([x, y, 0.0]);
+        uvs.push([(x + 1.0) / 2.0, (1.0 - y) / 2.0]);
+    }
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    let indices = (1..=segments).flat_map(|i| vec![0, i, i + 1]).collect();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+
+use bevy::core::{AsBytes, Byteable};
+use bevy::prelude::*;
+use bevy::render::draw::DrawContext;
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::{
+    DynamicBinding, InputStepMode, PipelineDescriptor, VertexAttributeDescriptor,
+    VertexBufferDescriptor,
+};
+use bevy::render::render_graph::base::{MainPass, Msaa};
+use bevy::render::renderer::{BufferId, BufferInfo, BufferUsage, RenderResourceBindings};
+use bevy::render::shader::{ShaderStage, ShaderStages};
+use bevy::render::stage as render_stage;
+use bevy::type_registry::TypeUuid;
+
+use crate::bodies::Body;
+use crate::geo_marker::{body_for, GeoMarker, GeoMarkerPin, GeoMarkerStyle};
+
+/// The pipeline drawing [`MarkerBatch`]es.
+pub const MARKER_BATCH_PIPELINE_HANDLE: Handle<PipelineDescriptor> =
+    Handle::weak_from_u64(PipelineDescriptor::TYPE_UUID, 9_126_604_773_085_149_731);
+/// Most pins one [`MarkerBatch`] draws, so that a marker moving uploads only its own
+/// batch again rather than every pin.
+const BATCH_SIZE: usize = 16_384;
+/// Subdivisions of the pins' icosphere, as on [`GeoMarkerPlugin`]'s own.
+///
+/// [`GeoMarkerPlugin`]: crate::geo_marker::GeoMarkerPlugin
+const PIN_SUBDIVISIONS: usize = 2;
+
+const VERTEX_SHADER: &str = r#"
+#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec4 I_Position;
+layout(location = 2) in vec4 I_Color;
+
+layout(location = 0) out vec4 v_Color;
+
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 ViewProj;
+};
+
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+
+void main() {
+    // The unit ball scaled to the pin's size and moved out to its place on the body.
+    vec3 local = I_Position.xyz + Vertex_Position * I_Position.w;
+    v_Color = I_Color;
+    gl_Position = ViewProj * Model * vec4(local, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 450
+
+layout(location = 0) in vec4 v_Color;
+
+layout(location = 0) out vec4 o_Target;
+
+void main() {
+    o_Target = v_Color;
+}
+"#;
+
+/// When [`GeoMarker`]s are drawn instanced, set by [`InstancedMarkerPlugin`]. Without
+/// the plugin they never are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarkerInstancing {
+    /// Markers are drawn instanced while there are at least this many, and as entities
+    /// of their own below that.
+    pub threshold: usize,
+    active: bool,
+}
+
+impl Default for MarkerInstancing {
+    fn default() -> Self {
+        MarkerInstancing::new(usize::MAX)
+    }
+}
+
+impl MarkerInstancing {
+    pub fn new(threshold: usize) -> Self {
+        MarkerInstancing {
+            threshold,
+            active: false,
+        }
+    }
+
+    /// Whether the markers are drawn instanced now.
+    pub fn active(&self) -> bool {
+        self.active
+    }
+}
+
+/// The color of a [`GeoMarker`]'s pin while it is drawn instanced, in place of
+/// [`GeoMarkerStyle::color`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstanceColor(pub Color);
+
+/// On a [`GeoMarker`] whose pin is drawn by a [`MarkerBatch`] rather than an entity of
+/// its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstancedPin;
+
+/// One pin as the instance buffer holds it, in the frame of its body.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PinInstance {
+    /// The center, with the ball's radius last.
+    position: [f32; 4],
+    color: [f32; 4],
+}
+
+unsafe impl Byteable for PinInstance {}
+
+/// A child of a [`Body`] drawing up to [`BATCH_SIZE`] of its instanced pins in one
+/// call, the `index`th of the body's batches.
+#[derive(Debug)]
+pub struct MarkerBatch {
+    pub body: Entity,
+    pub index: usize,
+    instances: Vec<PinInstance>,
+    uploaded: bool,
+}
+
+impl MarkerBatch {
+    /// How many pins the batch draws.
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+}
+
+/// Draws [`GeoMarker`] pins with GPU instancing once there are at least `threshold`
+/// markers: each pin is then one instance of a single ball mesh, placed and colored
+/// from a per-instance buffer, and every body's pins take one draw call per
+/// [`BATCH_SIZE`] of them. Below the threshold, and without this plugin,
+/// [`GeoMarkerPlugin`](crate::geo_marker::GeoMarkerPlugin) gives each pin an entity of
+/// its own, as before; markers move between the two as the count crosses it.
+///
+/// Instanced pins are marked [`InstancedPin`], keep their [`GeoMarkerPin`] for picking,
+/// and are drawn unlit in [`GeoMarkerStyle::color`] or their [`InstanceColor`]. Having
+/// no material of their own, they aren't faded by
+/// [`OcclusionPlugin`](crate::occlusion::OcclusionPlugin); the body hides those behind
+/// it. Add after the render plugins.
+pub struct InstancedMarkerPlugin {
+    pub threshold: usize,
+}
+
+impl Default for InstancedMarkerPlugin {
+    fn default() -> Self {
+        InstancedMarkerPlugin { threshold: 1000 }
+    }
+}
+
+impl Plugin for InstancedMarkerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(MarkerInstancing::new(self.threshold));
+        if !app.resources().contains::<GeoMarkerStyle>() {
+            app.init_resource::<GeoMarkerStyle>();
+        }
+        // Markers are moved between the two ways of drawing them before
+        // GeoMarkerPlugin's systems look at them.
+        app.add_system_to_stage(stage::PRE_UPDATE, instancing_switch_system.system())
+            .add_system_to_stage(stage::POST_UPDATE, marker_batch_system.system())
+            .add_system_to_stage(render_stage::DRAW, marker_batch_draw_system.system());
+        let resources = app.resources();
+        let samples = resources.get::<Msaa>().map_or(1, |msaa| msaa.samples);
+        let mut shaders = resources.get_mut::<Assets<Shader>>().unwrap();
+        let mut pipelines = resources.get_mut::<Assets<PipelineDescriptor>>().unwrap();
+        let pipeline = batch_pipeline(&mut shaders, samples);
+        pipelines.set_untracked(MARKER_BATCH_PIPELINE_HANDLE, pipeline);
+    }
+}
+
+/// The mesh every instanced pin is a copy of: a ball of radius 1.
+fn pin_mesh() -> Mesh {
+    Mesh::from(shape::Icosphere {
+        radius: 1.0,
+        subdivisions: PIN_SUBDIVISIONS,
+    })
+}
+
+/// The pipeline of [`MarkerBatch`]es. bevy's pipeline compiler only takes vertex
+/// attributes from the mesh, so this one is laid out by hand, with a second vertex
+/// buffer stepped per instance, and is never specialized.
+fn batch_pipeline(shaders: &mut Assets<Shader>, samples: u32) -> PipelineDescriptor {
+    let vertex = Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER).get_spirv_shader(None);
+    let fragment = Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER).get_spirv_shader(None);
+    let mut pipeline = PipelineDescriptor::default_config(ShaderStages {
+        vertex: shaders.add(vertex),
+        fragment: Some(shaders.add(fragment)),
+    });
+    pipeline.sample_count = samples;
+    pipeline.reflect_layout(
+        shaders,
+        true,
+        &[
+            // Transform
+            DynamicBinding {
+                bind_group: 1,
+                binding: 0,
+            },
+        ],
+    );
+    let mesh = pin_mesh().get_vertex_buffer_descriptor();
+    let mut vertices = VertexBufferDescriptor {
+        name: "Vertex".into(),
+        stride: mesh.stride,
+        step_mode: InputStepMode::Vertex,
+        attributes: Vec::new(),
+    };
+    let mut instances = VertexBufferDescriptor {
+        name: "Instance".into(),
+        stride: size_of::<PinInstance>() as u64,
+        step_mode: InputStepMode::Instance,
+        attributes: Vec::new(),
+    };
+    let layout = pipeline.get_layout_mut().unwrap();
+    for reflected in layout.vertex_buffer_descriptors.iter() {
+        let attribute = &reflected.attributes[0];
+        if reflected.step_mode == InputStepMode::Instance {
+            let offset = match &*attribute.name {
+                "I_Position" => 0,
+                _ => size_of::<[f32; 4]>() as u64,
+            };
+            instances.attributes.push(VertexAttributeDescriptor {
+                offset,
+                ..attribute.clone()
+            });
+        } else if let Some(from_mesh) = mesh.attributes.iter().find(|a| a.name == attribute.name) {
+            vertices.attributes.push(VertexAttributeDescriptor {
+                shader_location: attribute.shader_location,
+                ..from_mesh.clone()
+            });
+        }
+    }
+    layout.vertex_buffer_descriptors = vec![vertices, instances];
+    pipeline
+}
+
+#[allow(clippy::type_complexity)]
+fn instancing_switch_system(
+    mut commands: Commands,
+    mut instancing: ResMut<MarkerInstancing>,
+    markers: Query<&GeoMarker>,
+    unplaced: Query<Without<GeoMarkerPin, (Entity, &GeoMarker, Option<&Parent>)>>,
+    pinned: Query<With<GeoMarkerPin, Without<InstancedPin, (Entity, &GeoMarker)>>>,
+    instanced: Query<With<InstancedPin, (Entity, &GeoMarker)>>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    let active = markers.iter().count() >= instancing.threshold;
+    if instancing.active != active {
+        instancing.active = active;
+    }
+    if !active {
+        // GeoMarkerPlugin pins them again.
+        for (entity, _) in instanced.iter() {
+            commands.remove::<(GeoMarkerPin, InstancedPin)>(entity);
+        }
+        return;
+    }
+    for (entity, _) in pinned.iter() {
+        commands
+            .remove::<PbrComponents>(entity)
+            .insert_one(entity, InstancedPin);
+    }
+    for (entity, _, parent) in unplaced.iter() {
+        if let Some((body, _)) = body_for(parent, &bodies) {
+            commands.insert(entity, (GeoMarkerPin { body }, InstancedPin));
+        }
+    }
+}
+
+#[derive(Default)]
+struct BatchState {
+    markers: usize,
+    colors: usize,
+    style: Option<GeoMarkerStyle>,
+}
+
+/// Fills each body's [`MarkerBatch`]es with its instanced pins, in a steady order, on
+/// frames where a marker or its color changed, or one came or went; batches that end
+/// up empty are despawned.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn marker_batch_system(
+    mut commands: Commands,
+    mut state: Local<BatchState>,
+    style: Res<GeoMarkerStyle>,
+    markers: Query<With<InstancedPin, (Entity, &GeoMarker, &GeoMarkerPin)>>,
+    colored: Query<With<InstancedPin, &InstanceColor>>,
+    changed: Query<With<InstancedPin, Or<(Changed<GeoMarker>, Changed<InstanceColor>)>>>,
+    bodies: Query<&Body>,
+    mut batches: Query<(Entity, Mut<MarkerBatch>)>,
+) {
+    let count = markers.iter().count();
+    let colors = colored.iter().count();
+    let dirty = state.markers != count
+        || state.colors != colors
+        || state.style != Some(*style)
+        || changed.iter().next().is_some();
+    if !dirty {
+        return;
+    }
+    *state = BatchState {
+        markers: count,
+        colors,
+        style: Some(*style),
+    };
+
+    let mut pins = markers
+        .iter()
+        .filter_map(|(entity, marker, pin)| {
+            let body = bodies.get(pin.body).ok()?;
+            let center = marker.local(body.radius);
+            let color = colored.get(entity).map_or(style.color, |color| color.0);
+            let instance = PinInstance {
+                position: [center.x(), center.y(), center.z(), style.size],
+                color: [color.r(), color.g(), color.b(), color.a()],
+            };
+            Some(((pin.body.id(), entity.id()), pin.body, instance))
+        })
+        .collect::<Vec<_>>();
+    pins.sort_by_key(|(key, ..)| *key);
+    let mut wanted = Vec::<(Entity, Vec<PinInstance>)>::new();
+    for (_, body, instance) in pins {
+        match wanted.last_mut() {
+            Some((last, instances)) if *last == body && instances.len() < BATCH_SIZE => {
+                instances.push(instance);
+            }
+            _ => wanted.push((body, vec![instance])),
+        }
+    }
+    let mut filled = Vec::new();
+    for (entity, mut batch) in batches.iter_mut() {
+        let index = wanted
+            .iter()
+            .enumerate()
+            .filter(|(_, (body, _))| *body == batch.body)
+            .nth(batch.index)
+            .map(|(index, _)| index);
+        match index.filter(|index| !filled.contains(index)) {
+            Some(index) => {
+                let instances = std::mem::take(&mut wanted[index].1);
+                if batch.instances != instances {
+                    batch.instances = instances;
+                    batch.uploaded = false;
+                }
+                filled.push(index);
+            }
+            None => {
+                commands.despawn(entity);
+            }
+        }
+    }
+    let mut counts = Vec::<(Entity, usize)>::new();
+    for (index, (body, instances)) in wanted.into_iter().enumerate() {
+        let nth = match counts.iter_mut().find(|(other, _)| *other == body) {
+            Some((_, n)) => {
+                *n += 1;
+                *n - 1
+            }
+            None => {
+                counts.push((body, 1));
+                0
+            }
+        };
+        if filled.contains(&index) {
+            continue;
+        }
+        let batch = commands
+            .spawn((
+                MarkerBatch {
+                    body,
+                    index: nth,
+                    instances,
+                    uploaded: false,
+                },
+                MainPass,
+                Draw {
+                    is_transparent: true,
+                    ..Default::default()
+                },
+                // Only holds the transform's binding; the batch is drawn by
+                // marker_batch_draw_system, not bevy's.
+                RenderPipelines::from_pipelines(Vec::new()),
+                Transform::default(),
+                GlobalTransform::default(),
+            ))
+            .current_entity()
+            .unwrap();
+        commands.push_children(body, &[batch]);
+    }
+}
+
+/// The ball every pin is drawn from, uploaded on the first frame.
+struct PinBuffers {
+    vertices: BufferId,
+    indices: BufferId,
+    index_count: u32,
+}
+
+/// Uploads the instances of batches that changed and records their draw calls by
+/// hand, as bevy's draw system knows nothing of instance buffers. Each batch's buffer
+/// is kept here by entity, and freed once the batch is gone.
+#[allow(clippy::type_complexity)]
+fn marker_batch_draw_system(
+    mut draw_context: DrawContext,
+    mut render_resource_bindings: ResMut<RenderResourceBindings>,
+    mut pin: Local<Option<PinBuffers>>,
+    mut buffers: Local<HashMap<Entity, BufferId>>,
+    mut batches: Query<(Entity, Mut<MarkerBatch>, Mut<Draw>, Mut<RenderPipelines>)>,
+) {
+    let context = &**draw_context.render_resource_context;
+    if let Some(pipeline) = draw_context.pipelines.get(&MARKER_BATCH_PIPELINE_HANDLE) {
+        context.create_render_pipeline(
+            MARKER_BATCH_PIPELINE_HANDLE,
+            pipeline,
+            &draw_context.shaders,
+        );
+    }
+    let pin = pin.get_or_insert_with(|| {
+        let mesh = pin_mesh();
+        let index_count = match mesh.indices() {
+            Some(Indices::U32(indices)) => indices.len() as u32,
+            Some(Indices::U16(indices)) => indices.len() as u32,
+            None => 0,
+        };
+        PinBuffers {
+            vertices: context.create_buffer_with_data(
+                BufferInfo {
+                    buffer_usage: BufferUsage::VERTEX,
+                    ..Default::default()
+                },
+                &mesh.get_vertex_buffer_data(),
+            ),
+            indices: context.create_buffer_with_data(
+                BufferInfo {
+                    buffer_usage: BufferUsage::INDEX,
+                    ..Default::default()
+                },
+                &mesh.get_index_buffer_bytes().unwrap_or_default(),
+            ),
+            index_count,
+        }
+    });
+    let mut live = HashMap::new();
+    for (entity, mut batch, mut draw, mut render_pipelines) in batches.iter_mut() {
+        if batch.is_empty() {
+            continue;
+        }
+        let buffer = match buffers.remove(&entity) {
+            Some(buffer) if batch.uploaded => buffer,
+            old => {
+                if let Some(buffer) = old {
+                    context.remove_buffer(buffer);
+                }
+                batch.uploaded = true;
+                context.create_buffer_with_data(
+                    BufferInfo {
+                        buffer_usage: BufferUsage::VERTEX,
+                        ..Default::default()
+                    },
+                    batch.instances.as_slice().as_bytes(),
+                )
+            }
+        };
+        live.insert(entity, buffer);
+        if !draw.is_visible {
+            continue;
+        }
+        draw.set_pipeline(&MARKER_BATCH_PIPELINE_HANDLE);
+        draw_context.current_pipeline = Some(MARKER_BATCH_PIPELINE_HANDLE);
+        let bound = draw_context.set_bind_groups_from_bindings(
+            &mut draw,
+            &mut [
+                &mut render_pipelines.bindings,
+                &mut render_resource_bindings,
+            ],
+        );
+        if bound.is_err() {
+            continue;
+        }
+        draw.set_index_buffer(pin.indices, 0);
+        draw.set_vertex_buffer(0, pin.vertices, 0);
+        draw.set_vertex_buffer(1, buffer, 0);
+        draw.draw_indexed(0..pin.index_count, 0, 0..batch.len() as u32);
+    }
+    for (_, buffer) in buffers.drain() {
+        context.remove_buffer(buffer);
+    }
+    *buffers = live;
+}
@@ -0,0 +1,383 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::target::Target;
+use crate::units::{parse_angle, DistanceUnits, ParseError, Position};
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("{0}: {1}")]
+    Json(String, #[source] serde_json::Error),
+    #[error("{path}:{line}: {message}")]
+    Csv {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    #[error("{path}: record {record}: {message}")]
+    Invalid {
+        path: String,
+        record: usize,
+        message: String,
+    },
+    #[error("{0}: unknown file type, expected .json or .csv")]
+    UnknownFormat(String),
+}
+
+/// One target as written in a JSON or CSV target list. Angles are in degrees. Either
+/// may be written as a string too, the azimuth as [`parse_angle`] reads it and the
+/// distance with a unit as [`DistanceUnits::parse_distance`] does, e.g. `"123°24'"`
+/// and `"12.5nm"`. In place of both, a `position` field may give a compass bearing and
+/// range as [`DistanceUnits::parse_polar`] reads them, e.g. `"230/4.2nm"`. Deserializing
+/// takes the [`DistanceUnits::default`] units.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "RecordFields")]
+pub struct TargetRecord {
+    pub id: i32,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Degrees, measured the same way as `Target::azimuth`.
+    pub azimuth: f32,
+    pub distance: f32,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub priority: i32,
+    /// When the target was observed, in seconds, see [`Target::time`].
+    #[serde(default)]
+    pub time: Option<f64>,
+}
+
+/// A number, or a string to parse into one.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum NumberOrText {
+    Number(f32),
+    Text(String),
+}
+
+/// A [`TargetRecord`] as written, before its azimuth and distance are parsed.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecordFields {
+    id: i32,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    azimuth: Option<NumberOrText>,
+    #[serde(default)]
+    distance: Option<NumberOrText>,
+    #[serde(default)]
+    position: Option<String>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default)]
+    time: Option<f64>,
+}
+
+impl RecordFields {
+    fn resolve(self, units: &DistanceUnits) -> Result<TargetRecord, String> {
+        let id = self.id;
+        let error = |what: &str, e: ParseError| format!("target {}: {}: {}", id, what, e);
+        let (azimuth, distance) = match (self.position, self.azimuth, self.distance) {
+            (Some(position), None, None) => {
+                parse_record_position(&position, units).map_err(|e| error("position", e))?
+            }
+            (None, Some(azimuth), Some(distance)) => (
+                match azimuth {
+                    NumberOrText::Number(n) => n,
+                    NumberOrText::Text(t) => parse_angle(&t).map_err(|e| error("azimuth", e))?,
+                },
+                match distance {
+                    NumberOrText::Number(n) => n,
+                    NumberOrText::Text(t) => {
+                        units.parse_distance(&t).map_err(|e| error("distance", e))?
+                    }
+                },
+            ),
+            _ => {
+                return Err(format!(
+                    "target {}: expected either a position or an azimuth and a distance",
+                    id
+                ))
+            }
+        };
+        Ok(TargetRecord {
+            id,
+            label: self.label,
+            azimuth,
+            distance,
+            category: self.category,
+            priority: self.priority,
+            time: self.time,
+        })
+    }
+}
+
+/// The azimuth in degrees and the distance of a `position` field. Target lists place
+/// targets around the sensor, so a geodetic position is refused rather than guessed
+/// at.
+fn parse_record_position(input: &str, units: &DistanceUnits) -> Result<(f32, f32), ParseError> {
+    match units.parse_position(input)? {
+        Position::Polar { azimuth, dist } => Ok((azimuth.to_degrees(), dist)),
+        Position::Geo { .. } => Err(ParseError::Position {
+            input: input.trim().to_string(),
+            reason: "a target list takes a bearing and range, as BEARING/RANGE",
+        }),
+    }
+}
+
+impl TryFrom<RecordFields> for TargetRecord {
+    type Error = String;
+
+    fn try_from(fields: RecordFields) -> Result<Self, Self::Error> {
+        fields.resolve(&DistanceUnits::default())
+    }
+}
+
+impl TargetRecord {
+    /// Checks that the azimuth and any time are finite and the distance finite and
+    /// non-negative.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.azimuth.is_finite() {
+            return Err(format!("target {}: azimuth is not a number", self.id));
+        }
+        if !self.distance.is_finite() || self.distance < 0.0 {
+            return Err(format!(
+                "target {}: distance must be a non-negative number, got {}",
+                self.id, self.distance
+            ));
+        }
+        if self.time.is_some_and(|t| !t.is_finite()) {
+            return Err(format!("target {}: time is not a number", self.id));
+        }
+        Ok(())
+    }
+
+    pub fn into_target(self) -> Target {
+        let id = self.id;
+        Target {
+            id,
+            text: self.label.unwrap_or_else(|| id.to_string()),
+            azimuth: self
+                .azimuth
+                .to_radians()
+                .rem_euclid(std::f32::consts::PI * 2.0),
+            dist: self.distance,
+            category: self.category,
+            priority: self.priority,
+            time: self.time,
+            ..Default::default()
+        }
+    }
+}
+
+/// Loads a target list from a `.json` file (an array of [`TargetRecord`] objects) or
+/// a `.csv` file with a header row naming the same fields. Every record is checked for
+/// finite angles, non-negative distances and unique ids, and errors say which record
+/// or line is at fault. Distances with a unit are read in the default
+/// [`DistanceUnits`].
+pub fn load_targets(path: impl AsRef<Path>) -> Result<Vec<Target>, LoadError> {
+    load_targets_with(path, &DistanceUnits::default())
+}
+
+/// [`load_targets`], reading distances with a unit in `units`.
+pub fn load_targets_with(
+    path: impl AsRef<Path>,
+    units: &DistanceUnits,
+) -> Result<Vec<Target>, LoadError> {
+    let path = path.as_ref();
+    let name = path.display().to_string();
+    let contents = fs::read_to_string(path).map_err(|e| LoadError::Io(name.clone(), e))?;
+    let records = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("json") => parse_json(&name, &contents, units)?,
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => parse_csv(&name, &contents, units)?,
+        _ => return Err(LoadError::UnknownFormat(name)),
+    };
+    validate(&name, records)
+}
+
+pub fn parse_json(
+    name: &str,
+    json: &str,
+    units: &DistanceUnits,
+) -> Result<Vec<TargetRecord>, LoadError> {
+    let records: Vec<RecordFields> =
+        serde_json::from_str(json).map_err(|e| LoadError::Json(name.to_string(), e))?;
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| {
+            record.resolve(units).map_err(|message| LoadError::Invalid {
+                path: name.to_string(),
+                record: i + 1,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Parses comma-separated records with a header row. Fields may be double-quoted, with
+/// `""` for a literal quote; empty optional fields are left unset. Azimuths, distances
+/// and positions are read like the strings of [`TargetRecord`], distances in `units`;
+/// a record with a position leaves its azimuth and distance empty.
+pub fn parse_csv(
+    name: &str,
+    csv: &str,
+    units: &DistanceUnits,
+) -> Result<Vec<TargetRecord>, LoadError> {
+    let error = |line: usize, message: String| LoadError::Csv {
+        path: name.to_string(),
+        line,
+        message,
+    };
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l))
+        .filter(|(_, l)| !l.trim().is_empty());
+    let (header_line, header) = lines.next().ok_or_else(|| error(1, "empty file".into()))?;
+    let columns = split_csv_line(header).map_err(|e| error(header_line, e))?;
+    let column = |field: &str| columns.iter().position(|c| c.trim() == field);
+    for c in &columns {
+        let known = [
+            "id", "label", "azimuth", "distance", "position", "category", "priority", "time",
+        ];
+        if !known.contains(&c.trim()) {
+            return Err(error(header_line, format!("unknown column {:?}", c)));
+        }
+    }
+    let (id, azimuth, distance, position) = (
+        column("id"),
+        column("azimuth"),
+        column("distance"),
+        column("position"),
+    );
+    let id = match id {
+        Some(id) if position.is_some() || (azimuth.is_some() && distance.is_some()) => id,
+        _ => {
+            return Err(error(
+                header_line,
+                "header must name the id column and the azimuth and distance or the position \
+                 columns"
+                    .into(),
+            ))
+        }
+    };
+    let (label, category, priority) = (column("label"), column("category"), column("priority"));
+    let time = column("time");
+
+    let mut records = Vec::new();
+    for (line, text) in lines {
+        let fields = split_csv_line(text).map_err(|e| error(line, e))?;
+        if fields.len() != columns.len() {
+            return Err(error(
+                line,
+                format!("expected {} fields, found {}", columns.len(), fields.len()),
+            ));
+        }
+        let parsed = |what: &str, result: Result<f32, ParseError>| {
+            result.map_err(|e| error(line, format!("{}: {}", what, e)))
+        };
+        let optional = |i: Option<usize>| {
+            i.map(|i| fields[i].trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let (azimuth, distance) = match (optional(position), optional(azimuth), optional(distance))
+        {
+            (Some(position), None, None) => parse_record_position(&position, units)
+                .map_err(|e| error(line, format!("position: {}", e)))?,
+            (None, Some(azimuth), Some(distance)) => (
+                parsed("azimuth", parse_angle(&azimuth))?,
+                parsed("distance", units.parse_distance(&distance))?,
+            ),
+            _ => {
+                return Err(error(
+                    line,
+                    "expected either a position or an azimuth and a distance".into(),
+                ))
+            }
+        };
+        records.push(TargetRecord {
+            id: fields[id]
+                .trim()
+                .parse()
+                .map_err(|_| error(line, format!("id {:?} is not an integer", fields[id])))?,
+            label: optional(label),
+            azimuth,
+            distance,
+            category: optional(category),
+            priority: match optional(priority) {
+                Some(p) => p
+                    .parse()
+                    .map_err(|_| error(line, format!("priority {:?} is not an integer", p)))?,
+                None => 0,
+            },
+            time: match optional(time) {
+                Some(t) => Some(
+                    t.parse()
+                        .map_err(|_| error(line, format!("time {:?} is not a number", t)))?,
+                ),
+                None => None,
+            },
+        });
+    }
+    Ok(records)
+}
+
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".into());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+fn validate(name: &str, records: Vec<TargetRecord>) -> Result<Vec<Target>, LoadError> {
+    let mut seen = HashSet::new();
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let invalid = |message| LoadError::Invalid {
+                path: name.to_string(),
+                record: i + 1,
+                message,
+            };
+            record.validate().map_err(invalid)?;
+            if !seen.insert(record.id) {
+                return Err(invalid(format!("duplicate id {}", record.id)));
+            }
+            Ok(record.into_target())
+        })
+        .collect()
+}
@@ -0,0 +1,137 @@
+use std::convert::TryInto;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::render::texture::TextureFormat;
+use bevy::utils::BoxedFuture;
+use thiserror::Error;
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+/// Bytes before the level index: identifier, nine `u32` header fields, and the data
+/// format, key/value and supercompression global data offsets and lengths.
+const LEVEL_INDEX_OFFSET: usize = 80;
+const LEVEL_INDEX_ENTRY: usize = 24;
+
+// Vulkan format numbers of the 8-bit four-channel formats the renderer can take.
+const VK_FORMAT_R8G8B8A8_UNORM: u32 = 37;
+const VK_FORMAT_R8G8B8A8_SRGB: u32 = 43;
+const VK_FORMAT_B8G8R8A8_UNORM: u32 = 44;
+const VK_FORMAT_B8G8R8A8_SRGB: u32 = 50;
+
+#[derive(Debug, Error)]
+pub enum Ktx2Error {
+    #[error("not a KTX2 file")]
+    NotKtx2,
+    #[error("file ends early")]
+    Truncated,
+    #[error("unsupported format {0} (only 8-bit RGBA/BGRA, UNORM or sRGB, is supported)")]
+    UnsupportedFormat(u32),
+    #[error("supercompression scheme {0} needs a decoder that is not built in")]
+    Supercompressed(u32),
+    #[error("array, cube map and 3D textures are not supported")]
+    NotPlain2d,
+}
+
+/// Reads a 2D texture from a KTX2 container, taking the largest stored mip level
+/// that fits within `max_size` texels on each side, or the smallest one if none
+/// does. Imagery too large to keep in memory at full size can be shipped with its
+/// mip chain and loaded at the size the display needs, without decoding the rest.
+/// Block-compressed formats and Basis or zstd supercompression are refused: the
+/// renderer only takes uncompressed textures and no decoder is built in.
+pub fn read_ktx2(bytes: &[u8], max_size: u32) -> Result<Texture, Ktx2Error> {
+    if bytes.get(..IDENTIFIER.len()) != Some(&IDENTIFIER[..]) {
+        return Err(Ktx2Error::NotKtx2);
+    }
+    let u32_at = |at: usize| -> Result<u32, Ktx2Error> {
+        let bytes = bytes.get(at..at + 4).ok_or(Ktx2Error::Truncated)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let u64_at = |at: usize| -> Result<usize, Ktx2Error> {
+        let bytes = bytes.get(at..at + 8).ok_or(Ktx2Error::Truncated)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    };
+    let vk_format = u32_at(12)?;
+    let (width, height, depth) = (u32_at(20)?, u32_at(24)?, u32_at(28)?);
+    let (layers, faces, levels) = (u32_at(32)?, u32_at(36)?, u32_at(40)?.max(1));
+    let supercompression = u32_at(44)?;
+
+    let format = match vk_format {
+        VK_FORMAT_R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        VK_FORMAT_R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        VK_FORMAT_B8G8R8A8_UNORM => TextureFormat::Bgra8Unorm,
+        VK_FORMAT_B8G8R8A8_SRGB => TextureFormat::Bgra8UnormSrgb,
+        other => return Err(Ktx2Error::UnsupportedFormat(other)),
+    };
+    if supercompression != 0 {
+        return Err(Ktx2Error::Supercompressed(supercompression));
+    }
+    if depth > 1 || layers > 1 || faces > 1 {
+        return Err(Ktx2Error::NotPlain2d);
+    }
+
+    let level_size = |level: u32| ((width >> level).max(1), (height >> level).max(1));
+    let level = (0..levels)
+        .find(|&level| {
+            let (w, h) = level_size(level);
+            w <= max_size && h <= max_size
+        })
+        .unwrap_or(levels - 1);
+    let entry = LEVEL_INDEX_OFFSET + level as usize * LEVEL_INDEX_ENTRY;
+    let (offset, length) = (u64_at(entry)?, u64_at(entry + 8)?);
+    let (w, h) = level_size(level);
+    if length < w as usize * h as usize * 4 {
+        return Err(Ktx2Error::Truncated);
+    }
+    let data = bytes
+        .get(offset..offset + length)
+        .ok_or(Ktx2Error::Truncated)?;
+    Ok(Texture::new(
+        Vec2::new(w as f32, h as f32),
+        data[..w as usize * h as usize * 4].to_vec(),
+        format,
+    ))
+}
+
+/// Loads `.ktx2` files as textures with [`read_ktx2`].
+pub struct Ktx2Loader {
+    pub max_size: u32,
+}
+
+impl AssetLoader for Ktx2Loader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let texture = read_ktx2(bytes, self.max_size)?;
+            load_context.set_default_asset(LoadedAsset::new(texture));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ktx2"]
+    }
+}
+
+/// Lets the asset server load `.ktx2` textures, at most `max_size` texels on a side.
+pub struct Ktx2Plugin {
+    pub max_size: u32,
+}
+
+impl Default for Ktx2Plugin {
+    fn default() -> Self {
+        Ktx2Plugin { max_size: 8192 }
+    }
+}
+
+impl Plugin for Ktx2Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_asset_loader(Ktx2Loader {
+            max_size: self.max_size,
+        });
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use ab_glyph::{Font as _, PxScale, ScaleFont};
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+const ELLIPSIS: char = '…';
+/// Advance of an average character relative to the font size, for
+/// [`AverageAdvance::for_font_size`].
+const AVERAGE_ADVANCE: f32 = 0.55;
+
+/// How wide a piece of text is set on one line, in the units labels are drawn in.
+pub trait TextMeasure: Send + Sync + 'static {
+    fn width(&self, text: &str) -> f32;
+}
+
+/// Every character taken as the same width; a stand-in until a font is loaded, and
+/// for tools that lay targets out without one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AverageAdvance(pub f32);
+
+impl AverageAdvance {
+    pub fn for_font_size(font_size: f32) -> Self {
+        AverageAdvance(font_size * AVERAGE_ADVANCE)
+    }
+}
+
+impl TextMeasure for AverageAdvance {
+    fn width(&self, text: &str) -> f32 {
+        text.chars().count() as f32 * self.0
+    }
+}
+
+/// Advances of a font's Latin characters at one size, read off the font once it has
+/// loaded. Other characters count as the average of those.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphAdvances {
+    advances: HashMap<char, f32>,
+    fallback: f32,
+}
+
+impl GlyphAdvances {
+    pub fn new(font: &Font, font_size: f32) -> Self {
+        let font = font.font.as_scaled(PxScale::from(font_size));
+        let advances = (' '..='~')
+            .chain('\u{a0}'..='\u{ff}')
+            .chain(std::iter::once(ELLIPSIS))
+            .map(|c| (c, font.h_advance(font.glyph_id(c))))
+            .collect::<HashMap<_, _>>();
+        let fallback = advances.values().sum::<f32>() / advances.len() as f32;
+        GlyphAdvances { advances, fallback }
+    }
+}
+
+impl TextMeasure for GlyphAdvances {
+    fn width(&self, text: &str) -> f32 {
+        text.chars()
+            .map(|c| self.advances.get(&c).copied().unwrap_or(self.fallback))
+            .sum()
+    }
+}
+
+/// What to do with a label wider than [`LabelFit::max_width`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LabelOverflow {
+    /// Cut it short with an ellipsis.
+    Truncate,
+    /// Break it onto a second line between words, then cut that short if it is still
+    /// too long.
+    #[default]
+    Wrap,
+}
+
+/// Keeps labels within a width so they don't run into their neighbours. The display
+/// reserves the widest fitted label's width around every marker, see
+/// [`LayoutConfig::label_width`](crate::layout::LayoutConfig::label_width).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelFit {
+    /// In the units labels are drawn in.
+    pub max_width: f32,
+    pub overflow: LabelOverflow,
+}
+
+impl Default for LabelFit {
+    fn default() -> Self {
+        LabelFit {
+            max_width: 90.0,
+            overflow: LabelOverflow::Wrap,
+        }
+    }
+}
+
+/// A label laid out by [`LabelFit::fit`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FittedLabel {
+    /// The lines to draw, separated by `\n`.
+    pub text: String,
+    /// Of the widest line.
+    pub width: f32,
+    pub lines: usize,
+}
+
+impl LabelFit {
+    pub fn fit(&self, text: &str, measure: &dyn TextMeasure) -> FittedLabel {
+        let width = measure.width(text);
+        if width <= self.max_width {
+            return FittedLabel {
+                text: text.to_string(),
+                width,
+                lines: 1,
+            };
+        }
+        let lines = match self.overflow {
+            LabelOverflow::Truncate => vec![self.truncate(text, measure)],
+            LabelOverflow::Wrap => {
+                let (first, rest) = self.first_line(text, measure);
+                let rest = rest.trim_start();
+                if rest.is_empty() {
+                    vec![first.to_string()]
+                } else if measure.width(rest) <= self.max_width {
+                    vec![first.to_string(), rest.to_string()]
+                } else {
+                    vec![first.to_string(), self.truncate(rest, measure)]
+                }
+            }
+        };
+        FittedLabel {
+            width: lines
+                .iter()
+                .map(|line| measure.width(line))
+                .fold(0.0, f32::max),
+            lines: lines.len(),
+            text: lines.join("\n"),
+        }
+    }
+
+    /// The most whole words of `text` that fit on a line, or as many characters as fit
+    /// if the first word alone does not, and what is left over.
+    fn first_line<'t>(&self, text: &'t str, measure: &dyn TextMeasure) -> (&'t str, &'t str) {
+        let breaks = text
+            .char_indices()
+            .filter(|(_, c)| c.is_whitespace())
+            .map(|(at, _)| at);
+        let mut fitting = None;
+        for at in breaks {
+            let line = text[..at].trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if measure.width(line) > self.max_width {
+                break;
+            }
+            fitting = Some(at);
+        }
+        let at = fitting.unwrap_or_else(|| {
+            let mut at = text
+                .char_indices()
+                .map(|(at, _)| at)
+                .skip(1)
+                .take_while(|&at| measure.width(&text[..at]) <= self.max_width)
+                .last()
+                .unwrap_or(0);
+            // Always make progress, even if no character fits.
+            if at == 0 {
+                at = text.chars().next().map_or(0, char::len_utf8);
+            }
+            at
+        });
+        (text[..at].trim_end(), &text[at..])
+    }
+
+    /// The longest start of `text` that fits with an ellipsis after it.
+    fn truncate(&self, text: &str, measure: &dyn TextMeasure) -> String {
+        let ellipsis = measure.width(&ELLIPSIS.to_string());
+        let end = text
+            .char_indices()
+            .map(|(at, _)| at)
+            .chain(std::iter::once(text.len()))
+            .take_while(|&at| measure.width(text[..at].trim_end()) + ellipsis <= self.max_width)
+            .last()
+            .unwrap_or(0);
+        format!("{}{}", text[..end].trim_end(), ELLIPSIS)
+    }
+}
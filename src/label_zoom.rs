@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+use bevy::render::render_graph::base::MainPass;
+
+/// Re-rasterizes world-space text as the camera zooms. Glyphs are rasterized at the
+/// font size, in world units, so without this labels are drawn blown up and blurry
+/// when zoomed in and shrink to an unreadable smear when zoomed out. With it,
+/// [`LabelZoomPlugin`] scales each text's font size by the zoom, rounded to a power of
+/// `step`, so labels keep roughly their design size on screen and are only
+/// re-rasterized when a breakpoint is crossed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelZoom {
+    /// Ratio between consecutive breakpoints, above 1.
+    pub step: f32,
+    /// Smallest and largest factor applied to the design font size.
+    pub min_factor: f32,
+    pub max_factor: f32,
+}
+
+impl Default for LabelZoom {
+    fn default() -> Self {
+        LabelZoom {
+            step: 2.0,
+            min_factor: 1.0 / 8.0,
+            max_factor: 16.0,
+        }
+    }
+}
+
+impl LabelZoom {
+    /// The factor to apply to font sizes at camera scale `scale` (world units per
+    /// pixel).
+    pub fn factor(&self, scale: f32) -> f32 {
+        if scale <= 0.0 || self.step <= 1.0 {
+            return 1.0;
+        }
+        let exponent = (scale.ln() / self.step.ln()).round();
+        self.step
+            .powf(exponent)
+            .max(self.min_factor)
+            .min(self.max_factor)
+    }
+}
+
+/// The font size a text was spawned with, recorded by [`LabelZoomPlugin`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaseFontSize(pub f32);
+
+/// Applies [`LabelZoom`] to every text drawn in the main pass: labels, the range ring
+/// readout and tooltips.
+pub struct LabelZoomPlugin;
+
+impl Plugin for LabelZoomPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<LabelZoom>() {
+            app.init_resource::<LabelZoom>();
+        }
+        app.add_system(label_zoom_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn label_zoom_system(
+    mut commands: Commands,
+    mut applied: Local<Option<f32>>,
+    zoom: Res<LabelZoom>,
+    cameras: Query<(&Camera, &Transform)>,
+    mut texts: Query<With<MainPass, (Entity, Option<&BaseFontSize>, Mut<Text>)>>,
+) {
+    let scale = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, transform)) => transform.scale.x(),
+        None => return,
+    };
+    let factor = zoom.factor(scale);
+    let rescale = *applied != Some(factor);
+    *applied = Some(factor);
+    for (entity, base, mut text) in texts.iter_mut() {
+        match base {
+            Some(base) if rescale => text.style.font_size = base.0 * factor,
+            Some(_) => {}
+            None => {
+                commands.insert_one(entity, BaseFontSize(text.style.font_size));
+                text.style.font_size *= factor;
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+
+/// Visibility group of a spawned display entity. Every entity the display spawns
+/// carries exactly one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Layer {
+    Rings,
+    Grid,
+    Markers,
+    Leaders,
+    Labels,
+    Trails,
+    Zones,
+    Overlays,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 8] = [
+        Layer::Rings,
+        Layer::Grid,
+        Layer::Markers,
+        Layer::Leaders,
+        Layer::Labels,
+        Layer::Trails,
+        Layer::Zones,
+        Layer::Overlays,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layer::Rings => "rings",
+            Layer::Grid => "grid",
+            Layer::Markers => "markers",
+            Layer::Leaders => "leaders",
+            Layer::Labels => "labels",
+            Layer::Trails => "trails",
+            Layer::Zones => "zones",
+            Layer::Overlays => "overlays",
+        }
+    }
+
+    /// The action toggling the layer, bound to F1 through F8 in the order of
+    /// [`Layer::ALL`] unless rebound.
+    pub fn toggle_action(self) -> Action {
+        match self {
+            Layer::Rings => Action::ToggleRings,
+            Layer::Grid => Action::ToggleGrid,
+            Layer::Markers => Action::ToggleMarkers,
+            Layer::Leaders => Action::ToggleLeaders,
+            Layer::Labels => Action::ToggleLabels,
+            Layer::Trails => Action::ToggleTrails,
+            Layer::Zones => Action::ToggleZones,
+            Layer::Overlays => Action::ToggleOverlays,
+        }
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Layer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Layer::ALL
+            .iter()
+            .copied()
+            .find(|layer| layer.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown layer {:?}", s))
+    }
+}
+
+/// Hides an entity even when its layer is shown, e.g. a marker folded into a cluster
+/// by [`RingLodPlugin`](crate::lod::RingLodPlugin).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Collapsed(pub bool);
+
+/// Which layers are shown. All layers start visible.
+#[derive(Debug, Default, Clone)]
+pub struct LayerVisibility {
+    hidden: HashSet<Layer>,
+}
+
+impl LayerVisibility {
+    pub fn is_visible(&self, layer: Layer) -> bool {
+        !self.hidden.contains(&layer)
+    }
+
+    pub fn set_visible(&mut self, layer: Layer, visible: bool) {
+        if visible {
+            self.hidden.remove(&layer);
+        } else {
+            self.hidden.insert(layer);
+        }
+    }
+
+    pub fn toggle(&mut self, layer: Layer) {
+        let visible = self.is_visible(layer);
+        self.set_visible(layer, !visible);
+    }
+
+    pub fn show_all(&mut self) {
+        self.hidden.clear();
+    }
+}
+
+/// Applies [`LayerVisibility`] and [`Collapsed`] to every entity with a [`Layer`] and toggles layers with
+/// their [`Layer::toggle_action`].
+pub struct LayersPlugin;
+
+impl Plugin for LayersPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        app.init_resource::<LayerVisibility>()
+            .add_system(hotkey_system.system())
+            .add_system(visibility_system.system());
+    }
+}
+
+fn hotkey_system(actions: Res<ActionState>, mut visibility: ResMut<LayerVisibility>) {
+    for layer in Layer::ALL.iter() {
+        if actions.just_pressed(layer.toggle_action()) {
+            visibility.toggle(*layer);
+        }
+    }
+}
+
+fn visibility_system(
+    visibility: Res<LayerVisibility>,
+    mut query: Query<(&Layer, Option<&Collapsed>, Mut<Draw>)>,
+) {
+    for (layer, collapsed, mut draw) in query.iter_mut() {
+        let visible = visibility.is_visible(*layer) && !collapsed.is_some_and(|c| c.0);
+        if draw.is_visible != visible {
+            draw.is_visible = visible;
+        }
+    }
+}
@@ -0,0 +1,2139 @@
+use std::collections::{BTreeMap, HashMap};
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+use std::fmt;
+
+use clap::ValueEnum;
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::scale::RadialScale;
+use crate::smoothing::SmoothingFilter;
+use crate::target::Target;
+
+/// Targets placed on one ring, keyed by azimuth.
+pub type Ring = AngularOccupancy<Target>;
+
+/// How much wider than the marker the angular gap between neighbours on a ring is.
+pub const DEFAULT_SCATTER: f32 = 1.2;
+
+/// The golden angle, `2π / φ²`, which never brings a multiple of itself back round to
+/// the same place.
+pub const GOLDEN_ANGLE: f32 = 2.399_963;
+
+/// Which targets get first claim on the inner rings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlacementMode {
+    /// Nearest first; a target never displaces one already placed.
+    #[default]
+    Nearest,
+    /// Highest `Target::priority` first, nearest first among equals. A target that
+    /// collides only with lower-priority ones takes their place and evicts them to the
+    /// next ring out, where they may in turn evict others.
+    Priority,
+}
+
+/// Which ring a target goes on when it fits on several, under [`PlacementMode::Nearest`]
+/// with [`GreedyRings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TieBreak {
+    /// The innermost ring with room.
+    #[default]
+    Innermost,
+    /// The ring closest to where [`LayoutConfig::scale`] puts the target's distance; a
+    /// new outermost ring counts as having room.
+    TrueDistance,
+    /// The ring with room that holds the fewest markers, innermost among equals.
+    BalanceLoad,
+}
+
+/// Widens the gap below rings that would otherwise be crowded, trading radial fidelity
+/// for readability. A ring whose markers would fill more than `max_occupancy` of its
+/// circumference is pushed out by up to `max_growth` times the usual spacing, which
+/// gives it room for more markers.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveSpacing {
+    pub max_occupancy: f32,
+    pub max_growth: f32,
+}
+
+impl Default for AdaptiveSpacing {
+    fn default() -> Self {
+        AdaptiveSpacing {
+            max_occupancy: 0.5,
+            max_growth: 3.0,
+        }
+    }
+}
+
+/// Tunable parameters of the ring arrangement. Fields missing when deserializing take
+/// their [`Default`] value.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    /// Side length of a marker square, in world units.
+    pub poi_width: f32,
+    /// Distance between consecutive rings; ring `n` has radius `(n + 1) * ring_spacing`.
+    pub ring_spacing: f32,
+    /// Multiplier on the angle a marker subtends, so neighbours keep some air between
+    /// them. `1.0` lets squares touch corner to corner.
+    pub scatter: f32,
+    /// Which targets get the inner rings when they collide.
+    pub mode: PlacementMode,
+    /// Which ring a target takes when more than one has room.
+    pub tie_break: TieBreak,
+    /// Spacing that adapts to how crowded each ring is; `None` keeps it uniform.
+    pub adaptive: Option<AdaptiveSpacing>,
+    /// Rotate each ring's markers as drawn by a golden-angle fraction of the ring's
+    /// minimum angle, so markers on consecutive rings don't line up radially and hide
+    /// each other's leader lines. See [`RingLayout::ring_offset`].
+    pub stagger: bool,
+    /// How distance maps onto the rings, for [`TieBreak::TrueDistance`] and for
+    /// reading distances off the rings.
+    pub scale: RadialScale,
+    /// Which algorithm assigns the rings.
+    pub backend: LayoutBackend,
+    /// How many times larger than `poi_width` markers are drawn, in world units. The
+    /// spacing on the rings follows it; see
+    /// [`ConstantSizePlugin`](crate::constant_size::ConstantSizePlugin).
+    pub marker_scale: f32,
+    /// Bend leader lines around the markers of inner rings instead of drawing them
+    /// straight through; see [`LeaderRouter`].
+    pub leader_routing: bool,
+    /// Width reserved for each marker's label, in world units: neighbours on a ring are
+    /// kept far enough apart for the larger of it and [`LayoutConfig::marker_width`].
+    /// The display sets it from the widest label under its
+    /// [`LabelFit`](crate::label_fit::LabelFit); 0 reserves nothing beyond the marker.
+    pub label_width: f32,
+    /// Draw all leader lines as a few shared meshes, one per line style, instead of an
+    /// entity each; see [`LeaderBatch`](crate::batch::LeaderBatch). Meant for
+    /// thousands of targets, at the cost of per-target line effects like selection.
+    pub batch_leaders: bool,
+    /// Smooth each target's incoming azimuth and distance reports before they are laid
+    /// out, so markers from jittery sensors hold still; `None` takes reports as they
+    /// are. See [`Smoother`](crate::smoothing::Smoother).
+    pub smoothing: Option<SmoothingFilter>,
+}
+
+impl LayoutConfig {
+    /// Rings two marker widths apart with the default scatter.
+    pub fn new(poi_width: f32) -> Self {
+        LayoutConfig {
+            poi_width,
+            ring_spacing: poi_width * 2.0,
+            scatter: DEFAULT_SCATTER,
+            mode: PlacementMode::Nearest,
+            tie_break: TieBreak::Innermost,
+            adaptive: None,
+            stagger: false,
+            scale: RadialScale::Linear,
+            backend: LayoutBackend::Greedy,
+            marker_scale: 1.0,
+            leader_routing: false,
+            label_width: 0.0,
+            batch_leaders: false,
+            smoothing: None,
+        }
+    }
+
+    /// Side length of a marker as drawn, in world units.
+    pub fn marker_width(&self) -> f32 {
+        self.poi_width * self.marker_scale
+    }
+
+    /// Width a marker and its label take up along the ring, in world units.
+    pub fn footprint(&self) -> f32 {
+        self.marker_width().max(self.label_width)
+    }
+
+    pub fn ring_radius(&self, ring_ord: usize) -> f32 {
+        (ring_ord + 1) as f32 * self.ring_spacing
+    }
+
+    /// The distance ring `ring_ord` stands for under [`LayoutConfig::scale`].
+    pub fn ring_distance(&self, ring_ord: usize) -> f32 {
+        self.scale
+            .distance((ring_ord + 1) as f32, self.ring_spacing)
+    }
+
+    /// Where `dist` falls across the rings under [`LayoutConfig::scale`], in ring units:
+    /// ring `n` is at `n + 1`.
+    pub fn ring_position(&self, dist: f32) -> f32 {
+        self.scale.position(dist, self.ring_spacing)
+    }
+
+    /// The true azimuth and distance of a point drawn at `(x, y)` from its origin,
+    /// reading its radius back through [`LayoutConfig::scale`] as if it were on the
+    /// uniformly spaced rings.
+    pub fn polar_at(&self, x: f32, y: f32) -> (f32, f32) {
+        let radius = x.hypot(y);
+        if radius <= 0.0 || self.ring_spacing <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let azimuth = y.atan2(x).rem_euclid(PI * 2.0);
+        let dist = self
+            .scale
+            .distance(radius / self.ring_spacing, self.ring_spacing);
+        (azimuth, dist)
+    }
+
+    /// Smallest azimuth difference two markers on ring `ring_ord` may have.
+    pub fn min_angle(&self, ring_ord: usize) -> f32 {
+        separation_angle(self.footprint(), self.ring_radius(ring_ord), self.scatter)
+    }
+
+    /// Checks that `poi_width`, `ring_spacing` and `scatter` are finite and positive;
+    /// with any of them 0 or less, markers would not keep each other apart. Also checks
+    /// the gains of `smoothing`.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("poi_width", self.poi_width),
+            ("ring_spacing", self.ring_spacing),
+            ("scatter", self.scatter),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(format!("{} must be a positive number, got {}", name, value));
+            }
+        }
+        match &self.smoothing {
+            Some(filter) => filter.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig::new(30.0)
+    }
+}
+
+/// The result of decluttering a set of targets onto concentric rings.
+///
+/// Targets are taken nearest first, or by priority under [`PlacementMode::Priority`].
+/// Each goes on the innermost ring where it keeps at least [`RingLayout::min_angle`]
+/// from both neighbours, including across the 0/2π seam, and keeps its own azimuth;
+/// only its ring, and so its drawn radius, changes. That is the [`GreedyRings`]
+/// strategy; [`ForceDirected`] also shifts azimuths, see [`LayoutBackend`].
+#[derive(Debug, Clone)]
+pub struct RingLayout {
+    pub config: LayoutConfig,
+    pub rings: Vec<Ring>,
+    /// Radius of each ring when [`AdaptiveSpacing`] moved them; rings past the end
+    /// continue at the uniform spacing. Empty for uniform spacing.
+    pub radii: Vec<f32>,
+    slots: SlotIndex,
+}
+
+/// Where a single [`RingLayout::insert_changes`] or [`RingLayout::remove_changes`] moved
+/// one target: the ring it was on before and the ring it is on after, `None` for off
+/// the layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotChange {
+    pub id: i32,
+    pub from: Option<usize>,
+    pub to: Option<usize>,
+}
+
+/// The ring and azimuth key of each target as the layout last placed it, so a target is
+/// found without going through every ring. `rings` changed directly leave it stale, so
+/// every entry is checked against the ring before it is trusted.
+#[derive(Debug, Clone, Default)]
+struct SlotIndex(HashMap<i32, (usize, f32)>);
+
+impl SlotIndex {
+    fn of(rings: &[Ring]) -> Self {
+        let mut slots = HashMap::new();
+        for (ring, targets) in rings.iter().enumerate() {
+            for (azimuth, target) in targets.iter() {
+                slots.insert(target.id, (ring, azimuth));
+            }
+        }
+        SlotIndex(slots)
+    }
+}
+
+impl RingLayout {
+    /// Places `targets` (already sorted by distance) with the default spacing and
+    /// scatter for `poi_width`.
+    pub fn arrange(targets: &[Target], poi_width: f32) -> Self {
+        RingLayout::with_config(targets, LayoutConfig::new(poi_width))
+    }
+
+    /// Places `targets` (already sorted by distance) on rings, innermost first. With
+    /// [`AdaptiveSpacing`] a first, uniform arrangement measures how crowded each ring
+    /// is, and the targets are then placed again on rings spaced to match.
+    pub fn with_config(targets: &[Target], config: LayoutConfig) -> Self {
+        let backend = config.backend;
+        RingLayout::with_strategy(targets, config, backend.strategy())
+    }
+
+    /// Like [`RingLayout::with_config`], with `strategy` in place of `config.backend`.
+    pub fn with_strategy(
+        targets: &[Target],
+        config: LayoutConfig,
+        strategy: &dyn LayoutStrategy,
+    ) -> Self {
+        let rings = strategy.arrange(targets, &RingGeometry::new(&config, &[]));
+        let adaptive = match config.adaptive {
+            Some(adaptive) => adaptive,
+            None => {
+                return RingLayout::from_rings(config, rings, Vec::new());
+            }
+        };
+        let mut radii = Vec::with_capacity(rings.len());
+        let mut radius = 0.0;
+        for (ring_ord, ring) in rings.iter().enumerate() {
+            let occupancy = ring.len() as f32 * config.min_angle(ring_ord) / (PI * 2.0);
+            let growth = (occupancy / adaptive.max_occupancy).clamp(1.0, adaptive.max_growth);
+            radius += config.ring_spacing * growth;
+            radii.push(radius);
+        }
+        let rings = strategy.arrange(targets, &RingGeometry::new(&config, &radii));
+        RingLayout::from_rings(config, rings, radii)
+    }
+
+    /// A layout of rings arranged elsewhere, e.g. restored from a snapshot.
+    pub fn from_rings(config: LayoutConfig, rings: Vec<Ring>, radii: Vec<f32>) -> Self {
+        let slots = SlotIndex::of(&rings);
+        RingLayout {
+            config,
+            rings,
+            radii,
+            slots,
+        }
+    }
+
+    /// Catches up with changes made to `rings` directly, which [`RingLayout::find`] and
+    /// [`RingLayout::remove`] would otherwise have to search every ring for.
+    pub fn reindex(&mut self) {
+        self.slots = SlotIndex::of(&self.rings);
+    }
+
+    pub fn poi_width(&self) -> f32 {
+        self.config.poi_width
+    }
+
+    pub fn ring_radius(&self, ring_ord: usize) -> f32 {
+        RingGeometry::new(&self.config, &self.radii).radius(ring_ord)
+    }
+
+    /// The distance ring `ring_ord` stands for under [`LayoutConfig::scale`].
+    pub fn ring_distance(&self, ring_ord: usize) -> f32 {
+        self.config.ring_distance(ring_ord)
+    }
+
+    /// Rotation added to the azimuths of ring `ring_ord` as drawn: with
+    /// [`LayoutConfig::stagger`], up to half the ring's minimum angle either way, and
+    /// none for the first ring. The whole ring turns, so its spacing is unchanged.
+    pub fn ring_offset(&self, ring_ord: usize) -> f32 {
+        if !self.config.stagger {
+            return 0.0;
+        }
+        let turn = (ring_ord as f32 * GOLDEN_ANGLE / (PI * 2.0) + 0.5).fract() - 0.5;
+        turn * self.min_angle(ring_ord)
+    }
+
+    /// Smallest azimuth difference two markers on ring `ring_ord` may have.
+    pub fn min_angle(&self, ring_ord: usize) -> f32 {
+        RingGeometry::new(&self.config, &self.radii).min_angle(ring_ord)
+    }
+
+    /// Places one more target on the innermost ring where it fits and returns its ring.
+    /// Other placements are untouched except for evictions under
+    /// [`PlacementMode::Priority`]. Unlike [`RingLayout::with_config`] this does not take
+    /// distance order into account, so a long series of updates can drift from what a
+    /// fresh layout would produce.
+    pub fn insert(&mut self, target: Target) -> usize {
+        self.insert_evicting(target).0
+    }
+
+    /// Like [`RingLayout::insert`], also returning the ids of the targets evicted to make
+    /// room, which have moved to outer rings.
+    pub fn insert_evicting(&mut self, target: Target) -> (usize, Vec<i32>) {
+        let (ring, evicted) = self.place(target);
+        (ring, evicted.into_iter().map(|e| e.id).collect())
+    }
+
+    /// Like [`RingLayout::insert`], returning every target whose ring changed: the new
+    /// one first, from `None`, then those evicted to make room, each once with the ring
+    /// it ended up on. A target already on the layout under the same id is taken off
+    /// first, and its change is reported as a move. Each placement and eviction is a
+    /// lookup by azimuth, so this takes `O(log n)` per ring tried.
+    pub fn insert_changes(&mut self, target: Target) -> Vec<SlotChange> {
+        let id = target.id;
+        let from = self.remove(id).map(|(ring, _)| ring);
+        let (ring, evicted) = self.place(target);
+        let mut changes = vec![SlotChange {
+            id,
+            from,
+            to: Some(ring),
+        }];
+        for eviction in evicted {
+            match changes.iter_mut().find(|c| c.id == eviction.id) {
+                // Evicted again further out; it keeps the ring it started from.
+                Some(change) => change.to = Some(eviction.to),
+                None => changes.push(SlotChange {
+                    id: eviction.id,
+                    from: Some(eviction.from),
+                    to: Some(eviction.to),
+                }),
+            }
+        }
+        changes
+    }
+
+    /// Places `target` and brings the slot index up to date with it and everything it
+    /// evicted.
+    fn place(&mut self, target: Target) -> (usize, Vec<Eviction>) {
+        let mut evicted = Vec::new();
+        let (id, key) = (target.id, normalize(target.azimuth));
+        let geometry = RingGeometry::new(&self.config, &self.radii);
+        let ring = match self.config.mode {
+            PlacementMode::Nearest => place_with_tie_break(&mut self.rings, target, &geometry),
+            PlacementMode::Priority => {
+                place_by_priority(&mut self.rings, target, &geometry, 0, &mut evicted)
+            }
+        };
+        self.slots.0.insert(id, (ring, key));
+        for eviction in &evicted {
+            self.slots
+                .0
+                .insert(eviction.id, (eviction.to, eviction.key));
+        }
+        (ring, evicted)
+    }
+
+    /// Takes target `id` out of the layout, returning its ring and the target. Empty
+    /// rings left at the outside are dropped.
+    pub fn remove(&mut self, id: i32) -> Option<(usize, Target)> {
+        let (ring, azimuth) = self.find(id).map(|p| (p.ring, p.azimuth))?;
+        let target = self.rings[ring].remove(azimuth)?;
+        self.slots.0.remove(&id);
+        while self.rings.last().is_some_and(|r| r.is_empty()) {
+            self.rings.pop();
+        }
+        Some((ring, target))
+    }
+
+    /// Like [`RingLayout::remove`], returning the change as a [`SlotChange`]. Removing
+    /// never moves other targets, so there is at most one.
+    pub fn remove_changes(&mut self, id: i32) -> Option<SlotChange> {
+        self.remove(id).map(|(ring, _)| SlotChange {
+            id,
+            from: Some(ring),
+            to: None,
+        })
+    }
+
+    /// Where target `id` is placed.
+    pub fn find(&self, id: i32) -> Option<Placement<'_>> {
+        let indexed = self.slots.0.get(&id).and_then(|&(ring, azimuth)| {
+            let target = self.rings.get(ring)?.get(azimuth)?;
+            (target.id == id).then(|| Placement {
+                ring,
+                azimuth,
+                offset: self.ring_offset(ring),
+                radius: self.ring_radius(ring),
+                target,
+            })
+        });
+        indexed.or_else(|| self.placements().find(|p| p.target.id == id))
+    }
+
+    /// Every placed target, ring by ring and by increasing azimuth within a ring.
+    pub fn placements(&self) -> impl Iterator<Item = Placement<'_>> {
+        self.rings
+            .iter()
+            .enumerate()
+            .flat_map(move |(ring, targets)| {
+                let radius = self.ring_radius(ring);
+                let offset = self.ring_offset(ring);
+                targets.iter().map(move |(azimuth, target)| Placement {
+                    ring,
+                    azimuth,
+                    offset,
+                    radius,
+                    target,
+                })
+            })
+    }
+
+    /// All placements in clockwise order as drawn (decreasing
+    /// [`Placement::drawn_azimuth`]), starting with the first one at or clockwise of
+    /// `azimuth` and wrapping around past 0. Placements drawn at the same angle come
+    /// innermost ring first.
+    pub fn iter_clockwise_from(&self, azimuth: f32) -> impl Iterator<Item = Placement<'_>> {
+        let mut placements = self.placements().collect::<Vec<_>>();
+        placements.sort_by(|a, b| {
+            let key = |p: &Placement| (OrderedFloat(p.clockwise_from(azimuth)), p.ring);
+            key(a).cmp(&key(b))
+        });
+        placements.into_iter()
+    }
+
+    /// What changed from this layout to `newer`: targets only in `newer`, targets only in
+    /// this one, and targets placed on a different ring, azimuth or radius. Each list is
+    /// sorted by id.
+    pub fn diff(&self, newer: &RingLayout) -> LayoutDiff {
+        let slots = |layout: &RingLayout| {
+            layout
+                .placements()
+                .map(|p| (p.target.id, SlotPosition::of(&p)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let (old, new) = (slots(self), slots(newer));
+        let mut diff = LayoutDiff::default();
+        for (&id, &from) in &old {
+            match new.get(&id) {
+                None => diff.removed.push((id, from)),
+                Some(&to) if to != from => diff.moved.push(MovedPlacement { id, from, to }),
+                Some(_) => {}
+            }
+        }
+        diff.added = new
+            .into_iter()
+            .filter(|(id, _)| !old.contains_key(id))
+            .collect();
+        diff
+    }
+
+    /// The `k` placements closest to the layout-space point at `azimuth` and `range`
+    /// (a radius in the same units as the rings), nearest first.
+    pub fn nearest(&self, azimuth: f32, range: f32, k: usize) -> Vec<Placement<'_>> {
+        let mut placements = self
+            .placements()
+            .map(|p| (OrderedFloat(p.distance_to(azimuth, range)), p))
+            .collect::<Vec<_>>();
+        placements.sort_by_key(|(d, p)| (*d, p.ring));
+        placements.into_iter().take(k).map(|(_, p)| p).collect()
+    }
+
+    /// Leader lines for every placement, bent around inner markers by [`LeaderRouter`].
+    pub fn route_leaders(&self) -> Vec<LeaderRoute> {
+        LeaderRouter::new(self).routes()
+    }
+
+    /// How full each ring is and how many targets it turned away.
+    pub fn diagnostics(&self) -> LayoutDiagnostics {
+        let mut rings = (0..self.rings.len())
+            .map(|ring_ord| RingDiagnostics {
+                radius: self.ring_radius(ring_ord),
+                min_angle: self.min_angle(ring_ord),
+                targets: self.rings[ring_ord].len(),
+                occupancy: self.rings[ring_ord].len() as f32 * self.min_angle(ring_ord)
+                    / (PI * 2.0),
+                rejected: 0,
+            })
+            .collect::<Vec<_>>();
+        for placement in self.placements() {
+            for (ring_ord, ring) in self.rings[..placement.ring].iter().enumerate() {
+                if !ring.clears(placement.azimuth, rings[ring_ord].min_angle) {
+                    rings[ring_ord].rejected += 1;
+                }
+            }
+        }
+        LayoutDiagnostics { rings }
+    }
+}
+
+/// Where a target ended up in a [`RingLayout`].
+#[derive(Debug, Clone, Copy)]
+pub struct Placement<'a> {
+    pub ring: usize,
+    pub azimuth: f32,
+    /// [`RingLayout::ring_offset`] of the ring.
+    pub offset: f32,
+    pub radius: f32,
+    pub target: &'a Target,
+}
+
+impl Placement<'_> {
+    /// Where the marker is drawn, `azimuth` turned by the ring's `offset`.
+    pub fn drawn_azimuth(&self) -> f32 {
+        (self.azimuth + self.offset).rem_euclid(PI * 2.0)
+    }
+
+    /// Angle swept clockwise from `azimuth` to this placement as drawn, in `[0, 2π)`.
+    pub fn clockwise_from(&self, azimuth: f32) -> f32 {
+        (azimuth - self.drawn_azimuth()).rem_euclid(PI * 2.0)
+    }
+
+    /// Straight-line distance in layout space to the point at `azimuth` and `range`,
+    /// which accounts for both the angular and the radial separation.
+    pub fn distance_to(&self, azimuth: f32, range: f32) -> f32 {
+        let d2 = self.radius * self.radius + range * range
+            - 2.0 * self.radius * range * (self.azimuth - azimuth).cos();
+        d2.max(0.0).sqrt()
+    }
+}
+
+/// Where a placement sits, without the target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotPosition {
+    pub ring: usize,
+    pub azimuth: f32,
+    pub radius: f32,
+}
+
+impl SlotPosition {
+    pub fn of(placement: &Placement) -> Self {
+        SlotPosition {
+            ring: placement.ring,
+            azimuth: placement.azimuth,
+            radius: placement.radius,
+        }
+    }
+}
+
+/// A target placed differently in two layouts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovedPlacement {
+    pub id: i32,
+    pub from: SlotPosition,
+    pub to: SlotPosition,
+}
+
+/// The changes between two layouts, from [`RingLayout::diff`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayoutDiff {
+    pub added: Vec<(i32, SlotPosition)>,
+    pub removed: Vec<(i32, SlotPosition)>,
+    pub moved: Vec<MovedPlacement>,
+}
+
+impl LayoutDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// Ring-by-ring numbers on a layout, from [`RingLayout::diagnostics`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayoutDiagnostics {
+    pub rings: Vec<RingDiagnostics>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RingDiagnostics {
+    pub radius: f32,
+    pub min_angle: f32,
+    pub targets: usize,
+    /// Share of the circumference the markers' minimum gaps take up; 1 is full.
+    pub occupancy: f32,
+    /// Targets placed further out that collide with a marker on this ring.
+    pub rejected: usize,
+}
+
+impl fmt::Display for LayoutDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ring  radius  min angle  targets  full  rejected")?;
+        for (ring_ord, ring) in self.rings.iter().enumerate() {
+            write!(
+                f,
+                "\n{:>4}  {:>6.1}  {:>8.2}\u{b0}  {:>7}  {:>3.0}%  {:>8}",
+                ring_ord,
+                ring.radius,
+                ring.min_angle.to_degrees(),
+                ring.targets,
+                ring.occupancy * 100.0,
+                ring.rejected
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for LayoutDiff {
+    /// A one-line summary such as `2 added, 0 removed, 1 moved`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} removed, {} moved",
+            self.added.len(),
+            self.removed.len(),
+            self.moved.len()
+        )
+    }
+}
+
+/// A leader line from the origin to a target's marker, from [`LeaderRouter`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderRoute {
+    pub id: i32,
+    /// Layout-space points the line runs through, the origin first and the marker's
+    /// center last.
+    pub points: Vec<(f32, f32)>,
+    /// Other markers the line still passes over.
+    pub crossings: usize,
+}
+
+impl LeaderRoute {
+    /// The bends between the origin and the marker; none for a straight line.
+    pub fn elbows(&self) -> &[(f32, f32)] {
+        &self.points[1..self.points.len() - 1]
+    }
+}
+
+/// Bends leader lines around the markers of inner rings. A straight line from the
+/// origin is kept unless it passes over another marker; then the router tries lines
+/// that run out grazing either side of a marker in the way and turn back towards their
+/// own marker in one of the gaps between the rings further out, with one or two
+/// elbows. The route passing over the fewest markers wins, then the one with fewer
+/// elbows, then the shortest.
+pub struct LeaderRouter<'a> {
+    layout: &'a RingLayout,
+    /// Id, ring, drawn azimuth and radius of every marker.
+    markers: Vec<(i32, usize, f32, f32)>,
+    /// How close a line may pass to a marker's center: half its diagonal.
+    clearance: f32,
+}
+
+impl<'a> LeaderRouter<'a> {
+    /// Farthest from its marker's azimuth a route may run.
+    pub const MAX_DETOUR: f32 = PI / 6.0;
+
+    pub fn new(layout: &'a RingLayout) -> Self {
+        let markers = layout
+            .placements()
+            .map(|p| (p.target.id, p.ring, p.drawn_azimuth(), p.radius))
+            .collect();
+        LeaderRouter {
+            layout,
+            markers,
+            clearance: layout.config.marker_width() * FRAC_1_SQRT_2,
+        }
+    }
+
+    /// Routes for every placement, in [`RingLayout::placements`] order.
+    pub fn routes(&self) -> Vec<LeaderRoute> {
+        self.layout.placements().map(|p| self.route(&p)).collect()
+    }
+
+    pub fn route(&self, placement: &Placement) -> LeaderRoute {
+        let (id, azimuth, radius) = (
+            placement.target.id,
+            placement.drawn_azimuth(),
+            placement.radius,
+        );
+        let end = polar(azimuth, radius);
+        // Only markers a route can come near: inside the marker and within the detour.
+        let obstacles = self
+            .markers
+            .iter()
+            .filter(|&&(other, _, marker, marker_radius)| {
+                let reach = Self::MAX_DETOUR + (self.clearance / marker_radius).min(1.0).asin();
+                other != id
+                    && marker_radius <= radius + self.clearance
+                    && angular_distance(marker, azimuth) <= reach
+            })
+            .map(|&(_, ring, marker, marker_radius)| (ring, marker, polar(marker, marker_radius)))
+            .collect::<Vec<_>>();
+        let centers = obstacles.iter().map(|o| o.2).collect::<Vec<_>>();
+        let straight = vec![(0.0, 0.0), end];
+        let mut best = (self.crossings(&centers, &straight), straight);
+        if best.0 == 0 {
+            return self.finish(id, best);
+        }
+        let cost = |crossings: usize, points: &[(f32, f32)]| {
+            (crossings, points.len(), OrderedFloat(path_length(points)))
+        };
+        let blocking = obstacles
+            .iter()
+            .filter(|o| self.passes_over(&[(0.0, 0.0), end], o.2))
+            .map(|o| (o.0, o.1))
+            .collect::<Vec<_>>();
+        for (ring, marker) in blocking {
+            // A little over the clearance, so the grazing line is not itself a crossing.
+            let graze = (self.clearance * 1.05 / self.layout.ring_radius(ring))
+                .min(1.0)
+                .asin();
+            for channel in &[marker - graze, marker + graze] {
+                if angular_distance(*channel, azimuth) > Self::MAX_DETOUR {
+                    continue;
+                }
+                // Turn in a gap past the marker in the way.
+                for gap in ring + 1..=placement.ring {
+                    let gap =
+                        (self.layout.ring_radius(gap - 1) + self.layout.ring_radius(gap)) / 2.0;
+                    let out = polar(*channel, gap);
+                    let candidates = [
+                        vec![(0.0, 0.0), out, end],
+                        vec![(0.0, 0.0), out, polar(azimuth, gap), end],
+                    ];
+                    for points in candidates {
+                        let crossings = self.crossings(&centers, &points);
+                        if cost(crossings, &points) < cost(best.0, &best.1) {
+                            best = (crossings, points);
+                        }
+                    }
+                }
+            }
+        }
+        self.finish(id, best)
+    }
+
+    fn finish(&self, id: i32, (crossings, points): (usize, Vec<(f32, f32)>)) -> LeaderRoute {
+        LeaderRoute {
+            id,
+            points,
+            crossings,
+        }
+    }
+
+    fn passes_over(&self, points: &[(f32, f32)], center: (f32, f32)) -> bool {
+        points
+            .windows(2)
+            .any(|leg| segment_distance(leg[0], leg[1], center) < self.clearance)
+    }
+
+    /// How many of the marker `centers` the polyline through `points` passes over.
+    fn crossings(&self, centers: &[(f32, f32)], points: &[(f32, f32)]) -> usize {
+        centers
+            .iter()
+            .filter(|&&center| self.passes_over(points, center))
+            .count()
+    }
+}
+
+fn polar(azimuth: f32, radius: f32) -> (f32, f32) {
+    (radius * azimuth.cos(), radius * azimuth.sin())
+}
+
+fn path_length(points: &[(f32, f32)]) -> f32 {
+    points
+        .windows(2)
+        .map(|leg| (leg[1].0 - leg[0].0).hypot(leg[1].1 - leg[0].1))
+        .sum()
+}
+
+/// Distance from `point` to the segment from `a` to `b`.
+fn segment_distance(a: (f32, f32), b: (f32, f32), point: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length2 = dx * dx + dy * dy;
+    let t = if length2 > 0.0 {
+        (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (a.0 + dx * t - point.0).hypot(a.1 + dy * t - point.1)
+}
+
+/// The ring assignment behind [`RingLayout`], for callers that only want the rings.
+/// `targets` must already be sorted by distance. Rings are spaced uniformly; only
+/// [`RingLayout::with_config`] applies [`AdaptiveSpacing`].
+pub fn arrange_targets(targets: &[Target], config: &LayoutConfig) -> Vec<Ring> {
+    config
+        .backend
+        .strategy()
+        .arrange(targets, &RingGeometry::new(config, &[]))
+}
+
+/// Ring radii and separation angles for a config and, possibly, adapted radii.
+pub struct RingGeometry<'a> {
+    config: &'a LayoutConfig,
+    radii: &'a [f32],
+}
+
+impl<'a> RingGeometry<'a> {
+    pub fn new(config: &'a LayoutConfig, radii: &'a [f32]) -> Self {
+        RingGeometry { config, radii }
+    }
+
+    pub fn config(&self) -> &LayoutConfig {
+        self.config
+    }
+
+    pub fn radius(&self, ring_ord: usize) -> f32 {
+        match self.radii.last() {
+            None => self.config.ring_radius(ring_ord),
+            Some(_) if ring_ord < self.radii.len() => self.radii[ring_ord],
+            Some(last) => {
+                last + (ring_ord + 1 - self.radii.len()) as f32 * self.config.ring_spacing
+            }
+        }
+    }
+
+    /// See [`LayoutConfig::ring_position`].
+    pub fn ring_position(&self, dist: f32) -> f32 {
+        self.config.ring_position(dist)
+    }
+
+    /// Smallest azimuth difference two markers on ring `ring_ord` may have.
+    pub fn min_angle(&self, ring_ord: usize) -> f32 {
+        separation_angle(
+            self.config.footprint(),
+            self.radius(ring_ord),
+            self.config.scatter,
+        )
+    }
+}
+
+/// An algorithm that assigns targets to rings, for [`RingLayout::with_strategy`].
+pub trait LayoutStrategy {
+    /// Places `targets` (already sorted by distance) on rings of `geometry`. Every
+    /// marker must clear its neighbours by [`RingGeometry::min_angle`]; the azimuth a
+    /// target is keyed by on its ring is where it is drawn.
+    fn arrange(&self, targets: &[Target], geometry: &RingGeometry) -> Vec<Ring>;
+}
+
+/// The built-in [`LayoutStrategy`]s, chosen by [`LayoutConfig::backend`].
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutBackend {
+    #[default]
+    Greedy,
+    ForceDirected(ForceDirected),
+}
+
+impl LayoutBackend {
+    pub fn strategy(&self) -> &dyn LayoutStrategy {
+        match self {
+            LayoutBackend::Greedy => &GreedyRings,
+            LayoutBackend::ForceDirected(force) => force,
+        }
+    }
+}
+
+/// Each target keeps its own azimuth and goes on the innermost ring where it fits, as
+/// described on [`RingLayout`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GreedyRings;
+
+impl LayoutStrategy for GreedyRings {
+    fn arrange(&self, targets: &[Target], geometry: &RingGeometry) -> Vec<Ring> {
+        let mut rings = Vec::new();
+        match geometry.config.mode {
+            PlacementMode::Nearest => targets.iter().for_each(|t| {
+                place_with_tie_break(&mut rings, t.clone(), geometry);
+            }),
+            PlacementMode::Priority => {
+                let mut evicted = Vec::new();
+                for t in priority_order(targets) {
+                    place_by_priority(&mut rings, t.clone(), geometry, 0, &mut evicted);
+                }
+            }
+        }
+        rings
+    }
+}
+
+/// Lets markers slide sideways off their true azimuth so more of them fit on the inner
+/// rings, which keeps leader lines short. Ring by ring, the targets not yet placed
+/// repel neighbours closer than the ring's minimum angle while a spring pulls each back
+/// towards its own azimuth; then those that clear the ones already on the ring are
+/// placed there and the rest move on to the next ring.
+///
+/// [`PlacementMode::Priority`] only changes which targets are placed first; nothing is
+/// evicted. [`RingLayout::insert`] still places greedily at the true azimuth.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ForceDirected {
+    /// Relaxation steps per ring.
+    pub iterations: usize,
+    /// Fraction of its offset from its true azimuth a marker gives back each step.
+    pub stiffness: f32,
+    /// Furthest a marker may slide, in multiples of the ring's minimum angle.
+    pub max_shift: f32,
+}
+
+impl Default for ForceDirected {
+    fn default() -> Self {
+        ForceDirected {
+            iterations: 50,
+            stiffness: 0.1,
+            max_shift: 1.5,
+        }
+    }
+}
+
+impl ForceDirected {
+    /// Offsets from their own azimuths that spread `targets` out on a ring.
+    fn relax(&self, targets: &[&Target], min_angle: f32) -> Vec<f32> {
+        let n = targets.len();
+        let mut shifts = vec![0.0; n];
+        if n < 2 {
+            return shifts;
+        }
+        let max_shift = self.max_shift * min_angle;
+        // Overlaps shrink geometrically and never quite reach zero, so aim a little wide.
+        let min_angle = min_angle * 1.05;
+        let mut order = (0..n).collect::<Vec<_>>();
+        // Settle with the spring for `iterations` steps, then without it so that it
+        // leaves no residual overlap behind.
+        for step in 0..self.iterations * 2 {
+            if step < self.iterations {
+                shifts.iter_mut().for_each(|s| *s *= 1.0 - self.stiffness);
+            }
+            let azimuth = |i: usize| (targets[i].azimuth + shifts[i]).rem_euclid(PI * 2.0);
+            order.sort_by_key(|&i| OrderedFloat(azimuth(i)));
+            let mut push = vec![0.0; n];
+            for k in 0..n {
+                let (a, b) = (order[k], order[(k + 1) % n]);
+                let gap = (azimuth(b) - azimuth(a)).rem_euclid(PI * 2.0);
+                if gap < min_angle {
+                    push[a] -= (min_angle - gap) / 2.0;
+                    push[b] += (min_angle - gap) / 2.0;
+                }
+            }
+            for (shift, push) in shifts.iter_mut().zip(push) {
+                *shift = (*shift + push).clamp(-max_shift, max_shift);
+            }
+        }
+        shifts
+    }
+}
+
+impl LayoutStrategy for ForceDirected {
+    fn arrange(&self, targets: &[Target], geometry: &RingGeometry) -> Vec<Ring> {
+        let mut pending = match geometry.config.mode {
+            PlacementMode::Nearest => targets.iter().collect::<Vec<_>>(),
+            PlacementMode::Priority => priority_order(targets),
+        };
+        let mut rings = Vec::new();
+        while !pending.is_empty() {
+            let min_angle = geometry.min_angle(rings.len());
+            let shifts = self.relax(&pending, min_angle);
+            let mut ring = Ring::new();
+            let mut rest = Vec::new();
+            for (t, shift) in pending.into_iter().zip(shifts) {
+                let azimuth = (t.azimuth + shift).rem_euclid(PI * 2.0);
+                if ring.clears(azimuth, min_angle) {
+                    ring.insert(azimuth, t.clone());
+                } else {
+                    rest.push(t);
+                }
+            }
+            rings.push(ring);
+            pending = rest;
+        }
+        rings
+    }
+}
+
+/// `targets` by descending priority, keeping their order among equals.
+fn priority_order(targets: &[Target]) -> Vec<&Target> {
+    let mut targets = targets.iter().collect::<Vec<_>>();
+    targets.sort_by_key(|t| std::cmp::Reverse(t.priority));
+    targets
+}
+
+/// A target [`place_by_priority`] took off ring `from` and placed again on ring `to`, at
+/// azimuth key `key`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Eviction {
+    id: i32,
+    key: f32,
+    from: usize,
+    to: usize,
+}
+
+/// Puts `t` on the innermost ring from `first_ring` on where it either clears its
+/// neighbours or outranks every one it collides with. Those are evicted, recorded in
+/// `evicted` and placed again from the next ring out. A target evicted twice is
+/// recorded twice, the later entry holding where it ended up.
+fn place_by_priority(
+    rings: &mut Vec<Ring>,
+    t: Target,
+    geometry: &RingGeometry,
+    first_ring: usize,
+    evicted: &mut Vec<Eviction>,
+) -> usize {
+    let mut ring_ord = first_ring;
+    loop {
+        if rings.len() == ring_ord {
+            rings.push(Ring::new());
+        }
+        let min_angle = geometry.min_angle(ring_ord);
+        let ring = &mut rings[ring_ord];
+        let mut colliding = ring
+            .within(t.azimuth, min_angle)
+            .into_iter()
+            .map(|(azimuth, other)| (azimuth, other.priority))
+            .collect::<Vec<_>>();
+        // `within` finds nothing when `min_angle` is 0, but an occupied key still collides.
+        if colliding.is_empty() {
+            if let Some(other) = ring.get(t.azimuth) {
+                colliding.push((normalize(t.azimuth), other.priority));
+            }
+        }
+        if colliding.iter().all(|(_, priority)| *priority < t.priority) {
+            let bumped = colliding
+                .iter()
+                .filter_map(|&(azimuth, _)| ring.remove(azimuth))
+                .collect::<Vec<_>>();
+            ring.insert(t.azimuth, t);
+            for other in bumped {
+                let index = evicted.len();
+                evicted.push(Eviction {
+                    id: other.id,
+                    key: normalize(other.azimuth),
+                    from: ring_ord,
+                    to: ring_ord + 1,
+                });
+                evicted[index].to =
+                    place_by_priority(rings, other, geometry, ring_ord + 1, evicted);
+            }
+            return ring_ord;
+        }
+        ring_ord += 1;
+    }
+}
+
+/// Values placed around a circle by azimuth, which is kept in `[0, 2π)`. Neighbours are
+/// found cyclically, so the 0/2π seam is no different from any other point on the
+/// circle: the entry after the last one is the first, and the gap between them is
+/// measured the short way round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AngularOccupancy<T> {
+    slots: BTreeMap<OrderedFloat<f32>, T>,
+}
+
+impl<T> Default for AngularOccupancy<T> {
+    fn default() -> Self {
+        AngularOccupancy {
+            slots: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> AngularOccupancy<T> {
+    pub fn new() -> Self {
+        AngularOccupancy::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Entries by increasing azimuth.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (f32, &T)> + '_ {
+        self.slots.iter().map(|(azimuth, value)| (**azimuth, value))
+    }
+
+    pub fn azimuths(&self) -> impl DoubleEndedIterator<Item = f32> + '_ {
+        self.slots.keys().map(|azimuth| **azimuth)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        self.slots.values()
+    }
+
+    /// Puts `value` at `azimuth`, returning what was there before.
+    pub fn insert(&mut self, azimuth: f32, value: T) -> Option<T> {
+        self.slots.insert(OrderedFloat(normalize(azimuth)), value)
+    }
+
+    pub fn remove(&mut self, azimuth: f32) -> Option<T> {
+        self.slots.remove(&OrderedFloat(normalize(azimuth)))
+    }
+
+    /// The value at exactly `azimuth`.
+    pub fn get(&self, azimuth: f32) -> Option<&T> {
+        self.slots.get(&OrderedFloat(normalize(azimuth)))
+    }
+
+    /// Whether an entry sits at exactly `azimuth`.
+    pub fn contains(&self, azimuth: f32) -> bool {
+        self.slots.contains_key(&OrderedFloat(normalize(azimuth)))
+    }
+
+    /// The first entry at or counterclockwise of `azimuth`, wrapping past 2π.
+    pub fn next_from(&self, azimuth: f32) -> Option<(f32, &T)> {
+        let azimuth = OrderedFloat(normalize(azimuth));
+        self.slots
+            .range(azimuth..)
+            .chain(self.slots.range(..azimuth))
+            .next()
+            .map(|(azimuth, value)| (**azimuth, value))
+    }
+
+    /// The last entry clockwise of `azimuth`, wrapping past 0.
+    pub fn prev_from(&self, azimuth: f32) -> Option<(f32, &T)> {
+        let azimuth = OrderedFloat(normalize(azimuth));
+        self.slots
+            .range(..azimuth)
+            .rev()
+            .chain(self.slots.range(azimuth..).rev())
+            .next()
+            .map(|(azimuth, value)| (**azimuth, value))
+    }
+
+    /// The entry closest to `azimuth` either way round: always one of its two cyclic
+    /// neighbours.
+    pub fn nearest(&self, azimuth: f32) -> Option<(f32, &T)> {
+        let next = self.next_from(azimuth);
+        let prev = self.prev_from(azimuth);
+        next.into_iter()
+            .chain(prev)
+            .min_by_key(|(other, _)| OrderedFloat(angular_distance(*other, azimuth)))
+    }
+
+    /// Whether an entry at `azimuth` would keep `min_angle` from every other. An entry
+    /// already at `azimuth` never clears, whatever `min_angle`, since the new one would
+    /// take its place.
+    pub fn clears(&self, azimuth: f32, min_angle: f32) -> bool {
+        // Against the key the entry would be stored under, as the gaps are.
+        let azimuth = normalize(azimuth);
+        !self.contains(azimuth)
+            && self
+                .nearest(azimuth)
+                .is_none_or(|(other, _)| angular_distance(other, azimuth) >= min_angle)
+    }
+
+    /// Entries less than `angle` from `azimuth` either way round, by increasing azimuth.
+    pub fn within(&self, azimuth: f32, angle: f32) -> Vec<(f32, &T)> {
+        if angle <= 0.0 {
+            return Vec::new();
+        } else if angle > PI {
+            return self.iter().collect();
+        }
+        let azimuth = normalize(azimuth);
+        let (from, to) = (azimuth - angle, azimuth + angle);
+        let mut entries = if from < 0.0 || to >= PI * 2.0 {
+            // The arc crosses the seam; take both ends of the map.
+            let (high, low) = (normalize(from), normalize(to));
+            self.slots
+                .range(..=OrderedFloat(low))
+                .chain(self.slots.range(OrderedFloat(high)..))
+                .collect::<Vec<_>>()
+        } else {
+            self.slots
+                .range(OrderedFloat(from)..=OrderedFloat(to))
+                .collect::<Vec<_>>()
+        };
+        // At half a turn the two ends can meet on one entry.
+        entries.dedup_by_key(|(azimuth, _)| **azimuth);
+        entries
+            .into_iter()
+            .map(|(other, value)| (**other, value))
+            .filter(|(other, _)| angular_distance(*other, azimuth) < angle)
+            .collect()
+    }
+
+    /// Each entry with the one after it counterclockwise and the angle between them;
+    /// the last is paired with the first across the seam. A lone entry is paired with
+    /// itself a full turn away.
+    pub fn gaps(&self) -> impl Iterator<Item = ((f32, &T), (f32, &T), f32)> + '_ {
+        let first = self.iter().next();
+        self.iter()
+            .zip(self.iter().skip(1).chain(first))
+            .map(|(a, b)| {
+                let gap = (b.0 - a.0).rem_euclid(PI * 2.0);
+                (a, b, if gap == 0.0 { PI * 2.0 } else { gap })
+            })
+    }
+}
+
+/// `azimuth` brought into `[0, 2π)`.
+fn normalize(azimuth: f32) -> f32 {
+    let azimuth = azimuth.rem_euclid(PI * 2.0);
+    // Rounding can land a tiny negative angle on 2π itself.
+    if azimuth >= PI * 2.0 {
+        0.0
+    } else {
+        azimuth
+    }
+}
+
+/// Angle between two azimuths the short way round, in `[0, π]`. Taken as the smaller
+/// of the two ways round rather than from one of them, so that it never rounds above
+/// the gap [`AngularOccupancy::gaps`] measures either way.
+pub(crate) fn angular_distance(a: f32, b: f32) -> f32 {
+    (a - b)
+        .rem_euclid(PI * 2.0)
+        .min((b - a).rem_euclid(PI * 2.0))
+}
+
+/// Puts `t` on the ring [`LayoutConfig::tie_break`] prefers among those where it clears
+/// its neighbours and returns that ring.
+fn place_with_tie_break(rings: &mut Vec<Ring>, t: Target, geometry: &RingGeometry) -> usize {
+    let fits = (0..rings.len())
+        .filter(|&r| rings[r].clears(t.azimuth, geometry.min_angle(r)))
+        .collect::<Vec<_>>();
+    let ring_ord = match geometry.config.tie_break {
+        TieBreak::Innermost => return place(rings, t, geometry),
+        TieBreak::TrueDistance => fits
+            .into_iter()
+            .chain(std::iter::once(rings.len()))
+            .min_by_key(|&r| OrderedFloat(((r + 1) as f32 - geometry.ring_position(t.dist)).abs())),
+        TieBreak::BalanceLoad => fits.into_iter().min_by_key(|&r| rings[r].len()),
+    }
+    .unwrap_or(rings.len());
+    if rings.len() == ring_ord {
+        rings.push(Ring::new());
+    }
+    rings[ring_ord].insert(t.azimuth, t);
+    ring_ord
+}
+
+/// Puts `t` on the innermost ring where it clears its neighbours and returns that ring.
+fn place(rings: &mut Vec<Ring>, t: Target, geometry: &RingGeometry) -> usize {
+    tracing::trace!(
+        id = t.id,
+        azimuth = %t.azimuth,
+        dist = %t.dist,
+        "placing target"
+    );
+    let mut ring_ord = 0;
+    loop {
+        let min_azi = geometry.min_angle(ring_ord);
+        if rings.len() == ring_ord {
+            rings.push(Ring::new());
+        }
+        let ring = &mut rings[ring_ord];
+        // An occupied key blocks even when `min_azi` is 0, rather than being overwritten.
+        let blocking = ring
+            .nearest(t.azimuth)
+            .map(|(azi, _)| azi)
+            .filter(|_| !ring.clears(t.azimuth, min_azi));
+        if let Some(blocking) = blocking {
+            tracing::trace!(
+                id = t.id,
+                ring = ring_ord,
+                min_angle = %min_azi,
+                blocking = %blocking,
+                "slot taken"
+            );
+            ring_ord += 1;
+            continue;
+        }
+        tracing::trace!(id = t.id, ring = ring_ord, "placed");
+        ring.insert(t.azimuth, t);
+        return ring_ord;
+    }
+}
+
+/// Radius of ring `ring_ord` with the default spacing for `poi_width`.
+pub fn ring_radius(poi_width: f32, ring_ord: usize) -> f32 {
+    LayoutConfig::new(poi_width).ring_radius(ring_ord)
+}
+
+/// Minimum azimuth gap on ring `ring_ord` with the default spacing and scatter.
+pub fn min_angle(poi_width: f32, ring_ord: usize) -> f32 {
+    LayoutConfig::new(poi_width).min_angle(ring_ord)
+}
+
+/// Angle two markers `poi_width` wide on a circle of `radius` need between their
+/// centers so their squares (in any rotation) don't overlap, widened by `scatter`.
+pub fn separation_angle(poi_width: f32, radius: f32, scatter: f32) -> f32 {
+    (poi_width * FRAC_1_SQRT_2 / radius).min(1.0).asin() * 2.0 * scatter
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum LayoutViolation {
+    #[error("target {id} is placed {placed} times, expected {expected}")]
+    PlacementCount {
+        id: i32,
+        placed: usize,
+        expected: usize,
+    },
+    #[error("targets {a} and {b} on ring {ring} are {angle} rad apart, minimum is {min_angle}")]
+    TooClose {
+        ring: usize,
+        a: i32,
+        b: i32,
+        angle: f32,
+        min_angle: f32,
+    },
+    #[error("target {id} is on ring {ring}, but nothing placed ahead of it blocks ring {open}")]
+    OutOfOrder { id: i32, ring: usize, open: usize },
+}
+
+/// Checks that `layout` places every one of `targets` exactly once and that no two
+/// neighbours on a ring, including across the 0/2π seam, are closer than
+/// [`RingLayout::min_angle`].
+pub fn verify(layout: &RingLayout, targets: &[Target]) -> Result<(), LayoutViolation> {
+    let mut counts = HashMap::new();
+    for t in targets {
+        counts.entry(t.id).or_insert((0, 0)).1 += 1;
+    }
+    for t in layout.rings.iter().flat_map(|ring| ring.values()) {
+        counts.entry(t.id).or_insert((0, 0)).0 += 1;
+    }
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_unstable_by_key(|(id, _)| *id);
+    if let Some((id, (placed, expected))) = counts.into_iter().find(|(_, (p, e))| p != e) {
+        return Err(LayoutViolation::PlacementCount {
+            id,
+            placed,
+            expected,
+        });
+    }
+
+    for (ring_ord, ring) in layout.rings.iter().enumerate() {
+        if ring.len() < 2 {
+            continue;
+        }
+        let min_angle = layout.min_angle(ring_ord);
+        for ((_, a), (_, b), angle) in ring.gaps() {
+            if angle < min_angle {
+                return Err(LayoutViolation::TooClose {
+                    ring: ring_ord,
+                    a: a.id,
+                    b: b.id,
+                    angle,
+                    min_angle,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `layout`, a fresh arrangement of `targets` (sorted by distance), took
+/// them in order: a target is only ever off an inner ring because one placed ahead of
+/// it, nearer or under [`PlacementMode::Priority`] of higher priority, is within
+/// [`RingLayout::min_angle`] of it there. That holds for [`GreedyRings`] with
+/// [`TieBreak::Innermost`] and under [`PlacementMode::Priority`]; other backends and
+/// tie breaks, and layouts changed since by inserts and removals, may place targets
+/// further out on purpose, so they pass. Run it after [`verify`].
+pub fn verify_order(layout: &RingLayout, targets: &[Target]) -> Result<(), LayoutViolation> {
+    let config = &layout.config;
+    if config.backend != LayoutBackend::Greedy
+        || (config.mode == PlacementMode::Nearest && config.tie_break != TieBreak::Innermost)
+    {
+        return Ok(());
+    }
+    let order = targets
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.id, i))
+        .collect::<HashMap<_, _>>();
+    let ahead = |a: &Target, b: &Target| {
+        let (a_order, b_order) = (order.get(&a.id), order.get(&b.id));
+        match config.mode {
+            PlacementMode::Nearest => a_order < b_order,
+            PlacementMode::Priority => {
+                a.priority > b.priority || (a.priority == b.priority && a_order < b_order)
+            }
+        }
+    };
+    for (ring_ord, ring) in layout.rings.iter().enumerate() {
+        for (azimuth, t) in ring.iter() {
+            let open = (0..ring_ord).find(|&inner| {
+                layout.rings[inner]
+                    .within(azimuth, layout.min_angle(inner))
+                    .iter()
+                    .all(|(_, other)| !ahead(other, t))
+            });
+            if let Some(open) = open {
+                return Err(LayoutViolation::OutOfOrder {
+                    id: t.id,
+                    ring: ring_ord,
+                    open,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn target(id: i32, azimuth_deg: f32, dist: f32) -> Target {
+        Target {
+            id,
+            text: id.to_string(),
+            azimuth: azimuth_deg.to_radians(),
+            dist,
+            ..Default::default()
+        }
+    }
+
+    fn ring_of(layout: &RingLayout, id: i32) -> usize {
+        layout
+            .placements()
+            .find(|p| p.target.id == id)
+            .map(|p| p.ring)
+            .unwrap()
+    }
+
+    #[test]
+    fn diagnostics_count_targets_turned_away_by_each_ring() {
+        let targets = [
+            target(0, 90.0, 10.0),
+            target(1, 90.0, 20.0),
+            target(2, 90.0, 30.0),
+        ];
+        let diagnostics = RingLayout::arrange(&targets, 30.0).diagnostics();
+        let rejected = diagnostics
+            .rings
+            .iter()
+            .map(|r| r.rejected)
+            .collect::<Vec<_>>();
+        assert_eq!(rejected, [2, 1, 0]);
+        assert!(diagnostics.rings.iter().all(|r| r.targets == 1));
+    }
+
+    #[test]
+    fn leader_routes_bend_around_inner_markers() {
+        let targets = [
+            target(0, 90.0, 10.0),
+            target(1, 90.0, 20.0),
+            target(2, 0.0, 30.0),
+        ];
+        let routes = RingLayout::arrange(&targets, 30.0).route_leaders();
+        let route = |id| routes.iter().find(|r| r.id == id).unwrap();
+        assert!(routes.iter().all(|r| r.crossings == 0));
+        assert!(route(0).elbows().is_empty());
+        assert!(route(2).elbows().is_empty());
+        assert!(!route(1).elbows().is_empty());
+        let end = *route(1).points.last().unwrap();
+        assert!(end.0.abs() < 1e-3 && (end.1 - 120.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn empty_input_has_no_rings() {
+        let layout = RingLayout::arrange(&[], 30.0);
+        assert!(layout.rings.is_empty());
+        assert!(verify(&layout, &[]).is_ok());
+        assert!(verify_order(&layout, &[]).is_ok());
+    }
+
+    #[test]
+    fn ring_radius_grows_by_spacing() {
+        let config = LayoutConfig::new(30.0);
+        assert_eq!(config.ring_radius(0), 60.0);
+        assert_eq!(config.ring_radius(2), 180.0);
+        assert_eq!(ring_radius(30.0, 2), 180.0);
+    }
+
+    #[test]
+    fn min_angle_shrinks_on_outer_rings() {
+        let config = LayoutConfig::new(30.0);
+        assert!(config.min_angle(1) < config.min_angle(0));
+        assert_eq!(min_angle(30.0, 1), config.min_angle(1));
+    }
+
+    #[test]
+    fn scatter_scales_min_angle() {
+        let tight = LayoutConfig {
+            scatter: 1.0,
+            ..LayoutConfig::new(30.0)
+        };
+        let loose = LayoutConfig {
+            scatter: 2.0,
+            ..tight
+        };
+        assert!((loose.min_angle(0) - 2.0 * tight.min_angle(0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn well_separated_targets_share_the_first_ring() {
+        let targets = [
+            target(0, 0.0, 10.0),
+            target(1, 120.0, 20.0),
+            target(2, 240.0, 30.0),
+        ];
+        let layout = RingLayout::arrange(&targets, 30.0);
+        assert_eq!(layout.rings.len(), 1);
+        assert_eq!(layout.rings[0].len(), 3);
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn wide_labels_spread_markers_over_more_rings() {
+        let targets = [
+            target(0, 0.0, 10.0),
+            target(1, 120.0, 20.0),
+            target(2, 240.0, 30.0),
+        ];
+        let config = LayoutConfig {
+            label_width: 100.0,
+            ..LayoutConfig::new(30.0)
+        };
+        assert!(config.min_angle(0) > LayoutConfig::new(30.0).min_angle(0));
+        let layout = RingLayout::with_config(&targets, config);
+        assert!(layout.rings.len() > 1);
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn close_targets_are_pushed_outwards_in_distance_order() {
+        let targets = [
+            target(0, 10.0, 10.0),
+            target(1, 12.0, 20.0),
+            target(2, 14.0, 30.0),
+        ];
+        let layout = RingLayout::arrange(&targets, 30.0);
+        assert_eq!(ring_of(&layout, 0), 0);
+        assert!(ring_of(&layout, 1) > 0);
+        assert!(ring_of(&layout, 2) > ring_of(&layout, 1));
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn targets_across_the_seam_do_not_overlap() {
+        let targets = [target(0, 359.0, 10.0), target(1, 1.0, 20.0)];
+        let layout = RingLayout::arrange(&targets, 30.0);
+        assert_ne!(ring_of(&layout, 0), ring_of(&layout, 1));
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn wider_spacing_fits_more_per_ring() {
+        let targets = [target(0, 10.0, 10.0), target(1, 30.0, 20.0)];
+        let narrow = RingLayout::arrange(&targets, 30.0);
+        let wide = RingLayout::with_config(
+            &targets,
+            LayoutConfig {
+                ring_spacing: 200.0,
+                ..LayoutConfig::new(30.0)
+            },
+        );
+        assert_eq!(narrow.rings.len(), 2);
+        assert_eq!(wide.rings.len(), 1);
+    }
+
+    #[test]
+    fn verify_reports_missing_targets() {
+        let targets = [target(0, 0.0, 10.0), target(1, 180.0, 20.0)];
+        let layout = RingLayout::arrange(&targets[..1], 30.0);
+        assert_eq!(
+            verify(&layout, &targets),
+            Err(LayoutViolation::PlacementCount {
+                id: 1,
+                placed: 0,
+                expected: 1
+            })
+        );
+    }
+
+    #[test]
+    fn incremental_updates_keep_the_layout_valid() {
+        let targets = [
+            target(0, 10.0, 10.0),
+            target(1, 12.0, 20.0),
+            target(2, 200.0, 30.0),
+        ];
+        let mut layout = RingLayout::arrange(&targets, 30.0);
+        let (ring, removed) = layout.remove(1).unwrap();
+        assert_eq!((ring, removed.id), (1, 1));
+        assert_eq!(layout.rings.len(), 1);
+        assert_eq!(layout.insert(target(3, 11.0, 15.0)), 1);
+        assert!(verify(
+            &layout,
+            &[
+                targets[0].clone(),
+                targets[2].clone(),
+                target(3, 11.0, 15.0)
+            ]
+        )
+        .is_ok());
+        assert!(layout.remove(1).is_none());
+    }
+
+    fn prioritized(id: i32, azimuth_deg: f32, dist: f32, priority: i32) -> Target {
+        Target {
+            priority,
+            ..target(id, azimuth_deg, dist)
+        }
+    }
+
+    fn by_priority() -> LayoutConfig {
+        LayoutConfig {
+            mode: PlacementMode::Priority,
+            ..LayoutConfig::new(30.0)
+        }
+    }
+
+    #[test]
+    fn priority_mode_puts_important_targets_inside() {
+        let targets = [prioritized(0, 10.0, 10.0, 0), prioritized(1, 12.0, 20.0, 5)];
+        let nearest = RingLayout::arrange(&targets, 30.0);
+        assert_eq!(ring_of(&nearest, 0), 0);
+        let layout = RingLayout::with_config(&targets, by_priority());
+        assert_eq!(ring_of(&layout, 1), 0);
+        assert_eq!(ring_of(&layout, 0), 1);
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn inserting_a_higher_priority_target_evicts_a_cascade() {
+        let mut targets = vec![prioritized(0, 10.0, 10.0, 1), prioritized(1, 11.0, 20.0, 0)];
+        let mut layout = RingLayout::with_config(&targets, by_priority());
+        assert_eq!((ring_of(&layout, 0), ring_of(&layout, 1)), (0, 1));
+
+        let (ring, evicted) = layout.insert_evicting(prioritized(2, 10.5, 30.0, 2));
+        assert_eq!(ring, 0);
+        assert_eq!(evicted, vec![0, 1]);
+        assert_eq!((ring_of(&layout, 0), ring_of(&layout, 1)), (1, 2));
+        targets.push(prioritized(2, 10.5, 30.0, 2));
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn insert_and_remove_report_slot_changes() {
+        let targets = [prioritized(0, 10.0, 10.0, 1), prioritized(1, 11.0, 20.0, 0)];
+        let mut layout = RingLayout::with_config(&targets, by_priority());
+        let change = |id, from, to| SlotChange { id, from, to };
+
+        let changes = layout.insert_changes(prioritized(2, 10.5, 30.0, 2));
+        assert_eq!(
+            changes,
+            vec![
+                change(2, None, Some(0)),
+                change(0, Some(0), Some(1)),
+                change(1, Some(1), Some(2)),
+            ]
+        );
+        assert_eq!(layout.find(1).map(|p| p.ring), Some(2));
+        assert_eq!(layout.remove_changes(1), Some(change(1, Some(2), None)));
+        assert_eq!(layout.remove_changes(1), None);
+        assert_eq!(
+            layout.insert_changes(prioritized(2, 200.0, 30.0, 2)),
+            vec![change(2, Some(0), Some(0))]
+        );
+    }
+
+    #[test]
+    fn equal_priority_does_not_evict() {
+        let mut layout = RingLayout::with_config(&[prioritized(0, 10.0, 10.0, 3)], by_priority());
+        let (ring, evicted) = layout.insert_evicting(prioritized(1, 11.0, 5.0, 3));
+        assert_eq!(ring, 1);
+        assert!(evicted.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_moved() {
+        let before = RingLayout::arrange(&[target(0, 10.0, 10.0), target(1, 200.0, 20.0)], 30.0);
+        let mut after = before.clone();
+        after.remove(1);
+        after.remove(0);
+        after.insert(target(2, 11.0, 5.0));
+        after.insert(target(0, 10.0, 10.0));
+
+        let diff = before.diff(&after);
+        assert_eq!(
+            diff.added.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(
+            diff.removed.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(diff.moved.len(), 1);
+        assert_eq!(
+            (
+                diff.moved[0].id,
+                diff.moved[0].from.ring,
+                diff.moved[0].to.ring
+            ),
+            (0, 0, 1)
+        );
+        assert_eq!(diff.to_string(), "1 added, 1 removed, 1 moved");
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn adaptive_spacing_pushes_crowded_rings_out() {
+        // Twenty targets every 18° crowd the inner rings.
+        let targets = (0..20)
+            .map(|i| target(i, i as f32 * 18.0, 10.0 + i as f32))
+            .collect::<Vec<_>>();
+        let uniform = RingLayout::arrange(&targets, 10.0);
+        let adaptive = RingLayout::with_config(
+            &targets,
+            LayoutConfig {
+                adaptive: Some(AdaptiveSpacing::default()),
+                ..LayoutConfig::new(10.0)
+            },
+        );
+        assert!(adaptive.ring_radius(0) > uniform.ring_radius(0));
+        assert!(adaptive.rings.len() <= uniform.rings.len());
+        assert!(verify(&adaptive, &targets).is_ok());
+    }
+
+    fn force_directed() -> LayoutConfig {
+        LayoutConfig {
+            backend: LayoutBackend::ForceDirected(ForceDirected::default()),
+            ..LayoutConfig::new(30.0)
+        }
+    }
+
+    #[test]
+    fn force_directed_spreads_a_cluster_on_the_inner_ring() {
+        let targets = [
+            target(0, 10.0, 10.0),
+            target(1, 12.0, 20.0),
+            target(2, 14.0, 30.0),
+        ];
+        let layout = RingLayout::with_config(&targets, force_directed());
+        assert_eq!(layout.rings.len(), 1);
+        assert!(verify(&layout, &targets).is_ok());
+        let max_shift = ForceDirected::default().max_shift * layout.min_angle(0);
+        for p in layout.placements() {
+            assert!(angular_distance(p.azimuth, p.target.azimuth) <= max_shift + 1e-4);
+        }
+    }
+
+    #[test]
+    fn force_directed_moves_what_does_not_fit_outwards() {
+        let targets = (0..40)
+            .map(|i| target(i, (i % 5) as f32, i as f32))
+            .collect::<Vec<_>>();
+        let layout = RingLayout::with_config(&targets, force_directed());
+        let greedy = RingLayout::arrange(&targets, 30.0);
+        assert!(layout.rings.len() > 1);
+        assert!(layout.rings.len() <= greedy.rings.len());
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    fn tie_break(tie_break: TieBreak) -> LayoutConfig {
+        LayoutConfig {
+            tie_break,
+            ..LayoutConfig::new(30.0)
+        }
+    }
+
+    #[test]
+    fn true_distance_tie_break_puts_far_targets_outside() {
+        let targets = [target(0, 0.0, 10.0), target(1, 180.0, 130.0)];
+        let innermost = RingLayout::with_config(&targets, tie_break(TieBreak::Innermost));
+        assert_eq!(ring_of(&innermost, 1), 0);
+        let layout = RingLayout::with_config(&targets, tie_break(TieBreak::TrueDistance));
+        assert_eq!(ring_of(&layout, 1), 1);
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn true_distance_follows_the_radial_scale() {
+        let config = LayoutConfig {
+            scale: "log:10,10".parse().unwrap(),
+            ..tie_break(TieBreak::TrueDistance)
+        };
+        let targets = [
+            target(0, 0.0, 10.0),
+            target(1, 180.0, 50.0),
+            target(2, 90.0, 100.0),
+        ];
+        let layout = RingLayout::with_config(&targets, config);
+        // Linear, all three would be nearest the first ring.
+        assert_eq!(
+            (
+                ring_of(&layout, 0),
+                ring_of(&layout, 1),
+                ring_of(&layout, 2)
+            ),
+            (0, 1, 1)
+        );
+        assert!((layout.ring_distance(2) - 1000.0).abs() < 1e-2);
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn breakpoint_scales_invert() {
+        let scale = "breakpoints:0:0,100:1,1000:2"
+            .parse::<RadialScale>()
+            .unwrap();
+        assert_eq!(scale.position(550.0, 60.0), 1.5);
+        assert_eq!(scale.distance(3.0, 60.0), 1900.0);
+        assert_eq!(scale.to_string().parse(), Ok(scale));
+        assert!("breakpoints:0:0,100:1,50:2".parse::<RadialScale>().is_err());
+    }
+
+    #[test]
+    fn balance_load_tie_break_fills_the_emptier_ring() {
+        let targets = [
+            target(0, 0.0, 10.0),
+            target(1, 90.0, 15.0),
+            target(2, 5.0, 20.0),
+            target(3, 180.0, 30.0),
+        ];
+        let innermost = RingLayout::with_config(&targets, tie_break(TieBreak::Innermost));
+        assert_eq!(ring_of(&innermost, 3), 0);
+        let layout = RingLayout::with_config(&targets, tie_break(TieBreak::BalanceLoad));
+        assert_eq!(ring_of(&layout, 2), 1);
+        assert_eq!(ring_of(&layout, 3), 1);
+        assert!(verify(&layout, &targets).is_ok());
+    }
+
+    #[test]
+    fn stagger_turns_rings_by_less_than_half_a_slot() {
+        let targets = (0..30)
+            .map(|i| target(i, (i % 3) as f32, i as f32))
+            .collect::<Vec<_>>();
+        let config = LayoutConfig {
+            stagger: true,
+            ..LayoutConfig::new(30.0)
+        };
+        let layout = RingLayout::with_config(&targets, config);
+        assert!(layout.rings.len() > 2);
+        assert_eq!(layout.ring_offset(0), 0.0);
+        for ring_ord in 1..layout.rings.len() {
+            let offset = layout.ring_offset(ring_ord);
+            assert!(offset != 0.0 && offset.abs() <= layout.min_angle(ring_ord) / 2.0);
+            assert_ne!(offset, layout.ring_offset(ring_ord - 1));
+        }
+        let p = layout.find(20).unwrap();
+        assert!((angular_distance(p.drawn_azimuth(), p.azimuth) - p.offset.abs()).abs() < 1e-6);
+        assert_eq!(RingLayout::arrange(&targets, 30.0).ring_offset(1), 0.0);
+    }
+
+    #[test]
+    fn marker_scale_spreads_markers_like_wider_markers() {
+        let targets = (0..40)
+            .map(|i| target(i, (i % 8) as f32 * 4.0, 50.0 + i as f32))
+            .collect::<Vec<_>>();
+        let scaled = LayoutConfig {
+            marker_scale: 2.0,
+            ..LayoutConfig::new(20.0)
+        };
+        let wide = LayoutConfig {
+            ring_spacing: scaled.ring_spacing,
+            ..LayoutConfig::new(40.0)
+        };
+        assert_eq!(scaled.min_angle(3), wide.min_angle(3));
+        let layout = RingLayout::with_config(&targets, scaled);
+        assert_eq!(
+            layout.rings.len(),
+            RingLayout::with_config(&targets, wide).rings.len()
+        );
+        assert!(layout.rings.len() > RingLayout::arrange(&targets, 20.0).rings.len());
+        verify(&layout, &targets).unwrap();
+    }
+
+    #[test]
+    fn clockwise_iteration_wraps_around() {
+        let targets = [
+            target(0, 0.0, 10.0),
+            target(1, 90.0, 20.0),
+            target(2, 200.0, 30.0),
+        ];
+        let layout = RingLayout::arrange(&targets, 30.0);
+        let order = layout
+            .iter_clockwise_from(100f32.to_radians())
+            .map(|p| p.target.id)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn zero_width_markers_at_one_azimuth_all_stay_placed() {
+        let targets = [
+            target(0, 45.0, 10.0),
+            target(1, 45.0, 20.0),
+            target(2, 45.0, 30.0),
+        ];
+        for mode in [PlacementMode::Nearest, PlacementMode::Priority] {
+            let config = LayoutConfig {
+                mode,
+                ..LayoutConfig::new(0.0)
+            };
+            assert!(config.validate().is_err());
+            let layout = RingLayout::with_config(&targets, config);
+            assert_eq!(layout.placements().count(), targets.len());
+            assert_eq!(layout.rings.len(), targets.len());
+        }
+        assert!(LayoutConfig::new(30.0).validate().is_ok());
+    }
+
+    #[test]
+    fn clockwise_iteration_follows_staggered_rings_as_drawn() {
+        let targets = (0..30)
+            .map(|i| target(i, (i % 3) as f32, i as f32))
+            .collect::<Vec<_>>();
+        let config = LayoutConfig {
+            stagger: true,
+            ..LayoutConfig::new(30.0)
+        };
+        let layout = RingLayout::with_config(&targets, config);
+        let azimuth = 90f32.to_radians();
+        let swept = layout
+            .iter_clockwise_from(azimuth)
+            .map(|p| (azimuth - p.drawn_azimuth()).rem_euclid(PI * 2.0))
+            .collect::<Vec<_>>();
+        assert_eq!(swept.len(), targets.len());
+        assert!(swept.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn nearest_orders_by_layout_distance() {
+        let targets = [
+            target(0, 0.0, 10.0),
+            target(1, 90.0, 20.0),
+            target(2, 180.0, 30.0),
+        ];
+        let layout = RingLayout::arrange(&targets, 30.0);
+        let nearest = layout
+            .nearest(80f32.to_radians(), layout.ring_radius(0), 2)
+            .iter()
+            .map(|p| p.target.id)
+            .collect::<Vec<_>>();
+        assert_eq!(nearest, vec![1, 0]);
+    }
+
+    #[test]
+    fn seeded_random_targets_are_reproducible_and_lay_out_cleanly() {
+        use crate::scenario::RandomTargets;
+
+        let gen = RandomTargets {
+            count: 40,
+            clusters: 3,
+            spread: 0.05,
+            ..Default::default()
+        };
+        assert_eq!(
+            format!("{:?}", gen.generate(7)),
+            format!("{:?}", gen.generate(7))
+        );
+        for seed in 0..8 {
+            let targets = gen.generate(seed);
+            let layout = RingLayout::arrange(&targets, 30.0);
+            verify(&layout, &targets).unwrap();
+        }
+    }
+
+    #[test]
+    fn identical_targets_stack_one_per_ring() {
+        let targets = (0..5).map(|i| target(i, 45.0, 20.0)).collect::<Vec<_>>();
+        for mode in [PlacementMode::Nearest, PlacementMode::Priority] {
+            let config = LayoutConfig {
+                mode,
+                ..LayoutConfig::new(30.0)
+            };
+            let layout = RingLayout::with_config(&targets, config);
+            assert_eq!(layout.rings.len(), 5);
+            assert!(layout.rings.iter().all(|ring| ring.len() == 1));
+            verify(&layout, &targets).unwrap();
+            verify_order(&layout, &targets).unwrap();
+        }
+    }
+
+    #[test]
+    fn verify_order_reports_a_target_left_outside_an_open_ring() {
+        let targets = [target(0, 0.0, 10.0), target(1, 180.0, 20.0)];
+        let mut rings = vec![Ring::new(), Ring::new()];
+        rings[0].insert(targets[0].azimuth, targets[0].clone());
+        rings[1].insert(targets[1].azimuth, targets[1].clone());
+        let layout = RingLayout::from_rings(LayoutConfig::new(30.0), rings, Vec::new());
+        assert!(verify(&layout, &targets).is_ok());
+        assert_eq!(
+            verify_order(&layout, &targets),
+            Err(LayoutViolation::OutOfOrder {
+                id: 1,
+                ring: 1,
+                open: 0
+            })
+        );
+    }
+
+    /// Azimuths bunched around the 0/2π seam from either side, with a share exactly on
+    /// it and on a few round bearings, so that neighbours collide and some coincide.
+    fn azimuth() -> impl Strategy<Value = f32> {
+        prop_oneof![
+            1 => 0.0..PI * 2.0,
+            1 => Just(0.0),
+            1 => (0..8u8).prop_map(|k| k as f32 * PI / 4.0),
+            2 => -0.5f32..0.5,
+        ]
+    }
+
+    /// Up to 60 targets sorted by distance, as the layout takes them. Some share a
+    /// distance, and priorities are few enough to tie.
+    fn targets() -> impl Strategy<Value = Vec<Target>> {
+        let dist = prop_oneof![3 => 10f32..100.0, 1 => Just(50.0)];
+        prop::collection::vec((azimuth(), dist, 0..3i32), 0..60).prop_map(|fields| {
+            let mut targets = fields
+                .into_iter()
+                .enumerate()
+                .map(|(id, (azimuth, dist, priority))| Target {
+                    id: id as i32,
+                    text: id.to_string(),
+                    azimuth,
+                    dist,
+                    priority,
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+            targets.sort_by(|a, b| a.dist.total_cmp(&b.dist));
+            targets
+        })
+    }
+
+    fn config() -> impl Strategy<Value = LayoutConfig> {
+        let mode = prop_oneof![Just(PlacementMode::Nearest), Just(PlacementMode::Priority)];
+        let backend = prop_oneof![
+            Just(LayoutBackend::Greedy),
+            Just(LayoutBackend::ForceDirected(ForceDirected::default())),
+        ];
+        let adaptive = prop::option::of(Just(AdaptiveSpacing::default()));
+        (
+            5f32..60.0,
+            0.5f32..2.0,
+            mode,
+            backend,
+            adaptive,
+            any::<bool>(),
+        )
+            .prop_map(|(poi_width, scatter, mode, backend, adaptive, stagger)| {
+                LayoutConfig {
+                    scatter,
+                    mode,
+                    backend,
+                    adaptive,
+                    stagger,
+                    ..LayoutConfig::new(poi_width)
+                }
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn accepted_azimuths_keep_min_angle_across_the_seam(
+            min_angle in 0.05f32..1.0,
+            azimuths in prop::collection::vec(azimuth(), 0..60),
+            probe in -0.5f32..0.5,
+        ) {
+            let mut occupancy = AngularOccupancy::new();
+            for (i, azimuth) in azimuths.into_iter().enumerate() {
+                if occupancy.clears(azimuth, min_angle) {
+                    occupancy.insert(azimuth, i);
+                }
+            }
+            let azimuths = occupancy.azimuths().collect::<Vec<_>>();
+            for (i, &a) in azimuths.iter().enumerate() {
+                prop_assert!((0.0..PI * 2.0).contains(&a), "{} outside [0, 2π)", a);
+                for &b in &azimuths[i + 1..] {
+                    prop_assert!(angular_distance(a, b) >= min_angle, "{} and {}", a, b);
+                }
+            }
+            if !occupancy.is_empty() {
+                let turn = occupancy.gaps().map(|(_, _, gap)| gap).sum::<f32>();
+                prop_assert!((turn - PI * 2.0).abs() < 1e-4, "gaps sum to {}", turn);
+            }
+
+            let near = occupancy
+                .within(probe, min_angle)
+                .into_iter()
+                .map(|(azimuth, _)| azimuth)
+                .collect::<Vec<_>>();
+            let expected = azimuths
+                .iter()
+                .copied()
+                .filter(|&a| angular_distance(a, probe) < min_angle)
+                .collect::<Vec<_>>();
+            prop_assert_eq!(near, expected);
+        }
+
+        #[test]
+        fn layouts_keep_their_invariants(targets in targets(), config in config()) {
+            let layout = RingLayout::with_config(&targets, config);
+            if let Err(violation) =
+                verify(&layout, &targets).and_then(|_| verify_order(&layout, &targets))
+            {
+                return Err(TestCaseError::fail(violation.to_string()));
+            }
+        }
+    }
+}
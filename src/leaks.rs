@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::display::{Poi, PoiLabel, RingPart, Slot};
+use crate::motion::PolarTween;
+use crate::target::Target;
+
+/// What is wrong with an entity [`LeakCheckPlugin`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrphanKind {
+    /// A leader line or label of a target id no [`Target`] has any more.
+    Rigging,
+    /// A second leader line or label of the same target.
+    Duplicate,
+    /// A marker still drawn on an entity whose [`Target`] component was removed.
+    Marker,
+}
+
+/// A display entity left behind by its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Orphan {
+    pub entity: Entity,
+    /// Id of the target the entity was drawn for.
+    pub id: i32,
+    pub kind: OrphanKind,
+}
+
+/// The display entities [`LeakCheckPlugin`] found without a target at its latest
+/// audit, by entity.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntityAudit {
+    pub orphans: Vec<Orphan>,
+}
+
+impl EntityAudit {
+    pub fn is_clean(&self) -> bool {
+        self.orphans.is_empty()
+    }
+
+    pub fn count(&self, kind: OrphanKind) -> usize {
+        self.orphans.iter().filter(|o| o.kind == kind).count()
+    }
+}
+
+/// How often [`LeakCheckPlugin`] audits the display, and whether it cleans up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeakCheck {
+    /// Seconds between audits.
+    pub interval: f32,
+    /// Clean up whatever an audit finds rather than only reporting it.
+    pub auto_reconcile: bool,
+    requested: bool,
+}
+
+impl Default for LeakCheck {
+    fn default() -> Self {
+        LeakCheck::new(5.0, false)
+    }
+}
+
+impl LeakCheck {
+    pub fn new(interval: f32, auto_reconcile: bool) -> Self {
+        LeakCheck {
+            interval,
+            auto_reconcile,
+            requested: false,
+        }
+    }
+
+    /// Audits at the end of this frame and cleans up what is found, whatever
+    /// [`auto_reconcile`](LeakCheck::auto_reconcile) says.
+    pub fn reconcile(&mut self) {
+        self.requested = true;
+    }
+}
+
+/// Audits the entities [`PoiRingPlugin`](crate::display::PoiRingPlugin) spawns for
+/// targets against the [`Target`]s alive, every [`LeakCheck::interval`] seconds, so
+/// that a long-running display doesn't slowly fill up with meshes and text nobody
+/// sees. What is found goes into the [`EntityAudit`] resource and is printed when it
+/// changes. [`LeakCheck::reconcile`] cleans up on demand: orphaned and duplicate
+/// leader lines and labels are despawned, and orphaned markers lose their sprite and
+/// marker components, freeing their mesh and material, while the entity itself is left
+/// to whoever removed its `Target`.
+///
+/// Runs after the update stage, once the display has applied the frame's changes.
+pub struct LeakCheckPlugin;
+
+impl Plugin for LeakCheckPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<LeakCheck>() {
+            app.init_resource::<LeakCheck>();
+        }
+        app.init_resource::<EntityAudit>()
+            .add_system_to_stage(stage::POST_UPDATE, leak_check_system.system());
+    }
+}
+
+/// The display entities of `rigging` and `markers` that no target in `live` accounts
+/// for, in entity order.
+fn audit<'a>(
+    live: &HashSet<i32>,
+    rigging: impl Iterator<Item = (Entity, &'a Slot, bool)>,
+    markers: impl Iterator<Item = (Entity, &'a Poi)>,
+) -> Vec<Orphan> {
+    let mut orphans = Vec::new();
+    let mut seen = HashMap::new();
+    let mut rigging = rigging.collect::<Vec<_>>();
+    rigging.sort_unstable_by_key(|(entity, _, _)| entity.id());
+    for (entity, slot, label) in rigging {
+        let kind = if !live.contains(&slot.id) {
+            OrphanKind::Rigging
+        } else if seen.insert((slot.id, label), entity).is_some() {
+            OrphanKind::Duplicate
+        } else {
+            continue;
+        };
+        orphans.push(Orphan {
+            entity,
+            id: slot.id,
+            kind,
+        });
+    }
+    orphans.extend(markers.map(|(entity, poi)| Orphan {
+        entity,
+        id: poi.id,
+        kind: OrphanKind::Marker,
+    }));
+    orphans.sort_unstable_by_key(|o| o.entity.id());
+    orphans
+}
+
+fn clean_up(commands: &mut Commands, orphan: &Orphan) {
+    let entity = orphan.entity;
+    match orphan.kind {
+        OrphanKind::Rigging | OrphanKind::Duplicate => {
+            commands.despawn(entity);
+        }
+        OrphanKind::Marker => {
+            commands
+                .remove_one::<Draw>(entity)
+                .remove_one::<Sprite>(entity)
+                .remove_one::<Handle<Mesh>>(entity)
+                .remove_one::<Handle<ColorMaterial>>(entity)
+                .remove_one::<Poi>(entity)
+                .remove_one::<Slot>(entity)
+                .remove_one::<PolarTween>(entity);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn leak_check_system(
+    mut commands: Commands,
+    mut since_audit: Local<f32>,
+    time: Res<Time>,
+    mut check: ResMut<LeakCheck>,
+    mut audited: ResMut<EntityAudit>,
+    targets: Query<&Target>,
+    rigging: Query<With<RingPart, (Entity, &Slot, Option<&PoiLabel>)>>,
+    markers: Query<Without<Target, (Entity, &Poi)>>,
+) {
+    *since_audit += time.delta_seconds;
+    if !check.requested && *since_audit < check.interval {
+        return;
+    }
+    *since_audit = 0.0;
+    let live = targets.iter().map(|t| t.id).collect::<HashSet<_>>();
+    let orphans = audit(
+        &live,
+        rigging
+            .iter()
+            .map(|(entity, slot, label)| (entity, slot, label.is_some())),
+        markers.iter(),
+    );
+    let reconcile = check.requested || check.auto_reconcile;
+    check.requested = false;
+    if orphans != audited.orphans {
+        let found = EntityAudit { orphans };
+        if found.is_clean() {
+            tracing::info!("entity audit: clean");
+        } else {
+            tracing::warn!(
+                "entity audit: {} orphaned leader lines and labels, {} duplicates, {} orphaned markers",
+                found.count(OrphanKind::Rigging),
+                found.count(OrphanKind::Duplicate),
+                found.count(OrphanKind::Marker)
+            );
+        }
+        *audited = found;
+    }
+    if reconcile && !audited.is_clean() {
+        for orphan in &audited.orphans {
+            clean_up(&mut commands, orphan);
+        }
+        tracing::info!("entity audit: cleaned up {}", audited.orphans.len());
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+
+use crate::display::{LabelFont, RadarDisplay};
+use crate::layout::LayoutDiagnostics;
+use crate::metrics::Metrics;
+use crate::style::MarkerShape;
+use crate::target::Target;
+use crate::theme::Theme;
+
+const FONT_SIZE: f32 = 14.0;
+const PADDING: f32 = 4.0;
+/// Name of the legend entry for targets without a listed category.
+const FALLBACK_NAME: &str = "other";
+
+/// A character that looks like `shape`, for bevy_ui text.
+fn glyph(shape: MarkerShape) -> &'static str {
+    match shape {
+        MarkerShape::Square => "\u{25a0}",
+        MarkerShape::Diamond => "\u{25ca}",
+        MarkerShape::Triangle => "\u{25b2}",
+        MarkerShape::Circle => "\u{25cf}",
+    }
+}
+
+/// One line of the legend: a category and how its markers look.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegendEntry {
+    pub name: String,
+    pub color: Color,
+    pub shape: MarkerShape,
+}
+
+/// The legend entries for `targets`: one per category in use, by name, and one for
+/// those drawn with the fallback style, last.
+pub fn legend_entries<'a>(
+    display: &RadarDisplay,
+    theme: Theme,
+    targets: impl Iterator<Item = &'a Target>,
+) -> Vec<LegendEntry> {
+    let categories = display.categories();
+    let mut fallback = false;
+    let mut used = BTreeSet::new();
+    for target in targets {
+        match target.category.as_deref() {
+            Some(name) if categories.get(name).is_some() => {
+                used.insert(name);
+            }
+            _ => fallback = true,
+        }
+    }
+    let entry = |name: &str, color: Option<Color>, shape| LegendEntry {
+        name: name.to_string(),
+        color: color.unwrap_or_else(|| theme.stroke()),
+        shape,
+    };
+    let mut entries = categories
+        .categories()
+        .into_iter()
+        .filter(|(name, _)| used.contains(name))
+        .map(|(name, style)| entry(name, style.color, style.shape))
+        .collect::<Vec<_>>();
+    if fallback {
+        let style = categories.fallback;
+        entries.push(entry(FALLBACK_NAME, style.color, style.shape));
+    }
+    entries
+}
+
+/// The status bar line: target and ring counts and the latest layout and feed
+/// latencies.
+pub fn status_text(metrics: &Metrics, diagnostics: &LayoutDiagnostics) -> String {
+    let ms = |latency: Option<std::time::Duration>| {
+        latency.map_or_else(
+            || "-".to_string(),
+            |l| format!("{:.1} ms", l.as_secs_f64() * 1000.0),
+        )
+    };
+    format!(
+        "{} targets   {} rings   layout {}   feed {}",
+        metrics.active_tracks(),
+        diagnostics.rings.len(),
+        ms(metrics.layout_latency()),
+        ms(metrics.feed_latency())
+    )
+}
+
+/// Marks the legend's root node.
+pub struct LegendPanel;
+
+/// Marks the status bar's text.
+pub struct StatusBar;
+
+/// A legend of the marker shape and color of each category on the display, at the
+/// bottom left of the window, and a status bar along the bottom edge with the target
+/// and ring counts and the layout and feed latencies from [`Metrics`] and
+/// [`LayoutDiagnostics`]. Both are bevy_ui nodes and follow the targets, the
+/// [`RadarDisplay`] styles and the [`Theme`] as they change. Needs a UI camera and
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin).
+pub struct LegendPlugin;
+
+impl Plugin for LegendPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Metrics>() {
+            app.init_resource::<Metrics>();
+        }
+        app.init_resource::<LayoutDiagnostics>()
+            .add_system(legend_system.system())
+            .add_system(status_bar_system.system());
+    }
+}
+
+fn text(value: String, font: Handle<Font>, color: Color) -> TextComponents {
+    TextComponents {
+        text: Text {
+            value,
+            font,
+            style: TextStyle {
+                font_size: FONT_SIZE,
+                color,
+            },
+        },
+        ..Default::default()
+    }
+}
+
+fn backdrop(theme: Theme) -> Color {
+    let mut color = theme.background();
+    color.set_a(0.85);
+    color
+}
+
+#[allow(clippy::too_many_arguments)]
+fn legend_system(
+    mut commands: Commands,
+    mut shown: Local<Option<(Vec<LegendEntry>, Theme)>>,
+    display: Res<RadarDisplay>,
+    theme: Res<Theme>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    targets: Query<&Target>,
+    panels: Query<With<LegendPanel, Entity>>,
+) {
+    let entries = legend_entries(&display, *theme, targets.iter());
+    if shown.as_ref() == Some(&(entries.clone(), *theme)) {
+        return;
+    }
+    for panel in panels.iter() {
+        commands.despawn_recursive(panel);
+    }
+    *shown = Some((entries.clone(), *theme));
+    if entries.is_empty() {
+        return;
+    }
+    let font = asset_server.load(label_font.0);
+    let transparent = materials.add(Color::NONE.into());
+    commands
+        .spawn(NodeComponents {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.0),
+                    // Above the status bar.
+                    bottom: Val::Px(FONT_SIZE + 2.0 * PADDING + 10.0),
+                    ..Default::default()
+                },
+                // bevy_ui's y points up, so this lists the entries top down.
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(PADDING)),
+                ..Default::default()
+            },
+            material: materials.add(backdrop(*theme).into()),
+            ..Default::default()
+        })
+        .with(LegendPanel)
+        .with_children(|panel| {
+            for entry in &entries {
+                panel
+                    .spawn(NodeComponents {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: transparent.clone(),
+                        ..Default::default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(text(
+                            format!("{} ", glyph(entry.shape)),
+                            font.clone(),
+                            entry.color,
+                        ))
+                        .spawn(text(
+                            entry.name.clone(),
+                            font.clone(),
+                            theme.text(),
+                        ));
+                    });
+            }
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn status_bar_system(
+    mut commands: Commands,
+    mut backdrop_of: Local<Option<(Handle<ColorMaterial>, Theme)>>,
+    metrics: Res<Metrics>,
+    diagnostics: Res<LayoutDiagnostics>,
+    theme: Res<Theme>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut bars: Query<With<StatusBar, Mut<Text>>>,
+) {
+    let value = status_text(&metrics, &diagnostics);
+    let (material, shown_theme) = match &mut *backdrop_of {
+        Some(backdrop) => backdrop,
+        None => {
+            let material = materials.add(backdrop(*theme).into());
+            commands
+                .spawn(NodeComponents {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            left: Val::Px(0.0),
+                            right: Val::Px(0.0),
+                            bottom: Val::Px(0.0),
+                            ..Default::default()
+                        },
+                        padding: Rect::all(Val::Px(PADDING)),
+                        ..Default::default()
+                    },
+                    material: material.clone(),
+                    ..Default::default()
+                })
+                .with_children(|bar| {
+                    bar.spawn(text(value, asset_server.load(label_font.0), theme.text()))
+                        .with(StatusBar);
+                });
+            *backdrop_of = Some((material, *theme));
+            return;
+        }
+    };
+    if *shown_theme != *theme {
+        *shown_theme = *theme;
+        if let Some(material) = materials.get_mut(&*material) {
+            material.color = backdrop(*theme);
+        }
+    }
+    for mut text in bars.iter_mut() {
+        if text.value != value {
+            text.value = value.clone();
+        }
+        if text.style.color != theme.text() {
+            text.style.color = theme.text();
+        }
+    }
+}
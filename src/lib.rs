@@ -0,0 +1,118 @@
+#[cfg(all(target_arch = "wasm32", not(feature = "web")))]
+compile_error!("building for wasm32 needs the `web` feature");
+
+pub mod actions;
+pub mod aging;
+pub mod alerts;
+pub mod animated_texture;
+pub mod animation;
+pub mod asset_check;
+pub mod atmosphere;
+pub mod autolabel;
+pub mod batch;
+pub mod billboard;
+pub mod bodies;
+pub mod camera;
+pub mod capture;
+pub mod cli;
+pub mod clipboard;
+pub mod cluster;
+pub mod config;
+pub mod constant_size;
+pub mod coords;
+pub mod coverage;
+pub mod culling;
+pub mod curved_labels;
+pub mod day_night;
+pub mod debug_overlay;
+pub mod demo;
+pub mod designation;
+pub mod display;
+pub mod easing;
+pub mod edit;
+pub mod emphasis;
+pub mod events;
+pub mod feed;
+pub mod flat_map;
+pub mod frame;
+pub mod fuzz;
+pub mod geo;
+pub mod geo_marker;
+pub mod geojson;
+pub mod gesture;
+pub mod globe_link;
+pub mod globe_pick;
+pub mod globe_render;
+pub mod graticule;
+pub mod heatmap;
+pub mod impostor;
+pub mod instancing;
+pub mod io;
+#[cfg(feature = "ktx2")]
+pub mod ktx2;
+pub mod label_fit;
+pub mod label_zoom;
+pub mod layers;
+pub mod layout;
+pub mod leaks;
+pub mod legend;
+pub mod lighting;
+pub mod links;
+pub mod lod;
+pub mod lod_sphere;
+pub mod measure;
+pub mod mesh;
+pub mod metrics;
+pub mod minimap;
+pub mod mipmap;
+pub mod motion;
+pub mod multi_select;
+pub mod normal_map;
+pub mod notes;
+pub mod occlusion;
+pub mod origins;
+pub mod palette;
+pub mod persist;
+pub mod planet;
+pub mod pointer;
+pub mod polyline;
+pub mod prediction;
+pub mod prelude;
+pub mod probe;
+pub mod range_rings;
+pub mod raster;
+pub mod region;
+pub mod regression;
+pub mod replay;
+pub mod ring3d;
+pub mod route;
+pub mod scale;
+pub mod scenario;
+pub mod scene;
+pub mod selection;
+pub mod simulation;
+pub mod sky;
+pub mod smoothing;
+pub mod snapshot;
+pub mod spatial;
+pub mod split_view;
+pub mod style;
+pub mod svg;
+pub mod sweep;
+pub mod target;
+pub mod target_list;
+pub mod terrain;
+pub mod theme;
+pub mod tiles;
+pub mod timeline;
+pub mod tooltip;
+pub mod trails;
+pub mod tuning;
+pub mod undo;
+pub mod units;
+pub mod updates;
+pub mod viewport;
+#[cfg(feature = "web")]
+pub mod web;
+pub mod window_fit;
+pub mod zones;
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+/// How far out the rig's lights are placed. Bevy only has point lights, so lights this
+/// far away stand in for directional ones over a scene a few units across.
+const LIGHT_DISTANCE: f32 = 1000.0;
+
+/// Key, fill and ambient light for a shaded 3D scene, kept up to date by
+/// [`LightingPlugin`]. Intensities scale `color`; `1.0` lights a surface facing the
+/// light at full brightness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightingRig {
+    pub color: Color,
+    /// The main light, shining along `direction`.
+    pub key: f32,
+    /// A softer light from the opposite side to lift the key light's shadows.
+    pub fill: f32,
+    /// Light reaching every surface, whichever way it faces.
+    pub ambient: f32,
+    /// Which way the key light shines, in world space; need not be normalized. The
+    /// default comes from the upper right, in front of a camera looking down -z.
+    pub direction: Vec3,
+}
+
+impl Default for LightingRig {
+    fn default() -> Self {
+        LightingRig {
+            color: Color::WHITE,
+            key: 0.9,
+            fill: 0.3,
+            ambient: 0.15,
+            direction: Vec3::new(-1.0, -1.0, -1.0),
+        }
+    }
+}
+
+impl LightingRig {
+    /// A rig that lights everything evenly, close to what unshaded materials show.
+    pub fn flat() -> Self {
+        LightingRig {
+            key: 0.0,
+            fill: 0.0,
+            ambient: 1.0,
+            ..Default::default()
+        }
+    }
+
+    /// Positions and colors of the point lights making up the rig.
+    pub fn lights(&self) -> Vec<(Vec3, Color)> {
+        let toward = if self.direction.length_squared() > f32::EPSILON {
+            -self.direction.normalize()
+        } else {
+            Vec3::unit_z()
+        };
+        // The fill comes from the key's opposite side, turned about the view axis.
+        let fill = Vec3::new(-toward.x(), -toward.y(), toward.z());
+        let mut lights = vec![(toward, self.key), (fill, self.fill)];
+        // Diffuse shading has no ambient term to set, so light each axis from both
+        // sides: any surface then gets between 1 and √3 times `ambient`.
+        for axis in &[Vec3::unit_x(), Vec3::unit_y(), Vec3::unit_z()] {
+            lights.push((*axis, self.ambient));
+            lights.push((-*axis, self.ambient));
+        }
+        lights
+            .into_iter()
+            .filter(|&(_, intensity)| intensity > 0.0)
+            .map(|(toward, intensity)| (toward * LIGHT_DISTANCE, self.color * intensity))
+            .collect()
+    }
+}
+
+/// Marks a light spawned for the [`LightingRig`].
+pub struct RigLight;
+
+/// Lights the scene with the [`LightingRig`] resource, respawning the lights whenever
+/// it changes. Only materials with `shaded: true` are affected.
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<LightingRig>() {
+            app.init_resource::<LightingRig>();
+        }
+        app.add_system(lighting_system.system());
+    }
+}
+
+fn lighting_system(
+    mut commands: Commands,
+    rig: ChangedRes<LightingRig>,
+    lights: Query<With<RigLight, Entity>>,
+) {
+    for entity in lights.iter() {
+        commands.despawn(entity);
+    }
+    for (translation, color) in rig.lights() {
+        commands
+            .spawn(LightComponents {
+                light: Light {
+                    color,
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(translation),
+                ..Default::default()
+            })
+            .with(RigLight);
+    }
+}
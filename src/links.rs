@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::display::Poi;
+use crate::layers::{Collapsed, Layer};
+use crate::motion::PolarTween;
+use crate::style::LineStyle;
+use crate::theme::Theme;
+
+/// Points a connector's curve is drawn with.
+const LINK_SEGMENTS: usize = 24;
+
+/// A relationship between two targets, such as two reports of the same track or an
+/// escort and its charge, drawn as a curved connector between their markers. Spawn it
+/// on an entity of its own, or send [`TargetLinked`]; the connector follows both
+/// markers as they glide to new slots and is hidden while either is folded away or
+/// not shown at all.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetLink {
+    /// Target ids of the two ends.
+    pub from: i32,
+    pub to: i32,
+    pub style: LineStyle,
+    /// How far the middle of the curve bows out from the straight line, relative to
+    /// the distance between the ends; away from the origin for clarity against the
+    /// leader lines. 0 draws it straight.
+    pub bend: f32,
+}
+
+impl TargetLink {
+    pub fn new(from: i32, to: i32) -> Self {
+        TargetLink {
+            from,
+            to,
+            style: LineStyle::default(),
+            bend: 0.2,
+        }
+    }
+
+    /// Whether this link joins `a` and `b`, either way round.
+    pub fn joins(&self, a: i32, b: i32) -> bool {
+        (self.from, self.to) == (a, b) || (self.from, self.to) == (b, a)
+    }
+
+    /// The connector from `from` to `to` as a polyline.
+    pub fn curve(&self, from: Vec2, to: Vec2) -> Vec<Vec2> {
+        let mid = (from + to) / 2.0;
+        let along = to - from;
+        let mut normal = Vec2::new(-along.y(), along.x());
+        if normal.dot(mid) < 0.0 {
+            normal = -normal;
+        }
+        let control = mid + normal * self.bend;
+        (0..=LINK_SEGMENTS)
+            .map(|i| {
+                let t = i as f32 / LINK_SEGMENTS as f32;
+                from * (1.0 - t) * (1.0 - t) + control * 2.0 * t * (1.0 - t) + to * t * t
+            })
+            .collect()
+    }
+}
+
+/// Links two targets; a link that already joins them is replaced.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetLinked(pub TargetLink);
+
+/// Removes every link between the two target ids, either way round.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetUnlinked(pub i32, pub i32);
+
+/// Draws [`TargetLink`]s between the markers of the ring display and applies the
+/// [`TargetLinked`] and [`TargetUnlinked`] events. Connectors are on
+/// [`Layer::Leaders`].
+pub struct TargetLinksPlugin;
+
+impl Plugin for TargetLinksPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        app.add_event::<TargetLinked>()
+            .add_event::<TargetUnlinked>()
+            .add_system(link_event_system.system())
+            .add_system(link_system.system());
+    }
+}
+
+#[derive(Default)]
+struct LinkReaders {
+    linked: EventReader<TargetLinked>,
+    unlinked: EventReader<TargetUnlinked>,
+}
+
+fn link_event_system(
+    mut commands: Commands,
+    mut readers: Local<LinkReaders>,
+    linked: Res<Events<TargetLinked>>,
+    unlinked: Res<Events<TargetUnlinked>>,
+    mut links: Query<(Entity, Mut<TargetLink>)>,
+) {
+    for TargetLinked(link) in readers.linked.iter(&linked) {
+        match links
+            .iter_mut()
+            .find(|(_, existing)| existing.joins(link.from, link.to))
+        {
+            Some((_, mut existing)) => *existing = *link,
+            None => {
+                commands.spawn((*link,));
+            }
+        }
+    }
+    for TargetUnlinked(a, b) in readers.unlinked.iter(&unlinked) {
+        for (entity, link) in links.iter_mut() {
+            if link.joins(*a, *b) {
+                commands.despawn(entity);
+            }
+        }
+    }
+}
+
+// A connector is rebuilt when its link changes or either end moves, folds or comes and
+// goes; the rest keep their mesh.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn link_system(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    markers: Query<(&Poi, &PolarTween, Option<&Collapsed>)>,
+    moved: Query<With<Poi, (&Poi, Changed<PolarTween>)>>,
+    folded: Query<(&Poi, Changed<Collapsed>)>,
+    changed: Query<(Entity, Changed<TargetLink>)>,
+    mut links: Query<(
+        Entity,
+        &TargetLink,
+        Option<&Handle<Mesh>>,
+        Option<&Handle<ColorMaterial>>,
+        Option<Mut<Collapsed>>,
+    )>,
+) {
+    let ends = markers
+        .iter()
+        .map(|(poi, tween, collapsed)| (poi.id, (tween.point(), collapsed.is_some_and(|c| c.0))))
+        .collect::<HashMap<_, _>>();
+    let mut stale = moved
+        .iter()
+        .map(|(poi, _)| poi.id)
+        .chain(folded.iter().map(|(poi, _)| poi.id))
+        .collect::<HashSet<_>>();
+    // Markers come and go with re-layouts; that moves a marker as well, but not on
+    // removal.
+    if !markers.removed::<Poi>().is_empty() {
+        stale.extend(
+            links
+                .iter_mut()
+                .flat_map(|(_, link, ..)| [link.from, link.to]),
+        );
+    }
+    let relinked = changed
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect::<HashSet<_>>();
+
+    for (entity, link, mesh, material, collapsed) in links.iter_mut() {
+        let redraw = mesh.is_none()
+            || relinked.contains(&entity)
+            || stale.contains(&link.from)
+            || stale.contains(&link.to);
+        if !redraw {
+            continue;
+        }
+        let (from, to) = match (ends.get(&link.from), ends.get(&link.to)) {
+            (Some(&(from, false)), Some(&(to, false))) => (from, to),
+            _ => {
+                match collapsed {
+                    Some(mut collapsed) => collapsed.0 = true,
+                    None => {
+                        commands.insert_one(entity, Collapsed(true));
+                    }
+                }
+                continue;
+            }
+        };
+        match collapsed {
+            Some(mut collapsed) if collapsed.0 => collapsed.0 = false,
+            Some(_) => {}
+            None => {
+                commands.insert_one(entity, Collapsed(false));
+            }
+        }
+        let color = link.style.color.unwrap_or_else(|| theme.stroke());
+        let material = match material {
+            Some(material) => {
+                if materials.get(material).is_some_and(|m| m.color != color) {
+                    if let Some(m) = materials.get_mut(material) {
+                        m.color = color;
+                    }
+                }
+                material.clone()
+            }
+            None => materials.add(color.into()),
+        };
+        let line = link.style.stroke(
+            &link.curve(from, to),
+            link.style.width,
+            material,
+            &mut meshes,
+        );
+        match mesh {
+            Some(mesh) => {
+                if let Some(rebuilt) = meshes.remove(&line.mesh) {
+                    meshes.set(mesh, rebuilt);
+                }
+            }
+            None => {
+                commands
+                    .insert(entity, line)
+                    .insert_one(entity, Layer::Leaders);
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+
+use crate::display::{Poi, RefRing, Slot};
+use crate::layers::Collapsed;
+use crate::layout::{separation_angle, LayoutConfig};
+use crate::motion::PolarTween;
+
+/// Zoom level of detail for the ring display. Once adjacent rings would be drawn closer
+/// than `min_spacing_px` apart, consecutive rings are merged into one drawn at the
+/// outermost member's radius, and markers that would then overlap are folded into the
+/// first of them. Zooming back in expands them again.
+#[derive(Debug, Clone, Copy)]
+pub struct RingLod {
+    pub min_spacing_px: f32,
+}
+
+impl Default for RingLod {
+    fn default() -> Self {
+        RingLod {
+            min_spacing_px: 12.0,
+        }
+    }
+}
+
+impl RingLod {
+    /// How many rings are merged into one at the given on-screen ring spacing.
+    pub fn group_size(&self, spacing_px: f32) -> usize {
+        if spacing_px <= 0.0 {
+            1
+        } else {
+            (self.min_spacing_px / spacing_px).ceil().max(1.0) as usize
+        }
+    }
+}
+
+pub struct RingLodPlugin;
+
+impl Plugin for RingLodPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<RingLod>() {
+            app.init_resource::<RingLod>();
+        }
+        app.add_system(lod_system.system());
+    }
+}
+
+struct LodState {
+    group: usize,
+}
+
+impl Default for LodState {
+    fn default() -> Self {
+        LodState { group: 1 }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn lod_system(
+    mut state: Local<LodState>,
+    lod: Res<RingLod>,
+    config: Res<LayoutConfig>,
+    cameras: Query<(&Camera, &Transform)>,
+    mut rings: Query<(&RefRing, Mut<Collapsed>)>,
+    mut markers: Query<(&Slot, Mut<Collapsed>, Mut<PolarTween>, Mut<Poi>)>,
+    mut rigging: Query<Without<Poi, (&Slot, Mut<Collapsed>, Mut<PolarTween>)>>,
+) {
+    let scale = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, transform)) => transform.scale.x(),
+        None => return,
+    };
+    // Ring n sits at (n + 1) spacings from the origin.
+    let spacing = match rings.iter_mut().next() {
+        Some((ring, _)) => ring.radius / (ring.ring + 1) as f32,
+        None => return,
+    };
+    let group = lod.group_size(spacing / scale);
+    if group == state.group {
+        return;
+    }
+    state.group = group;
+
+    // Rings may be spaced unevenly (see `AdaptiveSpacing`), so merge onto the drawn
+    // radii, which differ between origins.
+    let radii = rings
+        .iter_mut()
+        .map(|(ring, _)| ((ring.origin, ring.ring), ring.radius))
+        .collect::<BTreeMap<_, _>>();
+    let last_ring = |origin: usize| {
+        radii
+            .range((origin, 0)..=(origin, usize::MAX))
+            .next_back()
+            .map_or(0, |(&(_, ring), _)| ring)
+    };
+    let merged_ring = |origin: usize, ring: usize| {
+        (
+            origin,
+            ((ring / group + 1) * group - 1).min(last_ring(origin)),
+        )
+    };
+    let radius_of = |key: (usize, usize)| {
+        radii
+            .get(&key)
+            .copied()
+            .unwrap_or(spacing * (key.1 + 1) as f32)
+    };
+    let merged_radius = |origin: usize, ring: usize| radius_of(merged_ring(origin, ring));
+
+    for (ring, mut collapsed) in rings.iter_mut() {
+        collapsed.0 = merged_ring(ring.origin, ring.ring).1 != ring.ring;
+    }
+
+    // Within each merged ring keep a marker only if it clears the last kept one by the
+    // merged ring's minimum angle.
+    let mut by_ring = BTreeMap::<(usize, usize), Vec<(f32, i32)>>::new();
+    for (slot, ..) in markers.iter_mut() {
+        by_ring
+            .entry(merged_ring(slot.origin, slot.ring))
+            .or_default()
+            .push((slot.azimuth, slot.id));
+    }
+    let mut folded = HashSet::new();
+    for (ring, mut slots) in by_ring {
+        let min_azi = separation_angle(config.marker_width(), radius_of(ring), config.scatter);
+        slots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut kept: Option<f32> = None;
+        for (azimuth, id) in slots {
+            match kept {
+                Some(k) if group > 1 && azimuth - k < min_azi => {
+                    folded.insert(id);
+                }
+                _ => kept = Some(azimuth),
+            }
+        }
+    }
+
+    for (slot, mut collapsed, mut tween, mut poi) in markers.iter_mut() {
+        let radius = merged_radius(slot.origin, slot.ring);
+        collapsed.0 = folded.contains(&slot.id);
+        tween.retarget(slot.azimuth, radius);
+        poi.center =
+            tween.origin + Vec2::new(radius * slot.azimuth.cos(), radius * slot.azimuth.sin());
+    }
+    for (slot, mut collapsed, mut tween) in rigging.iter_mut() {
+        collapsed.0 = folded.contains(&slot.id);
+        tween.retarget(slot.azimuth, merged_radius(slot.origin, slot.ring));
+    }
+}
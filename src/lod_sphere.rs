@@ -0,0 +1,417 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::camera::{Camera, PerspectiveProjection};
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+
+use crate::layers::Layer;
+
+/// Depth of the coarsest patches; the first split of each cube face puts the seam of
+/// the equirectangular texture along patch edges.
+const MIN_DEPTH: u8 = 1;
+/// Share of a patch's size its skirts hang down by, hiding the cracks where it meets a
+/// finer or coarser neighbour.
+const SKIRT: f32 = 0.05;
+
+/// On an entity with a sphere mesh of `radius` in the frame of
+/// [`sphere_mesh`](crate::mesh::sphere_mesh): [`LodSpherePlugin`] draws it instead as
+/// patches of a cube projected onto the sphere, each split into four finer ones where
+/// the camera comes close and merged again as it moves away, textured the same way. The
+/// patches take the entity's material and whether it is drawn, so swapping its material
+/// or hiding it works as before.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodSphere {
+    pub radius: f32,
+    /// Quads along each edge of a patch.
+    pub segments: u32,
+    /// Patches are split while their quads are larger than this many pixels across on
+    /// screen, and merged once they would be less than half of it.
+    pub max_pixels: f32,
+    /// Most times a cube face is split.
+    pub max_depth: u8,
+}
+
+impl LodSphere {
+    pub fn new(radius: f32) -> Self {
+        LodSphere {
+            radius,
+            segments: 16,
+            max_pixels: 24.0,
+            max_depth: 12,
+        }
+    }
+
+    /// The patches to draw for a camera at `eye` in the sphere's frame, `focal` pixels
+    /// away from the screen, given the patches split the frame before so that a patch
+    /// near the threshold isn't split and merged over and over.
+    pub fn leaves(&self, eye: Vec3, focal: f32, split: &HashSet<PatchId>) -> Vec<PatchId> {
+        let mut leaves = Vec::new();
+        let mut stack = (0..6)
+            .map(|face| PatchId {
+                face,
+                depth: 0,
+                x: 0,
+                y: 0,
+            })
+            .collect::<Vec<_>>();
+        while let Some(patch) = stack.pop() {
+            if patch.depth < MIN_DEPTH || self.splits(patch, eye, focal, split.contains(&patch)) {
+                stack.extend(patch.children().iter().copied());
+            } else {
+                leaves.push(patch);
+            }
+        }
+        leaves
+    }
+
+    fn splits(&self, patch: PatchId, eye: Vec3, focal: f32, was_split: bool) -> bool {
+        if patch.depth >= self.max_depth {
+            return false;
+        }
+        let samples = patch.samples().collect::<Vec<_>>();
+        // Patches wholly beyond the horizon stay coarse: those whose nearest point to
+        // the point below the camera is further from it than the horizon.
+        let center = patch.point(0.5, 0.5);
+        let spread = samples
+            .iter()
+            .map(|p| p.dot(center).clamp(-1.0, 1.0).acos())
+            .fold(0.0, f32::max);
+        let horizon = (self.radius / eye.length().max(self.radius)).acos();
+        let below = eye.normalize().dot(center).clamp(-1.0, 1.0).acos();
+        if below - spread > horizon {
+            return false;
+        }
+        let nearest = samples
+            .iter()
+            .map(|&p| (eye - p * self.radius).length())
+            .fold(f32::INFINITY, f32::min)
+            .max(f32::EPSILON);
+        let quad = self.radius * patch.size() / self.segments.max(1) as f32;
+        let pixels = quad / nearest * focal;
+        let threshold = if was_split {
+            self.max_pixels / 2.0
+        } else {
+            self.max_pixels
+        };
+        pixels > threshold
+    }
+}
+
+/// A patch of a [`LodSphere`]: square `(x, y)` of the 2^depth by 2^depth grid over cube
+/// face `face`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PatchId {
+    pub face: u8,
+    pub depth: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl PatchId {
+    pub fn children(&self) -> [PatchId; 4] {
+        let (face, depth, x, y) = (self.face, self.depth + 1, self.x * 2, self.y * 2);
+        [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| PatchId {
+            face,
+            depth,
+            x: x + dx,
+            y: y + dy,
+        })
+    }
+
+    /// Length of the patch's edge on the cube of side 2, about its length in radii on
+    /// the sphere.
+    fn size(&self) -> f32 {
+        2.0 / (1u32 << self.depth) as f32
+    }
+
+    /// The unit vector at `(s, t)` across the patch, each from 0 to 1.
+    fn point(&self, s: f32, t: f32) -> Vec3 {
+        let (normal, a, b) = face_axes(self.face);
+        let size = self.size();
+        let fa = -1.0 + (self.x as f32 + s) * size;
+        let fb = -1.0 + (self.y as f32 + t) * size;
+        (normal + a * fa + b * fb).normalize()
+    }
+
+    /// The patch's corners, the middles of its edges and its center, on the unit
+    /// sphere.
+    fn samples(&self) -> impl Iterator<Item = Vec3> + '_ {
+        (0..9).map(move |i| self.point((i % 3) as f32 / 2.0, (i / 3) as f32 / 2.0))
+    }
+
+    /// The patch on a sphere of `radius` as a grid of `segments` by `segments` quads,
+    /// facing outwards, with skirts hanging inwards from its edges and the texture
+    /// coordinates of [`sphere_mesh`](crate::mesh::sphere_mesh).
+    pub fn mesh(&self, radius: f32, segments: u32) -> Mesh {
+        patches_mesh(&[*self], radius, segments, true)
+    }
+}
+
+/// `patches` on a sphere of `radius` as one mesh, each as in [`PatchId::mesh`], and
+/// with its skirts only if `skirts`.
+pub(crate) fn patches_mesh(patches: &[PatchId], radius: f32, segments: u32, skirts: bool) -> Mesh {
+    let n = segments.max(1);
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    for patch in patches {
+        let base = positions.len() as u32;
+        let center = longitude(patch.point(0.5, 0.5)).rem_euclid(2.0 * PI);
+        let mut push = |p: Vec3, r: f32| {
+            // Keep the longitude of every vertex on the patch's side of the seam, and
+            // give the poles the patch's own.
+            let mut lon = longitude(p);
+            if p.x().abs() < f32::EPSILON && p.y().abs() < f32::EPSILON {
+                lon = center;
+            }
+            lon += (2.0 * PI) * ((center - lon) / (2.0 * PI)).round();
+            let lat = p.z().clamp(-1.0, 1.0).asin();
+            positions.push(<[f32; 3]>::from(p * r));
+            normals.push(<[f32; 3]>::from(p));
+            uvs.push([1.0 - lon / (2.0 * PI), (lat + PI / 2.0) / PI]);
+        };
+        let at = |i: u32, j: u32| patch.point(i as f32 / n as f32, j as f32 / n as f32);
+        for j in 0..=n {
+            for i in 0..=n {
+                push(at(i, j), radius);
+            }
+        }
+        let index = |i: u32, j: u32| base + j * (n + 1) + i;
+        for j in 0..n {
+            for i in 0..n {
+                let (a, b) = (index(i, j), index(i + 1, j));
+                let (c, d) = (index(i, j + 1), index(i + 1, j + 1));
+                indices.extend([a, b, d, a, d, c]);
+            }
+        }
+        if !skirts {
+            continue;
+        }
+        // The rim, once round, then the same lowered for the skirt.
+        let rim = (0..n)
+            .map(|i| (i, 0))
+            .chain((0..n).map(|j| (n, j)))
+            .chain((0..n).map(|i| (n - i, n)))
+            .chain((0..n).map(|j| (0, n - j)))
+            .collect::<Vec<_>>();
+        let skirt_start = base + (n + 1) * (n + 1);
+        let lowered = radius * (1.0 - SKIRT * patch.size());
+        for &(i, j) in &rim {
+            push(at(i, j), lowered);
+        }
+        let count = rim.len() as u32;
+        for k in 0..count {
+            let next = (k + 1) % count;
+            let top = |k: u32| {
+                let (i, j) = rim[k as usize];
+                index(i, j)
+            };
+            let (top, top_next) = (top(k), top(next));
+            let (low, low_next) = (skirt_start + k, skirt_start + next);
+            // Both sides, since the skirt is seen from outside the patch or from above.
+            indices.extend([top, low, low_next, top, low_next, top_next]);
+            indices.extend([top, low_next, low, top, top_next, low_next]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// The outward normal of cube face `face` and the two axes across it, with `a × b`
+/// the normal so that quads from `a` to `b` wind counter-clockwise seen from outside.
+fn face_axes(face: u8) -> (Vec3, Vec3, Vec3) {
+    let (x, y, z) = (Vec3::unit_x(), Vec3::unit_y(), Vec3::unit_z());
+    match face {
+        0 => (x, y, z),
+        1 => (-x, z, y),
+        2 => (y, z, x),
+        3 => (-y, x, z),
+        4 => (z, x, y),
+        _ => (-z, y, x),
+    }
+}
+
+/// Longitude of `p` in the sense of [`sphere_mesh`](crate::mesh::sphere_mesh)'s
+/// texture, in radians from +x towards +y.
+fn longitude(p: Vec3) -> f32 {
+    p.y().atan2(p.x())
+}
+
+/// A patch drawn for the [`LodSphere`] on `owner`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpherePatch {
+    pub owner: Entity,
+    pub id: PatchId,
+}
+
+/// On a [`LodSphere`] once [`LodSpherePlugin`] has taken over drawing it.
+#[derive(Debug, Clone, Default)]
+pub struct LodSphereState {
+    patches: HashMap<PatchId, Entity>,
+    split: HashSet<PatchId>,
+}
+
+impl LodSphereState {
+    /// The patches drawn at the moment.
+    pub fn patches(&self) -> impl Iterator<Item = PatchId> + '_ {
+        self.patches.keys().copied()
+    }
+}
+
+/// Draws each [`LodSphere`] as patches as fine as the 3D camera needs them, rebuilding
+/// only the patches split or merged since the frame before. The entity's own mesh is
+/// swapped for an empty one. Patches take its [`Layer`] if it has one.
+pub struct LodSpherePlugin;
+
+impl Plugin for LodSpherePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(lod_sphere_system.system())
+            .add_system(patch_sync_system.system());
+    }
+}
+
+/// A mesh of one triangle with no area, drawing nothing.
+fn empty_mesh() -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; 3].into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0f32, 0.0, 1.0]; 3].into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0f32; 2]; 3].into());
+    mesh.set_indices(Some(Indices::U32(vec![0, 1, 2])));
+    mesh
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn lod_sphere_system(
+    mut commands: Commands,
+    mut empty: Local<Option<Handle<Mesh>>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &PerspectiveProjection, &GlobalTransform)>,
+    mut spheres: Query<(
+        Entity,
+        &LodSphere,
+        &GlobalTransform,
+        &Handle<StandardMaterial>,
+        &Draw,
+        Option<&Layer>,
+        Option<Mut<LodSphereState>>,
+    )>,
+) {
+    let window_height = match windows.get_primary() {
+        Some(window) => window.height() as f32,
+        None => return,
+    };
+    let (projection, eye) = match cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some((_, projection, eye)) => (projection, eye),
+        None => return,
+    };
+    let focal = window_height / 2.0 / (projection.fov / 2.0).tan();
+    for (entity, sphere, transform, material, draw, layer, state) in spheres.iter_mut() {
+        let mut state = match state {
+            Some(state) => state,
+            None => {
+                let empty = empty.get_or_insert_with(|| meshes.add(empty_mesh()));
+                commands.insert(entity, (LodSphereState::default(), empty.clone()));
+                continue;
+            }
+        };
+        let local_eye = transform
+            .compute_matrix()
+            .inverse()
+            .transform_point3(eye.translation);
+        let leaves = sphere.leaves(local_eye, focal, &state.split);
+        let wanted = leaves.iter().copied().collect::<HashSet<_>>();
+        state.split = leaves
+            .iter()
+            .flat_map(|leaf| {
+                (MIN_DEPTH..leaf.depth).map(move |depth| {
+                    let shift = leaf.depth - depth;
+                    PatchId {
+                        face: leaf.face,
+                        depth,
+                        x: leaf.x >> shift,
+                        y: leaf.y >> shift,
+                    }
+                })
+            })
+            .collect();
+
+        let stale = state
+            .patches
+            .keys()
+            .filter(|id| !wanted.contains(id))
+            .copied()
+            .collect::<Vec<_>>();
+        for id in stale {
+            if let Some(patch) = state.patches.remove(&id) {
+                commands.despawn(patch);
+            }
+        }
+        for id in leaves {
+            if state.patches.contains_key(&id) {
+                continue;
+            }
+            commands
+                .spawn(PbrComponents {
+                    mesh: meshes.add(id.mesh(sphere.radius, sphere.segments)),
+                    material: material.clone(),
+                    draw: Draw {
+                        is_visible: draw.is_visible,
+                        is_transparent: draw.is_transparent,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(SpherePatch { owner: entity, id });
+            if let Some(layer) = layer {
+                commands.with(*layer);
+            }
+            if let Some(patch) = commands.current_entity() {
+                commands.push_children(entity, &[patch]);
+                state.patches.insert(id, patch);
+            }
+        }
+    }
+}
+
+/// Keeps patches drawn with their sphere's material and only while it is drawn, and
+/// despawns them with it.
+#[allow(clippy::type_complexity)]
+fn patch_sync_system(
+    mut commands: Commands,
+    spheres: Query<Without<SpherePatch, (&Handle<StandardMaterial>, &Draw)>>,
+    mut patches: Query<(
+        Entity,
+        &SpherePatch,
+        Mut<Handle<StandardMaterial>>,
+        Mut<Draw>,
+    )>,
+) {
+    for (entity, patch, mut material, mut draw) in patches.iter_mut() {
+        let (owner_material, owner_draw) = match spheres.get(patch.owner) {
+            Ok(owner) => owner,
+            Err(_) => {
+                commands.despawn(entity);
+                continue;
+            }
+        };
+        if *material != *owner_material {
+            *material = owner_material.clone();
+        }
+        if draw.is_visible != owner_draw.is_visible {
+            draw.is_visible = owner_draw.is_visible;
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::coords::CoordFormat;
+use crate::display::{LabelFont, Slot};
+use crate::events::DisplayEvent;
+use crate::geo::Polar;
+use crate::layers::Layer;
+use crate::layout::LayoutConfig;
+use crate::origins::SensorOrigins;
+use crate::target::Target;
+use crate::theme::Theme;
+use crate::undo::{EditCommand, UndoStack};
+
+const MEASURE_Z: f32 = 3.0;
+/// Radius of the dots marking the two ends.
+const END_RADIUS: f32 = 3.0;
+
+/// One end of a measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeasurePoint {
+    /// Where the end is drawn, in world space.
+    pub drawn: Vec2,
+    /// Where the end truly is, in target distance units with the world origin at the
+    /// origin of the first sensor. Other sensors are taken to be their offset away.
+    pub position: Vec2,
+    /// The target clicked, if any.
+    pub target: Option<i32>,
+}
+
+/// The state of [`MeasurePlugin`]'s measurement tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Measurement {
+    /// Whether clicks go to the tool; toggled with [`Action::Measure`].
+    pub active: bool,
+    pub from: Option<MeasurePoint>,
+    pub to: Option<MeasurePoint>,
+}
+
+impl Measurement {
+    /// Where `to` is from `from`, between their true positions.
+    pub fn offset(&self) -> Option<Polar> {
+        Some(Polar::from_cartesian(
+            self.to?.position - self.from?.position,
+        ))
+    }
+
+    /// Distance and compass bearing from `from` to `to` between their true positions,
+    /// the bearing in degrees clockwise from north.
+    pub fn range_bearing(&self) -> Option<(f32, f32)> {
+        self.offset().map(|offset| (offset.dist, offset.bearing()))
+    }
+}
+
+/// Marks the entities drawn by [`MeasurePlugin`].
+#[derive(Debug, Clone, Copy)]
+pub struct MeasurePart;
+
+/// A range and bearing tool for the 2D ring display. [`Action::Measure`], M unless
+/// rebound, turns it on and off; while on, clicking two points or two targets draws a
+/// line between them, labelled with the distance and bearing between their true
+/// positions rather than where the layout drew them, and sends
+/// [`DisplayEvent::MeasurementCompleted`]. A third click starts over and
+/// [`Action::Cancel`], Escape, clears the line. Free points are read back through
+/// [`LayoutConfig::scale`] around the first origin. Drawn on [`Layer::Overlays`].
+/// Changes to the line go on the [`UndoStack`] for
+/// [`UndoPlugin`](crate::undo::UndoPlugin).
+/// Needs [`PoiRingPlugin`](crate::display::PoiRingPlugin) and
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin).
+pub struct MeasurePlugin;
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<CoordFormat>() {
+            app.init_resource::<CoordFormat>();
+        }
+        if !app.resources().contains::<UndoStack>() {
+            app.init_resource::<UndoStack>();
+        }
+        if !app.resources().contains::<Measurement>() {
+            app.init_resource::<Measurement>();
+        }
+        add_actions(app);
+        app.add_system(measure_input_system.system())
+            .add_system(measure_draw_system.system());
+    }
+}
+
+/// The true position of the free point drawn at `world`.
+fn free_point(world: Vec2, config: &LayoutConfig, origins: &SensorOrigins) -> Vec2 {
+    let offset = origins.offset(0);
+    let drawn = world - offset;
+    let (azimuth, dist) = config.polar_at(drawn.x(), drawn.y());
+    offset + Polar::new(azimuth, dist).to_cartesian()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn measure_input_system(
+    mut reader: Local<EventReader<DisplayEvent>>,
+    actions: Res<ActionState>,
+    config: Res<LayoutConfig>,
+    origins: Res<SensorOrigins>,
+    mut events: ResMut<Events<DisplayEvent>>,
+    mut measurement: ResMut<Measurement>,
+    mut undo: ResMut<UndoStack>,
+    targets: Query<(&Target, &Slot)>,
+) {
+    let before = (measurement.from, measurement.to);
+    if actions.just_pressed(Action::Measure) {
+        measurement.active = !measurement.active;
+        tracing::info!(
+            "measurement {}",
+            if measurement.active { "on" } else { "off" }
+        );
+    }
+    if actions.just_pressed(Action::Cancel) {
+        measurement.from = None;
+        measurement.to = None;
+    }
+    let clicks = reader
+        .iter(&events)
+        .filter_map(|event| match event {
+            DisplayEvent::Clicked { world, target, .. } => Some((*world, *target)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if measurement.active {
+        measure_clicks(
+            &clicks,
+            &config,
+            &origins,
+            &mut events,
+            &mut measurement,
+            &targets,
+        );
+    }
+    let after = (measurement.from, measurement.to);
+    if after != before {
+        undo.push(EditCommand::Measure { before, after });
+    }
+}
+
+fn measure_clicks(
+    clicks: &[(Option<Vec2>, Option<i32>)],
+    config: &LayoutConfig,
+    origins: &SensorOrigins,
+    events: &mut Events<DisplayEvent>,
+    measurement: &mut Measurement,
+    targets: &Query<(&Target, &Slot)>,
+) {
+    for &(world, target) in clicks {
+        let clicked = target.and_then(|id| targets.iter().find(|(t, _)| t.id == id));
+        let point = match (clicked, world) {
+            (Some((target, slot)), _) => {
+                let offset = origins.offset(target.origin);
+                let polar = |azimuth: f32, radius: f32| {
+                    offset + Vec2::new(azimuth.cos(), azimuth.sin()) * radius
+                };
+                MeasurePoint {
+                    drawn: polar(slot.azimuth, slot.radius),
+                    position: polar(target.azimuth, target.dist),
+                    target: Some(target.id),
+                }
+            }
+            (None, Some(world)) => MeasurePoint {
+                drawn: world,
+                position: free_point(world, config, origins),
+                target: None,
+            },
+            (None, None) => continue,
+        };
+        if measurement.from.is_none() || measurement.to.is_some() {
+            measurement.from = Some(point);
+            measurement.to = None;
+            continue;
+        }
+        measurement.to = Some(point);
+        if let (Some(from), Some((distance, bearing))) =
+            (measurement.from, measurement.range_bearing())
+        {
+            events.send(DisplayEvent::MeasurementCompleted {
+                from: from.position,
+                to: point.position,
+                distance,
+                bearing,
+            });
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn measure_draw_system(
+    mut commands: Commands,
+    mut drawn: Local<Option<(Measurement, CoordFormat, Theme)>>,
+    measurement: Res<Measurement>,
+    format: Res<CoordFormat>,
+    theme: Res<Theme>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    parts: Query<With<MeasurePart, Entity>>,
+) {
+    let state = (*measurement, *format, *theme);
+    if drawn.as_ref() == Some(&state) {
+        return;
+    }
+    *drawn = Some(state);
+    for entity in parts.iter() {
+        commands.despawn(entity);
+    }
+    let material = materials.add(theme.stroke().into());
+    let ends = measurement.from.iter().chain(measurement.to.iter());
+    for end in ends {
+        let dot = primitive(
+            material.clone(),
+            &mut meshes,
+            ShapeType::Circle(END_RADIUS),
+            TessellationMode::Fill(&FillOptions::default()),
+            end.drawn.extend(MEASURE_Z),
+        );
+        commands.spawn(dot).with(MeasurePart).with(Layer::Overlays);
+    }
+    let (from, to, offset) = match (measurement.from, measurement.to, measurement.offset()) {
+        (Some(from), Some(to), Some(offset)) => (from.drawn, to.drawn, offset),
+        _ => return,
+    };
+    let mut builder = PathBuilder::new();
+    builder.move_to(point(from.x(), from.y()));
+    builder.line_to(point(to.x(), to.y()));
+    let line = builder.build().stroke(
+        material,
+        &mut meshes,
+        Vec3::new(0.0, 0.0, MEASURE_Z),
+        &StrokeOptions::default(),
+    );
+    commands.spawn(line).with(MeasurePart).with(Layer::Overlays);
+    commands
+        .spawn(TextComponents {
+            text: Text {
+                value: format.format_polar(offset),
+                font: asset_server.load(label_font.0),
+                style: TextStyle {
+                    font_size: 14.0,
+                    color: theme.text(),
+                },
+            },
+            transform: Transform::from_translation(((from + to) / 2.0).extend(MEASURE_Z)),
+            ..Default::default()
+        })
+        .with(MeasurePart)
+        .with(Layer::Overlays);
+}
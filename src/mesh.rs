@@ -0,0 +1,561 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::render::pipeline::PrimitiveTopology;
+
+use crate::lod_sphere::{patches_mesh, PatchId};
+
+/// A flat ring between `inner` and `outer` radius in the xy plane, seen from both
+/// sides, with `u` running from 0 at the inner edge to 1 at the outer one and `v` once
+/// around from +x.
+pub fn annulus_mesh(inner: f32, outer: f32, segments: u32) -> Mesh {
+    let segments = segments.max(3);
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    // The top face first, then the same vertices again facing down.
+    for normal in &[1.0, -1.0] {
+        for i in 0..=segments {
+            let v = i as f32 / segments as f32;
+            let (sin, cos) = (v * PI * 2.0).sin_cos();
+            for (radius, u) in &[(inner, 0.0), (outer, 1.0)] {
+                positions.push([radius * cos, radius * sin, 0.0]);
+                normals.push([0.0, 0.0, *normal]);
+                uvs.push([*u, v]);
+            }
+        }
+    }
+    let face = (segments + 1) * 2;
+    let mut indices = Vec::with_capacity((segments * 12) as usize);
+    for i in 0..segments {
+        let (a, b, c, d) = (i * 2, i * 2 + 1, i * 2 + 2, i * 2 + 3);
+        indices.extend(&[a, b, d, a, d, c]);
+        indices.extend(&[face + a, face + d, face + b, face + a, face + c, face + d]);
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// A UV sphere about the z axis with an equirectangular texture wrapped around it:
+/// `u` falling from 1 to 0 as longitude runs from +x towards +y, `v` rising from the
+/// -z pole to the +z pole.
+pub fn sphere_mesh(radius: f32, lat_counts: u32, lon_counts: u32) -> Mesh {
+    let lat_step = PI / lat_counts as f32;
+    let lon_step = PI * 2.0 / lon_counts as f32;
+    let vertex_count = ((lat_counts + 1) * (lon_counts + 1)) as usize;
+    let mut positions = Vec::with_capacity(vertex_count);
+    let mut normals = Vec::with_capacity(vertex_count);
+    let mut uvs = Vec::with_capacity(vertex_count);
+    for lon in 0..=lon_counts {
+        let theta = lon_step * lon as f32;
+        for lat in 0..=lat_counts {
+            let azu = -PI / 2.0 + lat_step * lat as f32;
+            let pos = Vec3::new(
+                radius * theta.cos() * azu.cos(),
+                radius * theta.sin() * azu.cos(),
+                radius * azu.sin(),
+            );
+            positions.push([pos.x(), pos.y(), pos.z()]);
+            let n = pos.normalize();
+            normals.push([n.x(), n.y(), n.z()]);
+            uvs.push([
+                1.0 - lon as f32 / lon_counts as f32,
+                lat as f32 / lat_counts as f32,
+            ])
+        }
+    }
+    let mut indices = Vec::with_capacity((lon_counts * lat_counts) as usize);
+    for lon in 0..lon_counts {
+        let idx = lon * (lat_counts + 1);
+        for lat in 0..lat_counts {
+            let idx = idx + lat;
+            if lat < lat_counts {
+                indices.extend(vec![idx, idx + lat_counts + 1, idx + 1]);
+            }
+            if lat > 0 {
+                indices.extend(vec![idx, idx + lat_counts, idx + lat_counts + 1]);
+            }
+        }
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Name of the vertex tangents added by [`generate_tangents`].
+pub const ATTRIBUTE_TANGENT: &str = "Vertex_Tangent";
+
+/// Adds [`ATTRIBUTE_TANGENT`] to a mesh with positions, normals and texture
+/// coordinates, such as [`sphere_mesh`]: at each vertex, the way `u` rises along the
+/// surface, averaged over the triangles meeting there, with `w` the bitangent's
+/// handedness, so that `cross(normal, tangent.xyz) * w` is the way `v` rises. Meshes
+/// missing any of those are left as they are.
+pub fn generate_tangents(mesh: &mut Mesh) {
+    let (positions, normals, uvs) = match (
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+        mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+        mesh.attribute(Mesh::ATTRIBUTE_UV_0),
+    ) {
+        (
+            Some(VertexAttributeValues::Float3(positions)),
+            Some(VertexAttributeValues::Float3(normals)),
+            Some(VertexAttributeValues::Float2(uvs)),
+        ) => (positions, normals, uvs),
+        _ => return,
+    };
+    let indices = match mesh.indices() {
+        Some(Indices::U32(indices)) => indices.iter().map(|&i| i as usize).collect(),
+        Some(Indices::U16(indices)) => indices.iter().map(|&i| i as usize).collect(),
+        None => (0..positions.len()).collect::<Vec<_>>(),
+    };
+    let mut tangents = vec![Vec3::zero(); positions.len()];
+    let mut bitangents = vec![Vec3::zero(); positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+        let (e1, e2) = (
+            Vec3::from(positions[b]) - Vec3::from(positions[a]),
+            Vec3::from(positions[c]) - Vec3::from(positions[a]),
+        );
+        let (du1, dv1) = (uvs[b][0] - uvs[a][0], uvs[b][1] - uvs[a][1]);
+        let (du2, dv2) = (uvs[c][0] - uvs[a][0], uvs[c][1] - uvs[a][1]);
+        let det = du1 * dv2 - du2 * dv1;
+        // Triangles squashed flat in the texture, as at a UV sphere's poles, say nothing
+        // about its directions.
+        if det.abs() <= f32::EPSILON {
+            continue;
+        }
+        let tangent = (e1 * dv2 - e2 * dv1) / det;
+        let bitangent = (e2 * du1 - e1 * du2) / det;
+        for &i in &[a, b, c] {
+            tangents[i] += tangent;
+            bitangents[i] += bitangent;
+        }
+    }
+    let tangents = normals
+        .iter()
+        .zip(tangents.iter().zip(&bitangents))
+        .map(|(&normal, (&tangent, &bitangent))| {
+            let normal = Vec3::from(normal);
+            // Square the tangent up with the normal, or pick any perpendicular where no
+            // triangle gave one.
+            let mut tangent = tangent - normal * normal.dot(tangent);
+            if tangent.length_squared() <= f32::EPSILON {
+                let axis = if normal.x().abs() < 0.9 {
+                    Vec3::unit_x()
+                } else {
+                    Vec3::unit_y()
+                };
+                tangent = normal.cross(axis);
+            }
+            let tangent = tangent.normalize();
+            let w = if normal.cross(tangent).dot(bitangent) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            [tangent.x(), tangent.y(), tangent.z(), w]
+        })
+        .collect::<Vec<_>>();
+    mesh.set_attribute(ATTRIBUTE_TANGENT, tangents.into());
+}
+
+/// A cube with each face cut into `resolution` by `resolution` quads, rounded up to an
+/// even number, and pushed out onto a sphere of `radius`: quads of about the same size
+/// all over, without the pinching of [`sphere_mesh`] at the poles. It is textured the
+/// same way, each face built in quarters so that the texture's seam runs between them.
+pub fn cube_sphere_mesh(radius: f32, resolution: u32) -> Mesh {
+    let patches = (0..6)
+        .flat_map(|face| {
+            (0..4).map(move |i| PatchId {
+                face,
+                depth: 1,
+                x: i % 2,
+                y: i / 2,
+            })
+        })
+        .collect::<Vec<_>>();
+    patches_mesh(&patches, radius, resolution.max(1).div_ceil(2), false)
+}
+
+/// A flat disc of `radius` in the xy plane, seen from both sides, textured as if cut
+/// out of a square image laid over it with +x to the right and +y up.
+pub fn disk_mesh(radius: f32, segments: u32) -> Mesh {
+    let mut builder = MeshBuilder::default();
+    builder.fan(Vec3::zero(), radius, 1.0, segments);
+    builder.fan(Vec3::zero(), radius, -1.0, segments);
+    builder.build()
+}
+
+/// A cylinder of `radius` about the z axis, `height` long and centred on the origin,
+/// with `u` running once around its side from +x towards +y and `v` from the -z end to
+/// the +z one. With `caps`, both ends are closed with discs textured like
+/// [`disk_mesh`].
+pub fn cylinder_mesh(radius: f32, height: f32, segments: u32, caps: bool) -> Mesh {
+    let half = height / 2.0;
+    let mut builder = MeshBuilder::default();
+    builder.lathe(
+        &[
+            ProfilePoint::new(radius, -half, Vec2::unit_x(), 0.0),
+            ProfilePoint::new(radius, half, Vec2::unit_x(), 1.0),
+        ],
+        segments,
+    );
+    if caps {
+        builder.fan(Vec3::new(0.0, 0.0, half), radius, 1.0, segments);
+        builder.fan(Vec3::new(0.0, 0.0, -half), radius, -1.0, segments);
+    }
+    builder.build()
+}
+
+/// A capsule about the z axis: a cylinder of `radius`, `length` long between the
+/// centres of the half spheres closing either end, centred on the origin. Each half
+/// sphere has `rings` rows of quads. `u` runs once around from +x towards +y, `v` from
+/// the -z tip to the +z one in proportion to the distance along the surface.
+pub fn capsule_mesh(radius: f32, length: f32, segments: u32, rings: u32) -> Mesh {
+    let (half, rings) = (length.max(0.0) / 2.0, rings.max(1));
+    let total = PI * radius + length.max(0.0);
+    let mut profile = Vec::with_capacity(2 * rings as usize + 2);
+    for (center, from) in &[(-half, -PI / 2.0), (half, 0.0)] {
+        for ring in 0..=rings {
+            let angle = from + PI / 2.0 * ring as f32 / rings as f32;
+            let (sin, cos) = angle.sin_cos();
+            // Distance along the surface from the -z tip.
+            let along = if *center < 0.0 {
+                radius * (angle + PI / 2.0)
+            } else {
+                radius * PI / 2.0 + length.max(0.0) + radius * angle
+            };
+            profile.push(ProfilePoint::new(
+                radius * cos,
+                center + radius * sin,
+                Vec2::new(cos, sin),
+                along / total.max(f32::EPSILON),
+            ));
+        }
+    }
+    let mut builder = MeshBuilder::default();
+    builder.lathe(&profile, segments);
+    builder.build()
+}
+
+/// A torus about the z axis, its tube of radius `minor` running in a circle of radius
+/// `major` in the xy plane, with `u` once around the z axis from +x towards +y and `v`
+/// once around the tube from its outer edge, over the top first.
+pub fn torus_mesh(major: f32, minor: f32, segments: u32, sides: u32) -> Mesh {
+    let sides = sides.max(3);
+    let profile = (0..=sides)
+        .map(|side| {
+            let v = side as f32 / sides as f32;
+            let (sin, cos) = (v * PI * 2.0).sin_cos();
+            ProfilePoint::new(major + minor * cos, minor * sin, Vec2::new(cos, sin), v)
+        })
+        .collect::<Vec<_>>();
+    let mut builder = MeshBuilder::default();
+    builder.lathe(&profile, segments);
+    builder.build()
+}
+
+/// A point of the outline [`MeshBuilder::lathe`] turns about the z axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProfilePoint {
+    /// Distance from the z axis.
+    radius: f32,
+    z: f32,
+    /// The outward normal, away from the axis in x and along it in y.
+    normal: Vec2,
+    v: f32,
+}
+
+impl ProfilePoint {
+    fn new(radius: f32, z: f32, normal: Vec2, v: f32) -> Self {
+        ProfilePoint {
+            radius,
+            z,
+            normal: normal.normalize(),
+            v,
+        }
+    }
+}
+
+/// Vertices and triangles gathered for a [`Mesh`].
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    fn vertex(&mut self, position: Vec3, normal: Vec3, uv: [f32; 2]) -> u32 {
+        self.positions.push(position.into());
+        self.normals.push(normal.into());
+        self.uvs.push(uv);
+        self.positions.len() as u32 - 1
+    }
+
+    /// Turns `profile` once about the z axis in `segments` steps, starting at +x. Drawn
+    /// with the distance from the axis to the right and z up, the profile has the
+    /// surface's outside on its right going from one point to the next, as going up
+    /// the side of a cylinder. The seam's vertices are repeated for the texture to
+    /// wrap.
+    fn lathe(&mut self, profile: &[ProfilePoint], segments: u32) {
+        let segments = segments.max(3);
+        let rows = profile.len() as u32;
+        let base = self.positions.len() as u32;
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let (sin, cos) = (u * PI * 2.0).sin_cos();
+            for point in profile {
+                self.vertex(
+                    Vec3::new(point.radius * cos, point.radius * sin, point.z),
+                    Vec3::new(
+                        point.normal.x() * cos,
+                        point.normal.x() * sin,
+                        point.normal.y(),
+                    ),
+                    [u, point.v],
+                );
+            }
+        }
+        for segment in 0..segments {
+            for row in 0..rows.saturating_sub(1) {
+                let a = base + segment * rows + row;
+                let (b, c, d) = (a + rows, a + rows + 1, a + 1);
+                self.indices.extend(&[a, b, c, a, c, d]);
+            }
+        }
+    }
+
+    /// A disc of `radius` about `center` in a plane of constant z, facing +z if
+    /// `facing` is positive and -z otherwise.
+    fn fan(&mut self, center: Vec3, radius: f32, facing: f32, segments: u32) {
+        let segments = segments.max(3);
+        let normal = Vec3::new(0.0, 0.0, facing.signum());
+        let middle = self.vertex(center, normal, [0.5, 0.5]);
+        for segment in 0..=segments {
+            let (sin, cos) = (segment as f32 / segments as f32 * PI * 2.0).sin_cos();
+            self.vertex(
+                center + Vec3::new(radius * cos, radius * sin, 0.0),
+                normal,
+                [(1.0 + cos) / 2.0, (1.0 - sin) / 2.0],
+            );
+        }
+        for segment in 0..segments {
+            let (a, b) = (middle + 1 + segment, middle + 2 + segment);
+            if facing > 0.0 {
+                self.indices.extend(&[middle, a, b]);
+            } else {
+                self.indices.extend(&[middle, b, a]);
+            }
+        }
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs.into());
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh
+    }
+}
+
+/// A sphere of `radius` made by splitting each face of an icosahedron `divisions`
+/// times, of nearly even triangles all over.
+pub fn icosphere_mesh(radius: f32, divisions: usize) -> Mesh {
+    use hexasphere::IcoSphere;
+    let hexasphere = IcoSphere::new(divisions, |point| {
+        let inclination = if point.x() > 0.0 {
+            point.z().acos()
+        } else {
+            PI * 2.0 - point.z().acos()
+        };
+        let azumith = Vec2::new(point.x(), point.z()).length().acos() * point.y().signum();
+        //let azumith = point.y().atan2(point.x().abs());
+
+        let norm_inclination = inclination / (PI * 2.0);
+        let norm_azumith = (azumith / PI) + 0.5;
+
+        [norm_inclination, norm_azumith]
+    });
+    let raw_points = hexasphere.raw_points();
+
+    let points = raw_points
+        .iter()
+        .map(|&p| (p * radius).into())
+        .collect::<Vec<[f32; 3]>>();
+
+    let normals = raw_points
+        .iter()
+        .copied()
+        .map(Into::into)
+        .collect::<Vec<[f32; 3]>>();
+
+    let uvs = hexasphere.raw_data().to_owned();
+
+    let mut indices = Vec::with_capacity(hexasphere.indices_per_main_triangle() * 20);
+
+    for i in 0..20 {
+        hexasphere.get_indices(i, &mut indices);
+    }
+
+    let indices = Indices::U32(indices);
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_indices(Some(indices));
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, points.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn float3(mesh: &Mesh, name: &'static str) -> Vec<Vec3> {
+        match mesh.attribute(name) {
+            Some(VertexAttributeValues::Float3(values)) => {
+                values.iter().map(|&v| Vec3::from(v)).collect()
+            }
+            _ => panic!("mesh has no {}", name),
+        }
+    }
+
+    fn indices(mesh: &Mesh) -> Vec<usize> {
+        match mesh.indices() {
+            Some(Indices::U32(indices)) => indices.iter().map(|&i| i as usize).collect(),
+            _ => panic!("mesh has no u32 indices"),
+        }
+    }
+
+    /// Checks that `mesh` is indexed in whole triangles within its vertices, has unit
+    /// normals and texture coordinates in range, and that every triangle of any size
+    /// is wound counter-clockwise seen from the side its vertices' normals face.
+    /// Returns its signed volume, positive for a closed mesh facing outwards.
+    fn check(mesh: &Mesh) -> f32 {
+        let positions = float3(mesh, Mesh::ATTRIBUTE_POSITION);
+        let normals = float3(mesh, Mesh::ATTRIBUTE_NORMAL);
+        let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float2(uvs)) => uvs.clone(),
+            _ => panic!("mesh has no uvs"),
+        };
+        assert_eq!(normals.len(), positions.len());
+        assert_eq!(uvs.len(), positions.len());
+        for normal in &normals {
+            assert!((normal.length() - 1.0).abs() < 1e-4, "normal {:?}", normal);
+        }
+        for uv in &uvs {
+            assert!(
+                uv.iter().all(|c| (-1e-5..=1.0 + 1e-5).contains(c)),
+                "uv {:?}",
+                uv
+            );
+        }
+        let indices = indices(mesh);
+        assert_eq!(indices.len() % 3, 0);
+        assert!(indices.iter().all(|&i| i < positions.len()));
+        let mut volume = 0.0;
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+            let [pa, pb, pc] = [positions[a], positions[b], positions[c]];
+            let face = (pb - pa).cross(pc - pa);
+            volume += pa.dot(pb.cross(pc)) / 6.0;
+            if face.length() <= 1e-6 {
+                continue;
+            }
+            let facing = normals[a] + normals[b] + normals[c];
+            assert!(
+                face.dot(facing) > 0.0,
+                "triangle {:?} at {:?} is wound against its normals",
+                triangle,
+                [pa, pb, pc]
+            );
+        }
+        volume
+    }
+
+    fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn spheres_are_closed_and_face_outwards() {
+        let sphere = 4.0 / 3.0 * PI * 8.0;
+        assert_close(check(&sphere_mesh(2.0, 32, 128)), sphere, 0.02);
+        assert_close(check(&cube_sphere_mesh(2.0, 16)), sphere, 0.02);
+        assert_close(check(&icosphere_mesh(2.0, 8)), sphere, 0.02);
+    }
+
+    #[test]
+    fn cylinder_with_caps_is_closed() {
+        assert_close(check(&cylinder_mesh(1.0, 3.0, 64, true)), PI * 3.0, 0.01);
+    }
+
+    #[test]
+    fn cylinder_without_caps_is_open_at_both_ends() {
+        let mesh = cylinder_mesh(1.0, 3.0, 16, false);
+        check(&mesh);
+        let positions = float3(&mesh, Mesh::ATTRIBUTE_POSITION);
+        assert!(positions
+            .iter()
+            .all(|p| (Vec2::new(p.x(), p.y()).length() - 1.0).abs() < 1e-5));
+    }
+
+    #[test]
+    fn capsule_is_a_cylinder_between_half_spheres() {
+        let expected = PI * 2.0 + 4.0 / 3.0 * PI;
+        assert_close(check(&capsule_mesh(1.0, 2.0, 64, 16)), expected, 0.02);
+        let positions = float3(&capsule_mesh(1.0, 2.0, 8, 4), Mesh::ATTRIBUTE_POSITION);
+        let top = positions.iter().map(|p| p.z()).fold(f32::MIN, f32::max);
+        assert_close(top, 2.0, 1e-5);
+    }
+
+    #[test]
+    fn torus_is_closed_and_faces_outwards() {
+        let expected = 2.0 * PI * PI * 2.0 * 0.25;
+        assert_close(check(&torus_mesh(2.0, 0.5, 64, 32)), expected, 0.02);
+    }
+
+    #[test]
+    fn annulus_and_disk_face_both_ways() {
+        for mesh in &[annulus_mesh(0.5, 1.0, 32), disk_mesh(1.0, 32)] {
+            assert_close(check(mesh).abs() + 1.0, 1.0, 1e-5);
+            let normals = float3(mesh, Mesh::ATTRIBUTE_NORMAL);
+            let up = normals.iter().filter(|n| n.z() > 0.0).count();
+            assert_eq!(up * 2, normals.len());
+        }
+    }
+
+    #[test]
+    fn tangents_lie_along_the_surface() {
+        let mut mesh = torus_mesh(2.0, 0.5, 16, 8);
+        generate_tangents(&mut mesh);
+        let normals = float3(&mesh, Mesh::ATTRIBUTE_NORMAL);
+        let tangents = match mesh.attribute(ATTRIBUTE_TANGENT) {
+            Some(VertexAttributeValues::Float4(tangents)) => tangents.clone(),
+            _ => panic!("mesh has no tangents"),
+        };
+        for (normal, tangent) in normals.iter().zip(&tangents) {
+            let along = Vec3::new(tangent[0], tangent[1], tangent[2]);
+            assert!(normal.dot(along).abs() < 1e-4);
+            assert!((along.length() - 1.0).abs() < 1e-4);
+            assert_eq!(tangent[3].abs(), 1.0);
+        }
+    }
+}
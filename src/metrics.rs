@@ -0,0 +1,270 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+/// Operational counters for long-running displays. Systems that ingest targets or run
+/// the layout report into this resource; [`MetricsPlugin`] periodically exports it.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    ingested_total: u64,
+    ingested_since_export: u64,
+    active_tracks: usize,
+    layout_latency: Option<Duration>,
+    feed_latency: Option<Duration>,
+    frame_time_sum: f64,
+    frame_count: u64,
+}
+
+impl Metrics {
+    pub fn record_ingest(&mut self, count: usize) {
+        self.ingested_total += count as u64;
+        self.ingested_since_export += count as u64;
+    }
+
+    pub fn set_active_tracks(&mut self, count: usize) {
+        self.active_tracks = count;
+    }
+
+    pub fn record_layout(&mut self, latency: Duration) {
+        self.layout_latency = Some(latency);
+    }
+
+    /// How long the latest feed messages waited between arriving and being applied.
+    pub fn record_feed(&mut self, latency: Duration) {
+        self.feed_latency = Some(latency);
+    }
+
+    pub fn active_tracks(&self) -> usize {
+        self.active_tracks
+    }
+
+    pub fn layout_latency(&self) -> Option<Duration> {
+        self.layout_latency
+    }
+
+    pub fn feed_latency(&self) -> Option<Duration> {
+        self.feed_latency
+    }
+
+    fn snapshot(&mut self, interval: f64) -> MetricsSnapshot {
+        let frame_time = if self.frame_count > 0 {
+            self.frame_time_sum / self.frame_count as f64
+        } else {
+            0.0
+        };
+        let snapshot = MetricsSnapshot {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or_default(),
+            ingested_total: self.ingested_total,
+            ingest_rate: if interval > 0.0 {
+                self.ingested_since_export as f64 / interval
+            } else {
+                0.0
+            },
+            active_tracks: self.active_tracks,
+            layout_latency_ms: self.layout_latency.map(|d| d.as_secs_f64() * 1000.0),
+            feed_latency_ms: self.feed_latency.map(|d| d.as_secs_f64() * 1000.0),
+            frame_time_ms: frame_time * 1000.0,
+        };
+        self.ingested_since_export = 0;
+        self.frame_time_sum = 0.0;
+        self.frame_count = 0;
+        snapshot
+    }
+}
+
+/// The latest samples of a duration, for their mean and worst case.
+#[derive(Debug, Clone, Default)]
+pub struct TimeCounter {
+    samples: VecDeque<Duration>,
+    total: u64,
+}
+
+impl TimeCounter {
+    /// How many of the latest samples are kept.
+    pub const WINDOW: usize = 120;
+
+    pub fn record(&mut self, sample: Duration) {
+        if self.samples.len() == Self::WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        self.total += 1;
+    }
+
+    /// Samples recorded since the start, kept or not.
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    pub fn latest(&self) -> Option<Duration> {
+        self.samples.back().copied()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let sum = self.samples.iter().sum::<Duration>();
+        (!self.samples.is_empty()).then(|| sum / self.samples.len() as u32)
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.samples.iter().max().copied()
+    }
+}
+
+/// Frame times and how long each relayout of
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin) took, over the last
+/// [`TimeCounter::WINDOW`] of each, shown by
+/// [`DebugOverlayPlugin`](crate::debug_overlay::DebugOverlayPlugin).
+#[derive(Debug, Clone, Default)]
+pub struct PerfCounters {
+    pub frame: TimeCounter,
+    pub relayout: TimeCounter,
+}
+
+/// Adds [`PerfCounters`] and the system timing frames into them, unless already added.
+pub(crate) fn add_perf_counters(app: &mut AppBuilder) {
+    if app.resources().contains::<PerfCounters>() {
+        return;
+    }
+    app.init_resource::<PerfCounters>()
+        .add_system(perf_frame_system.system());
+}
+
+fn perf_frame_system(time: Res<Time>, mut counters: ResMut<PerfCounters>) {
+    if time.delta_seconds_f64 > 0.0 {
+        counters.frame.record(time.delta);
+    }
+}
+
+/// One exported sample. Rates and the frame time are averaged over the export interval.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub timestamp: f64,
+    pub ingested_total: u64,
+    pub ingest_rate: f64,
+    pub active_tracks: usize,
+    pub layout_latency_ms: Option<f64>,
+    pub feed_latency_ms: Option<f64>,
+    pub frame_time_ms: f64,
+}
+
+impl MetricsSnapshot {
+    /// Prometheus text exposition format, suitable for the node_exporter textfile collector.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, value: f64| {
+            let _ = writeln!(out, "# TYPE debris_{} {}", name, kind);
+            let _ = writeln!(out, "debris_{} {}", name, value);
+        };
+        metric("ingested_total", "counter", self.ingested_total as f64);
+        metric("ingest_rate", "gauge", self.ingest_rate);
+        metric("active_tracks", "gauge", self.active_tracks as f64);
+        if let Some(latency) = self.layout_latency_ms {
+            metric("layout_latency_ms", "gauge", latency);
+        }
+        if let Some(latency) = self.feed_latency_ms {
+            metric("feed_latency_ms", "gauge", latency);
+        }
+        metric("frame_time_ms", "gauge", self.frame_time_ms);
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsFormat {
+    Json,
+    Prometheus,
+}
+
+/// Where and how often [`MetricsPlugin`] writes snapshots.
+#[derive(Debug, Clone)]
+pub struct MetricsExport {
+    pub path: PathBuf,
+    pub format: MetricsFormat,
+    pub interval: f32,
+}
+
+impl MetricsExport {
+    /// Exports every `interval` seconds, picking Prometheus format for `.prom` files and
+    /// JSON otherwise.
+    pub fn new(path: impl Into<PathBuf>, interval: f32) -> Self {
+        let path = path.into();
+        let format = match path.extension().and_then(|e| e.to_str()) {
+            Some("prom") => MetricsFormat::Prometheus,
+            _ => MetricsFormat::Json,
+        };
+        MetricsExport {
+            path,
+            format,
+            interval,
+        }
+    }
+
+    fn write(&self, snapshot: &MetricsSnapshot) -> io::Result<()> {
+        let contents = match self.format {
+            MetricsFormat::Json => {
+                serde_json::to_string_pretty(snapshot).map_err(io::Error::other)?
+            }
+            MetricsFormat::Prometheus => snapshot.to_prometheus(),
+        };
+        // Write next to the target and rename so readers never see a partial file.
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Collects frame time into [`Metrics`] and, if configured, exports it periodically.
+#[derive(Default)]
+pub struct MetricsPlugin {
+    pub export: Option<MetricsExport>,
+}
+
+struct ExportTimer(Timer);
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Metrics>()
+            .add_system(frame_time_system.system());
+        if let Some(export) = &self.export {
+            app.add_resource(export.clone())
+                .add_resource(ExportTimer(Timer::from_seconds(export.interval, true)))
+                .add_system(export_system.system());
+        }
+    }
+}
+
+fn frame_time_system(time: Res<Time>, mut metrics: ResMut<Metrics>) {
+    if time.delta_seconds_f64 > 0.0 {
+        metrics.frame_time_sum += time.delta_seconds_f64;
+        metrics.frame_count += 1;
+    }
+}
+
+fn export_system(
+    time: Res<Time>,
+    export: Res<MetricsExport>,
+    mut timer: ResMut<ExportTimer>,
+    mut metrics: ResMut<Metrics>,
+) {
+    timer.0.tick(time.delta_seconds);
+    if !timer.0.just_finished {
+        return;
+    }
+    let snapshot = metrics.snapshot(export.interval as f64);
+    if let Err(e) = export.write(&snapshot) {
+        tracing::error!(
+            "failed to write metrics to {}: {}",
+            export.path.display(),
+            e
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::events::DisplayEvent;
+use crate::layout::{LayoutConfig, LayoutDiagnostics};
+use crate::pointer::{screen_to_world, CursorPosition};
+use crate::split_view::ViewRect;
+use crate::theme::Theme;
+use crate::viewport::DisplayViewport;
+
+/// Name of the camera [`MinimapPlugin`] draws the overview with, to list in
+/// [`SplitViewPlugin::cameras`](crate::split_view::SplitViewPlugin::cameras).
+pub const MINIMAP_CAMERA: &str = "minimap";
+/// Thickness of the inset's frame and of the outline of the main view, in pixels.
+const LINE_WIDTH: f32 = 1.0;
+
+/// Where [`MinimapPlugin`] puts its overview, and when.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minimap {
+    /// The inset's rectangle, in fractions of the window.
+    pub rect: ViewRect,
+    /// Show the inset only while part of the rings is out of the main view.
+    pub auto_hide: bool,
+    /// Room left around the rings inside the inset, in pixels.
+    pub margin: f32,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Minimap {
+            rect: ViewRect::new(Vec2::new(0.79, 0.62), Vec2::new(0.2, 0.36)),
+            auto_hide: true,
+            margin: 6.0,
+        }
+    }
+}
+
+/// One side of the inset's frame or of the outline of the main view in it: bevy_ui
+/// nodes, so that they are drawn over both views and show in neither.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MinimapLine {
+    /// The main view's outline rather than the frame.
+    view: bool,
+    side: usize,
+}
+
+/// An overview of the whole ring set in a corner of the window while the 2D view is
+/// zoomed into part of it, framed, with the part the main view shows outlined.
+/// Clicking or dragging in it moves the main view to center there, and clicks there
+/// don't reach the main view. Needs a
+/// [`SplitViewPlugin`](crate::split_view::SplitViewPlugin) listing [`MINIMAP_CAMERA`],
+/// and [`PoiRingPlugin`](crate::display::PoiRingPlugin) and
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin).
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<Minimap>() {
+            app.init_resource::<Minimap>();
+        }
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        if !app.resources().contains::<LayoutDiagnostics>() {
+            app.init_resource::<LayoutDiagnostics>();
+        }
+        app.add_startup_system(minimap_setup_system.system())
+            .add_system(minimap_click_system.system())
+            .add_system(minimap_system.system());
+    }
+}
+
+fn minimap_setup_system(
+    mut commands: Commands,
+    minimap: Res<Minimap>,
+    theme: Res<Theme>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mut camera = Camera2dComponents::default();
+    camera.camera.name = Some(MINIMAP_CAMERA.to_string());
+    commands
+        .spawn(camera)
+        .with(ViewRect::new(minimap.rect.origin, Vec2::zero()));
+    let material = materials.add(theme.stroke().into());
+    for &view in &[false, true] {
+        for side in 0..4 {
+            commands
+                .spawn(NodeComponents {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    material: material.clone(),
+                    draw: Draw {
+                        is_visible: false,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(MinimapLine { view, side });
+        }
+    }
+}
+
+/// How far out the rings and their outermost markers reach, in world units.
+fn ring_extent(config: &LayoutConfig, diagnostics: &LayoutDiagnostics) -> f32 {
+    let outermost = diagnostics
+        .rings
+        .iter()
+        .map(|ring| ring.radius)
+        .fold(0.0, f32::max);
+    outermost + config.footprint()
+}
+
+/// World units per inset pixel to show the rings out to `extent` in an inset of `size`
+/// pixels.
+fn inset_scale(extent: f32, size: Vec2, margin: f32) -> f32 {
+    extent / (size.x().min(size.y()) / 2.0 - margin).max(1.0)
+}
+
+/// The window and the part of it the main view is drawn in, both in pixels.
+fn main_view(windows: &Windows, viewport: Option<&DisplayViewport>) -> Option<(Vec2, Vec2, Vec2)> {
+    let window = windows.get_primary()?;
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    Some(match viewport {
+        Some(viewport) => (size, viewport.origin, viewport.size),
+        None => (size, Vec2::zero(), size),
+    })
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn minimap_system(
+    minimap: Res<Minimap>,
+    windows: Res<Windows>,
+    (config, diagnostics): (Res<LayoutConfig>, Res<LayoutDiagnostics>),
+    mut cameras: Query<(
+        &Camera,
+        Mut<Transform>,
+        Option<Mut<ViewRect>>,
+        Option<&DisplayViewport>,
+    )>,
+    mut lines: Query<(&MinimapLine, Mut<Style>, Mut<Draw>)>,
+) {
+    let main = cameras
+        .iter_mut()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA2D))
+        .map(|(_, transform, _, viewport)| (*transform, viewport.copied()));
+    let (main, viewport) = match main {
+        Some(main) => main,
+        None => return,
+    };
+    let (window, origin, size) = match main_view(&windows, viewport.as_ref()) {
+        Some(view) => view,
+        None => return,
+    };
+    let extent = ring_extent(&config, &diagnostics);
+    let lo = screen_to_world(origin, window, &main);
+    let hi = screen_to_world(origin + size, window, &main);
+    let in_view = lo.x() <= -extent && lo.y() <= -extent && hi.x() >= extent && hi.y() >= extent;
+    let hidden = diagnostics.rings.is_empty() || (minimap.auto_hide && in_view);
+    let shown = !hidden;
+
+    let (inset_origin, inset_size) = minimap.rect.pixels(window);
+    let scale = inset_scale(extent, inset_size, minimap.margin);
+    for (camera, mut transform, rect, _) in cameras.iter_mut() {
+        if camera.name.as_deref() != Some(MINIMAP_CAMERA) {
+            continue;
+        }
+        if transform.scale.x() != scale || transform.translation.truncate() != Vec2::zero() {
+            transform.scale = Vec3::new(scale, scale, transform.scale.z());
+            transform.translation = Vec2::zero().extend(transform.translation.z());
+        }
+        let wanted = if shown {
+            minimap.rect
+        } else {
+            ViewRect::new(minimap.rect.origin, Vec2::zero())
+        };
+        if let Some(mut rect) = rect {
+            if *rect != wanted {
+                *rect = wanted;
+            }
+        }
+    }
+
+    // The main view's corners in the inset, kept to its frame.
+    let center = inset_origin + inset_size / 2.0;
+    let inset_hi = inset_origin + inset_size;
+    let to_inset = |world: Vec2| (center + world / scale).max(inset_origin).min(inset_hi);
+    let boxes = [
+        (inset_origin, inset_size),
+        (to_inset(lo), to_inset(hi) - to_inset(lo)),
+    ];
+    for (line, mut style, mut draw) in lines.iter_mut() {
+        if draw.is_visible != shown {
+            draw.is_visible = shown;
+        }
+        if !shown {
+            continue;
+        }
+        let (at, extent) = boxes[line.view as usize];
+        let (position, bar) = match line.side {
+            0 => (at, Vec2::new(extent.x(), LINE_WIDTH)),
+            1 => (
+                at + Vec2::new(0.0, extent.y() - LINE_WIDTH),
+                Vec2::new(extent.x(), LINE_WIDTH),
+            ),
+            2 => (at, Vec2::new(LINE_WIDTH, extent.y())),
+            _ => (
+                at + Vec2::new(extent.x() - LINE_WIDTH, 0.0),
+                Vec2::new(LINE_WIDTH, extent.y()),
+            ),
+        };
+        let position = Rect {
+            left: Val::Px(position.x()),
+            bottom: Val::Px(position.y()),
+            ..Default::default()
+        };
+        let bar = Size::new(
+            Val::Px(bar.x().max(LINE_WIDTH)),
+            Val::Px(bar.y().max(LINE_WIDTH)),
+        );
+        if style.position != position || style.size != bar {
+            style.position = position;
+            style.size = bar;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn minimap_click_system(
+    mut dragging: Local<bool>,
+    minimap: Res<Minimap>,
+    (config, diagnostics): (Res<LayoutConfig>, Res<LayoutDiagnostics>),
+    (windows, cursor, actions): (Res<Windows>, Res<CursorPosition>, Res<ActionState>),
+    mut display_events: ResMut<Events<DisplayEvent>>,
+    mut cameras: Query<(
+        &Camera,
+        Mut<Transform>,
+        Option<&ViewRect>,
+        Option<&DisplayViewport>,
+    )>,
+) {
+    let screen = match (actions.pressed(Action::Select), cursor.screen) {
+        (true, Some(screen)) => screen,
+        _ => {
+            *dragging = false;
+            return;
+        }
+    };
+    let window = match windows.get_primary() {
+        Some(window) => Vec2::new(window.width() as f32, window.height() as f32),
+        None => return,
+    };
+    let shown = cameras
+        .iter_mut()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(MINIMAP_CAMERA))
+        .and_then(|(_, _, rect, _)| rect.copied())
+        .filter(|rect| rect.size != Vec2::zero());
+    let local = match shown.and_then(|rect| rect.to_local(screen, window)) {
+        Some((local, size)) if *dragging || actions.just_pressed(Action::Select) => {
+            *dragging = true;
+            (local, size)
+        }
+        _ => return,
+    };
+    let scale = inset_scale(ring_extent(&config, &diagnostics), local.1, minimap.margin);
+    let target = (local.0 - local.1 / 2.0) * scale;
+
+    let (mut transform, viewport) = match cameras
+        .iter_mut()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, transform, _, viewport)) => (transform, viewport),
+        None => return,
+    };
+    let (window, origin, size) = match main_view(&windows, viewport) {
+        Some(view) => view,
+        None => return,
+    };
+    let centered = screen_to_world(origin + size / 2.0, window, &transform);
+    if centered != target {
+        transform.translation += (target - centered).extend(0.0);
+        display_events.send(DisplayEvent::ViewChanged);
+    }
+}
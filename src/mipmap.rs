@@ -0,0 +1,117 @@
+use std::num::NonZeroU8;
+
+use bevy::prelude::*;
+use bevy::render::texture::{AddressMode, FilterMode, SamplerDescriptor, TextureFormat};
+
+/// A texture and its successively halved, box-filtered copies, largest first.
+///
+/// The renderer uploads textures with a single mip level, so instead of sampling a
+/// GPU mip chain the texture is swapped for the level that best matches its size on
+/// screen, see [`MipChain::level_for`]. That keeps a far-off globe from shimmering,
+/// although unlike real mipmaps the level is chosen per texture, not per pixel.
+#[derive(Debug, Clone)]
+pub struct MipChain {
+    levels: Vec<Texture>,
+}
+
+impl MipChain {
+    /// Builds the chain down to a 1×1 texel level. Only 4-byte-per-texel formats are
+    /// filtered; others give a chain of just the original.
+    pub fn new(texture: &Texture) -> Self {
+        let mut levels = vec![texture.clone()];
+        if !matches!(
+            texture.format,
+            TextureFormat::Rgba8UnormSrgb
+                | TextureFormat::Rgba8Unorm
+                | TextureFormat::Bgra8UnormSrgb
+                | TextureFormat::Bgra8Unorm
+        ) {
+            return MipChain { levels };
+        }
+        loop {
+            let last = levels.last().unwrap();
+            let (width, height) = (last.size.x() as usize, last.size.y() as usize);
+            if width <= 1 && height <= 1 {
+                break;
+            }
+            let next = half_size(last, width, height);
+            levels.push(next);
+        }
+        MipChain { levels }
+    }
+
+    pub fn levels(&self) -> &[Texture] {
+        &self.levels
+    }
+
+    /// The level to draw when `texels_per_pixel` texels of the full-size texture land
+    /// on each screen pixel: the largest one that is not minified.
+    pub fn level_for(&self, texels_per_pixel: f32) -> usize {
+        if texels_per_pixel <= 1.0 {
+            return 0;
+        }
+        (texels_per_pixel.log2().floor() as usize).min(self.levels.len() - 1)
+    }
+}
+
+fn half_size(texture: &Texture, width: usize, height: usize) -> Texture {
+    let (half_width, half_height) = ((width / 2).max(1), (height / 2).max(1));
+    let mut data = Vec::with_capacity(half_width * half_height * 4);
+    for y in 0..half_height {
+        for x in 0..half_width {
+            let mut sum = [0u32; 4];
+            for (dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let sx = (x * 2 + dx).min(width - 1);
+                let sy = (y * 2 + dy).min(height - 1);
+                let at = (sy * width + sx) * 4;
+                for (channel, value) in sum.iter_mut().zip(&texture.data[at..at + 4]) {
+                    *channel += *value as u32;
+                }
+            }
+            data.extend(sum.iter().map(|v| (v / 4) as u8));
+        }
+    }
+    Texture {
+        data,
+        size: Vec2::new(half_width as f32, half_height as f32),
+        format: texture.format,
+        sampler: texture.sampler,
+    }
+}
+
+/// How a texture is sampled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplerSettings {
+    /// Wrapping across the left and right edges; `Repeat` hides the seam of a texture
+    /// wrapped around a sphere.
+    pub address_u: AddressMode,
+    /// Wrapping at the top and bottom edges.
+    pub address_v: AddressMode,
+    /// Filtering when magnified and minified.
+    pub filter: FilterMode,
+    /// Most samples taken along a glancing angle; `None` or 1 turns anisotropic
+    /// filtering off. The device must support it.
+    pub anisotropy: Option<NonZeroU8>,
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        SamplerSettings {
+            address_u: AddressMode::Repeat,
+            address_v: AddressMode::ClampToEdge,
+            filter: FilterMode::Linear,
+            anisotropy: None,
+        }
+    }
+}
+
+impl SamplerSettings {
+    pub fn apply(&self, sampler: &mut SamplerDescriptor) {
+        sampler.address_mode_u = self.address_u;
+        sampler.address_mode_v = self.address_v;
+        sampler.mag_filter = self.filter;
+        sampler.min_filter = self.filter;
+        sampler.mipmap_filter = self.filter;
+        sampler.anisotropy_clamp = self.anisotropy;
+    }
+}
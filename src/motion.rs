@@ -0,0 +1,261 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::easing::{Easing, EasingConfig};
+use crate::style::{LineStyle, MarkerShape};
+
+/// Signed angle from `from` to `to` along the shorter way around, in `(-π, π]`.
+pub fn shortest_arc(from: f32, to: f32) -> f32 {
+    let delta = (to - from).rem_euclid(PI * 2.0);
+    if delta > PI {
+        delta - PI * 2.0
+    } else {
+        delta
+    }
+}
+
+/// Interpolates between two azimuths along the shorter arc, so 350° to 10° passes
+/// through 0° instead of swinging back through 180°. The result is in `[0, 2π)`.
+pub fn lerp_azimuth(from: f32, to: f32, t: f32) -> f32 {
+    (from + shortest_arc(from, to) * t).rem_euclid(PI * 2.0)
+}
+
+/// Duration and easing of the transitions the display starts when targets move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TweenConfig {
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl Default for TweenConfig {
+    fn default() -> Self {
+        TweenConfig {
+            duration: crate::display::MARKER_TWEEN_SECS,
+            easing: EasingConfig::default().tween,
+        }
+    }
+}
+
+/// Moves an entity from one polar position around `origin` to another over
+/// `duration` seconds. `offset` is added to the polar position, e.g. to keep a marker
+/// centered on it. The component stays on the entity once finished; start the next
+/// move with [`PolarTween::retarget`].
+#[derive(Debug, Clone)]
+pub struct PolarTween {
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+    /// World position the polar coordinates are measured from, that of the target's
+    /// [`SensorOrigin`](crate::origins::SensorOrigin).
+    pub origin: Vec2,
+    pub offset: Vec2,
+    pub duration: f32,
+    pub easing: Easing,
+    elapsed: f32,
+}
+
+impl PolarTween {
+    /// A tween at rest at `(azimuth, radius)`, easing out once moved.
+    pub fn at(azimuth: f32, radius: f32, offset: Vec2, duration: f32) -> Self {
+        PolarTween {
+            from: (azimuth, radius),
+            to: (azimuth, radius),
+            origin: Vec2::zero(),
+            offset,
+            duration,
+            easing: EasingConfig::default().tween,
+            elapsed: duration,
+        }
+    }
+
+    /// [`PolarTween::at`] with the duration and easing of `config`.
+    pub fn with_config(azimuth: f32, radius: f32, offset: Vec2, config: &TweenConfig) -> Self {
+        PolarTween {
+            easing: config.easing,
+            ..PolarTween::at(azimuth, radius, offset, config.duration)
+        }
+    }
+
+    /// The tween measured from `origin` rather than the world origin.
+    pub fn around(self, origin: Vec2) -> Self {
+        PolarTween { origin, ..self }
+    }
+
+    /// The current `(azimuth, radius)`.
+    pub fn position(&self) -> (f32, f32) {
+        let t = if self.duration > 0.0 {
+            self.easing.apply(self.elapsed / self.duration)
+        } else {
+            1.0
+        };
+        (
+            lerp_azimuth(self.from.0, self.to.0, t),
+            self.from.1 + (self.to.1 - self.from.1) * t,
+        )
+    }
+
+    /// The current position in the world, without `offset`.
+    pub fn point(&self) -> Vec2 {
+        let (azimuth, radius) = self.position();
+        self.origin + Vec2::new(radius * azimuth.cos(), radius * azimuth.sin())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Starts moving from wherever the tween currently is towards a new position.
+    pub fn retarget(&mut self, azimuth: f32, radius: f32) {
+        self.from = self.position();
+        self.to = (azimuth, radius);
+        self.elapsed = 0.0;
+    }
+}
+
+/// Draws a line from the tween's origin to its [`PolarTween`]'s position, regenerating the mesh
+/// while the tween runs, instead of having the tween move the entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderLine {
+    /// Width it is drawn at: the style's, or wider while the target is selected.
+    pub width: f32,
+    pub style: LineStyle,
+    /// Points the line bends at on its way out, from
+    /// [`LeaderRouter`](crate::layout::LeaderRouter) and relative to the origin; empty
+    /// for a straight line.
+    pub elbows: Vec<Vec2>,
+    /// Shape and width of the target's marker, for styles that end the line at its
+    /// edge.
+    pub marker: Option<(MarkerShape, f32)>,
+}
+
+impl Default for LeaderLine {
+    fn default() -> Self {
+        LeaderLine::new(LineStyle::default())
+    }
+}
+
+impl LeaderLine {
+    pub fn new(style: LineStyle) -> Self {
+        LeaderLine {
+            width: style.width,
+            style,
+            elbows: Vec::new(),
+            marker: None,
+        }
+    }
+
+    /// Ends the line at the edge of a `shape` marker `width` across, if its style says
+    /// to.
+    pub fn ending_at(mut self, shape: MarkerShape, width: f32) -> Self {
+        self.marker = Some((shape, width));
+        self
+    }
+
+    /// Replaces `mesh` with the line at the tween's current position.
+    pub fn redraw(
+        &self,
+        tween: &PolarTween,
+        mesh: &Handle<Mesh>,
+        material: &Handle<ColorMaterial>,
+        meshes: &mut ResMut<'_, Assets<Mesh>>,
+    ) {
+        let (azimuth, radius) = tween.position();
+        let line = leader_line(
+            material.clone(),
+            meshes,
+            tween.origin,
+            &self.elbows,
+            azimuth,
+            radius,
+            &self.style,
+            self.width,
+            self.marker,
+        );
+        if let Some(regenerated) = meshes.remove(&line.mesh) {
+            meshes.set(mesh, regenerated);
+        }
+    }
+}
+
+/// Advances every [`PolarTween`] by [`AnimationTime`] and writes the result into the
+/// entity's `Transform`, or into the mesh of a [`LeaderLine`].
+pub struct MotionPlugin;
+
+impl Plugin for MotionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<TweenConfig>() {
+            app.init_resource::<TweenConfig>();
+        }
+        app.add_plugin(AnimationTimePlugin)
+            .add_system(tween_system.system())
+            .add_system(leader_system.system());
+    }
+}
+
+/// A line `width` wide in `style` from `origin` through `elbows` to
+/// `(azimuth, radius)`, both relative to `origin`, drawn with `material`, ending at the
+/// edge of a `marker` of that shape and width there if the style says to.
+#[allow(clippy::too_many_arguments)]
+pub fn leader_line(
+    material: Handle<ColorMaterial>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    origin: Vec2,
+    elbows: &[Vec2],
+    azimuth: f32,
+    radius: f32,
+    style: &LineStyle,
+    width: f32,
+    marker: Option<(MarkerShape, f32)>,
+) -> SpriteComponents {
+    let mut points = std::iter::once(Vec2::zero())
+        .chain(elbows.iter().copied())
+        .chain(std::iter::once(Vec2::new(
+            radius * azimuth.cos(),
+            radius * azimuth.sin(),
+        )))
+        .map(|p| origin + p)
+        .collect::<Vec<_>>();
+    if let Some((shape, marker_width)) = marker {
+        style.end_at_marker(&mut points, shape, marker_width);
+    }
+    style.stroke(&points, width, material, meshes)
+}
+
+#[allow(clippy::type_complexity)]
+fn leader_system(
+    time: Res<AnimationTime>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(
+        &LeaderLine,
+        Mut<PolarTween>,
+        &Handle<Mesh>,
+        &Handle<ColorMaterial>,
+    )>,
+) {
+    for (leader, mut tween, mesh, material) in query.iter_mut() {
+        if tween.is_finished() {
+            continue;
+        }
+        tween.elapsed += time.delta_seconds();
+        leader.redraw(&tween, mesh, material, &mut meshes);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn tween_system(
+    time: Res<AnimationTime>,
+    mut query: Query<Without<LeaderLine, (Mut<PolarTween>, Mut<Transform>)>>,
+) {
+    for (mut tween, mut transform) in query.iter_mut() {
+        if tween.is_finished() {
+            continue;
+        }
+        tween.elapsed += time.delta_seconds();
+        let at = tween.point() + tween.offset;
+        let mut translation = transform.translation;
+        translation.set_x(at.x());
+        translation.set_y(at.y());
+        transform.translation = translation;
+    }
+}
@@ -0,0 +1,675 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::f32::consts::PI;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::display::{Poi, PoiLabel, Slot};
+use crate::emphasis::{Emphasis, TargetEmphasis};
+use crate::events::DisplayEvent;
+use crate::layers::Layer;
+use crate::origins::SensorOrigins;
+use crate::palette::Categorical;
+use crate::pointer::{screen_to_world, CursorPosition};
+use crate::scenario::Scenario;
+use crate::target::Target;
+use crate::theme::Theme;
+use crate::viewport::DisplayViewport;
+
+const BAND_Z: f32 = 3.0;
+/// Pixels the cursor may move between press and release for a click rather than a drag.
+const CLICK_SLOP: f32 = 4.0;
+/// Radians between the points of a drawn sector's arcs.
+const ARC_STEP: f32 = 0.03;
+
+/// The targets picked by [`MultiSelectPlugin`], by id. Apart from the single
+/// [`Selected`](crate::selection::Selected) target, which a set may or may not hold.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SelectionSet {
+    ids: BTreeSet<i32>,
+    /// Keep the 2D camera centered on the selected markers, set by
+    /// [`BulkAction::Pin`].
+    pub pinned: bool,
+}
+
+impl SelectionSet {
+    pub fn contains(&self, id: i32) -> bool {
+        self.ids.contains(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// The selected ids, in order.
+    pub fn iter(&self) -> impl Iterator<Item = i32> + '_ {
+        self.ids.iter().copied()
+    }
+
+    /// Selects `id` if it isn't yet, and drops it if it is.
+    pub fn toggle(&mut self, id: i32) {
+        if !self.ids.remove(&id) {
+            self.ids.insert(id);
+        }
+    }
+
+    /// Selects exactly `ids`.
+    pub fn set(&mut self, ids: impl IntoIterator<Item = i32>) {
+        self.ids = ids.into_iter().collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+    }
+}
+
+/// How targets are drawn after a [`BulkAction`], whether or not they are still selected.
+/// Targets without an entry are drawn as usual.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TargetOverrides {
+    hidden: HashSet<i32>,
+    colors: HashMap<i32, Color>,
+}
+
+impl TargetOverrides {
+    pub fn is_hidden(&self, id: i32) -> bool {
+        self.hidden.contains(&id)
+    }
+
+    pub fn set_hidden(&mut self, id: i32, hidden: bool) {
+        if hidden {
+            self.hidden.insert(id);
+        } else {
+            self.hidden.remove(&id);
+        }
+    }
+
+    /// Shows every hidden target again.
+    pub fn show_all(&mut self) {
+        self.hidden.clear();
+    }
+
+    /// The color `id` is drawn in instead of its own, if any.
+    pub fn color(&self, id: i32) -> Option<Color> {
+        self.colors.get(&id).copied()
+    }
+
+    /// Draws `id` in `color`, or in its own color again with `None`.
+    pub fn set_color(&mut self, id: i32, color: Option<Color>) {
+        match color {
+            Some(color) => self.colors.insert(id, color),
+            None => self.colors.remove(&id),
+        };
+    }
+}
+
+/// Something to do with every target of the [`SelectionSet`] at once, for
+/// [`MultiSelectPlugin`]. Its keys send these, and panels and scripts can too.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkAction {
+    /// Hide the selected targets' markers, leader lines and labels.
+    Hide,
+    /// Show every target hidden so far again, selected or not.
+    ShowAll,
+    /// Draw the selected targets' markers and labels in this color.
+    Recolor(Color),
+    /// Draw the selected targets in their own colors again.
+    ResetColors,
+    /// Write the selected targets to this file as a [`Scenario`].
+    Export(PathBuf),
+    /// Keep the 2D camera centered on the selected markers, or stop.
+    Pin(bool),
+}
+
+/// Picks several targets of the 2D ring display at once, into the [`SelectionSet`].
+/// Dragging with [`Action::BandSelect`] held, Shift by default, selects the markers
+/// inside a rectangle, and with [`Action::SectorSelect`], Ctrl, those inside the
+/// sector of the nearest origin's rings between where the drag started and the
+/// cursor, the shorter way round; the set follows the cursor as it goes.
+/// Clicking a marker with either held adds it or takes it out again, and a plain click into empty
+/// space empties the set. Selected targets are highlighted.
+///
+/// With a selection, [`Action::HideSelected`] (H) hides it and [`Action::ShowAll`]
+/// (Shift+H) shows all hidden targets again, [`Action::RecolorSelected`] (V) draws it
+/// in the next [`Categorical::OkabeIto`] color and [`Action::ResetColors`] (Shift+V) in
+/// its own colors again, [`Action::ExportSelection`] (Ctrl+J) writes it to `export` as
+/// a [`Scenario`], and [`Action::PinSelected`] (K) pins the camera to its
+/// markers' centroid or unpins it, which a pan doesn't outlast. These are sent as
+/// [`BulkAction`]s and leave their marks in [`TargetOverrides`]; hidden markers can
+/// still be clicked. Picks through the [`DisplayEvent::Clicked`] events of
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin) and highlights through
+/// [`EmphasisPlugin`](crate::emphasis::EmphasisPlugin). Needs
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin).
+pub struct MultiSelectPlugin {
+    pub export: PathBuf,
+}
+
+struct SelectionExport(PathBuf);
+
+/// The rectangle or sector being dragged out, if any.
+#[derive(Default)]
+struct ActiveBand(Option<Band>);
+
+impl Plugin for MultiSelectPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<TargetEmphasis>() {
+            app.init_resource::<TargetEmphasis>();
+        }
+        if !app.resources().contains::<SelectionSet>() {
+            app.init_resource::<SelectionSet>();
+        }
+        if !app.resources().contains::<TargetOverrides>() {
+            app.init_resource::<TargetOverrides>();
+        }
+        app.init_resource::<ActiveBand>()
+            .add_resource(SelectionExport(self.export.clone()))
+            .add_event::<BulkAction>()
+            .add_system(band_select_system.system())
+            .add_system(band_draw_system.system())
+            .add_system(selection_emphasis_system.system())
+            .add_system(bulk_key_system.system())
+            .add_system(bulk_action_system.system())
+            .add_system(pin_system.system())
+            .add_system_to_stage(stage::PRE_UPDATE, unhide_system.system())
+            .add_system_to_stage(stage::POST_UPDATE, hide_system.system())
+            .add_system_to_stage(stage::POST_UPDATE, recolor_system.system());
+    }
+}
+
+/// An area of the display to select the markers in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Band {
+    /// Lower-left and upper-right corners, in world space.
+    Rect { min: Vec2, max: Vec2 },
+    /// The rings of `origin` drawn around `center`, from azimuth `from` radians
+    /// counter-clockwise through `span` and between two drawn radii.
+    Sector {
+        origin: usize,
+        center: Vec2,
+        from: f32,
+        span: f32,
+        near: f32,
+        far: f32,
+    },
+}
+
+impl Band {
+    fn rect(a: Vec2, b: Vec2) -> Self {
+        Band::Rect {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// The sector around `center` with corners `a` and `b`, the shorter way round.
+    fn sector(origin: usize, center: Vec2, a: Vec2, b: Vec2) -> Self {
+        let azimuth = |p: Vec2| (p - center).y().atan2((p - center).x());
+        let (from, to) = (azimuth(a), azimuth(b));
+        let turn = (to - from).rem_euclid(PI * 2.0);
+        let (from, span) = if turn <= PI {
+            (from, turn)
+        } else {
+            (to, PI * 2.0 - turn)
+        };
+        let (ra, rb) = ((a - center).length(), (b - center).length());
+        Band::Sector {
+            origin,
+            center,
+            from,
+            span,
+            near: ra.min(rb),
+            far: ra.max(rb),
+        }
+    }
+
+    fn contains(&self, poi: &Poi, slot: &Slot) -> bool {
+        match *self {
+            Band::Rect { min, max } => {
+                let p = poi.center;
+                p.x() >= min.x() && p.y() >= min.y() && p.x() <= max.x() && p.y() <= max.y()
+            }
+            Band::Sector {
+                origin,
+                from,
+                span,
+                near,
+                far,
+                ..
+            } => {
+                slot.origin == origin
+                    && slot.radius >= near
+                    && slot.radius <= far
+                    && (slot.azimuth - from).rem_euclid(PI * 2.0) <= span
+            }
+        }
+    }
+}
+
+/// A band or sector select click on the display, until the button is let go.
+#[derive(Debug, Clone, Copy)]
+struct BandDrag {
+    sector: bool,
+    screen: Vec2,
+    world: Vec2,
+    target: Option<i32>,
+    /// Moved far enough from the press to be a drag.
+    moved: bool,
+}
+
+#[derive(Default)]
+struct BandState {
+    reader: EventReader<DisplayEvent>,
+    drag: Option<BandDrag>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn band_select_system(
+    mut state: Local<BandState>,
+    actions: Res<ActionState>,
+    (cursor, origins): (Res<CursorPosition>, Res<SensorOrigins>),
+    events: Res<Events<DisplayEvent>>,
+    mut set: ResMut<SelectionSet>,
+    mut band: ResMut<ActiveBand>,
+    markers: Query<(&Poi, &Slot)>,
+) {
+    let click = state
+        .reader
+        .iter(&events)
+        .filter_map(|event| match event {
+            DisplayEvent::Clicked {
+                screen,
+                world: Some(world),
+                target,
+                ..
+            } => Some((*screen, *world, *target)),
+            _ => None,
+        })
+        .next_back();
+    let boxed = actions.pressed(Action::BandSelect);
+    let sector = actions.pressed(Action::SectorSelect);
+    if let Some((screen, world, target)) = click {
+        if boxed || sector {
+            state.drag = Some(BandDrag {
+                sector,
+                screen,
+                world,
+                target,
+                moved: false,
+            });
+        } else if target.is_none() && !set.is_empty() {
+            set.clear();
+        }
+    }
+    let mut drag = match state.drag {
+        Some(drag) => drag,
+        None => return,
+    };
+    let released = !actions.pressed(Action::Select);
+    drag.moved |= cursor
+        .screen
+        .is_some_and(|screen| (screen - drag.screen).length() > CLICK_SLOP);
+    if !drag.moved {
+        if released {
+            if let (false, Some(id)) = (drag.sector, drag.target) {
+                set.toggle(id);
+            }
+            state.drag = None;
+        }
+        return;
+    }
+    if let Some(world) = cursor.world {
+        let area = if drag.sector {
+            let origin = (0..origins.len())
+                .min_by(|&a, &b| {
+                    let d = |o| (origins.offset(o) - drag.world).length_squared();
+                    d(a).partial_cmp(&d(b)).unwrap()
+                })
+                .unwrap_or(0);
+            Band::sector(origin, origins.offset(origin), drag.world, world)
+        } else {
+            Band::rect(drag.world, world)
+        };
+        let picked = markers
+            .iter()
+            .filter(|(poi, slot)| area.contains(poi, slot))
+            .map(|(poi, _)| poi.id);
+        set.set(picked);
+        band.0 = Some(area);
+    }
+    if released {
+        tracing::info!("{} targets selected", set.len());
+        state.drag = None;
+        band.0 = None;
+    } else {
+        state.drag = Some(drag);
+    }
+}
+
+/// Marks the band [`MultiSelectPlugin`] draws while dragging.
+struct BandPart;
+
+fn band_draw_system(
+    mut commands: Commands,
+    mut drawn: Local<Option<Band>>,
+    band: Res<ActiveBand>,
+    theme: Res<Theme>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    parts: Query<With<BandPart, Entity>>,
+) {
+    if *drawn == band.0 {
+        return;
+    }
+    *drawn = band.0;
+    for entity in parts.iter() {
+        commands.despawn(entity);
+    }
+    let corners = match band.0 {
+        Some(Band::Rect { min, max }) => vec![
+            min,
+            Vec2::new(max.x(), min.y()),
+            max,
+            Vec2::new(min.x(), max.y()),
+        ],
+        // Out along one edge, round the far arc and back in along the other.
+        Some(Band::Sector {
+            center,
+            from,
+            span,
+            near,
+            far,
+            ..
+        }) => {
+            let steps = ((span / ARC_STEP).ceil() as usize).max(1);
+            let arc = |radius: f32| {
+                (0..=steps).map(move |i| {
+                    let azimuth = from + span * i as f32 / steps as f32;
+                    center + Vec2::new(azimuth.cos(), azimuth.sin()) * radius
+                })
+            };
+            arc(near)
+                .chain(arc(far).collect::<Vec<_>>().into_iter().rev())
+                .collect()
+        }
+        None => return,
+    };
+    let mut builder = PathBuilder::new();
+    for (i, at) in corners.iter().enumerate() {
+        if i == 0 {
+            builder.move_to(point(at.x(), at.y()));
+        } else {
+            builder.line_to(point(at.x(), at.y()));
+        }
+    }
+    builder.close();
+    let outline = builder.build().stroke(
+        materials.add(theme.stroke().into()),
+        &mut meshes,
+        Vec3::new(0.0, 0.0, BAND_Z),
+        &StrokeOptions::default(),
+    );
+    commands.spawn(outline).with(BandPart).with(Layer::Overlays);
+}
+
+fn selection_emphasis_system(
+    mut saved: Local<HashMap<i32, Emphasis>>,
+    set: Res<SelectionSet>,
+    mut emphasis: ResMut<TargetEmphasis>,
+) {
+    let dropped = saved
+        .keys()
+        .copied()
+        .filter(|&id| !set.contains(id))
+        .collect::<Vec<_>>();
+    for id in dropped {
+        if let Some(before) = saved.remove(&id) {
+            emphasis.set_target_emphasis(id, before);
+        }
+    }
+    for id in set.iter() {
+        if let Entry::Vacant(entry) = saved.entry(id) {
+            entry.insert(emphasis.emphasis(id));
+            emphasis.set_target_emphasis(id, Emphasis::Highlight);
+        }
+    }
+}
+
+fn bulk_key_system(
+    mut next_color: Local<usize>,
+    actions: Res<ActionState>,
+    (set, export): (Res<SelectionSet>, Res<SelectionExport>),
+    mut bulk: ResMut<Events<BulkAction>>,
+) {
+    if actions.just_pressed(Action::ShowAll) {
+        bulk.send(BulkAction::ShowAll);
+    }
+    if set.is_empty() {
+        return;
+    }
+    if actions.just_pressed(Action::HideSelected) {
+        bulk.send(BulkAction::Hide);
+    }
+    if actions.just_pressed(Action::ResetColors) {
+        bulk.send(BulkAction::ResetColors);
+    }
+    if actions.just_pressed(Action::RecolorSelected) {
+        let palette = Categorical::OkabeIto;
+        bulk.send(BulkAction::Recolor(palette.color(*next_color)));
+        *next_color = (*next_color + 1) % palette.len();
+    }
+    if actions.just_pressed(Action::ExportSelection) {
+        bulk.send(BulkAction::Export(export.0.clone()));
+    }
+    if actions.just_pressed(Action::PinSelected) {
+        bulk.send(BulkAction::Pin(!set.pinned));
+    }
+}
+
+fn bulk_action_system(
+    mut reader: Local<EventReader<BulkAction>>,
+    actions: Res<Events<BulkAction>>,
+    mut set: ResMut<SelectionSet>,
+    mut overrides: ResMut<TargetOverrides>,
+    targets: Query<&Target>,
+) {
+    for action in reader.iter(&actions) {
+        match action {
+            BulkAction::Hide => {
+                for id in set.iter() {
+                    overrides.set_hidden(id, true);
+                }
+                tracing::info!("{} targets hidden", set.len());
+            }
+            BulkAction::ShowAll => overrides.show_all(),
+            BulkAction::Recolor(color) => {
+                for id in set.iter() {
+                    overrides.set_color(id, Some(*color));
+                }
+            }
+            BulkAction::ResetColors => {
+                for id in set.iter() {
+                    overrides.set_color(id, None);
+                }
+            }
+            BulkAction::Export(path) => {
+                let mut selected = targets
+                    .iter()
+                    .filter(|t| set.contains(t.id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                selected.sort_by_key(|t| t.id);
+                let count = selected.len();
+                let exported = Scenario {
+                    targets: selected,
+                    ..Default::default()
+                };
+                match exported.to_file(path) {
+                    Ok(()) => tracing::info!("{} targets exported to {}", count, path.display()),
+                    Err(e) => tracing::error!("failed to export {}: {}", path.display(), e),
+                }
+            }
+            BulkAction::Pin(pinned) => {
+                set.pinned = *pinned;
+                tracing::info!(
+                    "camera {}",
+                    if *pinned {
+                        "pinned to the selection"
+                    } else {
+                        "unpinned"
+                    }
+                );
+            }
+        }
+    }
+}
+
+fn pin_system(
+    set: Res<SelectionSet>,
+    windows: Res<Windows>,
+    mut display_events: ResMut<Events<DisplayEvent>>,
+    markers: Query<&Poi>,
+    mut cameras: Query<(&Camera, Mut<Transform>, Option<&DisplayViewport>)>,
+) {
+    if !set.pinned {
+        return;
+    }
+    let (sum, count) = markers
+        .iter()
+        .filter(|poi| set.contains(poi.id))
+        .fold((Vec2::zero(), 0), |(sum, n), poi| (sum + poi.center, n + 1));
+    let window = windows
+        .get_primary()
+        .map(|window| Vec2::new(window.width() as f32, window.height() as f32));
+    let (centroid, window) = match (count, window) {
+        (0, _) | (_, None) => return,
+        (n, Some(window)) => (sum / n as f32, window),
+    };
+    let (mut transform, viewport) = match cameras
+        .iter_mut()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, transform, viewport)) => (transform, viewport),
+        None => return,
+    };
+    let middle = viewport.map_or(window / 2.0, DisplayViewport::center);
+    let centered = screen_to_world(middle, window, &transform);
+    if centered != centroid {
+        transform.translation += (centroid - centered).extend(0.0);
+        display_events.send(DisplayEvent::ViewChanged);
+    }
+}
+
+/// On an entity [`MultiSelectPlugin`] has looked at, whether it hid it this frame.
+struct BulkHidden(bool);
+
+// Like culling, this only ever hides, and shows again at the start of the next frame
+// what it hid, so the systems owning visibility decide as before.
+fn unhide_system(mut hidden: Query<(Mut<BulkHidden>, Mut<Draw>)>) {
+    for (mut hidden, mut draw) in hidden.iter_mut() {
+        if hidden.0 {
+            hidden.0 = false;
+            draw.is_visible = true;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn hide_system(
+    mut commands: Commands,
+    overrides: Res<TargetOverrides>,
+    mut parts: Query<(Entity, &Slot, Mut<Draw>, Option<Mut<BulkHidden>>)>,
+) {
+    for (entity, slot, mut draw, hidden) in parts.iter_mut() {
+        if !draw.is_visible || !overrides.is_hidden(slot.id) {
+            continue;
+        }
+        draw.is_visible = false;
+        match hidden {
+            Some(mut hidden) => hidden.0 = true,
+            None => {
+                commands.insert_one(entity, BulkHidden(true));
+            }
+        }
+    }
+}
+
+/// The color an entity [`MultiSelectPlugin`] recolored had before, to go back to.
+struct Recolored(Color);
+
+fn same_rgb(a: Color, b: Color) -> bool {
+    a.r() == b.r() && a.g() == b.g() && a.b() == b.b()
+}
+
+/// `color` with the alpha of `alpha`, which emphasis and aging keep changing.
+fn with_alpha(color: Color, alpha: Color) -> Color {
+    Color::rgba_linear(color.r(), color.g(), color.b(), alpha.a())
+}
+
+/// The color to draw an entity of `current` color in now, if it changes, given its
+/// override and the color it had before one, which this keeps up to date.
+fn recolor(
+    commands: &mut Commands,
+    entity: Entity,
+    recolored: Option<&Recolored>,
+    color: Option<Color>,
+    current: Color,
+) -> Option<Color> {
+    let color = match (color, recolored) {
+        (Some(color), Some(_)) => color,
+        (Some(color), None) => {
+            commands.insert_one(entity, Recolored(current));
+            color
+        }
+        (None, Some(before)) => {
+            commands.remove_one::<Recolored>(entity);
+            before.0
+        }
+        (None, None) => return None,
+    };
+    if same_rgb(color, current) {
+        None
+    } else {
+        Some(with_alpha(color, current))
+    }
+}
+
+// New labels and restyled markers come in their own colors, so this keeps checking
+// rather than only reacting to changes of the overrides.
+#[allow(clippy::type_complexity)]
+fn recolor_system(
+    mut commands: Commands,
+    overrides: Res<TargetOverrides>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    markers: Query<With<Poi, (Entity, &Slot, &Handle<ColorMaterial>, Option<&Recolored>)>>,
+    mut labels: Query<(Entity, &PoiLabel, Mut<Text>, Option<&Recolored>)>,
+) {
+    for (entity, slot, handle, recolored) in markers.iter() {
+        let current = match materials.get(handle) {
+            Some(material) => material.color,
+            None => continue,
+        };
+        let color = overrides.color(slot.id);
+        if let Some(color) = recolor(&mut commands, entity, recolored, color, current) {
+            if let Some(material) = materials.get_mut(handle) {
+                material.color = color;
+            }
+        }
+    }
+    for (entity, label, mut text, recolored) in labels.iter_mut() {
+        let color = overrides.color(label.id);
+        let current = text.style.color;
+        if let Some(color) = recolor(&mut commands, entity, recolored, color, current) {
+            text.style.color = color;
+        }
+    }
+}
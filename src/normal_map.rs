@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+use bevy::render::pipeline::{
+    DynamicBinding, PipelineDescriptor, PipelineSpecialization, RenderPipeline,
+};
+use bevy::render::render_graph::base;
+use bevy::render::render_graph::{AssetRenderResourcesNode, RenderGraph};
+use bevy::render::renderer::RenderResources;
+use bevy::render::shader::{ShaderDefs, ShaderStage, ShaderStages};
+use bevy::type_registry::TypeUuid;
+use bevy::utils::HashSet;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::lighting::LightingRig;
+
+/// The pipeline drawing [`NormalMappedMaterial`]s.
+pub const NORMAL_MAPPED_PIPELINE_HANDLE: Handle<PipelineDescriptor> =
+    Handle::weak_from_u64(PipelineDescriptor::TYPE_UUID, 11_702_384_115_960_312_447);
+/// Render graph node uploading [`NormalMappedMaterial`]s.
+const NORMAL_MAPPED_MATERIAL_NODE: &str = "normal_mapped_material";
+
+// The shaders start right at `#version`, as bevy only puts shader defs after it in the
+// right place when it is the first line.
+const VERTEX_SHADER: &str = r#"#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec3 Vertex_Normal;
+layout(location = 2) in vec2 Vertex_Uv;
+#ifdef NORMALMAPPEDMATERIAL_NORMAL_MAP
+layout(location = 3) in vec4 Vertex_Tangent;
+#endif
+
+layout(location = 0) out vec3 v_Normal;
+layout(location = 1) out vec2 v_Uv;
+#ifdef NORMALMAPPEDMATERIAL_NORMAL_MAP
+layout(location = 2) out vec4 v_Tangent;
+#endif
+
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 ViewProj;
+};
+
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+
+void main() {
+    v_Normal = mat3(Model) * Vertex_Normal;
+    v_Uv = Vertex_Uv;
+#ifdef NORMALMAPPEDMATERIAL_NORMAL_MAP
+    v_Tangent = vec4(mat3(Model) * Vertex_Tangent.xyz, Vertex_Tangent.w);
+#endif
+    gl_Position = ViewProj * Model * vec4(Vertex_Position, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"#version 450
+
+layout(location = 0) in vec3 v_Normal;
+layout(location = 1) in vec2 v_Uv;
+#ifdef NORMALMAPPEDMATERIAL_NORMAL_MAP
+layout(location = 2) in vec4 v_Tangent;
+#endif
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 2, binding = 0) uniform NormalMappedMaterial_albedo {
+    vec4 Albedo;
+};
+layout(set = 2, binding = 1) uniform texture2D NormalMappedMaterial_albedo_texture;
+layout(set = 2, binding = 2) uniform sampler NormalMappedMaterial_albedo_texture_sampler;
+layout(set = 2, binding = 3) uniform NormalMappedMaterial_light {
+    vec3 Light;
+};
+layout(set = 2, binding = 4) uniform NormalMappedMaterial_light_color {
+    vec4 LightColor;
+};
+layout(set = 2, binding = 5) uniform NormalMappedMaterial_ambient {
+    vec4 Ambient;
+};
+#ifdef NORMALMAPPEDMATERIAL_NORMAL_MAP
+layout(set = 2, binding = 6) uniform texture2D NormalMappedMaterial_normal_map;
+layout(set = 2, binding = 7) uniform sampler NormalMappedMaterial_normal_map_sampler;
+#endif
+
+void main() {
+    vec4 color = Albedo * texture(
+        sampler2D(NormalMappedMaterial_albedo_texture, NormalMappedMaterial_albedo_texture_sampler),
+        v_Uv);
+#ifdef NORMALMAPPEDMATERIAL_SHADED
+    vec3 normal = normalize(v_Normal);
+# ifdef NORMALMAPPEDMATERIAL_NORMAL_MAP
+    vec3 tangent = normalize(v_Tangent.xyz - normal * dot(normal, v_Tangent.xyz));
+    vec3 bitangent = cross(normal, tangent) * v_Tangent.w;
+    vec3 mapped = texture(
+        sampler2D(NormalMappedMaterial_normal_map, NormalMappedMaterial_normal_map_sampler),
+        v_Uv).xyz * 2.0 - 1.0;
+    normal = normalize(mat3(tangent, bitangent, normal) * mapped);
+# endif
+    float diffuse = max(dot(normal, normalize(Light)), 0.0);
+    color.rgb *= Ambient.rgb + LightColor.rgb * diffuse;
+#endif
+    o_Target = color;
+}
+"#;
+
+/// A textured material lit by one directional light, with its surface detail taken
+/// from a tangent-space `normal_map` if it has one. Meshes drawn with a normal map need
+/// [`generate_tangents`](crate::mesh::generate_tangents) run on them. With `shaded`
+/// off the texture shows as it is. [`NormalMapPlugin`] lights it with the
+/// [`LightingRig`]'s key light.
+#[derive(Debug, RenderResources, ShaderDefs, TypeUuid)]
+#[uuid = "3e7a51c8-90d2-4b6f-8c14-a2f9d06b7e35"]
+pub struct NormalMappedMaterial {
+    pub albedo: Color,
+    pub albedo_texture: Handle<Texture>,
+    /// Towards the light in world space.
+    pub light: Vec3,
+    pub light_color: Color,
+    /// Light reaching every surface, whichever way it faces.
+    pub ambient: Color,
+    #[shader_def]
+    pub normal_map: Option<Handle<Texture>>,
+    #[render_resources(ignore)]
+    #[shader_def]
+    pub shaded: bool,
+}
+
+impl NormalMappedMaterial {
+    pub fn new(albedo_texture: Handle<Texture>, normal_map: Option<Handle<Texture>>) -> Self {
+        NormalMappedMaterial {
+            albedo: Color::WHITE,
+            albedo_texture,
+            light: Vec3::unit_z(),
+            light_color: Color::WHITE,
+            ambient: Color::rgb(0.15, 0.15, 0.15),
+            normal_map,
+            shaded: true,
+        }
+    }
+
+    /// The render pipelines to give a mesh drawn with a [`NormalMappedMaterial`], along
+    /// with the mesh and material handles, [`MainPass`](base::MainPass), [`Draw`] and
+    /// its transforms.
+    pub fn render_pipelines() -> RenderPipelines {
+        RenderPipelines::from_pipelines(vec![RenderPipeline::specialized(
+            NORMAL_MAPPED_PIPELINE_HANDLE,
+            PipelineSpecialization {
+                dynamic_bindings: vec![
+                    // Transform
+                    DynamicBinding {
+                        bind_group: 1,
+                        binding: 0,
+                    },
+                ],
+                ..Default::default()
+            },
+        )])
+    }
+}
+
+/// Adds [`NormalMappedMaterial`] and its pipeline, lights every such material with the
+/// [`LightingRig`]'s key light and ambient, and switches them all between shaded and
+/// unshaded with [`Action::ToggleShading`], L by default. Add after the render plugins.
+pub struct NormalMapPlugin;
+
+impl Plugin for NormalMapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<LightingRig>() {
+            app.init_resource::<LightingRig>();
+        }
+        app.add_asset::<NormalMappedMaterial>()
+            .add_system(normal_map_light_system.system())
+            .add_system(normal_map_toggle_system.system())
+            .add_system_to_stage(stage::POST_UPDATE, shader_defs_system.system());
+        let resources = app.resources();
+        let mut shaders = resources.get_mut::<Assets<Shader>>().unwrap();
+        let mut pipelines = resources.get_mut::<Assets<PipelineDescriptor>>().unwrap();
+        pipelines.set_untracked(
+            NORMAL_MAPPED_PIPELINE_HANDLE,
+            PipelineDescriptor::default_config(ShaderStages {
+                vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER)),
+                fragment: Some(
+                    shaders.add(Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER)),
+                ),
+            }),
+        );
+        let mut graph = resources.get_mut::<RenderGraph>().unwrap();
+        graph.add_system_node(
+            NORMAL_MAPPED_MATERIAL_NODE,
+            AssetRenderResourcesNode::<NormalMappedMaterial>::new(false),
+        );
+        graph
+            .add_node_edge(NORMAL_MAPPED_MATERIAL_NODE, base::node::MAIN_PASS)
+            .unwrap();
+    }
+}
+
+fn normal_map_light_system(
+    rig: Res<LightingRig>,
+    mut materials: ResMut<Assets<NormalMappedMaterial>>,
+    handles: Query<&Handle<NormalMappedMaterial>>,
+) {
+    let light = if rig.direction.length_squared() > f32::EPSILON {
+        -rig.direction.normalize()
+    } else {
+        Vec3::unit_z()
+    };
+    let light_color = rig.color * rig.key;
+    let ambient = rig.color * rig.ambient;
+    for handle in handles.iter() {
+        // Only touch materials whose light changed, as changing one uploads it again.
+        let stale = materials.get(handle).is_some_and(|material| {
+            material.light != light
+                || material.light_color != light_color
+                || material.ambient != ambient
+        });
+        if stale {
+            if let Some(material) = materials.get_mut(handle) {
+                material.light = light;
+                material.light_color = light_color;
+                material.ambient = ambient;
+            }
+        }
+    }
+}
+
+fn normal_map_toggle_system(
+    actions: Res<ActionState>,
+    mut materials: ResMut<Assets<NormalMappedMaterial>>,
+) {
+    if actions.just_pressed(Action::ToggleShading) {
+        let ids = materials.ids().collect::<Vec<_>>();
+        for id in ids {
+            if let Some(material) = materials.get_mut(id) {
+                material.shaded = !material.shaded;
+            }
+        }
+    }
+}
+
+/// Sets each material's shader defs on its pipelines, like bevy's
+/// `asset_shader_defs_system` but dropping those turned off since, so that `shaded`
+/// can be switched back and forth.
+fn shader_defs_system(
+    materials: Res<Assets<NormalMappedMaterial>>,
+    mut query: Query<(&Handle<NormalMappedMaterial>, &mut RenderPipelines)>,
+) {
+    for (handle, mut render_pipelines) in query.iter_mut() {
+        let material = match materials.get(handle) {
+            Some(material) => material,
+            None => continue,
+        };
+        let defs = material
+            .iter_shader_defs()
+            .map(str::to_string)
+            .collect::<HashSet<_>>();
+        for pipeline in render_pipelines.pipelines.iter_mut() {
+            let current = &mut pipeline.specialization.shader_specialization.shader_defs;
+            if *current != defs {
+                *current = defs.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::display::Poi;
+use crate::layers::Layer;
+use crate::scenario::Scenario;
+use crate::target::Target;
+
+const GLYPH_RADIUS: f32 = 3.0;
+
+/// The small dot drawn at the top-right corner of the marker `of` when its target has
+/// notes.
+#[derive(Debug, Clone, Copy)]
+pub struct NoteGlyph {
+    pub of: Entity,
+}
+
+/// Keeps marker targets' notes in step with the [`Scenario`] (where
+/// [`Scenario::add_note`] puts them) and marks annotated targets with a [`NoteGlyph`].
+pub struct NotesPlugin;
+
+impl Plugin for NotesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(sync_notes_system.system())
+            .add_system(glyph_system.system());
+    }
+}
+
+fn sync_notes_system(scenario: ChangedRes<Scenario>, mut markers: Query<With<Poi, Mut<Target>>>) {
+    let notes = scenario
+        .targets
+        .iter()
+        .map(|t| (t.id, &t.notes))
+        .collect::<HashMap<_, _>>();
+    for mut target in markers.iter_mut() {
+        if let Some(notes) = notes.get(&target.id) {
+            if target.notes != **notes {
+                target.notes = notes.to_vec();
+            }
+        }
+    }
+}
+
+fn glyph_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    markers: Query<(Entity, Changed<Target>, &Poi, &Handle<ColorMaterial>)>,
+    glyphs: Query<(Entity, &NoteGlyph)>,
+) {
+    for (glyph, of) in glyphs.iter() {
+        if markers.removed::<Target>().contains(&of.of) {
+            commands.despawn(glyph);
+        }
+    }
+    for (marker, target, poi, material) in markers.iter() {
+        for (glyph, of) in glyphs.iter() {
+            if of.of == marker {
+                commands.despawn(glyph);
+            }
+        }
+        if target.notes.is_empty() {
+            continue;
+        }
+        let corner = poi.center + Vec2::new(poi.half_width, poi.half_width);
+        let dot = primitive(
+            material.clone(),
+            &mut meshes,
+            ShapeType::Circle(GLYPH_RADIUS),
+            TessellationMode::Fill(&FillOptions::default()),
+            corner.extend(0.0),
+        );
+        commands
+            .spawn(dot)
+            .with(NoteGlyph { of: marker })
+            .with(Layer::Markers);
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+use clap::ValueEnum;
+
+/// How close to an occluder's surface, relative to its radius, a point still counts
+/// as on top of it rather than inside: annotations sit right on the surface.
+const SURFACE_TOLERANCE: f32 = 1e-3;
+
+/// What becomes of annotations on the far side of a globe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum FarSide {
+    /// Not drawn at all.
+    #[default]
+    Hide,
+    /// Drawn faintly, at [`Occlusion::dim_alpha`].
+    Dim,
+}
+
+/// How [`OcclusionPlugin`] treats annotations hidden behind an [`Occluder`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Occlusion {
+    pub far_side: FarSide,
+    /// Opacity of dimmed annotations.
+    pub dim_alpha: f32,
+    /// How far from its horizon, in [`facing`] terms, an annotation on a globe starts
+    /// fading towards its far-side opacity, so that it fades out as the globe turns it
+    /// away rather than vanishing at the limb; 0 switches it at the horizon.
+    pub fade: f32,
+}
+
+impl Default for Occlusion {
+    fn default() -> Self {
+        Occlusion {
+            far_side: FarSide::Hide,
+            dim_alpha: 0.25,
+            fade: 0.15,
+        }
+    }
+}
+
+impl Occlusion {
+    /// The opacity of an annotation, whether `occluded` or not.
+    pub fn alpha(&self, occluded: bool) -> f32 {
+        match (occluded, self.far_side) {
+            (false, _) => 1.0,
+            (true, FarSide::Hide) => 0.0,
+            (true, FarSide::Dim) => self.dim_alpha,
+        }
+    }
+
+    /// The opacity of an annotation as far round its globe as `facing`, fading from
+    /// fully drawn to [`Occlusion::alpha`] of an occluded one over [`Occlusion::fade`].
+    pub fn fade_alpha(&self, facing: f32) -> f32 {
+        let far = self.alpha(true);
+        if facing <= 0.0 {
+            return far;
+        }
+        if self.fade <= 0.0 {
+            return 1.0;
+        }
+        far + (1.0 - far) * (facing / self.fade).min(1.0)
+    }
+}
+
+/// On a sphere centered on its entity that hides what is behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Occluder {
+    /// Radius of the sphere before its entity's scale.
+    pub radius: f32,
+}
+
+/// On a 3D annotation drawn with a material of its own, whose alpha
+/// [`OcclusionPlugin`] sets as the annotation goes behind an [`Occluder`] and comes
+/// back. Its draw should be transparent for that to show.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Occludable;
+
+/// Whether the sphere at `center` hides `point` from `eye`: the line of sight passes
+/// through the sphere on its way. Points on or above the surface facing the eye are
+/// visible; so is everything in front of the sphere's horizon as seen from `eye`,
+/// which takes in more of the sphere the farther away the eye is.
+pub fn occluded(eye: Vec3, center: Vec3, radius: f32, point: Vec3) -> bool {
+    let sight = point - eye;
+    let length_squared = sight.length_squared();
+    if length_squared <= f32::EPSILON {
+        return false;
+    }
+    // The point along the line of sight closest to the center.
+    let t = ((center - eye).dot(sight) / length_squared).clamp(0.0, 1.0);
+    let closest = eye + sight * t;
+    let limit = radius * (1.0 - SURFACE_TOLERANCE);
+    (closest - center).length_squared() < limit * limit
+}
+
+/// How far `point`, on or above the sphere at `center`, is turned towards `eye`: the
+/// cosine between its surface normal and its line of sight to the eye, 1 when facing it
+/// straight on, falling to 0 at its horizon and staying 0 behind it. The horizon is that
+/// of the point's height, so a raised point only reaches 0 as it sinks behind the limb.
+pub fn facing(eye: Vec3, center: Vec3, radius: f32, point: Vec3) -> f32 {
+    if occluded(eye, center, radius, point) {
+        return 0.0;
+    }
+    let normal = point - center;
+    let sight = eye - point;
+    let (height, distance) = (normal.length(), sight.length());
+    if height <= f32::EPSILON || distance <= f32::EPSILON {
+        return 1.0;
+    }
+    let cos = normal.dot(sight) / (height * distance);
+    // Seen from afar, a point at `height` goes behind the limb this far past its normal
+    // turning square to the eye.
+    let horizon = if height > radius {
+        (1.0 - (radius / height).powi(2)).sqrt()
+    } else {
+        0.0
+    };
+    ((cos + horizon) / (1.0 + horizon)).clamp(0.0, 1.0)
+}
+
+/// The opacity [`Occlusion`] gives an annotation at `point` seen from `eye` among the
+/// `spheres`, as centers and radii: that of an occluded one if any sphere hides it,
+/// otherwise faded by how far round the sphere it sits on it is, the one whose surface
+/// is nearest.
+pub fn occlusion_alpha(
+    occlusion: &Occlusion,
+    spheres: &[(Vec3, f32)],
+    eye: Vec3,
+    point: Vec3,
+) -> f32 {
+    if spheres
+        .iter()
+        .any(|&(center, radius)| occluded(eye, center, radius, point))
+    {
+        return occlusion.alpha(true);
+    }
+    let above = |&(center, radius): &(Vec3, f32)| ((point - center).length() - radius) / radius;
+    let on = spheres
+        .iter()
+        .filter(|(_, radius)| *radius > 0.0)
+        .min_by(|a, b| above(a).partial_cmp(&above(b)).unwrap());
+    match on {
+        Some(&(center, radius)) => occlusion.fade_alpha(facing(eye, center, radius, point)),
+        None => 1.0,
+    }
+}
+
+/// Hides or dims [`Occludable`] annotations behind any [`Occluder`] as seen from the
+/// 3D camera, following [`Occlusion`], fading them out as they turn towards the horizon
+/// of the globe they sit on.
+pub struct OcclusionPlugin;
+
+impl Plugin for OcclusionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Occlusion>() {
+            app.init_resource::<Occlusion>();
+        }
+        app.add_system(occlusion_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn occlusion_system(
+    occlusion: Res<Occlusion>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    occluders: Query<(&Occluder, &GlobalTransform)>,
+    annotations: Query<With<Occludable, (&GlobalTransform, &Handle<StandardMaterial>)>>,
+) {
+    let eye = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some((_, transform)) => transform.translation,
+        None => return,
+    };
+    let spheres = occluders
+        .iter()
+        .map(|(occluder, transform)| (transform.translation, occluder.radius * transform.scale.x()))
+        .collect::<Vec<_>>();
+    for (transform, material) in annotations.iter() {
+        let alpha = occlusion_alpha(&occlusion, &spheres, eye, transform.translation);
+        // Touching a material has it uploaded again, so only touch it on a change.
+        if materials
+            .get(material)
+            .is_some_and(|m| m.albedo.a() != alpha)
+        {
+            if let Some(material) = materials.get_mut(material) {
+                material.albedo.set_a(alpha);
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Ring colors [`SensorOrigins::in_a_row`] hands out in turn, as linear RGBA.
+const PALETTE: [[f32; 4]; 6] = [
+    [0.1, 0.9, 0.3, 1.0],
+    [0.2, 0.6, 1.0, 1.0],
+    [1.0, 0.7, 0.1, 1.0],
+    [0.9, 0.3, 0.9, 1.0],
+    [0.1, 0.9, 0.9, 1.0],
+    [1.0, 0.35, 0.3, 1.0],
+];
+
+/// A sensor the ring display is centered on. Targets say which one saw them with
+/// [`Target::origin`](crate::target::Target::origin), an index into
+/// [`SensorOrigins`]; each origin is laid out on its own, with rings, markers and
+/// leader lines around its `offset`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorOrigin {
+    #[serde(default)]
+    pub name: String,
+    /// Where the origin sits in the world, as `[x, y]`.
+    #[serde(default)]
+    pub offset: [f32; 2],
+    /// Color of its origin dot, rings and leader lines as linear RGBA; the theme's
+    /// stroke color when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[f32; 4]>,
+}
+
+impl SensorOrigin {
+    pub fn offset(&self) -> Vec2 {
+        Vec2::new(self.offset[0], self.offset[1])
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.color.map(|[r, g, b, a]| Color::rgba(r, g, b, a))
+    }
+}
+
+/// The sensor origins of the ring display, indexed by
+/// [`Target::origin`](crate::target::Target::origin). By default there is one, at the
+/// world origin in the theme's color. Targets of an origin not listed are drawn
+/// around the world origin too. Changing the resource lays everything out again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorOrigins(pub Vec<SensorOrigin>);
+
+impl Default for SensorOrigins {
+    fn default() -> Self {
+        SensorOrigins(vec![SensorOrigin {
+            name: String::new(),
+            offset: [0.0, 0.0],
+            color: None,
+        }])
+    }
+}
+
+impl SensorOrigins {
+    /// `count` origins along the x axis, `spacing` apart and centered on the world
+    /// origin, each in a color of its own.
+    pub fn in_a_row(count: usize, spacing: f32) -> Self {
+        let first = -spacing * count.saturating_sub(1) as f32 / 2.0;
+        SensorOrigins(
+            (0..count)
+                .map(|i| SensorOrigin {
+                    name: format!("sensor {}", i + 1),
+                    offset: [first + spacing * i as f32, 0.0],
+                    color: Some(PALETTE[i % PALETTE.len()]),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, origin: usize) -> Option<&SensorOrigin> {
+        self.0.get(origin)
+    }
+
+    /// Where `origin` sits in the world.
+    pub fn offset(&self, origin: usize) -> Vec2 {
+        self.get(origin).map_or(Vec2::zero(), SensorOrigin::offset)
+    }
+
+    /// The color of `origin`, if it has one of its own.
+    pub fn color(&self, origin: usize) -> Option<Color> {
+        self.get(origin).and_then(SensorOrigin::color)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+use crate::target::Target;
+
+/// Stops of the ramps of [`Ramp`], as sRGB hex, spaced evenly.
+const VIRIDIS: [u32; 9] = [
+    0x44_01_54, 0x47_2d_7b, 0x3b_52_8b, 0x2c_72_8e, 0x21_91_8c, 0x28_ae_80, 0x5e_c9_62, 0xad_dc_30,
+    0xfd_e7_25,
+];
+const TURBO: [u32; 13] = [
+    0x30_12_3b, 0x44_51_c1, 0x41_8d_fe, 0x25_c0_e7, 0x1b_e5_b5, 0x4c_fa_7b, 0x95_fb_3f, 0xc8_ef_34,
+    0xef_ce_3a, 0xfe_a3_31, 0xf3_63_15, 0xd0_2f_05, 0x7a_04_03,
+];
+/// Blue through light grey to red, the same lightness on either side of the middle.
+const DIVERGING: [u32; 9] = [
+    0x3b_4c_c0, 0x62_82_ea, 0x8d_b0_fe, 0xb8_d0_f9, 0xdd_dd_dd, 0xf5_c4_ad, 0xf4_9a_7b, 0xde_60_4d,
+    0xb4_04_26,
+];
+const TABLEAU10: [u32; 10] = [
+    0x4e_79_a7, 0xf2_8e_2b, 0xe1_57_59, 0x76_b7_b2, 0x59_a1_4f, 0xed_c9_48, 0xb0_7a_a1, 0xff_9d_a7,
+    0x9c_75_5f, 0xba_b0_ac,
+];
+/// Okabe and Ito's colors for color-blind readers, without the black, which is lost on
+/// a dark display.
+const OKABE_ITO: [u32; 7] = [
+    0x56_b4_e9, 0xd5_5e_00, 0x00_9e_73, 0xf0_e4_42, 0xe6_9f_00, 0x00_72_b2, 0xcc_79_a7,
+];
+
+/// An sRGB `0xRRGGBB` color.
+fn hex(rgb: u32) -> Color {
+    Color::rgb_u8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+/// Colors over `0` to `1`, blended between neighbouring stops, which go in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRamp {
+    pub stops: Vec<(f32, Color)>,
+}
+
+impl Default for ColorRamp {
+    fn default() -> Self {
+        Ramp::default().ramp()
+    }
+}
+
+impl ColorRamp {
+    /// `colors` spaced evenly from `0` to `1`.
+    pub fn even(colors: &[Color]) -> Self {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        ColorRamp {
+            stops: colors
+                .iter()
+                .enumerate()
+                .map(|(i, &color)| (i as f32 / last, color))
+                .collect(),
+        }
+    }
+
+    /// Clear where there is nothing, through blue, green and yellow to red, for
+    /// [`Heatmap`](crate::heatmap::Heatmap)s.
+    pub fn heat() -> Self {
+        ColorRamp {
+            stops: vec![
+                (0.0, Color::rgba(0.0, 0.0, 1.0, 0.0)),
+                (0.2, Color::rgba(0.0, 0.3, 1.0, 0.5)),
+                (0.45, Color::rgba(0.0, 0.9, 0.4, 0.7)),
+                (0.7, Color::rgba(1.0, 0.9, 0.0, 0.8)),
+                (1.0, Color::rgba(1.0, 0.1, 0.0, 0.9)),
+            ],
+        }
+    }
+
+    /// Color at `t`, that of the first or last stop outside them.
+    pub fn color(&self, t: f32) -> Color {
+        let upper = match self.stops.iter().position(|&(at, _)| at >= t) {
+            Some(0) => return self.stops[0].1,
+            Some(upper) => upper,
+            None => return self.stops.last().map_or(Color::NONE, |&(_, color)| color),
+        };
+        let ((from, a), (to, b)) = (self.stops[upper - 1], self.stops[upper]);
+        let t = ((t - from) / (to - from)).clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        // Color's channels are linear, so the blend is too.
+        Color::rgba_linear(
+            mix(a.r(), b.r()),
+            mix(a.g(), b.g()),
+            mix(a.b(), b.b()),
+            mix(a.a(), b.a()),
+        )
+    }
+}
+
+/// The built-in [`ColorRamp`]s. Viridis and the diverging ramp change evenly in
+/// lightness, as seen, from end to end, so equal steps of a value look like equal
+/// steps of color; turbo changes smoothly but not evenly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Ramp {
+    /// Dark purple through blue and green to yellow, readable in greyscale and by most
+    /// color-blind readers.
+    #[default]
+    Viridis,
+    /// Dark blue through cyan, green and yellow to dark red: a rainbow, for telling
+    /// values apart rather than reading how far apart they are.
+    Turbo,
+    /// Blue to red through grey in the middle, for values either side of a midpoint.
+    Diverging,
+    /// See [`ColorRamp::heat`].
+    Heat,
+}
+
+impl Ramp {
+    pub fn ramp(self) -> ColorRamp {
+        let stops: &[u32] = match self {
+            Ramp::Viridis => &VIRIDIS,
+            Ramp::Turbo => &TURBO,
+            Ramp::Diverging => &DIVERGING,
+            Ramp::Heat => return ColorRamp::heat(),
+        };
+        ColorRamp::even(&stops.iter().copied().map(hex).collect::<Vec<_>>())
+    }
+}
+
+/// Sets of distinct colors for telling categories apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Categorical {
+    /// Seven colors most color-blind readers can still tell apart.
+    #[default]
+    OkabeIto,
+    /// Ten colors of Tableau's.
+    Tableau10,
+}
+
+impl Categorical {
+    pub fn len(self) -> usize {
+        self.stops().len()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.stops().is_empty()
+    }
+
+    /// The `index`th color, from the start again past the last.
+    pub fn color(self, index: usize) -> Color {
+        let stops = self.stops();
+        hex(stops[index % stops.len()])
+    }
+
+    pub fn colors(self) -> Vec<Color> {
+        self.stops().iter().copied().map(hex).collect()
+    }
+
+    fn stops(self) -> &'static [u32] {
+        match self {
+            Categorical::OkabeIto => &OKABE_ITO,
+            Categorical::Tableau10 => &TABLEAU10,
+        }
+    }
+}
+
+/// A number about a target to color it by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TargetAttribute {
+    Distance,
+    /// Seconds since the target was last reported, which the target itself doesn't
+    /// know; see [`AgingPlugin`](crate::aging::AgingPlugin).
+    Age,
+    Priority,
+}
+
+impl TargetAttribute {
+    /// The attribute of `target`, or `None` for an age that isn't known.
+    pub fn value(self, target: &Target, age: Option<f32>) -> Option<f32> {
+        match self {
+            TargetAttribute::Distance => Some(target.dist),
+            TargetAttribute::Age => age,
+            TargetAttribute::Priority => Some(target.priority as f32),
+        }
+    }
+}
+
+/// Colors targets by a [`TargetAttribute`], `range` spanning the ramp: values at or
+/// below its start get the first color, at or above its end the last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorBy {
+    pub attribute: TargetAttribute,
+    pub ramp: ColorRamp,
+    pub range: (f32, f32),
+}
+
+impl ColorBy {
+    pub fn new(attribute: TargetAttribute, ramp: ColorRamp, range: (f32, f32)) -> Self {
+        ColorBy {
+            attribute,
+            ramp,
+            range,
+        }
+    }
+
+    /// Where `value` falls along the ramp, from `0` to `1`.
+    pub fn position(&self, value: f32) -> f32 {
+        let (from, to) = self.range;
+        if to == from {
+            return if value < from { 0.0 } else { 1.0 };
+        }
+        ((value - from) / (to - from)).clamp(0.0, 1.0)
+    }
+
+    /// The color of `target`, `age` seconds after its last report if known; `None` for
+    /// coloring by an unknown age.
+    pub fn color(&self, target: &Target, age: Option<f32>) -> Option<Color> {
+        let value = self.attribute.value(target, age)?;
+        Some(self.ramp.color(self.position(value)))
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::{CameraState, OrbitCamera};
+use crate::multi_select::SelectionSet;
+use crate::scenario::Scenario;
+use crate::selection::{SelectTarget, Selected};
+use crate::target::Target;
+use crate::zones::{AlertZone, AlertZones};
+
+/// Marks an entity whose `Transform` is saved with the session under the given key.
+pub struct Persist(pub &'static str);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Pose {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl From<&Transform> for Pose {
+    fn from(t: &Transform) -> Self {
+        Pose {
+            translation: [t.translation.x(), t.translation.y(), t.translation.z()],
+            rotation: [
+                t.rotation.x(),
+                t.rotation.y(),
+                t.rotation.z(),
+                t.rotation.w(),
+            ],
+            scale: [t.scale.x(), t.scale.y(), t.scale.z()],
+        }
+    }
+}
+
+impl Pose {
+    pub fn apply(&self, t: &mut Transform) {
+        let [x, y, z] = self.translation;
+        t.translation = Vec3::new(x, y, z);
+        let [x, y, z, w] = self.rotation;
+        t.rotation = Quat::from_xyzw(x, y, z, w);
+        let [x, y, z] = self.scale;
+        t.scale = Vec3::new(x, y, z);
+    }
+}
+
+/// Everything needed to bring a display back to where it was.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionState {
+    pub scenario: Scenario,
+    #[serde(default)]
+    pub poses: BTreeMap<String, Pose>,
+    /// The 3D camera's orbit is taken around the world origin unless it is an
+    /// [`OrbitCamera`].
+    #[serde(default)]
+    pub camera: CameraState,
+    /// The id of the [`Selected`] target.
+    #[serde(default)]
+    pub selected: Option<i32>,
+    /// The ids in the [`SelectionSet`].
+    #[serde(default)]
+    pub selection_set: Vec<i32>,
+    /// The [`AlertZones`], `None` from a display without them, which keeps the zones
+    /// it is configured with on restore.
+    #[serde(default)]
+    pub zones: Option<Vec<AlertZone>>,
+}
+
+impl SessionState {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes to a temporary file first and renames it over `path`, so a crash while
+    /// saving never leaves a truncated session behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(self).map_err(io::Error::other)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+/// Loads the session saved at `path` if there is one and the user wants it back.
+///
+/// Without `assume_yes` the user is asked on stdin; when stdin is not a terminal the
+/// saved session is left alone.
+pub fn offer_restore(path: &Path, assume_yes: bool) -> Option<SessionState> {
+    if !path.exists() {
+        return None;
+    }
+    let state = match SessionState::load(path) {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::warn!("ignoring unreadable session {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    if assume_yes {
+        return Some(state);
+    }
+    if !io::stdin().is_terminal() {
+        tracing::info!(
+            "found saved session {}, pass --restore to load it",
+            path.display()
+        );
+        return None;
+    }
+    print!("Restore previous session from {}? [Y/n] ", path.display());
+    io::stdout().flush().ok()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer).ok()?;
+    match answer.trim() {
+        "" | "y" | "Y" | "yes" => Some(state),
+        _ => None,
+    }
+}
+
+/// Periodically saves the current [`Scenario`] with the [`Target`]s as they are now,
+/// the [`CameraState`], every [`Persist`] transform, the selection and the alert zones
+/// to `path`, and applies the camera state and poses of a restored session to entities
+/// as they appear. The selected target is selected again through [`SelectTarget`] and
+/// the ids of the [`SelectionSet`] put back into it once their targets are there, and
+/// the [`AlertZones`] are put back. Add after
+/// [`SelectionPlugin`](crate::selection::SelectionPlugin),
+/// [`MultiSelectPlugin`](crate::multi_select::MultiSelectPlugin) and
+/// [`AlertZonesPlugin`](crate::zones::AlertZonesPlugin), whichever the display has.
+pub struct PersistPlugin {
+    pub path: PathBuf,
+    pub interval: f32,
+    pub restored: Option<SessionState>,
+}
+
+struct PersistConfig {
+    path: PathBuf,
+    timer: Timer,
+}
+
+#[derive(Default)]
+struct PendingPoses(BTreeMap<String, Pose>);
+
+/// What of a restored [`CameraState`] has no camera to go on yet.
+#[derive(Default)]
+struct PendingCamera(CameraState);
+
+/// What of a restored selection and zones is yet to be put back.
+#[derive(Default)]
+struct PendingSelection {
+    selected: Option<i32>,
+    selection_set: Vec<i32>,
+    zones: Option<Vec<AlertZone>>,
+}
+
+/// The [`SelectionSet`] and [`AlertZones`] as last changed, for [`save_system`] to save
+/// whether or not the display has them.
+#[derive(Default)]
+struct SavedSelection {
+    selection_set: Vec<i32>,
+    zones: Option<Vec<AlertZone>>,
+}
+
+impl Plugin for PersistPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let (pending, camera) = self
+            .restored
+            .as_ref()
+            .map(|s| (s.poses.clone(), s.camera))
+            .unwrap_or_default();
+        let selection = self
+            .restored
+            .as_ref()
+            .map(|s| PendingSelection {
+                selected: s.selected,
+                selection_set: s.selection_set.clone(),
+                zones: s.zones.clone(),
+            })
+            .unwrap_or_default();
+        let saved = SavedSelection {
+            selection_set: app
+                .resources()
+                .get::<SelectionSet>()
+                .map_or_else(Vec::new, |set| set.iter().collect()),
+            zones: app
+                .resources()
+                .get::<AlertZones>()
+                .map(|zones| zones.zones.clone()),
+        };
+        if app.resources().contains::<Events<SelectTarget>>() {
+            app.add_system(restore_selected_system.system());
+        }
+        if app.resources().contains::<SelectionSet>() {
+            app.add_system(restore_selection_set_system.system())
+                .add_system(track_selection_set_system.system());
+        }
+        if app.resources().contains::<AlertZones>() {
+            app.add_system(restore_zones_system.system())
+                .add_system(track_zones_system.system());
+        }
+        app.add_resource(PersistConfig {
+            path: self.path.clone(),
+            timer: Timer::from_seconds(self.interval, true),
+        })
+        .add_resource(PendingPoses(pending))
+        .add_resource(PendingCamera(camera))
+        .add_resource(selection)
+        .add_resource(saved)
+        .add_system(restore_poses_system.system())
+        .add_system(restore_camera_system.system())
+        .add_system(save_system.system());
+    }
+}
+
+fn restore_poses_system(
+    mut pending: ResMut<PendingPoses>,
+    mut query: Query<(&Persist, Mut<Transform>)>,
+) {
+    if pending.0.is_empty() {
+        return;
+    }
+    for (persist, mut transform) in query.iter_mut() {
+        if let Some(pose) = pending.0.remove(persist.0) {
+            pose.apply(&mut transform);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn restore_camera_system(
+    mut pending: ResMut<PendingCamera>,
+    mut cameras: Query<(&Camera, Mut<Transform>, Option<Mut<OrbitCamera>>)>,
+) {
+    if pending.0 == CameraState::default() {
+        return;
+    }
+    for (camera, mut transform, orbit_camera) in cameras.iter_mut() {
+        // An orbiting camera follows its orbit, so that is what takes the saved one.
+        if let (Some(orbit), Some(mut orbit_camera)) = (pending.0.orbit, orbit_camera) {
+            orbit_camera.set(orbit);
+            pending.0.orbit = None;
+        } else if pending.0.restore(camera, &mut transform) {
+            match camera.name.as_deref() {
+                Some(CAMERA2D) => pending.0.pan_zoom = None,
+                _ => pending.0.orbit = None,
+            }
+        }
+    }
+}
+
+fn restore_selected_system(
+    mut pending: ResMut<PendingSelection>,
+    mut requests: ResMut<Events<SelectTarget>>,
+    targets: Query<&Target>,
+) {
+    let id = match pending.selected {
+        Some(id) => id,
+        None => return,
+    };
+    if targets.iter().any(|target| target.id == id) {
+        requests.send(SelectTarget(Some(id)));
+        pending.selected = None;
+    }
+}
+
+fn restore_selection_set_system(
+    mut pending: ResMut<PendingSelection>,
+    mut set: ResMut<SelectionSet>,
+    targets: Query<&Target>,
+) {
+    if pending.selection_set.is_empty() {
+        return;
+    }
+    let (found, missing): (Vec<i32>, Vec<i32>) = pending
+        .selection_set
+        .iter()
+        .partition(|&&id| targets.iter().any(|target| target.id == id));
+    if !found.is_empty() {
+        let ids: Vec<i32> = set.iter().chain(found).collect();
+        set.set(ids);
+        pending.selection_set = missing;
+    }
+}
+
+fn restore_zones_system(mut pending: ResMut<PendingSelection>, mut zones: ResMut<AlertZones>) {
+    if let Some(restored) = pending.zones.take() {
+        zones.zones = restored;
+    }
+}
+
+fn track_selection_set_system(set: ChangedRes<SelectionSet>, mut saved: ResMut<SavedSelection>) {
+    saved.selection_set = set.iter().collect();
+}
+
+fn track_zones_system(zones: ChangedRes<AlertZones>, mut saved: ResMut<SavedSelection>) {
+    saved.zones = Some(zones.zones.clone());
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_system(
+    time: Res<Time>,
+    mut config: ResMut<PersistConfig>,
+    scenario: Res<Scenario>,
+    pending: Res<PendingSelection>,
+    saved: Res<SavedSelection>,
+    query: Query<(&Persist, &Transform)>,
+    cameras: Query<(&Camera, &Transform, Option<&OrbitCamera>)>,
+    targets: Query<&Target>,
+    selected: Query<With<Selected, &Target>>,
+) {
+    config.timer.tick(time.delta_seconds);
+    if !config.timer.just_finished {
+        return;
+    }
+    let camera = CameraState::save(cameras.iter().map(|(c, t, _)| (c, t)), Vec3::zero());
+    // The targets as the feed, updates and aging left them, not as authored.
+    let mut live: Vec<Target> = targets.iter().cloned().collect();
+    live.sort_by_key(|target| target.id);
+    let state = SessionState {
+        scenario: Scenario {
+            targets: live,
+            ..scenario.clone()
+        },
+        poses: query
+            .iter()
+            .map(|(persist, transform)| (persist.0.to_string(), Pose::from(transform)))
+            .collect(),
+        camera: CameraState {
+            orbit: cameras
+                .iter()
+                .find_map(|(_, _, orbit)| orbit.map(OrbitCamera::orbit))
+                .or(camera.orbit),
+            ..camera
+        },
+        // What is still waiting for its targets is kept for the next restore.
+        selected: selected
+            .iter()
+            .next()
+            .map(|target| target.id)
+            .or(pending.selected),
+        selection_set: saved
+            .selection_set
+            .iter()
+            .chain(&pending.selection_set)
+            .copied()
+            .collect(),
+        zones: saved.zones.clone(),
+    };
+    if let Err(e) = state.save(&config.path) {
+        tracing::error!("failed to save session to {}: {}", config.path.display(), e);
+    }
+}
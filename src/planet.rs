@@ -0,0 +1,239 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::texture::TextureFormat;
+use clap::ValueEnum;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+/// Cells per unit of the first noise octave; higher gives more, smaller continents.
+const CONTINENT_FREQUENCY: f32 = 1.6;
+/// How much each octave's amplitude shrinks relative to the one before.
+const PERSISTENCE: f32 = 0.5;
+/// Radians the ice cap edge wanders either side of [`PlanetTexture::ice_latitude`].
+const ICE_JITTER: f32 = 0.08;
+
+/// Color stops over elevation, from the deepest ocean at `-1.0` through the shore at
+/// `0.0` to the highest peak at `1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Palette {
+    /// Blue oceans, green lowlands, brown mountains and snowy peaks.
+    #[default]
+    Temperate,
+    /// Shallow teal seas and sand, ochre and rust highlands.
+    Arid,
+    /// Dark basalt plains cut by glowing lava seas.
+    Volcanic,
+}
+
+impl Palette {
+    fn stops(self) -> &'static [(f32, [u8; 3])] {
+        match self {
+            Palette::Temperate => &[
+                (-1.0, [8, 20, 60]),
+                (-0.3, [20, 60, 130]),
+                (0.0, [60, 120, 180]),
+                (0.02, [200, 190, 140]),
+                (0.1, [70, 130, 50]),
+                (0.45, [40, 90, 35]),
+                (0.7, [110, 90, 60]),
+                (0.9, [150, 140, 130]),
+                (1.0, [245, 245, 250]),
+            ],
+            Palette::Arid => &[
+                (-1.0, [20, 60, 80]),
+                (0.0, [70, 150, 150]),
+                (0.03, [230, 210, 160]),
+                (0.4, [200, 150, 80]),
+                (0.75, [150, 80, 40]),
+                (1.0, [90, 50, 35]),
+            ],
+            Palette::Volcanic => &[
+                (-1.0, [120, 10, 0]),
+                (-0.2, [230, 80, 10]),
+                (0.0, [255, 190, 60]),
+                (0.03, [40, 30, 30]),
+                (0.6, [25, 22, 24]),
+                (1.0, [80, 75, 75]),
+            ],
+        }
+    }
+
+    /// Color at `elevation`, blended between the neighbouring stops.
+    pub fn color(self, elevation: f32) -> [u8; 3] {
+        let stops = self.stops();
+        let upper = stops
+            .iter()
+            .position(|&(at, _)| at >= elevation)
+            .unwrap_or(stops.len() - 1);
+        if upper == 0 {
+            return stops[0].1;
+        }
+        let ((from, a), (to, b)) = (stops[upper - 1], stops[upper]);
+        let t = ((elevation - from) / (to - from)).clamp(0.0, 1.0);
+        let mut color = [0; 3];
+        for (c, (a, b)) in color.iter_mut().zip(a.iter().zip(&b)) {
+            *c = (*a as f32 + (*b as f32 - *a as f32) * t).round() as u8;
+        }
+        color
+    }
+}
+
+/// A fictional planet's surface, painted into an equirectangular texture by
+/// [`PlanetTexture::generate`]: fractal noise sampled on the sphere, so there is no
+/// seam at the antimeridian or pinching at the poles, colored by a [`Palette`] with
+/// ice caps over both poles. The same seed always gives the same planet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlanetTexture {
+    pub seed: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Share of the surface below sea level, from 0 to 1.
+    pub ocean: f32,
+    /// Latitude in radians poleward of which land and sea are iced over; `π/2` or more
+    /// for no ice caps.
+    pub ice_latitude: f32,
+    /// Noise octaves summed; more add finer coastline detail.
+    pub octaves: u32,
+    pub palette: Palette,
+}
+
+impl Default for PlanetTexture {
+    fn default() -> Self {
+        PlanetTexture {
+            seed: 0,
+            width: 2048,
+            height: 1024,
+            ocean: 0.65,
+            ice_latitude: 68f32.to_radians(),
+            octaves: 6,
+            palette: Palette::Temperate,
+        }
+    }
+}
+
+impl PlanetTexture {
+    pub fn generate(&self) -> Texture {
+        let noise = ValueNoise::new(self.seed);
+        let (width, height) = (self.width.max(1) as usize, self.height.max(1) as usize);
+        let latitude = |y: usize| (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+        let longitude = |x: usize| ((x as f32 + 0.5) / width as f32 - 0.5) * PI * 2.0;
+
+        let mut elevations = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let lat = latitude(y);
+            for x in 0..width {
+                let point = on_sphere(lat, longitude(x)) * CONTINENT_FREQUENCY;
+                elevations.push(noise.fractal(point, self.octaves.max(1)));
+            }
+        }
+
+        // Pick sea level so that `ocean` of the texels lie below it, then stretch each
+        // side of it to fill its half of the palette.
+        let mut sorted = elevations.clone();
+        sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+        let quantile = (self.ocean.clamp(0.0, 1.0) * (sorted.len() - 1) as f32) as usize;
+        let (lowest, highest) = (sorted[0], sorted[sorted.len() - 1]);
+        let sea_level = sorted[quantile];
+
+        let mut data = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let lat = latitude(y);
+            for x in 0..width {
+                let raw = elevations[y * width + x];
+                let elevation = if raw < sea_level {
+                    -(sea_level - raw) / (sea_level - lowest).max(f32::EPSILON)
+                } else {
+                    (raw - sea_level) / (highest - sea_level).max(f32::EPSILON)
+                };
+                // Offset noise so the cap edge does not follow the coastlines.
+                let edge_point = on_sphere(lat, longitude(x)) * 4.0 + Vec3::splat(17.0);
+                let edge = noise.fractal(edge_point, 3);
+                let [r, g, b] = if lat.abs() + edge * ICE_JITTER > self.ice_latitude {
+                    let shade = 235 + (elevation.max(0.0) * 20.0) as u8;
+                    [shade - 10, shade - 5, shade]
+                } else {
+                    self.palette.color(elevation)
+                };
+                data.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Texture::new(
+            Vec2::new(width as f32, height as f32),
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+}
+
+fn on_sphere(lat: f32, lon: f32) -> Vec3 {
+    Vec3::new(lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin())
+}
+
+/// Smoothly interpolated random values on an integer lattice, in `[-1, 1]`.
+struct ValueNoise {
+    permutation: [u8; 512],
+    values: [f32; 256],
+}
+
+impl ValueNoise {
+    fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut order: Vec<u8> = (0..=255).collect();
+        order.shuffle(&mut rng);
+        let mut permutation = [0; 512];
+        for (i, p) in permutation.iter_mut().enumerate() {
+            *p = order[i % 256];
+        }
+        let mut values = [0.0; 256];
+        for v in values.iter_mut() {
+            *v = rng.gen_range(-1.0, 1.0);
+        }
+        ValueNoise {
+            permutation,
+            values,
+        }
+    }
+
+    fn lattice(&self, x: i32, y: i32, z: i32) -> f32 {
+        let p = &self.permutation;
+        let i =
+            p[p[p[(x & 255) as usize] as usize + (y & 255) as usize] as usize + (z & 255) as usize];
+        self.values[i as usize]
+    }
+
+    fn sample(&self, point: Vec3) -> f32 {
+        let floor = Vec3::new(point.x().floor(), point.y().floor(), point.z().floor());
+        let (x, y, z) = (floor.x() as i32, floor.y() as i32, floor.z() as i32);
+        let f = point - floor;
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (u, v, w) = (smooth(f.x()), smooth(f.y()), smooth(f.z()));
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let face = |dz: i32| {
+            let near = lerp(
+                self.lattice(x, y, z + dz),
+                self.lattice(x + 1, y, z + dz),
+                u,
+            );
+            let far = lerp(
+                self.lattice(x, y + 1, z + dz),
+                self.lattice(x + 1, y + 1, z + dz),
+                u,
+            );
+            lerp(near, far, v)
+        };
+        lerp(face(0), face(1), w)
+    }
+
+    /// Octaves of doubling frequency and shrinking amplitude, normalized to `[-1, 1]`.
+    fn fractal(&self, point: Vec3, octaves: u32) -> f32 {
+        let (mut sum, mut amplitude, mut total, mut frequency) = (0.0, 1.0, 0.0, 1.0);
+        for _ in 0..octaves {
+            sum += self.sample(point * frequency) * amplitude;
+            total += amplitude;
+            amplitude *= PERSISTENCE;
+            frequency *= 2.0;
+        }
+        sum / total
+    }
+}
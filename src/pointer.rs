@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+
+use crate::split_view::ViewRect;
+use crate::viewport::DisplayViewport;
+
+/// Where the cursor is, in window pixels (origin bottom-left) and in 2D world space.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CursorPosition {
+    pub screen: Option<Vec2>,
+    pub world: Option<Vec2>,
+    /// Relative to the bottom-left corner of the camera's
+    /// [`DisplayViewport`](crate::viewport::DisplayViewport), or the window without one.
+    /// `None` while the cursor is outside the viewport, which also clears `world`, as
+    /// does being over the [`ViewRect`] of another camera, such as the inset of
+    /// [`MinimapPlugin`](crate::minimap::MinimapPlugin).
+    pub viewport: Option<Vec2>,
+}
+
+/// Maps a window position to world space through the 2D camera's transform, so camera
+/// translation and scale (zoom) are accounted for.
+pub fn screen_to_world(screen: Vec2, window_size: Vec2, camera: &Transform) -> Vec2 {
+    let centered = screen - window_size / 2.0;
+    let world = camera.compute_matrix() * centered.extend(0.0).extend(1.0);
+    Vec2::new(world.x(), world.y())
+}
+
+pub(crate) fn cursor_system(
+    mut reader: Local<EventReader<CursorMoved>>,
+    cursor_moved_events: Res<Events<CursorMoved>>,
+    windows: Res<Windows>,
+    mut cursor: ResMut<CursorPosition>,
+    cameras: Query<(&Camera, &Transform, Option<&DisplayViewport>)>,
+    views: Query<With<Camera, &ViewRect>>,
+) {
+    if let Some(event) = reader.latest(&cursor_moved_events) {
+        cursor.screen = Some(event.position);
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let camera = cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA2D));
+    let viewport = camera.and_then(|(_, _, viewport)| viewport);
+    let covered = |screen: Vec2| {
+        views
+            .iter()
+            .any(|rect| rect.to_local(screen, size).is_some())
+    };
+    cursor.viewport = match (cursor.screen, viewport) {
+        (Some(screen), _) if covered(screen) => None,
+        (Some(screen), Some(viewport)) => viewport.to_local(screen),
+        (screen, None) => screen,
+        (None, _) => None,
+    };
+    cursor.world = match (cursor.screen, cursor.viewport, camera) {
+        (Some(screen), Some(_), Some((_, transform, _))) => {
+            Some(screen_to_world(screen, size, transform))
+        }
+        _ => None,
+    };
+}
@@ -0,0 +1,241 @@
+use std::mem;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::bodies::{geo_to_local, local_to_geo, Body};
+use crate::geo_marker::body_for;
+use crate::route::{great_circle_point, ribbon_mesh};
+
+/// Below this distance on the unit sphere, two points of a line count as one.
+const SAME_POINT: f32 = 1e-6;
+/// Degrees within which a point counts as on a pole, where its longitude means nothing,
+/// or on the antimeridian, where it could be either side.
+const ON_LINE: f32 = 1e-3;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum PolylineError {
+    #[error("polyline {0:?} has fewer than two distinct points")]
+    TooShort(String),
+}
+
+/// A line through any number of points of a globe, such as a track or a border, drawn
+/// by [`GeoPolylinePlugin`] as a flat ribbon just above the surface. Each leg follows
+/// the great circle between its ends, so legs across the antimeridian or over a pole
+/// need no splitting beforehand: longitudes may be given in any range, 179° to -179°
+/// and 179° to 181° alike being two degrees apart. Spawn it as a child of the [`Body`]
+/// it belongs on, or on its own to put it on the body nearest the world origin, like a
+/// [`GeoRoute`](crate::route::GeoRoute).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoPolyline {
+    #[serde(default)]
+    pub name: String,
+    /// `[lat, lon]` in degrees. Repeated points are dropped.
+    pub points: Vec<[f32; 2]>,
+    /// Whether the last point joins back to the first.
+    #[serde(default)]
+    pub closed: bool,
+    /// Linear RGBA.
+    #[serde(default = "default_color")]
+    pub color: [f32; 4],
+    /// Width of the ribbon, relative to the body's radius.
+    #[serde(default = "default_width")]
+    pub width: f32,
+    /// How far above the surface the ribbon lies, relative to the body's radius.
+    #[serde(default = "default_lift")]
+    pub lift: f32,
+    /// Longest straight piece of the ribbon, in degrees of arc.
+    #[serde(default = "default_max_step")]
+    pub max_step: f32,
+}
+
+fn default_color() -> [f32; 4] {
+    [1.0, 0.8, 0.3, 0.9]
+}
+
+fn default_width() -> f32 {
+    0.003
+}
+
+fn default_lift() -> f32 {
+    0.005
+}
+
+fn default_max_step() -> f32 {
+    1.0
+}
+
+impl GeoPolyline {
+    pub fn new(points: Vec<[f32; 2]>) -> Self {
+        GeoPolyline {
+            name: String::new(),
+            points,
+            closed: false,
+            color: default_color(),
+            width: default_width(),
+            lift: default_lift(),
+            max_step: default_max_step(),
+        }
+    }
+
+    /// The line on the unit sphere, in the frame of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh): every point with more along each leg's
+    /// great circle, so that no step is longer than [`GeoPolyline::max_step`]. Legs
+    /// between opposite points go over the pole nearer their start.
+    pub fn path(&self) -> Vec<Vec3> {
+        let mut corners: Vec<Vec3> = Vec::new();
+        for &[lat, lon] in &self.points {
+            let p = geo_to_local(lat, lon, 1.0);
+            if corners
+                .last()
+                .is_none_or(|last| (*last - p).length() > SAME_POINT)
+            {
+                corners.push(p);
+            }
+        }
+        if self.closed
+            && corners.len() > 2
+            && (corners[0] - corners[corners.len() - 1]).length() > SAME_POINT
+        {
+            corners.push(corners[0]);
+        }
+        let max_step = self.max_step.max(0.01).to_radians();
+        let mut path = corners.first().copied().into_iter().collect::<Vec<_>>();
+        for leg in corners.windows(2) {
+            let (a, b) = (leg[0], leg[1]);
+            let angle = a.dot(b).clamp(-1.0, 1.0).acos();
+            let steps = (angle / max_step).ceil().max(1.0) as usize;
+            path.extend((1..=steps).map(|k| great_circle_point(a, b, k as f32 / steps as f32)));
+        }
+        path
+    }
+
+    /// The line as `[lat, lon]` in degrees for flat maps, such as plate carrée, in
+    /// pieces that each stay within -180° to 180° of longitude: [`GeoPolyline::path`]
+    /// split where it crosses the antimeridian, with both pieces ending at the latitude
+    /// it crosses at, and where it goes over a pole, one piece running up to the pole
+    /// along the meridian it arrived by and the next leaving along the other.
+    pub fn parts(&self) -> Vec<Vec<[f32; 2]>> {
+        let mut parts = Vec::new();
+        let mut part: Vec<[f32; 2]> = Vec::new();
+        let mut last: Option<Vec3> = None;
+        for p in self.path() {
+            let (lat, mut lon) = local_to_geo(p);
+            if let Some(q) = last {
+                // A pole takes the longitude of the way it is reached or, at the start,
+                // left.
+                if let [start] = &mut part[..] {
+                    if start[0].abs() > 90.0 - ON_LINE {
+                        start[1] = lon;
+                    }
+                }
+                let last_lon = part.last().map_or(lon, |last| last[1]);
+                if lat.abs() > 90.0 - ON_LINE {
+                    lon = last_lon;
+                } else if lon.abs() > 180.0 - ON_LINE {
+                    lon = 180_f32.copysign(last_lon);
+                }
+                let turn = (lon - last_lon + 180.0).rem_euclid(360.0) - 180.0;
+                if turn.abs() > 90.0 {
+                    let pole = 90_f32.copysign(lat);
+                    if part.last().is_none_or(|last| last[0] != pole) {
+                        part.push([pole, last_lon]);
+                    }
+                    parts.push(mem::take(&mut part));
+                    part.push([pole, lon]);
+                } else if (lon - last_lon).abs() > 180.0 {
+                    // The antimeridian is where the sphere's frame has y = 0 and x > 0.
+                    let t = q.y() / (q.y() - p.y());
+                    let (crossing, _) = local_to_geo(q.lerp(p, t));
+                    let side = 180_f32.copysign(last_lon);
+                    part.push([crossing, side]);
+                    parts.push(mem::take(&mut part));
+                    part.push([crossing, -side]);
+                }
+            }
+            part.push([lat, lon]);
+            last = Some(p);
+        }
+        parts.push(part);
+        parts.retain(|part| part.len() > 1);
+        parts
+    }
+
+    /// The line's ribbon on a body of `radius`, in the frame of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh), facing outwards. Its `u` runs from
+    /// 0 at the first point to 1 at the last.
+    pub fn mesh(&self, radius: f32) -> Result<Mesh, PolylineError> {
+        let path = self.path();
+        if path.len() < 2 {
+            return Err(PolylineError::TooShort(self.name.clone()));
+        }
+        Ok(ribbon_mesh(
+            &path,
+            radius * self.width,
+            radius * (1.0 + self.lift),
+        ))
+    }
+}
+
+/// On a [`GeoPolyline`] once [`GeoPolylinePlugin`] has drawn it on the body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPolylineDrawn {
+    pub body: Entity,
+}
+
+/// Draws each [`GeoPolyline`] as an unshaded ribbon, a child of its [`Body`] so it
+/// turns with it. A line of fewer than two distinct points is reported and left
+/// undrawn. Lines spawned before any body wait for one.
+pub struct GeoPolylinePlugin;
+
+impl Plugin for GeoPolylinePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(polyline_draw_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn polyline_draw_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    polylines: Query<Without<GeoPolylineDrawn, (Entity, &GeoPolyline, Option<&Parent>)>>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    for (entity, polyline, parent) in polylines.iter() {
+        let (body, adopt) = match body_for(parent, &bodies) {
+            Some(body) => body,
+            None => continue,
+        };
+        let radius = bodies.get(body).map_or(0.0, |(_, body, _)| body.radius);
+        commands.insert_one(entity, GeoPolylineDrawn { body });
+        if adopt {
+            commands.push_children(body, &[entity]);
+        }
+        let mesh = match polyline.mesh(radius) {
+            Ok(mesh) => mesh,
+            Err(e) => {
+                tracing::warn!("skipping polyline: {}", e);
+                continue;
+            }
+        };
+        let [r, g, b, a] = polyline.color;
+        commands.insert(
+            entity,
+            PbrComponents {
+                mesh: meshes.add(mesh),
+                material: materials.add(StandardMaterial {
+                    albedo: Color::rgba(r, g, b, a),
+                    shaded: false,
+                    ..Default::default()
+                }),
+                draw: Draw {
+                    is_transparent: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+    }
+}
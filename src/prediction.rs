@@ -0,0 +1,428 @@
+use std::collections::{HashSet, VecDeque};
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::bodies::{geo_to_local, Body};
+use crate::display::Poi;
+use crate::geo::{LatLon, Polar, EARTH_RADIUS_M};
+use crate::geo_marker::{body_for, GeoMarkerStyle};
+use crate::globe_link::GlobeLink;
+use crate::layers::Layer;
+use crate::layout::LayoutConfig;
+use crate::motion::PolarTween;
+use crate::polyline::GeoPolyline;
+use crate::simulation::predict;
+use crate::target::{GeoPoint, Target, Velocity};
+use crate::trails::DisplayHeading;
+
+const HISTORY_LEN: usize = 16;
+const GHOST_ALPHA: f32 = 0.5;
+const DOT_LENGTH: f32 = 3.0;
+const DOT_GAP: f32 = 4.0;
+
+/// Recent observed positions of a target, for estimating its motion when it does not
+/// report a [`Velocity`] itself.
+#[derive(Debug, Default, Clone)]
+pub struct TrackHistory {
+    samples: VecDeque<(f64, f32, f32)>,
+}
+
+impl TrackHistory {
+    /// Adds the target's position at `time` seconds, dropping the oldest sample once
+    /// the history is full.
+    pub fn record(&mut self, time: f64, target: &Target) {
+        if self.samples.len() == HISTORY_LEN {
+            self.samples.pop_front();
+        }
+        let at = Polar::new(target.azimuth, target.dist).to_cartesian();
+        self.samples.push_back((time, at.x(), at.y()));
+    }
+
+    /// When the latest sample was taken, in seconds since startup.
+    pub fn last_update(&self) -> Option<f64> {
+        self.samples.back().map(|s| s.0)
+    }
+
+    /// The recorded positions in true coordinates, oldest first.
+    pub fn positions(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.samples.iter().map(|&(_, x, y)| Vec2::new(x, y))
+    }
+
+    /// Least-squares straight-line fit through the recorded positions. `None` until
+    /// there are two samples at different times.
+    pub fn fitted_velocity(&self) -> Option<Velocity> {
+        let n = self.samples.len() as f64;
+        if n < 2.0 {
+            return None;
+        }
+        let mean_t = self.samples.iter().map(|s| s.0).sum::<f64>() / n;
+        let mean_x = self.samples.iter().map(|s| s.1 as f64).sum::<f64>() / n;
+        let mean_y = self.samples.iter().map(|s| s.2 as f64).sum::<f64>() / n;
+        let (mut var_t, mut cov_x, mut cov_y) = (0.0, 0.0, 0.0);
+        for &(t, x, y) in &self.samples {
+            let dt = t - mean_t;
+            var_t += dt * dt;
+            cov_x += dt * (x as f64 - mean_x);
+            cov_y += dt * (y as f64 - mean_y);
+        }
+        if var_t <= f64::EPSILON {
+            return None;
+        }
+        let (vx, vy) = ((cov_x / var_t) as f32, (cov_y / var_t) as f32);
+        Some(Velocity {
+            course: vy.atan2(vx).rem_euclid(PI * 2.0),
+            speed: vx.hypot(vy),
+        })
+    }
+}
+
+/// What [`PredictionPlugin`] and [`GlobePredictionPlugin`] draw of where moving targets
+/// are headed, by [`predict`]ing `horizon` seconds ahead. Zero turns them off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub horizon: f32,
+    /// A line from each target's true position along its course, as far as it gets in
+    /// `horizon`.
+    pub vectors: bool,
+    /// A hollow ghost marker at the predicted position.
+    pub ghosts: bool,
+}
+
+impl Default for Prediction {
+    fn default() -> Self {
+        Prediction {
+            horizon: 10.0,
+            vectors: false,
+            ghosts: true,
+        }
+    }
+}
+
+/// Marks the ghost marker and dotted connector drawn for the marker entity `of`.
+#[derive(Debug, Clone, Copy)]
+pub struct Ghost {
+    pub of: Entity,
+}
+
+/// Marks the prediction vector drawn for the marker entity `of`.
+#[derive(Debug, Clone, Copy)]
+pub struct PredictionVector {
+    pub of: Entity,
+}
+
+/// Draws what [`Prediction`] asks for of each moving target: a hollow ghost marker at
+/// its predicted position, joined to the marker by a dotted line, and a vector from its
+/// true position to where it will really be, placed like its trail and turned by the
+/// [`DisplayHeading`]. Motion comes from `Target::velocity`, or else from a fit over the
+/// marker's [`TrackHistory`].
+pub struct PredictionPlugin;
+
+impl Plugin for PredictionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Prediction>() {
+            app.init_resource::<Prediction>();
+        }
+        if !app.resources().contains::<DisplayHeading>() {
+            app.init_resource::<DisplayHeading>();
+        }
+        app.add_system(ghost_system.system());
+    }
+}
+
+#[derive(Default)]
+struct DrawnPredictions {
+    prediction: Option<Prediction>,
+    heading: Option<DisplayHeading>,
+    config: Option<LayoutConfig>,
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn ghost_system(
+    mut commands: Commands,
+    mut drawn: Local<DrawnPredictions>,
+    prediction: Res<Prediction>,
+    heading: Res<DisplayHeading>,
+    config: Res<LayoutConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    changed: Query<(Entity, Changed<Target>)>,
+    markers: Query<(
+        Entity,
+        &Target,
+        &Poi,
+        &PolarTween,
+        &Handle<ColorMaterial>,
+        Option<&TrackHistory>,
+    )>,
+    ghosts: Query<(Entity, &Ghost)>,
+    vectors: Query<(Entity, &PredictionVector)>,
+) {
+    let redraw_all = drawn.prediction != Some(*prediction)
+        || drawn.heading != Some(*heading)
+        || drawn.config != Some(*config);
+    drawn.prediction = Some(*prediction);
+    drawn.heading = Some(*heading);
+    drawn.config = Some(*config);
+    let dirty: HashSet<Entity> = if redraw_all {
+        markers.iter().map(|(entity, ..)| entity).collect()
+    } else {
+        changed
+            .iter()
+            .map(|(entity, _)| entity)
+            .chain(markers.removed::<Target>().iter().copied())
+            .collect()
+    };
+    if dirty.is_empty() {
+        return;
+    }
+    for (entity, ghost) in ghosts.iter() {
+        if dirty.contains(&ghost.of) {
+            commands.despawn(entity);
+        }
+    }
+    for (entity, vector) in vectors.iter() {
+        if dirty.contains(&vector.of) {
+            commands.despawn(entity);
+        }
+    }
+    if prediction.horizon <= 0.0 {
+        return;
+    }
+
+    for (entity, target, poi, tween, material, history) in markers.iter() {
+        if !dirty.contains(&entity) {
+            continue;
+        }
+        let velocity = match target
+            .velocity
+            .or_else(|| history.and_then(TrackHistory::fitted_velocity))
+        {
+            Some(velocity) if velocity.speed > 0.0 => velocity,
+            _ => continue,
+        };
+        let (azimuth, dist) = predict(target, velocity, prediction.horizon);
+        let origin = tween.origin;
+        let color = materials.get(material).map_or(Color::WHITE, |m| m.color);
+
+        if prediction.vectors {
+            // True positions are drawn where the rings put their distance, turned by the
+            // heading, as trails are.
+            let to_display = |p: Polar| {
+                let radius = config.ring_position(p.dist) * config.ring_spacing;
+                let azimuth = p.azimuth - heading.0;
+                origin + Vec2::new(radius * azimuth.cos(), radius * azimuth.sin())
+            };
+            let from = to_display(Polar::new(target.azimuth, target.dist));
+            let to = to_display(Polar::new(azimuth, dist));
+            let mut builder = PathBuilder::new();
+            builder.move_to(point(from.x(), from.y()));
+            builder.line_to(point(to.x(), to.y()));
+            let vector = builder.build().stroke(
+                materials.add(color.into()),
+                &mut meshes,
+                Vec3::zero(),
+                &StrokeOptions::default(),
+            );
+            commands
+                .spawn(vector)
+                .with(PredictionVector { of: entity })
+                .with(Layer::Overlays);
+        }
+
+        if !prediction.ghosts {
+            continue;
+        }
+        // The layout moves targets onto rings, so keep the marker's ring radius and
+        // offset it by the predicted change in range.
+        let radius = ((poi.center - origin).length() + dist - target.dist).max(0.0);
+        let ghost_center = origin + Vec2::new(radius * azimuth.cos(), radius * azimuth.sin());
+
+        let mut color = color;
+        color.set_a(color.a() * GHOST_ALPHA);
+        let ghost_material = materials.add(color.into());
+        let width = poi.half_width * 2.0;
+        let marker = primitive(
+            ghost_material.clone(),
+            &mut meshes,
+            ShapeType::Rectangle {
+                width,
+                height: width,
+            },
+            TessellationMode::Stroke(&StrokeOptions::default()),
+            (ghost_center - Vec2::new(poi.half_width, poi.half_width)).extend(0.0),
+        );
+        let connector = dotted_line(poi.center, ghost_center).stroke(
+            ghost_material,
+            &mut meshes,
+            Vec3::new(0.0, 0.0, 0.0),
+            &StrokeOptions::default(),
+        );
+        commands
+            .spawn(marker)
+            .with(Ghost { of: entity })
+            .with(Layer::Overlays)
+            .spawn(connector)
+            .with(Ghost { of: entity })
+            .with(Layer::Overlays);
+    }
+}
+
+/// Marks the ghost and vector [`GlobePredictionPlugin`] draws for the target with this id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobePrediction(pub i32);
+
+/// Draws what [`Prediction`] asks for on the globe, for each moving target with a
+/// [`GeoPoint`]: the vector as a [`GeoPolyline`] from its pin to where its course and
+/// speed take it as seen from [`GlobeLink::observer`], and the ghost as a faint pin
+/// there, both children of the body nearest the world origin. Add with
+/// [`GlobeLinkPlugin`](crate::globe_link::GlobeLinkPlugin), and
+/// [`GeoPolylinePlugin`](crate::polyline::GeoPolylinePlugin) to draw the vectors.
+pub struct GlobePredictionPlugin;
+
+impl Plugin for GlobePredictionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Prediction>() {
+            app.init_resource::<Prediction>();
+        }
+        if !app.resources().contains::<GlobeLink>() {
+            app.init_resource::<GlobeLink>();
+        }
+        if !app.resources().contains::<GeoMarkerStyle>() {
+            app.init_resource::<GeoMarkerStyle>();
+        }
+        app.add_system(globe_prediction_system.system());
+    }
+}
+
+#[derive(Default)]
+struct DrawnGlobePredictions {
+    settings: Option<(Prediction, GlobeLink, GeoMarkerStyle)>,
+    ghost_mesh: Option<Handle<Mesh>>,
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn globe_prediction_system(
+    mut commands: Commands,
+    mut drawn: Local<DrawnGlobePredictions>,
+    prediction: Res<Prediction>,
+    link: Res<GlobeLink>,
+    style: Res<GeoMarkerStyle>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    changed: Query<With<GeoPoint, Changed<Target>>>,
+    targets: Query<(&Target, &GeoPoint)>,
+    parts: Query<(Entity, &GlobePrediction)>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    let settings = Some((*prediction, *link, *style));
+    let redraw_all = drawn.settings != settings;
+    if redraw_all {
+        drawn.settings = settings;
+        drawn.ghost_mesh = None;
+    }
+    let mut dirty = changed
+        .iter()
+        .map(|target| target.id)
+        .collect::<HashSet<_>>();
+    for (entity, part) in parts.iter() {
+        let gone = targets.iter().all(|(target, _)| target.id != part.0);
+        if redraw_all || gone || dirty.contains(&part.0) {
+            commands.despawn_recursive(entity);
+        }
+    }
+    if redraw_all {
+        dirty = targets.iter().map(|(target, _)| target.id).collect();
+    }
+    if dirty.is_empty() || prediction.horizon <= 0.0 {
+        return;
+    }
+    let (body, radius) = match body_for(None, &bodies).and_then(|(body, _)| bodies.get(body).ok()) {
+        Some((body, info, _)) => (body, info.radius),
+        None => return,
+    };
+
+    for (target, geo) in targets.iter() {
+        if !dirty.contains(&target.id) {
+            continue;
+        }
+        let velocity = match target.velocity {
+            Some(velocity) if velocity.speed > 0.0 => velocity,
+            _ => continue,
+        };
+        let (azimuth, dist) = predict(target, velocity, prediction.horizon);
+        let from = LatLon::new(geo.lat, geo.lon);
+        let to = link.place(Polar::new(azimuth, dist));
+        if prediction.vectors && from.angle_to(to) > f32::EPSILON {
+            let (r, g, b, a) = (
+                style.color.r(),
+                style.color.g(),
+                style.color.b(),
+                style.color.a(),
+            );
+            commands.spawn((
+                GeoPolyline {
+                    color: [r, g, b, a],
+                    ..GeoPolyline::new(vec![[from.lat, from.lon], [to.lat, to.lon]])
+                },
+                GlobePrediction(target.id),
+            ));
+        }
+        if prediction.ghosts {
+            let mesh = drawn
+                .ghost_mesh
+                .get_or_insert_with(|| {
+                    meshes.add(Mesh::from(shape::Icosphere {
+                        radius: style.size,
+                        subdivisions: 2,
+                    }))
+                })
+                .clone();
+            let mut color = style.color;
+            color.set_a(color.a() * GHOST_ALPHA);
+            let height = geo.alt.max(0.0) / EARTH_RADIUS_M * radius;
+            commands
+                .spawn(PbrComponents {
+                    mesh,
+                    material: materials.add(StandardMaterial {
+                        albedo: color,
+                        shaded: false,
+                        ..Default::default()
+                    }),
+                    transform: Transform::from_translation(geo_to_local(
+                        to.lat,
+                        to.lon,
+                        radius + height,
+                    )),
+                    draw: Draw {
+                        is_transparent: true,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(GlobePrediction(target.id));
+            // The polyline plugin adopts vectors itself, but ghosts need a parent.
+            if let Some(ghost) = commands.current_entity() {
+                commands.push_children(body, &[ghost]);
+            }
+        }
+    }
+}
+
+fn dotted_line(from: Vec2, to: Vec2) -> Path {
+    let mut builder = PathBuilder::new();
+    let length = (to - from).length();
+    if length > 0.0 {
+        let dir = (to - from) / length;
+        let mut at = 0.0;
+        while at < length {
+            let start = from + dir * at;
+            let end = from + dir * (at + DOT_LENGTH).min(length);
+            builder.move_to(point(start.x(), start.y()));
+            builder.line_to(point(end.x(), end.y()));
+            at += DOT_LENGTH + DOT_GAP;
+        }
+    }
+    builder.build()
+}
@@ -0,0 +1,104 @@
+//! The plugins, core types, events and extension traits, for `use bevy_debris::prelude::*`.
+
+pub use crate::actions::{Action, ActionMap, ActionState, ActionsPlugin, Binding};
+pub use crate::aging::{AgingPlugin, TargetExpired};
+pub use crate::alerts::{Alert, AlertRule, AlertsPlugin};
+pub use crate::animated_texture::{AnimatedTexture, AnimatedTexturePlugin, FrameSource};
+pub use crate::animation::AnimationTimePlugin;
+pub use crate::asset_check::{AssetCheckPlugin, AssetError};
+pub use crate::atmosphere::{Atmosphere, AtmospherePlugin};
+pub use crate::autolabel::DesignatorPlugin;
+pub use crate::billboard::{Billboard, BillboardPlugin, BillboardText};
+pub use crate::bodies::{Bodies, BodiesPlugin, BodyConfig};
+pub use crate::camera::{
+    AutoSpin, CameraCommands, CameraControlPlugin, CameraState, GamepadBindings, GlobeZoom,
+    OrbitBindings, OrbitCamera, OrbitCameraPlugin, OrbitControls, OrbitMode,
+};
+pub use crate::capture::CapturePlugin;
+pub use crate::clipboard::ClipboardPlugin;
+pub use crate::cluster::SectorClusterPlugin;
+pub use crate::config::{ConfigPlugin, DebrisConfig};
+pub use crate::constant_size::ConstantSizePlugin;
+pub use crate::coords::CoordsPlugin;
+pub use crate::culling::{Culling, CullingPlugin};
+pub use crate::curved_labels::CurvedLabelsPlugin;
+pub use crate::day_night::{DayNightMaterial, DayNightPlugin, SunClock};
+pub use crate::debug_overlay::DebugOverlayPlugin;
+pub use crate::demo::DemoPlugin;
+pub use crate::designation::DesignationPlugin;
+pub use crate::display::{LabelContent, LayoutTuningPlugin, PoiRingPlugin, RadarDisplay};
+pub use crate::easing::{Easing, EasingConfig};
+pub use crate::edit::{EditMode, EditPlugin};
+pub use crate::emphasis::EmphasisPlugin;
+pub use crate::events::{DisplayEvent, DisplayEventsPlugin};
+pub use crate::feed::{FeedSource, TargetFeedPlugin};
+pub use crate::flat_map::{FlatMap, FlatMapPlugin};
+pub use crate::frame::FramePlugin;
+pub use crate::geo::{LatLon, Polar, Projection};
+pub use crate::geo_marker::{GeoMarker, GeoMarkerPlugin};
+pub use crate::geojson::{GeoJsonLayer, GeoJsonOverlay, GeoJsonPlugin};
+pub use crate::gesture::{Gesture, GesturePlugin};
+pub use crate::globe_link::{FollowSelection, GlobeLink, GlobeLinkPlugin};
+pub use crate::globe_pick::{GlobeClicked, GlobePickPlugin};
+pub use crate::globe_render::GlobeRenderPlugin;
+pub use crate::graticule::{Graticule, GraticulePlugin};
+pub use crate::heatmap::{Heatmap, HeatmapPlugin};
+pub use crate::impostor::ImpostorPlugin;
+pub use crate::instancing::{InstanceColor, InstancedMarkerPlugin};
+#[cfg(feature = "ktx2")]
+pub use crate::ktx2::Ktx2Plugin;
+pub use crate::label_fit::TextMeasure;
+pub use crate::label_zoom::LabelZoomPlugin;
+pub use crate::layers::LayersPlugin;
+pub use crate::layout::{
+    LayoutConfig, LayoutStrategy, Placement, PlacementMode, RingLayout, SlotChange,
+};
+pub use crate::leaks::LeakCheckPlugin;
+pub use crate::legend::LegendPlugin;
+pub use crate::lighting::LightingPlugin;
+pub use crate::links::{TargetLinked, TargetLinksPlugin, TargetUnlinked};
+pub use crate::lod::RingLodPlugin;
+pub use crate::lod_sphere::{LodSphere, LodSpherePlugin};
+pub use crate::measure::MeasurePlugin;
+pub use crate::metrics::{MetricsPlugin, PerfCounters};
+pub use crate::minimap::{Minimap, MinimapPlugin};
+pub use crate::motion::MotionPlugin;
+pub use crate::multi_select::{BulkAction, MultiSelectPlugin, SelectionSet, TargetOverrides};
+pub use crate::normal_map::{NormalMapPlugin, NormalMappedMaterial};
+pub use crate::notes::NotesPlugin;
+pub use crate::occlusion::OcclusionPlugin;
+pub use crate::palette::{Categorical, ColorBy, ColorRamp, Ramp, TargetAttribute};
+pub use crate::persist::PersistPlugin;
+pub use crate::polyline::{GeoPolyline, GeoPolylinePlugin};
+pub use crate::prediction::{GlobePredictionPlugin, Prediction, PredictionPlugin};
+pub use crate::probe::DataProbePlugin;
+pub use crate::range_rings::RangeRingsPlugin;
+pub use crate::replay::{FeedRecorderPlugin, FeedReplayPlugin};
+pub use crate::ring3d::ElevationRingPlugin;
+pub use crate::route::{GeoRoute, GeoRoutePlugin};
+pub use crate::scene::ScenePlugin;
+pub use crate::selection::{SelectTarget, Selected, SelectionPlugin, TargetSelected};
+pub use crate::simulation::{Maneuver, Simulation, SimulationPlugin};
+pub use crate::sky::{Sky, SkyPlugin, Starfield};
+pub use crate::smoothing::{Smoother, SmoothingFilter};
+pub use crate::spatial::{PolarPoint, SpatialIndex, SpatialIndexPlugin};
+pub use crate::split_view::{SplitViewPlugin, ViewRect};
+pub use crate::style::{CategoryStyle, LinePattern, LineStyle, MarkerShape, StyleRegistry};
+pub use crate::svg::SvgExportPlugin;
+pub use crate::sweep::SweepPlugin;
+pub use crate::target::{GeoPoint, Target, Velocity};
+pub use crate::target_list::TargetListPlugin;
+pub use crate::terrain::{Heightmap, Relief};
+pub use crate::theme::Theme;
+pub use crate::tiles::{GlobeTiles, GlobeTilesPlugin, TileSource};
+pub use crate::timeline::{Timeline, TimelinePlugin};
+pub use crate::tooltip::TooltipPlugin;
+pub use crate::trails::TrailsPlugin;
+pub use crate::tuning::TuningPlugin;
+pub use crate::undo::{EditCommand, UndoPlugin, UndoStack};
+pub use crate::updates::{TargetAdded, TargetChanged, TargetRemoved, TargetUpdatesPlugin};
+pub use crate::viewport::ViewportPlugin;
+#[cfg(feature = "web")]
+pub use crate::web::WebPlugin;
+pub use crate::window_fit::{WindowFit, WindowFitPlugin};
+pub use crate::zones::{AlertEvent, AlertZone, AlertZones, AlertZonesPlugin, ZoneShape};
@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+use bevy::render::texture::TextureFormat;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::bodies::local_to_geo;
+use crate::coords::CoordFormat;
+use crate::geo::LatLon;
+use crate::occlusion::Occluder;
+use crate::pointer::CursorPosition;
+
+/// Size of the probe readout text, in pixels.
+const READOUT_FONT_SIZE: f32 = 16.0;
+/// How far the readout sits up and to the right of the cursor, in pixels.
+const READOUT_OFFSET: f32 = 14.0;
+
+/// An equirectangular data raster, such as a temperature map, read out under the
+/// cursor by [`DataProbePlugin`]. A channel value `v` is shown as
+/// `v * scale + offset` in `unit`.
+#[derive(Debug, Clone)]
+pub struct DataProbe {
+    pub raster: Handle<Texture>,
+    pub scale: f32,
+    pub offset: f32,
+    pub unit: String,
+    /// Whether probing is on; P toggles it.
+    pub enabled: bool,
+}
+
+impl Default for DataProbe {
+    fn default() -> Self {
+        DataProbe {
+            raster: Handle::default(),
+            scale: 1.0,
+            offset: 0.0,
+            unit: String::new(),
+            enabled: false,
+        }
+    }
+}
+
+impl DataProbe {
+    /// The channel values of `pixel` in physical units.
+    pub fn values(&self, pixel: &[f32]) -> Vec<f32> {
+        pixel.iter().map(|v| v * self.scale + self.offset).collect()
+    }
+}
+
+/// On the sphere [`DataProbePlugin`] probes, whose [`Occluder`] gives its radius. Its
+/// mesh is in the frame of [`sphere_mesh`](crate::mesh::sphere_mesh).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProbeSurface;
+
+/// What the probe found under the cursor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reading {
+    pub lat: f32,
+    pub lon: f32,
+    /// The pixel's channel values through [`DataProbe::values`]; `None` while the raster
+    /// is loading or in a format [`sample`] cannot read.
+    pub values: Option<Vec<f32>>,
+}
+
+/// The latest [`Reading`], `None` while probing is off or the cursor is off the
+/// surface.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProbeReading(pub Option<Reading>);
+
+/// The raw channel values of the pixel of the equirectangular `raster` under
+/// `(lat, lon)` in degrees, red first: 0–255 for 8-bit formats, 0–65535 for 16-bit
+/// ones and as stored for float ones. `None` for other formats.
+pub fn sample(raster: &Texture, lat: f32, lon: f32) -> Option<Vec<f32>> {
+    let (width, height) = (raster.size.x() as usize, raster.size.y() as usize);
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let (channels, bytes) = match raster.format {
+        TextureFormat::R8Unorm => (1, 1),
+        TextureFormat::Rg8Unorm => (2, 1),
+        TextureFormat::Rgba8Unorm
+        | TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Bgra8Unorm
+        | TextureFormat::Bgra8UnormSrgb => (4, 1),
+        TextureFormat::R16Uint => (1, 2),
+        TextureFormat::Rg16Uint => (2, 2),
+        TextureFormat::Rgba16Uint => (4, 2),
+        TextureFormat::R32Float => (1, 4),
+        TextureFormat::Rg32Float => (2, 4),
+        TextureFormat::Rgba32Float => (4, 4),
+        _ => return None,
+    };
+    let [u, v] = LatLon::new(lat, lon).uv();
+    let x = ((u * width as f32) as usize).min(width - 1);
+    let y = ((v * height as f32) as usize).min(height - 1);
+    let start = (y * width + x) * channels * bytes;
+    let pixel = raster.data.get(start..start + channels * bytes)?;
+    let mut values = pixel
+        .chunks_exact(bytes)
+        .map(|c| match *c {
+            [a] => a as f32,
+            [a, b] => u16::from_ne_bytes([a, b]) as f32,
+            [a, b, c, d] => f32::from_ne_bytes([a, b, c, d]),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    if matches!(
+        raster.format,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+    ) {
+        values.swap(0, 2);
+    }
+    Some(values)
+}
+
+/// The ray from the eye through window position `screen` (origin bottom-left) of a
+/// window `size` pixels large, as origin and unit direction.
+pub fn screen_ray(
+    camera: &Camera,
+    eye: &GlobalTransform,
+    screen: Vec2,
+    size: Vec2,
+) -> (Vec3, Vec3) {
+    let ndc = screen / size * 2.0 - Vec2::one();
+    let inverse = (camera.projection_matrix * eye.compute_matrix().inverse()).inverse();
+    let unproject = |depth: f32| {
+        let world = inverse * ndc.extend(depth).extend(1.0);
+        Vec3::from(world.truncate()) / world.w()
+    };
+    let (near, far) = (unproject(0.0), unproject(1.0));
+    (near, (far - near).normalize())
+}
+
+/// Where the ray from `origin` along the unit vector `dir` first meets the sphere at
+/// `center`, if it does.
+pub fn ray_sphere(origin: Vec3, dir: Vec3, center: Vec3, radius: f32) -> Option<Vec3> {
+    let to_center = center - origin;
+    let along = to_center.dot(dir);
+    let miss = to_center.length_squared() - along * along;
+    if miss > radius * radius {
+        return None;
+    }
+    let half = (radius * radius - miss).sqrt();
+    // From inside the sphere the way out is the only hit.
+    let t = if along >= half {
+        along - half
+    } else {
+        along + half
+    };
+    (t >= 0.0).then(|| origin + dir * t)
+}
+
+/// Font asset path of the probe readout, set by [`DataProbePlugin`].
+#[derive(Debug, Clone, Copy)]
+pub struct ProbeFont(pub &'static str);
+
+/// Probes the [`ProbeSurface`] under the cursor as seen from the 3D camera while
+/// [`DataProbe::enabled`], [`Action::ToggleProbe`] (P) toggling it: the latitude and longitude there and the
+/// [`DataProbe`] raster's pixel are kept in [`ProbeReading`] and shown next to the
+/// cursor. The raster is sampled on the CPU from its `Assets<Texture>` data. Needs
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin) for the cursor.
+pub struct DataProbePlugin {
+    /// Asset path of the readout font.
+    pub font: &'static str,
+}
+
+impl Default for DataProbePlugin {
+    fn default() -> Self {
+        DataProbePlugin { font: "arial.ttf" }
+    }
+}
+
+impl Plugin for DataProbePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<DataProbe>() {
+            app.init_resource::<DataProbe>();
+        }
+        if !app.resources().contains::<CoordFormat>() {
+            app.init_resource::<CoordFormat>();
+        }
+        app.init_resource::<ProbeReading>()
+            .add_resource(ProbeFont(self.font))
+            .add_system(probe_system.system())
+            .add_system(probe_readout_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn probe_system(
+    actions: Res<ActionState>,
+    windows: Res<Windows>,
+    cursor: Res<CursorPosition>,
+    textures: Res<Assets<Texture>>,
+    mut probe: ResMut<DataProbe>,
+    mut reading: ResMut<ProbeReading>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    surfaces: Query<With<ProbeSurface, (&GlobalTransform, &Occluder)>>,
+) {
+    if actions.just_pressed(Action::ToggleProbe) {
+        probe.enabled = !probe.enabled;
+    }
+    let found = match (probe.enabled, cursor.screen, windows.get_primary()) {
+        (true, Some(screen), Some(window)) => {
+            let size = Vec2::new(window.width() as f32, window.height() as f32);
+            cameras
+                .iter()
+                .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
+                .and_then(|(camera, eye)| {
+                    let (origin, dir) = screen_ray(camera, eye, screen, size);
+                    surfaces
+                        .iter()
+                        .filter_map(|(transform, occluder)| {
+                            let radius = occluder.radius * transform.scale.x();
+                            ray_sphere(origin, dir, transform.translation, radius)
+                                .map(|hit| (transform, hit))
+                        })
+                        .min_by(|a, b| {
+                            let (a, b) = ((a.1 - origin).length(), (b.1 - origin).length());
+                            a.partial_cmp(&b).unwrap()
+                        })
+                })
+        }
+        _ => None,
+    };
+    let next = found.map(|(transform, hit)| {
+        let local = transform.compute_matrix().inverse().transform_point3(hit);
+        let (lat, lon) = local_to_geo(local);
+        let values = textures
+            .get(&probe.raster)
+            .and_then(|raster| sample(raster, lat, lon))
+            .map(|pixel| probe.values(&pixel));
+        Reading { lat, lon, values }
+    });
+    if reading.0 != next {
+        reading.0 = next;
+    }
+}
+
+#[derive(Default)]
+struct ReadoutState {
+    readout: Option<Entity>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn probe_readout_system(
+    mut commands: Commands,
+    mut state: Local<ReadoutState>,
+    asset_server: Res<AssetServer>,
+    font: Res<ProbeFont>,
+    format: Res<CoordFormat>,
+    probe: Res<DataProbe>,
+    reading: Res<ProbeReading>,
+    cursor: Res<CursorPosition>,
+    mut readouts: Query<(Mut<Text>, Mut<Style>, Mut<Draw>)>,
+) {
+    let readout = match (&reading.0, state.readout) {
+        (None, None) => return,
+        (_, Some(readout)) => readout,
+        // Filled in from the next frame, once the entity exists.
+        (Some(_), None) => {
+            let text = Text {
+                font: asset_server.load(font.0),
+                style: TextStyle {
+                    font_size: READOUT_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+                ..Default::default()
+            };
+            commands.spawn(TextComponents {
+                text,
+                ..Default::default()
+            });
+            state.readout = commands.current_entity();
+            return;
+        }
+    };
+    let (mut text, mut style, mut draw) = match readouts.get_mut(readout) {
+        Ok(readout) => readout,
+        Err(_) => return,
+    };
+    let (reading, at) = match (&reading.0, cursor.screen) {
+        (Some(reading), Some(at)) => (reading, at),
+        _ => {
+            if draw.is_visible {
+                draw.is_visible = false;
+            }
+            return;
+        }
+    };
+    draw.is_visible = true;
+    let values = match &reading.values {
+        Some(values) => values
+            .iter()
+            .map(|v| format!("{:.2}{}", v, probe.unit))
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => "no data".to_string(),
+    };
+    let value = format!(
+        "{}  {}",
+        format.format_geo(reading.lat, reading.lon),
+        values
+    );
+    // Changing the text lays its glyphs out again, so only change it when it differs.
+    if text.value != value {
+        text.value = value;
+    }
+    let (left, bottom) = (
+        Val::Px(at.x() + READOUT_OFFSET),
+        Val::Px(at.y() + READOUT_OFFSET),
+    );
+    if style.position.left != left || style.position.bottom != bottom {
+        style.position_type = PositionType::Absolute;
+        style.position = Rect {
+            left,
+            bottom,
+            ..Default::default()
+        };
+    }
+}
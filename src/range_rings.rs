@@ -0,0 +1,186 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::display::{LabelFont, RefRing};
+use crate::layers::{Collapsed, Layer};
+use crate::layout::LayoutConfig;
+use crate::theme::Theme;
+
+const TICK_Z: f32 = 2.0;
+
+/// The bearing/range readout [`RangeRingsPlugin`] draws over the reference rings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeRings {
+    /// Labels each ring with the distance it stands for under
+    /// [`LayoutConfig::scale`](crate::layout::LayoutConfig::scale).
+    pub distance_labels: bool,
+    /// Bearing the distance labels sit at, in degrees.
+    pub label_bearing: f32,
+    /// Degrees between ticks around the outermost ring; `0` for none.
+    pub minor_ticks: f32,
+    /// Degrees between the longer ticks, a multiple of `minor_ticks`.
+    pub major_ticks: f32,
+    /// Length of a minor tick; major ticks are twice as long.
+    pub tick_length: f32,
+    /// Labels bearings 0°, 90°, 180° and 270° N, E, S and W.
+    pub compass: bool,
+}
+
+impl Default for RangeRings {
+    fn default() -> Self {
+        RangeRings {
+            distance_labels: true,
+            label_bearing: 45.0,
+            minor_ticks: 10.0,
+            major_ticks: 30.0,
+            tick_length: 6.0,
+            compass: true,
+        }
+    }
+}
+
+/// Marks the entities drawn by [`RangeRingsPlugin`].
+#[derive(Debug, Clone, Copy)]
+pub struct RangeRingPart;
+
+/// Turns the reference rings into a bearing/range readout as configured by the
+/// [`RangeRings`] resource, on [`Layer::Grid`]. Rings folded away by
+/// [`RingLodPlugin`](crate::lod::RingLodPlugin) get no label. Needs
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin) for the rings and label font.
+pub struct RangeRingsPlugin;
+
+impl Plugin for RangeRingsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<RangeRings>() {
+            app.init_resource::<RangeRings>();
+        }
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        app.add_system(range_rings_system.system());
+    }
+}
+
+/// What the overlay was last drawn for.
+type Drawn = (Vec<(usize, f32, bool)>, RangeRings, Theme, LayoutConfig);
+
+#[allow(clippy::too_many_arguments)]
+fn range_rings_system(
+    mut commands: Commands,
+    mut drawn: Local<Option<Drawn>>,
+    overlay: Res<RangeRings>,
+    theme: Res<Theme>,
+    config: Res<LayoutConfig>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    rings: Query<(&RefRing, &Collapsed)>,
+    parts: Query<With<RangeRingPart, Entity>>,
+) {
+    // Drawn around the world origin, for the rings of the first origin.
+    let mut shown = rings
+        .iter()
+        .filter(|(ring, _)| ring.origin == 0)
+        .map(|(ring, collapsed)| (ring.ring, ring.radius, collapsed.0))
+        .collect::<Vec<_>>();
+    shown.sort_by_key(|(ring, ..)| *ring);
+    let state = (shown, *overlay, *theme, *config);
+    if drawn.as_ref() == Some(&state) {
+        return;
+    }
+    for entity in parts.iter() {
+        commands.despawn(entity);
+    }
+    let (shown, ..) = drawn.get_or_insert(state);
+    let outer = shown
+        .iter()
+        .map(|(_, radius, _)| *radius)
+        .fold(0.0, f32::max);
+    if outer <= 0.0 {
+        return;
+    }
+
+    if overlay.minor_ticks > 0.0 {
+        let material = materials.add(theme.stroke().into());
+        let count = (360.0 / overlay.minor_ticks).round() as usize;
+        let mut builder = PathBuilder::new();
+        for i in 0..count {
+            let bearing = i as f32 * overlay.minor_ticks;
+            let major = overlay.major_ticks > 0.0 && {
+                let steps = bearing / overlay.major_ticks;
+                (steps - steps.round()).abs() < 1e-3
+            };
+            let length = if major {
+                overlay.tick_length * 2.0
+            } else {
+                overlay.tick_length
+            };
+            let dir = direction(bearing);
+            let (from, to) = (dir * outer, dir * (outer + length));
+            builder.move_to(point(from.x(), from.y()));
+            builder.line_to(point(to.x(), to.y()));
+        }
+        let ticks = builder.build().stroke(
+            material,
+            &mut meshes,
+            Vec3::new(0.0, 0.0, TICK_Z),
+            &StrokeOptions::default(),
+        );
+        commands.spawn(ticks).with(RangeRingPart).with(Layer::Grid);
+    }
+
+    let font = asset_server.load(label_font.0);
+    let mut label = |text: String, at: Vec2, font_size: f32| {
+        commands
+            .spawn(TextComponents {
+                text: Text {
+                    value: text,
+                    font: font.clone(),
+                    style: TextStyle {
+                        font_size,
+                        color: theme.text(),
+                    },
+                },
+                transform: Transform::from_translation(at.extend(0.0)),
+                ..Default::default()
+            })
+            .with(RangeRingPart)
+            .with(Layer::Grid);
+    };
+    if overlay.distance_labels {
+        let dir = direction(overlay.label_bearing);
+        for (ring, radius, collapsed) in shown.iter() {
+            if !collapsed {
+                label(
+                    format_distance(config.ring_distance(*ring)),
+                    dir * *radius,
+                    14.0,
+                );
+            }
+        }
+    }
+    if overlay.compass {
+        let radius = outer + overlay.tick_length * 4.0;
+        for (i, name) in ["N", "E", "S", "W"].iter().enumerate() {
+            label(name.to_string(), direction(i as f32 * 90.0) * radius, 18.0);
+        }
+    }
+}
+
+/// Unit vector at `bearing` degrees, measured like target azimuths.
+fn direction(bearing: f32) -> Vec2 {
+    let angle = bearing * PI / 180.0;
+    Vec2::new(angle.cos(), angle.sin())
+}
+
+/// Whole units from 10 up, one decimal below.
+fn format_distance(distance: f32) -> String {
+    if distance >= 10.0 {
+        format!("{:.0}", distance)
+    } else {
+        format!("{:.1}", distance)
+    }
+}
@@ -0,0 +1,427 @@
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use bevy::render::texture::TextureFormat;
+use thiserror::Error;
+
+use crate::bodies::geo_to_local;
+
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_PHOTOMETRIC: u16 = 262;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_STRIP_BYTE_COUNTS: u16 = 279;
+const TAG_PLANAR_CONFIGURATION: u16 = 284;
+const TAG_MODEL_PIXEL_SCALE: u16 = 33550;
+const TAG_MODEL_TIEPOINT: u16 = 33922;
+/// Extensions of the world files looked for next to an image, in order.
+const WORLD_FILE_EXTENSIONS: [&str; 3] = ["pgw", "pngw", "wld"];
+
+#[derive(Debug, Error)]
+pub enum RasterError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("failed to decode {0}: {1}")]
+    Image(String, #[source] image::ImageError),
+    #[error("no world file (.pgw, .pngw or .wld) next to {0}")]
+    NoWorldFile(String),
+    #[error("bad world file for {0}: {1}")]
+    WorldFile(String, String),
+    #[error("unsupported TIFF {0}: {1}")]
+    Tiff(String, &'static str),
+    #[error("{0} is rotated or sheared, only north-up rasters are supported")]
+    Rotated(String),
+}
+
+/// The latitude and longitude rectangle a raster covers, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoExtent {
+    pub west: f32,
+    pub south: f32,
+    pub east: f32,
+    pub north: f32,
+}
+
+/// The six lines of an ESRI world file: the affine transform from pixel column and row
+/// to map coordinates, here longitude and latitude in degrees, with `(c, f)` the
+/// center of the top-left pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldFile {
+    pub a: f64,
+    pub d: f64,
+    pub b: f64,
+    pub e: f64,
+    pub c: f64,
+    pub f: f64,
+}
+
+impl WorldFile {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let values = text
+            .split_whitespace()
+            .map(|word| {
+                word.parse::<f64>()
+                    .map_err(|e| format!("{:?} is not a number: {}", word, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match values[..] {
+            [a, d, b, e, c, f] => Ok(WorldFile { a, d, b, e, c, f }),
+            _ => Err(format!("expected 6 numbers, found {}", values.len())),
+        }
+    }
+
+    /// The extent of a `width` by `height` pixel raster, or `None` if the transform
+    /// rotates or shears it.
+    pub fn extent(&self, width: u32, height: u32) -> Option<GeoExtent> {
+        if self.b != 0.0 || self.d != 0.0 {
+            return None;
+        }
+        let west = self.c - self.a / 2.0;
+        let north = self.f - self.e / 2.0;
+        let east = west + self.a * width as f64;
+        let south = north + self.e * height as f64;
+        Some(GeoExtent {
+            west: west.min(east) as f32,
+            south: south.min(north) as f32,
+            east: west.max(east) as f32,
+            north: south.max(north) as f32,
+        })
+    }
+}
+
+/// A raster with a known place on the globe: RGBA pixels row by row from the top,
+/// spanning `extent` in plate carrée.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoRaster {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+    pub extent: GeoExtent,
+}
+
+impl GeoRaster {
+    /// Loads a GeoTIFF (`.tif` or `.tiff`), or a PNG placed by the world file next to
+    /// it. Coordinates are taken as longitude and latitude; other projections are not
+    /// converted.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RasterError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let bytes = fs::read(path).map_err(|e| RasterError::Io(name.clone(), e))?;
+        let tiff = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("tif") || ext.eq_ignore_ascii_case("tiff"));
+        if tiff {
+            return Self::from_geotiff(&name, &bytes);
+        }
+        let image = image::load_from_memory(&bytes)
+            .map_err(|e| RasterError::Image(name.clone(), e))?
+            .to_rgba();
+        let (width, height) = image.dimensions();
+        let world = WORLD_FILE_EXTENSIONS
+            .iter()
+            .map(|ext| path.with_extension(ext))
+            .find(|world| world.exists())
+            .ok_or_else(|| RasterError::NoWorldFile(name.clone()))?;
+        let text = fs::read_to_string(&world)
+            .map_err(|e| RasterError::Io(world.display().to_string(), e))?;
+        let extent = WorldFile::parse(&text)
+            .map_err(|e| RasterError::WorldFile(name.clone(), e))?
+            .extent(width, height)
+            .ok_or(RasterError::Rotated(name))?;
+        Ok(GeoRaster {
+            width,
+            height,
+            pixels: image.into_raw(),
+            extent,
+        })
+    }
+
+    /// Reads a GeoTIFF: uncompressed, chunky, 8 bits per sample gray, gray and alpha,
+    /// RGB or RGBA in strips, placed by its model tie point and pixel scale.
+    pub fn from_geotiff(name: &str, bytes: &[u8]) -> Result<Self, RasterError> {
+        let fail = |reason| RasterError::Tiff(name.to_string(), reason);
+        let tiff = Tiff::parse(bytes).ok_or_else(|| fail("not a TIFF file"))?;
+        let one = |tag| tiff.values(tag).and_then(|v| v.first().copied());
+        let width = one(TAG_IMAGE_WIDTH).ok_or_else(|| fail("no width"))? as u32;
+        let height = one(TAG_IMAGE_LENGTH).ok_or_else(|| fail("no height"))? as u32;
+        let samples = one(TAG_SAMPLES_PER_PIXEL).unwrap_or(1.0) as usize;
+        if one(TAG_COMPRESSION).unwrap_or(1.0) != 1.0 {
+            return Err(fail("compressed"));
+        }
+        if one(TAG_PLANAR_CONFIGURATION).unwrap_or(1.0) != 1.0 {
+            return Err(fail("planar"));
+        }
+        let bits = tiff
+            .values(TAG_BITS_PER_SAMPLE)
+            .unwrap_or_else(|| vec![1.0]);
+        if bits.iter().any(|&b| b != 8.0) {
+            return Err(fail("not 8 bits per sample"));
+        }
+        // 0 is gray with white at zero, 1 gray with black at zero and 2 RGB.
+        let white_is_zero = match one(TAG_PHOTOMETRIC).map(|p| p as u32) {
+            Some(0) => true,
+            Some(1) | Some(2) => false,
+            _ => return Err(fail("neither gray nor RGB")),
+        };
+        if !(1..=4).contains(&samples) {
+            return Err(fail("not 1 to 4 samples per pixel"));
+        }
+
+        let offsets = tiff
+            .values(TAG_STRIP_OFFSETS)
+            .ok_or_else(|| fail("not in strips"))?;
+        let counts = tiff
+            .values(TAG_STRIP_BYTE_COUNTS)
+            .ok_or_else(|| fail("not in strips"))?;
+        let mut data = Vec::with_capacity(width as usize * height as usize * samples);
+        for (&offset, &count) in offsets.iter().zip(&counts) {
+            let strip = bytes
+                .get(offset as usize..(offset + count) as usize)
+                .ok_or_else(|| fail("strip past the end of the file"))?;
+            data.extend_from_slice(strip);
+        }
+        let pixel_count = width as usize * height as usize;
+        if data.len() < pixel_count * samples {
+            return Err(fail("fewer pixels than its size"));
+        }
+        let pixels = data
+            .chunks_exact(samples)
+            .take(pixel_count)
+            .flat_map(|pixel| {
+                let gray = |v: u8| if white_is_zero { 255 - v } else { v };
+                match *pixel {
+                    [v] => [gray(v), gray(v), gray(v), 255],
+                    [v, a] => [gray(v), gray(v), gray(v), a],
+                    [r, g, b] => [r, g, b, 255],
+                    [r, g, b, a] => [r, g, b, a],
+                    _ => unreachable!(),
+                }
+            })
+            .collect();
+
+        let scale = tiff
+            .values(TAG_MODEL_PIXEL_SCALE)
+            .filter(|s| s.len() >= 2)
+            .ok_or_else(|| fail("no model pixel scale"))?;
+        let tie = tiff
+            .values(TAG_MODEL_TIEPOINT)
+            .filter(|t| t.len() >= 6)
+            .ok_or_else(|| fail("no model tie point"))?;
+        // The tie point puts raster position (i, j), in pixel corners, at (x, y).
+        let (sx, sy) = (scale[0], scale[1]);
+        let west = tie[3] - tie[0] * sx;
+        let north = tie[4] + tie[1] * sy;
+        Ok(GeoRaster {
+            width,
+            height,
+            pixels,
+            extent: GeoExtent {
+                west: west as f32,
+                south: (north - height as f64 * sy) as f32,
+                east: (west + width as f64 * sx) as f32,
+                north: north as f32,
+            },
+        })
+    }
+
+    pub fn texture(&self) -> Texture {
+        Texture::new(
+            Vec2::new(self.width as f32, self.height as f32),
+            self.pixels.clone(),
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+
+    /// The raster's extent as a patch of a sphere of `radius` in the frame of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh), in steps of at most `max_step`
+    /// degrees of latitude and longitude, with the raster's texture coordinates.
+    pub fn mesh(&self, radius: f32, max_step: f32) -> Mesh {
+        let GeoExtent {
+            west,
+            south,
+            east,
+            north,
+        } = self.extent;
+        let steps = |span: f32| ((span.abs() / max_step.max(f32::EPSILON)).ceil() as u32).max(1);
+        patch_mesh(radius, steps(east - west), steps(north - south), |u, v| {
+            (north + (south - north) * v, west + (east - west) * u)
+        })
+    }
+}
+
+/// A grid of `columns` by `rows` quads on a sphere of `radius` in the frame of
+/// [`sphere_mesh`](crate::mesh::sphere_mesh), facing outwards, with texture
+/// coordinates `(u, v)` placed at the `[lat, lon]` that `place` gives for them.
+pub(crate) fn patch_mesh(
+    radius: f32,
+    columns: u32,
+    rows: u32,
+    place: impl Fn(f32, f32) -> (f32, f32),
+) -> Mesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    for row in 0..=rows {
+        let v = row as f32 / rows as f32;
+        for column in 0..=columns {
+            let u = column as f32 / columns as f32;
+            let (lat, lon) = place(u, v);
+            let local = geo_to_local(lat, lon, 1.0);
+            positions.push(<[f32; 3]>::from(local * radius));
+            normals.push(<[f32; 3]>::from(local));
+            uvs.push([u, v]);
+        }
+    }
+    let index = |row: u32, column: u32| row * (columns + 1) + column;
+    // Wind the triangles counter-clockwise seen from outside the sphere.
+    let corner = |i: u32| Vec3::from(positions[i as usize]);
+    let (a, b, c) = (
+        corner(index(0, 0)),
+        corner(index(0, 1)),
+        corner(index(1, 0)),
+    );
+    let outward = (b - a).cross(c - a).dot(a) > 0.0;
+    let mut indices = Vec::with_capacity((rows * columns * 6) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let (a, b) = (index(row, column), index(row, column + 1));
+            let (c, d) = (index(row + 1, column), index(row + 1, column + 1));
+            if outward {
+                indices.extend([a, b, c, b, d, c]);
+            } else {
+                indices.extend([a, c, b, b, c, d]);
+            }
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// The first image file directory of a TIFF file.
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+    /// `(tag, type, count, value or offset)` of each entry.
+    entries: Vec<(u16, u16, u32, u32)>,
+}
+
+impl<'a> Tiff<'a> {
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let little_endian = match bytes.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        let mut tiff = Tiff {
+            bytes,
+            little_endian,
+            entries: Vec::new(),
+        };
+        if tiff.u16_at(2)? != 42 {
+            return None;
+        }
+        let ifd = tiff.u32_at(4)? as usize;
+        let count = tiff.u16_at(ifd)? as usize;
+        for i in 0..count {
+            let at = ifd + 2 + i * 12;
+            let entry = (
+                tiff.u16_at(at)?,
+                tiff.u16_at(at + 2)?,
+                tiff.u32_at(at + 4)?,
+                tiff.u32_at(at + 8)?,
+            );
+            tiff.entries.push(entry);
+        }
+        Some(tiff)
+    }
+
+    fn u16_at(&self, at: usize) -> Option<u16> {
+        let bytes = self.bytes.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32_at(&self, at: usize) -> Option<u32> {
+        let bytes = self.bytes.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64_at(&self, at: usize) -> Option<f64> {
+        let bytes = self.bytes.get(at..at + 8)?.try_into().ok()?;
+        Some(if self.little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    /// The values of `tag` as numbers, for the byte, short, long and double types.
+    fn values(&self, tag: u16) -> Option<Vec<f64>> {
+        let &(_, kind, count, value) = self.entries.iter().find(|entry| entry.0 == tag)?;
+        let size = match kind {
+            1 => 1,
+            3 => 2,
+            4 => 4,
+            12 => 8,
+            _ => return None,
+        };
+        let count = count as usize;
+        // Values that fit in four bytes are stored in place of the offset.
+        let start = if size * count <= 4 {
+            None
+        } else {
+            Some(value as usize)
+        };
+        (0..count)
+            .map(|i| match (kind, start) {
+                (1, None) => Some(self.inline(value, i, 1) as f64),
+                (3, None) => Some(self.inline(value, i, 2) as f64),
+                (4, None) => Some(value as f64),
+                (1, Some(at)) => self.bytes.get(at + i).map(|&b| b as f64),
+                (3, Some(at)) => self.u16_at(at + i * 2).map(f64::from),
+                (4, Some(at)) => self.u32_at(at + i * 4).map(f64::from),
+                (12, Some(at)) => self.f64_at(at + i * 8),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Value `i` of `size` bytes packed into the four bytes of an entry's offset field,
+    /// which was read as a number in the file's byte order.
+    fn inline(&self, field: u32, i: usize, size: usize) -> u32 {
+        let bytes = if self.little_endian {
+            field.to_le_bytes()
+        } else {
+            field.to_be_bytes()
+        };
+        bytes[i * size..(i + 1) * size]
+            .iter()
+            .enumerate()
+            .map(|(j, &b)| {
+                let shift = if self.little_endian { j } else { size - 1 - j };
+                (b as u32) << (8 * shift)
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,249 @@
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::bodies::geo_to_local;
+
+/// Most times each triangle's edges are split to follow the curve of the sphere.
+const MAX_SUBDIVISIONS: usize = 64;
+/// Below this, three corners count as lying on one line.
+const COLLINEAR_EPSILON: f32 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RegionError {
+    #[error("region {0:?} needs at least three corners")]
+    TooFewPoints(String),
+    #[error("region {0:?} does not fit in one hemisphere")]
+    TooLarge(String),
+    #[error("region {0:?} has edges that cross each other")]
+    SelfIntersecting(String),
+}
+
+/// An area of the globe to highlight, such as a country or an area of interest: the
+/// polygon through `points`, with edges running along great circles, filled with
+/// `color`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoRegion {
+    #[serde(default)]
+    pub name: String,
+    /// Corners as `[lat, lon]` in degrees, in either winding. The last corner joins
+    /// back to the first.
+    pub points: Vec<[f32; 2]>,
+    /// Fill as linear RGBA; see-through by default.
+    #[serde(default = "default_fill")]
+    pub color: [f32; 4],
+}
+
+fn default_fill() -> [f32; 4] {
+    [1.0, 0.3, 0.2, 0.35]
+}
+
+impl GeoRegion {
+    /// A translucent fill of the region lying on a sphere of `radius` in the frame of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh), a little larger than the globe's to
+    /// stay clear of it. The polygon is triangulated in a gnomonic projection about its
+    /// center, which turns great circles into straight lines, and each triangle is
+    /// split until no edge spans more than `max_edge` radians so the fill follows the
+    /// curve when projected back onto the sphere.
+    pub fn mesh(&self, radius: f32, max_edge: f32) -> Result<Mesh, RegionError> {
+        let mut corners = self
+            .points
+            .iter()
+            .map(|&[lat, lon]| geo_to_local(lat, lon, 1.0))
+            .collect::<Vec<_>>();
+        if corners.len() > 1 && corners.first() == corners.last() {
+            corners.pop();
+        }
+        corners.dedup();
+        if corners.len() < 3 {
+            return Err(RegionError::TooFewPoints(self.name.clone()));
+        }
+        let plane =
+            Gnomonic::around(&corners).ok_or_else(|| RegionError::TooLarge(self.name.clone()))?;
+        let projected = corners
+            .iter()
+            .map(|&c| plane.project(c))
+            .collect::<Vec<_>>();
+        let triangles = triangulate(&projected)
+            .ok_or_else(|| RegionError::SelfIntersecting(self.name.clone()))?;
+        Ok(plane.fill_mesh(&projected, &triangles, radius, max_edge))
+    }
+}
+
+/// The gnomonic projection about the center of some points of the unit sphere, which
+/// turns great circles into straight lines: each point is taken along its ray from the
+/// sphere's center onto the plane touching the sphere at the center.
+pub(crate) struct Gnomonic {
+    center: Vec3,
+    east: Vec3,
+    north: Vec3,
+}
+
+impl Gnomonic {
+    /// The projection about the center of `points`, `None` unless they all lie in the
+    /// open hemisphere around it.
+    pub(crate) fn around(points: &[Vec3]) -> Option<Self> {
+        let sum = points.iter().fold(Vec3::zero(), |sum, &c| sum + c);
+        if sum.length_squared() <= f32::EPSILON {
+            return None;
+        }
+        let center = sum.normalize();
+        if points.iter().any(|c| c.dot(center) <= f32::EPSILON) {
+            return None;
+        }
+        let axis = if center.x().abs() < 0.9 {
+            Vec3::unit_x()
+        } else {
+            Vec3::unit_y()
+        };
+        let east = center.cross(axis).normalize();
+        let north = center.cross(east);
+        Some(Gnomonic {
+            center,
+            east,
+            north,
+        })
+    }
+
+    pub(crate) fn project(&self, point: Vec3) -> Vec2 {
+        let on_plane = point / point.dot(self.center);
+        Vec2::new(on_plane.dot(self.east), on_plane.dot(self.north))
+    }
+
+    /// The point of the unit sphere projected to `p`.
+    pub(crate) fn unproject(&self, p: Vec2) -> Vec3 {
+        (self.center + self.east * p.x() + self.north * p.y()).normalize()
+    }
+
+    /// The `triangles` of `projected` points, counter-clockwise on the plane, as a fill
+    /// on the sphere of `radius` facing outwards, each triangle split until no edge
+    /// spans more than `max_edge` radians so the fill follows the curve.
+    pub(crate) fn fill_mesh(
+        &self,
+        projected: &[Vec2],
+        triangles: &[[usize; 3]],
+        radius: f32,
+        max_edge: f32,
+    ) -> Mesh {
+        // With north = center × east, counter-clockwise on the plane is
+        // counter-clockwise seen from outside the sphere.
+        let on_sphere = |i: usize| self.unproject(projected[i]);
+        // Splitting every triangle the same number of times keeps shared edges
+        // matched, so the fill has no cracks.
+        let longest = triangles
+            .iter()
+            .flat_map(|t| (0..3).map(move |i| (t[i], t[(i + 1) % 3])))
+            .map(|(a, b)| on_sphere(a).dot(on_sphere(b)).min(1.0).acos())
+            .fold(0.0, f32::max);
+        let splits =
+            ((longest / max_edge.max(f32::EPSILON)).ceil() as usize).clamp(1, MAX_SUBDIVISIONS);
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+        for t in triangles {
+            let (a, b, c) = (projected[t[0]], projected[t[1]], projected[t[2]]);
+            let first = positions.len() as u32;
+            // Row `i` of the grid runs from a + (b - a) i/n towards c.
+            let index = |i: usize, j: usize| first + (i * (2 * splits + 3 - i) / 2 + j) as u32;
+            for i in 0..=splits {
+                for j in 0..=splits - i {
+                    let (u, v) = (i as f32 / splits as f32, j as f32 / splits as f32);
+                    let normal = self.unproject(a + (b - a) * u + (c - a) * v);
+                    positions.push(<[f32; 3]>::from(normal * radius));
+                    normals.push(<[f32; 3]>::from(normal));
+                }
+            }
+            for i in 0..splits {
+                for j in 0..splits - i {
+                    indices.extend([index(i, j), index(i + 1, j), index(i, j + 1)]);
+                    if i + j + 1 < splits {
+                        indices.extend([index(i + 1, j), index(i + 1, j + 1), index(i, j + 1)]);
+                    }
+                }
+            }
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(
+            Mesh::ATTRIBUTE_UV_0,
+            vec![[0.0f32, 0.0]; positions.len()].into(),
+        );
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+}
+
+/// Triangles covering the simple polygon through `points`, by ear clipping, each
+/// wound counter-clockwise whichever way the polygon is. `None` if the polygon
+/// crosses itself.
+fn triangulate(points: &[Vec2]) -> Option<Vec<[usize; 3]>> {
+    let cross = |o: Vec2, a: Vec2, b: Vec2| {
+        let (a, b) = (a - o, b - o);
+        a.x() * b.y() - a.y() * b.x()
+    };
+    let area = (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.x() * b.y() - b.x() * a.y()
+        })
+        .sum::<f32>();
+    let n = points.len();
+    let edge = |i: usize| (points[i], points[(i + 1) % n]);
+    let crosses = |(a, b): (Vec2, Vec2), (c, d): (Vec2, Vec2)| {
+        let side = |p: Vec2, q: Vec2, r: Vec2| cross(p, q, r).signum();
+        side(a, b, c) * side(a, b, d) < 0.0 && side(c, d, a) * side(c, d, b) < 0.0
+    };
+    // Edges meeting at a corner share an end, so only others can cross.
+    for i in 0..n {
+        for j in i + 2..n {
+            if (j + 1) % n != i && crosses(edge(i), edge(j)) {
+                return None;
+            }
+        }
+    }
+    let mut ring = (0..n).collect::<Vec<_>>();
+    if area < 0.0 {
+        ring.reverse();
+    }
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2));
+    while ring.len() > 3 {
+        let n = ring.len();
+        let corner = |i: usize| (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+        // A corner on a straight stretch of edge is no corner at all.
+        if let Some(straight) = (0..n).find(|&i| {
+            let (a, b, c) = corner(i);
+            cross(points[a], points[b], points[c]).abs() <= COLLINEAR_EPSILON
+        }) {
+            ring.remove(straight);
+            continue;
+        }
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = corner(i);
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            cross(pa, pb, pc) > 0.0
+                && ring.iter().all(|&other| {
+                    let p = points[other];
+                    other == a
+                        || other == b
+                        || other == c
+                        || cross(pa, pb, p) < 0.0
+                        || cross(pb, pc, p) < 0.0
+                        || cross(pc, pa, p) < 0.0
+                })
+        })?;
+        let (a, b, c) = corner(ear);
+        triangles.push([a, b, c]);
+        ring.remove(ear);
+    }
+    if let [a, b, c] = ring[..] {
+        if cross(points[a], points[b], points[c]).abs() > COLLINEAR_EPSILON {
+            triangles.push([a, b, c]);
+        }
+    }
+    Some(triangles)
+}
@@ -0,0 +1,198 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{self, LayoutConfig, LayoutViolation, Ring, RingLayout};
+use crate::target::Target;
+
+/// A target in the layout a [`LayoutCase`] starts from: its ring and its azimuth
+/// there, before the ring's offset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseSlot {
+    pub ring: usize,
+    pub azimuth: f32,
+    pub target: Target,
+}
+
+/// One change a [`LayoutCase`] makes to its layout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum LayoutStep {
+    Remove { id: i32 },
+    Insert { target: Target },
+}
+
+/// The inputs of one layout run exactly: the config, the layout it started from and
+/// the removals and insertions made to it, in order. Since layouts are built up
+/// incrementally a failure can depend on all of that, not just on the targets that
+/// ended up in it. Dumped as JSON when [`layout::verify`] fails at runtime, see
+/// [`LayoutCheck`](crate::display::LayoutCheck), and run again with
+/// [`LayoutCase::check`] or `ring_layout --case`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutCase {
+    pub config: LayoutConfig,
+    /// Radii of the starting layout's rings, as in [`RingLayout::radii`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub radii: Vec<f32>,
+    /// Rings of the starting layout, trailing empty ones included.
+    #[serde(default)]
+    pub rings: usize,
+    #[serde(default)]
+    pub start: Vec<CaseSlot>,
+    #[serde(default)]
+    pub steps: Vec<LayoutStep>,
+    /// The steps are all insertions into an empty layout, made in one go by
+    /// [`RingLayout::with_config`] rather than one by one.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub from_scratch: bool,
+    /// What [`layout::verify`] reported, once it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violation: Option<String>,
+}
+
+impl LayoutCase {
+    /// A case that starts from an empty layout, with no steps yet.
+    pub fn new(config: LayoutConfig) -> Self {
+        LayoutCase {
+            config,
+            radii: Vec::new(),
+            rings: 0,
+            start: Vec::new(),
+            steps: Vec::new(),
+            from_scratch: false,
+            violation: None,
+        }
+    }
+
+    /// A case that starts from `layout` as it is.
+    pub fn resume(layout: &RingLayout) -> Self {
+        LayoutCase {
+            radii: layout.radii.clone(),
+            rings: layout.rings.len(),
+            start: layout
+                .placements()
+                .map(|p| CaseSlot {
+                    ring: p.ring,
+                    azimuth: p.azimuth,
+                    target: p.target.clone(),
+                })
+                .collect(),
+            ..LayoutCase::new(layout.config)
+        }
+    }
+
+    /// A case that lays `targets`, sorted by distance, out from scratch as
+    /// [`RingLayout::with_config`] does.
+    pub fn fresh(targets: &[Target], config: LayoutConfig) -> Self {
+        LayoutCase {
+            from_scratch: true,
+            steps: targets
+                .iter()
+                .map(|t| LayoutStep::Insert { target: t.clone() })
+                .collect(),
+            ..LayoutCase::new(config)
+        }
+    }
+
+    pub fn remove(&mut self, id: i32) {
+        self.steps.push(LayoutStep::Remove { id });
+    }
+
+    pub fn insert(&mut self, target: &Target) {
+        self.steps.push(LayoutStep::Insert {
+            target: target.clone(),
+        });
+    }
+
+    /// The layout the case starts from.
+    pub fn start_layout(&self) -> RingLayout {
+        let rings = self
+            .start
+            .iter()
+            .map(|s| s.ring + 1)
+            .max()
+            .unwrap_or(0)
+            .max(self.rings);
+        let mut rings = vec![Ring::new(); rings];
+        for slot in &self.start {
+            rings[slot.ring].insert(slot.azimuth, slot.target.clone());
+        }
+        RingLayout::from_rings(self.config, rings, self.radii.clone())
+    }
+
+    /// The targets the layout should hold once every step is made.
+    pub fn expected(&self) -> Vec<Target> {
+        let mut targets = self
+            .start
+            .iter()
+            .map(|s| s.target.clone())
+            .collect::<Vec<_>>();
+        for step in &self.steps {
+            match step {
+                LayoutStep::Remove { id } => targets.retain(|t| t.id != *id),
+                LayoutStep::Insert { target } => {
+                    targets.retain(|t| t.id != target.id);
+                    targets.push(target.clone());
+                }
+            }
+        }
+        targets
+    }
+
+    /// Makes every step to the starting layout, or lays the insertions out in one go
+    /// if the case is [`from_scratch`](LayoutCase::from_scratch).
+    pub fn replay(&self) -> RingLayout {
+        if self.from_scratch {
+            return RingLayout::with_config(&self.expected(), self.config);
+        }
+        let mut layout = self.start_layout();
+        for step in &self.steps {
+            match step {
+                LayoutStep::Remove { id } => {
+                    layout.remove(*id);
+                }
+                LayoutStep::Insert { target } => {
+                    layout.insert(target.clone());
+                }
+            }
+        }
+        layout
+    }
+
+    /// Replays the case and runs [`layout::verify`] on the result.
+    pub fn check(&self) -> Result<RingLayout, LayoutViolation> {
+        let layout = self.replay();
+        layout::verify(&layout, &self.expected())?;
+        Ok(layout)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json + "\n")
+    }
+
+    /// Saves the case into `dir`, created if need be, under a name of its own, and
+    /// returns the path.
+    pub fn dump(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        let mut path = dir.join(format!("layout-case-{}.json", millis));
+        let mut n = 1;
+        while path.exists() {
+            path = dir.join(format!("layout-case-{}-{}.json", millis, n));
+            n += 1;
+        }
+        self.save(&path)?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::target::Target;
+use crate::updates::{TargetAdded, TargetChanged, TargetRemoved};
+
+/// Seconds [`Action::SeekBack`] and [`Action::SeekForward`] seek the replay by.
+const SEEK_STEP: f64 = 5.0;
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("failed to parse {0} line {1}: {2}")]
+    Parse(String, usize, #[source] serde_json::Error),
+}
+
+/// One of the target events of [`updates`](crate::updates), as recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TargetEvent {
+    Add { target: Target },
+    Change { target: Target },
+    Remove { id: i32 },
+}
+
+/// A [`TargetEvent`] and when it came, in seconds since the recording started. A
+/// recording file has one per line as JSON, e.g.
+///
+/// ```text
+/// {"t":0.52,"type":"add","target":{"id":7,"text":"buoy","azimuth":0.78,"dist":30.0,"priority":0}}
+/// {"t":1.5,"type":"remove","id":7}
+/// ```
+///
+/// Targets are stored as in scenario files, with the azimuth in radians.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub t: f64,
+    #[serde(flatten)]
+    pub event: TargetEvent,
+}
+
+/// The events of a recording file, in the order they came.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    pub events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let text = fs::read_to_string(path).map_err(|e| ReplayError::Io(name.clone(), e))?;
+        let mut events = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| ReplayError::Parse(name.clone(), i + 1, e))
+            })
+            .collect::<Result<Vec<RecordedEvent>, _>>()?;
+        // Stable, so events of the same instant keep their order.
+        events.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+        Ok(Recording { events })
+    }
+
+    /// Time of the last event.
+    pub fn duration(&self) -> f64 {
+        self.events.last().map_or(0.0, |event| event.t)
+    }
+
+    /// The targets shown after the first `count` events, by id.
+    pub fn state_after(&self, count: usize) -> BTreeMap<i32, &Target> {
+        let mut shown = BTreeMap::new();
+        for recorded in &self.events[..count.min(self.events.len())] {
+            match &recorded.event {
+                TargetEvent::Add { target } | TargetEvent::Change { target } => {
+                    shown.insert(target.id, target);
+                }
+                TargetEvent::Remove { id } => {
+                    shown.remove(id);
+                }
+            }
+        }
+        shown
+    }
+
+    /// How many events came at or before `t`.
+    pub fn events_until(&self, t: f64) -> usize {
+        self.events.partition_point(|event| event.t <= t)
+    }
+}
+
+/// Appends every [`TargetAdded`], [`TargetChanged`] and [`TargetRemoved`] event to
+/// `path` as a [`RecordedEvent`] line, timed from when the app started, whatever sent
+/// it. The file is replaced if it exists and flushed every frame something was
+/// recorded. Needs [`TargetUpdatesPlugin`](crate::updates::TargetUpdatesPlugin).
+pub struct FeedRecorderPlugin {
+    pub path: PathBuf,
+}
+
+struct Recorder {
+    path: PathBuf,
+    /// `None` once writing failed.
+    file: Option<BufWriter<File>>,
+}
+
+impl Plugin for FeedRecorderPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let file = match File::create(&self.path) {
+            Ok(file) => BufWriter::new(file),
+            Err(e) => {
+                tracing::warn!("not recording to {}: {}", self.path.display(), e);
+                return;
+            }
+        };
+        app.add_resource(Recorder {
+            path: self.path.clone(),
+            file: Some(file),
+        })
+        .add_system(record_system.system());
+    }
+}
+
+#[derive(Default)]
+struct RecordReaders {
+    added: EventReader<TargetAdded>,
+    changed: EventReader<TargetChanged>,
+    removed: EventReader<TargetRemoved>,
+}
+
+fn record_system(
+    mut readers: Local<RecordReaders>,
+    time: Res<Time>,
+    mut recorder: ResMut<Recorder>,
+    added: Res<Events<TargetAdded>>,
+    changed: Res<Events<TargetChanged>>,
+    removed: Res<Events<TargetRemoved>>,
+) {
+    let t = time.seconds_since_startup;
+    let events = readers
+        .added
+        .iter(&added)
+        .map(|e| TargetEvent::Add {
+            target: e.0.clone(),
+        })
+        .chain(readers.changed.iter(&changed).map(|e| TargetEvent::Change {
+            target: e.0.clone(),
+        }))
+        .chain(
+            readers
+                .removed
+                .iter(&removed)
+                .map(|e| TargetEvent::Remove { id: e.0 }),
+        )
+        .map(|event| RecordedEvent { t, event })
+        .collect::<Vec<_>>();
+    let recorder = &mut *recorder;
+    let file = match (&mut recorder.file, events.is_empty()) {
+        (Some(file), false) => file,
+        _ => return,
+    };
+    let written = events
+        .iter()
+        .try_for_each(|event| {
+            serde_json::to_writer(&mut *file, event).map_err(io::Error::other)?;
+            writeln!(file)
+        })
+        .and_then(|()| file.flush());
+    if let Err(e) = written {
+        tracing::warn!("recording to {} stopped: {}", recorder.path.display(), e);
+        recorder.file = None;
+    }
+}
+
+/// Where a [`FeedReplayPlugin`] is in its recording. Change `speed` or `paused`
+/// directly; [`Replay::seek`] jumps.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub recording: Recording,
+    pub speed: f32,
+    pub paused: bool,
+    position: f64,
+    /// Events sent so far.
+    next: usize,
+    /// Ids of the targets the replay has shown and not removed.
+    shown: BTreeSet<i32>,
+    /// Set by [`Replay::seek`] until the replay system catches the targets up.
+    seeking: bool,
+}
+
+impl Replay {
+    pub fn new(recording: Recording, speed: f32) -> Self {
+        Replay {
+            recording,
+            speed,
+            paused: false,
+            position: 0.0,
+            next: 0,
+            shown: BTreeSet::new(),
+            seeking: false,
+        }
+    }
+
+    /// Seconds into the recording.
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    /// Jumps to `t` seconds into the recording, showing the targets as they were then.
+    pub fn seek(&mut self, t: f64) {
+        self.position = t.max(0.0).min(self.recording.duration());
+        self.seeking = true;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.recording.events.len()
+    }
+}
+
+/// Plays a [`Recording`] back as target events, `speed` times as fast as it was
+/// recorded. [`Action::PlayPause`] pauses, the speed actions set 0.5x, 1x and 2x,
+/// [`Action::SeekBack`] and [`Action::SeekForward`] seek back and forth by five seconds
+/// and [`Action::SeekStart`] restarts; by default Space, 1, 2 and 3, and Shift with
+/// Left, Right and Home. The replay clock is the unscaled
+/// [`AnimationTime`], so it keeps going while animations are paused. Needs
+/// [`TargetUpdatesPlugin`](crate::updates::TargetUpdatesPlugin).
+pub struct FeedReplayPlugin {
+    pub recording: Recording,
+    pub speed: f32,
+}
+
+impl Plugin for FeedReplayPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        app.add_plugin(AnimationTimePlugin)
+            .add_resource(Replay::new(self.recording.clone(), self.speed))
+            .add_system(replay_keys_system.system())
+            .add_system(replay_system.system());
+    }
+}
+
+fn replay_keys_system(actions: Res<ActionState>, mut replay: ResMut<Replay>) {
+    let before = (replay.speed, replay.paused);
+    if actions.just_pressed(Action::PlayPause) {
+        replay.paused = !replay.paused;
+    }
+    for (action, speed) in [
+        (Action::HalfSpeed, 0.5),
+        (Action::NormalSpeed, 1.0),
+        (Action::DoubleSpeed, 2.0),
+    ] {
+        if actions.just_pressed(action) {
+            replay.speed = speed;
+        }
+    }
+    let position = replay.position;
+    if actions.just_pressed(Action::SeekBack) {
+        replay.seek(position - SEEK_STEP);
+    }
+    if actions.just_pressed(Action::SeekForward) {
+        replay.seek(position + SEEK_STEP);
+    }
+    if actions.just_pressed(Action::SeekStart) {
+        replay.seek(0.0);
+    }
+    if replay.seeking || (replay.speed, replay.paused) != before {
+        tracing::info!(
+            "replay {:.1}/{:.1} s at {}x{}",
+            replay.position,
+            replay.recording.duration(),
+            replay.speed,
+            if replay.paused { ", paused" } else { "" }
+        );
+    }
+}
+
+fn replay_system(
+    time: Res<AnimationTime>,
+    mut replay: ResMut<Replay>,
+    mut added: ResMut<Events<TargetAdded>>,
+    mut changed: ResMut<Events<TargetChanged>>,
+    mut removed: ResMut<Events<TargetRemoved>>,
+) {
+    let replay = &mut *replay;
+    if replay.seeking {
+        // Bring the targets to how they were at the new position in one go.
+        replay.seeking = false;
+        replay.next = replay.recording.events_until(replay.position);
+        let state = replay.recording.state_after(replay.next);
+        for &id in replay.shown.iter().filter(|id| !state.contains_key(id)) {
+            removed.send(TargetRemoved(id));
+        }
+        for &target in state.values() {
+            changed.send(TargetChanged(target.clone()));
+        }
+        replay.shown = state.keys().copied().collect();
+        return;
+    }
+    if replay.paused || replay.is_finished() {
+        return;
+    }
+    replay.position += (time.unscaled_delta_seconds() * replay.speed.max(0.0)) as f64;
+    let until = replay.recording.events_until(replay.position);
+    for recorded in &replay.recording.events[replay.next..until] {
+        match &recorded.event {
+            TargetEvent::Add { target } => {
+                replay.shown.insert(target.id);
+                added.send(TargetAdded(target.clone()));
+            }
+            TargetEvent::Change { target } => {
+                replay.shown.insert(target.id);
+                changed.send(TargetChanged(target.clone()));
+            }
+            TargetEvent::Remove { id } => {
+                replay.shown.remove(id);
+                removed.send(TargetRemoved(*id));
+            }
+        }
+    }
+    replay.next = until;
+}
@@ -0,0 +1,297 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+
+use crate::display::{RadarDisplay, LABEL_FONT_SIZE};
+use crate::geo::Polar;
+use crate::layout::{LayoutConfig, RingLayout};
+use crate::target::Target;
+use crate::theme::{RingTextScale, Theme};
+
+/// Elevations rings are raised to, so that targets at similar elevations share one.
+const ELEVATION_STEP: f32 = PI / 18.0;
+/// Segments each ring circle is drawn with.
+const CIRCLE_SEGMENTS: usize = 96;
+/// How far labels sit to the right of their marker, in pixels.
+const LABEL_OFFSET: f32 = 8.0;
+
+/// Where `(azimuth, elevation, radius)` is in the 3D display: `radius` out from the
+/// origin, `azimuth` counter-clockwise from +x seen from above and `elevation` up
+/// from the ground plane, with y up.
+pub fn polar_to_world(azimuth: f32, elevation: f32, radius: f32) -> Vec3 {
+    Polar::new(azimuth, radius).to_world(elevation)
+}
+
+/// On an entity turned to face the 3D camera every frame.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Billboard;
+
+/// On a label of [`ElevationRingPlugin`]: the world position it is drawn next to.
+#[derive(Debug, Clone, Copy)]
+pub struct Label3d {
+    pub anchor: Vec3,
+}
+
+/// Marks the entities [`ElevationRingPlugin`] owns; they are respawned on every
+/// layout.
+#[derive(Debug, Clone, Copy)]
+pub struct Ring3dPart;
+
+/// Font asset path for [`ElevationRingPlugin`] labels.
+#[derive(Debug, Clone, Copy)]
+pub struct Ring3dFont(pub &'static str);
+
+/// The ring display in a 3D scene, for targets with an
+/// [`elevation`](Target::elevation). Targets are placed by the same [`RingLayout`] as
+/// in [`PoiRingPlugin`](crate::display::PoiRingPlugin), then lifted to their
+/// elevation: markers are billboards facing the camera at `(azimuth, elevation,
+/// ring radius)` with a leader line from the origin, and each ring is drawn on the
+/// ground and again raised onto the cone of every elevation, in steps of 10°, its
+/// targets are at. It spawns no camera; add it to an app with a 3D camera.
+///
+/// Any change of a [`Target`] or of the [`LayoutConfig`] resource lays everything out
+/// again. Markers take their category color from the [`RadarDisplay`] and their label
+/// from its label content.
+pub struct ElevationRingPlugin {
+    /// Becomes the [`LayoutConfig`] resource unless the app already has one.
+    pub config: LayoutConfig,
+    /// Asset path of the label font.
+    pub font: &'static str,
+}
+
+impl Default for ElevationRingPlugin {
+    fn default() -> Self {
+        ElevationRingPlugin {
+            config: LayoutConfig::default(),
+            font: "arial.ttf",
+        }
+    }
+}
+
+impl Plugin for ElevationRingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<RadarDisplay>() {
+            app.init_resource::<RadarDisplay>();
+        }
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        if !app.resources().contains::<LayoutConfig>() {
+            app.add_resource(self.config);
+        }
+        if !app.resources().contains::<RingTextScale>() {
+            app.init_resource::<RingTextScale>();
+        }
+        app.add_resource(Ring3dFont(self.font))
+            .add_system(ring3d_layout_system.system())
+            .add_system(billboard_system.system())
+            .add_system(label3d_system.system());
+    }
+}
+
+#[derive(Default)]
+struct Ring3dState {
+    config: Option<LayoutConfig>,
+    text_scale: Option<RingTextScale>,
+    font: Option<Handle<Font>>,
+}
+
+fn unshaded(color: Color) -> StandardMaterial {
+    StandardMaterial {
+        albedo: color,
+        shaded: false,
+        ..Default::default()
+    }
+}
+
+/// A mesh of line segments, each consecutive pair of `points` one segment.
+fn line_mesh(points: Vec<Vec3>) -> Mesh {
+    let count = points.len();
+    let positions = points.into_iter().map(<[f32; 3]>::from).collect::<Vec<_>>();
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0f32, 1.0, 0.0]; count].into(),
+    );
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0f32, 0.0]; count].into());
+    mesh.set_indices(Some(Indices::U32((0..count as u32).collect())));
+    mesh
+}
+
+/// The elevation step `target` is drawn at, 0 for the ground.
+fn elevation_step(target: &Target) -> i32 {
+    (target.elevation.unwrap_or(0.0) / ELEVATION_STEP).round() as i32
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn ring3d_layout_system(
+    mut commands: Commands,
+    mut state: Local<Ring3dState>,
+    config: Res<LayoutConfig>,
+    display: Res<RadarDisplay>,
+    (theme, text_scale): (Res<Theme>, Res<RingTextScale>),
+    font: Res<Ring3dFont>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    changed: Query<(Entity, Changed<Target>)>,
+    targets: Query<&Target>,
+    parts: Query<With<Ring3dPart, Entity>>,
+) {
+    let dirty = state.config != Some(*config)
+        || state.text_scale != Some(*text_scale)
+        || changed.iter().next().is_some()
+        || !targets.removed::<Target>().is_empty();
+    if !dirty {
+        return;
+    }
+    state.config = Some(*config);
+    state.text_scale = Some(*text_scale);
+    for entity in parts.iter() {
+        commands.despawn(entity);
+    }
+    let font = state
+        .font
+        .get_or_insert_with(|| asset_server.load(font.0))
+        .clone();
+    let mut sorted = targets.iter().cloned().collect::<Vec<_>>();
+    sorted.sort_unstable_by(|a, b| a.dist.total_cmp(&b.dist));
+    let layout = RingLayout::with_config(&sorted, *config);
+    let stroke = materials.add(unshaded(theme.stroke()));
+
+    let mut rings = Vec::new();
+    for (ring_ord, ring) in layout.rings.iter().enumerate() {
+        let radius = layout.ring_radius(ring_ord);
+        let mut steps = std::iter::once(0)
+            .chain(ring.values().map(elevation_step))
+            .collect::<Vec<_>>();
+        steps.sort_unstable();
+        steps.dedup();
+        for step in steps {
+            let elevation = step as f32 * ELEVATION_STEP;
+            let at = |i: usize| {
+                let azimuth = i as f32 / CIRCLE_SEGMENTS as f32 * PI * 2.0;
+                polar_to_world(azimuth, elevation, radius)
+            };
+            rings.extend((0..CIRCLE_SEGMENTS).flat_map(|i| [at(i), at(i + 1)]));
+        }
+    }
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(line_mesh(rings)),
+            material: stroke.clone(),
+            ..Default::default()
+        })
+        .with(Ring3dPart);
+
+    let quad = meshes.add(Mesh::from(shape::Quad::new(Vec2::new(
+        config.poi_width,
+        config.poi_width,
+    ))));
+    let mut leaders = Vec::new();
+    for placement in layout.placements() {
+        let target = placement.target;
+        let at = polar_to_world(
+            placement.drawn_azimuth(),
+            target.elevation.unwrap_or(0.0),
+            placement.radius,
+        );
+        leaders.extend([Vec3::zero(), at]);
+        let color = display
+            .categories()
+            .style(target)
+            .color
+            .unwrap_or_else(|| theme.stroke());
+        commands
+            .spawn(PbrComponents {
+                mesh: quad.clone(),
+                material: materials.add(unshaded(color)),
+                transform: Transform::from_translation(at),
+                ..Default::default()
+            })
+            .with(Billboard)
+            .with(Ring3dPart)
+            .spawn(TextComponents {
+                text: Text {
+                    value: display.label(target),
+                    font: font.clone(),
+                    style: TextStyle {
+                        font_size: text_scale.font_size(LABEL_FONT_SIZE, placement.ring),
+                        color: theme.text(),
+                    },
+                },
+                ..Default::default()
+            })
+            .with(Label3d { anchor: at })
+            .with(Ring3dPart);
+    }
+    commands
+        .spawn(PbrComponents {
+            mesh: meshes.add(line_mesh(leaders)),
+            material: stroke,
+            ..Default::default()
+        })
+        .with(Ring3dPart);
+}
+
+fn billboard_system(
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut billboards: Query<With<Billboard, Mut<Transform>>>,
+) {
+    let rotation = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some((_, eye)) => eye.rotation,
+        None => return,
+    };
+    for mut transform in billboards.iter_mut() {
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+    }
+}
+
+fn label3d_system(
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut labels: Query<(&Label3d, Mut<Style>, Mut<Draw>)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let view_projection = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some((camera, eye)) => camera.projection_matrix * eye.compute_matrix().inverse(),
+        None => return,
+    };
+    for (label, mut style, mut draw) in labels.iter_mut() {
+        let clip = view_projection * label.anchor.extend(1.0);
+        // Behind the camera.
+        if clip.w() <= 0.0 {
+            draw.is_visible = false;
+            continue;
+        }
+        draw.is_visible = true;
+        let ndc = Vec2::new(clip.x(), clip.y()) / clip.w();
+        let at = (ndc + Vec2::one()) / 2.0 * size;
+        let (left, bottom) = (Val::Px(at.x() + LABEL_OFFSET), Val::Px(at.y()));
+        if style.position.left != left || style.position.bottom != bottom {
+            style.position_type = PositionType::Absolute;
+            style.position = Rect {
+                left,
+                bottom,
+                ..Default::default()
+            };
+        }
+    }
+}
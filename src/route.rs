@@ -0,0 +1,284 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::pipeline::PrimitiveTopology;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::bodies::{geo_to_local, Body};
+use crate::geo_marker::body_for;
+
+/// Below this, a route's ends count as the same point or as opposite each other.
+const COINCIDENT_EPSILON: f32 = 1e-6;
+/// Radius of a route's flight marker, in widths of the route.
+const FLIGHT_SIZE: f32 = 1.5;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RouteError {
+    #[error("route {0:?} starts where it ends")]
+    Degenerate(String),
+}
+
+/// A route along the great circle between two points of a globe, such as a flight
+/// path, drawn by [`GeoRoutePlugin`] as a flat ribbon just above the surface. Spawn it
+/// as a child of the [`Body`] it belongs on, or on its own to put it on the body
+/// nearest the world origin, like a [`GeoMarker`](crate::geo_marker::GeoMarker).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoRoute {
+    #[serde(default)]
+    pub name: String,
+    /// Ends as `[lat, lon]` in degrees. Opposite ends, which every great circle through
+    /// one passes, are joined over the pole nearer the start.
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+    /// Linear RGBA.
+    #[serde(default = "default_color")]
+    pub color: [f32; 4],
+    /// Width of the ribbon, relative to the body's radius.
+    #[serde(default = "default_width")]
+    pub width: f32,
+    /// How far above the surface the ribbon lies, relative to the body's radius.
+    #[serde(default = "default_lift")]
+    pub lift: f32,
+    /// Straight pieces the ribbon is made of.
+    #[serde(default = "default_segments")]
+    pub segments: usize,
+    /// Seconds a marker takes to travel the route from start to end, over and over;
+    /// no marker without.
+    #[serde(default)]
+    pub flight: Option<f32>,
+}
+
+fn default_color() -> [f32; 4] {
+    [0.3, 0.8, 1.0, 0.9]
+}
+
+fn default_width() -> f32 {
+    0.004
+}
+
+fn default_lift() -> f32 {
+    0.005
+}
+
+fn default_segments() -> usize {
+    64
+}
+
+impl GeoRoute {
+    pub fn new(from: [f32; 2], to: [f32; 2]) -> Self {
+        GeoRoute {
+            name: String::new(),
+            from,
+            to,
+            color: default_color(),
+            width: default_width(),
+            lift: default_lift(),
+            segments: default_segments(),
+            flight: None,
+        }
+    }
+
+    /// The unit vector `t` of the way along the route, in the frame of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh): the start at 0, the end at 1.
+    pub fn point_at(&self, t: f32) -> Vec3 {
+        let a = geo_to_local(self.from[0], self.from[1], 1.0);
+        let b = geo_to_local(self.to[0], self.to[1], 1.0);
+        great_circle_point(a, b, t)
+    }
+
+    /// The route's ribbon on a body of `radius`, in the frame of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh), facing outwards. Its `u` runs from
+    /// 0 at the start to 1 at the end.
+    pub fn mesh(&self, radius: f32) -> Result<Mesh, RouteError> {
+        let a = geo_to_local(self.from[0], self.from[1], 1.0);
+        let b = geo_to_local(self.to[0], self.to[1], 1.0);
+        if a.dot(b) > 1.0 - COINCIDENT_EPSILON {
+            return Err(RouteError::Degenerate(self.name.clone()));
+        }
+        let segments = self.segments.max(1);
+        let points = (0..=segments)
+            .map(|i| self.point_at(i as f32 / segments as f32))
+            .collect::<Vec<_>>();
+        Ok(ribbon_mesh(
+            &points,
+            radius * self.width,
+            radius * (1.0 + self.lift),
+        ))
+    }
+}
+
+/// The unit vector `t` of the way along the great circle from `a` to `b`, both unit
+/// vectors: `a` at 0, `b` at 1. Opposite ends, which every great circle through one
+/// passes, are joined over the pole nearer `a`.
+pub(crate) fn great_circle_point(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    let cos = a.dot(b).clamp(-1.0, 1.0);
+    if cos > 1.0 - COINCIDENT_EPSILON {
+        return a.lerp(b, t).normalize();
+    }
+    // Opposite ends leave the great circle open; go by way of the pole.
+    let (towards, angle) = if cos < -1.0 + COINCIDENT_EPSILON {
+        let pole = if a.z() > 0.0 {
+            Vec3::unit_z()
+        } else {
+            -Vec3::unit_z()
+        };
+        let mut towards = pole - a * a.dot(pole);
+        if towards.length_squared() < COINCIDENT_EPSILON {
+            towards = a.cross(Vec3::unit_x());
+        }
+        (towards.normalize(), PI)
+    } else {
+        ((b - a * cos).normalize(), cos.acos())
+    };
+    let along = angle * t;
+    a * along.cos() + towards * along.sin()
+}
+
+/// A flat ribbon `width` wide through `points` on the unit sphere, at `radius` from
+/// the center and facing outwards. Its `u` runs from 0 at the first point to 1 at the
+/// last.
+pub(crate) fn ribbon_mesh(points: &[Vec3], width: f32, radius: f32) -> Mesh {
+    let last = points.len().saturating_sub(1);
+    let half = width / 2.0;
+    let mut positions = Vec::with_capacity(points.len() * 2);
+    let mut normals = Vec::with_capacity(points.len() * 2);
+    let mut uvs = Vec::with_capacity(points.len() * 2);
+    for (i, &p) in points.iter().enumerate() {
+        let ahead = points[(i + 1).min(last)] - points[i.saturating_sub(1)];
+        let side = p.cross(ahead).normalize() * half;
+        let u = i as f32 / last.max(1) as f32;
+        for (offset, v) in [(side, 0.0), (-side, 1.0)] {
+            positions.push(<[f32; 3]>::from(p * radius + offset));
+            normals.push(<[f32; 3]>::from(p));
+            uvs.push([u, v]);
+        }
+    }
+    // Vertex 2i is left of the way along, 2i + 1 right of it; both triangles of a
+    // piece are counter-clockwise seen from outside.
+    let indices = (0..last as u32)
+        .flat_map(|i| {
+            let (left, right) = (2 * i, 2 * i + 1);
+            [right, right + 2, left, left, right + 2, left + 2]
+        })
+        .collect::<Vec<_>>();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// On a [`GeoRoute`] once [`GeoRoutePlugin`] has drawn it on the body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoRouteDrawn {
+    pub body: Entity,
+}
+
+/// The marker traveling along the route it is a child of, for [`GeoRoute::flight`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RouteFlight;
+
+/// Draws each [`GeoRoute`] as an unshaded ribbon along its great circle, a child of its
+/// [`Body`] so it turns with it, and moves a ball along the routes that have a
+/// [`flight`](GeoRoute::flight) by [`AnimationTime`]. A route whose ends are the same
+/// point is reported and left undrawn. Routes spawned before any body wait for one.
+pub struct GeoRoutePlugin;
+
+impl Plugin for GeoRoutePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(AnimationTimePlugin)
+            .add_system(route_draw_system.system())
+            .add_system(route_flight_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn route_draw_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    routes: Query<Without<GeoRouteDrawn, (Entity, &GeoRoute, Option<&Parent>)>>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+) {
+    for (entity, route, parent) in routes.iter() {
+        let (body, adopt) = match body_for(parent, &bodies) {
+            Some(body) => body,
+            None => continue,
+        };
+        let radius = bodies.get(body).map_or(0.0, |(_, body, _)| body.radius);
+        commands.insert_one(entity, GeoRouteDrawn { body });
+        if adopt {
+            commands.push_children(body, &[entity]);
+        }
+        let mesh = match route.mesh(radius) {
+            Ok(mesh) => mesh,
+            Err(e) => {
+                tracing::warn!("skipping route: {}", e);
+                continue;
+            }
+        };
+        let [r, g, b, a] = route.color;
+        let color = Color::rgba(r, g, b, a);
+        let unshaded = |albedo| StandardMaterial {
+            albedo,
+            shaded: false,
+            ..Default::default()
+        };
+        commands.insert(
+            entity,
+            PbrComponents {
+                mesh: meshes.add(mesh),
+                material: materials.add(unshaded(color)),
+                draw: Draw {
+                    is_transparent: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        if route.flight.is_some() {
+            let marker = commands
+                .spawn(PbrComponents {
+                    mesh: meshes.add(Mesh::from(shape::Icosphere {
+                        radius: route.width * radius * FLIGHT_SIZE,
+                        subdivisions: 2,
+                    })),
+                    material: materials.add(unshaded(Color::rgb(r, g, b))),
+                    transform: Transform::from_translation(
+                        route.point_at(0.0) * radius * (1.0 + route.lift),
+                    ),
+                    ..Default::default()
+                })
+                .with(RouteFlight)
+                .current_entity();
+            if let Some(marker) = marker {
+                commands.push_children(entity, &[marker]);
+            }
+        }
+    }
+}
+
+fn route_flight_system(
+    time: Res<AnimationTime>,
+    bodies: Query<&Body>,
+    routes: Query<(&GeoRoute, &GeoRouteDrawn)>,
+    mut flights: Query<With<RouteFlight, (&Parent, Mut<Transform>)>>,
+) {
+    for (parent, mut transform) in flights.iter_mut() {
+        let (route, drawn) = match routes.get(parent.0) {
+            Ok(route) => route,
+            Err(_) => continue,
+        };
+        let (period, radius) = match (route.flight, bodies.get(drawn.body)) {
+            (Some(period), Ok(body)) if period > 0.0 => (period, body.radius),
+            _ => continue,
+        };
+        let t = (time.seconds() / period as f64).fract() as f32;
+        transform.translation = route.point_at(t) * radius * (1.0 + route.lift);
+    }
+}
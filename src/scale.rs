@@ -0,0 +1,183 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Most points a [`RadialScale::Breakpoints`] scale can have.
+pub const MAX_BREAKPOINTS: usize = 8;
+
+/// Maps target distance onto a position across the rings, so distances spanning
+/// several orders of magnitude can share one display. Positions are in ring units: `1.0`
+/// is the first ring, `2.0` the second and so on, with the origin at `0.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RadialScale {
+    /// Distance is in world units, like ring radii.
+    #[default]
+    Linear,
+    /// `near` lands on the first ring and each ring further out covers `ratio` times
+    /// the distance of the one inside it. Below `near` the scale is linear to the origin.
+    Log { near: f32, ratio: f32 },
+    /// Piecewise linear through `(distance, position)` points.
+    Breakpoints(Breakpoints),
+}
+
+impl RadialScale {
+    /// Where `dist` falls across the rings. `ring_spacing` is only used by
+    /// [`RadialScale::Linear`].
+    pub fn position(&self, dist: f32, ring_spacing: f32) -> f32 {
+        match self {
+            RadialScale::Linear => dist / ring_spacing,
+            RadialScale::Log { near, .. } if dist < *near => dist / near,
+            RadialScale::Log { near, ratio } => 1.0 + (dist / near).ln() / ratio.ln(),
+            RadialScale::Breakpoints(points) => points.position(dist),
+        }
+    }
+
+    /// The distance at ring position `position`; the inverse of [`RadialScale::position`].
+    pub fn distance(&self, position: f32, ring_spacing: f32) -> f32 {
+        match self {
+            RadialScale::Linear => position * ring_spacing,
+            RadialScale::Log { near, .. } if position < 1.0 => position * near,
+            RadialScale::Log { near, ratio } => near * ratio.powf(position - 1.0),
+            RadialScale::Breakpoints(points) => points.distance(position),
+        }
+    }
+}
+
+impl FromStr for RadialScale {
+    type Err = String;
+
+    /// `linear`, `log:NEAR[,RATIO]` (ratio defaults to 10) or
+    /// `breakpoints:DIST:POSITION,DIST:POSITION,...`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, params) = s.split_once(':').unwrap_or((s, ""));
+        let number = |v: &str| {
+            v.trim()
+                .parse::<f32>()
+                .map_err(|e| format!("{:?}: {}", v, e))
+        };
+        match kind {
+            "linear" if params.is_empty() => Ok(RadialScale::Linear),
+            "log" => {
+                let mut values = params.split(',');
+                let near = number(values.next().unwrap_or(""))?;
+                let ratio = values.next().map(number).transpose()?.unwrap_or(10.0);
+                if values.next().is_some() || near <= 0.0 || ratio <= 1.0 {
+                    return Err(format!(
+                        "expected log:NEAR[,RATIO] with NEAR > 0 and RATIO > 1, got {:?}",
+                        s
+                    ));
+                }
+                Ok(RadialScale::Log { near, ratio })
+            }
+            "breakpoints" => {
+                let points = params
+                    .split(',')
+                    .map(|point| match point.split_once(':') {
+                        Some((dist, position)) => Ok((number(dist)?, number(position)?)),
+                        None => Err(format!("expected DIST:POSITION, got {:?}", point)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Breakpoints::new(&points).map(RadialScale::Breakpoints)
+            }
+            _ => Err(format!(
+                "expected linear, log:NEAR[,RATIO] or breakpoints:DIST:POSITION,..., got {:?}",
+                s
+            )),
+        }
+    }
+}
+
+/// As the string [`RadialScale::from_str`] reads.
+impl Serialize for RadialScale {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RadialScale {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl fmt::Display for RadialScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RadialScale::Linear => write!(f, "linear"),
+            RadialScale::Log { near, ratio } => write!(f, "log:{},{}", near, ratio),
+            RadialScale::Breakpoints(points) => {
+                write!(f, "breakpoints:")?;
+                for (i, (dist, position)) in points.points().iter().enumerate() {
+                    let sep = if i == 0 { "" } else { "," };
+                    write!(f, "{}{}:{}", sep, dist, position)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The points of a [`RadialScale::Breakpoints`] scale, increasing in both distance and
+/// position. Beyond the last point the last segment's slope continues.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakpoints {
+    points: [(f32, f32); MAX_BREAKPOINTS],
+    len: usize,
+}
+
+impl Breakpoints {
+    /// At least two and at most [`MAX_BREAKPOINTS`] `(distance, position)` points,
+    /// strictly increasing in both.
+    pub fn new(points: &[(f32, f32)]) -> Result<Self, String> {
+        if points.len() < 2 || points.len() > MAX_BREAKPOINTS {
+            return Err(format!(
+                "expected 2 to {} breakpoints, got {}",
+                MAX_BREAKPOINTS,
+                points.len()
+            ));
+        }
+        if let Some(pair) = points
+            .windows(2)
+            .find(|pair| pair[1].0 <= pair[0].0 || pair[1].1 <= pair[0].1)
+        {
+            return Err(format!(
+                "breakpoints must increase, got {:?} then {:?}",
+                pair[0], pair[1]
+            ));
+        }
+        let mut breakpoints = Breakpoints {
+            points: [(0.0, 0.0); MAX_BREAKPOINTS],
+            len: points.len(),
+        };
+        breakpoints.points[..points.len()].copy_from_slice(points);
+        Ok(breakpoints)
+    }
+
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points[..self.len]
+    }
+
+    fn position(&self, dist: f32) -> f32 {
+        let (from, to) = self.segment(|(d, _)| d, dist);
+        from.1 + (dist - from.0) * (to.1 - from.1) / (to.0 - from.0)
+    }
+
+    fn distance(&self, position: f32) -> f32 {
+        let (from, to) = self.segment(|(_, p)| p, position);
+        from.0 + (position - from.1) * (to.0 - from.0) / (to.1 - from.1)
+    }
+
+    /// The segment whose `key` range holds `value`, or the first or last one outside
+    /// the points.
+    fn segment(&self, key: impl Fn((f32, f32)) -> f32, value: f32) -> ((f32, f32), (f32, f32)) {
+        let points = self.points();
+        let i = points[1..self.len - 1]
+            .iter()
+            .take_while(|&&p| key(p) < value)
+            .count();
+        (points[i], points[i + 1])
+    }
+}
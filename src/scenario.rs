@@ -0,0 +1,319 @@
+use std::f32::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bevy::math::Vec2;
+use clap::ValueEnum;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::alerts::AlertRule;
+use crate::bodies::BodyConfig;
+use crate::coverage::CoverageVolume;
+use crate::geo::Polar;
+use crate::origins::SensorOrigin;
+use crate::polyline::GeoPolyline;
+use crate::region::GeoRegion;
+use crate::route::GeoRoute;
+use crate::target::{GeoPoint, Target, Velocity};
+
+/// Speed of the formations in the crossing preset, in distance units per second.
+const CROSSING_SPEED: f32 = 2.0;
+
+#[derive(Debug, Error)]
+pub enum ScenarioError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[cfg(not(feature = "web"))]
+    #[error("failed to fetch {0}: {1}")]
+    Fetch(String, #[source] Box<ureq::Error>),
+    #[cfg(feature = "web")]
+    #[error("failed to fetch {0}: {1}")]
+    Fetch(String, #[source] crate::web::FetchError),
+    #[error("failed to parse {0}: {1}")]
+    Parse(String, #[source] serde_json::Error),
+    #[error("unsupported data source {0:?}, expected a path, file:// or http(s):// URL")]
+    UnsupportedSource(String),
+}
+
+/// A set of targets to display, as stored in a scenario file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub targets: Vec<Target>,
+    #[serde(default)]
+    pub geo: Vec<GeoPoint>,
+    /// Rules for [`AlertsPlugin`](crate::alerts::AlertsPlugin).
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    /// Where the sensors are, for the globe view.
+    #[serde(default)]
+    pub own_ship: Option<GeoPoint>,
+    /// Sensor coverage around [`Scenario::own_ship`], ranges in meters.
+    #[serde(default)]
+    pub coverage: Vec<CoverageVolume>,
+    /// Moons and other bodies orbiting the globe in the globe view.
+    #[serde(default)]
+    pub bodies: Vec<BodyConfig>,
+    /// Areas highlighted on the globe in the globe view.
+    #[serde(default)]
+    pub regions: Vec<GeoRegion>,
+    /// Great-circle routes drawn on the globe in the globe view.
+    #[serde(default)]
+    pub routes: Vec<GeoRoute>,
+    /// Lines through any number of points drawn on the globe in the globe view.
+    #[serde(default)]
+    pub polylines: Vec<GeoPolyline>,
+    /// Pairs of target ids connected in the ring display, see
+    /// [`TargetLink`](crate::links::TargetLink).
+    #[serde(default)]
+    pub links: Vec<[i32; 2]>,
+    /// Sensor origins of the ring display, see
+    /// [`SensorOrigins`](crate::origins::SensorOrigins); one at the world origin when
+    /// empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub origins: Vec<SensorOrigin>,
+}
+
+impl Scenario {
+    /// Reads a scenario file; with the `web` feature, which has no files, the path is
+    /// fetched relative to the page instead.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        #[cfg(not(feature = "web"))]
+        let json = fs::read_to_string(path).map_err(|e| ScenarioError::Io(name.clone(), e))?;
+        #[cfg(feature = "web")]
+        let json = fetch(&name)?;
+        Self::from_json(&name, &json)
+    }
+
+    /// Writes the scenario as indented JSON, as [`Scenario::from_file`] reads it.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Loads a scenario from a plain path, a `file://` URL or an `http(s)://` URL.
+    pub fn from_source(source: &str) -> Result<Self, ScenarioError> {
+        if let Some(path) = source.strip_prefix("file://") {
+            Self::from_file(path)
+        } else if source.starts_with("http://") || source.starts_with("https://") {
+            Self::from_json(source, &fetch(source)?)
+        } else if source.contains("://") {
+            Err(ScenarioError::UnsupportedSource(source.to_string()))
+        } else {
+            Self::from_file(source)
+        }
+    }
+
+    /// Builds the built-in preset called `name`, see [`Preset`].
+    pub fn preset(name: &str, seed: u64) -> Option<Self> {
+        Preset::from_str(name, true).ok().map(|p| p.build(seed))
+    }
+
+    /// Appends an operator note to target `id`. Returns `false` if there is no such
+    /// target. Notes are saved with the scenario.
+    pub fn add_note(&mut self, id: i32, note: impl Into<String>) -> bool {
+        match self.targets.iter_mut().find(|t| t.id == id) {
+            Some(target) => {
+                target.notes.push(note.into());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes all notes from target `id`.
+    pub fn clear_notes(&mut self, id: i32) {
+        if let Some(target) = self.targets.iter_mut().find(|t| t.id == id) {
+            target.notes.clear();
+        }
+    }
+
+    fn from_json(name: &str, json: &str) -> Result<Self, ScenarioError> {
+        serde_json::from_str(json).map_err(|e| ScenarioError::Parse(name.to_string(), e))
+    }
+}
+
+#[cfg(not(feature = "web"))]
+fn fetch(url: &str) -> Result<String, ScenarioError> {
+    ureq::get(url)
+        .call()
+        .map_err(|e| ScenarioError::Fetch(url.to_string(), Box::new(e)))?
+        .into_string()
+        .map_err(|e| ScenarioError::Io(url.to_string(), e))
+}
+
+#[cfg(feature = "web")]
+fn fetch(url: &str) -> Result<String, ScenarioError> {
+    let bytes = crate::web::fetch(url).map_err(|e| ScenarioError::Fetch(url.to_string(), e))?;
+    String::from_utf8(bytes).map_err(|e| {
+        ScenarioError::Io(
+            url.to_string(),
+            io::Error::new(io::ErrorKind::InvalidData, e),
+        )
+    })
+}
+
+/// Built-in scenarios for demos and reproducible bug reports.
+///
+/// The same preset and seed always produce the same scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// Many targets packed around a single bearing.
+    Cluster,
+    /// Targets spread uniformly over all bearings and distances.
+    Swarm,
+    /// Two line formations crossing each other near the origin.
+    Crossing,
+    /// Geodetic points concentrated around both poles and the antimeridian.
+    Polar,
+}
+
+impl Preset {
+    pub fn build(self, seed: u64) -> Scenario {
+        let mut rng = StdRng::seed_from_u64(seed);
+        match self {
+            Preset::Cluster => {
+                let bearing = rng.gen_range(0.0, PI * 2.0);
+                let targets = (0..30)
+                    .map(|id| {
+                        let azimuth = bearing + rng.gen_range(-0.05, 0.05);
+                        target(id, azimuth, rng.gen_range(20.0, 90.0))
+                    })
+                    .collect();
+                Scenario {
+                    targets,
+                    ..Default::default()
+                }
+            }
+            Preset::Swarm => {
+                let targets = (0..60)
+                    .map(|id| target(id, rng.gen_range(0.0, PI * 2.0), rng.gen_range(10.0, 100.0)))
+                    .collect();
+                Scenario {
+                    targets,
+                    ..Default::default()
+                }
+            }
+            Preset::Crossing => {
+                let heading = rng.gen_range(0.0, PI);
+                let mut targets = Vec::new();
+                for (formation, angle) in [heading, heading + PI / 2.0].iter().enumerate() {
+                    let (dy, dx) = angle.sin_cos();
+                    let (ny, nx) = (-dx, dy);
+                    let offset = rng.gen_range(-15.0, 15.0);
+                    for i in 0..12 {
+                        let along = -66.0 + 12.0 * i as f32;
+                        let at = Vec2::new(dx * along + nx * offset, dy * along + ny * offset);
+                        let Polar { azimuth, dist } = Polar::from_cartesian(at);
+                        let mut t = target((formation * 12 + i) as i32, azimuth, dist.max(10.0));
+                        t.velocity = Some(Velocity {
+                            course: *angle,
+                            speed: CROSSING_SPEED,
+                        });
+                        targets.push(t);
+                    }
+                }
+                Scenario {
+                    targets,
+                    ..Default::default()
+                }
+            }
+            Preset::Polar => {
+                let geo = (0..48)
+                    .map(|id| {
+                        let (lat, lon) = match id % 3 {
+                            0 => (rng.gen_range(70.0, 90.0), rng.gen_range(-180.0, 180.0)),
+                            1 => (rng.gen_range(-90.0, -70.0), rng.gen_range(-180.0, 180.0)),
+                            _ => (
+                                rng.gen_range(-60.0, 60.0),
+                                180.0 - rng.gen_range(0.0, 10.0_f32),
+                            ),
+                        };
+                        let lon = if id % 2 == 0 { lon } else { -lon };
+                        GeoPoint {
+                            id,
+                            text: format!("{}", id),
+                            lat,
+                            lon,
+                            alt: 0.0,
+                        }
+                    })
+                    .collect();
+                Scenario {
+                    geo,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+}
+
+/// Random targets for demos and regression tests. The same settings and seed always
+/// produce the same targets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RandomTargets {
+    pub count: usize,
+    pub min_dist: f32,
+    pub max_dist: f32,
+    /// Bearings the targets gather around, chosen at random; `0` spreads them over
+    /// all bearings.
+    pub clusters: usize,
+    /// How far, in radians, targets stray either side of their cluster's bearing.
+    pub spread: f32,
+}
+
+impl Default for RandomTargets {
+    fn default() -> Self {
+        RandomTargets {
+            count: 20,
+            min_dist: 10.0,
+            max_dist: 100.0,
+            clusters: 0,
+            spread: 0.1,
+        }
+    }
+}
+
+impl RandomTargets {
+    pub fn generate(&self, seed: u64) -> Vec<Target> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let bearings = (0..self.clusters)
+            .map(|_| rng.gen_range(0.0, PI * 2.0))
+            .collect::<Vec<f32>>();
+        // `gen_range` needs a non-empty range.
+        let dist = |rng: &mut StdRng| {
+            if self.max_dist > self.min_dist {
+                rng.gen_range(self.min_dist, self.max_dist)
+            } else {
+                self.min_dist
+            }
+        };
+        (0..self.count)
+            .map(|id| {
+                let azimuth = match bearings.choose(&mut rng) {
+                    Some(&bearing) if self.spread > 0.0 => {
+                        bearing + rng.gen_range(-self.spread, self.spread)
+                    }
+                    Some(&bearing) => bearing,
+                    None => rng.gen_range(0.0, PI * 2.0),
+                };
+                target(id as i32, azimuth, dist(&mut rng))
+            })
+            .collect()
+    }
+}
+
+fn target(id: i32, azimuth: f32, dist: f32) -> Target {
+    Target {
+        id,
+        text: format!("{}", id),
+        azimuth: azimuth.rem_euclid(PI * 2.0),
+        dist,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::cluster::TargetCluster;
+use crate::display::{Poi, PresetLayouts, Slot};
+use crate::events::DisplayEvent;
+use crate::layout::{LayoutConfig, Ring, RingLayout};
+use crate::origins::{SensorOrigin, SensorOrigins};
+use crate::selection::Selected;
+use crate::target::Target;
+
+/// Where a target was placed when its [`DisplayScene`] was saved: its ring and the
+/// azimuth and radius it was drawn at around its origin, as in its [`Slot`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SceneSlot {
+    pub id: i32,
+    #[serde(default)]
+    pub origin: usize,
+    pub ring: usize,
+    pub azimuth: f32,
+    pub radius: f32,
+}
+
+impl From<&Slot> for SceneSlot {
+    fn from(slot: &Slot) -> Self {
+        SceneSlot {
+            id: slot.id,
+            origin: slot.origin,
+            ring: slot.ring,
+            azimuth: slot.azimuth,
+            radius: slot.radius,
+        }
+    }
+}
+
+/// A moment of the ring display to reopen later or attach to a bug report: the
+/// targets, the ring slots they had, the selection, the layout config and the sensor
+/// origins. Saved as RON for `.ron` files and JSON otherwise.
+///
+/// Since targets are laid out incrementally their slots depend on the order they came
+/// in, which a fresh layout of the same targets would not reproduce, so the slots are
+/// saved too and [`DisplayScene::layouts`] puts everyone back in them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DisplayScene {
+    #[serde(default)]
+    pub config: LayoutConfig,
+    /// The [`SensorOrigins`]; the default single origin when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub origins: Vec<SensorOrigin>,
+    #[serde(default)]
+    pub targets: Vec<Target>,
+    #[serde(default)]
+    pub slots: Vec<SceneSlot>,
+    /// Id of the selected target.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected: Option<i32>,
+}
+
+fn is_ron(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ron"))
+}
+
+impl DisplayScene {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        if is_ron(path) {
+            ron::de::from_str(&text).map_err(|e| invalid(e.to_string()))
+        } else {
+            serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))
+        }
+    }
+
+    /// Writes to a temporary file first and renames it over `path`, like
+    /// [`SessionState::save`](crate::persist::SessionState::save).
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text = if is_ron(path) {
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())
+                .map_err(io::Error::other)?
+        } else {
+            serde_json::to_string_pretty(self).map_err(io::Error::other)?
+        };
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, path)
+    }
+
+    /// The markers of the display as they are placed: the targets and their slots,
+    /// with no selection or origins.
+    pub fn placed<'a>(
+        config: LayoutConfig,
+        placed: impl Iterator<Item = (&'a Target, &'a Slot)>,
+    ) -> Self {
+        let (targets, slots) = placed
+            .map(|(target, slot)| (target.clone(), SceneSlot::from(slot)))
+            .unzip();
+        DisplayScene {
+            config,
+            targets,
+            slots,
+            ..Default::default()
+        }
+    }
+
+    pub fn sensor_origins(&self) -> SensorOrigins {
+        if self.origins.is_empty() {
+            SensorOrigins::default()
+        } else {
+            SensorOrigins(self.origins.clone())
+        }
+    }
+
+    /// The layout of each origin with the targets in their saved slots, for
+    /// [`PresetLayouts`]. Targets without a slot are left for the display to place.
+    pub fn layouts(&self) -> BTreeMap<usize, RingLayout> {
+        let targets = self
+            .targets
+            .iter()
+            .map(|t| (t.id, t))
+            .collect::<BTreeMap<_, _>>();
+        let mut by_origin = BTreeMap::<usize, Vec<&SceneSlot>>::new();
+        for slot in &self.slots {
+            by_origin.entry(slot.origin).or_default().push(slot);
+        }
+        by_origin
+            .into_iter()
+            .map(|(origin, slots)| {
+                let rings = slots.iter().map(|s| s.ring + 1).max().unwrap_or(0);
+                let mut layout =
+                    RingLayout::from_rings(self.config, vec![Ring::new(); rings], Vec::new());
+                if self.config.adaptive.is_some() {
+                    // Rings left without a marker are taken to be spaced uniformly.
+                    let mut radii = vec![None; rings];
+                    for slot in &slots {
+                        radii[slot.ring] = Some(slot.radius);
+                    }
+                    let mut radius = 0.0;
+                    layout.radii = radii
+                        .into_iter()
+                        .map(|r| {
+                            radius = r.unwrap_or(radius + self.config.ring_spacing);
+                            radius
+                        })
+                        .collect();
+                }
+                for slot in slots {
+                    if let Some(&target) = targets.get(&slot.id) {
+                        // Slots hold where markers are drawn, turned by the ring's offset.
+                        let azimuth = slot.azimuth - layout.ring_offset(slot.ring);
+                        layout.rings[slot.ring].insert(azimuth, target.clone());
+                    }
+                }
+                layout.reindex();
+                (origin, layout)
+            })
+            .collect()
+    }
+}
+
+/// Saves the ring display as a [`DisplayScene`] to `path` on [`Action::SaveScene`],
+/// Ctrl+S by default, and puts a
+/// `restored` one back: its targets into their saved slots through [`PresetLayouts`]
+/// and its selection through a [`DisplayEvent::Clicked`] once the target is placed.
+/// The app spawns the restored targets and takes its config and origins itself. Needs
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin) and
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin).
+pub struct ScenePlugin {
+    pub path: PathBuf,
+    pub restored: Option<DisplayScene>,
+}
+
+struct SceneFile(PathBuf);
+
+/// The selection of a restored scene, until its target has a marker.
+struct PendingSelection(Option<i32>);
+
+impl Plugin for ScenePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if let Some(scene) = &self.restored {
+            app.add_resource(PresetLayouts(Some(scene.layouts())));
+        }
+        app.add_resource(SceneFile(self.path.clone()))
+            .add_resource(PendingSelection(
+                self.restored.as_ref().and_then(|scene| scene.selected),
+            ))
+            .add_system(scene_save_system.system())
+            .add_system(restore_selection_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn scene_save_system(
+    actions: Res<ActionState>,
+    file: Res<SceneFile>,
+    config: Res<LayoutConfig>,
+    origins: Res<SensorOrigins>,
+    targets: Query<(
+        &Target,
+        Option<&Slot>,
+        Option<&Selected>,
+        Option<&TargetCluster>,
+    )>,
+) {
+    if !actions.just_pressed(Action::SaveScene) {
+        return;
+    }
+    let mut scene = DisplayScene {
+        config: *config,
+        origins: origins.0.clone(),
+        ..Default::default()
+    };
+    for (target, slot, selected, cluster) in targets.iter() {
+        // Merged targets are saved on their own and merge again once restored.
+        if let Some(cluster) = cluster {
+            scene.targets.extend(cluster.members.iter().cloned());
+            continue;
+        }
+        scene.targets.push(target.clone());
+        scene.slots.extend(slot.map(SceneSlot::from));
+        if selected.is_some() {
+            scene.selected = Some(target.id);
+        }
+    }
+    scene.targets.sort_by_key(|t| t.id);
+    scene.slots.sort_by_key(|s| s.id);
+    match scene.save(&file.0) {
+        Ok(()) => tracing::info!("saved scene to {}", file.0.display()),
+        Err(e) => tracing::error!("failed to save scene to {}: {}", file.0.display(), e),
+    }
+}
+
+fn restore_selection_system(
+    mut pending: ResMut<PendingSelection>,
+    mut events: ResMut<Events<DisplayEvent>>,
+    placed: Query<With<Poi, &Target>>,
+) {
+    let id = match pending.0 {
+        Some(id) => id,
+        None => return,
+    };
+    if let Some(target) = placed.iter().find(|t| t.id == id) {
+        events.send(DisplayEvent::Clicked {
+            screen: Vec2::zero(),
+            world: None,
+            target: Some(id),
+            origin: Some(target.origin),
+        });
+        pending.0 = None;
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::display::Slot;
+use crate::emphasis::{Emphasis, TargetEmphasis};
+use crate::events::DisplayEvent;
+use crate::motion::{LeaderLine, PolarTween};
+use crate::target::Target;
+
+/// Leader line width of the selected target.
+const SELECTED_LEADER_WIDTH: f32 = 3.0;
+
+/// On the entity of the selected target, which is also its marker. Query
+/// `With<Selected, ...>` or watch for it being added and removed to follow the
+/// selection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Selected;
+
+/// Sent by [`SelectionPlugin`] whenever the selection changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetSelected {
+    /// `None` when the selection was cleared.
+    pub target: Option<i32>,
+    /// The [`SensorOrigins`](crate::origins::SensorOrigins) entry of `target`.
+    pub origin: Option<usize>,
+    pub previous: Option<i32>,
+}
+
+/// Asks [`SelectionPlugin`] to select the target with this id, or to clear the
+/// selection with `None`, as clicking its marker would. Lets panels and scripts select
+/// without a click on the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectTarget(pub Option<i32>);
+
+/// Selects the target whose marker square is clicked, clearing the selection on a
+/// click into empty space, or the one a [`SelectTarget`] asks for, which wins over a
+/// click in the same frame. The selected target gets the [`Selected`] component, a
+/// highlighted marker and a thicker leader line, and each change is sent as
+/// [`TargetSelected`]. Picks through the [`DisplayEvent::Clicked`] events of
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin) and highlights through
+/// [`EmphasisPlugin`](crate::emphasis::EmphasisPlugin).
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<TargetEmphasis>() {
+            app.init_resource::<TargetEmphasis>();
+        }
+        app.add_event::<TargetSelected>()
+            .add_event::<SelectTarget>()
+            .add_system(selection_system.system())
+            .add_system(selected_leader_system.system());
+    }
+}
+
+#[derive(Default)]
+struct SelectionState {
+    reader: EventReader<DisplayEvent>,
+    requests: EventReader<SelectTarget>,
+    target: Option<i32>,
+    /// Emphasis the selected target had before it was highlighted.
+    emphasis: Emphasis,
+}
+
+fn selection_system(
+    mut commands: Commands,
+    mut state: Local<SelectionState>,
+    display_events: Res<Events<DisplayEvent>>,
+    requests: Res<Events<SelectTarget>>,
+    mut selected_events: ResMut<Events<TargetSelected>>,
+    mut emphasis: ResMut<TargetEmphasis>,
+    targets: Query<(Entity, &Target)>,
+) {
+    let clicked = state
+        .reader
+        .iter(&display_events)
+        .filter_map(|event| match event {
+            DisplayEvent::Clicked { target, origin, .. } => Some((*target, *origin)),
+            _ => None,
+        })
+        .next_back();
+    let requested = state.requests.iter(&requests).next_back().map(|request| {
+        let origin = request
+            .0
+            .and_then(|id| targets.iter().find(|(_, t)| t.id == id))
+            .map(|(_, t)| t.origin);
+        (request.0, origin)
+    });
+    let (target, origin) = match requested.or(clicked) {
+        Some((target, origin)) if target != state.target => (target, origin),
+        _ => return,
+    };
+    let previous = state.target;
+    if let Some(id) = previous {
+        emphasis.set_target_emphasis(id, state.emphasis);
+    }
+    if let Some(id) = target {
+        state.emphasis = emphasis.emphasis(id);
+        emphasis.set_target_emphasis(id, Emphasis::Highlight);
+    }
+    for (entity, t) in targets.iter() {
+        if Some(t.id) == previous {
+            commands.remove_one::<Selected>(entity);
+        }
+        if Some(t.id) == target {
+            commands.insert_one(entity, Selected);
+        }
+    }
+    state.target = target;
+    selected_events.send(TargetSelected {
+        target,
+        origin,
+        previous,
+    });
+}
+
+// Leader lines are respawned on a full re-layout, so this keeps checking rather than
+// only reacting to selection changes.
+#[allow(clippy::type_complexity)]
+fn selected_leader_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    selected: Query<With<Selected, &Target>>,
+    mut leaders: Query<(
+        &Slot,
+        Mut<LeaderLine>,
+        &PolarTween,
+        &Handle<Mesh>,
+        &Handle<ColorMaterial>,
+    )>,
+) {
+    let selected = selected.iter().next().map(|t| t.id);
+    for (slot, mut leader, tween, mesh, material) in leaders.iter_mut() {
+        let width = if Some(slot.id) == selected {
+            SELECTED_LEADER_WIDTH.max(leader.style.width)
+        } else {
+            leader.style.width
+        };
+        if leader.width != width {
+            leader.width = width;
+            leader.redraw(tween, mesh, material, &mut meshes);
+        }
+    }
+}
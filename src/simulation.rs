@@ -0,0 +1,242 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::geo::Polar;
+use crate::motion::lerp_azimuth;
+use crate::target::{Target, Velocity};
+
+/// Stage [`SimulationPlugin`] runs in, between `PRE_UPDATE` and `UPDATE`.
+pub const SIMULATION: &str = "simulation";
+
+/// The fixed-step clock of [`SimulationPlugin`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simulation {
+    /// Seconds of one step.
+    pub step: f32,
+    /// Whether targets are drawn between their last two steps, or jump from step to
+    /// step.
+    pub interpolate: bool,
+    ticks: u64,
+    /// Seconds of [`AnimationTime`] not yet taken up by a step.
+    accumulator: f32,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Simulation::new(1.0 / 30.0)
+    }
+}
+
+impl Simulation {
+    pub fn new(step: f32) -> Self {
+        Simulation {
+            step,
+            interpolate: true,
+            ticks: 0,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Steps taken so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Simulated seconds so far, a whole number of steps.
+    pub fn seconds(&self) -> f64 {
+        self.ticks as f64 * self.step as f64
+    }
+
+    /// How far into the next step the clock is, from 0 up to 1.
+    pub fn alpha(&self) -> f32 {
+        if self.step > 0.0 {
+            (self.accumulator / self.step).min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Adds `delta` seconds to the clock and returns how many steps it now takes.
+    pub fn advance(&mut self, delta: f32) -> u32 {
+        if self.step <= 0.0 {
+            return 0;
+        }
+        self.accumulator += delta.max(0.0);
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        self.ticks += steps as u64;
+        steps
+    }
+}
+
+/// On a [`Target`], how its course and speed change, for [`SimulationPlugin`] to
+/// steer and speed it up with.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Maneuver {
+    /// Radians per second, counter-clockwise like the azimuth.
+    pub turn_rate: f32,
+    /// Distance units per second, per second.
+    pub acceleration: f32,
+}
+
+/// Where a target is and how it moves at one step, relative to its sensor origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KinematicState {
+    pub position: Vec2,
+    pub course: f32,
+    pub speed: f32,
+}
+
+impl KinematicState {
+    pub fn of(target: &Target) -> Self {
+        let velocity = target.velocity.unwrap_or(Velocity {
+            course: 0.0,
+            speed: 0.0,
+        });
+        KinematicState {
+            position: Polar::new(target.azimuth, target.dist).to_cartesian(),
+            course: velocity.course,
+            speed: velocity.speed,
+        }
+    }
+
+    /// The state `dt` seconds later, moving at this step's course and speed.
+    pub fn step(self, maneuver: Maneuver, dt: f32) -> Self {
+        KinematicState {
+            position: self.position + Polar::new(self.course, self.speed * dt).to_cartesian(),
+            course: (self.course + maneuver.turn_rate * dt).rem_euclid(PI * 2.0),
+            speed: (self.speed + maneuver.acceleration * dt).max(0.0),
+        }
+    }
+
+    /// The state `t` of the way from `self` to `next`.
+    pub fn lerp(self, next: Self, t: f32) -> Self {
+        KinematicState {
+            position: self.position + (next.position - self.position) * t,
+            course: lerp_azimuth(self.course, next.course, t),
+            speed: self.speed + (next.speed - self.speed) * t,
+        }
+    }
+}
+
+/// Where `target` will be after `horizon` seconds at `velocity` by dead reckoning, on
+/// a straight line at a steady speed, as `(azimuth, dist)`.
+pub fn predict(target: &Target, velocity: Velocity, horizon: f32) -> (f32, f32) {
+    let state = KinematicState {
+        course: velocity.course,
+        speed: velocity.speed,
+        ..KinematicState::of(target)
+    };
+    let Polar { azimuth, dist } =
+        Polar::from_cartesian(state.step(Maneuver::default(), horizon).position);
+    (azimuth, dist)
+}
+
+/// The simulated motion of a target: its states at the last two steps, and what was
+/// last written to its [`Target`], to tell the simulation's own writes from reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Kinematics {
+    pub previous: KinematicState,
+    pub current: KinematicState,
+    written: (f32, f32, Option<Velocity>),
+}
+
+impl Kinematics {
+    fn of(target: &Target) -> Self {
+        let state = KinematicState::of(target);
+        Kinematics {
+            previous: state,
+            current: state,
+            written: (target.azimuth, target.dist, target.velocity),
+        }
+    }
+}
+
+/// Moves every [`Target`] with a [`Velocity`] along it in fixed steps of
+/// [`Simulation::step`], turning and speeding up by its [`Maneuver`] if it has one,
+/// so the same run of [`AnimationTime`] moves targets the same way whatever the frame
+/// rate. Targets are drawn between their last two steps unless
+/// [`Simulation::interpolate`] is off. A target changed by anything else, such as a
+/// feed report or a replayed event, starts again from where that put it.
+pub struct SimulationPlugin {
+    pub step: f32,
+}
+
+impl Default for SimulationPlugin {
+    fn default() -> Self {
+        SimulationPlugin {
+            step: Simulation::default().step,
+        }
+    }
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Simulation>() {
+            app.add_resource(Simulation::new(self.step));
+        }
+        app.add_plugin(AnimationTimePlugin)
+            .add_stage_after(stage::PRE_UPDATE, SIMULATION)
+            .add_system_to_stage(SIMULATION, simulation_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn simulation_system(
+    mut commands: Commands,
+    time: Res<AnimationTime>,
+    mut simulation: ResMut<Simulation>,
+    mut targets: Query<(
+        Entity,
+        Mut<Target>,
+        Option<Mut<Kinematics>>,
+        Option<&Maneuver>,
+    )>,
+) {
+    let steps = simulation.advance(time.delta_seconds());
+    let (dt, alpha) = (simulation.step, simulation.alpha());
+    for (entity, mut target, kinematics, maneuver) in targets.iter_mut() {
+        let mut kinematics = match kinematics {
+            Some(kinematics) => kinematics,
+            None => {
+                if target.velocity.is_some() {
+                    commands.insert_one(entity, Kinematics::of(&target));
+                }
+                continue;
+            }
+        };
+        if kinematics.written != (target.azimuth, target.dist, target.velocity) {
+            *kinematics = Kinematics::of(&target);
+            continue;
+        }
+        if target.velocity.is_none() {
+            continue;
+        }
+        let maneuver = maneuver.copied().unwrap_or_default();
+        for _ in 0..steps {
+            kinematics.previous = kinematics.current;
+            kinematics.current = kinematics.current.step(maneuver, dt);
+        }
+        let drawn = if simulation.interpolate {
+            kinematics.previous.lerp(kinematics.current, alpha)
+        } else {
+            kinematics.current
+        };
+        let Polar { azimuth, dist } = Polar::from_cartesian(drawn.position);
+        let velocity = Some(Velocity {
+            course: drawn.course,
+            speed: drawn.speed,
+        });
+        if (azimuth, dist, velocity) != kinematics.written {
+            target.azimuth = azimuth;
+            target.dist = dist;
+            target.velocity = velocity;
+            kinematics.written = (azimuth, dist, velocity);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::render::pipeline::PrimitiveTopology;
+use bevy::render::render_graph::base::camera::CAMERA3D;
+use bevy::render::texture::TextureFormat;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::mesh::sphere_mesh;
+
+/// Distance of the sky from the camera: half bevy's default far plane, so that the
+/// corners of a [`SkyCubemap`] stay inside it and everything in the scene in front.
+const SKY_RADIUS: f32 = 500.0;
+/// Rows of the starfield's sphere, which has four times as many columns.
+const STARFIELD_ROWS: u32 = 32;
+/// Brightness above which a star spills into the texels around it.
+const GLOW_THRESHOLD: f32 = 0.6;
+
+/// Star colors from cool red to hot blue, picked between at random.
+const STAR_TINTS: [[f32; 3]; 5] = [
+    [1.0, 0.75, 0.6],
+    [1.0, 0.9, 0.75],
+    [1.0, 1.0, 1.0],
+    [0.85, 0.9, 1.0],
+    [0.7, 0.8, 1.0],
+];
+
+/// A backdrop that stays put as the camera moves, so turning and zooming the view has
+/// something to be seen against. Spawn it on an entity of its own; [`SkyPlugin`] keeps
+/// it centred on the 3D camera, too far off for anything in the scene to go behind it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sky {
+    Starfield(Starfield),
+    Cubemap(SkyCubemap),
+}
+
+/// A night sky of random stars, painted by [`Starfield::generate`] into an
+/// equirectangular texture. The same seed always gives the same sky.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Starfield {
+    pub seed: u64,
+    pub count: u32,
+    /// Width of the texture in texels; it is half as tall.
+    pub width: u32,
+}
+
+impl Default for Starfield {
+    fn default() -> Self {
+        Starfield {
+            seed: 0,
+            count: 8000,
+            width: 4096,
+        }
+    }
+}
+
+impl Starfield {
+    pub fn generate(&self) -> Texture {
+        let (width, height) = (self.width.max(2) as usize, (self.width / 2).max(1) as usize);
+        let mut colors = vec![[0.0f32; 3]; width * height];
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        for _ in 0..self.count {
+            // Evenly over the sphere, so stars don't bunch up at the poles.
+            let z: f32 = rng.gen_range(-1.0, 1.0);
+            let lon: f32 = rng.gen_range(0.0, 2.0 * PI);
+            let x = (lon / (2.0 * PI) * width as f32) as usize % width;
+            let y = ((z.asin() / PI + 0.5) * height as f32).min(height as f32 - 1.0) as usize;
+            // Most stars are faint, a few bright.
+            let brightness = rng.gen::<f32>().powi(6) * 0.9 + 0.1;
+            let tint = STAR_TINTS[rng.gen_range(0, STAR_TINTS.len())];
+            let mut add = |x: usize, y: usize, share: f32| {
+                let texel = &mut colors[y * width + x];
+                for (channel, tint) in texel.iter_mut().zip(&tint) {
+                    *channel += tint * brightness * share;
+                }
+            };
+            add(x, y, 1.0);
+            if brightness > GLOW_THRESHOLD {
+                for (dx, dy) in [(1, 0), (width - 1, 0), (0, 1), (0, height - 1)] {
+                    add((x + dx) % width, ((y + dy) % height).min(height - 1), 0.3);
+                }
+            }
+        }
+        let data = colors
+            .iter()
+            .flat_map(|texel| {
+                let [r, g, b] = texel.map(|c| (c.min(1.0) * 255.0).round() as u8);
+                [r, g, b, 255]
+            })
+            .collect();
+        Texture::new(
+            Vec2::new(width as f32, height as f32),
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+}
+
+/// Six images, asset paths in the order +x, -x, +y, -y, +z, -z, on the faces of a cube
+/// around the scene. Each is as seen from inside looking along its axis: the sides
+/// upright with +y up, and the top and bottom as reached by looking up or down from
+/// facing -z.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkyCubemap {
+    pub faces: [String; 6],
+}
+
+impl SkyCubemap {
+    /// The faces `px`, `nx`, `py`, `ny`, `pz` and `nz` with extension `extension` in
+    /// the asset directory `dir`.
+    pub fn from_dir(dir: &str, extension: &str) -> Self {
+        let dir = dir.trim_end_matches('/');
+        let face = |name: &str| format!("{}/{}.{}", dir, name, extension);
+        SkyCubemap {
+            faces: [
+                face("px"),
+                face("nx"),
+                face("py"),
+                face("ny"),
+                face("pz"),
+                face("nz"),
+            ],
+        }
+    }
+}
+
+/// Which way each face of a [`SkyCubemap`] looks and which way is up in its image, in
+/// the faces' order.
+const CUBE_FACES: [([f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, -1.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+];
+
+/// A square `radius` out along `forward`, facing back towards the origin, with its
+/// image's top towards `up`.
+fn face_mesh(forward: Vec3, up: Vec3, radius: f32) -> Mesh {
+    let right = forward.cross(up);
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+    let positions = corners
+        .iter()
+        .map(|&(x, y)| <[f32; 3]>::from((forward + right * x + up * y) * radius))
+        .collect::<Vec<_>>();
+    let normals = vec![<[f32; 3]>::from(-forward); 4];
+    let uvs = corners
+        .iter()
+        .map(|&(x, y)| [(x + 1.0) / 2.0, (1.0 - y) / 2.0])
+        .collect::<Vec<_>>();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into());
+    mesh.set_indices(Some(Indices::U32(vec![0, 1, 2, 0, 2, 3])));
+    mesh
+}
+
+/// `mesh` turned inside out, to be seen from within: its triangles wound the other
+/// way and its normals reversed.
+fn inside_out(mut mesh: Mesh) -> Mesh {
+    if let Some(Indices::U32(indices)) = mesh.indices() {
+        let mut indices = indices.clone();
+        for triangle in indices.chunks_exact_mut(3) {
+            triangle.swap(1, 2);
+        }
+        mesh.set_indices(Some(Indices::U32(indices)));
+    }
+    if let Some(VertexAttributeValues::Float3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        let normals = normals
+            .iter()
+            .map(|normal| normal.map(|n| -n))
+            .collect::<Vec<_>>();
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+    }
+    mesh
+}
+
+/// Marks a [`Sky`] whose meshes have been spawned.
+struct SkyDrawn;
+
+/// Draws each [`Sky`] and keeps it centred on the 3D camera.
+pub struct SkyPlugin;
+
+impl Plugin for SkyPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(sky_draw_system.system())
+            .add_system(sky_follow_system.system());
+    }
+}
+
+fn sky_draw_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    skies: Query<Without<SkyDrawn, (Entity, &Sky)>>,
+) {
+    for (entity, sky) in skies.iter() {
+        let parts = match sky {
+            Sky::Starfield(starfield) => vec![(
+                meshes.add(inside_out(sphere_mesh(
+                    SKY_RADIUS,
+                    STARFIELD_ROWS,
+                    4 * STARFIELD_ROWS,
+                ))),
+                textures.add(starfield.generate()),
+            )],
+            Sky::Cubemap(cubemap) => CUBE_FACES
+                .iter()
+                .zip(&cubemap.faces)
+                .map(|(&(forward, up), path)| {
+                    (
+                        meshes.add(face_mesh(forward.into(), up.into(), SKY_RADIUS)),
+                        asset_server.load(path.as_str()),
+                    )
+                })
+                .collect(),
+        };
+        let children = parts
+            .into_iter()
+            .filter_map(|(mesh, texture)| {
+                commands
+                    .spawn(PbrComponents {
+                        mesh,
+                        material: materials.add(StandardMaterial {
+                            albedo_texture: Some(texture),
+                            shaded: false,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    })
+                    .current_entity()
+            })
+            .collect::<Vec<_>>();
+        commands.insert(
+            entity,
+            (SkyDrawn, Transform::default(), GlobalTransform::default()),
+        );
+        commands.push_children(entity, &children);
+    }
+}
+
+fn sky_follow_system(
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut skies: Query<With<Sky, &mut Transform>>,
+) {
+    let eye = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some((_, eye)) => eye.translation,
+        None => return,
+    };
+    for mut transform in skies.iter_mut() {
+        if transform.translation != eye {
+            transform.translation = eye;
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::motion::shortest_arc;
+use crate::target::Target;
+
+/// Smoothing applied to noisy azimuth/range reports before they reach the layout, set
+/// by [`LayoutConfig::smoothing`](crate::layout::LayoutConfig::smoothing).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmoothingFilter {
+    /// Exponential moving average; `alpha` in `(0, 1]` is the weight of each new report.
+    Ema { alpha: f32 },
+    /// Alpha-beta tracker: `alpha` corrects the position and `beta` the rate estimate.
+    /// Follows steadily moving targets without the lag of a plain average.
+    AlphaBeta { alpha: f32, beta: f32 },
+}
+
+impl Default for SmoothingFilter {
+    fn default() -> Self {
+        SmoothingFilter::AlphaBeta {
+            alpha: 0.5,
+            beta: 0.1,
+        }
+    }
+}
+
+impl SmoothingFilter {
+    /// Checks that every gain is within `(0, 1]`; outside it the filter would ignore
+    /// reports or overshoot them.
+    pub fn validate(&self) -> Result<(), String> {
+        let gains = match *self {
+            SmoothingFilter::Ema { alpha } => vec![("alpha", alpha)],
+            SmoothingFilter::AlphaBeta { alpha, beta } => vec![("alpha", alpha), ("beta", beta)],
+        };
+        for (name, gain) in gains {
+            if !(gain > 0.0 && gain <= 1.0) {
+                return Err(format!(
+                    "smoothing {} must be within (0, 1], got {}",
+                    name, gain
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Filter state for one target.
+#[derive(Debug, Clone, Copy)]
+struct TrackState {
+    time: f64,
+    azimuth: f32,
+    dist: f32,
+    azimuth_rate: f32,
+    dist_rate: f32,
+}
+
+impl TrackState {
+    fn update(&mut self, filter: SmoothingFilter, time: f64, azimuth: f32, dist: f32) {
+        let dt = (time - self.time).max(0.0) as f32;
+        self.time = time;
+        match filter {
+            SmoothingFilter::Ema { alpha } => {
+                self.azimuth += alpha * shortest_arc(self.azimuth, azimuth);
+                self.dist += alpha * (dist - self.dist);
+            }
+            SmoothingFilter::AlphaBeta { alpha, beta } => {
+                let predicted_azimuth = self.azimuth + self.azimuth_rate * dt;
+                let predicted_dist = self.dist + self.dist_rate * dt;
+                let azimuth_residual = shortest_arc(predicted_azimuth, azimuth);
+                let dist_residual = dist - predicted_dist;
+                self.azimuth = predicted_azimuth + alpha * azimuth_residual;
+                self.dist = predicted_dist + alpha * dist_residual;
+                if dt > 0.0 {
+                    self.azimuth_rate += beta * azimuth_residual / dt;
+                    self.dist_rate += beta * dist_residual / dt;
+                }
+            }
+        }
+        self.azimuth = self.azimuth.rem_euclid(PI * 2.0);
+    }
+}
+
+/// Per-target smoothing of incoming reports, keyed by `Target::id`. As a resource,
+/// [`TargetUpdatesPlugin`](crate::updates::TargetUpdatesPlugin) passes every
+/// [`TargetAdded`](crate::updates::TargetAdded) and
+/// [`TargetChanged`](crate::updates::TargetChanged) through it, with the filter
+/// [`LayoutConfig::smoothing`](crate::layout::LayoutConfig::smoothing) sets.
+#[derive(Debug, Default, Clone)]
+pub struct Smoother {
+    /// `None` passes reports through as they are.
+    pub filter: Option<SmoothingFilter>,
+    tracks: HashMap<i32, TrackState>,
+}
+
+impl Smoother {
+    pub fn new(filter: Option<SmoothingFilter>) -> Self {
+        Smoother {
+            filter,
+            tracks: HashMap::new(),
+        }
+    }
+
+    /// Feeds a report taken at `time` seconds through the target's filter and replaces
+    /// its azimuth and distance with the smoothed estimate. The first report of a target
+    /// passes through unchanged.
+    pub fn smooth(&mut self, target: &mut Target, time: f64) {
+        let filter = match self.filter {
+            Some(filter) => filter,
+            None => return,
+        };
+        let state = self.tracks.entry(target.id).or_insert(TrackState {
+            time,
+            azimuth: target.azimuth,
+            dist: target.dist,
+            azimuth_rate: 0.0,
+            dist_rate: 0.0,
+        });
+        state.update(filter, time, target.azimuth, target.dist);
+        target.azimuth = state.azimuth;
+        target.dist = state.dist.max(0.0);
+    }
+
+    /// Forgets a target, e.g. when it is dropped, so a later one with the same id
+    /// starts fresh.
+    pub fn remove(&mut self, id: i32) {
+        self.tracks.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(azimuth: f32, dist: f32) -> Target {
+        Target {
+            id: 1,
+            azimuth,
+            dist,
+            ..Default::default()
+        }
+    }
+
+    /// Feeds reports of a still target, jittered alternately either side of it, ten a
+    /// second, and returns the largest error of the smoothed azimuth and distance over
+    /// the last few.
+    fn settled_error(filter: SmoothingFilter) -> (f32, f32) {
+        let (azimuth, dist) = (0.02, 500.0);
+        let mut smoother = Smoother::new(Some(filter));
+        let mut errors = (0.0f32, 0.0f32);
+        for i in 0..200 {
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let mut report = target(azimuth + sign * 0.05, dist + sign * 20.0);
+            smoother.smooth(&mut report, i as f64 * 0.1);
+            if i >= 180 {
+                errors.0 = errors.0.max(shortest_arc(azimuth, report.azimuth).abs());
+                errors.1 = errors.1.max((report.dist - dist).abs());
+            }
+        }
+        errors
+    }
+
+    #[test]
+    fn jittered_reports_settle() {
+        for &filter in &[
+            SmoothingFilter::Ema { alpha: 0.2 },
+            SmoothingFilter::default(),
+        ] {
+            let (azimuth, dist) = settled_error(filter);
+            // The jitter is 0.05 rad and 20 either way; across the seam at 0 too.
+            assert!(azimuth < 0.025, "{:?}: azimuth off by {}", filter, azimuth);
+            assert!(dist < 10.0, "{:?}: distance off by {}", filter, dist);
+        }
+    }
+
+    #[test]
+    fn gains_outside_unit_interval_are_rejected() {
+        assert!(SmoothingFilter::default().validate().is_ok());
+        assert!(SmoothingFilter::Ema { alpha: 1.0 }.validate().is_ok());
+        assert!(SmoothingFilter::Ema { alpha: 0.0 }.validate().is_err());
+        assert!(SmoothingFilter::Ema { alpha: f32::NAN }.validate().is_err());
+        let overshoot = SmoothingFilter::AlphaBeta {
+            alpha: 0.5,
+            beta: 1.5,
+        };
+        assert!(overshoot.validate().is_err());
+    }
+
+    #[test]
+    fn without_a_filter_reports_pass_through() {
+        let mut smoother = Smoother::default();
+        for (i, &dist) in [100.0, 140.0, 90.0].iter().enumerate() {
+            let mut report = target(1.0, dist);
+            smoother.smooth(&mut report, i as f64);
+            assert_eq!(report.dist, dist);
+        }
+    }
+}
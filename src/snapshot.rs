@@ -0,0 +1,142 @@
+use bevy::prelude::Color;
+use image::{Rgba, RgbaImage};
+
+use crate::layout::RingLayout;
+use crate::theme::Theme;
+
+const ORIGIN_RADIUS: f32 = 5.0;
+const STROKE_WIDTH: f32 = 1.0;
+
+/// Rasterizes `layout` on the CPU the same way the ring display draws it: origin dot,
+/// reference rings, POI squares and leader lines (labels are not drawn).
+///
+/// World coordinates map one unit to one pixel with the origin at the image center and
+/// y pointing up, like the default 2D camera. The output depends only on the inputs, so
+/// it can be compared byte for byte against a stored golden image.
+pub fn render_layout_to_image(layout: &RingLayout, theme: Theme, size: (u32, u32)) -> RgbaImage {
+    let mut canvas = Canvas::new(size, rgba(theme.background()));
+    let stroke = rgba(theme.stroke());
+    canvas.fill_circle(0.0, 0.0, ORIGIN_RADIUS, stroke);
+    for (ring_ord, ring) in layout.rings.iter().enumerate() {
+        let r = layout.ring_radius(ring_ord);
+        canvas.stroke_circle(0.0, 0.0, r, stroke);
+        for azi in ring.azimuths() {
+            let (x, y) = (r * azi.cos(), r * azi.sin());
+            canvas.line(0.0, 0.0, x, y, stroke);
+            canvas.stroke_square(x, y, layout.poi_width(), stroke);
+        }
+    }
+    canvas.image
+}
+
+fn rgba(color: Color) -> Rgba<u8> {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgba([
+        channel(color.r()),
+        channel(color.g()),
+        channel(color.b()),
+        channel(color.a()),
+    ])
+}
+
+struct Canvas {
+    image: RgbaImage,
+}
+
+impl Canvas {
+    fn new((width, height): (u32, u32), background: Rgba<u8>) -> Self {
+        Canvas {
+            image: RgbaImage::from_pixel(width, height, background),
+        }
+    }
+
+    /// Center of pixel (`px`, `py`) in world coordinates.
+    fn world(&self, px: u32, py: u32) -> (f32, f32) {
+        let (w, h) = self.image.dimensions();
+        (
+            px as f32 + 0.5 - w as f32 / 2.0,
+            h as f32 / 2.0 - (py as f32 + 0.5),
+        )
+    }
+
+    /// Pixel range covering the world-space box, clamped to the image.
+    fn bounds(&self, min: (f32, f32), max: (f32, f32)) -> (u32, u32, u32, u32) {
+        let (w, h) = self.image.dimensions();
+        let (w2, h2) = (w as f32 / 2.0, h as f32 / 2.0);
+        let clamp = |v: f32, hi: u32| v.max(0.0).min(hi as f32) as u32;
+        (
+            clamp((min.0 + w2).floor(), w),
+            clamp((h2 - max.1).floor(), h),
+            clamp((max.0 + w2).ceil() + 1.0, w),
+            clamp((h2 - min.1).ceil() + 1.0, h),
+        )
+    }
+
+    fn paint_where(
+        &mut self,
+        min: (f32, f32),
+        max: (f32, f32),
+        color: Rgba<u8>,
+        inside: impl Fn(f32, f32) -> bool,
+    ) {
+        let (x0, y0, x1, y1) = self.bounds(min, max);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let (x, y) = self.world(px, py);
+                if inside(x, y) {
+                    self.image.put_pixel(px, py, color);
+                }
+            }
+        }
+    }
+
+    fn fill_circle(&mut self, cx: f32, cy: f32, r: f32, color: Rgba<u8>) {
+        self.paint_where((cx - r, cy - r), (cx + r, cy + r), color, |x, y| {
+            (x - cx).hypot(y - cy) <= r
+        });
+    }
+
+    fn stroke_circle(&mut self, cx: f32, cy: f32, r: f32, color: Rgba<u8>) {
+        let half = STROKE_WIDTH / 2.0;
+        let outer = r + half;
+        self.paint_where(
+            (cx - outer, cy - outer),
+            (cx + outer, cy + outer),
+            color,
+            |x, y| ((x - cx).hypot(y - cy) - r).abs() <= half,
+        );
+    }
+
+    fn line(&mut self, ax: f32, ay: f32, bx: f32, by: f32, color: Rgba<u8>) {
+        let half = STROKE_WIDTH / 2.0;
+        let (dx, dy) = (bx - ax, by - ay);
+        let len_sq = dx * dx + dy * dy;
+        self.paint_where(
+            (ax.min(bx) - half, ay.min(by) - half),
+            (ax.max(bx) + half, ay.max(by) + half),
+            color,
+            |x, y| {
+                let t = if len_sq > 0.0 {
+                    (((x - ax) * dx + (y - ay) * dy) / len_sq).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (x - (ax + t * dx)).hypot(y - (ay + t * dy)) <= half
+            },
+        );
+    }
+
+    fn stroke_square(&mut self, cx: f32, cy: f32, width: f32, color: Rgba<u8>) {
+        let h = width / 2.0;
+        let corners = [
+            (cx - h, cy - h),
+            (cx + h, cy - h),
+            (cx + h, cy + h),
+            (cx - h, cy + h),
+        ];
+        for i in 0..4 {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            self.line(a.0, a.1, b.0, b.1, color);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::display::Poi;
+use crate::target::Target;
+
+/// A target by its true position around its sensor, as `Target::azimuth` and
+/// `Target::dist` give it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolarPoint {
+    pub id: i32,
+    pub origin: usize,
+    pub azimuth: f32,
+    pub dist: f32,
+}
+
+/// The markers on the display by where they are drawn, in a grid of square cells at
+/// least a marker wide, and the targets by their true azimuth, so the target under or
+/// nearest to a point, or those in a sector, are found without going through every
+/// marker. [`SpatialIndexPlugin`] keeps one up to date as a resource.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+    cell: f32,
+    markers: Vec<Poi>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// Smallest and largest cell coordinates in use.
+    bounds: Option<((i32, i32), (i32, i32))>,
+    /// By increasing azimuth in `[0, 2π)`.
+    polar: Vec<PolarPoint>,
+}
+
+impl SpatialIndex {
+    pub fn new<'a>(
+        markers: impl IntoIterator<Item = Poi>,
+        targets: impl IntoIterator<Item = &'a Target>,
+    ) -> Self {
+        let markers = markers.into_iter().collect::<Vec<_>>();
+        // Any marker containing a point then has its center in the point's cell or
+        // one of the eight around it.
+        let cell = markers
+            .iter()
+            .map(|m| m.half_width * 2.0)
+            .fold(1.0, f32::max);
+        let mut index = SpatialIndex {
+            cell,
+            ..Default::default()
+        };
+        for (i, marker) in markers.iter().enumerate() {
+            let key = index.cell_of(marker.center);
+            index.cells.entry(key).or_default().push(i);
+            index.bounds = Some(match index.bounds {
+                None => (key, key),
+                Some((min, max)) => (
+                    (min.0.min(key.0), min.1.min(key.1)),
+                    (max.0.max(key.0), max.1.max(key.1)),
+                ),
+            });
+        }
+        index.markers = markers;
+        index.polar = targets
+            .into_iter()
+            .map(|t| PolarPoint {
+                id: t.id,
+                origin: t.origin,
+                azimuth: t.azimuth.rem_euclid(PI * 2.0),
+                dist: t.dist,
+            })
+            .collect();
+        index
+            .polar
+            .sort_by(|a, b| a.azimuth.partial_cmp(&b.azimuth).unwrap());
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.markers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+
+    fn cell_of(&self, world: Vec2) -> (i32, i32) {
+        (
+            (world.x() / self.cell).floor() as i32,
+            (world.y() / self.cell).floor() as i32,
+        )
+    }
+
+    fn cell_markers(&self, key: (i32, i32)) -> impl Iterator<Item = &Poi> + '_ {
+        self.cells
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .map(move |&i| &self.markers[i])
+    }
+
+    /// The marker under `world`, the one with the closest center where markers
+    /// overlap, as [`poi_under`](crate::events::poi_under) finds it.
+    pub fn under(&self, world: Vec2) -> Option<&Poi> {
+        let (x, y) = self.cell_of(world);
+        (x - 1..=x + 1)
+            .flat_map(|x| (y - 1..=y + 1).map(move |y| (x, y)))
+            .flat_map(|key| self.cell_markers(key))
+            .filter(|poi| poi.contains(world))
+            .map(|poi| (poi, (poi.center - world).length_squared()))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(poi, _)| poi)
+    }
+
+    /// The marker whose center is nearest to `world`, however far away.
+    pub fn nearest_to(&self, world: Vec2) -> Option<&Poi> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds?;
+        let (x, y) = self.cell_of(world);
+        // Rings of cells closer in than the first one in use are empty.
+        let first = (min_x - x)
+            .max(x - max_x)
+            .max(min_y - y)
+            .max(y - max_y)
+            .max(0);
+        let last = (x - min_x).max(max_x - x).max(y - min_y).max(max_y - y);
+        let mut best: Option<(&Poi, f32)> = None;
+        for ring in first..=last {
+            // Every point of a cell `ring` cells out is at least `ring - 1` cells away.
+            let reach = (ring - 1).max(0) as f32 * self.cell;
+            if best.is_some_and(|(_, d)| d <= reach * reach) {
+                break;
+            }
+            for key in ring_cells((x, y), ring, ((min_x, min_y), (max_x, max_y))) {
+                for poi in self.cell_markers(key) {
+                    let d = (poi.center - world).length_squared();
+                    if best.is_none_or(|(_, best)| d < best) {
+                        best = Some((poi, d));
+                    }
+                }
+            }
+        }
+        best.map(|(poi, _)| poi)
+    }
+
+    /// Markers whose centers lie within `radius` of `world`, nearest first.
+    pub fn within_radius(&self, world: Vec2, radius: f32) -> Vec<&Poi> {
+        let (min_x, min_y) = self.cell_of(world - Vec2::new(radius, radius));
+        let (max_x, max_y) = self.cell_of(world + Vec2::new(radius, radius));
+        let mut found = (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .flat_map(|key| self.cell_markers(key))
+            .map(|poi| (poi, (poi.center - world).length_squared()))
+            .filter(|(_, d)| *d <= radius * radius)
+            .collect::<Vec<_>>();
+        found.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        found.into_iter().map(|(poi, _)| poi).collect()
+    }
+
+    /// Targets at a true azimuth from `az_min` counterclockwise to `az_max`, wrapping
+    /// past 2π when `az_max` is the smaller, and at a distance from `r_min` to `r_max`,
+    /// by increasing azimuth from `az_min`. Targets of every origin are included.
+    pub fn within_sector(
+        &self,
+        az_min: f32,
+        az_max: f32,
+        r_min: f32,
+        r_max: f32,
+    ) -> Vec<PolarPoint> {
+        let (from, to) = (az_min.rem_euclid(PI * 2.0), az_max.rem_euclid(PI * 2.0));
+        let start = self.polar.partition_point(|p| p.azimuth < from);
+        let in_range = |p: &&PolarPoint| p.dist >= r_min && p.dist <= r_max;
+        if from <= to {
+            let end = self.polar.partition_point(|p| p.azimuth <= to);
+            self.polar[start..end.max(start)]
+                .iter()
+                .filter(in_range)
+                .copied()
+                .collect()
+        } else {
+            let end = self.polar.partition_point(|p| p.azimuth <= to);
+            self.polar[start..]
+                .iter()
+                .chain(&self.polar[..end])
+                .filter(in_range)
+                .copied()
+                .collect()
+        }
+    }
+}
+
+/// The cells `ring` steps out from `center`, by the larger of the two axes, that lie
+/// within `bounds`.
+fn ring_cells(
+    center: (i32, i32),
+    ring: i32,
+    ((min_x, min_y), (max_x, max_y)): ((i32, i32), (i32, i32)),
+) -> impl Iterator<Item = (i32, i32)> {
+    let (x, y) = center;
+    let xs = (x - ring).max(min_x)..=(x + ring).min(max_x);
+    let ys = (y - ring + 1).max(min_y)..=(y + ring - 1).min(max_y);
+    let rows = IntoIterator::into_iter([y - ring, y + ring])
+        .take(if ring == 0 { 1 } else { 2 })
+        .filter(move |y| (min_y..=max_y).contains(y))
+        .flat_map(move |y| xs.clone().map(move |x| (x, y)));
+    let columns = IntoIterator::into_iter([x - ring, x + ring])
+        .take(if ring == 0 { 0 } else { 2 })
+        .filter(move |x| (min_x..=max_x).contains(x))
+        .flat_map(move |x| ys.clone().map(move |y| (x, y)));
+    rows.chain(columns)
+}
+
+/// Keeps a [`SpatialIndex`] of the markers and targets as a resource, built again
+/// after any of them change. It is updated after the display lays out the frame, so
+/// queries see the markers where they were last drawn. Needs
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin).
+pub struct SpatialIndexPlugin;
+
+impl Plugin for SpatialIndexPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_index(app);
+    }
+}
+
+/// Adds the [`SpatialIndex`] and the system keeping it up to date, once however many
+/// plugins ask for it.
+pub(crate) fn add_index(app: &mut AppBuilder) {
+    if app.resources().contains::<SpatialIndex>() {
+        return;
+    }
+    app.init_resource::<SpatialIndex>()
+        .add_system_to_stage(stage::POST_UPDATE, spatial_index_system.system());
+}
+
+fn spatial_index_system(
+    mut index: ResMut<SpatialIndex>,
+    moved: Query<Changed<Poi>>,
+    changed: Query<Changed<Target>>,
+    markers: Query<&Poi>,
+    targets: Query<&Target>,
+) {
+    let dirty = moved.iter().next().is_some()
+        || changed.iter().next().is_some()
+        || !markers.removed::<Poi>().is_empty()
+        || !targets.removed::<Target>().is_empty();
+    if dirty {
+        *index = SpatialIndex::new(markers.iter().copied(), targets.iter());
+    }
+}
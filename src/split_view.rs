@@ -0,0 +1,465 @@
+use bevy::prelude::*;
+use bevy::render::camera::{
+    ActiveCameras, Camera, CameraProjection, OrthographicProjection, PerspectiveProjection,
+};
+use bevy::render::pass::{
+    LoadOp, Operations, PassDescriptor, RenderPass, RenderPassDepthStencilAttachmentDescriptor,
+    TextureAttachment,
+};
+use bevy::render::pipeline::{
+    CompareFunction, CullMode, DynamicBinding, PipelineDescriptor, PipelineSpecialization,
+    RenderPipeline,
+};
+use bevy::render::render_graph::base::{self, MainPass, Msaa};
+use bevy::render::render_graph::{
+    AssetRenderResourcesNode, CameraNode, Node, PassNode, RenderGraph, ResourceSlotInfo,
+    ResourceSlots, WindowSwapChainNode, WindowTextureNode,
+};
+use bevy::render::renderer::{
+    BufferId, RenderContext, RenderResourceBindings, RenderResourceContext, RenderResources,
+    TextureId,
+};
+use bevy::render::shader::{ShaderStage, ShaderStages};
+use bevy::render::texture::Extent3d;
+use bevy::type_registry::TypeUuid;
+
+/// The pipeline drawing the [`BackdropMaterial`] behind each view.
+pub const BACKDROP_PIPELINE_HANDLE: Handle<PipelineDescriptor> =
+    Handle::weak_from_u64(PipelineDescriptor::TYPE_UUID, 7_305_118_642_990_413_251);
+/// Render graph node uploading [`BackdropMaterial`]s.
+const BACKDROP_MATERIAL_NODE: &str = "view_backdrop_material";
+/// bevy_ui's pass, which it keeps the name constant of private; the views are drawn
+/// before it so that the UI stays on top.
+const UI_PASS: &str = "ui_pass";
+
+const VERTEX_SHADER: &str = r#"
+#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec3 Vertex_Normal;
+layout(location = 2) in vec2 Vertex_Uv;
+
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 ViewProj;
+};
+
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+
+void main() {
+    // A quad over the whole view, on the far plane, whatever the camera.
+    gl_Position = vec4(Vertex_Position.xy, 1.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 450
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 2, binding = 0) uniform BackdropMaterial_color {
+    vec4 Color;
+};
+
+void main() {
+    o_Target = Color;
+}
+"#;
+
+/// The rectangle of the window a camera named in [`SplitViewPlugin::cameras`] draws
+/// into, in fractions of the window's width and height from its bottom-left corner,
+/// like cursor positions. The camera's picture keeps its proportions at the
+/// rectangle's shape, and [`OrbitCamera`](crate::camera::OrbitCamera)s and
+/// [`GlobePickPlugin`](crate::globe_pick::GlobePickPlugin) take input from the
+/// cursor over the view they are in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewRect {
+    pub origin: Vec2,
+    pub size: Vec2,
+}
+
+impl ViewRect {
+    pub fn new(origin: Vec2, size: Vec2) -> Self {
+        ViewRect { origin, size }
+    }
+
+    /// The bottom-left corner and size of the rectangle in pixels of a window of
+    /// `window` pixels.
+    pub fn pixels(&self, window: Vec2) -> (Vec2, Vec2) {
+        (self.origin * window, self.size * window)
+    }
+
+    /// `screen`, in window pixels, relative to the rectangle's bottom-left corner, with
+    /// the rectangle's size in pixels; `None` outside it.
+    pub fn to_local(&self, screen: Vec2, window: Vec2) -> Option<(Vec2, Vec2)> {
+        let (origin, size) = self.pixels(window);
+        let local = screen - origin;
+        let inside =
+            local.x() >= 0.0 && local.y() >= 0.0 && local.x() < size.x() && local.y() < size.y();
+        if inside {
+            Some((local, size))
+        } else {
+            None
+        }
+    }
+}
+
+/// The camera of the view under the cursor: the one of a [`ViewRect`] the cursor is
+/// over, the last such if they overlap, as the last named view is drawn on top, or
+/// otherwise `main`, the camera drawn over the whole window. Gives where the cursor
+/// is in that view and the view's size, both in pixels; `None` when no camera is
+/// found.
+pub fn view_under_cursor<T: Copy>(
+    screen: Vec2,
+    window: Vec2,
+    views: impl Iterator<Item = (T, Option<ViewRect>)>,
+    main: Option<T>,
+) -> Option<(T, Vec2, Vec2)> {
+    let mut under = None;
+    for (camera, rect) in views {
+        if let Some((local, size)) = rect.and_then(|rect| rect.to_local(screen, window)) {
+            under = Some((camera, local, size));
+        }
+    }
+    under.or_else(|| main.map(|main| (main, screen, window)))
+}
+
+/// The color behind what a camera of [`SplitViewPlugin`] sees, set to the
+/// [`ClearColor`] so views look like the main one.
+#[derive(Debug, RenderResources, TypeUuid)]
+#[uuid = "4c1e7a92-0d35-4b8f-a6e3-95b2f17c80d4"]
+pub struct BackdropMaterial {
+    pub color: Color,
+}
+
+/// On the quad [`SplitViewPlugin`] fills the back of each view with; drawn only by the
+/// views' passes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ViewBackdrop;
+
+/// Draws extra views in rectangles of the window, over the main view, each from a
+/// camera named in `cameras` with a [`ViewRect`] saying where, for a detail inset next
+/// to the globe, two views side by side, or the overview of
+/// [`MinimapPlugin`](crate::minimap::MinimapPlugin). Each camera gets a pass of its
+/// own, drawing what the main pass draws into its rectangle with a depth buffer of its
+/// own, after the main pass and before the UI's, in the order named. Spawn the cameras
+/// as [`Camera3dComponents`] or [`Camera2dComponents`] with [`Camera::name`] set. Add
+/// after the render and UI plugins.
+#[derive(Debug, Clone, Default)]
+pub struct SplitViewPlugin {
+    pub cameras: Vec<String>,
+}
+
+impl Plugin for SplitViewPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_asset::<BackdropMaterial>()
+            .add_startup_system(backdrop_setup_system.system())
+            .add_system(backdrop_sync_system.system())
+            .add_system_to_stage(
+                stage::LAST,
+                view_rect_system::<PerspectiveProjection>.system(),
+            )
+            .add_system_to_stage(
+                stage::LAST,
+                view_rect_system::<OrthographicProjection>.system(),
+            );
+        let resources = app.resources();
+        let mut shaders = resources.get_mut::<Assets<Shader>>().unwrap();
+        let mut pipelines = resources.get_mut::<Assets<PipelineDescriptor>>().unwrap();
+        let mut pipeline = PipelineDescriptor::default_config(ShaderStages {
+            vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER)),
+            fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER))),
+        });
+        if let Some(rasterization) = pipeline.rasterization_state.as_mut() {
+            rasterization.cull_mode = CullMode::None;
+        }
+        // Drawn behind everything whichever comes first: it only fills where the depth
+        // buffer is still clear, and leaves it so.
+        if let Some(depth) = pipeline.depth_stencil_state.as_mut() {
+            depth.depth_write_enabled = false;
+            depth.depth_compare = CompareFunction::LessEqual;
+        }
+        pipelines.set_untracked(BACKDROP_PIPELINE_HANDLE, pipeline);
+
+        let msaa = resources.get::<Msaa>().unwrap();
+        let mut active_cameras = resources.get_mut::<ActiveCameras>().unwrap();
+        let mut graph = resources.get_mut::<RenderGraph>().unwrap();
+        graph.add_system_node(
+            BACKDROP_MATERIAL_NODE,
+            AssetRenderResourcesNode::<BackdropMaterial>::new(false),
+        );
+        let mut previous = base::node::MAIN_PASS.to_string();
+        for name in &self.cameras {
+            let camera_node = format!("{}_camera", name);
+            let pass_node = format!("{}_pass", name);
+            active_cameras.add(name);
+            graph.add_system_node(camera_node.clone(), CameraNode::new(name.clone()));
+            graph.add_node(pass_node.clone(), ViewPassNode::new(name, &msaa));
+            graph
+                .add_slot_edge(
+                    base::node::PRIMARY_SWAP_CHAIN,
+                    WindowSwapChainNode::OUT_TEXTURE,
+                    pass_node.clone(),
+                    if msaa.samples > 1 {
+                        "color_resolve_target"
+                    } else {
+                        "color_attachment"
+                    },
+                )
+                .unwrap();
+            if msaa.samples > 1 {
+                graph
+                    .add_slot_edge(
+                        base::node::MAIN_SAMPLED_COLOR_ATTACHMENT,
+                        WindowTextureNode::OUT_TEXTURE,
+                        pass_node.clone(),
+                        "color_attachment",
+                    )
+                    .unwrap();
+            }
+            // Cleared for each view, once the main pass is done with it.
+            graph
+                .add_slot_edge(
+                    base::node::MAIN_DEPTH_TEXTURE,
+                    WindowTextureNode::OUT_TEXTURE,
+                    pass_node.clone(),
+                    "depth",
+                )
+                .unwrap();
+            graph.add_node_edge(camera_node, pass_node.clone()).unwrap();
+            graph
+                .add_node_edge(BACKDROP_MATERIAL_NODE, pass_node.clone())
+                .unwrap();
+            graph.add_node_edge(previous, pass_node.clone()).unwrap();
+            previous = pass_node;
+        }
+        if graph.get_node_id(UI_PASS).is_ok() {
+            graph.add_node_edge(previous, UI_PASS).unwrap();
+        }
+    }
+}
+
+/// The pass of one view: bevy's own, for the [`MainPass`] entities and the backdrop,
+/// drawing into the view's rectangle.
+struct ViewPassNode {
+    camera: String,
+    pass: PassNode<Or<(&'static MainPass, &'static ViewBackdrop)>>,
+}
+
+impl ViewPassNode {
+    fn new(camera: &str, msaa: &Msaa) -> Self {
+        let mut pass = PassNode::new(PassDescriptor {
+            color_attachments: vec![msaa.color_attachment_descriptor(
+                TextureAttachment::Input("color_attachment".to_string()),
+                TextureAttachment::Input("color_resolve_target".to_string()),
+                Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                },
+            )],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+                attachment: TextureAttachment::Input("depth".to_string()),
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+            sample_count: msaa.samples,
+        });
+        pass.add_camera(camera);
+        ViewPassNode {
+            camera: camera.to_string(),
+            pass,
+        }
+    }
+}
+
+impl Node for ViewPassNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        self.pass.input()
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        resources: &Resources,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        output: &mut ResourceSlots,
+    ) {
+        let window = match resources.get::<Windows>().and_then(|windows| {
+            windows
+                .get_primary()
+                .map(|window| Vec2::new(window.width() as f32, window.height() as f32))
+        }) {
+            Some(window) => window,
+            None => return,
+        };
+        let rect = resources
+            .get::<ActiveCameras>()
+            .and_then(|cameras| cameras.get(&self.camera))
+            .and_then(|camera| world.get::<ViewRect>(camera).ok().copied());
+        let (origin, size) = match rect {
+            Some(rect) => rect.pixels(window),
+            None => return,
+        };
+        // Clamped to the window, as the GPU wants, and flipped to count from the top.
+        let lo = origin.max(Vec2::zero()).min(window);
+        let hi = (origin + size).max(Vec2::zero()).min(window);
+        if hi.x() - lo.x() < 1.0 || hi.y() - lo.y() < 1.0 {
+            return;
+        }
+        let mut context = ViewportContext {
+            inner: render_context,
+            viewport: [
+                lo.x(),
+                window.y() - hi.y(),
+                hi.x() - lo.x(),
+                hi.y() - lo.y(),
+            ],
+        };
+        self.pass
+            .update(world, resources, &mut context, input, output);
+    }
+}
+
+/// A render context whose passes draw into `viewport`, as x, y from the top left,
+/// width and height in pixels.
+struct ViewportContext<'a> {
+    inner: &'a mut dyn RenderContext,
+    viewport: [f32; 4],
+}
+
+impl RenderContext for ViewportContext<'_> {
+    fn resources(&self) -> &dyn RenderResourceContext {
+        self.inner.resources()
+    }
+
+    fn resources_mut(&mut self) -> &mut dyn RenderResourceContext {
+        self.inner.resources_mut()
+    }
+
+    fn copy_buffer_to_buffer(
+        &mut self,
+        source_buffer: BufferId,
+        source_offset: u64,
+        destination_buffer: BufferId,
+        destination_offset: u64,
+        size: u64,
+    ) {
+        self.inner.copy_buffer_to_buffer(
+            source_buffer,
+            source_offset,
+            destination_buffer,
+            destination_offset,
+            size,
+        );
+    }
+
+    fn copy_buffer_to_texture(
+        &mut self,
+        source_buffer: BufferId,
+        source_offset: u64,
+        source_bytes_per_row: u32,
+        destination_texture: TextureId,
+        destination_origin: [u32; 3],
+        destination_mip_level: u32,
+        size: Extent3d,
+    ) {
+        self.inner.copy_buffer_to_texture(
+            source_buffer,
+            source_offset,
+            source_bytes_per_row,
+            destination_texture,
+            destination_origin,
+            destination_mip_level,
+            size,
+        );
+    }
+
+    fn begin_pass(
+        &mut self,
+        pass_descriptor: &PassDescriptor,
+        render_resource_bindings: &RenderResourceBindings,
+        run_pass: &mut dyn Fn(&mut dyn RenderPass),
+    ) {
+        let [x, y, width, height] = self.viewport;
+        self.inner
+            .begin_pass(pass_descriptor, render_resource_bindings, &mut |pass| {
+                pass.set_viewport(x, y, width, height, 0.0, 1.0);
+                run_pass(pass);
+            });
+    }
+}
+
+fn backdrop_setup_system(
+    mut commands: Commands,
+    clear_color: Res<ClearColor>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<BackdropMaterial>>,
+) {
+    commands.spawn((
+        ViewBackdrop,
+        meshes.add(Mesh::from(shape::Quad {
+            size: Vec2::new(2.0, 2.0),
+            flip: false,
+        })),
+        materials.add(BackdropMaterial {
+            color: clear_color.0,
+        }),
+        Draw::default(),
+        RenderPipelines::from_pipelines(vec![RenderPipeline::specialized(
+            BACKDROP_PIPELINE_HANDLE,
+            PipelineSpecialization {
+                dynamic_bindings: vec![
+                    // Transform
+                    DynamicBinding {
+                        bind_group: 1,
+                        binding: 0,
+                    },
+                ],
+                ..Default::default()
+            },
+        )]),
+        Transform::default(),
+        GlobalTransform::default(),
+    ));
+}
+
+fn backdrop_sync_system(
+    clear_color: ChangedRes<ClearColor>,
+    mut materials: ResMut<Assets<BackdropMaterial>>,
+    backdrops: Query<With<ViewBackdrop, &Handle<BackdropMaterial>>>,
+) {
+    for handle in backdrops.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = clear_color.0;
+        }
+    }
+}
+
+// bevy sets the projection for the window's shape on resizes; this puts it back to the
+// view's.
+fn view_rect_system<P: CameraProjection + Component + Clone>(
+    windows: Res<Windows>,
+    mut cameras: Query<(Mut<Camera>, &P, &ViewRect)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => Vec2::new(window.width() as f32, window.height() as f32),
+        None => return,
+    };
+    for (mut camera, projection, rect) in cameras.iter_mut() {
+        let (_, size) = rect.pixels(window);
+        if size.x() < 1.0 || size.y() < 1.0 {
+            continue;
+        }
+        let mut projection = projection.clone();
+        projection.update(size.x() as usize, size.y() as usize);
+        let matrix = projection.get_projection_matrix();
+        if camera.projection_matrix != matrix {
+            camera.projection_matrix = matrix;
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::palette::Categorical;
+use crate::target::Target;
+
+/// Outline of a target marker, centered on the target's position and sized to fit the
+/// marker square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerShape {
+    #[default]
+    Square,
+    Diamond,
+    Triangle,
+    Circle,
+}
+
+impl MarkerShape {
+    /// The shape for a marker `width` across, and the translation that centers it.
+    pub fn shape_type(self, width: f32) -> (ShapeType, Vec3) {
+        let h = width / 2.0;
+        match self {
+            MarkerShape::Square => (
+                ShapeType::Rectangle {
+                    width,
+                    height: width,
+                },
+                Vec3::new(-h, -h, 0.0),
+            ),
+            MarkerShape::Diamond => (
+                ShapeType::Quad(point(0.0, h), point(h, 0.0), point(0.0, -h), point(-h, 0.0)),
+                Vec3::zero(),
+            ),
+            MarkerShape::Triangle => (
+                ShapeType::Triangle(point(-h, -h), point(h, -h), point(0.0, h)),
+                Vec3::zero(),
+            ),
+            MarkerShape::Circle => (ShapeType::Circle(h), Vec3::zero()),
+        }
+    }
+
+    /// How far the outline of a marker `width` across is from its center in
+    /// `direction`.
+    pub fn edge(self, width: f32, direction: Vec2) -> f32 {
+        let h = width / 2.0;
+        let corners = match self {
+            MarkerShape::Circle => return h,
+            MarkerShape::Square => vec![(-h, -h), (h, -h), (h, h), (-h, h)],
+            MarkerShape::Diamond => vec![(0.0, h), (h, 0.0), (0.0, -h), (-h, 0.0)],
+            MarkerShape::Triangle => vec![(-h, -h), (h, -h), (0.0, h)],
+        };
+        let corners = corners
+            .into_iter()
+            .map(|(x, y)| Vec2::new(x, y))
+            .collect::<Vec<_>>();
+        let cross = |a: Vec2, b: Vec2| a.x() * b.y() - a.y() * b.x();
+        // The nearest side the ray from the center crosses.
+        corners
+            .iter()
+            .zip(corners.iter().cycle().skip(1))
+            .filter_map(|(&a, &b)| {
+                let side = b - a;
+                let det = cross(direction, side);
+                if det.abs() <= f32::EPSILON {
+                    return None;
+                }
+                let (t, s) = (cross(a, side) / det, cross(a, direction) / det);
+                (t >= 0.0 && (0.0..=1.0).contains(&s)).then_some(t)
+            })
+            .fold(None, |nearest: Option<f32>, t| {
+                Some(nearest.map_or(t, |n| n.min(t)))
+            })
+            .unwrap_or(h)
+    }
+}
+
+/// How a line is broken up along its length. Lengths are in display units.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinePattern {
+    #[default]
+    Solid,
+    /// Dashes `dash` long with `gap` between them.
+    Dashed { dash: f32, gap: f32 },
+    /// Round dots as wide as the line, `gap` between their centers.
+    Dotted { gap: f32 },
+    /// Not drawn at all.
+    Hidden,
+}
+
+/// How a leader line is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    /// The theme's stroke color when `None`.
+    pub color: Option<Color>,
+    pub width: f32,
+    pub pattern: LinePattern,
+    /// Draw an arrowhead at the far end of the line, pointing at the target's marker.
+    pub arrowhead: bool,
+    /// End the line at the outline of the target's marker rather than at its center.
+    pub to_marker_edge: bool,
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        LineStyle {
+            color: None,
+            width: 1.0,
+            pattern: LinePattern::Solid,
+            arrowhead: false,
+            to_marker_edge: false,
+        }
+    }
+}
+
+impl LineStyle {
+    pub fn is_hidden(&self) -> bool {
+        self.pattern == LinePattern::Hidden
+    }
+
+    /// Pulls the end of the line through `points` back to the outline of a `shape`
+    /// marker `width` across centered there, if the style ends lines at the marker's
+    /// edge. A line that ends inside the marker is left with no points.
+    pub fn end_at_marker(&self, points: &mut Vec<Vec2>, shape: MarkerShape, width: f32) {
+        if !self.to_marker_edge {
+            return;
+        }
+        let tip = match points.last() {
+            Some(&tip) => tip,
+            None => return,
+        };
+        let back = match points
+            .iter()
+            .rposition(|&p| (p - tip).length() > f32::EPSILON)
+        {
+            Some(back) => back,
+            None => return points.clear(),
+        };
+        let (from, length) = (points[back], (points[back] - tip).length());
+        let direction = (from - tip) / length;
+        let edge = shape.edge(width, direction);
+        points.truncate(back + 1);
+        if length > edge {
+            points.push(tip + direction * edge);
+        } else if points.len() < 2 {
+            points.clear();
+        }
+    }
+
+    /// The line through `points` in this style, with `width` in place of the style's.
+    pub fn stroke(
+        &self,
+        points: &[Vec2],
+        width: f32,
+        material: Handle<ColorMaterial>,
+        meshes: &mut ResMut<'_, Assets<Mesh>>,
+    ) -> SpriteComponents {
+        let mut builder = PathBuilder::new();
+        self.trace(&mut builder, points, width);
+        builder
+            .build()
+            .stroke(material, meshes, Vec3::zero(), &self.stroke_options(width))
+    }
+
+    /// Adds the line through `points` in this style, `width` wide, to `builder`, so
+    /// that many lines can be tessellated together with [`LineStyle::stroke_options`].
+    pub fn trace(&self, builder: &mut PathBuilder, points: &[Vec2], width: f32) {
+        let mut polyline = |points: &[Vec2]| {
+            if let Some((first, rest)) = points.split_first() {
+                builder.move_to(point(first.x(), first.y()));
+                // A dot is a single point, which round caps draw as a circle.
+                for p in rest.iter().chain(rest.is_empty().then_some(first)) {
+                    builder.line_to(point(p.x(), p.y()));
+                }
+            }
+        };
+        match self.pattern {
+            LinePattern::Solid => polyline(points),
+            LinePattern::Dashed { dash, gap } => {
+                for piece in dashes(points, dash, gap) {
+                    polyline(&piece);
+                }
+            }
+            LinePattern::Dotted { gap } => {
+                for dot in dashes(points, 0.0, gap) {
+                    polyline(&dot[..1]);
+                }
+            }
+            LinePattern::Hidden => return,
+        }
+        if self.arrowhead {
+            if let Some((shaft, tip)) = arrow_shaft(points) {
+                let length = ARROWHEAD_LENGTH * width.max(1.0);
+                let back = tip - shaft * length;
+                let side = Vec2::new(-shaft.y(), shaft.x()) * length * ARROWHEAD_SPREAD;
+                polyline(&[back + side, tip, back - side]);
+            }
+        }
+    }
+
+    /// How lines in this style are stroked `width` wide.
+    pub fn stroke_options(&self, width: f32) -> StrokeOptions {
+        let cap = match self.pattern {
+            LinePattern::Dotted { .. } => LineCap::Round,
+            _ => LineCap::Butt,
+        };
+        StrokeOptions::default()
+            .with_line_width(width)
+            .with_line_cap(cap)
+    }
+}
+
+/// Arrowhead length per unit of line width.
+const ARROWHEAD_LENGTH: f32 = 8.0;
+/// Half the arrowhead's width relative to its length.
+const ARROWHEAD_SPREAD: f32 = 0.5;
+
+/// The direction of the last non-empty segment of `points` and the point it ends at.
+fn arrow_shaft(points: &[Vec2]) -> Option<(Vec2, Vec2)> {
+    let tip = *points.last()?;
+    points
+        .iter()
+        .rev()
+        .map(|&p| tip - p)
+        .find(|d| d.length_squared() > f32::EPSILON)
+        .map(|d| (d.normalize(), tip))
+}
+
+/// Cuts the polyline through `points` into dashes `dash` long, `gap` apart, starting
+/// with a dash at the first point. Dashes carry on around the polyline's bends, so
+/// each is a polyline of its own; a `dash` of zero gives single points. A `gap` of
+/// zero or less leaves the line whole.
+pub fn dashes(points: &[Vec2], dash: f32, gap: f32) -> Vec<Vec<Vec2>> {
+    if gap <= 0.0 || points.is_empty() {
+        return vec![points.to_vec()];
+    }
+    let dash = dash.max(0.0);
+    let mut pieces = Vec::new();
+    let mut current = vec![points[0]];
+    // Distance left to go in the current dash or gap.
+    let (mut drawing, mut left) = (true, dash);
+    if dash == 0.0 {
+        pieces.push(std::mem::take(&mut current));
+        drawing = false;
+        left = gap;
+    }
+    for pair in points.windows(2) {
+        let (mut from, to) = (pair[0], pair[1]);
+        let mut length = (to - from).length();
+        while length >= left {
+            let at = from + (to - from) * (left / length);
+            length -= left;
+            from = at;
+            if drawing {
+                current.push(at);
+                pieces.push(std::mem::take(&mut current));
+                drawing = false;
+                left = gap;
+            } else if dash == 0.0 {
+                pieces.push(vec![at]);
+                left = gap;
+            } else {
+                current.push(at);
+                drawing = true;
+                left = dash;
+            }
+        }
+        left -= length;
+        if drawing {
+            current.push(to);
+        }
+    }
+    if drawing && current.first() != current.last() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+/// How the markers of one category are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CategoryStyle {
+    /// Stroke color; the theme's stroke color when `None`.
+    pub color: Option<Color>,
+    pub shape: MarkerShape,
+    /// Line width at [`Emphasis::Normal`](crate::emphasis::Emphasis::Normal), scaled
+    /// along with the emphasis.
+    pub stroke_width: f32,
+    /// The category's leader lines.
+    pub leader: LineStyle,
+}
+
+impl Default for CategoryStyle {
+    fn default() -> Self {
+        CategoryStyle {
+            color: None,
+            shape: MarkerShape::Square,
+            stroke_width: 1.0,
+            leader: LineStyle::default(),
+        }
+    }
+}
+
+/// Put by the display on the marker of every target with a `Target::category`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TargetCategory(pub String);
+
+impl TargetCategory {
+    pub fn of(target: &Target) -> Option<Self> {
+        target.category.clone().map(TargetCategory)
+    }
+}
+
+/// Marker and leader line styles by target category, kept by
+/// [`RadarDisplay`](crate::display::RadarDisplay) and read when it builds a marker.
+/// Targets without a category, or with one not listed, get the fallback: a
+/// square in the theme's stroke color. A leader style set for a single target id
+/// overrides its category's. Lists `friend`, `foe`, `neutral` and `unknown`, in the
+/// first four colors of [`Categorical::OkabeIto`], and `cluster`, for
+/// [`TargetCluster`](crate::cluster::TargetCluster)s, by default.
+#[derive(Debug, Clone)]
+pub struct StyleRegistry {
+    pub fallback: CategoryStyle,
+    styles: HashMap<String, CategoryStyle>,
+    leaders: HashMap<i32, LineStyle>,
+}
+
+impl Default for StyleRegistry {
+    fn default() -> Self {
+        let palette = Categorical::OkabeIto;
+        let mut registry = StyleRegistry::empty();
+        registry.insert(
+            "friend",
+            CategoryStyle {
+                color: Some(palette.color(0)),
+                shape: MarkerShape::Circle,
+                stroke_width: 1.0,
+                leader: LineStyle::default(),
+            },
+        );
+        registry.insert(
+            "foe",
+            CategoryStyle {
+                color: Some(palette.color(1)),
+                shape: MarkerShape::Diamond,
+                stroke_width: 1.5,
+                leader: LineStyle::default(),
+            },
+        );
+        registry.insert(
+            "neutral",
+            CategoryStyle {
+                color: Some(palette.color(2)),
+                shape: MarkerShape::Square,
+                stroke_width: 1.0,
+                leader: LineStyle::default(),
+            },
+        );
+        registry.insert(
+            "unknown",
+            CategoryStyle {
+                color: Some(palette.color(3)),
+                shape: MarkerShape::Triangle,
+                stroke_width: 1.0,
+                leader: LineStyle::default(),
+            },
+        );
+        registry.insert(
+            crate::cluster::CLUSTER_CATEGORY,
+            CategoryStyle {
+                color: Some(Color::WHITE),
+                shape: MarkerShape::Circle,
+                stroke_width: 2.0,
+                leader: LineStyle::default(),
+            },
+        );
+        registry
+    }
+}
+
+impl StyleRegistry {
+    /// A registry that draws every target with the fallback style.
+    pub fn empty() -> Self {
+        StyleRegistry {
+            fallback: CategoryStyle::default(),
+            styles: HashMap::new(),
+            leaders: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, category: impl Into<String>, style: CategoryStyle) {
+        self.styles.insert(category.into(), style);
+    }
+
+    pub fn remove(&mut self, category: &str) -> Option<CategoryStyle> {
+        self.styles.remove(category)
+    }
+
+    pub fn get(&self, category: &str) -> Option<&CategoryStyle> {
+        self.styles.get(category)
+    }
+
+    /// The fallback style and those of every listed category, to change them all.
+    pub fn all_mut(&mut self) -> impl Iterator<Item = &mut CategoryStyle> {
+        std::iter::once(&mut self.fallback).chain(self.styles.values_mut())
+    }
+
+    /// Every listed category with its style, by name.
+    pub fn categories(&self) -> Vec<(&str, &CategoryStyle)> {
+        let mut categories = self
+            .styles
+            .iter()
+            .map(|(name, style)| (name.as_str(), style))
+            .collect::<Vec<_>>();
+        categories.sort_unstable_by_key(|(name, _)| *name);
+        categories
+    }
+
+    /// The style `target` is drawn with.
+    pub fn style(&self, target: &Target) -> CategoryStyle {
+        target
+            .category
+            .as_deref()
+            .and_then(|category| self.get(category))
+            .copied()
+            .unwrap_or(self.fallback)
+    }
+
+    /// Draws the leader line of the target with `id` in `style`, whatever its category.
+    pub fn set_leader(&mut self, id: i32, style: LineStyle) {
+        self.leaders.insert(id, style);
+    }
+
+    pub fn clear_leader(&mut self, id: i32) -> Option<LineStyle> {
+        self.leaders.remove(&id)
+    }
+
+    /// The style `target`'s leader line is drawn with.
+    pub fn leader(&self, target: &Target) -> LineStyle {
+        self.leaders
+            .get(&target.id)
+            .copied()
+            .unwrap_or_else(|| self.style(target).leader)
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::display::{Poi, RadarDisplay, Slot, LABEL_FONT_SIZE};
+use crate::layout::{LayoutConfig, RingLayout};
+use crate::origins::SensorOrigins;
+use crate::scene::DisplayScene;
+use crate::style::{LinePattern, LineStyle, MarkerShape};
+use crate::target::Target;
+use crate::theme::{RingTextScale, Theme};
+
+/// Room left around the outermost ring, in display units.
+const MARGIN: f32 = 40.0;
+
+/// Writes ring layouts as an SVG drawing laid out like
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin) draws them: each origin's rings,
+/// the leader lines, routed if the layout routes them, the markers in their category's
+/// shape and color and the labels from the [`RadarDisplay`]'s label content. One SVG
+/// unit is one display unit, with y flipped to point down.
+pub struct SvgExport<'a> {
+    pub display: &'a RadarDisplay,
+    pub origins: &'a SensorOrigins,
+    pub theme: Theme,
+    pub text_scale: RingTextScale,
+}
+
+fn hex(color: Color) -> String {
+    let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        byte(color.r()),
+        byte(color.g()),
+        byte(color.b())
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `at` in SVG coordinates.
+fn svg_point(at: Vec2) -> (f32, f32) {
+    // Subtracted rather than negated so 0 doesn't come out as -0.
+    (at.x(), 0.0 - at.y())
+}
+
+fn stroke_attributes(color: Color, line: &LineStyle) -> String {
+    let mut attributes = format!(r#"stroke="{}" stroke-width="{}""#, hex(color), line.width);
+    match line.pattern {
+        LinePattern::Solid | LinePattern::Hidden => {}
+        LinePattern::Dashed { dash, gap } => {
+            let _ = write!(attributes, r#" stroke-dasharray="{} {}""#, dash, gap);
+        }
+        LinePattern::Dotted { gap } => {
+            let _ = write!(
+                attributes,
+                r#" stroke-dasharray="0 {}" stroke-linecap="round""#,
+                gap
+            );
+        }
+    }
+    attributes
+}
+
+fn marker(shape: MarkerShape, at: Vec2, width: f32) -> String {
+    let (x, y) = svg_point(at);
+    let h = width / 2.0;
+    let polygon = |corners: &[(f32, f32)]| {
+        let points = corners
+            .iter()
+            .map(|(dx, dy)| format!("{},{}", x + dx, y - dy))
+            .collect::<Vec<_>>();
+        format!(r#"<polygon points="{}"/>"#, points.join(" "))
+    };
+    match shape {
+        MarkerShape::Square => format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#,
+            x - h,
+            y - h,
+            width,
+            width
+        ),
+        MarkerShape::Diamond => polygon(&[(0.0, h), (h, 0.0), (0.0, -h), (-h, 0.0)]),
+        MarkerShape::Triangle => polygon(&[(-h, -h), (h, -h), (0.0, h)]),
+        MarkerShape::Circle => format!(r#"<circle cx="{}" cy="{}" r="{}"/>"#, x, y, h),
+    }
+}
+
+impl SvgExport<'_> {
+    /// The SVG document for `layouts`, by origin as the display keeps them.
+    pub fn render(&self, layouts: &BTreeMap<usize, RingLayout>) -> String {
+        let stroke = self.theme.stroke();
+        let categories = self.display.categories();
+        let (mut rings, mut leaders, mut markers, mut labels) =
+            (String::new(), String::new(), String::new(), String::new());
+        let (mut min, mut max) = (Vec2::zero(), Vec2::zero());
+        for (&origin, layout) in layouts {
+            let offset = self.origins.offset(origin);
+            let color = hex(self.origins.color(origin).unwrap_or(stroke));
+            let (x, y) = svg_point(offset);
+            let outer = layout.ring_radius(layout.rings.len().max(1) - 1);
+            min = min.min(offset - Vec2::splat(outer));
+            max = max.max(offset + Vec2::splat(outer));
+            let _ = writeln!(
+                rings,
+                r#"    <circle cx="{}" cy="{}" r="5" fill="{}"/>"#,
+                x, y, color
+            );
+            for ring in 0..layout.rings.len() {
+                let _ = writeln!(
+                    rings,
+                    r#"    <circle cx="{}" cy="{}" r="{}" stroke="{}"/>"#,
+                    x,
+                    y,
+                    layout.ring_radius(ring),
+                    color
+                );
+            }
+
+            let routes = if layout.config.leader_routing {
+                layout
+                    .route_leaders()
+                    .into_iter()
+                    .map(|route| (route.id, route.points))
+                    .collect()
+            } else {
+                BTreeMap::new()
+            };
+            let width = layout.config.marker_width();
+            for placement in layout.placements() {
+                let target = placement.target;
+                let azimuth = placement.drawn_azimuth();
+                let at = offset + Vec2::new(azimuth.cos(), azimuth.sin()) * placement.radius;
+                let style = categories.style(target);
+
+                let leader = categories.leader(target);
+                let mut points = match routes.get(&target.id) {
+                    Some(points) => points
+                        .iter()
+                        .map(|&(x, y)| offset + Vec2::new(x, y))
+                        .collect(),
+                    None => vec![offset, at],
+                };
+                leader.end_at_marker(&mut points, style.shape, width);
+                if !leader.is_hidden() && !points.is_empty() {
+                    let points = points
+                        .into_iter()
+                        .map(|p| {
+                            let (x, y) = svg_point(p);
+                            format!("{},{}", x, y)
+                        })
+                        .collect::<Vec<_>>();
+                    let _ = writeln!(
+                        leaders,
+                        r#"    <polyline points="{}" {}/>"#,
+                        points.join(" "),
+                        stroke_attributes(leader.color.unwrap_or(stroke), &leader)
+                    );
+                }
+
+                let _ = writeln!(
+                    markers,
+                    r#"    <g stroke="{}" stroke-width="{}">{}</g>"#,
+                    hex(style.color.unwrap_or(stroke)),
+                    style.stroke_width,
+                    marker(style.shape, at, width)
+                );
+
+                let (x, y) = svg_point(at);
+                let _ = writeln!(
+                    labels,
+                    r#"    <text x="{}" y="{}" font-size="{}">{}</text>"#,
+                    x + width / 2.0 + 2.0,
+                    y,
+                    self.text_scale.font_size(LABEL_FONT_SIZE, placement.ring),
+                    escape(&self.display.label(target))
+                );
+            }
+        }
+
+        let (min, max) = (min - Vec2::splat(MARGIN), max + Vec2::splat(MARGIN));
+        let size = max - min;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+            min.x(),
+            0.0 - max.y(),
+            size.x(),
+            size.y(),
+            size.x(),
+            size.y()
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            min.x(),
+            0.0 - max.y(),
+            size.x(),
+            size.y(),
+            hex(self.theme.background())
+        );
+        let _ = write!(
+            svg,
+            "  <g id=\"rings\" fill=\"none\">\n{}  </g>\n  <g id=\"leaders\" fill=\"none\">\n{}  </g>\n",
+            rings, leaders
+        );
+        let _ = write!(
+            svg,
+            "  <g id=\"markers\" fill=\"none\">\n{}  </g>\n  <g id=\"labels\" fill=\"{}\" font-family=\"sans-serif\" dominant-baseline=\"middle\">\n{}  </g>\n",
+            markers,
+            hex(self.theme.text()),
+            labels
+        );
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save(&self, layouts: &BTreeMap<usize, RingLayout>, path: &Path) -> io::Result<()> {
+        fs::write(path, self.render(layouts))
+    }
+}
+
+/// Exports the ring display as SVG to `path` on F10, through [`SvgExport`] with the
+/// app's [`Theme`] and [`RingTextScale`]. The layouts are taken from where the markers
+/// are placed, so what is written is what is shown. Needs
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin).
+pub struct SvgExportPlugin {
+    pub path: PathBuf,
+}
+
+struct SvgFile(PathBuf);
+
+impl Plugin for SvgExportPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        app.add_resource(SvgFile(self.path.clone()))
+            .add_system(svg_export_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn svg_export_system(
+    actions: Res<ActionState>,
+    file: Res<SvgFile>,
+    display: Res<RadarDisplay>,
+    config: Res<LayoutConfig>,
+    origins: Res<SensorOrigins>,
+    theme: Res<Theme>,
+    text_scale: Res<RingTextScale>,
+    placed: Query<With<Poi, (&Target, &Slot)>>,
+) {
+    if !actions.just_pressed(Action::ExportSvg) {
+        return;
+    }
+    let scene = DisplayScene::placed(*config, placed.iter());
+    let export = SvgExport {
+        display: &display,
+        origins: &origins,
+        theme: *theme,
+        text_scale: *text_scale,
+    };
+    match export.save(&scene.layouts(), &file.0) {
+        Ok(()) => tracing::info!("exported the layout to {}", file.0.display()),
+        Err(e) => tracing::error!("failed to export the layout to {}: {}", file.0.display(), e),
+    }
+}
@@ -0,0 +1,237 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::display::{Poi, RefRing};
+use crate::easing::{Easing, EasingConfig};
+use crate::layers::Layer;
+use crate::target::Target;
+use crate::theme::Theme;
+
+const SWEEP_Z: f32 = 1.0;
+const BEAM_ALPHA: f32 = 0.8;
+const WEDGE_ALPHA: f32 = 0.25;
+/// Slices the afterglow wedge is drawn in, each fainter than the one before.
+const WEDGE_SLICES: u32 = 12;
+/// How far a marker is brightened towards white right under the beam.
+const GLOW_STRENGTH: f32 = 0.7;
+
+/// Speed and afterglow of the [`SweepPlugin`] beam.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+    /// Radians per second, measured like target azimuths; negative turns clockwise.
+    pub speed: f32,
+    /// Seconds for a swept target to fade back to normal and for the wedge behind
+    /// the beam to fade out.
+    pub afterglow: f32,
+    /// How the glow and the wedge fade over the afterglow.
+    pub easing: Easing,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Sweep {
+            speed: -PI / 2.0,
+            afterglow: 1.5,
+            easing: EasingConfig::default().afterglow,
+        }
+    }
+}
+
+/// Where the beam points now, measured like target azimuths.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SweepAngle(pub f32);
+
+/// Marks the beam and wedge drawn by [`SweepPlugin`].
+#[derive(Debug, Clone, Copy)]
+pub struct SweepPart;
+
+/// On a marker: its color before the sweep lit it, and how lit it is drawn now.
+#[derive(Debug, Clone, Copy)]
+pub struct SweepGlow {
+    pub base: Color,
+    pub glow: f32,
+}
+
+/// Draws a beam turning over the ring display as set by the [`Sweep`] resource, with
+/// a fading wedge behind it, on [`Layer::Overlays`]. Markers brighten as the beam
+/// passes their target's true azimuth and fade back over the afterglow. Leave the
+/// plugin out to do without the effect.
+pub struct SweepPlugin;
+
+impl Plugin for SweepPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Sweep>() {
+            app.init_resource::<Sweep>();
+        }
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        app.add_plugin(AnimationTimePlugin)
+            .init_resource::<SweepAngle>()
+            .add_system(sweep_beam_system.system())
+            .add_system(sweep_glow_system.system());
+    }
+}
+
+/// How long ago a beam at `angle` turning at `speed` last passed `azimuth`.
+fn time_since_pass(angle: f32, azimuth: f32, speed: f32) -> f32 {
+    if speed == 0.0 {
+        return f32::INFINITY;
+    }
+    ((angle - azimuth) * speed.signum()).rem_euclid(PI * 2.0) / speed.abs()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sweep_beam_system(
+    mut commands: Commands,
+    mut drawn: Local<Option<(Sweep, Theme, f32)>>,
+    time: Res<AnimationTime>,
+    sweep: Res<Sweep>,
+    theme: Res<Theme>,
+    mut angle: ResMut<SweepAngle>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    rings: Query<&RefRing>,
+    mut parts: Query<With<SweepPart, (Entity, Mut<Transform>)>>,
+) {
+    angle.0 = (angle.0 + sweep.speed * time.delta_seconds()).rem_euclid(PI * 2.0);
+    // The beam turns about the world origin, so it spans the rings of the first origin.
+    let outer = rings
+        .iter()
+        .filter(|ring| ring.origin == 0)
+        .map(|ring| ring.radius)
+        .fold(0.0, f32::max);
+    let state = (*sweep, *theme, outer);
+    if drawn.as_ref() != Some(&state) {
+        *drawn = Some(state);
+        for (entity, _) in parts.iter_mut() {
+            commands.despawn(entity);
+        }
+        if outer > 0.0 {
+            spawn_sweep(
+                &mut commands,
+                &sweep,
+                *theme,
+                outer,
+                &mut meshes,
+                &mut materials,
+            );
+        }
+        return;
+    }
+    for (_, mut transform) in parts.iter_mut() {
+        transform.rotation = Quat::from_rotation_z(angle.0);
+    }
+}
+
+/// Draws the beam along +x and the wedge trailing behind it, to be turned to the
+/// sweep angle.
+fn spawn_sweep(
+    commands: &mut Commands,
+    sweep: &Sweep,
+    theme: Theme,
+    outer: f32,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+) {
+    let color = theme.stroke();
+    let mut beam = PathBuilder::new();
+    beam.move_to(point(0.0, 0.0));
+    beam.line_to(point(outer, 0.0));
+    let mut beam_color = color;
+    beam_color.set_a(color.a() * BEAM_ALPHA);
+    let beam = beam.build().stroke(
+        materials.add(beam_color.into()),
+        meshes,
+        Vec3::new(0.0, 0.0, SWEEP_Z),
+        &StrokeOptions::default().with_line_width(2.0),
+    );
+    commands.spawn(beam).with(SweepPart).with(Layer::Overlays);
+
+    let span = (sweep.speed.abs() * sweep.afterglow).min(PI * 2.0);
+    if span <= 0.0 {
+        return;
+    }
+    // The wedge trails the beam, so it lies on the side the beam has come from.
+    let behind = -sweep.speed.signum();
+    let slice = span / WEDGE_SLICES as f32;
+    for i in 0..WEDGE_SLICES {
+        let (from, to) = (i as f32 * slice * behind, (i + 1) as f32 * slice * behind);
+        let mut path = PathBuilder::new();
+        path.move_to(point(0.0, 0.0));
+        path.line_to(point(outer * from.cos(), outer * from.sin()));
+        path.arc(point(0.0, 0.0), outer, outer, to - from, 0.0);
+        path.close();
+        let mut slice_color = color;
+        let fade = sweep.easing.lerp(1.0, 0.0, i as f32 / WEDGE_SLICES as f32);
+        slice_color.set_a(color.a() * WEDGE_ALPHA * fade.clamp(0.0, 1.0));
+        let wedge = path.build().fill(
+            materials.add(slice_color.into()),
+            meshes,
+            Vec3::new(0.0, 0.0, SWEEP_Z),
+            &FillOptions::default(),
+        );
+        commands.spawn(wedge).with(SweepPart).with(Layer::Overlays);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn sweep_glow_system(
+    mut commands: Commands,
+    sweep: Res<Sweep>,
+    angle: Res<SweepAngle>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut markers: Query<
+        With<
+            Poi,
+            (
+                Entity,
+                &Target,
+                &Handle<ColorMaterial>,
+                Option<Mut<SweepGlow>>,
+            ),
+        >,
+    >,
+) {
+    for (entity, target, material, glow) in markers.iter_mut() {
+        let since = time_since_pass(angle.0, target.azimuth, sweep.speed);
+        let lit = if sweep.afterglow > 0.0 {
+            sweep
+                .easing
+                .lerp(1.0, 0.0, since / sweep.afterglow)
+                .clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let mut glow = match glow {
+            Some(glow) => glow,
+            None => {
+                if let Some(material) = materials.get(material) {
+                    let base = material.color;
+                    commands.insert_one(entity, SweepGlow { base, glow: 0.0 });
+                }
+                continue;
+            }
+        };
+        if glow.glow == lit {
+            continue;
+        }
+        let material = match materials.get_mut(material) {
+            Some(material) => material,
+            None => continue,
+        };
+        glow.glow = lit;
+        // Keep the alpha, which emphasis owns.
+        let (base, t) = (glow.base, lit * GLOW_STRENGTH);
+        let alpha = material.color.a();
+        material.color = Color::rgba(
+            base.r() + (1.0 - base.r()) * t,
+            base.g() + (1.0 - base.g()) * t,
+            base.b() + (1.0 - base.b()) * t,
+            alpha,
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Target {
+    pub id: i32,
+    /// The label; empty for none, which
+    /// [`DesignatorPlugin`](crate::autolabel::DesignatorPlugin) fills in.
+    #[serde(default)]
+    pub text: String,
+    pub azimuth: f32,
+    pub dist: f32,
+    /// Angle above the horizon in radians, where the sensor measures one; drawn by
+    /// [`ElevationRingPlugin`](crate::ring3d::ElevationRingPlugin).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elevation: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub velocity: Option<Velocity>,
+    /// Free-text operator notes, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// Free-form classification from the data source, e.g. `"debris"` or `"payload"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Higher values are more important. With
+    /// [`PlacementMode::Priority`](crate::layout::PlacementMode::Priority) they are placed
+    /// on inner rings ahead of lower ones.
+    #[serde(default)]
+    pub priority: i32,
+    /// Index of the sensor that reports the target in
+    /// [`SensorOrigins`](crate::origins::SensorOrigins); the first by default.
+    #[serde(default, skip_serializing_if = "is_first_origin")]
+    pub origin: usize,
+    /// When the target was observed, in seconds on the data's own clock; see
+    /// [`TimelinePlugin`](crate::timeline::TimelinePlugin).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<f64>,
+}
+
+fn is_first_origin(origin: &usize) -> bool {
+    *origin == 0
+}
+
+/// Course (radians, same convention as `Target::azimuth`) and speed (distance units
+/// per second) of a moving target.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Velocity {
+    pub course: f32,
+    pub speed: f32,
+}
+
+impl fmt::Debug for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Target")
+            .field("id", &self.id)
+            .field("text", &self.text)
+            .field("azimuth(deg)", &self.azimuth.to_degrees())
+            .field("(rad)", &self.azimuth)
+            .field("dist", &self.dist)
+            .field("elevation(deg)", &self.elevation.map(f32::to_degrees))
+            .field("velocity", &self.velocity)
+            .field("notes", &self.notes)
+            .field("category", &self.category)
+            .field("priority", &self.priority)
+            .field("origin", &self.origin)
+            .field("time", &self.time)
+            .finish()
+    }
+}
+
+/// A point of interest given in geodetic coordinates (degrees).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub id: i32,
+    pub text: String,
+    pub lat: f32,
+    pub lon: f32,
+    /// Height above the surface in meters; zero for targets on the ground.
+    #[serde(default)]
+    pub alt: f32,
+}
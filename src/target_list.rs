@@ -0,0 +1,511 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::display::LabelFont;
+use crate::emphasis::{Emphasis, TargetEmphasis};
+use crate::geo::azimuth_to_bearing;
+use crate::selection::{SelectTarget, Selected};
+use crate::target::Target;
+use crate::theme::Theme;
+
+const FONT_SIZE: f32 = 14.0;
+const ROW_HEIGHT: f32 = 18.0;
+const PADDING: f32 = 4.0;
+/// How long a POI picked from the list flashes, and how fast, in seconds.
+const FLASH_SECS: f32 = 1.2;
+const FLASH_PERIOD: f32 = 0.2;
+
+/// A column of the [`TargetListPlugin`] panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListColumn {
+    Id,
+    Bearing,
+    Range,
+    Category,
+}
+
+impl ListColumn {
+    pub const ALL: [ListColumn; 4] = [
+        ListColumn::Id,
+        ListColumn::Bearing,
+        ListColumn::Range,
+        ListColumn::Category,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ListColumn::Id => "id",
+            ListColumn::Bearing => "bearing",
+            ListColumn::Range => "range",
+            ListColumn::Category => "category",
+        }
+    }
+
+    fn width(self) -> f32 {
+        match self {
+            ListColumn::Id => 50.0,
+            ListColumn::Bearing | ListColumn::Range => 70.0,
+            ListColumn::Category => 90.0,
+        }
+    }
+}
+
+/// One line of the target list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetRow {
+    pub id: i32,
+    /// Compass bearing, in degrees clockwise from north.
+    pub bearing: f32,
+    pub range: f32,
+    pub category: Option<String>,
+}
+
+impl TargetRow {
+    pub fn of(target: &Target) -> Self {
+        TargetRow {
+            id: target.id,
+            bearing: azimuth_to_bearing(target.azimuth),
+            range: target.dist,
+            category: target.category.clone(),
+        }
+    }
+
+    /// What the row shows in `column`.
+    pub fn cell(&self, column: ListColumn) -> String {
+        match column {
+            ListColumn::Id => self.id.to_string(),
+            ListColumn::Bearing => format!("{:.1}\u{b0}", self.bearing),
+            ListColumn::Range => format!("{:.1}", self.range),
+            ListColumn::Category => self.category.clone().unwrap_or_default(),
+        }
+    }
+
+    fn cmp_by(&self, other: &TargetRow, column: ListColumn) -> Ordering {
+        let by_column = match column {
+            ListColumn::Id => Ordering::Equal,
+            ListColumn::Bearing => self.bearing.partial_cmp(&other.bearing).unwrap(),
+            ListColumn::Range => self.range.partial_cmp(&other.range).unwrap(),
+            // Targets without a category go last.
+            ListColumn::Category => match (&self.category, &other.category) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+        };
+        by_column.then(self.id.cmp(&other.id))
+    }
+}
+
+/// The rows of `targets` ordered by `column`, ties by id.
+pub fn sorted_rows<'a>(
+    targets: impl Iterator<Item = &'a Target>,
+    column: ListColumn,
+    descending: bool,
+) -> Vec<TargetRow> {
+    let mut rows = targets.map(TargetRow::of).collect::<Vec<_>>();
+    rows.sort_by(|a, b| {
+        let order = a.cmp_by(b, column);
+        if descending {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+    rows
+}
+
+/// How the [`TargetListPlugin`] panel is sorted and scrolled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetList {
+    pub sort: ListColumn,
+    pub descending: bool,
+    /// Index of the first row shown.
+    pub scroll: usize,
+    /// Rows shown at a time.
+    pub rows: usize,
+}
+
+impl Default for TargetList {
+    fn default() -> Self {
+        TargetList {
+            sort: ListColumn::Id,
+            descending: false,
+            scroll: 0,
+            rows: 20,
+        }
+    }
+}
+
+impl TargetList {
+    /// Sorts by `column`, or turns the order around if the list is sorted by it
+    /// already.
+    pub fn sort_by(&mut self, column: ListColumn) {
+        if self.sort == column {
+            self.descending = !self.descending;
+        } else {
+            self.sort = column;
+            self.descending = false;
+        }
+    }
+
+    /// Scrolls as little as it takes to show row `index` of `len`.
+    pub fn show_row(&mut self, index: usize, len: usize) {
+        if index < self.scroll {
+            self.scroll = index;
+        } else if index >= self.scroll + self.rows {
+            self.scroll = index + 1 - self.rows;
+        }
+        self.clamp_scroll(len);
+    }
+
+    fn clamp_scroll(&mut self, len: usize) {
+        self.scroll = self.scroll.min(len.saturating_sub(self.rows));
+    }
+}
+
+/// Marks the root node of the [`TargetListPlugin`] panel.
+pub struct TargetListPanel;
+
+/// A column header; clicking it sorts by the column.
+struct ListHeader(ListColumn);
+
+/// The `n`th row shown, whichever target it currently holds.
+struct ListRow(usize);
+
+struct ListCell {
+    row: usize,
+    column: ListColumn,
+}
+
+/// The ids shown in each row and the materials the panel is drawn with.
+#[derive(Default)]
+struct ListView {
+    ids: Vec<Option<i32>>,
+    panel: Handle<ColorMaterial>,
+    row: Handle<ColorMaterial>,
+    selected: Handle<ColorMaterial>,
+    theme: Option<Theme>,
+}
+
+/// A target flashing after being picked from the list.
+#[derive(Default)]
+struct ListFlash {
+    /// Picked, waiting for [`SelectionPlugin`](crate::selection::SelectionPlugin) to
+    /// select it.
+    pending: Option<i32>,
+    /// Flashing, for how long so far.
+    active: Option<(i32, f32)>,
+}
+
+/// A panel at the top right of the window listing every target with its id, bearing,
+/// range and category, on bevy_ui nodes. Clicking a column header sorts by that
+/// column, clicking it again reverses the order, and [`Action::PageUp`]/[`Action::PageDown`] scroll. Clicking a
+/// row selects its target through [`SelectTarget`] and flashes its marker; selecting a
+/// target on the display scrolls its row into view and highlights it. The layout is in
+/// the [`TargetList`] resource. Needs a UI camera and
+/// [`SelectionPlugin`](crate::selection::SelectionPlugin), with
+/// [`EmphasisPlugin`](crate::emphasis::EmphasisPlugin) for the flashing.
+pub struct TargetListPlugin;
+
+impl Plugin for TargetListPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<TargetList>() {
+            app.init_resource::<TargetList>();
+        }
+        if !app.resources().contains::<TargetEmphasis>() {
+            app.init_resource::<TargetEmphasis>();
+        }
+        app.init_resource::<ListView>()
+            .init_resource::<ListFlash>()
+            .add_startup_system(target_list_setup.system())
+            .add_system(target_list_input_system.system())
+            .add_system(target_list_sync_system.system())
+            .add_system(target_list_flash_system.system());
+    }
+}
+
+fn panel_colors(theme: Theme) -> [Color; 3] {
+    let (mut panel, mut selected) = (theme.background(), theme.stroke());
+    panel.set_a(0.85);
+    selected.set_a(0.35);
+    [panel, Color::NONE, selected]
+}
+
+fn cell_text(value: String, font: Handle<Font>, color: Color) -> TextComponents {
+    TextComponents {
+        text: Text {
+            value,
+            font,
+            style: TextStyle {
+                font_size: FONT_SIZE,
+                color,
+            },
+        },
+        ..Default::default()
+    }
+}
+
+fn row_style(width: f32) -> Style {
+    Style {
+        size: Size::new(Val::Px(width), Val::Px(ROW_HEIGHT)),
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        ..Default::default()
+    }
+}
+
+fn cell_style(column: ListColumn) -> Style {
+    Style {
+        size: Size::new(Val::Px(column.width()), Val::Px(ROW_HEIGHT)),
+        align_items: AlignItems::Center,
+        ..Default::default()
+    }
+}
+
+fn target_list_setup(
+    mut commands: Commands,
+    list: Res<TargetList>,
+    theme: Res<Theme>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    mut view: ResMut<ListView>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let [panel, row, selected] = panel_colors(*theme);
+    view.panel = materials.add(panel.into());
+    view.row = materials.add(row.into());
+    view.selected = materials.add(selected.into());
+    view.theme = Some(*theme);
+    view.ids = vec![None; list.rows];
+    let font = asset_server.load(label_font.0);
+    let width = ListColumn::ALL.iter().map(|c| c.width()).sum::<f32>();
+    let transparent = materials.add(Color::NONE.into());
+    let (view_row, text) = (view.row.clone(), theme.text());
+    commands
+        .spawn(NodeComponents {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..Default::default()
+                },
+                // bevy_ui's y points up, so this stacks the rows top down.
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(PADDING)),
+                ..Default::default()
+            },
+            material: view.panel.clone(),
+            ..Default::default()
+        })
+        .with(TargetListPanel)
+        // Takes the clicks between the rows away from the display too.
+        .with(Interaction::default())
+        .with(FocusPolicy::Block)
+        .with_children(|panel| {
+            panel
+                .spawn(NodeComponents {
+                    style: row_style(width),
+                    material: transparent.clone(),
+                    ..Default::default()
+                })
+                .with(FocusPolicy::Pass)
+                .with_children(|header| {
+                    for &column in &ListColumn::ALL {
+                        header
+                            .spawn(ButtonComponents {
+                                style: cell_style(column),
+                                material: transparent.clone(),
+                                ..Default::default()
+                            })
+                            .with(ListHeader(column))
+                            .with_children(|cell| {
+                                cell.spawn(cell_text(
+                                    column.title().to_string(),
+                                    font.clone(),
+                                    text,
+                                ))
+                                .with(ListHeader(column));
+                            });
+                    }
+                });
+            for row in 0..list.rows {
+                panel
+                    .spawn(ButtonComponents {
+                        style: row_style(width),
+                        material: view_row.clone(),
+                        ..Default::default()
+                    })
+                    .with(ListRow(row))
+                    .with_children(|cells| {
+                        for &column in &ListColumn::ALL {
+                            cells
+                                .spawn(NodeComponents {
+                                    style: cell_style(column),
+                                    material: transparent.clone(),
+                                    ..Default::default()
+                                })
+                                .with(FocusPolicy::Pass)
+                                .with_children(|cell| {
+                                    cell.spawn(cell_text(String::new(), font.clone(), text))
+                                        .with(ListCell { row, column });
+                                });
+                        }
+                    });
+            }
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn target_list_input_system(
+    actions: Res<ActionState>,
+    view: Res<ListView>,
+    mut list: ResMut<TargetList>,
+    mut flash: ResMut<ListFlash>,
+    mut requests: ResMut<Events<SelectTarget>>,
+    headers: Query<With<Button, (Mutated<Interaction>, &ListHeader)>>,
+    rows: Query<(Mutated<Interaction>, &ListRow)>,
+) {
+    for (interaction, header) in headers.iter() {
+        if *interaction == Interaction::Clicked {
+            list.sort_by(header.0);
+        }
+    }
+    for (interaction, row) in rows.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        if let Some(id) = view.ids.get(row.0).copied().flatten() {
+            requests.send(SelectTarget(Some(id)));
+            flash.pending = Some(id);
+            flash.active = None;
+        }
+    }
+    let page = list.rows.max(1);
+    if actions.just_pressed(Action::PageDown) {
+        list.scroll += page;
+    }
+    if actions.just_pressed(Action::PageUp) {
+        list.scroll = list.scroll.saturating_sub(page);
+    }
+}
+
+// Rows are refilled every frame rather than on target changes, which would have to
+// follow sorting, scrolling and selection as well; only cells whose text differs are
+// touched, so bevy_ui lays out just those again.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn target_list_sync_system(
+    mut followed: Local<Option<i32>>,
+    theme: Res<Theme>,
+    mut list: ResMut<TargetList>,
+    mut view: ResMut<ListView>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    targets: Query<&Target>,
+    selected: Query<With<Selected, &Target>>,
+    mut rows: Query<(&ListRow, Mut<Handle<ColorMaterial>>)>,
+    mut cells: Query<(&ListCell, Mut<Text>)>,
+    mut headers: Query<(&ListHeader, Mut<Text>)>,
+) {
+    let selected = selected.iter().next().map(|t| t.id);
+    let sorted = sorted_rows(targets.iter(), list.sort, list.descending);
+    if selected != *followed {
+        *followed = selected;
+        if let Some(index) = selected.and_then(|id| sorted.iter().position(|r| r.id == id)) {
+            list.show_row(index, sorted.len());
+        }
+    }
+    list.clamp_scroll(sorted.len());
+
+    if view.theme != Some(*theme) {
+        view.theme = Some(*theme);
+        let handles = [view.panel.clone(), view.row.clone(), view.selected.clone()];
+        for (handle, color) in handles.iter().zip(panel_colors(*theme).iter()) {
+            if let Some(material) = materials.get_mut(handle) {
+                material.color = *color;
+            }
+        }
+    }
+    let shown = &sorted[list.scroll.min(sorted.len())..];
+    for (i, id) in view.ids.iter_mut().enumerate() {
+        *id = shown.get(i).map(|r| r.id);
+    }
+    for (row, mut material) in rows.iter_mut() {
+        let wanted = match view.ids.get(row.0).copied().flatten() {
+            Some(id) if Some(id) == selected => &view.selected,
+            _ => &view.row,
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+    let color = theme.text();
+    for (cell, mut text) in cells.iter_mut() {
+        let value = shown
+            .get(cell.row)
+            .map(|r| r.cell(cell.column))
+            .unwrap_or_default();
+        if text.value != value {
+            text.value = value;
+        }
+        if text.style.color != color {
+            text.style.color = color;
+        }
+    }
+    for (header, mut text) in headers.iter_mut() {
+        let mut value = header.0.title().to_string();
+        if header.0 == list.sort {
+            value.push_str(if list.descending {
+                " \u{25bc}"
+            } else {
+                " \u{25b2}"
+            });
+        }
+        if text.value != value {
+            text.value = value;
+        }
+        if text.style.color != color {
+            text.style.color = color;
+        }
+    }
+}
+
+fn target_list_flash_system(
+    time: Res<Time>,
+    mut flash: ResMut<ListFlash>,
+    mut emphasis: ResMut<TargetEmphasis>,
+    selected: Query<With<Selected, &Target>>,
+) {
+    let selected = selected.iter().next().map(|t| t.id);
+    // Start once the selection has the target, so its emphasis from before is kept.
+    if flash.pending.is_some() && flash.pending == selected {
+        flash.active = flash.pending.take().map(|id| (id, 0.0));
+    }
+    let (id, elapsed) = match flash.active {
+        Some(active) => active,
+        None => return,
+    };
+    if selected != Some(id) {
+        // Selected something else meanwhile, which has seen to the emphasis.
+        flash.active = None;
+        return;
+    }
+    let elapsed = elapsed + time.delta_seconds;
+    if elapsed >= FLASH_SECS {
+        emphasis.set_target_emphasis(id, Emphasis::Highlight);
+        flash.active = None;
+        return;
+    }
+    let on = ((elapsed / FLASH_PERIOD) as u32).is_multiple_of(2);
+    emphasis.set_target_emphasis(
+        id,
+        if on {
+            Emphasis::Highlight
+        } else {
+            Emphasis::Dim
+        },
+    );
+    flash.active = Some((id, elapsed));
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use image::DynamicImage;
+use thiserror::Error;
+
+/// Vertices closer than this are raised as one, relative to the size of the mesh, so
+/// that the seams of a sphere mesh don't open up or show in the shading.
+const WELD: f32 = 1e-5;
+
+#[derive(Debug, Error)]
+pub enum TerrainError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("failed to decode {0}: {1}")]
+    Image(String, #[source] image::ImageError),
+    #[error("mesh has no {0}")]
+    MissingAttribute(&'static str),
+}
+
+/// Elevations over the whole globe in plate carrée, like the globe texture: row by row
+/// from the north, each from 0 at the lowest to 1 at the highest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightmap {
+    pub width: u32,
+    pub height: u32,
+    pub values: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Loads a grayscale PNG, of 8 or 16 bits; colour images are taken by their
+    /// brightness.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TerrainError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let bytes = fs::read(path).map_err(|e| TerrainError::Io(name.clone(), e))?;
+        let image = image::load_from_memory(&bytes).map_err(|e| TerrainError::Image(name, e))?;
+        Ok(Self::from_image(&image))
+    }
+
+    pub fn from_image(image: &DynamicImage) -> Self {
+        match image {
+            DynamicImage::ImageLuma16(gray) => Heightmap {
+                width: gray.width(),
+                height: gray.height(),
+                values: gray.pixels().map(|p| p.0[0] as f32 / 65535.0).collect(),
+            },
+            image => {
+                let gray = image.to_luma();
+                Heightmap {
+                    width: gray.width(),
+                    height: gray.height(),
+                    values: gray.pixels().map(|p| p.0[0] as f32 / 255.0).collect(),
+                }
+            }
+        }
+    }
+
+    /// The elevation at texture coordinates `uv` of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh), interpolated between the four
+    /// nearest samples. It wraps around east to west and stops at the poles.
+    pub fn sample(&self, uv: [f32; 2]) -> f32 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        let (w, h) = (self.width as i64, self.height as i64);
+        let x = uv[0] * w as f32 - 0.5;
+        let y = (uv[1] * h as f32 - 0.5).clamp(0.0, (h - 1) as f32);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let at = |x: i64, y: i64| {
+            let (x, y) = (x.rem_euclid(w), y.clamp(0, h - 1));
+            self.values[(y * w + x) as usize]
+        };
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = at(x0, y0) * (1.0 - fx) + at(x0 + 1, y0) * fx;
+        let bottom = at(x0, y0 + 1) * (1.0 - fx) + at(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    /// Raises each vertex of a sphere `mesh` along its normal by its elevation times
+    /// `scale`, sampled at the vertex's texture coordinates, and recomputes the normals
+    /// from the raised surface. Works for any of the crate's sphere meshes, as they
+    /// share the texture's layout.
+    pub fn displace(&self, mesh: &mut Mesh, scale: f32) -> Result<(), TerrainError> {
+        let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float2(uvs)) => uvs.clone(),
+            _ => return Err(TerrainError::MissingAttribute(Mesh::ATTRIBUTE_UV_0)),
+        };
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float3(normals)) => normals.clone(),
+            _ => return Err(TerrainError::MissingAttribute(Mesh::ATTRIBUTE_NORMAL)),
+        };
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float3(positions)) => positions.clone(),
+            _ => return Err(TerrainError::MissingAttribute(Mesh::ATTRIBUTE_POSITION)),
+        };
+        let size = positions
+            .iter()
+            .map(|&p| Vec3::from(p).length())
+            .fold(0.0, f32::max)
+            .max(f32::EPSILON);
+        // Vertices at the same place before raising, such as those either side of the
+        // texture's seam, are raised by the same and share a normal.
+        let weld = |p: Vec3| {
+            let q = p / (size * WELD);
+            [
+                q.x().round() as i64,
+                q.y().round() as i64,
+                q.z().round() as i64,
+            ]
+        };
+        let mut heights = HashMap::new();
+        let raised = positions
+            .iter()
+            .zip(&normals)
+            .zip(&uvs)
+            .map(|((&p, &n), &uv)| {
+                let p = Vec3::from(p);
+                let height = *heights.entry(weld(p)).or_insert_with(|| self.sample(uv));
+                p + Vec3::from(n).normalize() * height * scale
+            })
+            .collect::<Vec<_>>();
+
+        let indices = match mesh.indices() {
+            Some(Indices::U32(indices)) => indices.iter().map(|&i| i as usize).collect(),
+            Some(Indices::U16(indices)) => indices.iter().map(|&i| i as usize).collect(),
+            None => (0..raised.len()).collect::<Vec<_>>(),
+        };
+        let mut summed = HashMap::new();
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+            // Larger triangles weigh more, by the length of the cross product.
+            let normal = (raised[b] - raised[a]).cross(raised[c] - raised[a]);
+            for &i in &[a, b, c] {
+                *summed
+                    .entry(weld(Vec3::from(positions[i])))
+                    .or_insert_with(Vec3::zero) += normal;
+            }
+        }
+        let normals = positions
+            .iter()
+            .zip(&normals)
+            .map(|(&p, &n)| {
+                let summed = summed.get(&weld(Vec3::from(p))).copied();
+                match summed.filter(|s| s.length_squared() > 0.0) {
+                    // Meshes wound the other way still get normals facing outwards.
+                    Some(summed) if summed.dot(Vec3::from(n)) < 0.0 => {
+                        <[f32; 3]>::from(-summed.normalize())
+                    }
+                    Some(summed) => <[f32; 3]>::from(summed.normalize()),
+                    None => n,
+                }
+            })
+            .collect::<Vec<_>>();
+        let raised = raised.into_iter().map(<[f32; 3]>::from).collect::<Vec<_>>();
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, raised.into());
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals.into());
+        Ok(())
+    }
+}
+
+/// The triangles of a body's mesh raised by [`Heightmap::displace`], in the body's
+/// frame, on the body's entity so that
+/// [`GlobePickPlugin`](crate::globe_pick::GlobePickPlugin) meets clicks on the surface
+/// as drawn rather than the sphere under it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relief {
+    triangles: Vec<[Vec3; 3]>,
+    /// How far the furthest vertex is from the frame's origin.
+    outer: f32,
+}
+
+impl Relief {
+    /// The surface of `mesh`, a triangle list with or without indices.
+    pub fn from_mesh(mesh: &Mesh) -> Result<Self, TerrainError> {
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float3(positions)) => {
+                positions.iter().map(|&p| Vec3::from(p)).collect::<Vec<_>>()
+            }
+            _ => return Err(TerrainError::MissingAttribute(Mesh::ATTRIBUTE_POSITION)),
+        };
+        let indices = match mesh.indices() {
+            Some(Indices::U32(indices)) => indices.iter().map(|&i| i as usize).collect(),
+            Some(Indices::U16(indices)) => indices.iter().map(|&i| i as usize).collect(),
+            None => (0..positions.len()).collect::<Vec<_>>(),
+        };
+        Ok(Relief {
+            triangles: indices
+                .chunks_exact(3)
+                .map(|t| [positions[t[0]], positions[t[1]], positions[t[2]]])
+                .collect(),
+            outer: positions.iter().map(|p| p.length()).fold(0.0, f32::max),
+        })
+    }
+
+    /// Where the ray from `origin` along `dir`, both in the body's frame, first meets
+    /// the surface, on the triangle it passes through; a ray grazing a peak near the
+    /// limb hits it even where it misses the sphere under it.
+    pub fn ray_hit(&self, origin: Vec3, dir: Vec3) -> Option<Vec3> {
+        let dir = dir.normalize();
+        let (_, far) = ray_sphere_span(origin, dir, self.outer)?;
+        if far < 0.0 {
+            return None;
+        }
+        self.triangles
+            .iter()
+            .filter_map(|triangle| ray_triangle(origin, dir, triangle))
+            .min_by(f32::total_cmp)
+            .map(|t| origin + dir * t)
+    }
+}
+
+/// How far along `dir` from `origin` the ray meets `triangle`, from either side, by
+/// Möller and Trumbore's test; `None` if it misses or the triangle is behind.
+fn ray_triangle(origin: Vec3, dir: Vec3, triangle: &[Vec3; 3]) -> Option<f32> {
+    let [a, b, c] = *triangle;
+    let (ab, ac) = (b - a, c - a);
+    let p = dir.cross(ac);
+    let det = ab.dot(p);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let from_a = origin - a;
+    let u = from_a.dot(p) / det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = from_a.cross(ab);
+    let v = dir.dot(q) / det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(q) / det;
+    if t >= 0.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// How far along the unit vector `dir` from `origin` the ray enters and leaves a sphere
+/// of `radius` at the frame's origin, if it passes through it; the entry is negative
+/// from inside.
+fn ray_sphere_span(origin: Vec3, dir: Vec3, radius: f32) -> Option<(f32, f32)> {
+    let along = -origin.dot(dir);
+    let miss = origin.length_squared() - along * along;
+    if miss > radius * radius {
+        return None;
+    }
+    let half = (radius * radius - miss).sqrt();
+    Some((along - half, along + half))
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+/// Color scheme shared by the demo binaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Theme {
+    /// The original colors: red strokes on a gray background.
+    #[default]
+    Classic,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub fn background(self) -> Color {
+        match self {
+            Theme::Classic => Color::rgb(0.4, 0.4, 0.4),
+            Theme::Dark => Color::rgb(0.02, 0.04, 0.06),
+            Theme::Light => Color::rgb(0.95, 0.95, 0.92),
+        }
+    }
+
+    pub fn stroke(self) -> Color {
+        match self {
+            Theme::Classic => Color::rgb(0.8, 0.0, 0.0),
+            Theme::Dark => Color::rgb(0.1, 0.9, 0.3),
+            Theme::Light => Color::rgb(0.1, 0.2, 0.6),
+        }
+    }
+
+    /// The frame drawn around the display by [`FramePlugin`](crate::frame::FramePlugin).
+    pub fn bezel(self) -> Color {
+        match self {
+            Theme::Classic => Color::rgb(0.2, 0.2, 0.2),
+            Theme::Dark => Color::rgb(0.15, 0.17, 0.2),
+            Theme::Light => Color::rgb(0.7, 0.7, 0.68),
+        }
+    }
+
+    pub fn text(self) -> Color {
+        match self {
+            Theme::Classic | Theme::Dark => Color::WHITE,
+            Theme::Light => Color::BLACK,
+        }
+    }
+}
+
+/// How label font sizes change from ring to ring, set alongside the [`Theme`]: labels
+/// on ring `n` are `per_ring`ⁿ times the base size, kept between `min` and `max` times
+/// it. Below 1 outer labels shrink, which keeps dense outer rings readable; above 1
+/// they grow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RingTextScale {
+    pub per_ring: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Multiplies every size, for text as large on a HiDPI screen as on any other; set
+    /// by [`WindowFitPlugin`](crate::window_fit::WindowFitPlugin).
+    pub scale_factor: f32,
+}
+
+impl Default for RingTextScale {
+    fn default() -> Self {
+        RingTextScale {
+            per_ring: 1.0,
+            min: 0.6,
+            max: 1.5,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+impl RingTextScale {
+    /// The size of labels on ring `ring` for a `base` font size.
+    pub fn font_size(&self, base: f32, ring: usize) -> f32 {
+        let factor = self.per_ring.max(0.0).powi(ring as i32);
+        base * self.scale_factor * factor.max(self.min).min(self.max)
+    }
+}
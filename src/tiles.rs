@@ -0,0 +1,555 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+use std::fs;
+use std::io;
+#[cfg(not(feature = "web"))]
+use std::io::Read;
+use std::path::PathBuf;
+use std::thread;
+
+use bevy::prelude::*;
+use bevy::render::camera::{Camera, PerspectiveProjection};
+use bevy::render::render_graph::base::camera::CAMERA3D;
+use bevy::render::texture::TextureFormat;
+use crossbeam_channel::{Receiver, Sender};
+use thiserror::Error;
+
+use crate::bodies::{geo_to_local, local_to_geo, Body};
+use crate::geo_marker::body_for;
+use crate::layers::Layer;
+use crate::raster::{patch_mesh, GeoExtent};
+
+/// Furthest north and south Web Mercator tiles reach, in degrees.
+pub const MAX_TILE_LATITUDE: f32 = 85.051_13;
+/// Coarsest zoom tiles are drawn at; coarser tiles span so much of the globe that their
+/// corners say little about whether they are in view.
+const MIN_ZOOM: u8 = 2;
+/// Threads fetching and decoding the tiles of one [`GlobeTiles`].
+const WORKERS: usize = 4;
+/// Most tiles asked for at once, so that tiles wanted a moment ago don't hold up the
+/// ones wanted now when the camera moves on.
+const MAX_PENDING: usize = 2 * WORKERS;
+/// Longest step of a tile's grid, in degrees of latitude or longitude.
+const TILE_MAX_STEP: f32 = 2.0;
+/// Sent with tile requests over HTTP, which tile servers such as OpenStreetMap's ask
+/// for.
+#[cfg(not(feature = "web"))]
+const USER_AGENT: &str = concat!("bevy_debris/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Error)]
+pub enum TileError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[cfg(not(feature = "web"))]
+    #[error("failed to fetch {0}: {1}")]
+    Fetch(String, #[source] Box<ureq::Error>),
+    #[cfg(feature = "web")]
+    #[error("failed to fetch {0}: {1}")]
+    Fetch(String, #[source] crate::web::FetchError),
+    #[error("failed to decode {0}: {1}")]
+    Image(String, #[source] image::ImageError),
+}
+
+/// A tile of the XYZ scheme shared by slippy maps and the Web Mercator tile matrix set
+/// of WMTS: at `zoom` the world between [`MAX_TILE_LATITUDE`] north and south is cut
+/// into 2^zoom by 2^zoom tiles, `x` counting eastwards from the antimeridian and `y`
+/// southwards from the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileId {
+    pub zoom: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    /// The tile at `zoom` over the point `lat`, `lon` in degrees, or the nearest one to
+    /// it past [`MAX_TILE_LATITUDE`].
+    pub fn at(lat: f32, lon: f32, zoom: u8) -> Self {
+        let n = (1u32 << zoom) as f32;
+        let lat = lat
+            .clamp(-MAX_TILE_LATITUDE, MAX_TILE_LATITUDE)
+            .to_radians();
+        let x = (lon + 180.0).rem_euclid(360.0) / 360.0 * n;
+        let y = (1.0 - lat.tan().asinh() / PI) / 2.0 * n;
+        let last = n as u32 - 1;
+        TileId {
+            zoom,
+            x: (x as u32).min(last),
+            y: (y as u32).min(last),
+        }
+    }
+
+    /// The latitude and longitude rectangle the tile covers, in degrees.
+    pub fn extent(&self) -> GeoExtent {
+        let n = (1u32 << self.zoom) as f32;
+        let lon = |x: u32| x as f32 / n * 360.0 - 180.0;
+        GeoExtent {
+            west: lon(self.x),
+            south: mercator_lat((self.y + 1) as f32 / n),
+            east: lon(self.x + 1),
+            north: mercator_lat(self.y as f32 / n),
+        }
+    }
+
+    /// The four tiles of the next zoom level covering this one.
+    pub fn children(&self) -> [TileId; 4] {
+        let (zoom, x, y) = (self.zoom + 1, self.x * 2, self.y * 2);
+        [
+            TileId { zoom, x, y },
+            TileId { zoom, x: x + 1, y },
+            TileId { zoom, x, y: y + 1 },
+            TileId {
+                zoom,
+                x: x + 1,
+                y: y + 1,
+            },
+        ]
+    }
+
+    /// The tile as a patch of a sphere of `radius` in the frame of
+    /// [`sphere_mesh`](crate::mesh::sphere_mesh), facing outwards, with texture
+    /// coordinates spaced as the tile's Mercator pixels are.
+    pub fn mesh(&self, radius: f32, max_step: f32) -> Mesh {
+        let GeoExtent {
+            west,
+            south,
+            east,
+            north,
+        } = self.extent();
+        let steps = |span: f32| ((span.abs() / max_step.max(f32::EPSILON)).ceil() as u32).max(1);
+        let n = (1u32 << self.zoom) as f32;
+        patch_mesh(radius, steps(east - west), steps(north - south), |u, v| {
+            (
+                mercator_lat((self.y as f32 + v) / n),
+                west + (east - west) * u,
+            )
+        })
+    }
+
+    /// Points spread over the tile, on the unit sphere, to tell whether and how near to
+    /// the camera it is: its corners, the middles of its edges and its center.
+    fn samples(&self) -> impl Iterator<Item = Vec3> {
+        let GeoExtent {
+            west,
+            south,
+            east,
+            north,
+        } = self.extent();
+        let lats = [north, (north + south) / 2.0, south];
+        let lons = [west, (west + east) / 2.0, east];
+        (0..9).map(move |i| geo_to_local(lats[i / 3], lons[i % 3], 1.0))
+    }
+
+    fn contains(&self, lat: f32, lon: f32) -> bool {
+        let GeoExtent {
+            west,
+            south,
+            east,
+            north,
+        } = self.extent();
+        (south..=north).contains(&lat) && (west..=east).contains(&lon)
+    }
+}
+
+/// Latitude in degrees of the Mercator row `t` of the way down from the top of the map.
+fn mercator_lat(t: f32) -> f32 {
+    (PI * (1.0 - 2.0 * t)).sinh().atan().to_degrees()
+}
+
+/// The pixels of a fetched tile, RGBA row by row from the top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl TileImage {
+    pub fn texture(&self) -> Texture {
+        Texture::new(
+            Vec2::new(self.width as f32, self.height as f32),
+            self.pixels.clone(),
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+}
+
+/// Where tiles come from: `url` with `{z}`, `{x}` and `{y}`, or the WMTS
+/// `{TileMatrix}`, `{TileCol}` and `{TileRow}`, replaced by the tile's, as in
+/// `https://tile.openstreetmap.org/{z}/{x}/{y}.png`. Templates without a scheme, or
+/// with `file://`, are paths on disk. Tiles fetched over HTTP are kept under `cache` if
+/// given, as `{z}/{x}/{y}.png`, and read from there from then on. Only PNG tiles can be
+/// decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileSource {
+    pub url: String,
+    pub cache: Option<PathBuf>,
+}
+
+impl TileSource {
+    pub fn new(url: impl Into<String>) -> Self {
+        TileSource {
+            url: url.into(),
+            cache: None,
+        }
+    }
+
+    /// The URL or path of tile `id`.
+    pub fn location(&self, id: TileId) -> String {
+        let (z, x, y) = (id.zoom.to_string(), id.x.to_string(), id.y.to_string());
+        self.url
+            .replace("{z}", &z)
+            .replace("{x}", &x)
+            .replace("{y}", &y)
+            .replace("{TileMatrix}", &z)
+            .replace("{TileCol}", &x)
+            .replace("{TileRow}", &y)
+    }
+
+    /// Reads or downloads tile `id` and decodes it.
+    pub fn fetch(&self, id: TileId) -> Result<TileImage, TileError> {
+        let location = self.location(id);
+        let bytes = if location.starts_with("http://") || location.starts_with("https://") {
+            let cached = self
+                .cache
+                .as_ref()
+                .map(|cache| cache.join(format!("{}/{}/{}.png", id.zoom, id.x, id.y)));
+            match cached.as_ref().and_then(|path| fs::read(path).ok()) {
+                Some(bytes) => bytes,
+                None => {
+                    let bytes = download(&location)?;
+                    if let Some(path) = cached {
+                        // A tile that can't be cached is still drawn, and fetched again
+                        // next time.
+                        let _ = path
+                            .parent()
+                            .map_or(Ok(()), fs::create_dir_all)
+                            .and_then(|_| fs::write(&path, &bytes));
+                    }
+                    bytes
+                }
+            }
+        } else {
+            let path = location.strip_prefix("file://").unwrap_or(&location);
+            fs::read(path).map_err(|e| TileError::Io(location.clone(), e))?
+        };
+        let image = image::load_from_memory(&bytes)
+            .map_err(|e| TileError::Image(location, e))?
+            .to_rgba();
+        let (width, height) = image.dimensions();
+        Ok(TileImage {
+            width,
+            height,
+            pixels: image.into_raw(),
+        })
+    }
+}
+
+#[cfg(not(feature = "web"))]
+fn download(url: &str) -> Result<Vec<u8>, TileError> {
+    let mut bytes = Vec::new();
+    ureq::get(url)
+        .set("User-Agent", USER_AGENT)
+        .call()
+        .map_err(|e| TileError::Fetch(url.to_string(), Box::new(e)))?
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|e| TileError::Io(url.to_string(), e))?;
+    Ok(bytes)
+}
+
+#[cfg(feature = "web")]
+fn download(url: &str) -> Result<Vec<u8>, TileError> {
+    crate::web::fetch(url).map_err(|e| TileError::Fetch(url.to_string(), e))
+}
+
+/// The zoom level whose tile pixels are as large as a screen pixel for a globe of
+/// `radius` seen from `distance` away from its surface, through a camera of vertical
+/// field of view `fov` on a window `window_height` pixels tall; with `tile_pixels` wide
+/// tiles. Fractional, and negative when the whole world fits in less than a tile.
+pub fn zoom_for(distance: f32, radius: f32, fov: f32, window_height: f32, tile_pixels: f32) -> f32 {
+    let per_pixel = 2.0 * distance.max(f32::EPSILON) * (fov / 2.0).tan() / window_height;
+    (2.0 * PI * radius / (tile_pixels * per_pixel)).log2()
+}
+
+/// Slippy-map tiles streamed over a globe: spawn it as a child of the [`Body`] it
+/// belongs on, or on its own to put it on the body nearest the world origin, like a
+/// [`GeoMarker`](crate::geo_marker::GeoMarker). [`GlobeTilesPlugin`] keeps the tiles
+/// facing the camera drawn over the body, finer where the surface is nearer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobeTiles {
+    pub source: TileSource,
+    /// Finest zoom level fetched.
+    pub max_zoom: u8,
+    /// Most tiles drawn at once; tiles are left coarser rather than going past it.
+    pub max_tiles: usize,
+    /// Width of the source's tiles in pixels.
+    pub tile_pixels: f32,
+    /// How far above the surface tiles lie, relative to the body's radius.
+    pub lift: f32,
+}
+
+impl GlobeTiles {
+    pub fn new(source: TileSource) -> Self {
+        GlobeTiles {
+            source,
+            max_zoom: 18,
+            max_tiles: 128,
+            tile_pixels: 256.0,
+            lift: 0.002,
+        }
+    }
+
+    /// The tiles to draw for a camera at `eye` in the body's frame, where the body is
+    /// a sphere of `radius`: those in view from it, each split until its pixels are no
+    /// larger than a screen pixel, for [`zoom_for`] with the camera's `fov` and
+    /// `window_height`, or until there would be more than [`max_tiles`](Self::max_tiles).
+    /// The tiles nearest the camera are split first.
+    pub fn wanted(&self, eye: Vec3, radius: f32, fov: f32, window_height: f32) -> Vec<TileId> {
+        let (below_lat, below_lon) = local_to_geo(eye);
+        let in_view = |tile: &TileId| {
+            tile.contains(below_lat, below_lon) || tile.samples().any(|p| p.dot(eye) > radius)
+        };
+        let nearest = |tile: &TileId| {
+            if tile.contains(below_lat, below_lon) {
+                eye.length() - radius
+            } else {
+                tile.samples()
+                    .map(|p| (eye - p * radius).length())
+                    .fold(f32::INFINITY, f32::min)
+            }
+        };
+        let start = TileId {
+            zoom: 0,
+            x: 0,
+            y: 0,
+        };
+        let mut level = vec![start];
+        for _ in 0..MIN_ZOOM.min(self.max_zoom) {
+            level = level.iter().flat_map(TileId::children).collect();
+        }
+        level.retain(|tile| in_view(tile));
+
+        // Split the tile whose pixels are largest on screen first, so that running out
+        // of tiles leaves those furthest away or seen edge-on coarse.
+        let need = |tile: &TileId| {
+            zoom_for(nearest(tile), radius, fov, window_height, self.tile_pixels) - tile.zoom as f32
+        };
+        let mut leaves = level
+            .into_iter()
+            .map(|tile| (need(&tile), tile))
+            .collect::<Vec<_>>();
+        loop {
+            let split = leaves
+                .iter()
+                .enumerate()
+                .filter(|(_, (need, tile))| *need > 0.0 && tile.zoom < self.max_zoom)
+                .max_by(|a, b| (a.1).0.partial_cmp(&(b.1).0).unwrap())
+                .map(|(i, _)| i);
+            let i = match split {
+                Some(i) => i,
+                None => break,
+            };
+            let children = leaves[i]
+                .1
+                .children()
+                .iter()
+                .copied()
+                .filter(|tile| in_view(tile))
+                .collect::<Vec<_>>();
+            if leaves.len() - 1 + children.len() > self.max_tiles {
+                break;
+            }
+            leaves.swap_remove(i);
+            leaves.extend(children.into_iter().map(|tile| (need(&tile), tile)));
+        }
+        leaves.into_iter().map(|(_, tile)| tile).collect()
+    }
+}
+
+/// On a [`GlobeTiles`] once [`GlobeTilesPlugin`] has started streaming it: the threads
+/// fetching its tiles and the tiles drawn.
+pub struct TileStream {
+    pub body: Entity,
+    requests: Sender<TileId>,
+    results: Receiver<(TileId, Result<TileImage, TileError>)>,
+    pending: HashSet<TileId>,
+    failed: HashSet<TileId>,
+    drawn: HashMap<TileId, Entity>,
+}
+
+impl TileStream {
+    fn start(body: Entity, source: &TileSource) -> Self {
+        let (requests, requested) = crossbeam_channel::unbounded::<TileId>();
+        let (sender, results) = crossbeam_channel::unbounded();
+        for _ in 0..WORKERS {
+            let (requested, sender, source) = (requested.clone(), sender.clone(), source.clone());
+            thread::spawn(move || {
+                for id in requested.iter() {
+                    if sender.send((id, source.fetch(id))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        TileStream {
+            body,
+            requests,
+            results,
+            pending: HashSet::new(),
+            failed: HashSet::new(),
+            drawn: HashMap::new(),
+        }
+    }
+
+    /// Tiles drawn at the moment.
+    pub fn drawn(&self) -> impl Iterator<Item = TileId> + '_ {
+        self.drawn.keys().copied()
+    }
+
+    /// Tiles asked for and not yet fetched.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Streams the tiles of every [`GlobeTiles`] as the camera moves: the tiles wanted from
+/// where the 3D camera is are fetched on background threads, drawn unshaded over the
+/// body as they arrive and turning with it, a few at a time, and the tiles no longer
+/// wanted despawned once all the wanted ones are in, so zooming never leaves holes. Finer tiles lie a
+/// little above coarser ones. Tiles that fail to load are reported once and not asked
+/// for again. Everything takes the [`GlobeTiles`]' [`Layer`] if it has one.
+pub struct GlobeTilesPlugin;
+
+impl Plugin for GlobeTilesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(tile_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn tile_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &PerspectiveProjection, &GlobalTransform)>,
+    bodies: Query<(Entity, &Body, &GlobalTransform)>,
+    mut tiles: Query<(
+        Entity,
+        &GlobeTiles,
+        Option<&Parent>,
+        Option<&Layer>,
+        Option<Mut<TileStream>>,
+    )>,
+) {
+    let window_height = match windows.get_primary() {
+        Some(window) => window.height() as f32,
+        None => return,
+    };
+    let (projection, eye) = match cameras
+        .iter()
+        .find(|(camera, ..)| camera.name.as_deref() == Some(CAMERA3D))
+    {
+        Some((_, projection, eye)) => (projection, eye),
+        None => return,
+    };
+    for (entity, globe_tiles, parent, layer, stream) in tiles.iter_mut() {
+        let mut stream = match stream {
+            Some(stream) => stream,
+            None => {
+                let (body, adopt) = match body_for(parent, &bodies) {
+                    Some(body) => body,
+                    None => continue,
+                };
+                if adopt {
+                    commands.push_children(body, &[entity]);
+                }
+                // The tiles are children of this entity, which needs a transform to pass
+                // on.
+                commands.insert(
+                    entity,
+                    (
+                        TileStream::start(body, &globe_tiles.source),
+                        Transform::default(),
+                        GlobalTransform::default(),
+                    ),
+                );
+                continue;
+            }
+        };
+        let (radius, body_transform) = match bodies.get(stream.body) {
+            Ok((_, body, transform)) => (body.radius, transform),
+            Err(_) => continue,
+        };
+        let local_eye = body_transform
+            .compute_matrix()
+            .inverse()
+            .transform_point3(eye.translation);
+        let wanted = globe_tiles
+            .wanted(local_eye, radius, projection.fov, window_height)
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let arrived = stream.results.try_iter().collect::<Vec<_>>();
+        for (id, result) in arrived {
+            stream.pending.remove(&id);
+            let image = match result {
+                Ok(image) => image,
+                Err(e) => {
+                    tracing::warn!("skipping tile: {}", e);
+                    stream.failed.insert(id);
+                    continue;
+                }
+            };
+            if !wanted.contains(&id) || stream.drawn.contains_key(&id) {
+                continue;
+            }
+            let lift =
+                globe_tiles.lift * (1.0 + id.zoom as f32 / globe_tiles.max_zoom.max(1) as f32);
+            commands.spawn(PbrComponents {
+                mesh: meshes.add(id.mesh(radius * (1.0 + lift), TILE_MAX_STEP)),
+                material: materials.add(StandardMaterial {
+                    albedo_texture: Some(textures.add(image.texture())),
+                    shaded: false,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            if let Some(layer) = layer {
+                commands.with(*layer);
+            }
+            if let Some(tile) = commands.current_entity() {
+                commands.push_children(entity, &[tile]);
+                stream.drawn.insert(id, tile);
+            }
+        }
+
+        for &id in &wanted {
+            let known = stream.drawn.contains_key(&id)
+                || stream.pending.contains(&id)
+                || stream.failed.contains(&id);
+            if !known && stream.pending.len() < MAX_PENDING && stream.requests.send(id).is_ok() {
+                stream.pending.insert(id);
+            }
+        }
+        let complete = wanted
+            .iter()
+            .all(|id| stream.drawn.contains_key(id) || stream.failed.contains(id));
+        if !complete {
+            continue;
+        }
+        let stale = stream
+            .drawn
+            .keys()
+            .filter(|id| !wanted.contains(id))
+            .copied()
+            .collect::<Vec<_>>();
+        for id in stale {
+            if let Some(tile) = stream.drawn.remove(&id) {
+                commands.despawn(tile);
+            }
+        }
+    }
+}
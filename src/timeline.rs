@@ -0,0 +1,485 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::animation::{AnimationTime, AnimationTimePlugin};
+use crate::display::LabelFont;
+use crate::pointer::CursorPosition;
+use crate::replay::{Recording, TargetEvent};
+use crate::target::{GeoPoint, Target};
+use crate::theme::Theme;
+
+const FONT_SIZE: f32 = 14.0;
+const BAR_HEIGHT: f32 = 30.0;
+const PADDING: f32 = 4.0;
+const BUTTON_WIDTH: f32 = 60.0;
+const TIME_WIDTH: f32 = 150.0;
+/// Width of the scrubber's handle, in pixels.
+const HANDLE_WIDTH: f32 = 4.0;
+
+/// One report of a target on the [`Timeline`], the target as it was at `t`.
+#[derive(Debug, Clone)]
+pub struct TimelineSample {
+    pub t: f64,
+    pub target: Target,
+    /// Where the target was on the globe, for
+    /// [`GlobeLinkPlugin`](crate::globe_link::GlobeLinkPlugin).
+    pub geo: Option<GeoPoint>,
+}
+
+/// The timestamped reports [`TimelinePlugin`] shows a window of, and where that window
+/// is. Change `window`, `speed` or `playing` directly; [`Timeline::seek`] moves the
+/// window.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    /// In the order of their times.
+    samples: Vec<TimelineSample>,
+    /// Seconds on the data's clock the window ends at.
+    position: f64,
+    /// Set once the position was moved, until then kept at the first sample.
+    moved: bool,
+    /// Seconds the window reaches back from its end: targets reported within it are
+    /// shown, as last reported.
+    pub window: f64,
+    pub playing: bool,
+    /// Seconds of data played per second.
+    pub speed: f32,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Timeline::new(60.0)
+    }
+}
+
+impl Timeline {
+    pub fn new(window: f64) -> Self {
+        Timeline {
+            samples: Vec::new(),
+            position: 0.0,
+            moved: false,
+            window,
+            playing: true,
+            speed: 1.0,
+        }
+    }
+
+    /// Every added and changed target of `recording`, at the time it came. Removals
+    /// don't count: a target is shown while a report of it falls in the window.
+    pub fn from_recording(recording: &Recording, window: f64) -> Self {
+        let mut timeline = Timeline::new(window);
+        for recorded in &recording.events {
+            if let TargetEvent::Add { target } | TargetEvent::Change { target } = &recorded.event {
+                let target = Target {
+                    time: Some(recorded.t),
+                    ..target.clone()
+                };
+                timeline.add(recorded.t, target, None);
+            }
+        }
+        timeline
+    }
+
+    /// Adds a report of `target` at `t`.
+    pub fn add(&mut self, t: f64, target: Target, geo: Option<GeoPoint>) {
+        let at = self.samples.partition_point(|sample| sample.t <= t);
+        self.samples.insert(at, TimelineSample { t, target, geo });
+        if !self.moved {
+            self.position = self.start();
+        }
+    }
+
+    pub fn samples(&self) -> &[TimelineSample] {
+        &self.samples
+    }
+
+    /// Time of the first sample.
+    pub fn start(&self) -> f64 {
+        self.samples.first().map_or(0.0, |sample| sample.t)
+    }
+
+    /// Time of the last sample.
+    pub fn end(&self) -> f64 {
+        self.samples.last().map_or(0.0, |sample| sample.t)
+    }
+
+    /// Where the window ends.
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    /// Moves the end of the window to `t`, within the samples.
+    pub fn seek(&mut self, t: f64) {
+        self.position = t.max(self.start()).min(self.end());
+        self.moved = true;
+    }
+
+    /// The index of the last sample in the window of each target there, by id.
+    fn visible_indices(&self) -> BTreeMap<i32, usize> {
+        let from = self
+            .samples
+            .partition_point(|sample| sample.t < self.position - self.window);
+        let to = self
+            .samples
+            .partition_point(|sample| sample.t <= self.position);
+        (from..to.max(from))
+            .map(|i| (self.samples[i].target.id, i))
+            .collect()
+    }
+
+    /// The targets shown, each as last reported in the window, by id.
+    pub fn visible(&self) -> BTreeMap<i32, &TimelineSample> {
+        self.visible_indices()
+            .into_iter()
+            .map(|(id, i)| (id, &self.samples[i]))
+            .collect()
+    }
+}
+
+/// On the target entities [`TimelinePlugin`] spawned for the window.
+#[derive(Debug, Clone, Copy)]
+pub struct TimelineTarget;
+
+/// Shows recorded or timestamped targets a window of time at a time. Every target
+/// entity with a [`Target::time`] is taken onto the [`Timeline`], along with its
+/// [`GeoPoint`], and only those reported within [`Timeline::window`] seconds before
+/// the timeline's position are spawned again, each as last reported then; targets
+/// without a time stay as they are. The ring display and the globe pins of
+/// [`GlobeLinkPlugin`](crate::globe_link::GlobeLinkPlugin) follow, as they follow any
+/// target coming and going.
+///
+/// A bar along the bottom of the window has a play/pause button, a scrubber showing
+/// the window over the whole timeline, which moves it when clicked or dragged, and the
+/// time at the end of the window from the first sample. [`Action::PlayPause`], Space by
+/// default, plays and pauses too.
+/// Playing follows the unscaled [`AnimationTime`] and stops at the last sample. Needs a
+/// UI camera.
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<Timeline>() {
+            app.init_resource::<Timeline>();
+        }
+        app.add_plugin(AnimationTimePlugin)
+            .add_system(timeline_ingest_system.system())
+            .add_system(timeline_input_system.system())
+            .add_system(timeline_play_system.system())
+            .add_system(timeline_filter_system.system())
+            .add_system(timeline_bar_system.system())
+            .add_system(timeline_sync_system.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn timeline_ingest_system(
+    mut commands: Commands,
+    mut timeline: ResMut<Timeline>,
+    targets: Query<Without<TimelineTarget, (Entity, &Target, Option<&GeoPoint>)>>,
+) {
+    for (entity, target, geo) in targets.iter() {
+        if let Some(t) = target.time {
+            timeline.add(t, target.clone(), geo.cloned());
+            commands.despawn(entity);
+        }
+    }
+}
+
+fn timeline_play_system(time: Res<AnimationTime>, mut timeline: ResMut<Timeline>) {
+    if !timeline.playing || timeline.samples().is_empty() {
+        return;
+    }
+    if timeline.position() >= timeline.end() {
+        timeline.playing = false;
+        return;
+    }
+    let step = (time.unscaled_delta_seconds() * timeline.speed.max(0.0)) as f64;
+    let position = timeline.position();
+    timeline.seek(position + step);
+}
+
+/// The entity spawned for each target in the window, and the sample it shows.
+#[derive(Default)]
+struct Shown(HashMap<i32, (Entity, usize)>);
+
+fn timeline_filter_system(
+    mut commands: Commands,
+    mut shown: Local<Shown>,
+    timeline: Res<Timeline>,
+    mut targets: Query<(Mut<Target>, Option<Mut<GeoPoint>>)>,
+) {
+    let visible = timeline.visible_indices();
+    let gone = shown
+        .0
+        .keys()
+        .copied()
+        .filter(|id| !visible.contains_key(id))
+        .collect::<Vec<_>>();
+    for id in gone {
+        if let Some((entity, _)) = shown.0.remove(&id) {
+            commands.despawn(entity);
+        }
+    }
+    for (&id, &i) in &visible {
+        let sample = &timeline.samples()[i];
+        match shown.0.get_mut(&id) {
+            Some((_, showing)) if *showing == i => {}
+            Some((entity, showing)) => {
+                *showing = i;
+                if let Ok((mut target, geo)) = targets.get_mut(*entity) {
+                    *target = sample.target.clone();
+                    match (geo, &sample.geo) {
+                        (Some(mut geo), Some(sampled)) => *geo = sampled.clone(),
+                        (None, Some(sampled)) => {
+                            commands.insert_one(*entity, sampled.clone());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            None => {
+                commands.spawn((sample.target.clone(), TimelineTarget));
+                if let Some(geo) = &sample.geo {
+                    commands.with(geo.clone());
+                }
+                if let Some(entity) = commands.current_entity() {
+                    shown.0.insert(id, (entity, i));
+                }
+            }
+        }
+    }
+}
+
+struct TimelineBar;
+
+struct PlayButton;
+
+struct PlayText;
+
+/// The scrubber, whose width is the whole timeline.
+struct TimelineTrack;
+
+/// The part of the scrubber the window covers.
+struct TimelineSpan;
+
+struct TimelineHandle;
+
+struct TimelineText;
+
+fn text(value: &str, font: Handle<Font>, color: Color) -> TextComponents {
+    TextComponents {
+        text: Text {
+            value: value.to_string(),
+            font,
+            style: TextStyle {
+                font_size: FONT_SIZE,
+                color,
+            },
+        },
+        ..Default::default()
+    }
+}
+
+fn timeline_bar_system(
+    mut commands: Commands,
+    mut shown: Local<Option<Theme>>,
+    theme: Res<Theme>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    bars: Query<With<TimelineBar, Entity>>,
+) {
+    if *shown == Some(*theme) {
+        return;
+    }
+    *shown = Some(*theme);
+    for bar in bars.iter() {
+        commands.despawn_recursive(bar);
+    }
+    let font = asset_server.load(label_font.0);
+    let tinted = |alpha: f32| {
+        let mut color = theme.stroke();
+        color.set_a(alpha);
+        color
+    };
+    let mut backdrop = theme.background();
+    backdrop.set_a(0.85);
+    let color = theme.text();
+    let transparent = materials.add(Color::NONE.into());
+    let track = materials.add(tinted(0.2).into());
+    let span = materials.add(tinted(0.5).into());
+    let button = materials.add(tinted(0.35).into());
+    let handle = materials.add(color.into());
+    let inner = BAR_HEIGHT - 2.0 * PADDING;
+    commands
+        .spawn(NodeComponents {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(100.0), Val::Px(BAR_HEIGHT)),
+                align_items: AlignItems::Center,
+                padding: Rect::all(Val::Px(PADDING)),
+                ..Default::default()
+            },
+            material: materials.add(backdrop.into()),
+            ..Default::default()
+        })
+        .with(TimelineBar)
+        // Takes the clicks on the bar away from the display too.
+        .with(Interaction::default())
+        .with(FocusPolicy::Block)
+        .with_children(|bar| {
+            bar.spawn(ButtonComponents {
+                style: Style {
+                    size: Size::new(Val::Px(BUTTON_WIDTH), Val::Px(inner)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                material: button,
+                ..Default::default()
+            })
+            .with(PlayButton)
+            .with_children(|button| {
+                button.spawn(text("", font.clone(), color)).with(PlayText);
+            });
+            bar.spawn(NodeComponents {
+                style: Style {
+                    flex_grow: 1.0,
+                    size: Size::new(Val::Auto, Val::Px(inner)),
+                    margin: Rect {
+                        left: Val::Px(2.0 * PADDING),
+                        right: Val::Px(2.0 * PADDING),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                material: track,
+                ..Default::default()
+            })
+            .with(TimelineTrack)
+            .with(Interaction::default())
+            .with(FocusPolicy::Block)
+            .with_children(|track| {
+                for (material, handle) in [(span, false), (handle, true)] {
+                    let part = track.spawn(NodeComponents {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            size: Size::new(Val::Px(HANDLE_WIDTH), Val::Px(inner)),
+                            ..Default::default()
+                        },
+                        material,
+                        ..Default::default()
+                    });
+                    if handle {
+                        part.with(TimelineHandle);
+                    } else {
+                        part.with(TimelineSpan);
+                    }
+                    part.with(FocusPolicy::Pass);
+                }
+            });
+            bar.spawn(NodeComponents {
+                style: Style {
+                    size: Size::new(Val::Px(TIME_WIDTH), Val::Px(inner)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                material: transparent,
+                ..Default::default()
+            })
+            .with(FocusPolicy::Pass)
+            .with_children(|cell| {
+                cell.spawn(text("", font.clone(), color)).with(TimelineText);
+            });
+        });
+}
+
+/// Where `screen` falls along `node`, from `0` at its left edge to `1` at its right.
+fn fraction_along(screen: Vec2, node: &Node, transform: &GlobalTransform) -> f32 {
+    let left = transform.translation.x() - node.size.x() / 2.0;
+    ((screen.x() - left) / node.size.x().max(1.0)).clamp(0.0, 1.0)
+}
+
+#[allow(clippy::type_complexity)]
+fn timeline_input_system(
+    actions: Res<ActionState>,
+    cursor: Res<CursorPosition>,
+    mut timeline: ResMut<Timeline>,
+    buttons: Query<With<PlayButton, Mutated<Interaction>>>,
+    tracks: Query<With<TimelineTrack, (&Interaction, &Node, &GlobalTransform)>>,
+) {
+    let clicked = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked);
+    if clicked || actions.just_pressed(Action::PlayPause) {
+        timeline.playing = !timeline.playing;
+        // Playing from the end starts over.
+        if timeline.playing && timeline.position() >= timeline.end() {
+            let start = timeline.start();
+            timeline.seek(start);
+        }
+    }
+    let screen = match cursor.screen {
+        Some(screen) => screen,
+        None => return,
+    };
+    // A press on the track stays Clicked until let go, wherever the cursor goes.
+    for (interaction, node, transform) in tracks.iter() {
+        if *interaction == Interaction::Clicked {
+            let (start, end) = (timeline.start(), timeline.end());
+            let t = start + (end - start) * fraction_along(screen, node, transform) as f64;
+            if t != timeline.position() {
+                timeline.seek(t);
+            }
+        }
+    }
+}
+
+fn timeline_sync_system(
+    timeline: Res<Timeline>,
+    mut play_texts: Query<With<PlayText, Mut<Text>>>,
+    mut time_texts: Query<With<TimelineText, Mut<Text>>>,
+    mut spans: Query<With<TimelineSpan, Mut<Style>>>,
+    mut handles: Query<With<TimelineHandle, Mut<Style>>>,
+) {
+    let (start, end) = (timeline.start(), timeline.end());
+    let length = (end - start).max(f64::EPSILON);
+    let along = |t: f64| (((t - start) / length).clamp(0.0, 1.0) * 100.0) as f32;
+    let position = timeline.position();
+    let play = if timeline.playing { "pause" } else { "play" };
+    let time = format!("{:.1} / {:.1} s", position - start, end - start);
+    let texts = play_texts
+        .iter_mut()
+        .map(|text| (text, play))
+        .chain(time_texts.iter_mut().map(|text| (text, time.as_str())));
+    for (mut text, value) in texts {
+        if text.value != value {
+            text.value = value.to_string();
+        }
+    }
+    let from = along(position - timeline.window);
+    let to = along(position);
+    let parts = spans
+        .iter_mut()
+        .map(|style| (style, Val::Percent(from), Val::Percent(to - from)))
+        .chain(
+            handles
+                .iter_mut()
+                .map(|style| (style, Val::Percent(to), Val::Px(HANDLE_WIDTH))),
+        );
+    for (mut style, left, width) in parts {
+        if style.position.left != left || style.size.width != width {
+            style.position.left = left;
+            style.size.width = width;
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+use bevy::render::render_graph::base::MainPass;
+
+use crate::coords::CoordFormat;
+use crate::display::{LabelFont, RadarDisplay};
+use crate::events::DisplayEvent;
+use crate::pointer::{screen_to_world, CursorPosition};
+use crate::target::Target;
+use crate::theme::Theme;
+
+/// Marks the tooltip entity spawned by [`TooltipPlugin`].
+#[derive(Debug, Clone, Copy)]
+pub struct Tooltip {
+    pub target: i32,
+}
+
+/// How [`TooltipPlugin`] draws tooltips.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TooltipStyle {
+    pub font_size: f32,
+    /// From the cursor to the tooltip's nearest corner, in window pixels.
+    pub offset: Vec2,
+}
+
+impl Default for TooltipStyle {
+    fn default() -> Self {
+        TooltipStyle {
+            font_size: 16.0,
+            offset: Vec2::new(16.0, 16.0),
+        }
+    }
+}
+
+/// Roughly how much room `text` takes at `font_size`, in pixels, from its longest line
+/// and line count.
+pub fn estimate_size(text: &str, font_size: f32) -> Vec2 {
+    let columns = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    let lines = text.lines().count();
+    Vec2::new(
+        columns as f32 * font_size * 0.6,
+        lines as f32 * font_size * 1.2,
+    )
+}
+
+/// Top-left corner, in window pixels (origin bottom-left), for a tooltip of `size`
+/// next to the `cursor`: below and to the right of it, flipped to the other side on
+/// either axis where that would leave the `window`, and pushed back inside it if it
+/// fits on neither.
+pub fn place_tooltip(cursor: Vec2, size: Vec2, window: Vec2, offset: Vec2) -> Vec2 {
+    let mut x = cursor.x() + offset.x();
+    if x + size.x() > window.x() {
+        x = cursor.x() - offset.x() - size.x();
+    }
+    let mut top = cursor.y() - offset.y();
+    if top - size.y() < 0.0 {
+        top = cursor.y() + offset.y() + size.y();
+    }
+    Vec2::new(
+        x.min(window.x() - size.x()).max(0.0),
+        top.max(size.y()).min(window.y()),
+    )
+}
+
+/// Shows the hovered target's [`RadarDisplay::tooltip`] next to the cursor, following
+/// it until the cursor leaves the marker. Needs
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin) for hover events and
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin) for the label font.
+pub struct TooltipPlugin;
+
+impl Plugin for TooltipPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<TooltipStyle>() {
+            app.init_resource::<TooltipStyle>();
+        }
+        if !app.resources().contains::<CoordFormat>() {
+            app.init_resource::<CoordFormat>();
+        }
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        app.add_system(tooltip_system.system());
+    }
+}
+
+#[derive(Default)]
+struct TooltipState {
+    reader: EventReader<DisplayEvent>,
+    shown: Option<Entity>,
+    target: Option<i32>,
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn tooltip_system(
+    mut commands: Commands,
+    mut state: Local<TooltipState>,
+    events: Res<Events<DisplayEvent>>,
+    cursor: Res<CursorPosition>,
+    windows: Res<Windows>,
+    display: Res<RadarDisplay>,
+    format: Res<CoordFormat>,
+    style: Res<TooltipStyle>,
+    theme: Res<Theme>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    targets: Query<&Target>,
+    cameras: Query<(&Camera, &Transform)>,
+    mut tooltips: Query<With<Tooltip, (Mut<Transform>, Mut<Text>)>>,
+) {
+    let hovered = state
+        .reader
+        .iter(&events)
+        .filter_map(|event| match event {
+            DisplayEvent::Hovered { target } => Some(*target),
+            _ => None,
+        })
+        .next_back();
+    if let Some(hovered) = hovered {
+        if let Some(entity) = state.shown.take() {
+            commands.despawn(entity);
+        }
+        state.target = hovered;
+    }
+    let target = match state
+        .target
+        .and_then(|id| targets.iter().find(|t| t.id == id))
+    {
+        Some(target) => target,
+        None => return,
+    };
+    let (window, screen) = match (windows.get_primary(), cursor.screen) {
+        (Some(window), Some(screen)) => (window, screen),
+        _ => return,
+    };
+    let camera = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, camera)) => camera,
+        None => return,
+    };
+
+    let text = display.tooltip(target, *format);
+    let window_size = Vec2::new(window.width() as f32, window.height() as f32);
+    let size = estimate_size(&text, style.font_size);
+    let corner = place_tooltip(screen, size, window_size, style.offset);
+    let translation = screen_to_world(corner, window_size, camera).extend(0.0);
+
+    if let Some(entity) = state.shown {
+        if let Ok((mut transform, mut shown)) = tooltips.get_mut(entity) {
+            transform.translation = translation;
+            if shown.value != text {
+                shown.value = text;
+            }
+        }
+        return;
+    }
+    let entity = commands
+        .spawn(TextComponents {
+            text: Text {
+                value: text,
+                font: asset_server.load(label_font.0),
+                style: TextStyle {
+                    font_size: style.font_size,
+                    color: theme.text(),
+                },
+            },
+            transform: Transform::from_translation(translation),
+            ..Default::default()
+        })
+        .with(MainPass)
+        .with(Tooltip { target: target.id })
+        .current_entity();
+    state.shown = entity;
+}
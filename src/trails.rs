@@ -0,0 +1,256 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::display::Poi;
+use crate::easing::{Easing, EasingConfig};
+use crate::layers::Layer;
+use crate::layout::LayoutConfig;
+use crate::palette::Ramp;
+use crate::target::Target;
+
+const TRAIL_ALPHA: f32 = 0.4;
+/// Opacity levels a fading trail is drawn with; it is redrawn as samples age past each.
+const FADE_STEPS: u32 = 8;
+
+/// How far the display is turned from true north-up, in radians, e.g. by a heading-up
+/// mode following own-ship heading: something at true azimuth `a` is drawn at
+/// `a - heading`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DisplayHeading(pub f32);
+
+/// How much of each target's past [`TrailsPlugin`] draws.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailSettings {
+    /// Most positions kept per target.
+    pub length: usize,
+    /// Seconds over which a position fades out, after which it is dropped. Zero keeps
+    /// positions at full trail opacity until pushed out by `length`.
+    pub fade: f32,
+    /// Fewest seconds between kept positions; reports in between only move the head of
+    /// the trail.
+    pub interval: f32,
+    /// How opacity falls off along the trail as positions age.
+    pub easing: Easing,
+    /// Colors the trail by age through this ramp, the head at its top end and the
+    /// oldest positions at the bottom, rather than in the marker's color.
+    pub ramp: Option<Ramp>,
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        TrailSettings {
+            length: 16,
+            fade: 30.0,
+            interval: 1.0,
+            easing: EasingConfig::default().trail,
+            ramp: None,
+        }
+    }
+}
+
+/// A target's reported positions sampled for its trail, in true coordinates.
+#[derive(Debug, Default, Clone)]
+pub struct TrailHistory {
+    samples: VecDeque<(f64, Vec2)>,
+}
+
+impl TrailHistory {
+    /// Keeps the target's position at `time` seconds if `settings.interval` has passed
+    /// since the last kept one, dropping the oldest beyond `settings.length`.
+    pub fn record(&mut self, time: f64, target: &Target, settings: &TrailSettings) {
+        let due = self
+            .samples
+            .back()
+            .is_none_or(|&(last, _)| time - last >= settings.interval as f64);
+        if due {
+            self.samples.push_back((time, true_position(target)));
+        }
+        while self.samples.len() > settings.length {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The kept positions with the time they were reported, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = (f64, Vec2)> + '_ {
+        self.samples.iter().copied()
+    }
+}
+
+/// Marks a trail segment drawn for the marker entity `of`.
+#[derive(Debug, Clone, Copy)]
+pub struct TrailLine {
+    pub of: Entity,
+}
+
+/// Draws each moving target's last reported positions as a fading line in true
+/// (azimuth, distance) space, on [`Layer::Trails`], so the trail shows where the target
+/// has really been rather than where the layout put its marker. What is kept and how it
+/// fades is set by the [`TrailSettings`] resource. The trails follow the
+/// [`DisplayHeading`], so they stay geographically correct while the display turns.
+pub struct TrailsPlugin;
+
+impl Plugin for TrailsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<DisplayHeading>() {
+            app.init_resource::<DisplayHeading>();
+        }
+        if !app.resources().contains::<TrailSettings>() {
+            app.init_resource::<TrailSettings>();
+        }
+        app.add_system(trail_sample_system.system())
+            .add_system(trail_system.system());
+    }
+}
+
+fn true_position(target: &Target) -> Vec2 {
+    Vec2::new(
+        target.dist * target.azimuth.cos(),
+        target.dist * target.azimuth.sin(),
+    )
+}
+
+fn trail_sample_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<TrailSettings>,
+    mut targets: Query<(Entity, Changed<Target>, Option<Mut<TrailHistory>>)>,
+) {
+    let now = time.seconds_since_startup;
+    for (entity, target, history) in targets.iter_mut() {
+        match history {
+            Some(mut history) => history.record(now, &target, &settings),
+            None => {
+                let mut history = TrailHistory::default();
+                history.record(now, &target, &settings);
+                commands.insert_one(entity, history);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct DrawnTrails {
+    heading: Option<DisplayHeading>,
+    settings: Option<TrailSettings>,
+    config: Option<LayoutConfig>,
+    fade_step: Option<i64>,
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn trail_system(
+    mut commands: Commands,
+    mut drawn: Local<DrawnTrails>,
+    time: Res<Time>,
+    heading: Res<DisplayHeading>,
+    settings: Res<TrailSettings>,
+    config: Res<LayoutConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    changed: Query<With<Poi, (Entity, Or<(Changed<TrailHistory>, Changed<Target>)>)>>,
+    markers: Query<With<Poi, (Entity, &Target, &Handle<ColorMaterial>, &TrailHistory)>>,
+    trails: Query<(Entity, &TrailLine)>,
+) {
+    let now = time.seconds_since_startup;
+    let fade_step = if settings.fade > 0.0 {
+        Some((now * FADE_STEPS as f64 / settings.fade as f64).floor() as i64)
+    } else {
+        None
+    };
+    let redraw_all = drawn.heading != Some(*heading)
+        || drawn.settings != Some(*settings)
+        || drawn.config != Some(*config)
+        || drawn.fade_step != fade_step;
+    drawn.heading = Some(*heading);
+    drawn.settings = Some(*settings);
+    drawn.config = Some(*config);
+    drawn.fade_step = fade_step;
+    let dirty: HashSet<Entity> = if redraw_all {
+        trails
+            .iter()
+            .map(|(_, trail)| trail.of)
+            .chain(markers.iter().map(|(entity, ..)| entity))
+            .collect()
+    } else {
+        changed
+            .iter()
+            .map(|(entity, _)| entity)
+            .chain(markers.removed::<Target>().iter().copied())
+            .collect()
+    };
+    if dirty.is_empty() {
+        return;
+    }
+    for (entity, trail) in trails.iter() {
+        if dirty.contains(&trail.of) {
+            commands.despawn(entity);
+        }
+    }
+
+    // True positions are drawn where the rings put their distance, turned by the heading.
+    let to_display = |p: Vec2| {
+        let radius = config.ring_position(p.length()) * config.ring_spacing;
+        let azimuth = p.y().atan2(p.x()) - heading.0;
+        Vec2::new(radius * azimuth.cos(), radius * azimuth.sin())
+    };
+    for (entity, target, material, history) in markers.iter() {
+        if !dirty.contains(&entity) {
+            continue;
+        }
+        // The head of the trail is the latest report, kept or not.
+        let mut points = history
+            .samples()
+            .filter(|&(at, _)| settings.fade <= 0.0 || now - at < settings.fade as f64)
+            .map(|(at, p)| ((now - at) as f32, to_display(p)))
+            .collect::<Vec<_>>();
+        points.push((0.0, to_display(true_position(target))));
+        if points.len() < 2 {
+            continue;
+        }
+
+        // One path per opacity level, a segment taking the level of its older end.
+        let level = |age: f32| {
+            if settings.fade > 0.0 {
+                ((age / settings.fade * FADE_STEPS as f32) as u32).min(FADE_STEPS - 1)
+            } else {
+                0
+            }
+        };
+        let color = materials.get(material).map_or(Color::WHITE, |m| m.color);
+        let ramp = settings.ramp.map(Ramp::ramp);
+        let mut builders = (0..FADE_STEPS).map(|_| None).collect::<Vec<_>>();
+        for pair in points.windows(2) {
+            let ((age, from), (_, to)) = (pair[0], pair[1]);
+            if from == to {
+                continue;
+            }
+            let builder = builders[level(age) as usize].get_or_insert_with(PathBuilder::new);
+            builder.move_to(point(from.x(), from.y()));
+            builder.line_to(point(to.x(), to.y()));
+        }
+        for (level, builder) in builders.into_iter().enumerate() {
+            let builder = match builder {
+                Some(builder) => builder,
+                None => continue,
+            };
+            let mut color = ramp.as_ref().map_or(color, |ramp| {
+                ramp.color(1.0 - level as f32 / (FADE_STEPS - 1) as f32)
+            });
+            let fade = settings
+                .easing
+                .lerp(1.0, 0.0, level as f32 / FADE_STEPS as f32);
+            color.set_a(color.a() * TRAIL_ALPHA * fade.clamp(0.0, 1.0));
+            let line = builder.build().stroke(
+                materials.add(color.into()),
+                &mut meshes,
+                Vec3::zero(),
+                &StrokeOptions::default(),
+            );
+            commands
+                .spawn(line)
+                .with(TrailLine { of: entity })
+                .with(Layer::Trails);
+        }
+    }
+}
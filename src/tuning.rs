@@ -0,0 +1,453 @@
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::camera::CameraControls;
+use crate::display::{restyle_leaders, LabelFont, RadarDisplay, Slot};
+use crate::layout::LayoutConfig;
+use crate::motion::{LeaderLine, PolarTween};
+use crate::style::LineStyle;
+use crate::sweep::Sweep;
+use crate::target::Target;
+use crate::theme::Theme;
+
+const FONT_SIZE: f32 = 14.0;
+const ROW_HEIGHT: f32 = 18.0;
+const PADDING: f32 = 4.0;
+const NAME_WIDTH: f32 = 130.0;
+const VALUE_WIDTH: f32 = 60.0;
+const BUTTON_WIDTH: f32 = 20.0;
+
+/// A setting on the [`TuningPlugin`] panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Knob {
+    PoiWidth,
+    RingSpacing,
+    Scatter,
+    Stagger,
+    LeaderRouting,
+    LeaderWidth,
+    LeaderToEdge,
+    SweepSpeed,
+    Afterglow,
+    MinZoom,
+    MaxZoom,
+    ZoomStep,
+}
+
+/// What a [`Knob`] is set to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KnobValue {
+    Number(f32),
+    Toggle(bool),
+}
+
+impl KnobValue {
+    /// As the panel shows it.
+    pub fn text(self) -> String {
+        match self {
+            KnobValue::Number(value) => format!("{:.2}", value),
+            KnobValue::Toggle(true) => "on".to_string(),
+            KnobValue::Toggle(false) => "off".to_string(),
+        }
+    }
+}
+
+/// The values the knobs read and adjust, copied out of their resources. The leader
+/// line knobs read the fallback style's and set those of every category.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub layout: LayoutConfig,
+    pub leader: LineStyle,
+    pub sweep: Sweep,
+    pub camera: CameraControls,
+}
+
+impl Knob {
+    pub const ALL: [Knob; 12] = [
+        Knob::PoiWidth,
+        Knob::RingSpacing,
+        Knob::Scatter,
+        Knob::Stagger,
+        Knob::LeaderRouting,
+        Knob::LeaderWidth,
+        Knob::LeaderToEdge,
+        Knob::SweepSpeed,
+        Knob::Afterglow,
+        Knob::MinZoom,
+        Knob::MaxZoom,
+        Knob::ZoomStep,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Knob::PoiWidth => "marker width",
+            Knob::RingSpacing => "ring spacing",
+            Knob::Scatter => "scatter",
+            Knob::Stagger => "stagger",
+            Knob::LeaderRouting => "route leaders",
+            Knob::LeaderWidth => "leader width",
+            Knob::LeaderToEdge => "leaders to edge",
+            Knob::SweepSpeed => "sweep \u{b0}/s",
+            Knob::Afterglow => "afterglow s",
+            Knob::MinZoom => "min zoom",
+            Knob::MaxZoom => "max zoom",
+            Knob::ZoomStep => "zoom step",
+        }
+    }
+
+    pub fn is_toggle(self) -> bool {
+        matches!(
+            self,
+            Knob::Stagger | Knob::LeaderRouting | Knob::LeaderToEdge
+        )
+    }
+
+    /// Step, lower and upper limit of a number knob; `None` for a toggle.
+    fn range(self, settings: &Settings) -> Option<(f32, f32, f32)> {
+        let camera = settings.camera;
+        match self {
+            Knob::PoiWidth => Some((1.0, 4.0, 100.0)),
+            Knob::RingSpacing => Some((2.0, 4.0, 200.0)),
+            Knob::Scatter => Some((0.05, 0.5, 4.0)),
+            Knob::LeaderWidth => Some((0.5, 0.5, 10.0)),
+            Knob::SweepSpeed => Some((15.0, -720.0, 720.0)),
+            Knob::Afterglow => Some((0.25, 0.0, 10.0)),
+            Knob::MinZoom => Some((0.05, 0.05, camera.max_zoom)),
+            Knob::MaxZoom => Some((1.0, camera.min_zoom, 50.0)),
+            Knob::ZoomStep => Some((0.05, 1.05, 3.0)),
+            Knob::Stagger | Knob::LeaderRouting | Knob::LeaderToEdge => None,
+        }
+    }
+
+    pub fn value(self, settings: &Settings) -> KnobValue {
+        let (layout, leader, sweep, camera) = (
+            &settings.layout,
+            &settings.leader,
+            &settings.sweep,
+            &settings.camera,
+        );
+        match self {
+            Knob::PoiWidth => KnobValue::Number(layout.poi_width),
+            Knob::RingSpacing => KnobValue::Number(layout.ring_spacing),
+            Knob::Scatter => KnobValue::Number(layout.scatter),
+            Knob::Stagger => KnobValue::Toggle(layout.stagger),
+            Knob::LeaderRouting => KnobValue::Toggle(layout.leader_routing),
+            Knob::LeaderWidth => KnobValue::Number(leader.width),
+            Knob::LeaderToEdge => KnobValue::Toggle(leader.to_marker_edge),
+            Knob::SweepSpeed => KnobValue::Number(sweep.speed.to_degrees()),
+            Knob::Afterglow => KnobValue::Number(sweep.afterglow),
+            Knob::MinZoom => KnobValue::Number(camera.min_zoom),
+            Knob::MaxZoom => KnobValue::Number(camera.max_zoom),
+            Knob::ZoomStep => KnobValue::Number(camera.zoom_step),
+        }
+    }
+
+    /// Moves a number knob `steps` steps, down when negative, within its limits, or
+    /// flips a toggle whatever `steps` is.
+    pub fn adjust(self, settings: &mut Settings, steps: i32) {
+        let number = match (self.value(settings), self.range(settings)) {
+            (KnobValue::Number(value), Some((step, min, max))) => {
+                (value + step * steps as f32).max(min).min(max)
+            }
+            _ => 0.0,
+        };
+        let (layout, leader, sweep, camera) = (
+            &mut settings.layout,
+            &mut settings.leader,
+            &mut settings.sweep,
+            &mut settings.camera,
+        );
+        match self {
+            Knob::PoiWidth => layout.poi_width = number,
+            Knob::RingSpacing => layout.ring_spacing = number,
+            Knob::Scatter => layout.scatter = number,
+            Knob::Stagger => layout.stagger = !layout.stagger,
+            Knob::LeaderRouting => layout.leader_routing = !layout.leader_routing,
+            Knob::LeaderWidth => leader.width = number,
+            Knob::LeaderToEdge => leader.to_marker_edge = !leader.to_marker_edge,
+            Knob::SweepSpeed => sweep.speed = number.to_radians(),
+            Knob::Afterglow => sweep.afterglow = number,
+            Knob::MinZoom => camera.min_zoom = number,
+            Knob::MaxZoom => camera.max_zoom = number,
+            Knob::ZoomStep => camera.zoom_step = number,
+        }
+    }
+}
+
+/// Whether the [`TuningPlugin`] panel is shown; T shows and hides it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    pub visible: bool,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning { visible: true }
+    }
+}
+
+/// Marks the root node of the [`TuningPlugin`] panel.
+pub struct TuningPanel;
+
+/// A button adjusting `knob` by `steps`, see [`Knob::adjust`].
+struct KnobButton {
+    knob: Knob,
+    steps: i32,
+}
+
+/// The text showing a knob's value.
+struct KnobText(Knob);
+
+/// A panel at the top left of the window for tuning the display while it runs, on
+/// bevy_ui nodes: the marker width, ring spacing, scatter, staggering and leader
+/// routing of the [`LayoutConfig`], the width of every category's leader lines and
+/// whether they end at the marker edge, the [`Sweep`] speed and afterglow, and the
+/// [`CameraControls`] zoom limits and step. Each number has `-` and `+` buttons and
+/// each toggle flips when its value is clicked. Changes take effect at once: the
+/// display lays out again for a new config and leader lines are restyled in place.
+/// [`Action::ToggleTuning`], T by default, shows and hides the panel, see [`Tuning`].
+/// Needs a UI camera and [`PoiRingPlugin`](crate::display::PoiRingPlugin).
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<Tuning>() {
+            app.init_resource::<Tuning>();
+        }
+        if !app.resources().contains::<Sweep>() {
+            app.init_resource::<Sweep>();
+        }
+        if !app.resources().contains::<CameraControls>() {
+            app.init_resource::<CameraControls>();
+        }
+        app.add_system(tuning_panel_system.system())
+            .add_system(tuning_input_system.system())
+            .add_system(tuning_sync_system.system());
+    }
+}
+
+fn settings(
+    layout: &LayoutConfig,
+    display: &RadarDisplay,
+    sweep: &Sweep,
+    camera: &CameraControls,
+) -> Settings {
+    Settings {
+        layout: *layout,
+        leader: display.categories().fallback.leader,
+        sweep: *sweep,
+        camera: *camera,
+    }
+}
+
+fn text(value: String, font: Handle<Font>, color: Color) -> TextComponents {
+    TextComponents {
+        text: Text {
+            value,
+            font,
+            style: TextStyle {
+                font_size: FONT_SIZE,
+                color,
+            },
+        },
+        ..Default::default()
+    }
+}
+
+fn cell_style(width: f32) -> Style {
+    Style {
+        size: Size::new(Val::Px(width), Val::Px(ROW_HEIGHT)),
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        ..Default::default()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn tuning_panel_system(
+    mut commands: Commands,
+    mut shown: Local<Option<Theme>>,
+    actions: Res<ActionState>,
+    theme: Res<Theme>,
+    label_font: Res<LabelFont>,
+    asset_server: Res<AssetServer>,
+    mut tuning: ResMut<Tuning>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    panels: Query<With<TuningPanel, Entity>>,
+) {
+    if actions.just_pressed(Action::ToggleTuning) {
+        tuning.visible = !tuning.visible;
+    }
+    let wanted = Some(*theme).filter(|_| tuning.visible);
+    if *shown == wanted {
+        return;
+    }
+    for panel in panels.iter() {
+        commands.despawn_recursive(panel);
+    }
+    *shown = wanted;
+    if !tuning.visible {
+        return;
+    }
+    let font = asset_server.load(label_font.0);
+    let mut backdrop = theme.background();
+    backdrop.set_a(0.85);
+    let mut button = theme.stroke();
+    button.set_a(0.35);
+    let transparent = materials.add(Color::NONE.into());
+    let button = materials.add(button.into());
+    let color = theme.text();
+    commands
+        .spawn(NodeComponents {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..Default::default()
+                },
+                // bevy_ui's y points up, so this stacks the rows top down.
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(PADDING)),
+                ..Default::default()
+            },
+            material: materials.add(backdrop.into()),
+            ..Default::default()
+        })
+        .with(TuningPanel)
+        // Takes the clicks between the buttons away from the display too.
+        .with(Interaction::default())
+        .with(FocusPolicy::Block)
+        .with_children(|panel| {
+            for &knob in &Knob::ALL {
+                let toggle = knob.is_toggle();
+                panel
+                    .spawn(NodeComponents {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: transparent.clone(),
+                        ..Default::default()
+                    })
+                    .with(FocusPolicy::Pass)
+                    .with_children(|row| {
+                        row.spawn(NodeComponents {
+                            style: Style {
+                                size: Size::new(Val::Px(NAME_WIDTH), Val::Px(ROW_HEIGHT)),
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            material: transparent.clone(),
+                            ..Default::default()
+                        })
+                        .with(FocusPolicy::Pass)
+                        .with_children(|cell| {
+                            cell.spawn(text(knob.name().to_string(), font.clone(), color));
+                        });
+                        let value = if toggle {
+                            row.spawn(ButtonComponents {
+                                style: cell_style(VALUE_WIDTH),
+                                material: button.clone(),
+                                ..Default::default()
+                            })
+                            .with(KnobButton { knob, steps: 0 })
+                        } else {
+                            row.spawn(NodeComponents {
+                                style: cell_style(VALUE_WIDTH),
+                                material: transparent.clone(),
+                                ..Default::default()
+                            })
+                            .with(FocusPolicy::Pass)
+                        };
+                        value.with_children(|cell| {
+                            cell.spawn(text(String::new(), font.clone(), color))
+                                .with(KnobText(knob));
+                        });
+                        if toggle {
+                            return;
+                        }
+                        for &(label, steps) in &[("-", -1), ("+", 1)] {
+                            row.spawn(ButtonComponents {
+                                style: cell_style(BUTTON_WIDTH),
+                                material: button.clone(),
+                                ..Default::default()
+                            })
+                            .with(KnobButton { knob, steps })
+                            .with_children(|cell| {
+                                cell.spawn(text(label.to_string(), font.clone(), color));
+                            });
+                        }
+                    });
+            }
+        });
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn tuning_input_system(
+    mut layout: ResMut<LayoutConfig>,
+    mut display: ResMut<RadarDisplay>,
+    mut sweep: ResMut<Sweep>,
+    mut camera: ResMut<CameraControls>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    buttons: Query<(Mutated<Interaction>, &KnobButton)>,
+    targets: Query<&Target>,
+    mut leaders: Query<(
+        &Slot,
+        Mut<LeaderLine>,
+        &PolarTween,
+        &Handle<Mesh>,
+        &Handle<ColorMaterial>,
+    )>,
+) {
+    let before = settings(&layout, &display, &sweep, &camera);
+    let mut after = before;
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Clicked {
+            button.knob.adjust(&mut after, button.steps);
+        }
+    }
+    // Only the resources that changed are written, so the rest don't look changed.
+    if after.layout != before.layout {
+        *layout = after.layout;
+    }
+    if after.sweep != before.sweep {
+        *sweep = after.sweep;
+    }
+    if after.camera != before.camera {
+        *camera = after.camera;
+    }
+    if after.leader == before.leader {
+        return;
+    }
+    for style in display.categories_mut().all_mut() {
+        style.leader.width = after.leader.width;
+        style.leader.to_marker_edge = after.leader.to_marker_edge;
+    }
+    restyle_leaders(
+        display.categories(),
+        layout.poi_width,
+        &targets,
+        &mut leaders,
+        &mut meshes,
+    );
+}
+
+fn tuning_sync_system(
+    layout: Res<LayoutConfig>,
+    display: Res<RadarDisplay>,
+    sweep: Res<Sweep>,
+    camera: Res<CameraControls>,
+    mut texts: Query<(Mut<Text>, &KnobText)>,
+) {
+    let settings = settings(&layout, &display, &sweep, &camera);
+    for (mut text, knob) in texts.iter_mut() {
+        let value = knob.0.value(&settings).text();
+        if text.value != value {
+            text.value = value;
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::measure::{MeasurePoint, Measurement};
+use crate::scenario::Scenario;
+use crate::target::Target;
+use crate::zones::{AlertZone, AlertZones};
+
+/// An interactive edit, recorded so that [`UndoPlugin`] can take it back and make it
+/// again.
+#[derive(Debug, Clone)]
+pub enum EditCommand {
+    /// A target was added by clicking empty space.
+    AddTarget(Target),
+    /// A target was dragged from one true `(azimuth, dist)` to another.
+    MoveTarget {
+        id: i32,
+        from: (f32, f32),
+        to: (f32, f32),
+    },
+    /// A zone was added at this index of [`AlertZones::zones`].
+    AddZone(usize, AlertZone),
+    /// The zone at this index of [`AlertZones::zones`] was removed.
+    RemoveZone(usize, AlertZone),
+    /// The ends of the [`Measurement`] changed, as `(from, to)`.
+    Measure {
+        before: (Option<MeasurePoint>, Option<MeasurePoint>),
+        after: (Option<MeasurePoint>, Option<MeasurePoint>),
+    },
+}
+
+impl EditCommand {
+    /// What the edit did, for printing.
+    pub fn describe(&self) -> String {
+        match self {
+            EditCommand::AddTarget(target) => format!("add target #{}", target.id),
+            EditCommand::MoveTarget { id, .. } => format!("move target #{}", id),
+            EditCommand::AddZone(_, zone) => format!("add zone {}", zone.name),
+            EditCommand::RemoveZone(_, zone) => format!("remove zone {}", zone.name),
+            EditCommand::Measure { .. } => "measure".to_string(),
+        }
+    }
+}
+
+/// The edits made so far, and those undone since, newest last. The editing tools push
+/// to it as they go; [`UndoPlugin`] pops.
+#[derive(Debug, Clone)]
+pub struct UndoStack {
+    /// Most edits kept; the oldest are forgotten past it.
+    pub limit: usize,
+    done: Vec<EditCommand>,
+    undone: Vec<EditCommand>,
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        UndoStack {
+            limit: 100,
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+}
+
+impl UndoStack {
+    /// Records an edit just made, forgetting those undone before it.
+    pub fn push(&mut self, command: EditCommand) {
+        self.done.push(command);
+        self.undone.clear();
+        if self.done.len() > self.limit {
+            let over = self.done.len() - self.limit;
+            self.done.drain(..over);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// The latest edit, moved over to be redone.
+    fn undo(&mut self) -> Option<EditCommand> {
+        let command = self.done.pop()?;
+        self.undone.push(command.clone());
+        Some(command)
+    }
+
+    /// The latest edit undone, moved back over to be undone again.
+    fn redo(&mut self) -> Option<EditCommand> {
+        let command = self.undone.pop()?;
+        self.done.push(command.clone());
+        Some(command)
+    }
+}
+
+/// Undoes the latest edit on the [`UndoStack`] on [`Action::Undo`], Ctrl+Z by default,
+/// and redoes it on [`Action::Redo`], Ctrl+Y or Ctrl+Shift+Z: targets added and dragged with
+/// [`EditPlugin`](crate::edit::EditPlugin), zones drawn with
+/// [`AlertZonesPlugin`](crate::zones::AlertZonesPlugin) and measurements of
+/// [`MeasurePlugin`](crate::measure::MeasurePlugin). Undoing keeps the [`Scenario`] in
+/// step, so an export after it leaves the edit out.
+pub struct UndoPlugin;
+
+impl Plugin for UndoPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<UndoStack>() {
+            app.init_resource::<UndoStack>();
+        }
+        if !app.resources().contains::<Scenario>() {
+            app.init_resource::<Scenario>();
+        }
+        if !app.resources().contains::<AlertZones>() {
+            app.init_resource::<AlertZones>();
+        }
+        if !app.resources().contains::<Measurement>() {
+            app.init_resource::<Measurement>();
+        }
+        app.add_system(undo_system.system());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn undo_system(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    mut stack: ResMut<UndoStack>,
+    mut scenario: ResMut<Scenario>,
+    mut zones: ResMut<AlertZones>,
+    mut measurement: ResMut<Measurement>,
+    mut targets: Query<(Entity, Mut<Target>)>,
+) {
+    let (command, forward) = if actions.just_pressed(Action::Redo) {
+        (stack.redo(), true)
+    } else if actions.just_pressed(Action::Undo) {
+        (stack.undo(), false)
+    } else {
+        return;
+    };
+    let command = match command {
+        Some(command) => command,
+        None => return,
+    };
+    tracing::info!(
+        "{}: {}",
+        if forward { "redo" } else { "undo" },
+        command.describe()
+    );
+    match command {
+        EditCommand::AddTarget(target) if forward => {
+            scenario.targets.push(target.clone());
+            commands.spawn((target,));
+        }
+        EditCommand::AddTarget(target) => {
+            scenario.targets.retain(|saved| saved.id != target.id);
+            for (entity, live) in targets.iter_mut() {
+                if live.id == target.id {
+                    commands.despawn(entity);
+                }
+            }
+        }
+        EditCommand::MoveTarget { id, from, to } => {
+            let (azimuth, dist) = if forward { to } else { from };
+            for (_, mut target) in targets.iter_mut() {
+                if target.id == id {
+                    target.azimuth = azimuth;
+                    target.dist = dist;
+                }
+            }
+            if let Some(saved) = scenario.targets.iter_mut().find(|t| t.id == id) {
+                saved.azimuth = azimuth;
+                saved.dist = dist;
+            }
+        }
+        EditCommand::AddZone(index, zone) if forward => insert_zone(&mut zones, index, zone),
+        EditCommand::RemoveZone(index, zone) if !forward => insert_zone(&mut zones, index, zone),
+        EditCommand::AddZone(_, zone) | EditCommand::RemoveZone(_, zone) => {
+            if let Some(at) = zones.zones.iter().position(|z| *z == zone) {
+                zones.zones.remove(at);
+            }
+        }
+        EditCommand::Measure { before, after } => {
+            let (from, to) = if forward { after } else { before };
+            measurement.from = from;
+            measurement.to = to;
+        }
+    }
+}
+
+fn insert_zone(zones: &mut AlertZones, index: usize, zone: AlertZone) {
+    let index = index.min(zones.zones.len());
+    zones.zones.insert(index, zone);
+}
@@ -0,0 +1,487 @@
+use thiserror::Error;
+
+use crate::geo::bearing_to_azimuth;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParseError {
+    #[error("nothing to parse")]
+    Empty,
+    #[error("{0:?} is not a number")]
+    Number(String),
+    #[error("unknown unit {unit:?}, expected one of {known}")]
+    UnknownUnit { unit: String, known: String },
+    #[error("{input:?} is not an angle: {reason}")]
+    Angle { input: String, reason: &'static str },
+    #[error("{input:?} is not a position: {reason}")]
+    Position { input: String, reason: &'static str },
+    #[error("{what} {value} is out of range, must be within \u{b1}{max}")]
+    OutOfRange {
+        what: &'static str,
+        value: f32,
+        max: f32,
+    },
+    #[error("{what} {value} must not be negative")]
+    Negative { what: &'static str, value: f32 },
+}
+
+/// A position as written by a user or a data source, in crate units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    /// Bearing and range from the sensor, in the units of `Target::azimuth` and
+    /// `Target::dist`.
+    Polar { azimuth: f32, dist: f32 },
+    /// Degrees, north and east positive, as in [`GeoPoint`](crate::target::GeoPoint).
+    Geo { lat: f32, lon: f32 },
+}
+
+/// The distance units inputs may be written in, each by its suffix and its size in the
+/// unit `Target::dist` is kept in. Numbers without a suffix are taken as they are. By
+/// default target distances are in meters and `m`, `km`, `ft`, `kft`, `mi` (statute)
+/// and `nm`/`nmi` (nautical) are known; [`DistanceUnits::insert`] adds more.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceUnits {
+    units: Vec<(String, f64)>,
+}
+
+impl Default for DistanceUnits {
+    fn default() -> Self {
+        DistanceUnits::meters_per_unit(1.0)
+    }
+}
+
+impl DistanceUnits {
+    /// The standard units, for target distances that are `meters` long per unit.
+    pub fn meters_per_unit(meters: f64) -> Self {
+        let mut units = DistanceUnits { units: Vec::new() };
+        for &(suffix, size) in &[
+            ("m", 1.0),
+            ("km", 1000.0),
+            ("ft", 0.3048),
+            ("kft", 304.8),
+            ("mi", 1609.344),
+            ("nm", 1852.0),
+            ("nmi", 1852.0),
+        ] {
+            units.insert(suffix, size / meters);
+        }
+        units
+    }
+
+    /// Adds the unit written `suffix`, `size` target distance units long, or resizes
+    /// it. Suffixes are matched without regard to case.
+    pub fn insert(&mut self, suffix: &str, size: f64) {
+        let suffix = suffix.to_lowercase();
+        match self.units.iter_mut().find(|(s, _)| *s == suffix) {
+            Some(unit) => unit.1 = size,
+            None => self.units.push((suffix, size)),
+        }
+    }
+
+    /// Size of the unit written `suffix`, in target distance units.
+    pub fn size(&self, suffix: &str) -> Option<f64> {
+        let suffix = suffix.to_lowercase();
+        self.units.iter().find(|(s, _)| *s == suffix).map(|u| u.1)
+    }
+
+    /// A distance like `12.5nm`, `300 m`, `1e3m` or `4.2`, in target distance units.
+    pub fn parse_distance(&self, input: &str) -> Result<f32, ParseError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let split = number_len(input);
+        let (number, suffix) = (&input[..split], input[split..].trim());
+        let value = number
+            .parse::<f64>()
+            .map_err(|_| ParseError::Number(input.to_string()))?;
+        if suffix.is_empty() {
+            return Ok(value as f32);
+        }
+        match self.size(suffix) {
+            Some(size) => Ok((value * size) as f32),
+            None => Err(ParseError::UnknownUnit {
+                unit: suffix.to_string(),
+                known: self
+                    .units
+                    .iter()
+                    .map(|(s, _)| s.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }),
+        }
+    }
+
+    /// A compass bearing and range like `230/4.2` or `230/4.2nm`, the bearing in
+    /// degrees clockwise from north as [`parse_angle`] reads them. Returns the azimuth
+    /// in radians like `Target::azimuth` and the range, which may not be negative, in
+    /// target distance units.
+    pub fn parse_polar(&self, input: &str) -> Result<(f32, f32), ParseError> {
+        let (bearing, range) = input.split_once('/').ok_or_else(|| ParseError::Position {
+            input: input.to_string(),
+            reason: "expected BEARING/RANGE",
+        })?;
+        let azimuth = bearing_to_azimuth(parse_angle(bearing)?);
+        let range = self.parse_distance(range)?;
+        if range < 0.0 {
+            return Err(ParseError::Negative {
+                what: "range",
+                value: range,
+            });
+        }
+        Ok((azimuth, range))
+    }
+
+    /// A bearing and range as [`parse_polar`](DistanceUnits::parse_polar) reads them,
+    /// or else a geodetic position as [`parse_geo`] does.
+    pub fn parse_position(&self, input: &str) -> Result<Position, ParseError> {
+        if input.contains('/') {
+            let (azimuth, dist) = self.parse_polar(input)?;
+            Ok(Position::Polar { azimuth, dist })
+        } else {
+            let (lat, lon) = parse_geo(input)?;
+            Ok(Position::Geo { lat, lon })
+        }
+    }
+}
+
+/// Length of the number `input` starts with, taking an exponent as part of it when
+/// digits follow the `e`, so that `1e3m` is `1e3` meters.
+fn number_len(input: &str) -> usize {
+    let bytes = input.as_bytes();
+    let digit_at = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+    let mut len = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    while digit_at(len) || bytes.get(len) == Some(&b'.') {
+        len += 1;
+    }
+    if matches!(bytes.get(len), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(len + 1), Some(b'+' | b'-')));
+        if digit_at(len + 1 + sign) {
+            len += 1 + sign;
+            while digit_at(len) {
+                len += 1;
+            }
+        }
+    }
+    len
+}
+
+/// An angle in degrees, written as decimal degrees (`123.4`, `123.4°`) or in degrees,
+/// minutes and seconds (`123°24'`, `123°24'30.5"`, also with `′` and `″`). A leading
+/// `-` makes it negative.
+pub fn parse_angle(input: &str) -> Result<f32, ParseError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let error = |reason| ParseError::Angle {
+        input: input.trim().to_string(),
+        reason,
+    };
+    let (negative, rest) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    // Degrees, minutes and seconds in order; each part may only follow the one before.
+    let mut parts = [None::<f64>; 3];
+    let mut next = 0;
+    let mut number = String::new();
+    let mut take = |part: usize, number: &mut String, next: &mut usize| {
+        if part < *next || number.trim().is_empty() {
+            return Err(error("expected degrees, then minutes, then seconds"));
+        }
+        let value = number
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| error("expected numbers"))?;
+        parts[part] = Some(value);
+        number.clear();
+        *next = part + 1;
+        Ok(())
+    };
+    for c in rest.chars() {
+        match c {
+            '\u{b0}' | '\u{ba}' => take(0, &mut number, &mut next)?,
+            '\'' | '\u{2032}' => take(1, &mut number, &mut next)?,
+            '"' | '\u{2033}' => take(2, &mut number, &mut next)?,
+            c if c.is_ascii_digit() || c == '.' || c.is_whitespace() => number.push(c),
+            _ => return Err(error("unexpected character")),
+        }
+    }
+    if !number.trim().is_empty() {
+        // A bare number is degrees, or what follows the last part written.
+        let part = next;
+        if part > 2 {
+            return Err(error("nothing may follow the seconds"));
+        }
+        take(part, &mut number, &mut next)?;
+    }
+    let last = parts
+        .iter()
+        .rposition(Option::is_some)
+        .ok_or_else(|| error("no degrees"))?;
+    if parts[..last].iter().flatten().any(|p| p.fract() != 0.0) {
+        return Err(error("only the last part may have a fraction"));
+    }
+    let [degrees, minutes, seconds] = parts;
+    let (minutes, seconds) = (minutes.unwrap_or(0.0), seconds.unwrap_or(0.0));
+    if minutes >= 60.0 || seconds >= 60.0 {
+        return Err(error("minutes and seconds must be below 60"));
+    }
+    let degrees = degrees.ok_or_else(|| error("no degrees"))? + minutes / 60.0 + seconds / 3600.0;
+    Ok((if negative { -degrees } else { degrees }) as f32)
+}
+
+/// A geodetic position in degrees, north and east positive. Each coordinate is an
+/// angle as [`parse_angle`] reads it, with its hemisphere letter before or after it,
+/// as in `48°51'N 2°21'E`, `N48.85 E2.35` or `48.85N, 2.35E`, or signed without
+/// letters, latitude first, as in `48.85, -2.35` or `48° 51' -2° 21'`.
+pub fn parse_geo(input: &str) -> Result<(f32, f32), ParseError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let error = |reason| ParseError::Position {
+        input: trimmed.to_string(),
+        reason,
+    };
+    let hemisphere = |c: char| "NSEWnsew".contains(c);
+    let (lat, lon) = if trimmed.contains(hemisphere) {
+        // Split after the latitude's letter if it follows the number, before the
+        // longitude's letter if it leads.
+        let letters = trimmed
+            .char_indices()
+            .filter(|(_, c)| hemisphere(*c))
+            .collect::<Vec<_>>();
+        let (first, second) = match letters[..] {
+            [first, second] => (first, second),
+            _ => return Err(error("expected one hemisphere letter for each coordinate")),
+        };
+        let leading = trimmed[..first.0].trim().is_empty();
+        let (lat, lon) = if leading {
+            (&trimmed[..second.0], &trimmed[second.0..])
+        } else {
+            (&trimmed[..=first.0], &trimmed[first.0 + 1..])
+        };
+        let coordinate = |part: &str, letter: char| {
+            let number = part
+                .trim()
+                .trim_matches(|c: char| c == ',' || c.is_whitespace())
+                .trim_matches(hemisphere)
+                .trim();
+            parse_angle(number).map(|a| match letter.to_ascii_uppercase() {
+                'S' | 'W' => -a,
+                _ => a,
+            })
+        };
+        match (first.1.to_ascii_uppercase(), second.1.to_ascii_uppercase()) {
+            ('N' | 'S', 'E' | 'W') => (coordinate(lat, first.1)?, coordinate(lon, second.1)?),
+            _ => {
+                return Err(error(
+                    "expected the latitude, N or S, before the longitude, E or W",
+                ))
+            }
+        }
+    } else if let Some((lat, lon)) = trimmed.split_once(',') {
+        (parse_angle(lat)?, parse_angle(lon)?)
+    } else {
+        // Without a comma the coordinates may have spaces inside them too, so take the
+        // one split between words that reads as two angles.
+        let words = trimmed.split_whitespace().collect::<Vec<_>>();
+        let mut splits = (1..words.len()).filter_map(|at| {
+            let lat = parse_angle(&words[..at].join(" ")).ok()?;
+            let lon = parse_angle(&words[at..].join(" ")).ok()?;
+            Some((lat, lon))
+        });
+        match (splits.next(), splits.next()) {
+            (Some(position), None) => position,
+            (Some(_), Some(_)) => {
+                return Err(error("ambiguous, separate the coordinates with ','"))
+            }
+            (None, _) => return Err(error("expected a latitude and a longitude")),
+        }
+    };
+    if lat.abs() > 90.0 {
+        return Err(ParseError::OutOfRange {
+            what: "latitude",
+            value: lat,
+            max: 90.0,
+        });
+    }
+    if lon.abs() > 180.0 {
+        return Err(ParseError::OutOfRange {
+            what: "longitude",
+            value: lon,
+            max: 180.0,
+        });
+    }
+    Ok((lat, lon))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn geo(input: &str) -> (f32, f32) {
+        parse_geo(input).unwrap_or_else(|e| panic!("{:?}: {}", input, e))
+    }
+
+    #[test]
+    fn angles_in_decimal_degrees_and_dms() {
+        assert!(close(parse_angle("123.4").unwrap(), 123.4));
+        assert!(close(parse_angle("123.4°").unwrap(), 123.4));
+        assert!(close(parse_angle("123°24'").unwrap(), 123.4));
+        assert!(close(parse_angle("123°24'36\"").unwrap(), 123.41));
+        assert!(close(
+            parse_angle("123\u{b0}24\u{2032}36\u{2033}").unwrap(),
+            123.41
+        ));
+        assert!(close(parse_angle("-12° 30'").unwrap(), -12.5));
+    }
+
+    #[test]
+    fn malformed_angles_are_rejected() {
+        assert_eq!(parse_angle("  "), Err(ParseError::Empty));
+        for input in ["12.5°30'", "12°75'", "30'12°", "12°30'15\"4", "12x"] {
+            assert!(
+                matches!(parse_angle(input), Err(ParseError::Angle { .. })),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn geo_positions_with_hemisphere_letters() {
+        for input in [
+            "48°51'N 2°21'E",
+            "N48.85 E2.35",
+            "48.85N, 2.35E",
+            "n48°51' e2°21'",
+        ] {
+            let (lat, lon) = geo(input);
+            assert!(close(lat, 48.85) && close(lon, 2.35), "{:?}", input);
+        }
+        let (lat, lon) = geo("33°52'S 151°12'W");
+        assert!(close(lat, -(33.0 + 52.0 / 60.0)) && close(lon, -151.2));
+    }
+
+    #[test]
+    fn signed_geo_positions() {
+        for input in [
+            "48.85, -2.35",
+            "48.85 -2.35",
+            "48° 51' -2° 21'",
+            "48°51', -2°21'",
+        ] {
+            let (lat, lon) = geo(input);
+            assert!(close(lat, 48.85) && close(lon, -2.35), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn malformed_geo_positions_are_rejected() {
+        assert_eq!(parse_geo(""), Err(ParseError::Empty));
+        for input in ["48.85", "2.35E 48.85N", "48.85N", "48.85 2.35 1.0"] {
+            assert!(
+                matches!(parse_geo(input), Err(ParseError::Position { .. })),
+                "{:?}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_geo("95, 0"),
+            Err(ParseError::OutOfRange {
+                what: "latitude",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_geo("0, 190"),
+            Err(ParseError::OutOfRange {
+                what: "longitude",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn distances_with_units() {
+        let units = DistanceUnits::default();
+        assert!(close(units.parse_distance("4.2").unwrap(), 4.2));
+        assert!(close(units.parse_distance("300 m").unwrap(), 300.0));
+        assert!(close(units.parse_distance("12.5nm").unwrap(), 23_150.0));
+        assert!(close(units.parse_distance("2KM").unwrap(), 2000.0));
+        assert!(close(units.parse_distance("1e3m").unwrap(), 1000.0));
+        assert!(close(units.parse_distance("2.5E-1km").unwrap(), 250.0));
+        assert!(close(units.parse_distance("-1e2").unwrap(), -100.0));
+
+        let mut km = DistanceUnits::meters_per_unit(1000.0);
+        assert!(close(km.parse_distance("500m").unwrap(), 0.5));
+        km.insert("fur", 0.201_168);
+        assert!(close(km.parse_distance("10 fur").unwrap(), 2.011_68));
+    }
+
+    #[test]
+    fn malformed_distances_are_rejected() {
+        let units = DistanceUnits::default();
+        assert_eq!(units.parse_distance(" "), Err(ParseError::Empty));
+        assert!(matches!(
+            units.parse_distance("km"),
+            Err(ParseError::Number(_))
+        ));
+        assert!(matches!(
+            units.parse_distance("3 furlongs"),
+            Err(ParseError::UnknownUnit { .. })
+        ));
+        // An `e` without digits after it is a unit, not an exponent.
+        assert!(matches!(
+            units.parse_distance("3e"),
+            Err(ParseError::UnknownUnit { .. })
+        ));
+    }
+
+    #[test]
+    fn polar_positions_take_compass_bearings() {
+        let units = DistanceUnits::default();
+        let (azimuth, dist) = units.parse_polar("230/4.2").unwrap();
+        assert!(close(azimuth, bearing_to_azimuth(230.0)) && close(dist, 4.2));
+        let (azimuth, dist) = units.parse_polar("90°/2nm").unwrap();
+        assert!(close(azimuth, 0.0) && close(dist, 3704.0));
+        let (azimuth, _) = units.parse_polar("0/1").unwrap();
+        assert!(close(azimuth, std::f32::consts::FRAC_PI_2));
+    }
+
+    #[test]
+    fn malformed_polar_positions_are_rejected() {
+        let units = DistanceUnits::default();
+        assert!(matches!(
+            units.parse_polar("230"),
+            Err(ParseError::Position { .. })
+        ));
+        assert!(matches!(
+            units.parse_polar("230/-1"),
+            Err(ParseError::Negative { what: "range", .. })
+        ));
+        assert!(matches!(
+            units.parse_polar("x/1"),
+            Err(ParseError::Angle { .. })
+        ));
+    }
+
+    #[test]
+    fn positions_are_polar_with_a_slash_and_geo_otherwise() {
+        let units = DistanceUnits::default();
+        assert!(matches!(
+            units.parse_position("230/4.2"),
+            Ok(Position::Polar { .. })
+        ));
+        assert!(matches!(
+            units.parse_position("48°51'N 2°21'E"),
+            Ok(Position::Geo { .. })
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bevy::prelude::*;
+
+use crate::aging::LastSeen;
+use crate::layout::LayoutConfig;
+use crate::prediction::TrackHistory;
+use crate::smoothing::Smoother;
+use crate::target::Target;
+
+/// A new target to show. An add for an id that is already shown updates it.
+#[derive(Debug, Clone)]
+pub struct TargetAdded(pub Target);
+
+/// A new report for a shown target, matched by id. A change for an unknown id adds it.
+#[derive(Debug, Clone)]
+pub struct TargetChanged(pub Target);
+
+/// The target with this id is gone.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetRemoved(pub i32);
+
+/// Applies [`TargetAdded`], [`TargetChanged`] and [`TargetRemoved`] events to the
+/// `Target` entities, recording each report in the entity's [`TrackHistory`] and
+/// [`LastSeen`]. Reports go through the [`Smoother`] resource first, with the filter
+/// of [`LayoutConfig::smoothing`].
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin) picks up the result and re-lays out
+/// only the targets that changed.
+pub struct TargetUpdatesPlugin;
+
+impl Plugin for TargetUpdatesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Smoother>() {
+            app.init_resource::<Smoother>();
+        }
+        app.add_event::<TargetAdded>()
+            .add_event::<TargetChanged>()
+            .add_event::<TargetRemoved>()
+            .add_system(apply_updates_system.system());
+    }
+}
+
+#[derive(Default)]
+struct UpdateReaders {
+    added: EventReader<TargetAdded>,
+    changed: EventReader<TargetChanged>,
+    removed: EventReader<TargetRemoved>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_updates_system(
+    mut commands: Commands,
+    mut readers: Local<UpdateReaders>,
+    time: Res<Time>,
+    config: Res<LayoutConfig>,
+    mut smoother: ResMut<Smoother>,
+    added: Res<Events<TargetAdded>>,
+    changed: Res<Events<TargetChanged>>,
+    removed: Res<Events<TargetRemoved>>,
+    mut targets: Query<(Entity, Mut<Target>, Option<Mut<TrackHistory>>)>,
+) {
+    // Only the latest report per target counts when several arrive in one frame.
+    let updates = readers
+        .added
+        .iter(&added)
+        .map(|e| &e.0)
+        .chain(readers.changed.iter(&changed).map(|e| &e.0))
+        .map(|t| (t.id, t))
+        .collect::<BTreeMap<_, _>>();
+    let removals = readers
+        .removed
+        .iter(&removed)
+        .map(|e| e.0)
+        .collect::<Vec<_>>();
+    if smoother.filter != config.smoothing {
+        *smoother = Smoother::new(config.smoothing);
+    }
+    if updates.is_empty() && removals.is_empty() {
+        return;
+    }
+    let now = time.seconds_since_startup;
+    let mut entity_of = targets
+        .iter_mut()
+        .map(|(entity, target, _)| (target.id, entity))
+        .collect::<HashMap<_, _>>();
+
+    for (id, update) in updates {
+        let mut update = update.clone();
+        smoother.smooth(&mut update, now);
+        let update = &update;
+        let mut history = TrackHistory::default();
+        match entity_of.get(&id).and_then(|e| targets.get_mut(*e).ok()) {
+            Some((entity, mut target, existing)) => {
+                match existing {
+                    Some(mut existing) => existing.record(now, update),
+                    None => {
+                        history.record(now, update);
+                        commands.insert_one(entity, history);
+                    }
+                }
+                *target = update.clone();
+                commands.insert_one(entity, LastSeen(now));
+            }
+            None => {
+                history.record(now, update);
+                commands.spawn((update.clone(), history, LastSeen(now)));
+            }
+        }
+    }
+    for id in removals {
+        smoother.remove(id);
+        if let Some(entity) = entity_of.remove(&id) {
+            commands.despawn(entity);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::pointer::screen_to_world;
+use crate::theme::Theme;
+
+/// Above everything the display and [`FramePlugin`](crate::frame::FramePlugin) draw.
+const MASK_Z: f32 = 60.0;
+
+/// Confines the display to a rectangle of the window, for embedding it next to other
+/// panels. Put it on the 2D camera entity and add [`ViewportPlugin`]. The display's
+/// origin is centered in the rectangle, and the cursor only counts as over the display
+/// inside it (see [`CursorPosition::viewport`](crate::pointer::CursorPosition::viewport)).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayViewport {
+    /// Bottom-left corner in window pixels, origin bottom-left like cursor positions.
+    pub origin: Vec2,
+    pub size: Vec2,
+    /// Whether to cover the rest of the window; off when another view, such as a 3D
+    /// camera with a [`ViewOffset`], is shown there.
+    pub mask: bool,
+}
+
+impl DisplayViewport {
+    pub fn new(origin: Vec2, size: Vec2) -> Self {
+        DisplayViewport {
+            origin,
+            size,
+            mask: true,
+        }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.origin + self.size / 2.0
+    }
+
+    pub fn contains(&self, screen: Vec2) -> bool {
+        let local = screen - self.origin;
+        local.x() >= 0.0
+            && local.y() >= 0.0
+            && local.x() < self.size.x()
+            && local.y() < self.size.y()
+    }
+
+    /// `screen` relative to the viewport's bottom-left corner, `None` outside it.
+    pub fn to_local(&self, screen: Vec2) -> Option<Vec2> {
+        if self.contains(screen) {
+            Some(screen - self.origin)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for DisplayViewport {
+    type Err = String;
+
+    /// `X,Y,WIDTH,HEIGHT` in window pixels.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{:?}: {}", s, e))?;
+        match values[..] {
+            [x, y, width, height] if width > 0.0 && height > 0.0 => Ok(DisplayViewport::new(
+                Vec2::new(x, y),
+                Vec2::new(width, height),
+            )),
+            _ => Err(format!(
+                "expected X,Y,WIDTH,HEIGHT with a positive size, got {:?}",
+                s
+            )),
+        }
+    }
+}
+
+/// Shifts the picture of the camera it is on across the window, by fractions of the
+/// window's width and height, up and to the right for positive values. Lets a 3D view
+/// be centered in the part of the window a [`DisplayViewport`] leaves to it, e.g.
+/// `0.25` across for the right half when the display has the left. The camera's
+/// projection is offset after bevy updates it, so picking through
+/// [`screen_ray`](crate::probe::screen_ray) follows the shift. Needs [`ViewportPlugin`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewOffset {
+    pub shift: Vec2,
+    /// The projection before and after the shift, as last applied.
+    applied: Option<(Mat4, Mat4)>,
+}
+
+impl ViewOffset {
+    pub fn new(shift: Vec2) -> Self {
+        ViewOffset {
+            shift,
+            applied: None,
+        }
+    }
+}
+
+/// Marks the quads [`ViewportPlugin`] uses to cover the window outside the viewport.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewportMask;
+
+/// Keeps the camera of a [`DisplayViewport`] centered on the viewport and, unless
+/// [`DisplayViewport::mask`] is off, covers the rest of the window in the background
+/// color. Text labels are drawn by the UI pass and are not covered. Also shifts
+/// cameras with a [`ViewOffset`].
+pub struct ViewportPlugin;
+
+impl Plugin for ViewportPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<Theme>() {
+            app.init_resource::<Theme>();
+        }
+        app.add_system(viewport_system.system())
+            .add_system_to_stage(stage::LAST, view_offset_system.system());
+    }
+}
+
+#[derive(Default)]
+struct ViewportState {
+    masks: Vec<Entity>,
+    /// Camera translation currently added to center the viewport, in world units.
+    offset: Vec2,
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn viewport_system(
+    mut commands: Commands,
+    mut state: Local<ViewportState>,
+    windows: Res<Windows>,
+    theme: Res<Theme>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut cameras: Query<With<Camera, (&DisplayViewport, Mut<Transform>)>>,
+    mut masks: Query<With<ViewportMask, Mut<Transform>>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let size = Vec2::new(window.width() as f32, window.height() as f32);
+    let (viewport, mut camera) = match cameras.iter_mut().next() {
+        Some(camera) => camera,
+        None => {
+            for entity in state.masks.drain(..) {
+                commands.despawn(entity);
+            }
+            return;
+        }
+    };
+
+    let scale = camera.scale.x();
+    let offset = (size / 2.0 - viewport.center()) * scale;
+    if offset != state.offset {
+        camera.translation += (offset - state.offset).extend(0.0);
+        state.offset = offset;
+    }
+
+    if !viewport.mask {
+        for entity in state.masks.drain(..) {
+            commands.despawn(entity);
+        }
+        return;
+    }
+    if state.masks.is_empty() {
+        let material = materials.add(theme.background().into());
+        for _ in 0..4 {
+            let quad = primitive(
+                material.clone(),
+                &mut meshes,
+                ShapeType::Rectangle {
+                    width: 1.0,
+                    height: 1.0,
+                },
+                TessellationMode::Fill(&FillOptions::default()),
+                Vec3::new(0.0, 0.0, MASK_Z),
+            );
+            state.masks.push(
+                commands
+                    .spawn(quad)
+                    .with(ViewportMask)
+                    .current_entity()
+                    .unwrap(),
+            );
+        }
+        // The quads are positioned from the next frame on, once they exist.
+        return;
+    }
+
+    let (lo, hi) = (viewport.origin, viewport.origin + viewport.size);
+    let regions = [
+        (Vec2::zero(), Vec2::new(size.x(), lo.y())),
+        (Vec2::new(0.0, hi.y()), size),
+        (Vec2::new(0.0, lo.y()), Vec2::new(lo.x(), hi.y())),
+        (Vec2::new(hi.x(), lo.y()), Vec2::new(size.x(), hi.y())),
+    ];
+    for (entity, (from, to)) in state.masks.iter().zip(regions.iter()) {
+        if let Ok(mut transform) = masks.get_mut(*entity) {
+            let from = screen_to_world(*from, size, &camera);
+            let to = screen_to_world(*to, size, &camera);
+            let extent = (to - from).max(Vec2::zero());
+            transform.translation = from.extend(MASK_Z);
+            transform.scale = extent.extend(1.0);
+        }
+    }
+}
+
+// bevy sets the projection again on resizes, after which the shift is put back on top.
+fn view_offset_system(mut cameras: Query<(Mut<Camera>, Mut<ViewOffset>)>) {
+    for (mut camera, mut offset) in cameras.iter_mut() {
+        let base = match offset.applied {
+            Some((base, shifted)) if shifted == camera.projection_matrix => base,
+            _ => camera.projection_matrix,
+        };
+        let shift = offset.shift * 2.0;
+        let shifted = Mat4::from_translation(shift.extend(0.0)) * base;
+        if offset.applied != Some((base, shifted)) {
+            camera.projection_matrix = shifted;
+            offset.applied = Some((base, shifted));
+        }
+    }
+}
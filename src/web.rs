@@ -0,0 +1,163 @@
+use std::io;
+
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+use thiserror::Error;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{BinaryType, ErrorEvent, MessageEvent, WebSocket, XmlHttpRequest};
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::gesture::{self, Gesture};
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("{0}")]
+    Browser(String),
+    #[error("the server answered {0}")]
+    Status(u16),
+}
+
+fn browser_error(value: JsValue) -> FetchError {
+    FetchError::Browser(format!("{:?}", value))
+}
+
+/// Fetches `url`, relative to the page, the way the browser does, blocking until it
+/// has all of it.
+pub fn fetch(url: &str) -> Result<Vec<u8>, FetchError> {
+    let request = XmlHttpRequest::new().map_err(browser_error)?;
+    request
+        .open_with_async("GET", url, false)
+        .map_err(browser_error)?;
+    // Blocking requests can't ask for an ArrayBuffer, so the bytes come as the
+    // characters of a charset the browser leaves alone, one byte in the low bits of each.
+    request
+        .override_mime_type("text/plain; charset=x-user-defined")
+        .map_err(browser_error)?;
+    request.send().map_err(browser_error)?;
+    match request.status().map_err(browser_error)? {
+        200..=299 => {}
+        status => return Err(FetchError::Status(status)),
+    }
+    let text = request.response_text().map_err(browser_error)?;
+    Ok(text
+        .unwrap_or_default()
+        .chars()
+        .map(|c| c as u32 as u8)
+        .collect())
+}
+
+/// Connects to the WebSocket server at `url` and calls `on_text` with each message,
+/// binary ones taken as UTF-8 text too.
+pub(crate) fn connect(url: &str, mut on_text: impl FnMut(&str) + 'static) -> io::Result<()> {
+    let socket = WebSocket::new(url).map_err(|e| io::Error::other(format!("{:?}", e)))?;
+    socket.set_binary_type(BinaryType::Arraybuffer);
+    let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
+        let data = event.data();
+        if let Some(text) = data.as_string() {
+            on_text(&text);
+        } else if let Ok(buffer) = data.dyn_into::<js_sys::ArrayBuffer>() {
+            on_text(&String::from_utf8_lossy(
+                &js_sys::Uint8Array::new(&buffer).to_vec(),
+            ));
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
+    let failed = url.to_string();
+    let on_error = Closure::wrap(Box::new(move |_: ErrorEvent| {
+        tracing::warn!("target feed: {}: connection failed", failed);
+    }) as Box<dyn FnMut(ErrorEvent)>);
+    socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    socket.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    // The browser keeps an open socket and its handlers alive as long as the page.
+    on_message.forget();
+    on_error.forget();
+    Ok(())
+}
+
+/// What runs differently in a web page: touches stand in for the mouse, a finger
+/// moving the cursor and a tap clicking where it lands, as [`Action::Select`] and the
+/// left button for bevy_ui, on top of
+/// the pans, pinches and double taps of
+/// [`GesturePlugin`](crate::gesture::GesturePlugin).
+pub struct WebPlugin;
+
+impl Plugin for WebPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        gesture::add_gestures(app);
+        app.add_system_to_stage(stage::PRE_UPDATE, touch_pointer_system.system());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TapClick {
+    /// The cursor was sent to the tap, to press the button once it is there.
+    Moved,
+    /// The button is down, to let go of next frame.
+    Pressed,
+}
+
+#[derive(Default)]
+struct TouchPointerState {
+    gestures: EventReader<Gesture>,
+    /// Where the cursor was last sent, flipped to the origin bottom-left.
+    last: Option<Vec2>,
+    click: Option<TapClick>,
+}
+
+fn touch_pointer_system(
+    mut state: Local<TouchPointerState>,
+    windows: Res<Windows>,
+    touches: Res<Touches>,
+    gestures: Res<Events<Gesture>>,
+    mut cursor_moved: ResMut<Events<CursorMoved>>,
+    mut actions: ResMut<ActionState>,
+    mut mouse_button: ResMut<Input<MouseButton>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let height = window.height() as f32;
+    let mut move_to = |state: &mut TouchPointerState, position: Vec2| {
+        if state.last != Some(position) {
+            state.last = Some(position);
+            cursor_moved.send(CursorMoved {
+                id: window.id(),
+                position,
+            });
+        }
+    };
+
+    state.click = match state.click {
+        // bevy_ui reads the button rather than actions, so buttons get clicked too.
+        Some(TapClick::Moved) => {
+            actions.press(Action::Select);
+            mouse_button.press(MouseButton::Left);
+            Some(TapClick::Pressed)
+        }
+        Some(TapClick::Pressed) => {
+            actions.release(Action::Select);
+            mouse_button.release(MouseButton::Left);
+            None
+        }
+        None => None,
+    };
+    let finger = touches.iter().min_by_key(|touch| touch.id);
+    if let Some(touch) = finger {
+        let position = Vec2::new(touch.position.x(), height - touch.position.y());
+        move_to(&mut state, position);
+    }
+    let tapped = state
+        .gestures
+        .iter(&gestures)
+        .filter_map(|gesture| match *gesture {
+            Gesture::Tap { position } => Some(position),
+            _ => None,
+        })
+        .next_back();
+    if let (Some(position), None) = (tapped, state.click) {
+        move_to(&mut state, position);
+        state.click = Some(TapClick::Moved);
+    }
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use bevy::render::render_graph::base::camera::CAMERA2D;
+use bevy::window::WindowResized;
+use bevy::winit::WinitWindows;
+
+use crate::camera::CameraControls;
+use crate::layout::{LayoutConfig, LayoutDiagnostics};
+use crate::theme::RingTextScale;
+use crate::viewport::DisplayViewport;
+
+/// How [`WindowFitPlugin`] keeps the display fitted to the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowFit {
+    /// Zoom the 2D camera so that the outermost ring and its markers fit the window, or
+    /// the [`DisplayViewport`] when there is one.
+    pub fit_rings: bool,
+    /// Room left between the outermost markers and the edge, in window pixels.
+    pub margin: f32,
+    /// Size markers, ring spacing and labels in physical pixels, multiplied by the
+    /// window's scale factor: as large on a HiDPI screen as on any other, and drawn at
+    /// that size rather than zoomed up and blurred.
+    pub physical_pixels: bool,
+}
+
+impl Default for WindowFit {
+    fn default() -> Self {
+        WindowFit {
+            fit_rings: true,
+            margin: 20.0,
+            physical_pixels: true,
+        }
+    }
+}
+
+/// Physical pixels per logical pixel of the primary window, as the windowing system
+/// reports it: 2 on most HiDPI screens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowScale(pub f64);
+
+impl Default for WindowScale {
+    fn default() -> Self {
+        WindowScale(1.0)
+    }
+}
+
+/// Keeps the rings inside the window as it is resized or moved to a screen of another
+/// scale factor, following [`WindowFit`]. The camera is zoomed to fit at the first
+/// layout, on every resize, and whenever the rings grow or shrink while the zoom is
+/// still the fitted one; zooming by hand is left alone until the next resize. Any pan
+/// is kept. With [`WindowFit::physical_pixels`] the [`LayoutConfig`] marker width and
+/// ring spacing and the label sizes follow the scale factor; the values found there
+/// are taken as those for the current factor, so the tuning keys keep working. Needs
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin).
+pub struct WindowFitPlugin;
+
+impl Plugin for WindowFitPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.resources().contains::<WindowFit>() {
+            app.init_resource::<WindowFit>();
+        }
+        if !app.resources().contains::<CameraControls>() {
+            app.init_resource::<CameraControls>();
+        }
+        if !app.resources().contains::<LayoutDiagnostics>() {
+            app.init_resource::<LayoutDiagnostics>();
+        }
+        if !app.resources().contains::<WinitWindows>() {
+            app.init_resource::<WinitWindows>();
+        }
+        app.init_resource::<WindowScale>()
+            .add_system_to_stage(stage::PRE_UPDATE, window_scale_system.system())
+            .add_system_to_stage(stage::PRE_UPDATE, physical_size_system.system())
+            // Before the viewport system, which re-centers the camera for a new scale.
+            .add_system_to_stage(stage::PRE_UPDATE, fit_system.system());
+    }
+}
+
+fn window_scale_system(
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    mut scale: ResMut<WindowScale>,
+) {
+    let factor = windows
+        .get_primary()
+        .and_then(|window| winit_windows.get_window(window.id()))
+        .map(|window| window.scale_factor());
+    if let Some(factor) = factor {
+        if factor != scale.0 {
+            scale.0 = factor;
+        }
+    }
+}
+
+struct PixelState {
+    /// The scale factor the layout and label sizes are currently for.
+    factor: f32,
+}
+
+impl Default for PixelState {
+    fn default() -> Self {
+        PixelState { factor: 1.0 }
+    }
+}
+
+fn physical_size_system(
+    mut state: Local<PixelState>,
+    fit: Res<WindowFit>,
+    scale: Res<WindowScale>,
+    mut config: ResMut<LayoutConfig>,
+    mut text_scale: ResMut<RingTextScale>,
+) {
+    let factor = if fit.physical_pixels {
+        scale.0 as f32
+    } else {
+        1.0
+    };
+    if factor == state.factor {
+        return;
+    }
+    let k = factor / state.factor;
+    config.poi_width *= k;
+    config.ring_spacing *= k;
+    text_scale.scale_factor = factor;
+    state.factor = factor;
+}
+
+#[derive(Default)]
+struct FitState {
+    resized: EventReader<WindowResized>,
+    /// The camera scale last fitted, and the extent of the rings it was fitted to.
+    fitted: Option<(f32, f32)>,
+}
+
+#[allow(clippy::type_complexity)]
+fn fit_system(
+    mut state: Local<FitState>,
+    fit: Res<WindowFit>,
+    (config, controls, diagnostics): (
+        Res<LayoutConfig>,
+        Res<CameraControls>,
+        Res<LayoutDiagnostics>,
+    ),
+    (windows, resized): (Res<Windows>, Res<Events<WindowResized>>),
+    mut cameras: Query<(&Camera, Mut<Transform>, Option<&DisplayViewport>)>,
+) {
+    let resized = state.resized.iter(&resized).next_back().is_some();
+    if !fit.fit_rings {
+        return;
+    }
+    let outermost = diagnostics
+        .rings
+        .iter()
+        .map(|ring| ring.radius)
+        .fold(0.0, f32::max);
+    if outermost <= 0.0 {
+        return;
+    }
+    let extent = outermost + config.footprint();
+    let (mut transform, viewport) = match cameras
+        .iter_mut()
+        .find(|(camera, _, _)| camera.name.as_deref() == Some(CAMERA2D))
+    {
+        Some((_, transform, viewport)) => (transform, viewport),
+        None => return,
+    };
+    let refit = match state.fitted {
+        None => true,
+        Some((scale, fitted_extent)) => {
+            resized || (scale == transform.scale.x() && fitted_extent != extent)
+        }
+    };
+    if !refit {
+        return;
+    }
+    let size = match (viewport, windows.get_primary()) {
+        (Some(viewport), _) => viewport.size,
+        (None, Some(window)) => Vec2::new(window.width() as f32, window.height() as f32),
+        (None, None) => return,
+    };
+    let room = (size.x().min(size.y()) / 2.0 - fit.margin).max(1.0);
+    let zoom = (room / extent)
+        .max(controls.min_zoom)
+        .min(controls.max_zoom);
+    let scale = 1.0 / zoom;
+    transform.scale = Vec3::new(scale, scale, transform.scale.z());
+    state.fitted = Some((scale, extent));
+}
@@ -0,0 +1,524 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{add_actions, Action, ActionState};
+use crate::edit::place_at;
+use crate::emphasis::{Emphasis, TargetEmphasis};
+use crate::events::DisplayEvent;
+use crate::geo::{LatLon, Polar};
+use crate::globe_link::GlobeLink;
+use crate::globe_pick::GlobeClicked;
+use crate::layers::Layer;
+use crate::layout::LayoutConfig;
+use crate::origins::SensorOrigins;
+use crate::polyline::GeoPolyline;
+use crate::target::{GeoPoint, Target};
+use crate::trails::DisplayHeading;
+use crate::undo::{EditCommand, UndoStack};
+
+/// How long a target flashes on crossing into or out of a zone, and how fast, in
+/// seconds.
+const FLASH_SECS: f32 = 1.2;
+const FLASH_PERIOD: f32 = 0.2;
+/// Color zones are outlined in, on the rings and on the globe.
+const ZONE_COLOR: [f32; 4] = [1.0, 0.45, 0.1, 0.8];
+/// Degrees between the points of a drawn sector's arcs.
+const ARC_STEP: f32 = 2.0;
+
+/// The area an [`AlertZone`] covers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneShape {
+    /// Azimuths from `from` counter-clockwise to `to`, in degrees like
+    /// [`Target::azimuth`] is in radians, between two distances from the target's
+    /// origin.
+    Sector {
+        from: f32,
+        to: f32,
+        #[serde(default)]
+        min_dist: f32,
+        max_dist: f32,
+    },
+    /// Distances from the target's origin between `min` and `max`, all the way round.
+    RangeBand { min: f32, max: f32 },
+    /// The polygon through `points`, `[lat, lon]` in degrees, with straight edges in
+    /// latitude and longitude. Targets without a [`GeoPoint`] are placed by the
+    /// [`GlobeLink`].
+    Polygon { points: Vec<[f32; 2]> },
+}
+
+impl ZoneShape {
+    /// Whether a target `dist` from its origin at `azimuth` radians, and at `place` on
+    /// the globe, is inside.
+    pub fn contains(&self, azimuth: f32, dist: f32, place: LatLon) -> bool {
+        match self {
+            ZoneShape::Sector {
+                from,
+                to,
+                min_dist,
+                max_dist,
+            } => {
+                let span = (to - from).rem_euclid(360.0);
+                (azimuth.to_degrees() - from).rem_euclid(360.0) <= span
+                    && dist >= *min_dist
+                    && dist <= *max_dist
+            }
+            ZoneShape::RangeBand { min, max } => dist >= *min && dist <= *max,
+            ZoneShape::Polygon { points } => polygon_contains(points, place),
+        }
+    }
+}
+
+/// Even-odd test of `place` against the polygon through `points`, with longitudes
+/// taken within half a turn of the place's so polygons across the antimeridian work.
+fn polygon_contains(points: &[[f32; 2]], place: LatLon) -> bool {
+    let unwrap = |lon: f32| place.lon + (lon - place.lon + 180.0).rem_euclid(360.0) - 180.0;
+    let mut inside = false;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        let (a_lat, a_lon, b_lat, b_lon) = (a[0], unwrap(a[1]), b[0], unwrap(b[1]));
+        if (a_lat > place.lat) != (b_lat > place.lat) {
+            let lon = a_lon + (place.lat - a_lat) / (b_lat - a_lat) * (b_lon - a_lon);
+            if place.lon < lon {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+fn default_flash() -> bool {
+    true
+}
+
+/// One zone of [`AlertZonesPlugin`], as listed under `zones` in a
+/// [`DebrisConfig`](crate::config::DebrisConfig), e.g. in RON:
+///
+/// ```text
+/// zones: [
+///     (name: "harbor", shape: sector(from: 30.0, to: 60.0, max_dist: 50.0), sound: Some("alert.mp3")),
+///     (name: "close", shape: range_band(min: 0.0, max: 20.0), flash: false),
+///     (name: "strait", shape: polygon(points: [(36.2, -6.0), (35.8, -6.0), (35.9, -5.2)])),
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertZone {
+    pub name: String,
+    pub shape: ZoneShape,
+    /// Sound file played at this asset path when a target crosses in or out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+    /// Whether a target crossing in or out flashes its marker.
+    #[serde(default = "default_flash")]
+    pub flash: bool,
+}
+
+impl AlertZone {
+    pub fn new(name: impl Into<String>, shape: ZoneShape) -> Self {
+        AlertZone {
+            name: name.into(),
+            shape,
+            sound: None,
+            flash: true,
+        }
+    }
+}
+
+/// The zones [`AlertZonesPlugin`] watches. Change them directly; targets already
+/// inside a zone that goes are forgotten without an event.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlertZones {
+    pub zones: Vec<AlertZone>,
+}
+
+/// Sent by [`AlertZonesPlugin`] when a target enters or leaves a zone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertEvent {
+    pub target: i32,
+    pub zone: String,
+    pub entered: bool,
+}
+
+/// Whether [`AlertZonesPlugin`]'s clicks draw zones; toggled with
+/// [`Action::ToggleZoneDrawing`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ZoneEditMode {
+    pub active: bool,
+}
+
+/// Watches targets against [`AlertZones`]: a target entering or leaving a zone sends
+/// an [`AlertEvent`] and a [`DisplayEvent::ZoneCrossed`], which
+/// [`AlertRule`](crate::alerts::AlertRule)s can trip on, flashes its marker through
+/// [`TargetEmphasis`] and plays the zone's sound. Zones are outlined on the rings and,
+/// as [`GeoPolyline`]s, on the globe.
+///
+/// [`Action::ToggleZoneDrawing`], Z by default, turns drawing zones on and off. While
+/// on, two clicks on empty space of the ring display add the sector between them, and
+/// clicks on the globe add the corners of a polygon that [`Action::FinishZone`]
+/// (Return) closes; [`Action::Cancel`] (Escape) drops the corners so far and
+/// [`Action::RemoveLastZone`] (Backspace) removes the zone added last. New zones are printed to be pasted into the config; additions
+/// and removals go on the [`UndoStack`] for [`UndoPlugin`](crate::undo::UndoPlugin). Needs
+/// [`PoiRingPlugin`](crate::display::PoiRingPlugin) and
+/// [`DisplayEventsPlugin`](crate::events::DisplayEventsPlugin), with
+/// [`EmphasisPlugin`](crate::emphasis::EmphasisPlugin) for the flashing; add after
+/// [`GlobePickPlugin`](crate::globe_pick::GlobePickPlugin) to draw on the globe.
+#[derive(Debug, Clone, Default)]
+pub struct AlertZonesPlugin {
+    pub zones: Vec<AlertZone>,
+}
+
+#[derive(Default)]
+struct ZoneFlashes(HashMap<i32, (f32, Emphasis)>);
+
+impl Plugin for AlertZonesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_actions(app);
+        if !app.resources().contains::<TargetEmphasis>() {
+            app.init_resource::<TargetEmphasis>();
+        }
+        if !app.resources().contains::<GlobeLink>() {
+            app.init_resource::<GlobeLink>();
+        }
+        if !app.resources().contains::<DisplayHeading>() {
+            app.init_resource::<DisplayHeading>();
+        }
+        if !app.resources().contains::<UndoStack>() {
+            app.init_resource::<UndoStack>();
+        }
+        if app.resources().contains::<Events<GlobeClicked>>() {
+            app.add_system(zone_globe_edit_system.system());
+        }
+        app.add_resource(AlertZones {
+            zones: self.zones.clone(),
+        })
+        .init_resource::<ZoneEditMode>()
+        .init_resource::<ZoneDraft>()
+        .init_resource::<ZoneFlashes>()
+        .add_event::<AlertEvent>()
+        .add_system(zone_system.system())
+        .add_system(zone_flash_system.system())
+        .add_system(zone_edit_system.system())
+        .add_system(zone_draw_system.system());
+    }
+}
+
+#[derive(Default)]
+struct ZoneState {
+    /// Zone names and the ids of the targets inside them.
+    inside: HashSet<(String, i32)>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn zone_system(
+    mut state: Local<ZoneState>,
+    zones: Res<AlertZones>,
+    link: Res<GlobeLink>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    mut alerts: ResMut<Events<AlertEvent>>,
+    mut display_events: ResMut<Events<DisplayEvent>>,
+    (emphasis, mut flashes): (Res<TargetEmphasis>, ResMut<ZoneFlashes>),
+    targets: Query<(&Target, Option<&GeoPoint>)>,
+) {
+    let mut inside = HashSet::new();
+    for (target, geo) in targets.iter() {
+        let place = match geo {
+            Some(geo) => LatLon::new(geo.lat, geo.lon),
+            None => link.place(Polar::new(target.azimuth, target.dist)),
+        };
+        for zone in &zones.zones {
+            if zone.shape.contains(target.azimuth, target.dist, place) {
+                inside.insert((zone.name.clone(), target.id));
+            }
+        }
+    }
+    let present = targets
+        .iter()
+        .map(|(target, _)| target.id)
+        .collect::<HashSet<_>>();
+    let crossed = inside
+        .difference(&state.inside)
+        .map(|key| (key, true))
+        .chain(
+            state
+                .inside
+                .difference(&inside)
+                .filter(|(name, id)| {
+                    present.contains(id) && zones.zones.iter().any(|zone| &zone.name == name)
+                })
+                .map(|key| (key, false)),
+        );
+    for ((name, id), entered) in crossed {
+        alerts.send(AlertEvent {
+            target: *id,
+            zone: name.clone(),
+            entered,
+        });
+        display_events.send(DisplayEvent::ZoneCrossed {
+            target: *id,
+            zone: name.clone(),
+            entered,
+        });
+        let zone = match zones.zones.iter().find(|zone| &zone.name == name) {
+            Some(zone) => zone,
+            None => continue,
+        };
+        if let Some(path) = &zone.sound {
+            audio.play(asset_server.load(path.as_str()));
+        }
+        if zone.flash {
+            // A flash already running keeps the emphasis from before it.
+            let before = emphasis.emphasis(*id);
+            let (elapsed, _) = flashes.0.entry(*id).or_insert((0.0, before));
+            *elapsed = 0.0;
+        }
+    }
+    state.inside = inside;
+}
+
+fn zone_flash_system(
+    time: Res<Time>,
+    mut flashes: ResMut<ZoneFlashes>,
+    mut emphasis: ResMut<TargetEmphasis>,
+) {
+    flashes.0.retain(|&id, (elapsed, before)| {
+        *elapsed += time.delta_seconds;
+        if *elapsed >= FLASH_SECS {
+            emphasis.set_target_emphasis(id, *before);
+            return false;
+        }
+        let on = ((*elapsed / FLASH_PERIOD) as u32).is_multiple_of(2);
+        emphasis.set_target_emphasis(
+            id,
+            if on {
+                Emphasis::Highlight
+            } else {
+                Emphasis::Dim
+            },
+        );
+        true
+    });
+}
+
+/// The corners of the zone being drawn.
+#[derive(Default)]
+struct ZoneDraft {
+    /// Azimuth in radians and distance of the first corner of a sector.
+    sector: Option<(f32, f32)>,
+    /// `[lat, lon]` corners of a polygon.
+    polygon: Vec<[f32; 2]>,
+}
+
+/// The next name of the form `zone N` not yet taken.
+fn next_name(zones: &AlertZones) -> String {
+    (1..)
+        .map(|n| format!("zone {}", n))
+        .find(|name| zones.zones.iter().all(|zone| &zone.name != name))
+        .unwrap()
+}
+
+fn add_zone(zones: &mut AlertZones, undo: &mut UndoStack, shape: ZoneShape) {
+    let zone = AlertZone::new(next_name(zones), shape);
+    match ron::ser::to_string(&zone) {
+        Ok(text) => tracing::info!("zone added: {}", text),
+        Err(_) => tracing::info!("zone added: {}", zone.name),
+    }
+    undo.push(EditCommand::AddZone(zones.zones.len(), zone.clone()));
+    zones.zones.push(zone);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn zone_edit_system(
+    mut reader: Local<EventReader<DisplayEvent>>,
+    actions: Res<ActionState>,
+    (config, origins): (Res<LayoutConfig>, Res<SensorOrigins>),
+    events: Res<Events<DisplayEvent>>,
+    mut mode: ResMut<ZoneEditMode>,
+    mut draft: ResMut<ZoneDraft>,
+    (mut zones, mut undo): (ResMut<AlertZones>, ResMut<UndoStack>),
+) {
+    if actions.just_pressed(Action::ToggleZoneDrawing) {
+        mode.active = !mode.active;
+        *draft = ZoneDraft::default();
+        tracing::info!("drawing zones {}", if mode.active { "on" } else { "off" });
+    }
+    let clicks = reader
+        .iter(&events)
+        .filter_map(|event| match event {
+            DisplayEvent::Clicked {
+                world: Some(world),
+                target: None,
+                ..
+            } => Some(*world),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !mode.active {
+        return;
+    }
+    if actions.just_pressed(Action::Cancel) {
+        *draft = ZoneDraft::default();
+    }
+    if actions.just_pressed(Action::RemoveLastZone) {
+        if let Some(zone) = zones.zones.pop() {
+            tracing::info!("zone removed: {}", zone.name);
+            undo.push(EditCommand::RemoveZone(zones.zones.len(), zone));
+        }
+    }
+    if actions.just_pressed(Action::FinishZone) && draft.polygon.len() >= 3 {
+        let points = std::mem::take(&mut draft.polygon);
+        add_zone(&mut zones, &mut undo, ZoneShape::Polygon { points });
+    }
+    for world in clicks {
+        let (_, azimuth, dist) = place_at(world, &config, &origins);
+        let (first_azimuth, first_dist) = match draft.sector.take() {
+            Some(first) => first,
+            None => {
+                draft.sector = Some((azimuth, dist));
+                continue;
+            }
+        };
+        // The shorter way round between the two corners.
+        let (a, b) = (first_azimuth.to_degrees(), azimuth.to_degrees());
+        let (from, to) = if (b - a).rem_euclid(360.0) <= 180.0 {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        add_zone(
+            &mut zones,
+            &mut undo,
+            ZoneShape::Sector {
+                from: from.rem_euclid(360.0),
+                to: to.rem_euclid(360.0),
+                min_dist: first_dist.min(dist),
+                max_dist: first_dist.max(dist),
+            },
+        );
+    }
+}
+
+fn zone_globe_edit_system(
+    mut reader: Local<EventReader<GlobeClicked>>,
+    mode: Res<ZoneEditMode>,
+    clicks: Res<Events<GlobeClicked>>,
+    mut draft: ResMut<ZoneDraft>,
+) {
+    for click in reader.iter(&clicks) {
+        if mode.active {
+            draft.polygon.push([click.lat, click.lon]);
+        }
+    }
+}
+
+/// Marks the outlines [`AlertZonesPlugin`] draws.
+struct ZoneOutline;
+
+#[derive(Default)]
+struct DrawnZones {
+    zones: Option<AlertZones>,
+    config: Option<LayoutConfig>,
+    origins: Option<SensorOrigins>,
+    heading: Option<DisplayHeading>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn zone_draw_system(
+    mut commands: Commands,
+    mut drawn: Local<DrawnZones>,
+    zones: Res<AlertZones>,
+    (config, origins, heading): (Res<LayoutConfig>, Res<SensorOrigins>, Res<DisplayHeading>),
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    outlines: Query<With<ZoneOutline, Entity>>,
+) {
+    if drawn.zones.as_ref() == Some(&*zones)
+        && drawn.config == Some(*config)
+        && drawn.origins.as_ref() == Some(&*origins)
+        && drawn.heading == Some(*heading)
+    {
+        return;
+    }
+    drawn.zones = Some(zones.clone());
+    drawn.config = Some(*config);
+    drawn.origins = Some(origins.clone());
+    drawn.heading = Some(*heading);
+    for entity in outlines.iter() {
+        commands.despawn_recursive(entity);
+    }
+
+    let [r, g, b, a] = ZONE_COLOR;
+    let material = materials.add(Color::rgba(r, g, b, a).into());
+    let radius = |dist: f32| config.ring_position(dist) * config.ring_spacing;
+    let heading = heading.0;
+    for zone in &zones.zones {
+        let (from, span, near, far) = match zone.shape {
+            ZoneShape::Sector {
+                from,
+                to,
+                min_dist,
+                max_dist,
+            } => (from, (to - from).rem_euclid(360.0), min_dist, max_dist),
+            ZoneShape::RangeBand { min, max } => (0.0, 360.0, min, max),
+            ZoneShape::Polygon { ref points } => {
+                commands.spawn((
+                    GeoPolyline {
+                        name: zone.name.clone(),
+                        closed: true,
+                        color: ZONE_COLOR,
+                        ..GeoPolyline::new(points.clone())
+                    },
+                    ZoneOutline,
+                ));
+                continue;
+            }
+        };
+        let steps = ((span / ARC_STEP).ceil() as usize).max(1);
+        let arc = |dist: f32| {
+            (0..=steps)
+                .map(|i| {
+                    let azimuth = (from + span * i as f32 / steps as f32).to_radians() - heading;
+                    Vec2::new(azimuth.cos(), azimuth.sin()) * radius(dist)
+                })
+                .collect::<Vec<_>>()
+        };
+        for o in 0..origins.len() {
+            let offset = origins.offset(o);
+            let mut builder = PathBuilder::new();
+            let mut trace = |points: Vec<Vec2>, start: bool| {
+                for (i, at) in points.into_iter().enumerate() {
+                    let at = offset + at;
+                    if start && i == 0 {
+                        builder.move_to(point(at.x(), at.y()));
+                    } else {
+                        builder.line_to(point(at.x(), at.y()));
+                    }
+                }
+            };
+            // A band is two circles; a sector goes out along one edge and back in
+            // along the other.
+            let full = span >= 360.0;
+            trace(arc(near), true);
+            if full {
+                trace(arc(far), true);
+            } else {
+                trace(arc(far).into_iter().rev().collect(), false);
+                builder.close();
+            }
+            let outline = builder.build().stroke(
+                material.clone(),
+                &mut meshes,
+                Vec3::zero(),
+                &StrokeOptions::default(),
+            );
+            commands
+                .spawn(outline)
+                .with(ZoneOutline)
+                .with(Layer::Overlays);
+        }
+    }
+}